@@ -1,151 +1,24672 @@
 // 导入所需的库和模块
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    entrypoint,
-    entrypoint::ProgramResult,
+    alt_bn128::prelude::{alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing},
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
     msg,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    hash::Hash,
+    bpf_loader_upgradeable,
+    ed25519_program,
+    program::{invoke, invoke_signed, set_return_data},
     pubkey::Pubkey,
     program_error::ProgramError,
-    program_pack::{Pack, IsInitialized},
-    sysvar::{rent::Rent, Sysvar},
+    program_pack::{Pack, IsInitialized, Sealed},
+    system_instruction,
+    system_program,
+    sysvar::{instructions as sysvar_instructions, rent::Rent, Sysvar},
 };
+use state::{Escrow, EscrowStatus, Listing, ListingStatus, Offer, OfferStatus};
+// 购买凭证和市场配置定义在state模块中，保留crate根下原有的公开路径
+pub use state::{MarketplaceConfig, PurchaseReceipt, ReceiptStatus};
 
-// 定义一个结构体来存储人工智能模型数据
-#[derive(Clone, Debug, Default, PartialEq)]
-pub struct AIModel {
-    pub is_initialized: bool,
-    pub name: String,
-    pub description: String,
-    pub owner: Pubkey,
-    pub price: u64,
-    pub model_file: Vec<u8>,
+// 程序版本号，每次发布改变指令或账户布局时递增
+// 版本2：所有账户数据以8字节类型标识开头，购买节流记录增加预售席位数，PurchasePresale增加限购席位数
+// 版本3：交易状态拆分为挂单、出价和托管三种账户，Sell增加草稿标记，新增SetListingStatus
+pub const PROGRAM_VERSION: u32 = 3;
+
+// 当前部署支持的功能位，客户端构造交易前可以据此判断功能是否可用
+pub const CAPABILITY_PAY_WHAT_YOU_WANT: u64 = 1 << 0;
+pub const CAPABILITY_LICENSE_TIERS: u64 = 1 << 1;
+pub const CAPABILITY_VOLUME_DISCOUNTS: u64 = 1 << 2;
+pub const CAPABILITY_CPI_INTERFACE: u64 = 1 << 3;
+pub const CAPABILITY_PROCEEDS_VAULT: u64 = 1 << 4;
+pub const CAPABILITY_SALES_LEDGER: u64 = 1 << 5;
+pub const CAPABILITY_SELLER_BOND: u64 = 1 << 6;
+pub const CAPABILITY_TRADE_STATES: u64 = 1 << 7;
+pub const CAPABILITY_TOKEN_METADATA: u64 = 1 << 8;
+pub const CAPABILITY_ARTIFACT_ATTESTATION: u64 = 1 << 9;
+pub const CAPABILITY_LISTING_APPROVAL: u64 = 1 << 10;
+pub const CAPABILITY_INFERENCE: u64 = 1 << 11;
+pub const CAPABILITY_RENTALS: u64 = 1 << 12;
+pub const CAPABILITY_SUBSCRIPTIONS: u64 = 1 << 13;
+pub const CAPABILITY_SIGNED_ORDERS: u64 = 1 << 14;
+pub const CAPABILITY_CREDITS: u64 = 1 << 15;
+pub const CAPABILITY_PRESALE: u64 = 1 << 16;
+pub const CAPABILITY_ORACLE_PRICING: u64 = 1 << 17;
+pub const CAPABILITY_WITHHOLDING: u64 = 1 << 18;
+pub const CAPABILITY_MEMO: u64 = 1 << 19;
+pub const CAPABILITY_COMMISSIONS: u64 = 1 << 20;
+pub const CAPABILITY_AIRDROPS: u64 = 1 << 21;
+pub const CAPABILITY_DROP_THROTTLE: u64 = 1 << 22;
+pub const CAPABILITY_VERIFICATION_PASS: u64 = 1 << 23;
+pub const CAPABILITY_CHARITY: u64 = 1 << 24;
+pub const CAPABILITY_LOYALTY_POINTS: u64 = 1 << 25;
+// 账户数据以ACCOUNT_DISCRIMINATOR_LEN字节的类型标识开头，字段偏移都从标识之后算起
+pub const CAPABILITY_ACCOUNT_DISCRIMINATORS: u64 = 1 << 26;
+// 挂单有草稿、在售、暂停和下架状态，出价金额托管在独立的托管账户中
+pub const CAPABILITY_LISTING_LIFECYCLE: u64 = 1 << 27;
+pub const PROGRAM_CAPABILITIES: u64 = CAPABILITY_PAY_WHAT_YOU_WANT
+    | CAPABILITY_LICENSE_TIERS
+    | CAPABILITY_VOLUME_DISCOUNTS
+    | CAPABILITY_CPI_INTERFACE
+    | CAPABILITY_PROCEEDS_VAULT
+    | CAPABILITY_SALES_LEDGER
+    | CAPABILITY_SELLER_BOND
+    | CAPABILITY_TRADE_STATES
+    | CAPABILITY_TOKEN_METADATA
+    | CAPABILITY_ARTIFACT_ATTESTATION
+    | CAPABILITY_LISTING_APPROVAL
+    | CAPABILITY_INFERENCE
+    | CAPABILITY_RENTALS
+    | CAPABILITY_SUBSCRIPTIONS
+    | CAPABILITY_SIGNED_ORDERS
+    | CAPABILITY_CREDITS
+    | CAPABILITY_PRESALE
+    | CAPABILITY_ORACLE_PRICING
+    | CAPABILITY_WITHHOLDING
+    | CAPABILITY_MEMO
+    | CAPABILITY_COMMISSIONS
+    | CAPABILITY_AIRDROPS
+    | CAPABILITY_DROP_THROTTLE
+    | CAPABILITY_VERIFICATION_PASS
+    | CAPABILITY_CHARITY
+    | CAPABILITY_LOYALTY_POINTS
+    | CAPABILITY_ACCOUNT_DISCRIMINATORS
+    | CAPABILITY_LISTING_LIFECYCLE;
+
+// 管理员可以开关的功能，用于逐步上线各个子系统，新创建的配置默认全部关闭
+pub const FEATURE_AUCTIONS: u64 = 1 << 0;
+pub const FEATURE_SUBSCRIPTIONS: u64 = 1 << 1;
+pub const FEATURE_RESALE: u64 = 1 << 2;
+pub const FEATURE_CROSS_CHAIN_ANNOUNCEMENTS: u64 = 1 << 3;
+
+// 紧急资金恢复最多可配置的守护者数量
+pub const MAX_GUARDIANS: usize = 5;
+// 资金恢复提案至少需要等待的秒数，给观察者留出反应时间
+pub const MIN_RECOVERY_DELAY_SECONDS: i64 = 24 * 60 * 60;
+// 资金恢复提案的PDA种子
+pub const RECOVERY_SEED: &[u8] = b"recovery";
+
+// 计算托管账户对应的资金恢复提案地址
+pub fn find_recovery_address(escrow: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECOVERY_SEED, escrow.as_ref()], program_id)
+}
+
+// 市场金库的PDA种子，平台收入存放在该账户中
+pub const TREASURY_SEED: &[u8] = b"treasury";
+// 金库提款排队后至少需要等待的秒数，与资金恢复一样给观察者留出反应时间
+pub const MIN_WITHDRAWAL_DELAY_SECONDS: i64 = 24 * 60 * 60;
+
+// 计算市场金库的地址
+pub fn find_treasury_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED], program_id)
+}
+
+// 卖家保证金的PDA种子
+pub const SELLER_BOND_SEED: &[u8] = b"seller_bond";
+
+// 计算卖家保证金账户的地址
+pub fn find_seller_bond_address(seller: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SELLER_BOND_SEED, seller.as_ref()], program_id)
+}
+
+// 市场手续费（基点），质押保证金达到门槛的卖家享受较低费率
+pub const DEFAULT_FEE_BPS: u64 = 200;
+pub const STAKED_FEE_BPS: u64 = 100;
+pub const STAKED_SELLER_MIN_BOND: u64 = 10 * LAMPORTS_PER_SOL;
+
+// 根据卖家质押的保证金计算手续费率
+pub fn marketplace_fee_bps(staked: u64) -> u64 {
+    if staked >= STAKED_SELLER_MIN_BOND {
+        STAKED_FEE_BPS
+    } else {
+        DEFAULT_FEE_BPS
+    }
+}
+
+// 计算成交金额对应的市场手续费，向下取整
+pub fn marketplace_fee(amount: u64, staked: u64) -> u64 {
+    fee_at_bps(amount, marketplace_fee_bps(staked))
+}
+
+// 按fee_bps计算amount的手续费，向下取整
+pub fn fee_at_bps(amount: u64, fee_bps: u64) -> u64 {
+    (amount as u128 * fee_bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+// 购买凭证的PDA种子，买家对同一模型的第n次购买对应purchase_index为n的凭证，
+// 因此purchase_index为0的凭证存在即说明钱包持有该模型的许可证
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+
+// 计算买家对模型第purchase_index次购买的凭证地址
+pub fn find_receipt_address(model: &Pubkey, buyer: &Pubkey, purchase_index: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[RECEIPT_SEED, model.as_ref(), buyer.as_ref(), &purchase_index.to_le_bytes()],
+        program_id,
+    )
+}
+
+// 争议的PDA种子，每张购买凭证最多对应一个争议
+pub const DISPUTE_SEED: &[u8] = b"dispute";
+// 买卖双方各自最多可以提交的证据条数
+pub const MAX_EVIDENCE_PER_PARTY: usize = 8;
+
+// 计算购买凭证对应的争议地址
+pub fn find_dispute_address(receipt: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DISPUTE_SEED, receipt.as_ref()], program_id)
+}
+
+// 买家统计的PDA种子
+pub const BUYER_STATS_SEED: &[u8] = b"buyer_stats";
+
+// 计算买家统计账户的地址
+pub fn find_buyer_stats_address(buyer: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BUYER_STATS_SEED, buyer.as_ref()], program_id)
+}
+
+// 限量发售（drop）中钱包购买节流记录的PDA种子
+pub const PURCHASE_THROTTLE_SEED: &[u8] = b"purchase_throttle";
+
+// 登记的慈善机构的PDA种子
+pub const CHARITY_SEED: &[u8] = b"charity";
+
+// 计算收款钱包为wallet的慈善机构账户地址
+pub fn find_charity_address(wallet: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CHARITY_SEED, wallet.as_ref()], program_id)
+}
+
+// 计算钱包对drop模型的购买节流记录地址
+pub fn find_purchase_throttle_address(model: &Pubkey, wallet: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PURCHASE_THROTTLE_SEED, model.as_ref(), wallet.as_ref()], program_id)
+}
+
+// 买家凭证索引分页的PDA种子，每页最多记录的凭证数量
+pub const BUYER_RECEIPTS_SEED: &[u8] = b"buyer_receipts";
+pub const BUYER_RECEIPTS_PAGE_CAPACITY: usize = 64;
+
+// 计算买家凭证索引第page_index页的地址
+pub fn find_buyer_receipts_address(buyer: &Pubkey, page_index: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[BUYER_RECEIPTS_SEED, buyer.as_ref(), &page_index.to_le_bytes()],
+        program_id,
+    )
+}
+
+// 评价的PDA种子，每张购买凭证只能评价一次
+pub const REVIEW_SEED: &[u8] = b"review";
+
+// 计算购买凭证对应的评价地址
+pub fn find_review_address(receipt: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REVIEW_SEED, receipt.as_ref()], program_id)
+}
+
+// 模型版本的PDA种子，模型的第n个发布版本对应version_index为n的账户
+pub const MODEL_VERSION_SEED: &[u8] = b"model_version";
+
+// 计算模型第version_index个发布版本的地址
+pub fn find_model_version_address(model: &Pubkey, version_index: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[MODEL_VERSION_SEED, model.as_ref(), &version_index.to_le_bytes()],
+        program_id,
+    )
+}
+
+// 更新日志的PDA种子，每个发布版本最多一条更新日志
+pub const CHANGELOG_SEED: &[u8] = b"changelog";
+// 更新日志正文URI和摘要的最大字节长度
+pub const MAX_CHANGELOG_URI_LEN: usize = 128;
+pub const MAX_CHANGELOG_SUMMARY_LEN: usize = 128;
+
+// 关注卖家和收藏模型的PDA种子，关系账户存在即表示关注/收藏成立
+pub const FOLLOW_SEED: &[u8] = b"follow";
+pub const FAVORITE_SEED: &[u8] = b"favorite";
+
+// 计算钱包关注卖家的关系地址
+pub fn find_follow_address(follower: &Pubkey, seller: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FOLLOW_SEED, follower.as_ref(), seller.as_ref()], program_id)
+}
+
+// 计算钱包收藏模型的关系地址
+pub fn find_favorite_address(wallet: &Pubkey, model: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FAVORITE_SEED, wallet.as_ref(), model.as_ref()], program_id)
+}
+
+// 精选列表的PDA种子，以及每个列表最多包含的模型数量
+pub const FEATURED_LIST_SEED: &[u8] = b"featured_list";
+pub const MAX_FEATURED_LISTINGS: usize = 16;
+// 精选列表标签的最大字节长度
+pub const MAX_FEATURED_LABEL_LEN: usize = 32;
+
+// 计算编号为list_id的精选列表地址
+pub fn find_featured_list_address(list_id: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEATURED_LIST_SEED, &list_id.to_le_bytes()], program_id)
+}
+
+// 分类注册表的PDA种子，注册表最多容纳的分类数量，以及分类标签的最大字节长度
+pub const CATEGORY_REGISTRY_SEED: &[u8] = b"category_registry";
+pub const MAX_CATEGORIES: usize = 64;
+pub const MAX_CATEGORY_LABEL_LEN: usize = 32;
+
+// 计算分类注册表的地址
+pub fn find_category_registry_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CATEGORY_REGISTRY_SEED], program_id)
+}
+
+// 本地化元数据的PDA种子，每个模型每种语言一个条目
+pub const LOCALIZED_METADATA_SEED: &[u8] = b"localized";
+// 语言代码（BCP 47，如"zh-Hans"）、本地化名称和描述URI的最大字节长度
+pub const MAX_LANG_CODE_LEN: usize = 16;
+pub const MAX_LOCALIZED_NAME_LEN: usize = 64;
+pub const MAX_LOCALIZED_URI_LEN: usize = 128;
+
+// 计算模型某种语言的本地化元数据地址
+pub fn find_localized_metadata_address(model: &Pubkey, lang: &str, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LOCALIZED_METADATA_SEED, model.as_ref(), lang.as_bytes()], program_id)
+}
+
+// 溯源证明验证器和溯源证明的PDA种子：验证器按verifier_id注册，每个模型每个验证器一条证明
+pub const PROVENANCE_VERIFIER_SEED: &[u8] = b"provenance_verifier";
+pub const PROVENANCE_SEED: &[u8] = b"provenance";
+// 验证器声明（如"trained on licensed data"）的最大字节长度
+pub const MAX_PROVENANCE_CLAIM_LEN: usize = 64;
+// Groth16证明允许的最大公开输入数量
+pub const MAX_PROOF_PUBLIC_INPUTS: usize = 4;
+// Groth16验证密钥: alpha(G1) + beta/gamma/delta(G2) + (公开输入数+1)个IC点(G1)
+pub const GROTH16_VK_FIXED_LEN: usize = 64 + 128 * 3;
+pub const MAX_VERIFYING_KEY_LEN: usize = GROTH16_VK_FIXED_LEN + 64 * (MAX_PROOF_PUBLIC_INPUTS + 1);
+// Groth16证明: A(G1) + B(G2) + C(G1)
+pub const GROTH16_PROOF_LEN: usize = 64 + 128 + 64;
+
+// 计算溯源证明验证器地址
+pub fn find_provenance_verifier_address(verifier_id: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROVENANCE_VERIFIER_SEED, &verifier_id.to_le_bytes()], program_id)
+}
+
+// 计算模型在某个验证器下的溯源证明地址
+pub fn find_provenance_address(model: &Pubkey, verifier: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROVENANCE_SEED, model.as_ref(), verifier.as_ref()], program_id)
+}
+
+// 推理结果承诺的PDA种子，每个工作节点对某个模型版本的每个输入最多提交一次
+pub const INFERENCE_COMMITMENT_SEED: &[u8] = b"inference";
+
+// 计算工作节点对模型版本某个输入的推理结果承诺地址
+pub fn find_inference_commitment_address(
+    model_version: &Pubkey,
+    worker: &Pubkey,
+    input_hash: &[u8; 32],
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[INFERENCE_COMMITMENT_SEED, model_version.as_ref(), worker.as_ref(), input_hash],
+        program_id,
+    )
+}
+
+// 推理请求的PDA种子，request_id由请求者自行选择，同一请求者对同一模型不能重复
+pub const INFERENCE_REQUEST_SEED: &[u8] = b"inference_request";
+// 工作节点锁定推理请求的时长（秒），超时未完成的锁定可由任何人释放
+pub const INFERENCE_CLAIM_TIMEOUT: i64 = 10 * 60;
+// 请求者可以设置的最长结果挑战期（秒）
+pub const MAX_INFERENCE_CHALLENGE_WINDOW: i64 = 3 * 24 * 60 * 60;
+// 结果证明URI的最大字节长度
+pub const MAX_PROOF_URI_LEN: usize = 128;
+
+// 计算请求者对模型的推理请求地址
+pub fn find_inference_request_address(model: &Pubkey, requester: &Pubkey, request_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[INFERENCE_REQUEST_SEED, model.as_ref(), requester.as_ref(), &request_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+// 工作节点的PDA种子，每个运营者一个
+pub const WORKER_SEED: &[u8] = b"worker";
+
+// 工作节点完成推理请求所需的最低质押
+pub const MIN_WORKER_STAKE: u64 = LAMPORTS_PER_SOL;
+// 最近一次完成请求后需等待的秒数才能取回质押，留出发现和罚没错误结果的时间
+pub const WORKER_UNSTAKE_DELAY: i64 = 7 * 24 * 60 * 60;
+// 结果被证伪时罚没的质押比例，罚没部分转给请求者
+pub const WORKER_SLASH_BPS: u64 = 5_000;
+
+// 计算运营者的工作节点地址
+pub fn find_worker_address(operator: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[WORKER_SEED, operator.as_ref()], program_id)
+}
+
+// 流式租用的PDA种子，每个租用者对每个模型同时只有一个租用流
+pub const RENTAL_STREAM_SEED: &[u8] = b"rental_stream";
+
+// 计算租用者对模型的租用流地址
+pub fn find_rental_stream_address(model: &Pubkey, renter: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RENTAL_STREAM_SEED, model.as_ref(), renter.as_ref()], program_id)
+}
+
+// 委托开发的PDA种子，以及一份委托最多包含的里程碑数量
+pub const COMMISSION_SEED: &[u8] = b"commission";
+pub const MAX_COMMISSION_MILESTONES: usize = 8;
+
+// 计算买家向卖家发起的委托地址，commission_id由买家选择
+pub fn find_commission_address(buyer: &Pubkey, seller: &Pubkey, commission_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[COMMISSION_SEED, buyer.as_ref(), seller.as_ref(), &commission_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+// 订阅方案和订阅的PDA种子：每个模型一个订阅方案，每个订阅者对每个模型一个订阅
+pub const SUBSCRIPTION_PLAN_SEED: &[u8] = b"subscription_plan";
+pub const SUBSCRIPTION_SEED: &[u8] = b"subscription";
+// 一个订阅方案最多定义的档位数量
+pub const MAX_SUBSCRIPTION_TIERS: usize = 4;
+
+// 订阅档位可以授予的访问范围
+pub const ACCESS_SCOPE_INFERENCE: u32 = 1 << 0;
+pub const ACCESS_SCOPE_DOWNLOAD: u32 = 1 << 1;
+pub const ACCESS_SCOPE_FINE_TUNE: u32 = 1 << 2;
+pub const ACCESS_SCOPE_COMMERCIAL: u32 = 1 << 3;
+
+// 计算模型的订阅方案地址
+pub fn find_subscription_plan_address(model: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SUBSCRIPTION_PLAN_SEED, model.as_ref()], program_id)
+}
+
+// 计算订阅者对模型的订阅地址
+pub fn find_subscription_address(model: &Pubkey, subscriber: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SUBSCRIPTION_SEED, model.as_ref(), subscriber.as_ref()], program_id)
+}
+
+// 组织的PDA种子、成员上限和名称的最大字节长度
+pub const ORGANIZATION_SEED: &[u8] = b"organization";
+pub const MAX_ORGANIZATION_MEMBERS: usize = 16;
+pub const MAX_ORGANIZATION_NAME_LEN: usize = 32;
+
+// 计算创建者的组织地址，org_id由创建者选择
+pub fn find_organization_address(creator: &Pubkey, org_id: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ORGANIZATION_SEED, creator.as_ref(), &org_id.to_le_bytes()], program_id)
+}
+
+// 角色分配的PDA种子
+pub const ROLE_SEED: &[u8] = b"role";
+
+// 计算某个持有者的角色分配地址，每个角色一个PDA
+pub fn find_role_address(role: Role, holder: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ROLE_SEED, &[role as u8], holder.as_ref()], program_id)
+}
+
+// 许可证用量计数的PDA种子，以及计量周期的slot数（约一小时）
+pub const USAGE_COUNTER_SEED: &[u8] = b"usage";
+pub const METERING_EPOCH_SLOTS: u64 = 9_000;
+
+// 计算购买凭证的用量计数地址
+pub fn find_usage_counter_address(receipt: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[USAGE_COUNTER_SEED, receipt.as_ref()], program_id)
+}
+
+// 钱包预付额度的PDA种子，按量计费的用量从额度中结算；推理和下载计量共用同一额度
+pub const CREDIT_SEED: &[u8] = b"credits";
+
+// 计算钱包以mint计价的预付额度地址，mint为全零表示SOL额度
+pub fn find_credit_address(wallet: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CREDIT_SEED, wallet.as_ref(), mint.as_ref()], program_id)
+}
+
+// 市场额度代币mint和储备权限的PDA种子：额度代币按1:1由存入储备的代币铸造，
+// 储备代币托管在储备权限PDA的关联代币账户中，额度mint同时作为自身的铸造权限
+pub const CREDIT_MINT_SEED: &[u8] = b"credit_mint";
+pub const CREDIT_RESERVE_SEED: &[u8] = b"credit_reserve";
+
+// 计算市场额度代币的mint地址
+pub fn find_credit_mint_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CREDIT_MINT_SEED], program_id)
+}
+
+// 计算额度储备的权限地址
+pub fn find_credit_reserve_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CREDIT_RESERVE_SEED], program_id)
+}
+
+// 空投活动和空投许可证凭证的PDA种子：每个钱包在同一活动中至多获得一份凭证，由凭证地址去重
+pub const AIRDROP_CAMPAIGN_SEED: &[u8] = b"airdrop_campaign";
+pub const AIRDROP_RECEIPT_SEED: &[u8] = b"airdrop_receipt";
+// Merkle证明最多包含的节点数，对应不超过2^24个叶子
+pub const MAX_MERKLE_PROOF_LEN: usize = 24;
+
+// 计算卖家为模型创建的空投活动地址，campaign_id由卖家选择
+pub fn find_airdrop_campaign_address(model: &Pubkey, campaign_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AIRDROP_CAMPAIGN_SEED, model.as_ref(), &campaign_id.to_le_bytes()], program_id)
+}
+
+// 计算钱包在空投活动中获得的许可证凭证地址
+pub fn find_airdrop_receipt_address(campaign: &Pubkey, wallet: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AIRDROP_RECEIPT_SEED, campaign.as_ref(), wallet.as_ref()], program_id)
+}
+
+// 钱包链下签名订单的nonce账户PDA种子，以及min_nonce之后用位图记录的nonce数量
+pub const ORDER_NONCE_SEED: &[u8] = b"order_nonce";
+pub const ORDER_NONCE_WINDOW: u64 = 256;
+
+// 计算钱包的订单nonce账户地址
+pub fn find_order_nonce_address(wallet: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ORDER_NONCE_SEED, wallet.as_ref()], program_id)
+}
+
+// 链下签名订单消息的域前缀，避免签名被当作其他用途的消息
+pub const SIGNED_ORDER_DOMAIN: &[u8] = b"ai_marketplace_order";
+// 签名订单成交进度的PDA种子
+pub const ORDER_FILL_SEED: &[u8] = b"order_fill";
+
+// 计算卖家签名订单的成交进度地址，订单由卖家和nonce唯一确定
+pub fn find_order_fill_address(seller: &Pubkey, nonce: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ORDER_FILL_SEED, seller.as_ref(), &nonce.to_le_bytes()], program_id)
+}
+
+// 校验BCP 47语言代码：2到3个小写字母的主语言子标签，
+// 之后是若干以'-'分隔、由1到8个字母或数字组成的子标签
+pub fn validate_lang_code(lang: &str) -> ProgramResult {
+    let mut subtags = lang.split('-');
+    let primary = subtags.next().unwrap_or_default();
+    let valid = lang.len() <= MAX_LANG_CODE_LEN
+        && (2..=3).contains(&primary.len())
+        && primary.bytes().all(|b| b.is_ascii_lowercase())
+        && subtags.all(|subtag| (1..=8).contains(&subtag.len()) && subtag.bytes().all(|b| b.is_ascii_alphanumeric()));
+    if !valid {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+// 计算发布版本对应的更新日志地址
+pub fn find_changelog_address(model_version: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CHANGELOG_SEED, model_version.as_ref()], program_id)
 }
 
-// 实现IsInitialized trait来检查AIModel是否已初始化
-impl IsInitialized for AIModel {
-    fn is_initialized(&self) -> bool {
-        self.is_initialized
+// Token-2022和关联代币账户程序，徽章以不可转让的Token-2022代币发放
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = solana_program::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = solana_program::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+// 带NonTransferable扩展的mint账户大小：165字节基础账户 + 1字节账户类型 + 4字节TLV头
+const NON_TRANSFERABLE_MINT_LEN: usize = 165 + 1 + 4;
+// 不带扩展的Token-2022 mint账户大小
+const TOKEN_2022_MINT_LEN: usize = 82;
+// 徽章mint和领取记录的PDA种子，徽章mint同时作为自身的铸造权限
+pub const BADGE_MINT_SEED: &[u8] = b"badge_mint";
+pub const BADGE_CLAIM_SEED: &[u8] = b"badge";
+// 领取徽章所需的里程碑
+pub const PURCHASER_BADGE_MIN_PURCHASES: u64 = 10;
+pub const REVIEWER_BADGE_MIN_REVIEWS: u64 = 5;
+// 成就徽章mint的PDA种子，以及成就的里程碑：累计100笔销售，至少10条评价且平均不低于4.5星
+pub const ACHIEVEMENT_MINT_SEED: &[u8] = b"achievement_mint";
+pub const SALES_ACHIEVEMENT_MIN_SALES: u64 = 100;
+pub const TOP_RATED_MIN_REVIEWS: u64 = 10;
+pub const TOP_RATED_MIN_RATING_X10: u64 = 45;
+
+// 计算指定成就的徽章mint地址
+pub fn find_achievement_mint_address(achievement: Achievement, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ACHIEVEMENT_MINT_SEED, &[achievement as u8]], program_id)
+}
+
+// 计算指定种类徽章的mint地址
+pub fn find_badge_mint_address(kind: BadgeKind, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BADGE_MINT_SEED, &[kind as u8]], program_id)
+}
+
+// 计算钱包领取指定种类徽章的记录地址
+pub fn find_badge_claim_address(kind: BadgeKind, wallet: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BADGE_CLAIM_SEED, &[kind as u8], wallet.as_ref()], program_id)
+}
+
+// 计算钱包持有Token-2022代币的关联代币账户地址
+pub fn find_token_2022_associated_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), TOKEN_2022_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+// 市场配置账户的PDA种子
+pub const CONFIG_SEED: &[u8] = b"config";
+
+// 计算市场配置PDA的地址
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+// 卖家统计账户的PDA种子
+pub const SELLER_STATS_SEED: &[u8] = b"seller_stats";
+
+// 计算卖家统计PDA的地址
+pub fn find_seller_stats_address(seller: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SELLER_STATS_SEED, seller.as_ref()], program_id)
+}
+
+// 卖家收益金库的PDA种子
+pub const PROCEEDS_VAULT_SEED: &[u8] = b"proceeds_vault";
+
+// 计算卖家收益金库PDA的地址
+pub fn find_proceeds_vault_address(seller: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROCEEDS_VAULT_SEED, seller.as_ref()], program_id)
+}
+
+// 销售账本分页的PDA种子
+pub const SALES_LEDGER_SEED: &[u8] = b"sales_ledger";
+// 每个账本分页最多记录的成交数量
+pub const SALES_LEDGER_PAGE_CAPACITY: usize = 32;
+// 使用原生SOL付款时账本中记录的付款币种
+pub const NATIVE_PAYMENT_MINT: Pubkey = Pubkey::new_from_array([0; 32]);
+
+// 计算模型销售账本第page_index页的地址
+pub fn find_sales_ledger_address(model: &Pubkey, page_index: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SALES_LEDGER_SEED, model.as_ref(), &page_index.to_le_bytes()],
+        program_id,
+    )
+}
+
+// 挂单和出价的PDA种子，按钱包、模型和价格推导，与Metaplex Auction House的trade state语义一致；
+// 出价的托管账户按出价地址推导
+pub const LISTING_SEED: &[u8] = b"listing";
+pub const OFFER_SEED: &[u8] = b"offer";
+pub const ESCROW_SEED: &[u8] = b"escrow";
+
+// 计算卖家以指定价格挂单的地址
+pub fn find_listing_address(seller: &Pubkey, model: &Pubkey, price: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LISTING_SEED, seller.as_ref(), model.as_ref(), &price.to_le_bytes()],
+        program_id,
+    )
+}
+
+// 计算买家以指定价格出价的地址
+pub fn find_offer_address(buyer: &Pubkey, model: &Pubkey, price: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[OFFER_SEED, buyer.as_ref(), model.as_ref(), &price.to_le_bytes()],
+        program_id,
+    )
+}
+
+// 计算出价的托管账户地址
+pub fn find_escrow_address(offer: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ESCROW_SEED, offer.as_ref()], program_id)
+}
+
+// Metaplex Token Metadata程序，已作为NFT存在的模型可以关联其元数据账户
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = solana_program::pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+// 价格源的PDA种子：由Oracle角色推送每个计价单位对应的lamports，供按计价单位标价的模型换算售价；
+// 计价金额带ORACLE_QUOTE_DECIMALS位小数，例如美元计价时1_000_000表示1美元
+pub const PRICE_FEED_SEED: &[u8] = b"price_feed";
+pub const ORACLE_QUOTE_DECIMALS: u32 = 6;
+
+// 计算feed_id对应的价格源地址
+pub fn find_price_feed_address(feed_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PRICE_FEED_SEED, &feed_id.to_le_bytes()], program_id)
+}
+
+// SPL Memo程序（v2），成交时可选地附带结构化备忘，供以备忘而不是程序日志对账的交易所和财务流水线使用
+pub const SPL_MEMO_PROGRAM_ID: Pubkey = solana_program::pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+// Civic网关程序，其网关令牌可作为购买需要真人验证的模型时出示的验证通行证
+pub const CIVIC_GATEWAY_PROGRAM_ID: Pubkey = solana_program::pubkey!("gatem74V238djXdzWnJf94Wo1DcnuGkfijbf3AuBhfs");
+
+// Wormhole核心桥程序，其他链上的镜像市场通过Wormhole消息跟踪本市场的模型上架和成交
+pub const WORMHOLE_CORE_BRIDGE_PROGRAM_ID: Pubkey = solana_program::pubkey!("worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMTth");
+// 本程序的Wormhole发送者PDA种子，以及消息要求的确认级别（1为finalized）
+pub const WORMHOLE_EMITTER_SEED: &[u8] = b"emitter";
+pub const WORMHOLE_CONSISTENCY_FINALIZED: u8 = 1;
+// Wormhole桥配置账户中消息手续费的字节偏移：guardian_set_index u32 + last_lamports u64 + guardian_set_expiration_time u32
+const WORMHOLE_BRIDGE_FEE_OFFSET: usize = 4 + 8 + 4;
+
+// 计算本程序的Wormhole发送者地址
+pub fn find_wormhole_emitter_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[WORMHOLE_EMITTER_SEED], program_id)
+}
+
+// Token Metadata账户的key字节，MetadataV1为4
+const TOKEN_METADATA_KEY_V1: u8 = 4;
+
+// 计算mint对应的Token Metadata账户地址
+pub fn find_token_metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+}
+
+// 每个模型最多可定义的许可证档位数量（个人 / 商业 / 企业）
+pub const MAX_LICENSE_TIERS: usize = 3;
+// 许可条款URI的最大字节长度
+pub const MAX_TERMS_URI_LEN: usize = 128;
+// 模型许可证（SPDX表达式或自定义条款URI）的最大字节长度
+pub const MAX_LICENSE_LEN: usize = 128;
+// SPDX表达式中括号的最大嵌套层数，限制递归深度
+const MAX_SPDX_NESTING: usize = 4;
+// 批量购买折扣曲线最多包含的档位数量
+pub const MAX_VOLUME_DISCOUNTS: usize = 4;
+// 链上model_file字段的固定容量
+pub const MODEL_FILE_LEN: usize = 1024;
+// 模型名称和简介的最大字节长度，链上以0填充的定长字段存放
+pub const MAX_MODEL_NAME_LEN: usize = 32;
+pub const MAX_MODEL_DESCRIPTION_LEN: usize = 32;
+// 链下元数据JSON的格式版本，对应仓库中的metadata.schema.json
+pub const METADATA_SCHEMA_VERSION: u8 = 1;
+// 链下元数据URI的最大字节长度
+pub const MAX_METADATA_URI_LEN: usize = 128;
+// 模型产物引用（Arweave交易ID等）的最大字节长度
+pub const MAX_ARTIFACT_URI_LEN: usize = 128;
+// Arweave交易ID为32字节的base64url编码，共43个字符
+const ARWEAVE_TX_ID_LEN: usize = 43;
+// CIDv0为base58btc编码的sha2-256 multihash，固定以"Qm"开头共46个字符
+const CID_V0_LEN: usize = 46;
+// CIDv1支持的内容编码：raw、dag-pb、dag-cbor
+const CID_CODEC_RAW: u64 = 0x55;
+const CID_CODEC_DAG_PB: u64 = 0x70;
+const CID_CODEC_DAG_CBOR: u64 = 0x71;
+// 支持的multihash哈希函数，摘要长度均为32字节
+const MULTIHASH_SHA2_256: u64 = 0x12;
+const MULTIHASH_BLAKE3: u64 = 0x1e;
+const MULTIHASH_DIGEST_LEN: u64 = 32;
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+// 基点分母，10000基点即100%
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// 定义定价模式：固定价格，或由买家自定金额（不低于卖家设定的底价）
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PricingMode {
+    #[default]
+    Fixed,
+    PayWhatYouWant,
+}
+
+impl PricingMode {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(PricingMode::Fixed),
+            1 => Ok(PricingMode::PayWhatYouWant),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+// 定义模型许可证能否转让：仅限本人使用、转让时向卖家支付版税，或自由转让
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LicenseTransferability {
+    #[default]
+    NonTransferable,
+    TransferableWithRoyalty,
+    FreelyTransferable,
+}
+
+impl LicenseTransferability {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(LicenseTransferability::NonTransferable),
+            1 => Ok(LicenseTransferability::TransferableWithRoyalty),
+            2 => Ok(LicenseTransferability::FreelyTransferable),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+// 定义推理请求的状态：等待处理 -> 已提交结果（挑战期） -> [被挑战] -> 已结算或被罚没；取消的请求直接关闭
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum InferenceRequestStatus {
+    #[default]
+    Open,
+    Fulfilled,
+    // 已完成的结果被证伪，工作节点的质押已被罚没
+    Slashed,
+    // 工作节点已提交结果，处于挑战期内
+    Submitted,
+    // 请求者在挑战期内对结果提出异议，等待仲裁者裁定
+    Challenged,
+}
+
+impl InferenceRequestStatus {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(InferenceRequestStatus::Open),
+            1 => Ok(InferenceRequestStatus::Fulfilled),
+            2 => Ok(InferenceRequestStatus::Slashed),
+            3 => Ok(InferenceRequestStatus::Submitted),
+            4 => Ok(InferenceRequestStatus::Challenged),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+// 定义组织成员的角色：管理员管理成员并提取组织资金，编辑可以修改组织模型的元数据；管理员拥有编辑的全部权限
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OrganizationRole {
+    Admin,
+    #[default]
+    Editor,
+}
+
+impl OrganizationRole {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(OrganizationRole::Admin),
+            1 => Ok(OrganizationRole::Editor),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    // 该角色是否具备required角色的权限
+    pub fn grants(&self, required: OrganizationRole) -> bool {
+        matches!((self, required), (OrganizationRole::Admin, _) | (OrganizationRole::Editor, OrganizationRole::Editor))
+    }
+}
+
+// 定义市场级角色：超级管理员管理配置和角色，版主管理精选与分类，仲裁者裁决争议，
+// 预言机审核上架和产物，财务管理金库；超级管理员拥有全部角色的权限，配置中的admin始终是超级管理员
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Role {
+    #[default]
+    SuperAdmin,
+    Moderator,
+    Arbiter,
+    Oracle,
+    Treasurer,
+}
+
+impl Role {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(Role::SuperAdmin),
+            1 => Ok(Role::Moderator),
+            2 => Ok(Role::Arbiter),
+            3 => Ok(Role::Oracle),
+            4 => Ok(Role::Treasurer),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    // 该角色是否具备required角色的权限
+    pub fn grants(&self, required: Role) -> bool {
+        *self == Role::SuperAdmin || *self == required
+    }
+}
+
+// 定义委托里程碑的状态：待交付 -> 已交付 -> 买家确认 -> 卖家已领取；
+// 有争议的里程碑由仲裁者裁定为确认（卖家可领取）或退款给买家
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MilestoneStatus {
+    #[default]
+    Pending,
+    Submitted,
+    Approved,
+    Released,
+    Disputed,
+    Refunded,
+}
+
+impl MilestoneStatus {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(MilestoneStatus::Pending),
+            1 => Ok(MilestoneStatus::Submitted),
+            2 => Ok(MilestoneStatus::Approved),
+            3 => Ok(MilestoneStatus::Released),
+            4 => Ok(MilestoneStatus::Disputed),
+            5 => Ok(MilestoneStatus::Refunded),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+// 定义徽章种类：累计购买达到里程碑的买家徽章，和累计发表已验证评价的评价者徽章
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BadgeKind {
+    Purchaser,
+    Reviewer,
+}
+
+impl BadgeKind {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(BadgeKind::Purchaser),
+            1 => Ok(BadgeKind::Reviewer),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    // 判断买家统计是否已达到领取该徽章的里程碑
+    pub fn is_earned(&self, stats: &BuyerStats) -> bool {
+        match self {
+            BadgeKind::Purchaser => stats.total_purchases >= PURCHASER_BADGE_MIN_PURCHASES,
+            BadgeKind::Reviewer => stats.verified_reviews >= REVIEWER_BADGE_MIN_REVIEWS,
+        }
+    }
+}
+
+// 定义成就：卖家的首笔销售、累计100笔销售和高评分卖家，以及买家的首次购买；
+// 达成后记录在卖家或买家统计的achievements标志位中，领取后记录在claimed_achievements中
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Achievement {
+    FirstSale,
+    HundredSales,
+    TopRated,
+    FirstPurchase,
+}
+
+impl Achievement {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(Achievement::FirstSale),
+            1 => Ok(Achievement::HundredSales),
+            2 => Ok(Achievement::TopRated),
+            3 => Ok(Achievement::FirstPurchase),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    // 成就在achievements和claimed_achievements中的标志位
+    pub fn flag(&self) -> u32 {
+        1 << (*self as u8)
+    }
+
+    // 成就记录在卖家统计中还是买家统计中
+    pub fn is_seller_achievement(&self) -> bool {
+        !matches!(self, Achievement::FirstPurchase)
+    }
+}
+
+// 领取earned中已达成的成就，每个成就只能领取一次
+fn claim_achievement_flag(earned: u32, claimed: &mut u32, achievement: Achievement) -> ProgramResult {
+    let flag = achievement.flag();
+    if earned & flag == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if *claimed & flag != 0 {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    *claimed |= flag;
+    Ok(())
+}
+
+// 定义模型文件的框架/格式，买家可按固定偏移MODEL_FORMAT_OFFSET筛选与自己运行时兼容的模型
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ModelFormat {
+    #[default]
+    Onnx,
+    SafeTensors,
+    Gguf,
+    PyTorch,
+    TensorFlow,
+}
+
+impl ModelFormat {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(ModelFormat::Onnx),
+            1 => Ok(ModelFormat::SafeTensors),
+            2 => Ok(ModelFormat::Gguf),
+            3 => Ok(ModelFormat::PyTorch),
+            4 => Ok(ModelFormat::TensorFlow),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    // 格式在工作节点supported_formats位图中对应的位
+    pub fn bit(&self) -> u8 {
+        1 << (*self as u8)
+    }
+}
+
+// 所有已定义模型格式的位图，工作节点声明的supported_formats不能超出此范围
+pub const ALL_MODEL_FORMATS: u8 = (1 << 5) - 1;
+
+// 定义工作节点的硬件类别，请求者据此挑选合适的算力提供方
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HardwareClass {
+    #[default]
+    Cpu,
+    ConsumerGpu,
+    DatacenterGpu,
+    Accelerator,
+}
+
+impl HardwareClass {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(HardwareClass::Cpu),
+            1 => Ok(HardwareClass::ConsumerGpu),
+            2 => Ok(HardwareClass::DatacenterGpu),
+            3 => Ok(HardwareClass::Accelerator),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+// 定义模型产物的存储位置：直接存放在model_file中，或存放在Arweave、IPFS、HTTPS服务器上
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StorageClass {
+    #[default]
+    OnChain,
+    Arweave,
+    Ipfs,
+    Https,
+}
+
+impl StorageClass {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(StorageClass::OnChain),
+            1 => Ok(StorageClass::Arweave),
+            2 => Ok(StorageClass::Ipfs),
+            3 => Ok(StorageClass::Https),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    // 校验产物引用的格式，链上存储时不需要引用
+    pub fn validate_uri(&self, uri: &str) -> ProgramResult {
+        let valid = match self {
+            StorageClass::OnChain => uri.is_empty(),
+            StorageClass::Arweave => {
+                uri.len() == ARWEAVE_TX_ID_LEN
+                    && uri.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+            }
+            StorageClass::Ipfs => validate_ipfs_cid(uri).is_ok(),
+            // 必须是带主机名的https地址，且只包含可打印ASCII字符
+            StorageClass::Https => uri
+                .strip_prefix("https://")
+                .map(|rest| !rest.is_empty() && !rest.starts_with('/'))
+                .unwrap_or(false)
+                && uri.bytes().all(|b| b.is_ascii_graphic()),
+        };
+        if !valid {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    // 校验声明的产物大小：链上产物不能超过model_file的容量，链下产物必须声明非零大小
+    pub fn validate_size(&self, size_bytes: u64) -> ProgramResult {
+        let valid = match self {
+            StorageClass::OnChain => size_bytes <= MODEL_FILE_LEN as u64,
+            StorageClass::Arweave | StorageClass::Ipfs | StorageClass::Https => size_bytes > 0,
+        };
+        if !valid {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    // 该存储位置的产物是否需要验证人确认后才能售卖；IPFS按内容寻址，CID本身即可校验内容
+    pub fn requires_attestation(&self) -> bool {
+        matches!(self, StorageClass::Arweave | StorageClass::Https)
+    }
+}
+
+// 校验模型许可证：SPDX许可证表达式（如"MIT"、"Apache-2.0 OR MIT"、"GPL-2.0+ WITH Classpath-exception-2.0"），
+// 或以https://、ar://、ipfs://开头的自定义条款URI
+pub fn validate_license(license: &str) -> ProgramResult {
+    if license.is_empty() || license.len() > MAX_LICENSE_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let valid = if ["https://", "ar://", "ipfs://"].iter().any(|scheme| license.starts_with(scheme)) {
+        license.bytes().all(|b| b.is_ascii_graphic())
+    } else {
+        let tokens = tokenize_spdx(license);
+        matches!(parse_spdx_expression(&tokens, 0), Some(rest) if rest.is_empty())
+    };
+    if !valid {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+// 将SPDX表达式拆分为括号和以空白分隔的单词
+fn tokenize_spdx(expression: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in expression.char_indices() {
+        if c == '(' || c == ')' || c.is_whitespace() {
+            if let Some(s) = start.take() {
+                tokens.push(&expression[s..i]);
+            }
+            if !c.is_whitespace() {
+                tokens.push(&expression[i..i + 1]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(&expression[s..]);
+    }
+    tokens
+}
+
+// SPDX许可证或例外标识符只能包含字母、数字、'.'和'-'
+fn is_spdx_id(id: &str) -> bool {
+    !id.is_empty()
+        && !matches!(id, "AND" | "OR" | "WITH")
+        && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'.' || b == b'-')
+}
+
+// expression := term (("AND" | "OR") term)*，返回未消费的token
+fn parse_spdx_expression<'a, 'b>(tokens: &'b [&'a str], depth: usize) -> Option<&'b [&'a str]> {
+    let mut rest = parse_spdx_term(tokens, depth)?;
+    while let Some((&op, next)) = rest.split_first() {
+        if op != "AND" && op != "OR" {
+            break;
+        }
+        rest = parse_spdx_term(next, depth)?;
+    }
+    Some(rest)
+}
+
+// term := "(" expression ")" | license-id ["+"] ["WITH" exception-id]
+fn parse_spdx_term<'a, 'b>(tokens: &'b [&'a str], depth: usize) -> Option<&'b [&'a str]> {
+    let (&first, rest) = tokens.split_first()?;
+    if first == "(" {
+        if depth >= MAX_SPDX_NESTING {
+            return None;
+        }
+        let rest = parse_spdx_expression(rest, depth + 1)?;
+        return match rest.split_first() {
+            Some((&")", rest)) => Some(rest),
+            _ => None,
+        };
+    }
+    if !is_spdx_id(first.strip_suffix('+').unwrap_or(first)) {
+        return None;
+    }
+    match rest.split_first() {
+        Some((&"WITH", rest)) => {
+            let (&exception, rest) = rest.split_first()?;
+            is_spdx_id(exception).then_some(rest)
+        }
+        _ => Some(rest),
+    }
+}
+
+// 读取一个无符号LEB128变长整数（multiformats中的varint），最多9字节
+fn read_varint(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+    let mut value: u64 = 0;
+    for (i, byte) in input.iter().enumerate().take(9) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, &input[i + 1..]));
+        }
+    }
+    Err(ProgramError::InvalidArgument)
+}
+
+// 解码base58btc字符串，前导'1'对应前导零字节
+fn decode_base58(input: &str) -> Result<Vec<u8>, ProgramError> {
+    // 按小端顺序累积，最后再反转
+    let mut output: Vec<u8> = Vec::new();
+    for c in input.bytes() {
+        let mut carry = BASE58_ALPHABET
+            .iter()
+            .position(|&a| a == c)
+            .ok_or(ProgramError::InvalidArgument)? as u32;
+        for byte in output.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            output.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    output.extend(input.bytes().take_while(|&c| c == b'1').map(|_| 0));
+    output.reverse();
+    Ok(output)
+}
+
+// 解码RFC4648小写base32字符串（multibase前缀'b'，无填充）
+fn decode_base32(input: &str) -> Result<Vec<u8>, ProgramError> {
+    let mut output = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in input.bytes() {
+        let val = match c {
+            b'a'..=b'z' => c - b'a',
+            b'2'..=b'7' => c - b'2' + 26,
+            _ => return Err(ProgramError::InvalidArgument),
+        };
+        buffer = (buffer << 5) | val as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    // 末尾剩余的位必须是零填充，且不能多出一个完整字符
+    if bits >= 5 || buffer != 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(output)
+}
+
+// 校验IPFS CID的结构：CIDv0（Qm...）或以base32/base58btc编码的CIDv1，
+// multihash必须使用支持的哈希函数且摘要长度正确
+pub fn validate_ipfs_cid(cid: &str) -> ProgramResult {
+    let (multihash, v0) = if cid.len() == CID_V0_LEN && cid.starts_with("Qm") {
+        (decode_base58(cid)?, true)
+    } else {
+        let bytes = match cid.as_bytes().first() {
+            Some(b'b') => decode_base32(&cid[1..])?,
+            Some(b'z') => decode_base58(&cid[1..])?,
+            _ => return Err(ProgramError::InvalidArgument),
+        };
+        let (version, rest) = read_varint(&bytes)?;
+        let (codec, rest) = read_varint(rest)?;
+        if version != 1 || !matches!(codec, CID_CODEC_RAW | CID_CODEC_DAG_PB | CID_CODEC_DAG_CBOR) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        (rest.to_vec(), false)
+    };
+
+    let (hash_function, rest) = read_varint(&multihash)?;
+    let (digest_len, digest) = read_varint(rest)?;
+    let supported = match hash_function {
+        MULTIHASH_SHA2_256 => true,
+        MULTIHASH_BLAKE3 => !v0,
+        _ => false,
+    };
+    if !supported || digest_len != MULTIHASH_DIGEST_LEN || digest.len() as u64 != digest_len {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+// 账户布局中各字段类型序列化后的字节数。Pack::LEN通过packed_len!按字段类型求和得到，
+// 不再手写数字，字段增删或改类型时LEN随之变化
+pub trait PackedSize {
+    const PACKED_SIZE: usize;
+}
+
+macro_rules! impl_packed_size {
+    ($($ty:ty => $size:expr),+ $(,)?) => {
+        $(impl PackedSize for $ty {
+            const PACKED_SIZE: usize = $size;
+        })+
+    };
+}
+
+impl<T: PackedSize, const N: usize> PackedSize for [T; N] {
+    const PACKED_SIZE: usize = T::PACKED_SIZE * N;
+}
+
+// 按pack顺序列出字段类型求和：定长字符串写作[u8; MAX_*_LEN]，Vec写作计数前缀加定长数组
+macro_rules! packed_len {
+    ($($ty:ty),+ $(,)?) => {
+        0 $(+ <$ty as PackedSize>::PACKED_SIZE)+
+    };
+}
+
+impl_packed_size!(bool => 1, u8 => 1, u16 => 2, u32 => 4, u64 => 8, i64 => 8, Pubkey => 32);
+
+// 枚举字段统一以一个字节存储判别值
+impl_packed_size!(
+    EscrowStatus => 1,
+    HardwareClass => 1,
+    InferenceRequestStatus => 1,
+    LicenseTransferability => 1,
+    ListingStatus => 1,
+    MilestoneStatus => 1,
+    ModelFormat => 1,
+    OfferStatus => 1,
+    OrganizationRole => 1,
+    PricingMode => 1,
+    ProofSystem => 1,
+    Role => 1,
+    StorageClass => 1,
+);
+
+// 嵌入其他账户布局的结构体按自身LEN计
+impl_packed_size!(
+    SemVer => SemVer::LEN,
+    LicenseTier => LicenseTier::LEN,
+    SubscriptionTier => SubscriptionTier::LEN,
+    VolumeDiscount => VolumeDiscount::LEN,
+    Category => Category::LEN,
+    OrganizationMember => OrganizationMember::LEN,
+    Milestone => Milestone::LEN,
+    SaleRecord => SaleRecord::LEN,
+    DisputeEvidence => DisputeEvidence::LEN,
+);
+
+// 每个账户数据开头的8字节类型标识，由类型名哈希得到。加载时校验标识，
+// 防止把一种账户（例如出价的托管账户）当作另一种长度相同的账户传入
+pub const ACCOUNT_DISCRIMINATOR_LEN: usize = 8;
+
+pub trait AccountDiscriminator {
+    const DISCRIMINATOR: [u8; ACCOUNT_DISCRIMINATOR_LEN];
+}
+
+// 对"account:<类型名>"做64位FNV-1a哈希，编译期求值
+const fn account_discriminator(type_name: &str) -> [u8; ACCOUNT_DISCRIMINATOR_LEN] {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = FNV_OFFSET_BASIS;
+    let prefix = b"account:";
+    let mut i = 0;
+    while i < prefix.len() {
+        hash = (hash ^ prefix[i] as u64).wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+    let name = type_name.as_bytes();
+    let mut i = 0;
+    while i < name.len() {
+        hash = (hash ^ name[i] as u64).wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+    hash.to_le_bytes()
+}
+
+macro_rules! impl_account_discriminator {
+    ($($ty:ident),+ $(,)?) => {
+        $(impl AccountDiscriminator for $ty {
+            const DISCRIMINATOR: [u8; ACCOUNT_DISCRIMINATOR_LEN] = account_discriminator(stringify!($ty));
+        })+
+
+        // 类型标识两两不同且都不为全零，全零保留给尚未初始化的账户
+        const _: () = {
+            let discriminators = [$($ty::DISCRIMINATOR),+];
+            let mut i = 0;
+            while i < discriminators.len() {
+                assert!(u64::from_le_bytes(discriminators[i]) != 0);
+                let mut j = i + 1;
+                while j < discriminators.len() {
+                    assert!(u64::from_le_bytes(discriminators[i]) != u64::from_le_bytes(discriminators[j]));
+                    j += 1;
+                }
+                i += 1;
+            }
+        };
+    };
+}
+
+impl_account_discriminator!(
+    AIModel, PriceFeed, PurchaseReceipt, SellerStats, PurchaseThrottle, Charity,
+    BuyerStats, BuyerReceiptsPage, ModelVersion, ChangelogEntry, Relationship, CategoryRegistry,
+    FeaturedList, ProvenanceVerifier, ProvenanceAttestation, InferenceCommitment, InferenceRequest, Worker,
+    RentalStream, Organization, Commission, SubscriptionPlan, Subscription, RoleAssignment,
+    UsageCounter, CreditAccount, AirdropCampaign, OrderNonces, OrderFill, LocalizedMetadata,
+    Review, ProceedsVault, SalesLedgerPage, Dispute, MarketplaceConfig, RecoveryRequest,
+    Treasury, SellerBond, Listing, Offer, Escrow,
+);
+
+// 读取账户数据开头的类型标识，供按类型分别处理多种账户的指令使用
+fn read_discriminator(data: &[u8]) -> Result<[u8; ACCOUNT_DISCRIMINATOR_LEN], ProgramError> {
+    data.get(..ACCOUNT_DISCRIMINATOR_LEN)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+// 校验账户数据开头的类型标识。新分配的账户数据全为0，标识和初始化标记都为0时按未初始化账户解码，
+// 由调用方的is_initialized检查处理
+fn check_discriminator<T: AccountDiscriminator>(input: &[u8]) -> ProgramResult {
+    let header = input.get(..ACCOUNT_DISCRIMINATOR_LEN + 1).ok_or(ProgramError::InvalidAccountData)?;
+    let (discriminator, is_initialized) = header.split_at(ACCOUNT_DISCRIMINATOR_LEN);
+    if discriminator == T::DISCRIMINATOR {
+        return Ok(());
+    }
+    if discriminator.iter().all(|b| *b == 0) && is_initialized[0] == 0 {
+        return Ok(());
+    }
+    msg!("Account discriminator does not match the expected account type");
+    Err(ProgramError::InvalidAccountData)
+}
+
+// 账户由create_account在CPI中创建，单次CPI最多分配MAX_PERMITTED_DATA_INCREASE字节
+const _: () = {
+    let lens = [
+        AIModel::LEN, PriceFeed::LEN, PurchaseReceipt::LEN, SellerStats::LEN, PurchaseThrottle::LEN,
+        Charity::LEN, BuyerStats::LEN, BuyerReceiptsPage::LEN, ModelVersion::LEN, ChangelogEntry::LEN,
+        Relationship::LEN, CategoryRegistry::LEN, FeaturedList::LEN, ProvenanceVerifier::LEN,
+        ProvenanceAttestation::LEN, InferenceCommitment::LEN, InferenceRequest::LEN, Worker::LEN,
+        RentalStream::LEN, Organization::LEN, Commission::LEN, SubscriptionPlan::LEN, Subscription::LEN,
+        RoleAssignment::LEN, UsageCounter::LEN, CreditAccount::LEN, AirdropCampaign::LEN,
+        OrderNonces::LEN, OrderFill::LEN, LocalizedMetadata::LEN, Review::LEN, ProceedsVault::LEN,
+        SalesLedgerPage::LEN, Dispute::LEN, MarketplaceConfig::LEN, RecoveryRequest::LEN,
+        Treasury::LEN, SellerBond::LEN, Listing::LEN, Offer::LEN, Escrow::LEN,
+    ];
+    let mut i = 0;
+    while i < lens.len() {
+        assert!(lens[i] <= MAX_PERMITTED_DATA_INCREASE);
+        i += 1;
+    }
+};
+
+// 定义语义化版本号，字段顺序即比较顺序
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SemVer {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl SemVer {
+    // 按语义化版本规则判断该版本能否替代required：主版本相同且不低于required，
+    // 0.x版本的次版本号变化也视为不兼容
+    pub fn is_compatible_with(&self, required: &SemVer) -> bool {
+        if self.major != required.major || self < required {
+            return false;
+        }
+        self.major != 0 || self.minor == required.minor
+    }
+}
+
+impl Sealed for SemVer {}
+
+impl Pack for SemVer {
+    const LEN: usize = packed_len!(u32, u32, u32);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let mut offset = 0;
+        output[offset..offset+4].copy_from_slice(&self.major.to_le_bytes());
+        offset += 4;
+        output[offset..offset+4].copy_from_slice(&self.minor.to_le_bytes());
+        offset += 4;
+        output[offset..offset+4].copy_from_slice(&self.patch.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let mut offset = 0;
+        let major = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let minor = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let patch = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        Ok(Self {
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+// 定义许可证档位，每个档位有独立的价格和许可条款URI
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LicenseTier {
+    pub price: u64,
+    pub terms_uri: String,
+    // 计量访问每个计量周期允许的请求数，0表示不限
+    pub requests_per_epoch: u64,
+    // 企业条款允许卖家在违约等情况下吊销许可证，成交时记录到购买凭证
+    pub revocable: bool,
+    // 期限许可证自成交起的有效slot数，到期后需要续费，0表示永久有效
+    pub term_slots: u64,
+}
+
+impl Sealed for LicenseTier {}
+
+impl Pack for LicenseTier {
+    const LEN: usize = packed_len!(u64, [u8; MAX_TERMS_URI_LEN], u64, bool, u64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let mut offset = 0;
+        output[offset..offset+8].copy_from_slice(&self.price.to_le_bytes());
+        offset += 8;
+        pack_fixed_str(&mut output[offset..offset+MAX_TERMS_URI_LEN], &self.terms_uri);
+        offset += MAX_TERMS_URI_LEN;
+        output[offset..offset+8].copy_from_slice(&self.requests_per_epoch.to_le_bytes());
+        offset += 8;
+        output[offset] = self.revocable as u8;
+        offset += 1;
+        output[offset..offset+8].copy_from_slice(&self.term_slots.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let mut offset = 0;
+        let price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let terms_uri = unpack_fixed_str(&input[offset..offset+MAX_TERMS_URI_LEN])?;
+        offset += MAX_TERMS_URI_LEN;
+        let requests_per_epoch = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let revocable = input[offset] != 0;
+        offset += 1;
+        let term_slots = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            price,
+            terms_uri,
+            requests_per_epoch,
+            revocable,
+            term_slots,
+        })
+    }
+}
+
+// 定义订阅档位：每个周期的价格和授予的访问范围（ACCESS_SCOPE_*位图）
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SubscriptionTier {
+    pub price: u64,
+    pub access_scopes: u32,
+}
+
+impl Sealed for SubscriptionTier {}
+
+impl Pack for SubscriptionTier {
+    const LEN: usize = packed_len!(u64, u32);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[0..8].copy_from_slice(&self.price.to_le_bytes());
+        output[8..12].copy_from_slice(&self.access_scopes.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let price = u64::from_le_bytes(input[0..8].try_into().unwrap());
+        let access_scopes = u32::from_le_bytes(input[8..12].try_into().unwrap());
+        Ok(Self {
+            price,
+            access_scopes,
+        })
+    }
+}
+
+// 定义批量购买折扣，购买席位数达到min_seats时享受discount_bps的折扣
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VolumeDiscount {
+    pub min_seats: u32,
+    pub discount_bps: u16,
+}
+
+impl Sealed for VolumeDiscount {}
+
+impl Pack for VolumeDiscount {
+    const LEN: usize = packed_len!(u32, u16);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[0..4].copy_from_slice(&self.min_seats.to_le_bytes());
+        output[4..6].copy_from_slice(&self.discount_bps.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let min_seats = u32::from_le_bytes(input[0..4].try_into().unwrap());
+        let discount_bps = u16::from_le_bytes(input[4..6].try_into().unwrap());
+        Ok(Self {
+            min_seats,
+            discount_bps,
+        })
+    }
+}
+
+// 将字符串写入定长字段，剩余部分以0填充
+fn pack_fixed_str(output: &mut [u8], val: &str) {
+    output.fill(0);
+    output[..val.len()].copy_from_slice(val.as_bytes());
+}
+
+// 从定长字段读取字符串，去掉末尾的0填充
+fn unpack_fixed_str(input: &[u8]) -> Result<String, ProgramError> {
+    let end = input.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    String::from_utf8(input[..end].to_vec()).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+// 定义一个结构体来存储人工智能模型数据
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AIModel {
+    pub is_initialized: bool,
+    pub name: String,
+    pub description: String,
+    pub owner: Pubkey,
+    pub price: u64,
+    pub model_file: Vec<u8>,
+    pub pricing_mode: PricingMode,
+    // 已定义的许可证档位，为空时按price统一售卖
+    pub license_tiers: Vec<LicenseTier>,
+    // 是否按席位售卖许可证，只有按席位售卖时才允许一次购买多个席位
+    pub per_seat: bool,
+    // 卖家定义的批量折扣曲线
+    pub volume_discounts: Vec<VolumeDiscount>,
+    // 为true时销售收入先存入卖家的收益金库，由卖家通过WithdrawProceeds提取
+    pub vault_proceeds: bool,
+    // 累计成交笔数，每笔成交对应一条销售账本记录，因此也决定下一条记录所在的账本分页
+    pub total_sales: u64,
+    // 累计成交金额
+    pub total_volume: u64,
+    // 最近一次成交所在的slot
+    pub last_sold_slot: u64,
+    // 创建和最近一次由卖家修改的unix时间戳，来自Clock系统变量；成交不会刷新updated_at
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 关联的NFT mint，元数据从其Token Metadata账户读取；全零表示未关联
+    pub metadata_mint: Pubkey,
+    // 链下产物的存储位置、引用和内容哈希
+    pub storage_class: StorageClass,
+    pub artifact_uri: String,
+    pub artifact_hash: [u8; 32],
+    // 验证人确认产物存在且与artifact_hash一致的时间戳，0表示尚未确认
+    pub artifact_attested_at: i64,
+    // 产物的字节大小，供买家和网关在下载前评估带宽
+    pub artifact_size_bytes: u64,
+    // 模型文件的框架/格式，位于账户数据的MODEL_FORMAT_OFFSET处
+    pub model_format: ModelFormat,
+    // 模型的使用许可：SPDX许可证表达式或自定义条款URI，成交时写入购买凭证
+    pub license: String,
+    // 已发布的版本数量，也是下一个版本PDA的version_index
+    pub version_count: u32,
+    // 最近发布的版本号，新版本必须严格大于该版本
+    pub latest_version: SemVer,
+    // 最新一条更新日志，沿ChangelogEntry::previous可遍历完整的发布历史；全零表示没有更新日志
+    pub latest_changelog: Pubkey,
+    // 所有者已弃用该模型，购买时买家必须显式确认
+    pub deprecated: bool,
+    // 替代该模型的后继模型，全零表示没有指定
+    pub successor: Pubkey,
+    // 当前收藏该模型的钱包数量
+    pub favorite_count: u64,
+    // 分类注册表中的分类编号，0表示未分类
+    pub category_id: u16,
+    // 链下元数据JSON（图片、完整介绍、示例输出等）的URI及其原始字节的SHA-256
+    pub metadata_uri: String,
+    pub metadata_hash: [u8; 32],
+    // 可以修改元数据、产物、版本和分类的密钥，与收款的owner分离；全零表示由owner兼任
+    pub update_authority: Pubkey,
+    // 列出的创作者及其是否已签名确认，买家据此区分官方上架和冒名上架
+    pub creator: Pubkey,
+    pub creator_verified: bool,
+    // 为true时需要审核预言机扫描产物（恶意代码、侵权检查）并通过ApproveListing后才能购买
+    pub approval_required: bool,
+    // 预言机提交的扫描报告哈希和通过时间，0表示尚未通过；修改产物后失效
+    pub approval_hash: [u8; 32],
+    pub approved_at: i64,
+    // owner已通过Ed25519签名确认artifact_hash，把卖家钱包与所售产物绑定；修改产物时未重新签名则清除
+    pub artifact_signed: bool,
+    // 流式租用的每slot费率（lamports），0表示不提供流式租用
+    pub rental_rate_per_slot: u64,
+    // 许可证的发行上限，0表示不限；已发行的版次数，即最近一张购买凭证的版次号
+    pub max_supply: u32,
+    pub editions_minted: u32,
+    // 开放版次的截止slot：不限量发行，到达该slot后不能再购买，0表示不限时
+    pub open_edition_end_slot: u64,
+    // 截止后已定格发行数量，此后editions_minted即为最终版次数
+    pub editions_finalized: bool,
+    // 持有前代模型（successor指向本模型）许可证的买家销毁旧凭证升级时享受的折扣
+    pub upgrade_discount_bps: u16,
+    // 许可证能否转让，以及带版税转让时卖家从转让价格中抽取的版税
+    pub license_transferability: LicenseTransferability,
+    pub resale_royalty_bps: u16,
+    // 按量计费时每个计量请求的价格（lamports），从买家的预付额度中结算，0表示不按量计费
+    pub usage_unit_price: u64,
+    // 预售白名单的Merkle根，叶子为merkle::leaf(&[模型地址, 钱包, 限购席位数的小端字节])；全零表示没有预售
+    pub presale_merkle_root: [u8; 32],
+    // 预售期间档位0每个席位的价格，其他档位按标价比例折算
+    pub presale_price: u64,
+    // 公开发售开始的slot，此前只能通过白名单预售购买，0表示不限制
+    pub public_sale_slot: u64,
+    // 按价格源标价：每个席位quote_price个计价最小单位，全零的price_feed表示按lamports标价
+    pub price_feed: Pubkey,
+    pub quote_price: u64,
+    // 价格源降级的判定：超过max_staleness_slots未更新，或置信区间超过价格的max_confidence_bps（0表示不检查）
+    pub max_staleness_slots: u64,
+    pub max_confidence_bps: u16,
+    // 价格源降级时每个席位的备用lamports价格，0表示降级时拒绝成交
+    pub fallback_price: u64,
+    // 限量发售（drop）的防机器人节流：同一钱包两次购买至少间隔drop_cooldown_slots个slot，
+    // 同一slot内最多成交drop_max_per_slot笔；两者都为0表示不是drop
+    pub drop_cooldown_slots: u64,
+    pub drop_max_per_slot: u32,
+    // last_sold_slot所在slot内已成交的drop笔数
+    pub drop_slot_purchases: u32,
+    // 要求买家出示该网关网络签发的有效验证通行证（Civic网关令牌）才能购买，全零表示不要求
+    pub gatekeeper_network: Pubkey,
+    // 卖家为模型指定的慈善机构PDA和从卖家收入中捐出的成交额比例（基点），全零表示不捐赠
+    pub charity: Pubkey,
+    pub charity_bps: u16,
+}
+
+// model_format在AIModel账户数据中的字节偏移，可用于getProgramAccounts的memcmp筛选
+pub const MODEL_FORMAT_OFFSET: usize = packed_len!(
+    [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, [u8; MAX_MODEL_NAME_LEN], [u8; MAX_MODEL_DESCRIPTION_LEN], Pubkey, u64,
+    [u8; MODEL_FILE_LEN], PricingMode, u8, [LicenseTier; MAX_LICENSE_TIERS], bool, u8,
+    [VolumeDiscount; MAX_VOLUME_DISCOUNTS], bool, u64, u64, u64, i64, i64, Pubkey, StorageClass,
+    [u8; MAX_ARTIFACT_URI_LEN], [u8; 32], i64, u64
+);
+
+// 实现IsInitialized trait来检查AIModel是否已初始化
+impl IsInitialized for AIModel {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for AIModel {}
+
+// 实现Pack trait来序列化和反序列化AIModel
+impl Pack for AIModel {
+    const LEN: usize = MODEL_FORMAT_OFFSET + packed_len!(
+        ModelFormat, [u8; MAX_LICENSE_LEN], u32, SemVer, Pubkey, bool, Pubkey, u64, u16,
+        [u8; MAX_METADATA_URI_LEN], [u8; 32], Pubkey, Pubkey, bool, bool, [u8; 32], i64, bool, u64,
+        u32, u32, u64, bool, u16, LicenseTransferability, u16, u64, [u8; 32], u64, u64, Pubkey,
+        u64, u64, u16, u64, u64, u32, u32, Pubkey, Pubkey, u16
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        pack_fixed_str(&mut output[offset..offset+MAX_MODEL_NAME_LEN], &self.name);
+        offset += MAX_MODEL_NAME_LEN;
+        pack_fixed_str(&mut output[offset..offset+MAX_MODEL_DESCRIPTION_LEN], &self.description);
+        offset += MAX_MODEL_DESCRIPTION_LEN;
+        output[offset..offset+32].copy_from_slice(self.owner.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.price.to_le_bytes());
+        offset += 8;
+        // 模型文件不足MODEL_FILE_LEN时补0，创建模型时留空、由处理程序直接写入账户
+        let model_file = &mut output[offset..offset+MODEL_FILE_LEN];
+        model_file.fill(0);
+        model_file[..self.model_file.len()].copy_from_slice(&self.model_file);
+        offset += MODEL_FILE_LEN;
+        output[offset] = self.pricing_mode as u8;
+        offset += 1;
+        output[offset] = self.license_tiers.len() as u8;
+        offset += 1;
+        for i in 0..MAX_LICENSE_TIERS {
+            let slot = &mut output[offset..offset+LicenseTier::LEN];
+            match self.license_tiers.get(i) {
+                Some(tier) => tier.pack_into_slice(slot),
+                None => slot.fill(0),
+            }
+            offset += LicenseTier::LEN;
+        }
+        output[offset] = self.per_seat as u8;
+        offset += 1;
+        output[offset] = self.volume_discounts.len() as u8;
+        offset += 1;
+        for i in 0..MAX_VOLUME_DISCOUNTS {
+            let slot = &mut output[offset..offset+VolumeDiscount::LEN];
+            match self.volume_discounts.get(i) {
+                Some(discount) => discount.pack_into_slice(slot),
+                None => slot.fill(0),
+            }
+            offset += VolumeDiscount::LEN;
+        }
+        output[offset] = self.vault_proceeds as u8;
+        offset += 1;
+        output[offset..offset+8].copy_from_slice(&self.total_sales.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_volume.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.last_sold_slot.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(self.metadata_mint.as_ref());
+        offset += 32;
+        output[offset] = self.storage_class as u8;
+        offset += 1;
+        pack_fixed_str(&mut output[offset..offset+MAX_ARTIFACT_URI_LEN], &self.artifact_uri);
+        offset += MAX_ARTIFACT_URI_LEN;
+        output[offset..offset+32].copy_from_slice(&self.artifact_hash);
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.artifact_attested_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.artifact_size_bytes.to_le_bytes());
+        offset += 8;
+        output[offset] = self.model_format as u8;
+        offset += 1;
+        pack_fixed_str(&mut output[offset..offset+MAX_LICENSE_LEN], &self.license);
+        offset += MAX_LICENSE_LEN;
+        output[offset..offset+4].copy_from_slice(&self.version_count.to_le_bytes());
+        offset += 4;
+        self.latest_version.pack_into_slice(&mut output[offset..offset+SemVer::LEN]);
+        offset += SemVer::LEN;
+        output[offset..offset+32].copy_from_slice(self.latest_changelog.as_ref());
+        offset += 32;
+        output[offset] = self.deprecated as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.successor.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.favorite_count.to_le_bytes());
+        offset += 8;
+        output[offset..offset+2].copy_from_slice(&self.category_id.to_le_bytes());
+        offset += 2;
+        pack_fixed_str(&mut output[offset..offset+MAX_METADATA_URI_LEN], &self.metadata_uri);
+        offset += MAX_METADATA_URI_LEN;
+        output[offset..offset+32].copy_from_slice(&self.metadata_hash);
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.update_authority.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.creator.as_ref());
+        offset += 32;
+        output[offset] = self.creator_verified as u8;
+        offset += 1;
+        output[offset] = self.approval_required as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(&self.approval_hash);
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.approved_at.to_le_bytes());
+        offset += 8;
+        output[offset] = self.artifact_signed as u8;
+        offset += 1;
+        output[offset..offset+8].copy_from_slice(&self.rental_rate_per_slot.to_le_bytes());
+        offset += 8;
+        output[offset..offset+4].copy_from_slice(&self.max_supply.to_le_bytes());
+        offset += 4;
+        output[offset..offset+4].copy_from_slice(&self.editions_minted.to_le_bytes());
+        offset += 4;
+        output[offset..offset+8].copy_from_slice(&self.open_edition_end_slot.to_le_bytes());
+        offset += 8;
+        output[offset] = self.editions_finalized as u8;
+        offset += 1;
+        output[offset..offset+2].copy_from_slice(&self.upgrade_discount_bps.to_le_bytes());
+        offset += 2;
+        output[offset] = self.license_transferability as u8;
+        offset += 1;
+        output[offset..offset+2].copy_from_slice(&self.resale_royalty_bps.to_le_bytes());
+        offset += 2;
+        output[offset..offset+8].copy_from_slice(&self.usage_unit_price.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(&self.presale_merkle_root);
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.presale_price.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.public_sale_slot.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(self.price_feed.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.quote_price.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.max_staleness_slots.to_le_bytes());
+        offset += 8;
+        output[offset..offset+2].copy_from_slice(&self.max_confidence_bps.to_le_bytes());
+        offset += 2;
+        output[offset..offset+8].copy_from_slice(&self.fallback_price.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.drop_cooldown_slots.to_le_bytes());
+        offset += 8;
+        output[offset..offset+4].copy_from_slice(&self.drop_max_per_slot.to_le_bytes());
+        offset += 4;
+        output[offset..offset+4].copy_from_slice(&self.drop_slot_purchases.to_le_bytes());
+        offset += 4;
+        output[offset..offset+32].copy_from_slice(self.gatekeeper_network.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.charity.as_ref());
+        offset += 32;
+        output[offset..offset+2].copy_from_slice(&self.charity_bps.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = match input.get(offset) {
+            Some(val) => *val != 0,
+            None => return Err(ProgramError::InvalidAccountData),
+        };
+        offset += 1;
+        let name = unpack_fixed_str(&input[offset..offset+MAX_MODEL_NAME_LEN])?;
+        offset += MAX_MODEL_NAME_LEN;
+        let description = unpack_fixed_str(&input[offset..offset+MAX_MODEL_DESCRIPTION_LEN])?;
+        offset += MAX_MODEL_DESCRIPTION_LEN;
+        let owner = match input[offset..offset+32].try_into() {
+            Ok(val) => Pubkey::new_from_array(val),
+            Err(_) => return Err(ProgramError::InvalidAccountData),
+        };
+        offset += 32;
+        let price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let model_file = input[offset..offset+MODEL_FILE_LEN].to_vec();
+        offset += MODEL_FILE_LEN;
+        let pricing_mode = PricingMode::from_u8(input[offset])?;
+        offset += 1;
+        let tier_count = input[offset] as usize;
+        if tier_count > MAX_LICENSE_TIERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset += 1;
+        let mut license_tiers = Vec::with_capacity(tier_count);
+        for _ in 0..tier_count {
+            license_tiers.push(LicenseTier::unpack_from_slice(&input[offset..offset+LicenseTier::LEN])?);
+            offset += LicenseTier::LEN;
+        }
+        offset += LicenseTier::LEN * (MAX_LICENSE_TIERS - tier_count);
+        let per_seat = input[offset] != 0;
+        offset += 1;
+        let discount_count = input[offset] as usize;
+        if discount_count > MAX_VOLUME_DISCOUNTS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset += 1;
+        let mut volume_discounts = Vec::with_capacity(discount_count);
+        for _ in 0..discount_count {
+            volume_discounts.push(VolumeDiscount::unpack_from_slice(&input[offset..offset+VolumeDiscount::LEN])?);
+            offset += VolumeDiscount::LEN;
+        }
+        offset += VolumeDiscount::LEN * (MAX_VOLUME_DISCOUNTS - discount_count);
+        let vault_proceeds = input[offset] != 0;
+        offset += 1;
+        let total_sales = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_volume = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let last_sold_slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let metadata_mint = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let storage_class = StorageClass::from_u8(input[offset])?;
+        offset += 1;
+        let artifact_uri = unpack_fixed_str(&input[offset..offset+MAX_ARTIFACT_URI_LEN])?;
+        offset += MAX_ARTIFACT_URI_LEN;
+        let artifact_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let artifact_attested_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let artifact_size_bytes = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let model_format = ModelFormat::from_u8(input[offset])?;
+        offset += 1;
+        let license = unpack_fixed_str(&input[offset..offset+MAX_LICENSE_LEN])?;
+        offset += MAX_LICENSE_LEN;
+        let version_count = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let latest_version = SemVer::unpack_from_slice(&input[offset..offset+SemVer::LEN])?;
+        offset += SemVer::LEN;
+        let latest_changelog = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let deprecated = input[offset] != 0;
+        offset += 1;
+        let successor = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let favorite_count = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let category_id = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        offset += 2;
+        let metadata_uri = unpack_fixed_str(&input[offset..offset+MAX_METADATA_URI_LEN])?;
+        offset += MAX_METADATA_URI_LEN;
+        let metadata_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let update_authority = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let creator = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let creator_verified = input[offset] != 0;
+        offset += 1;
+        let approval_required = input[offset] != 0;
+        offset += 1;
+        let approval_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let approved_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let artifact_signed = input[offset] != 0;
+        offset += 1;
+        let rental_rate_per_slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let max_supply = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let editions_minted = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let open_edition_end_slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let editions_finalized = input[offset] != 0;
+        offset += 1;
+        let upgrade_discount_bps = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        offset += 2;
+        let license_transferability = LicenseTransferability::from_u8(input[offset])?;
+        offset += 1;
+        let resale_royalty_bps = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        offset += 2;
+        let usage_unit_price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let presale_merkle_root = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let presale_price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let public_sale_slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let price_feed = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let quote_price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let max_staleness_slots = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let max_confidence_bps = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        offset += 2;
+        let fallback_price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let drop_cooldown_slots = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let drop_max_per_slot = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let drop_slot_purchases = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let gatekeeper_network = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let charity = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let charity_bps = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            name,
+            description,
+            owner,
+            price,
+            model_file,
+            pricing_mode,
+            license_tiers,
+            per_seat,
+            volume_discounts,
+            vault_proceeds,
+            total_sales,
+            total_volume,
+            last_sold_slot,
+            created_at,
+            updated_at,
+            metadata_mint,
+            storage_class,
+            artifact_uri,
+            artifact_hash,
+            artifact_attested_at,
+            artifact_size_bytes,
+            model_format,
+            license,
+            version_count,
+            latest_version,
+            latest_changelog,
+            deprecated,
+            successor,
+            favorite_count,
+            category_id,
+            metadata_uri,
+            metadata_hash,
+            update_authority,
+            creator,
+            creator_verified,
+            approval_required,
+            approval_hash,
+            approved_at,
+            artifact_signed,
+            rental_rate_per_slot,
+            max_supply,
+            editions_minted,
+            open_edition_end_slot,
+            editions_finalized,
+            upgrade_discount_bps,
+            license_transferability,
+            resale_royalty_bps,
+            usage_unit_price,
+            presale_merkle_root,
+            presale_price,
+            public_sale_slot,
+            price_feed,
+            quote_price,
+            max_staleness_slots,
+            max_confidence_bps,
+            fallback_price,
+            drop_cooldown_slots,
+            drop_max_per_slot,
+            drop_slot_purchases,
+            gatekeeper_network,
+            charity,
+            charity_bps,
+        })
+    }
+}
+
+// 模型的上架状态，由弃用标记和审核字段推出，索引器据此过滤可展示的上架；挂单自身的生命周期见state::ListingStatus
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModelStatus {
+    Active,
+    // 开启了审核但审核预言机尚未通过
+    PendingApproval,
+    Deprecated,
+}
+
+// AIModel账户数据的借用视图，只按固定偏移读取所有者、标价等定长字段，
+// 不复制名称、描述和1KB的模型文件，供只做权限或存在性检查的处理程序使用，
+// 省下完整解码的计算单元和BPF堆空间
+pub struct AIModelView<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> AIModelView<'a> {
+    pub const OWNER_OFFSET: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, [u8; MAX_MODEL_NAME_LEN], [u8; MAX_MODEL_DESCRIPTION_LEN]
+    );
+    pub const PRICE_OFFSET: usize = Self::OWNER_OFFSET + packed_len!(Pubkey);
+    pub const MODEL_FILE_OFFSET: usize = Self::PRICE_OFFSET + packed_len!(u64);
+    pub const DEPRECATED_OFFSET: usize =
+        MODEL_FORMAT_OFFSET + packed_len!(ModelFormat, [u8; MAX_LICENSE_LEN], u32, SemVer, Pubkey);
+    pub const APPROVAL_REQUIRED_OFFSET: usize = Self::DEPRECATED_OFFSET
+        + packed_len!(bool, Pubkey, u64, u16, [u8; MAX_METADATA_URI_LEN], [u8; 32], Pubkey, Pubkey, bool);
+    pub const APPROVED_AT_OFFSET: usize = Self::APPROVAL_REQUIRED_OFFSET + packed_len!(bool, [u8; 32]);
+    pub const CATEGORY_ID_OFFSET: usize = Self::DEPRECATED_OFFSET + packed_len!(bool, Pubkey, u64);
+
+    // 与AIModel::unpack相同，要求数据长度等于AIModel::LEN、类型标识匹配且账户已初始化
+    pub fn new(data: &'a [u8]) -> Result<Self, ProgramError> {
+        if data.len() != AIModel::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_discriminator::<AIModel>(data)?;
+        if data[ACCOUNT_DISCRIMINATOR_LEN] == 0 {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(Self { data })
+    }
+
+    pub fn owner(&self) -> Pubkey {
+        Pubkey::new_from_array(self.data[Self::OWNER_OFFSET..Self::OWNER_OFFSET + 32].try_into().unwrap())
+    }
+
+    pub fn price(&self) -> u64 {
+        u64::from_le_bytes(self.data[Self::PRICE_OFFSET..Self::PRICE_OFFSET + 8].try_into().unwrap())
+    }
+
+    pub fn model_format(&self) -> Result<ModelFormat, ProgramError> {
+        ModelFormat::from_u8(self.data[MODEL_FORMAT_OFFSET])
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.data[Self::DEPRECATED_OFFSET] != 0
+    }
+
+    pub fn status(&self) -> ModelStatus {
+        let approved_at = i64::from_le_bytes(
+            self.data[Self::APPROVED_AT_OFFSET..Self::APPROVED_AT_OFFSET + 8].try_into().unwrap(),
+        );
+        if self.data[Self::DEPRECATED_OFFSET] != 0 {
+            ModelStatus::Deprecated
+        } else if self.data[Self::APPROVAL_REQUIRED_OFFSET] != 0 && approved_at == 0 {
+            ModelStatus::PendingApproval
+        } else {
+            ModelStatus::Active
+        }
+    }
+}
+
+// 供索引器批量处理快照中的AIModel账户：只读取单个字段，不解码整个账户
+impl AIModel {
+    pub fn owner_from_slice(data: &[u8]) -> Result<Pubkey, ProgramError> {
+        Ok(AIModelView::new(data)?.owner())
+    }
+
+    pub fn price_from_slice(data: &[u8]) -> Result<u64, ProgramError> {
+        Ok(AIModelView::new(data)?.price())
+    }
+
+    pub fn status_from_slice(data: &[u8]) -> Result<ModelStatus, ProgramError> {
+        Ok(AIModelView::new(data)?.status())
+    }
+}
+
+// 视图和memcmp筛选使用的偏移必须落在账户数据内且互不重叠，布局改动破坏这一点时无法编译
+const _: () = {
+    assert!(AIModelView::MODEL_FILE_OFFSET + MODEL_FILE_LEN <= MODEL_FORMAT_OFFSET);
+    assert!(MODEL_FORMAT_OFFSET < AIModel::LEN);
+    assert!(AIModelView::APPROVED_AT_OFFSET + 8 <= AIModel::LEN);
+    assert!(AIModelView::CATEGORY_ID_OFFSET + 2 <= AIModelView::APPROVAL_REQUIRED_OFFSET);
+};
+
+impl AIModel {
+    pub fn is_drop(&self) -> bool {
+        self.drop_cooldown_slots != 0 || self.drop_max_per_slot != 0
+    }
+
+    // 记录slot内的一笔drop成交，超过每slot上限时失败；须在record_sale更新last_sold_slot之前调用
+    pub fn record_drop_purchase(&mut self, slot: u64) -> ProgramResult {
+        let purchases = if self.last_sold_slot == slot { self.drop_slot_purchases.saturating_add(1) } else { 1 };
+        if self.drop_max_per_slot != 0 && purchases > self.drop_max_per_slot {
+            msg!("Drop purchase cap of {} per slot reached", self.drop_max_per_slot);
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.drop_slot_purchases = purchases;
+        Ok(())
+    }
+
+    pub fn is_oracle_priced(&self) -> bool {
+        self.price_feed != Pubkey::default()
+    }
+
+    // 按价格源计算指定档位quantity个席位的lamports价格，每席位价格向上取整；quote_price和备用价格按档位0计，
+    // 其他档位按标价比例折算并套用批量折扣；价格源降级时使用备用价格，
+    // 没有备用价格时失败，避免按过期或不可靠的价格成交
+    pub fn oracle_total(&self, feed: &PriceFeed, tier_index: u8, quantity: u32, slot: u64) -> Result<u64, ProgramError> {
+        let per_seat = if feed.is_degraded(self.max_staleness_slots, self.max_confidence_bps, slot) {
+            if self.fallback_price == 0 {
+                msg!("Price feed degraded and no fallback price is set");
+                return Err(ProgramError::InvalidArgument);
+            }
+            self.fallback_price as u128
+        } else {
+            (self.quote_price as u128 * feed.price as u128).div_ceil(10u128.pow(ORACLE_QUOTE_DECIMALS))
+        };
+        self.scaled_list_price(per_seat, tier_index, quantity)
+    }
+
+    // 公开发售开始前不能按标价购买
+    pub fn require_public_sale(&self, slot: u64) -> ProgramResult {
+        if self.public_sale_slot != 0 && slot < self.public_sale_slot {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    // 预售期间白名单中的钱包购买指定档位quantity个席位的价格，allocation为白名单叶子中该钱包的限购席位数；
+    // 预售未开放、已结束或钱包不在白名单中时失败，累计限购由购买节流PDA记录
+    #[allow(clippy::too_many_arguments)]
+    pub fn presale_total(
+        &self,
+        model: &Pubkey,
+        wallet: &Pubkey,
+        allocation: u32,
+        proof: &[[u8; 32]],
+        tier_index: u8,
+        quantity: u32,
+        slot: u64,
+    ) -> Result<u64, ProgramError> {
+        if self.presale_merkle_root == [0u8; 32] || slot >= self.public_sale_slot {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let leaf = merkle::leaf(&[model.as_ref(), wallet.as_ref(), &allocation.to_le_bytes()]);
+        if !merkle::verify(proof, &self.presale_merkle_root, &leaf) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.scaled_list_price(self.presale_price as u128, tier_index, quantity)
+    }
+
+    // 当前生效的更新权限：未单独设置时由owner兼任
+    pub fn effective_update_authority(&self) -> Pubkey {
+        if self.update_authority == Pubkey::default() {
+            self.owner
+        } else {
+            self.update_authority
+        }
+    }
+
+    pub fn is_update_authority(&self, key: &Pubkey) -> bool {
+        self.effective_update_authority() == *key
+    }
+
+    // 修改列出的创作者，只有创作者本人签名修改时才保持已验证
+    pub fn set_creator(&mut self, creator: Pubkey, signer: &Pubkey) {
+        self.creator_verified = creator == *signer;
+        self.creator = creator;
+    }
+
+    // 成交额amount_paid中按模型慈善分成比例捐出的金额，向下取整
+    pub fn charity_donation(&self, amount_paid: u64) -> u64 {
+        fee_at_bps(amount_paid, self.charity_bps as u64)
+    }
+
+    // 检查模型当前是否可以购买：需要验证的链下产物必须已由验证人确认，开启审核的上架必须已通过审核
+    pub fn require_purchasable(&self) -> ProgramResult {
+        if self.storage_class.requires_attestation() && self.artifact_attested_at == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        // 开启审核的上架必须已由审核预言机通过
+        if self.approval_required && self.approved_at == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    // 已弃用的模型只有在买家显式确认弃用后才能购买
+    pub fn require_deprecation_acknowledged(&self, acknowledge_deprecation: bool) -> ProgramResult {
+        if self.deprecated && !acknowledge_deprecation {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    // 获取指定许可证档位的标价；未定义档位时只接受档位0并使用price
+    pub fn tier_price(&self, tier_index: u8) -> Result<u64, ProgramError> {
+        if self.license_tiers.is_empty() {
+            if tier_index != 0 {
+                return Err(ProgramError::InvalidArgument);
+            }
+            return Ok(self.price);
+        }
+        self.license_tiers
+            .get(tier_index as usize)
+            .map(|tier| tier.price)
+            .ok_or(ProgramError::InvalidArgument)
+    }
+
+    // 许可证档位每个计量周期允许的请求数，0表示不限；未定义档位的模型不限
+    pub fn tier_request_limit(&self, tier_index: u8) -> u64 {
+        self.license_tiers
+            .get(tier_index as usize)
+            .map(|tier| tier.requests_per_epoch)
+            .unwrap_or(0)
+    }
+
+    // 许可证档位的有效期slot数，0表示永久有效；未定义档位的模型永久有效
+    pub fn tier_term_slots(&self, tier_index: u8) -> u64 {
+        self.license_tiers
+            .get(tier_index as usize)
+            .map(|tier| tier.term_slots)
+            .unwrap_or(0)
+    }
+
+    // 下一条成交记录应写入的账本分页序号
+    pub fn current_ledger_page(&self) -> u32 {
+        (self.total_sales / SALES_LEDGER_PAGE_CAPACITY as u64) as u32
+    }
+
+    // 成交后更新模型上的销售统计，供前端按热度排序
+    pub fn record_sale(&mut self, amount: u64, slot: u64) -> ProgramResult {
+        self.total_sales = self.total_sales
+            .checked_add(1)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        self.total_volume = self.total_volume
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        self.last_sold_slot = slot;
+        Ok(())
+    }
+
+    // 为新的购买凭证发行一个版次，返回从1开始的版次号；达到发行上限或开放版次截止后失败
+    pub fn mint_edition(&mut self, slot: u64) -> Result<u32, ProgramError> {
+        if self.max_supply != 0 && self.editions_minted >= self.max_supply {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.open_edition_end_slot != 0 && slot >= self.open_edition_end_slot {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.editions_minted = self.editions_minted
+            .checked_add(1)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(self.editions_minted)
+    }
+
+    // 设置发行上限：设置后只能调低且不能低于已发行数量，也不能取消，保证限量许可证的稀缺性
+    pub fn set_max_supply(&mut self, max_supply: u32) -> ProgramResult {
+        if self.open_edition_end_slot != 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if max_supply == 0 && self.max_supply != 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if max_supply != 0 && (max_supply < self.editions_minted || (self.max_supply != 0 && max_supply > self.max_supply)) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.max_supply = max_supply;
+        Ok(())
+    }
+
+    // 把不限量的模型设为开放版次并设置截止slot：只能设置在slot之后，设置后只能提前不能延后
+    pub fn set_open_edition_end(&mut self, end_slot: u64, slot: u64) -> ProgramResult {
+        if self.max_supply != 0 || end_slot <= slot {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.open_edition_end_slot != 0 && (end_slot > self.open_edition_end_slot || slot >= self.open_edition_end_slot) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.open_edition_end_slot = end_slot;
+        Ok(())
+    }
+
+    // 开放版次截止后定格发行数量，只能执行一次
+    pub fn finalize_editions(&mut self, slot: u64) -> ProgramResult {
+        if self.open_edition_end_slot == 0 || slot < self.open_edition_end_slot || self.editions_finalized {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.editions_finalized = true;
+        Ok(())
+    }
+
+    // 根据购买席位数在折扣曲线上取可享受的最大折扣
+    pub fn discount_bps(&self, quantity: u32) -> u16 {
+        self.volume_discounts
+            .iter()
+            .filter(|discount| quantity >= discount.min_seats)
+            .map(|discount| discount.discount_bps)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // 计算指定档位和席位数的折后总价
+    pub fn list_price(&self, tier_index: u8, quantity: u32) -> Result<u64, ProgramError> {
+        if quantity == 0 || (!self.per_seat && quantity != 1) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let gross = self.tier_price(tier_index)? as u128 * quantity as u128;
+        let discount_bps = self.discount_bps(quantity) as u128;
+        let net = gross * (BPS_DENOMINATOR as u128 - discount_bps) / BPS_DENOMINATOR as u128;
+        u64::try_from(net).map_err(|_| ProgramError::ArithmeticOverflow)
+    }
+
+    // 把档位0的每席位价格base_per_seat折算为指定档位和席位数的折后总价：按该档位相对档位0的标价比例缩放，
+    // 再套用批量折扣；预售价等只对档位0设定的价格都经此换算，档位0标价为0时只能购买档位0
+    pub fn scaled_list_price(&self, base_per_seat: u128, tier_index: u8, quantity: u32) -> Result<u64, ProgramError> {
+        if quantity == 0 || (!self.per_seat && quantity != 1) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let tier_price = self.tier_price(tier_index)? as u128;
+        let per_seat = if tier_index == 0 {
+            base_per_seat
+        } else {
+            let base_tier_price = self.tier_price(0)? as u128;
+            if base_tier_price == 0 {
+                return Err(ProgramError::InvalidArgument);
+            }
+            base_per_seat
+                .checked_mul(tier_price)
+                .ok_or(ProgramError::ArithmeticOverflow)?
+                .div_ceil(base_tier_price)
+        };
+        let gross = per_seat
+            .checked_mul(quantity as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let discount_bps = self.discount_bps(quantity) as u128;
+        let net = gross
+            .checked_mul(BPS_DENOMINATOR as u128 - discount_bps)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            / BPS_DENOMINATOR as u128;
+        u64::try_from(net).map_err(|_| ProgramError::ArithmeticOverflow)
+    }
+
+    // 以price转让许可证时付给卖家的版税；不允许转让时报错
+    pub fn transfer_royalty(&self, price: u64) -> Result<u64, ProgramError> {
+        match self.license_transferability {
+            LicenseTransferability::NonTransferable => Err(ProgramError::InvalidArgument),
+            LicenseTransferability::TransferableWithRoyalty => {
+                let royalty_bps = (self.resale_royalty_bps as u64).min(BPS_DENOMINATOR) as u128;
+                Ok((price as u128 * royalty_bps / BPS_DENOMINATOR as u128) as u64)
+            }
+            LicenseTransferability::FreelyTransferable => Ok(0),
+        }
+    }
+
+    // 销毁前代许可证升级到本模型的价格：折后标价再扣除卖家设置的升级折扣
+    pub fn upgrade_price(&self, tier_index: u8, quantity: u32) -> Result<u64, ProgramError> {
+        let list_price = self.list_price(tier_index, quantity)? as u128;
+        let discount_bps = (self.upgrade_discount_bps as u64).min(BPS_DENOMINATOR) as u128;
+        Ok((list_price * (BPS_DENOMINATOR as u128 - discount_bps) / BPS_DENOMINATOR as u128) as u64)
+    }
+
+    // 根据定价模式计算买家实际支付的金额；随意付模式下折后总价即为底价
+    pub fn sale_price(&self, tier_index: u8, quantity: u32, offered: u64) -> Result<u64, ProgramError> {
+        let list_price = self.list_price(tier_index, quantity)?;
+        match self.pricing_mode {
+            PricingMode::Fixed => Ok(list_price),
+            PricingMode::PayWhatYouWant => {
+                if offered < list_price {
+                    return Err(ProgramError::InvalidArgument);
+                }
+                Ok(offered)
+            }
+        }
+    }
+}
+
+// 定义跨链公告的类型：模型上架，或一份许可证成交
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ListingAnnouncementKind {
+    Listed,
+    Sold,
+}
+
+impl ListingAnnouncementKind {
+    pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+        match val {
+            0 => Ok(ListingAnnouncementKind::Listed),
+            1 => Ok(ListingAnnouncementKind::Sold),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+// 定义通过Wormhole发布的跨链公告载荷，Listed公告中凭证相关字段为零；
+// 载荷以版本号开头，其他链上的解析方应拒绝未知版本
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListingAnnouncement {
+    pub kind: ListingAnnouncementKind,
+    pub model: Pubkey,
+    pub owner: Pubkey,
+    pub artifact_hash: [u8; 32],
+    pub price: u64,
+    pub editions_minted: u32,
+    pub total_sales: u64,
+    pub receipt: Pubkey,
+    pub buyer: Pubkey,
+    pub edition: u32,
+    pub amount_paid: u64,
+}
+
+impl ListingAnnouncement {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 1 + 32 + 32 + 32 + 8 + 4 + 8 + 32 + 32 + 4 + 8;
+
+    pub fn to_payload(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(Self::LEN);
+        buf.push(Self::VERSION);
+        buf.push(self.kind as u8);
+        buf.extend_from_slice(self.model.as_ref());
+        buf.extend_from_slice(self.owner.as_ref());
+        buf.extend_from_slice(&self.artifact_hash);
+        buf.extend_from_slice(&self.price.to_le_bytes());
+        buf.extend_from_slice(&self.editions_minted.to_le_bytes());
+        buf.extend_from_slice(&self.total_sales.to_le_bytes());
+        buf.extend_from_slice(self.receipt.as_ref());
+        buf.extend_from_slice(self.buyer.as_ref());
+        buf.extend_from_slice(&self.edition.to_le_bytes());
+        buf.extend_from_slice(&self.amount_paid.to_le_bytes());
+        buf
+    }
+
+    pub fn from_payload(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() != Self::LEN || input[0] != Self::VERSION {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let kind = ListingAnnouncementKind::from_u8(input[1])?;
+        let (model, rest) = unpack_pubkey(&input[2..])?;
+        let (owner, rest) = unpack_pubkey(rest)?;
+        let (artifact_hash, rest) = unpack_hash(rest)?;
+        let (price, rest) = unpack_u64(rest)?;
+        let (editions_minted, rest) = unpack_u32(rest)?;
+        let (total_sales, rest) = unpack_u64(rest)?;
+        let (receipt, rest) = unpack_pubkey(rest)?;
+        let (buyer, rest) = unpack_pubkey(rest)?;
+        let (edition, rest) = unpack_u32(rest)?;
+        let (amount_paid, _) = unpack_u64(rest)?;
+        Ok(Self {
+            kind,
+            model,
+            owner,
+            artifact_hash,
+            price,
+            editions_minted,
+            total_sales,
+            receipt,
+            buyer,
+            edition,
+            amount_paid,
+        })
+    }
+}
+
+// 定义Oracle角色推送的价格源：每个计价单位（10^ORACLE_QUOTE_DECIMALS个最小单位）对应的lamports及其置信区间
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PriceFeed {
+    pub is_initialized: bool,
+    pub feed_id: u64,
+    pub price: u64,
+    pub confidence: u64,
+    // 最近一次推送所在的slot
+    pub published_slot: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl PriceFeed {
+    // 价格为0、超过max_staleness_slots未更新，或置信区间超过价格的max_confidence_bps时视为降级
+    pub fn is_degraded(&self, max_staleness_slots: u64, max_confidence_bps: u16, slot: u64) -> bool {
+        self.price == 0
+            || slot.saturating_sub(self.published_slot) > max_staleness_slots
+            || (max_confidence_bps != 0
+                && self.confidence as u128 * BPS_DENOMINATOR as u128 > self.price as u128 * max_confidence_bps as u128)
+    }
+}
+
+impl IsInitialized for PriceFeed {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for PriceFeed {}
+
+impl Pack for PriceFeed {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, u64, u64, u64, u64, i64, i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+8].copy_from_slice(&self.feed_id.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.price.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.confidence.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.published_slot.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let feed_id = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let confidence = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let published_slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            feed_id,
+            price,
+            confidence,
+            published_slot,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义卖家统计数据，由购买指令在每次成交时更新，关注和取消关注时更新关注者数量
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SellerStats {
+    pub is_initialized: bool,
+    pub seller: Pubkey,
+    pub total_sales: u64,
+    pub total_revenue: u64,
+    // 随意付模式下买家在底价之上额外支付的金额总和
+    pub total_tips: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 当前关注该卖家的钱包数量
+    pub follower_count: u64,
+    // 卖家模型收到的已验证评价数量和星级总和
+    pub review_count: u64,
+    pub rating_sum: u64,
+    // 已达成和已领取的成就标志位，见Achievement::flag
+    pub achievements: u32,
+    pub claimed_achievements: u32,
+}
+
+impl SellerStats {
+    // 至少有TOP_RATED_MIN_REVIEWS条评价且平均星级不低于4.5
+    pub fn is_top_rated(&self) -> bool {
+        self.review_count >= TOP_RATED_MIN_REVIEWS
+            && self.rating_sum.saturating_mul(10) >= self.review_count.saturating_mul(TOP_RATED_MIN_RATING_X10)
+    }
+
+    // 按当前统计标记新达成的成就，已达成的成就不会因统计变化而撤销
+    pub fn refresh_achievements(&mut self) {
+        if self.total_sales >= 1 {
+            self.achievements |= Achievement::FirstSale.flag();
+        }
+        if self.total_sales >= SALES_ACHIEVEMENT_MIN_SALES {
+            self.achievements |= Achievement::HundredSales.flag();
+        }
+        if self.is_top_rated() {
+            self.achievements |= Achievement::TopRated.flag();
+        }
+    }
+
+    // 记录一条对卖家模型的评价
+    pub fn record_rating(&mut self, rating: u8) {
+        self.review_count = self.review_count.saturating_add(1);
+        self.rating_sum = self.rating_sum.saturating_add(rating as u64);
+        self.refresh_achievements();
+    }
+}
+
+impl IsInitialized for SellerStats {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for SellerStats {}
+
+impl Pack for SellerStats {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u64, u64, u64, i64, i64, u64, u64, u64, u32, u32
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.seller.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.total_sales.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_revenue.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_tips.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.follower_count.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.review_count.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.rating_sum.to_le_bytes());
+        offset += 8;
+        output[offset..offset+4].copy_from_slice(&self.achievements.to_le_bytes());
+        offset += 4;
+        output[offset..offset+4].copy_from_slice(&self.claimed_achievements.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let seller = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let total_sales = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_revenue = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_tips = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let follower_count = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let review_count = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let rating_sum = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let achievements = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let claimed_achievements = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            seller,
+            total_sales,
+            total_revenue,
+            total_tips,
+            created_at,
+            updated_at,
+            follower_count,
+            review_count,
+            rating_sum,
+            achievements,
+            claimed_achievements,
+        })
+    }
+}
+
+// 定义钱包对单个模型的购买节流记录：drop模型的购买冷却，以及预售期间已购买的席位数
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PurchaseThrottle {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub wallet: Pubkey,
+    pub purchases: u32,
+    pub last_purchase_slot: u64,
+    // 预售期间累计购买的席位数，不能超过白名单叶子中的限购席位数
+    pub presale_seats: u32,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl PurchaseThrottle {
+    // 记录slot内的一次购买，距上次购买不足cooldown_slots个slot时失败
+    pub fn record_purchase(&mut self, slot: u64, cooldown_slots: u64) -> ProgramResult {
+        if self.purchases > 0 && slot < self.last_purchase_slot.saturating_add(cooldown_slots) {
+            msg!("Drop purchase cooldown active until slot {}", self.last_purchase_slot.saturating_add(cooldown_slots));
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.purchases = self.purchases.saturating_add(1);
+        self.last_purchase_slot = slot;
+        Ok(())
+    }
+
+    // 记录一笔预售购买的quantity个席位，累计超过allocation时失败
+    pub fn record_presale(&mut self, quantity: u32, allocation: u32) -> ProgramResult {
+        let presale_seats = self.presale_seats
+            .checked_add(quantity)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        if presale_seats > allocation {
+            msg!("Presale allocation of {} seats exceeded", allocation);
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.presale_seats = presale_seats;
+        Ok(())
+    }
+}
+
+impl IsInitialized for PurchaseThrottle {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for PurchaseThrottle {}
+
+impl Pack for PurchaseThrottle {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, u32, u64, u32, i64, i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.wallet.as_ref());
+        offset += 32;
+        output[offset..offset+4].copy_from_slice(&self.purchases.to_le_bytes());
+        offset += 4;
+        output[offset..offset+8].copy_from_slice(&self.last_purchase_slot.to_le_bytes());
+        offset += 8;
+        output[offset..offset+4].copy_from_slice(&self.presale_seats.to_le_bytes());
+        offset += 4;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let wallet = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let purchases = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let last_purchase_slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let presale_seats = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            model,
+            wallet,
+            purchases,
+            last_purchase_slot,
+            presale_seats,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义管理员登记的慈善机构，托管成交时分给它的捐款并累计捐赠统计，收款钱包随时可以提取
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Charity {
+    pub is_initialized: bool,
+    pub wallet: Pubkey,
+    // 停用的机构不再接受新的慈善分成，已托管的捐款仍可提取
+    pub active: bool,
+    // 托管中尚未提取的捐款
+    pub balance: u64,
+    pub total_donations: u64,
+    pub donation_count: u64,
+    pub total_withdrawn: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Charity {
+    // 记入一笔托管的捐款
+    pub fn record_donation(&mut self, amount: u64) -> ProgramResult {
+        self.balance = self.balance
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        self.total_donations = self.total_donations
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        self.donation_count = self.donation_count
+            .checked_add(1)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+impl IsInitialized for Charity {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Charity {}
+
+impl Pack for Charity {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, bool, u64, u64, u64, u64, i64, i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.wallet.as_ref());
+        offset += 32;
+        output[offset] = self.active as u8;
+        offset += 1;
+        output[offset..offset+8].copy_from_slice(&self.balance.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_donations.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.donation_count.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_withdrawn.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let wallet = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let active = input[offset] != 0;
+        offset += 1;
+        let balance = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_donations = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let donation_count = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_withdrawn = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            wallet,
+            active,
+            balance,
+            total_donations,
+            donation_count,
+            total_withdrawn,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义买家统计，记录买家的累计购买和已验证评价，用于徽章等声誉功能
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuyerStats {
+    pub is_initialized: bool,
+    pub buyer: Pubkey,
+    pub total_purchases: u64,
+    pub total_spent: u64,
+    // 凭购买凭证发表的评价数量
+    pub verified_reviews: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 累计获得且尚未兑换的忠诚度积分
+    pub loyalty_points: u64,
+    // 已达成和已领取的成就标志位，见Achievement::flag
+    pub achievements: u32,
+    pub claimed_achievements: u32,
+    // 买家偏好的慈善机构PDA和结账时在成交额之外另行捐赠的比例（基点），全零表示不捐赠
+    pub charity: Pubkey,
+    pub charity_bps: u16,
+}
+
+impl IsInitialized for BuyerStats {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for BuyerStats {}
+
+impl Pack for BuyerStats {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u64, u64, u64, i64, i64, u64, u32, u32, Pubkey, u16
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.buyer.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.total_purchases.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_spent.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.verified_reviews.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.loyalty_points.to_le_bytes());
+        offset += 8;
+        output[offset..offset+4].copy_from_slice(&self.achievements.to_le_bytes());
+        offset += 4;
+        output[offset..offset+4].copy_from_slice(&self.claimed_achievements.to_le_bytes());
+        offset += 4;
+        output[offset..offset+32].copy_from_slice(self.charity.as_ref());
+        offset += 32;
+        output[offset..offset+2].copy_from_slice(&self.charity_bps.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let buyer = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let total_purchases = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_spent = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let verified_reviews = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let loyalty_points = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let achievements = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let claimed_achievements = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let charity = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let charity_bps = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            buyer,
+            total_purchases,
+            total_spent,
+            verified_reviews,
+            created_at,
+            updated_at,
+            loyalty_points,
+            achievements,
+            claimed_achievements,
+            charity,
+            charity_bps,
+        })
+    }
+}
+
+impl BuyerStats {
+    // 买家下一张凭证应写入的索引分页
+    pub fn current_receipts_page(&self) -> u32 {
+        (self.total_purchases / BUYER_RECEIPTS_PAGE_CAPACITY as u64) as u32
+    }
+
+    // 按当前统计标记新达成的成就
+    pub fn refresh_achievements(&mut self) {
+        if self.total_purchases >= 1 {
+            self.achievements |= Achievement::FirstPurchase.flag();
+        }
+    }
+
+    // 结账时在成交额amount_paid之外按偏好另行捐赠的金额，向下取整
+    pub fn charity_donation(&self, amount_paid: u64) -> u64 {
+        fee_at_bps(amount_paid, self.charity_bps as u64)
+    }
+
+    // 兑换points个忠诚度积分，积分不足时失败
+    pub fn redeem_loyalty_points(&mut self, points: u64) -> ProgramResult {
+        self.loyalty_points = self.loyalty_points
+            .checked_sub(points)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        Ok(())
+    }
+}
+
+// 定义买家凭证索引的一页，按购买顺序记录买家的购买凭证PDA，
+// 钱包界面读取一页即可列出买家购买过的模型
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuyerReceiptsPage {
+    pub is_initialized: bool,
+    pub buyer: Pubkey,
+    pub page_index: u32,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub receipts: Vec<Pubkey>,
+}
+
+impl IsInitialized for BuyerReceiptsPage {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for BuyerReceiptsPage {}
+
+impl Pack for BuyerReceiptsPage {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u32, i64, i64, u16, [Pubkey; BUYER_RECEIPTS_PAGE_CAPACITY]
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.buyer.as_ref());
+        offset += 32;
+        output[offset..offset+4].copy_from_slice(&self.page_index.to_le_bytes());
+        offset += 4;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+2].copy_from_slice(&(self.receipts.len() as u16).to_le_bytes());
+        offset += 2;
+        for receipt in &self.receipts {
+            output[offset..offset+32].copy_from_slice(receipt.as_ref());
+            offset += 32;
+        }
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let buyer = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let page_index = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let receipt_count = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap()) as usize;
+        if receipt_count > BUYER_RECEIPTS_PAGE_CAPACITY {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset += 2;
+        let mut receipts = Vec::with_capacity(receipt_count);
+        for _ in 0..receipt_count {
+            receipts.push(Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap()));
+            offset += 32;
+        }
+        Ok(Self {
+            is_initialized,
+            buyer,
+            page_index,
+            created_at,
+            updated_at,
+            receipts,
+        })
+    }
+}
+
+// 定义模型的发布版本，每个版本记录版本号和对应产物的内容哈希
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelVersion {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub version_index: u32,
+    pub version: SemVer,
+    pub artifact_hash: [u8; 32],
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl IsInitialized for ModelVersion {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for ModelVersion {}
+
+impl Pack for ModelVersion {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u32, SemVer, [u8; 32], i64, i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+4].copy_from_slice(&self.version_index.to_le_bytes());
+        offset += 4;
+        self.version.pack_into_slice(&mut output[offset..offset+SemVer::LEN]);
+        offset += SemVer::LEN;
+        output[offset..offset+32].copy_from_slice(&self.artifact_hash);
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let version_index = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let version = SemVer::unpack_from_slice(&input[offset..offset+SemVer::LEN])?;
+        offset += SemVer::LEN;
+        let artifact_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            model,
+            version_index,
+            version,
+            artifact_hash,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义发布版本的更新日志，previous指向上一条更新日志，形成从AIModel出发的链表
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChangelogEntry {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub model_version: Pubkey,
+    pub version: SemVer,
+    // 链下完整更新日志的内容哈希和URI
+    pub content_hash: [u8; 32],
+    pub uri: String,
+    pub summary: String,
+    // 上一条更新日志，全零表示这是第一条
+    pub previous: Pubkey,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl IsInitialized for ChangelogEntry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for ChangelogEntry {}
+
+impl Pack for ChangelogEntry {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, SemVer, [u8; 32], [u8; MAX_CHANGELOG_URI_LEN],
+        [u8; MAX_CHANGELOG_SUMMARY_LEN], Pubkey, i64, i64
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.model_version.as_ref());
+        offset += 32;
+        self.version.pack_into_slice(&mut output[offset..offset+SemVer::LEN]);
+        offset += SemVer::LEN;
+        output[offset..offset+32].copy_from_slice(&self.content_hash);
+        offset += 32;
+        pack_fixed_str(&mut output[offset..offset+MAX_CHANGELOG_URI_LEN], &self.uri);
+        offset += MAX_CHANGELOG_URI_LEN;
+        pack_fixed_str(&mut output[offset..offset+MAX_CHANGELOG_SUMMARY_LEN], &self.summary);
+        offset += MAX_CHANGELOG_SUMMARY_LEN;
+        output[offset..offset+32].copy_from_slice(self.previous.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let model_version = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let version = SemVer::unpack_from_slice(&input[offset..offset+SemVer::LEN])?;
+        offset += SemVer::LEN;
+        let content_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let uri = unpack_fixed_str(&input[offset..offset+MAX_CHANGELOG_URI_LEN])?;
+        offset += MAX_CHANGELOG_URI_LEN;
+        let summary = unpack_fixed_str(&input[offset..offset+MAX_CHANGELOG_SUMMARY_LEN])?;
+        offset += MAX_CHANGELOG_SUMMARY_LEN;
+        let previous = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            model,
+            model_version,
+            version,
+            content_hash,
+            uri,
+            summary,
+            previous,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义关注/收藏关系：follower关注卖家或收藏模型target
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Relationship {
+    pub is_initialized: bool,
+    pub follower: Pubkey,
+    pub target: Pubkey,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl IsInitialized for Relationship {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Relationship {}
+
+impl Pack for Relationship {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, i64, i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.follower.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.target.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let follower = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let target = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            follower,
+            target,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义一个分类，parent为上级分类编号，0表示顶级分类
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Category {
+    pub id: u16,
+    pub parent: u16,
+    pub label: String,
+}
+
+impl Sealed for Category {}
+
+impl Pack for Category {
+    const LEN: usize = packed_len!(u16, u16, [u8; MAX_CATEGORY_LABEL_LEN]);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let mut offset = 0;
+        output[offset..offset+2].copy_from_slice(&self.id.to_le_bytes());
+        offset += 2;
+        output[offset..offset+2].copy_from_slice(&self.parent.to_le_bytes());
+        offset += 2;
+        pack_fixed_str(&mut output[offset..offset+MAX_CATEGORY_LABEL_LEN], &self.label);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let mut offset = 0;
+        let id = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        offset += 2;
+        let parent = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        offset += 2;
+        let label = unpack_fixed_str(&input[offset..offset+MAX_CATEGORY_LABEL_LEN])?;
+        Ok(Self {
+            id,
+            parent,
+            label,
+        })
+    }
+}
+
+// 定义由管理员维护的分类注册表，模型通过category_id引用其中的分类
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CategoryRegistry {
+    pub is_initialized: bool,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub categories: Vec<Category>,
+}
+
+impl CategoryRegistry {
+    pub fn get(&self, id: u16) -> Option<&Category> {
+        self.categories.iter().find(|category| category.id == id)
+    }
+
+    // 注册新分类：编号不能为0或重复，上级分类必须已注册
+    pub fn register(&mut self, category: Category) -> ProgramResult {
+        if category.id == 0
+            || self.get(category.id).is_some()
+            || (category.parent != 0 && self.get(category.parent).is_none())
+            || category.label.is_empty()
+            || category.label.len() > MAX_CATEGORY_LABEL_LEN
+        {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.categories.len() >= MAX_CATEGORIES {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        self.categories.push(category);
+        Ok(())
+    }
+}
+
+impl IsInitialized for CategoryRegistry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for CategoryRegistry {}
+
+impl Pack for CategoryRegistry {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, i64, i64, u16, [Category; MAX_CATEGORIES]);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+2].copy_from_slice(&(self.categories.len() as u16).to_le_bytes());
+        offset += 2;
+        for category in &self.categories {
+            category.pack_into_slice(&mut output[offset..offset+Category::LEN]);
+            offset += Category::LEN;
+        }
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let category_count = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap()) as usize;
+        if category_count > MAX_CATEGORIES {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset += 2;
+        let mut categories = Vec::with_capacity(category_count);
+        for _ in 0..category_count {
+            categories.push(Category::unpack_from_slice(&input[offset..offset+Category::LEN])?);
+            offset += Category::LEN;
+        }
+        Ok(Self {
+            is_initialized,
+            created_at,
+            updated_at,
+            categories,
+        })
+    }
+}
+
+// 定义管理员维护的精选列表，listings的顺序即展示顺序
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeaturedList {
+    pub is_initialized: bool,
+    pub list_id: u32,
+    pub label: String,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub listings: Vec<Pubkey>,
+}
+
+impl IsInitialized for FeaturedList {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for FeaturedList {}
+
+impl Pack for FeaturedList {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, u32, [u8; MAX_FEATURED_LABEL_LEN], i64, i64, u8, [Pubkey; MAX_FEATURED_LISTINGS]
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+4].copy_from_slice(&self.list_id.to_le_bytes());
+        offset += 4;
+        pack_fixed_str(&mut output[offset..offset+MAX_FEATURED_LABEL_LEN], &self.label);
+        offset += MAX_FEATURED_LABEL_LEN;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset] = self.listings.len() as u8;
+        offset += 1;
+        output[offset..offset+32*MAX_FEATURED_LISTINGS].fill(0);
+        for listing in &self.listings {
+            output[offset..offset+32].copy_from_slice(listing.as_ref());
+            offset += 32;
+        }
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let list_id = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let label = unpack_fixed_str(&input[offset..offset+MAX_FEATURED_LABEL_LEN])?;
+        offset += MAX_FEATURED_LABEL_LEN;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let listing_count = input[offset] as usize;
+        if listing_count > MAX_FEATURED_LISTINGS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset += 1;
+        let mut listings = Vec::with_capacity(listing_count);
+        for _ in 0..listing_count {
+            listings.push(Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap()));
+            offset += 32;
+        }
+        Ok(Self {
+            is_initialized,
+            list_id,
+            label,
+            created_at,
+            updated_at,
+            listings,
+        })
+    }
+}
+
+// 定义溯源证明使用的证明系统，验证器的verifying_key按证明系统解释，新的证明系统在这里扩展
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProofSystem {
+    // BN254曲线上的Groth16，通过alt_bn128系统调用验证
+    #[default]
+    Groth16Bn254,
+}
+
+impl ProofSystem {
+    pub fn from_u8(value: u8) -> Result<Self, ProgramError> {
+        match value {
+            0 => Ok(Self::Groth16Bn254),
+            _ => Err(ProgramError::InvalidArgument),
+        }
+    }
+
+    // 检查验证密钥的格式，返回其支持的公开输入数量
+    pub fn public_input_count(&self, verifying_key: &[u8]) -> Result<usize, ProgramError> {
+        match self {
+            Self::Groth16Bn254 => {
+                let ic_len = verifying_key
+                    .len()
+                    .checked_sub(GROTH16_VK_FIXED_LEN)
+                    .ok_or(ProgramError::InvalidArgument)?;
+                if ic_len == 0 || ic_len % 64 != 0 || ic_len / 64 - 1 > MAX_PROOF_PUBLIC_INPUTS {
+                    return Err(ProgramError::InvalidArgument);
+                }
+                Ok(ic_len / 64 - 1)
+            }
+        }
+    }
+
+    // 按证明系统校验证明和公开输入
+    pub fn verify(&self, verifying_key: &[u8], proof: &[u8], public_inputs: &[[u8; 32]]) -> ProgramResult {
+        match self {
+            Self::Groth16Bn254 => verify_groth16(verifying_key, proof, public_inputs),
+        }
+    }
+}
+
+// 定义管理员注册的溯源证明验证器：一种证明系统、它的验证密钥以及证明通过时所代表的声明
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProvenanceVerifier {
+    pub is_initialized: bool,
+    pub verifier_id: u32,
+    pub proof_system: ProofSystem,
+    pub claim: String,
+    pub verifying_key: Vec<u8>,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl IsInitialized for ProvenanceVerifier {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for ProvenanceVerifier {}
+
+impl Pack for ProvenanceVerifier {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, u32, ProofSystem, [u8; MAX_PROVENANCE_CLAIM_LEN], u16, [u8; MAX_VERIFYING_KEY_LEN],
+        i64, i64
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+4].copy_from_slice(&self.verifier_id.to_le_bytes());
+        offset += 4;
+        output[offset] = self.proof_system as u8;
+        offset += 1;
+        pack_fixed_str(&mut output[offset..offset+MAX_PROVENANCE_CLAIM_LEN], &self.claim);
+        offset += MAX_PROVENANCE_CLAIM_LEN;
+        output[offset..offset+2].copy_from_slice(&(self.verifying_key.len() as u16).to_le_bytes());
+        offset += 2;
+        let key_slot = &mut output[offset..offset+MAX_VERIFYING_KEY_LEN];
+        key_slot.fill(0);
+        key_slot[..self.verifying_key.len()].copy_from_slice(&self.verifying_key);
+        offset += MAX_VERIFYING_KEY_LEN;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let verifier_id = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let proof_system = ProofSystem::from_u8(input[offset]).map_err(|_| ProgramError::InvalidAccountData)?;
+        offset += 1;
+        let claim = unpack_fixed_str(&input[offset..offset+MAX_PROVENANCE_CLAIM_LEN])?;
+        offset += MAX_PROVENANCE_CLAIM_LEN;
+        let key_len = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap()) as usize;
+        if key_len > MAX_VERIFYING_KEY_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset += 2;
+        let verifying_key = input[offset..offset+key_len].to_vec();
+        offset += MAX_VERIFYING_KEY_LEN;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            verifier_id,
+            proof_system,
+            claim,
+            verifying_key,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义模型的溯源证明，证明通过对应验证器的校验时写入；重新验证会覆盖旧的证明
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProvenanceAttestation {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub verifier: Pubkey,
+    // 证明账户数据和公开输入的SHA-256，供链下复核
+    pub proof_hash: [u8; 32],
+    pub public_inputs_hash: [u8; 32],
+    pub verified_at: i64,
+}
+
+impl IsInitialized for ProvenanceAttestation {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for ProvenanceAttestation {}
+
+impl Pack for ProvenanceAttestation {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, [u8; 32], [u8; 32], i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.verifier.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(&self.proof_hash);
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(&self.public_inputs_hash);
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.verified_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let verifier = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let proof_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let public_inputs_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let verified_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            model,
+            verifier,
+            proof_hash,
+            public_inputs_hash,
+            verified_at,
+        })
+    }
+}
+
+// 定义推理结果承诺：工作节点用指定模型版本处理输入后得到的输出哈希，
+// 发生争议时链下可以用同一版本的产物和输入确定性地复现并比对；承诺提交后不可修改
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InferenceCommitment {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub model_version: Pubkey,
+    // 提交时模型版本的版本号，避免审计时再读取版本账户
+    pub version: SemVer,
+    pub worker: Pubkey,
+    pub input_hash: [u8; 32],
+    pub output_hash: [u8; 32],
+    pub committed_at: i64,
+}
+
+impl IsInitialized for InferenceCommitment {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for InferenceCommitment {}
+
+impl Pack for InferenceCommitment {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, SemVer, Pubkey, [u8; 32], [u8; 32], i64
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.model_version.as_ref());
+        offset += 32;
+        self.version.pack_into_slice(&mut output[offset..offset+SemVer::LEN]);
+        offset += SemVer::LEN;
+        output[offset..offset+32].copy_from_slice(self.worker.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(&self.input_hash);
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(&self.output_hash);
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.committed_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let model_version = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let version = SemVer::unpack_from_slice(&input[offset..offset+SemVer::LEN])?;
+        offset += SemVer::LEN;
+        let worker = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let input_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let output_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let committed_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            model,
+            model_version,
+            version,
+            worker,
+            input_hash,
+            output_hash,
+            committed_at,
+        })
+    }
+}
+
+// 定义付费推理请求，请求PDA除租金外还托管max_price；工作节点提交结果后经过挑战期，按报价结算给工作节点，余额退回请求者
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InferenceRequest {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub requester: Pubkey,
+    pub request_id: u64,
+    // 输入（提示词）的哈希，工作节点的结果承诺必须针对同一输入
+    pub prompt_hash: [u8; 32],
+    pub max_price: u64,
+    // 截止时间的unix时间戳，截止后未完成的请求可以由请求者取消并退款
+    pub deadline: i64,
+    pub status: InferenceRequestStatus,
+    // 完成请求的工作节点、其结果承诺和实际收取的价格
+    pub worker: Pubkey,
+    pub commitment: Pubkey,
+    pub price: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 当前锁定请求的工作节点和锁定到期时间，全零表示未被锁定
+    pub claimed_by: Pubkey,
+    pub claim_expires_at: i64,
+    // 请求者设置的挑战期时长，以及工作节点提交的输出哈希、证明URI和挑战期结束时间
+    pub challenge_window: i64,
+    pub output_hash: [u8; 32],
+    pub proof_uri: String,
+    pub challenge_ends_at: i64,
+}
+
+impl IsInitialized for InferenceRequest {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for InferenceRequest {}
+
+impl Pack for InferenceRequest {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, u64, [u8; 32], u64, i64, InferenceRequestStatus, Pubkey, Pubkey, u64,
+        i64, i64, Pubkey, i64, i64, [u8; 32], [u8; MAX_PROOF_URI_LEN], i64
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.requester.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.request_id.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(&self.prompt_hash);
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.max_price.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.deadline.to_le_bytes());
+        offset += 8;
+        output[offset] = self.status as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.worker.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.commitment.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.price.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(self.claimed_by.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.claim_expires_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.challenge_window.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(&self.output_hash);
+        offset += 32;
+        pack_fixed_str(&mut output[offset..offset+MAX_PROOF_URI_LEN], &self.proof_uri);
+        offset += MAX_PROOF_URI_LEN;
+        output[offset..offset+8].copy_from_slice(&self.challenge_ends_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let requester = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let request_id = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let prompt_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let max_price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let deadline = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let status = InferenceRequestStatus::from_u8(input[offset])?;
+        offset += 1;
+        let worker = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let commitment = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let claimed_by = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let claim_expires_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let challenge_window = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let output_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let proof_uri = unpack_fixed_str(&input[offset..offset+MAX_PROOF_URI_LEN])?;
+        offset += MAX_PROOF_URI_LEN;
+        let challenge_ends_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            model,
+            requester,
+            request_id,
+            prompt_hash,
+            max_price,
+            deadline,
+            status,
+            worker,
+            commitment,
+            price,
+            created_at,
+            updated_at,
+            claimed_by,
+            claim_expires_at,
+            challenge_window,
+            output_hash,
+            proof_uri,
+            challenge_ends_at,
+        })
+    }
+}
+
+impl InferenceRequest {
+    // 请求是否正被某个工作节点锁定且锁定尚未到期
+    pub fn is_claimed(&self, now: i64) -> bool {
+        self.claimed_by != Pubkey::default() && now < self.claim_expires_at
+    }
+
+    // 工作节点锁定请求：请求仍在等待、未过截止时间，且没有未到期的锁定
+    pub fn claim(&mut self, worker: &Pubkey, now: i64) -> ProgramResult {
+        if self.status != InferenceRequestStatus::Open || now >= self.deadline || self.is_claimed(now) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.claimed_by = *worker;
+        self.claim_expires_at = now.saturating_add(INFERENCE_CLAIM_TIMEOUT).min(self.deadline);
+        Ok(())
+    }
+
+    // 释放已到期的锁定，使其他工作节点可以重新锁定
+    pub fn release_expired_claim(&mut self, now: i64) -> ProgramResult {
+        if self.status != InferenceRequestStatus::Open
+            || self.claimed_by == Pubkey::default()
+            || self.is_claimed(now)
+        {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.claimed_by = Pubkey::default();
+        self.claim_expires_at = 0;
+        Ok(())
+    }
+
+    // 请求者在挑战期内对已提交的结果提出异议
+    pub fn challenge(&mut self, now: i64) -> ProgramResult {
+        if self.status != InferenceRequestStatus::Submitted || now >= self.challenge_ends_at {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.status = InferenceRequestStatus::Challenged;
+        Ok(())
+    }
+
+    // 挑战期结束且未被挑战的结果可以结算
+    pub fn require_settleable(&self, now: i64) -> ProgramResult {
+        if self.status != InferenceRequestStatus::Submitted || now < self.challenge_ends_at {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    // 检查结果承诺能否完成该请求：请求仍在等待且由承诺的工作节点锁定，承诺针对同一模型和输入，且在截止时间前提交；
+    // 锁定到期但尚未被释放时，锁定者仍可完成请求
+    pub fn require_fulfillable_by(&self, commitment: &InferenceCommitment, price: u64) -> ProgramResult {
+        if self.status != InferenceRequestStatus::Open || self.claimed_by != commitment.worker {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if commitment.model != self.model
+            || commitment.input_hash != self.prompt_hash
+            || commitment.committed_at > self.deadline
+        {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if price > self.max_price {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+}
+
+// 定义注册的推理工作节点及其公开的能力，只有注册过且支持模型格式的工作节点才能完成推理请求
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Worker {
+    pub is_initialized: bool,
+    pub operator: Pubkey,
+    pub hardware_class: HardwareClass,
+    // 支持的模型格式位图，见ModelFormat::bit
+    pub supported_formats: u8,
+    // 每计费单位的报价（lamports），供请求者在链下比较
+    pub price_per_unit: u64,
+    // 推理服务端点描述的哈希，端点本身在链下交换
+    pub endpoint_hash: [u8; 32],
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 质押的lamports，存放在工作节点PDA中（不含租金）
+    pub stake: u64,
+    // 最近一次完成推理请求的时间戳，决定何时可以取回质押
+    pub last_fulfilled_at: i64,
+}
+
+impl Worker {
+    pub fn supports(&self, format: ModelFormat) -> bool {
+        self.supported_formats & format.bit() != 0
+    }
+
+    // 完成推理请求需要达到最低质押
+    pub fn require_staked(&self) -> ProgramResult {
+        if self.stake < MIN_WORKER_STAKE {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        Ok(())
+    }
+
+    // 取回质押需要距最近一次完成请求超过WORKER_UNSTAKE_DELAY
+    pub fn require_unstakable(&self, now: i64) -> ProgramResult {
+        if self.last_fulfilled_at != 0 && now < self.last_fulfilled_at.saturating_add(WORKER_UNSTAKE_DELAY) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    // 计算结果被证伪时罚没的质押
+    pub fn slash_amount(&self) -> u64 {
+        (self.stake as u128 * WORKER_SLASH_BPS as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+}
+
+impl IsInitialized for Worker {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Worker {}
+
+impl Pack for Worker {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, HardwareClass, u8, u64, [u8; 32], i64, i64, u64, i64
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.operator.as_ref());
+        offset += 32;
+        output[offset] = self.hardware_class as u8;
+        offset += 1;
+        output[offset] = self.supported_formats;
+        offset += 1;
+        output[offset..offset+8].copy_from_slice(&self.price_per_unit.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(&self.endpoint_hash);
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.stake.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.last_fulfilled_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let operator = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let hardware_class = HardwareClass::from_u8(input[offset])?;
+        offset += 1;
+        let supported_formats = input[offset];
+        offset += 1;
+        let price_per_unit = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let endpoint_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let stake = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let last_fulfilled_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            operator,
+            hardware_class,
+            supported_formats,
+            price_per_unit,
+            endpoint_hash,
+            created_at,
+            updated_at,
+            stake,
+            last_fulfilled_at,
+        })
+    }
+}
+
+// 定义流式租用，租用者预存的押金按经过的slot以固定费率流向模型所有者，任一方关闭时按实际使用的slot结算
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RentalStream {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    // 开启时的模型所有者，租金流向该钱包
+    pub owner: Pubkey,
+    pub renter: Pubkey,
+    // 开启时锁定的每slot费率，模型后续调价不影响已开启的租用流
+    pub rate_per_slot: u64,
+    pub start_slot: u64,
+    // 存放在租用流PDA中的押金（不含租金）及所有者已提取的部分
+    pub deposit: u64,
+    pub withdrawn: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl RentalStream {
+    // 截至slot已流向所有者的金额，押金耗尽后不再增加
+    pub fn streamed(&self, slot: u64) -> u64 {
+        proration::metered(self.rate_per_slot, slot.saturating_sub(self.start_slot), self.deposit)
+    }
+
+    // 关闭时退回租用者的未使用押金
+    pub fn refund(&self, slot: u64) -> u64 {
+        proration::unused(self.deposit, self.streamed(slot))
+    }
+
+    // 所有者当前可以提取的金额
+    pub fn claimable(&self, slot: u64) -> u64 {
+        self.streamed(slot) - self.withdrawn
+    }
+
+    // 押金尚未耗尽时租用仍然有效
+    pub fn is_active(&self, slot: u64) -> bool {
+        self.streamed(slot) < self.deposit
+    }
+}
+
+impl IsInitialized for RentalStream {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for RentalStream {}
+
+impl Pack for RentalStream {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, Pubkey, u64, u64, u64, u64, i64, i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.owner.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.renter.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.rate_per_slot.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.start_slot.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.deposit.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.withdrawn.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let owner = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let renter = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let rate_per_slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let start_slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let deposit = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let withdrawn = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            model,
+            owner,
+            renter,
+            rate_per_slot,
+            start_slot,
+            deposit,
+            withdrawn,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义组织成员及其角色
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OrganizationMember {
+    pub member: Pubkey,
+    pub role: OrganizationRole,
+}
+
+impl OrganizationMember {
+    pub const LEN: usize = packed_len!(Pubkey, OrganizationRole);
+}
+
+// 定义组织（团队）账户。模型的所有者或更新权限可以设为组织PDA：销售收入进入组织PDA，由管理员提取；
+// 组织的编辑和管理员签名并传入组织PDA即可行使更新权限，管理员还可以行使所有者的权限
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Organization {
+    pub is_initialized: bool,
+    pub creator: Pubkey,
+    pub org_id: u32,
+    pub name: String,
+    pub members: Vec<OrganizationMember>,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Organization {
+    pub fn role_of(&self, key: &Pubkey) -> Option<OrganizationRole> {
+        self.members.iter().find(|entry| entry.member == *key).map(|entry| entry.role)
+    }
+
+    pub fn require_role(&self, key: &Pubkey, required: OrganizationRole) -> ProgramResult {
+        match self.role_of(key) {
+            Some(role) if role.grants(required) => Ok(()),
+            _ => Err(ProgramError::InvalidArgument),
+        }
+    }
+
+    fn admin_count(&self) -> usize {
+        self.members.iter().filter(|entry| entry.role == OrganizationRole::Admin).count()
+    }
+
+    // 添加成员或修改已有成员的角色，组织至少保留一名管理员
+    pub fn set_member(&mut self, member: Pubkey, role: OrganizationRole) -> ProgramResult {
+        if let Some(entry) = self.members.iter_mut().find(|entry| entry.member == member) {
+            entry.role = role;
+        } else if self.members.len() >= MAX_ORGANIZATION_MEMBERS {
+            return Err(ProgramError::AccountDataTooSmall);
+        } else {
+            self.members.push(OrganizationMember { member, role });
+        }
+        if self.admin_count() == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    pub fn remove_member(&mut self, member: &Pubkey) -> ProgramResult {
+        let index = self
+            .members
+            .iter()
+            .position(|entry| entry.member == *member)
+            .ok_or(ProgramError::InvalidArgument)?;
+        self.members.remove(index);
+        if self.admin_count() == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+}
+
+impl IsInitialized for Organization {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Organization {}
+
+impl Pack for Organization {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u32, [u8; MAX_ORGANIZATION_NAME_LEN], u8,
+        [OrganizationMember; MAX_ORGANIZATION_MEMBERS], i64, i64
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.creator.as_ref());
+        offset += 32;
+        output[offset..offset+4].copy_from_slice(&self.org_id.to_le_bytes());
+        offset += 4;
+        pack_fixed_str(&mut output[offset..offset+MAX_ORGANIZATION_NAME_LEN], &self.name);
+        offset += MAX_ORGANIZATION_NAME_LEN;
+        output[offset] = self.members.len() as u8;
+        offset += 1;
+        for i in 0..MAX_ORGANIZATION_MEMBERS {
+            let slot = &mut output[offset..offset+OrganizationMember::LEN];
+            match self.members.get(i) {
+                Some(entry) => {
+                    slot[..32].copy_from_slice(entry.member.as_ref());
+                    slot[32] = entry.role as u8;
+                }
+                None => slot.fill(0),
+            }
+            offset += OrganizationMember::LEN;
+        }
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let creator = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let org_id = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let name = unpack_fixed_str(&input[offset..offset+MAX_ORGANIZATION_NAME_LEN])?;
+        offset += MAX_ORGANIZATION_NAME_LEN;
+        let member_count = input[offset] as usize;
+        if member_count > MAX_ORGANIZATION_MEMBERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset += 1;
+        let mut members = Vec::with_capacity(member_count);
+        for i in 0..MAX_ORGANIZATION_MEMBERS {
+            if i < member_count {
+                members.push(OrganizationMember {
+                    member: Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap()),
+                    role: OrganizationRole::from_u8(input[offset+32])?,
+                });
+            }
+            offset += OrganizationMember::LEN;
+        }
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            creator,
+            org_id,
+            name,
+            members,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义委托开发的一个里程碑，金额在开启委托时全部托管
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Milestone {
+    pub amount: u64,
+    pub status: MilestoneStatus,
+    // 卖家交付物的内容哈希，交付物本身在链下交换
+    pub deliverable_hash: [u8; 32],
+    pub updated_at: i64,
+}
+
+impl Milestone {
+    pub const LEN: usize = packed_len!(u64, MilestoneStatus, [u8; 32], i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[0..8].copy_from_slice(&self.amount.to_le_bytes());
+        output[8] = self.status as u8;
+        output[9..41].copy_from_slice(&self.deliverable_hash);
+        output[41..49].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Self {
+            amount: u64::from_le_bytes(input[0..8].try_into().unwrap()),
+            status: MilestoneStatus::from_u8(input[8])?,
+            deliverable_hash: input[9..41].try_into().unwrap(),
+            updated_at: i64::from_le_bytes(input[41..49].try_into().unwrap()),
+        })
+    }
+}
+
+// 定义买家出资的委托开发合约，委托PDA托管全部里程碑金额（不含租金），卖家在买家逐个确认里程碑后分批领取
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Commission {
+    pub is_initialized: bool,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub commission_id: u64,
+    pub milestones: Vec<Milestone>,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Commission {
+    // 仍托管在委托PDA中的里程碑金额，已领取或已退款的里程碑不再计入
+    pub fn escrowed_amount(&self) -> Result<u64, ProgramError> {
+        self.milestones
+            .iter()
+            .filter(|milestone| !matches!(milestone.status, MilestoneStatus::Released | MilestoneStatus::Refunded))
+            .try_fold(0u64, |total, milestone| total.checked_add(milestone.amount))
+            .ok_or(ProgramError::ArithmeticOverflow)
+    }
+
+    fn milestone_mut(&mut self, index: u8) -> Result<&mut Milestone, ProgramError> {
+        self.milestones.get_mut(index as usize).ok_or(ProgramError::InvalidArgument)
+    }
+
+    // 把里程碑从from状态推进到to状态，状态不符时拒绝
+    fn transition(&mut self, index: u8, from: &[MilestoneStatus], to: MilestoneStatus, now: i64) -> Result<&mut Milestone, ProgramError> {
+        let milestone = self.milestone_mut(index)?;
+        if !from.contains(&milestone.status) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        milestone.status = to;
+        milestone.updated_at = now;
+        Ok(milestone)
+    }
+
+    // 卖家提交交付物，买家确认前可以重新提交
+    pub fn submit(&mut self, index: u8, deliverable_hash: [u8; 32], now: i64) -> ProgramResult {
+        let milestone = self.transition(
+            index,
+            &[MilestoneStatus::Pending, MilestoneStatus::Submitted],
+            MilestoneStatus::Submitted,
+            now,
+        )?;
+        milestone.deliverable_hash = deliverable_hash;
+        Ok(())
+    }
+
+    pub fn approve(&mut self, index: u8, now: i64) -> ProgramResult {
+        self.transition(index, &[MilestoneStatus::Submitted], MilestoneStatus::Approved, now)?;
+        Ok(())
+    }
+
+    // 买家拒绝交付物，或卖家认为买家拖延确认时，任一方都可以对已交付的里程碑发起争议
+    pub fn dispute(&mut self, index: u8, now: i64) -> ProgramResult {
+        self.transition(index, &[MilestoneStatus::Submitted], MilestoneStatus::Disputed, now)?;
+        Ok(())
+    }
+
+    // 仲裁者裁定争议，返回需要退给买家的金额
+    pub fn resolve(&mut self, index: u8, release: bool, now: i64) -> Result<u64, ProgramError> {
+        let to = if release { MilestoneStatus::Approved } else { MilestoneStatus::Refunded };
+        let milestone = self.transition(index, &[MilestoneStatus::Disputed], to, now)?;
+        Ok(if release { 0 } else { milestone.amount })
+    }
+
+    // 卖家领取已确认的里程碑，返回领取的金额
+    pub fn release(&mut self, index: u8, now: i64) -> Result<u64, ProgramError> {
+        let milestone = self.transition(index, &[MilestoneStatus::Approved], MilestoneStatus::Released, now)?;
+        Ok(milestone.amount)
+    }
+
+    // 所有里程碑都已领取或退款后才能关闭委托
+    pub fn is_settled(&self) -> bool {
+        self.milestones
+            .iter()
+            .all(|milestone| matches!(milestone.status, MilestoneStatus::Released | MilestoneStatus::Refunded))
+    }
+}
+
+impl IsInitialized for Commission {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Commission {}
+
+impl Pack for Commission {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, u64, u8, [Milestone; MAX_COMMISSION_MILESTONES], i64, i64
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.buyer.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.seller.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.commission_id.to_le_bytes());
+        offset += 8;
+        output[offset] = self.milestones.len() as u8;
+        offset += 1;
+        for i in 0..MAX_COMMISSION_MILESTONES {
+            let slot = &mut output[offset..offset+Milestone::LEN];
+            match self.milestones.get(i) {
+                Some(milestone) => milestone.pack_into_slice(slot),
+                None => slot.fill(0),
+            }
+            offset += Milestone::LEN;
+        }
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let buyer = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let seller = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let commission_id = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let milestone_count = input[offset] as usize;
+        if milestone_count > MAX_COMMISSION_MILESTONES {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset += 1;
+        let mut milestones = Vec::with_capacity(milestone_count);
+        for i in 0..MAX_COMMISSION_MILESTONES {
+            if i < milestone_count {
+                milestones.push(Milestone::unpack_from_slice(&input[offset..offset+Milestone::LEN])?);
+            }
+            offset += Milestone::LEN;
+        }
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            buyer,
+            seller,
+            commission_id,
+            milestones,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义模型的订阅方案，所有档位共用同一个计费周期
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SubscriptionPlan {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub period_slots: u64,
+    // 档位只能追加不能删除，已有订阅引用的档位编号始终有效
+    pub tiers: Vec<SubscriptionTier>,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 到期后仍可续订并保留原开始时间的宽限期，超过宽限期的订阅视为失效
+    pub grace_period_slots: u64,
+}
+
+impl SubscriptionPlan {
+    pub fn tier(&self, index: u8) -> Result<&SubscriptionTier, ProgramError> {
+        self.tiers.get(index as usize).ok_or(ProgramError::InvalidArgument)
+    }
+}
+
+impl IsInitialized for SubscriptionPlan {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for SubscriptionPlan {}
+
+impl Pack for SubscriptionPlan {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u64, u8, [SubscriptionTier; MAX_SUBSCRIPTION_TIERS], i64, i64, u64
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.period_slots.to_le_bytes());
+        offset += 8;
+        output[offset] = self.tiers.len() as u8;
+        offset += 1;
+        for i in 0..MAX_SUBSCRIPTION_TIERS {
+            let slot = &mut output[offset..offset+SubscriptionTier::LEN];
+            match self.tiers.get(i) {
+                Some(tier) => tier.pack_into_slice(slot),
+                None => slot.fill(0),
+            }
+            offset += SubscriptionTier::LEN;
+        }
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.grace_period_slots.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let period_slots = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let tier_count = input[offset] as usize;
+        if tier_count > MAX_SUBSCRIPTION_TIERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset += 1;
+        let mut tiers = Vec::with_capacity(tier_count);
+        for i in 0..MAX_SUBSCRIPTION_TIERS {
+            if i < tier_count {
+                tiers.push(SubscriptionTier::unpack_from_slice(&input[offset..offset+SubscriptionTier::LEN])?);
+            }
+            offset += SubscriptionTier::LEN;
+        }
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let grace_period_slots = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            model,
+            period_slots,
+            tiers,
+            created_at,
+            updated_at,
+            grace_period_slots,
+        })
+    }
+}
+
+// 定义订阅者对模型的订阅，访问在paid_through_slot之前有效
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Subscription {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub subscriber: Pubkey,
+    pub tier: u8,
+    // 订阅或最近一次变更档位时锁定的周期价格和周期长度，按此折算剩余时间，方案调价不影响当前周期
+    pub price: u64,
+    pub period_slots: u64,
+    pub start_slot: u64,
+    pub paid_through_slot: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 订阅者预存在订阅PDA中用于自动续订的lamports（不含租金）
+    pub renewal_balance: u64,
+    // 订阅者取消自动续订的时间戳，0表示未取消；取消后访问保持到paid_through_slot，续订清理指令跳过该订阅
+    pub canceled_at: i64,
+}
+
+impl Subscription {
+    pub fn is_active(&self, slot: u64) -> bool {
+        slot < self.paid_through_slot
+    }
+
+    pub fn remaining_slots(&self, slot: u64) -> u64 {
+        self.paid_through_slot.saturating_sub(slot)
+    }
+
+    // 到期后超过宽限期仍未续订即为失效，失效的订阅只能重新订阅
+    pub fn is_lapsed(&self, slot: u64, grace_period_slots: u64) -> bool {
+        slot >= self.paid_through_slot.saturating_add(grace_period_slots)
+    }
+
+    pub fn is_canceled(&self) -> bool {
+        self.canceled_at != 0
+    }
+
+    // 订阅是否已经结束：已取消的订阅到期即结束，没有宽限期
+    pub fn is_ended(&self, slot: u64, grace_period_slots: u64) -> bool {
+        if self.is_canceled() {
+            !self.is_active(slot)
+        } else {
+            self.is_lapsed(slot, grace_period_slots)
+        }
+    }
+
+    // 取消自动续订，返回退回订阅者的预存余额
+    pub fn cancel(&mut self, slot: u64, now: i64) -> Result<u64, ProgramError> {
+        if self.is_canceled() || !self.is_active(slot) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.canceled_at = now;
+        Ok(std::mem::take(&mut self.renewal_balance))
+    }
+
+    // 到期后（含宽限期内）用预存余额按档位当前价格续订一个周期；
+    // 新周期紧接原到期时间，开始时间保持不变。余额不足时续订失败，订阅者可以在宽限期内补足后重试
+    pub fn renew(&mut self, price: u64, period_slots: u64, grace_period_slots: u64, slot: u64) -> ProgramResult {
+        if self.is_canceled() || self.is_active(slot) || self.is_lapsed(slot, grace_period_slots) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.renewal_balance < price {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        self.renewal_balance -= price;
+        self.price = price;
+        self.period_slots = period_slots;
+        self.paid_through_slot = self.paid_through_slot.saturating_add(period_slots);
+        Ok(())
+    }
+
+    // 在周期中途变更档位，返回订阅者需要补交的金额，折算规则见proration::tier_change
+    pub fn change_tier(&mut self, tier: u8, new_price: u64, period_slots: u64, slot: u64) -> Result<u64, ProgramError> {
+        if !self.is_active(slot) || tier == self.tier || new_price == 0 || period_slots == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let change = proration::tier_change(
+            self.price,
+            self.period_slots,
+            new_price,
+            period_slots,
+            self.remaining_slots(slot),
+        );
+        self.paid_through_slot = slot.saturating_add(change.remaining_slots);
+        self.tier = tier;
+        self.price = new_price;
+        self.period_slots = period_slots;
+        Ok(change.charge)
+    }
+}
+
+impl IsInitialized for Subscription {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Subscription {}
+
+impl Pack for Subscription {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, u8, u64, u64, u64, u64, i64, i64, u64, i64
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.subscriber.as_ref());
+        offset += 32;
+        output[offset] = self.tier;
+        offset += 1;
+        output[offset..offset+8].copy_from_slice(&self.price.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.period_slots.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.start_slot.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.paid_through_slot.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.renewal_balance.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.canceled_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let subscriber = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let tier = input[offset];
+        offset += 1;
+        let price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let period_slots = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let start_slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let paid_through_slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let renewal_balance = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let canceled_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            model,
+            subscriber,
+            tier,
+            price,
+            period_slots,
+            start_slot,
+            paid_through_slot,
+            created_at,
+            updated_at,
+            renewal_balance,
+            canceled_at,
+        })
+    }
+}
+
+// 定义超级管理员授予的角色，PDA由角色和持有者推导，撤销时关闭
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RoleAssignment {
+    pub is_initialized: bool,
+    pub role: Role,
+    pub holder: Pubkey,
+    pub granted_by: Pubkey,
+    // 授予的unix时间戳，来自Clock系统变量
+    pub granted_at: i64,
+}
+
+impl IsInitialized for RoleAssignment {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for RoleAssignment {}
+
+impl Pack for RoleAssignment {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Role, Pubkey, Pubkey, i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset] = self.role as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.holder.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.granted_by.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.granted_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let role = Role::from_u8(input[offset]).map_err(|_| ProgramError::InvalidAccountData)?;
+        offset += 1;
+        let holder = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let granted_by = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let granted_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            role,
+            holder,
+            granted_by,
+            granted_at,
+        })
+    }
+}
+
+// 定义许可证的用量计数，由计量权限按计量周期累计请求数；
+// 限额按滑动窗口检查：上一周期的请求数按其与窗口重叠的比例折算后计入本周期
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UsageCounter {
+    pub is_initialized: bool,
+    pub receipt: Pubkey,
+    pub model: Pubkey,
+    // 当前计量周期序号，即slot / METERING_EPOCH_SLOTS
+    pub epoch: u64,
+    pub epoch_requests: u64,
+    pub previous_epoch_requests: u64,
+    // 累计接受和因超出限额被拒绝的请求数，供审计
+    pub total_requests: u64,
+    pub rejected_requests: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 已从预付额度中结算的累计请求数
+    pub settled_requests: u64,
+}
+
+impl UsageCounter {
+    // 进入slot所在的计量周期：相邻周期的计数转为上一周期，间隔更久则清零
+    pub fn advance(&mut self, slot: u64) {
+        let epoch = slot / METERING_EPOCH_SLOTS;
+        if epoch == self.epoch {
+            return;
+        }
+        self.previous_epoch_requests = if epoch == self.epoch + 1 { self.epoch_requests } else { 0 };
+        self.epoch_requests = 0;
+        self.epoch = epoch;
+    }
+
+    // 截至slot的滑动窗口请求数，调用前需已advance到slot所在周期
+    pub fn windowed_requests(&self, slot: u64) -> u64 {
+        let elapsed = slot % METERING_EPOCH_SLOTS;
+        proration::credit(self.previous_epoch_requests, METERING_EPOCH_SLOTS, METERING_EPOCH_SLOTS - elapsed)
+            .saturating_add(self.epoch_requests)
+    }
+
+    // 记录requests个请求，limit为0表示不限；超出限额时整批拒绝并计入rejected_requests，返回是否接受
+    pub fn record(&mut self, requests: u64, limit: u64, slot: u64) -> bool {
+        self.advance(slot);
+        let accepted = limit == 0 || self.windowed_requests(slot).saturating_add(requests) <= limit;
+        if accepted {
+            self.epoch_requests = self.epoch_requests.saturating_add(requests);
+            self.total_requests = self.total_requests.saturating_add(requests);
+        } else {
+            self.rejected_requests = self.rejected_requests.saturating_add(requests);
+        }
+        accepted
+    }
+
+    // 已接受但尚未结算的请求数
+    pub fn unsettled_requests(&self) -> u64 {
+        self.total_requests.saturating_sub(self.settled_requests)
+    }
+
+    // 按unit_price从balance中结算尽可能多的未结算请求，返回结算的请求数和金额；
+    // 额度不足时只结算额度覆盖的部分，其余留待充值后再结算
+    pub fn settle(&mut self, unit_price: u64, balance: u64) -> Result<(u64, u64), ProgramError> {
+        if unit_price == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let requests = self.unsettled_requests().min(balance / unit_price);
+        self.settled_requests += requests;
+        Ok((requests, requests * unit_price))
+    }
+}
+
+impl IsInitialized for UsageCounter {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for UsageCounter {}
+
+impl Pack for UsageCounter {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, u64, u64, u64, u64, u64, i64, i64, u64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.receipt.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.epoch.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.epoch_requests.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.previous_epoch_requests.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_requests.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.rejected_requests.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.settled_requests.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let receipt = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let epoch = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let epoch_requests = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let previous_epoch_requests = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_requests = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let rejected_requests = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let settled_requests = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            receipt,
+            model,
+            epoch,
+            epoch_requests,
+            previous_epoch_requests,
+            total_requests,
+            rejected_requests,
+            created_at,
+            updated_at,
+            settled_requests,
+        })
+    }
+}
+
+// 定义钱包的预付额度，SOL额度的lamports托管在该PDA中，代币额度的代币托管在该PDA的关联代币账户中，
+// balance为可用于结算的额度（不含租金）
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CreditAccount {
+    pub is_initialized: bool,
+    pub wallet: Pubkey,
+    pub balance: u64,
+    // 累计充值和累计结算的额度，供对账
+    pub total_deposited: u64,
+    pub total_spent: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 额度计价的Token-2022 mint，全零表示SOL
+    pub mint: Pubkey,
+}
+
+impl CreditAccount {
+    pub fn deposit(&mut self, amount: u64) -> ProgramResult {
+        self.balance = self.balance
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        self.total_deposited = self.total_deposited
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    pub fn is_sol(&self) -> bool {
+        self.mint == Pubkey::default()
+    }
+
+    pub fn spend(&mut self, amount: u64) -> ProgramResult {
+        self.balance = self.balance
+            .checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        self.total_spent = self.total_spent
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+impl IsInitialized for CreditAccount {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for CreditAccount {}
+
+impl Pack for CreditAccount {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u64, u64, u64, i64, i64, Pubkey);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.wallet.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.balance.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_deposited.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_spent.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(self.mint.as_ref());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let wallet = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let balance = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_deposited = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_spent = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let mint = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            wallet,
+            balance,
+            total_deposited,
+            total_spent,
+            created_at,
+            updated_at,
+            mint,
+        })
+    }
+}
+
+// 定义卖家的促销空投活动：创建时预存max_recipients份凭证的租金，空投凭证的租金由活动账户支付；
+// 设置了merkle_root时，名单中的钱包也可以凭Merkle证明自行领取，推送和领取共用同一凭证地址去重
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AirdropCampaign {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub seller: Pubkey,
+    pub campaign_id: u64,
+    // 空投许可证的档位
+    pub tier: u8,
+    pub max_recipients: u32,
+    pub airdropped: u32,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 接收者名单的Merkle根，叶子为merkle::leaf(&[活动地址, 钱包])；全零表示只能由卖家推送
+    pub merkle_root: [u8; 32],
+}
+
+impl AirdropCampaign {
+    // 校验钱包是否在campaign地址对应活动的接收者名单中
+    pub fn verify_recipient(&self, campaign: &Pubkey, wallet: &Pubkey, proof: &[[u8; 32]]) -> bool {
+        self.merkle_root != [0u8; 32]
+            && merkle::verify(proof, &self.merkle_root, &merkle::leaf(&[campaign.as_ref(), wallet.as_ref()]))
+    }
+
+    // 记录count份新空投，累计数量不能超过max_recipients
+    pub fn record_airdrops(&mut self, count: u32) -> ProgramResult {
+        let airdropped = self.airdropped
+            .checked_add(count)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        if airdropped > self.max_recipients {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.airdropped = airdropped;
+        Ok(())
+    }
+}
+
+impl IsInitialized for AirdropCampaign {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for AirdropCampaign {}
+
+impl Pack for AirdropCampaign {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, u64, u8, u32, u32, i64, i64, [u8; 32]);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.seller.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.campaign_id.to_le_bytes());
+        offset += 8;
+        output[offset] = self.tier;
+        offset += 1;
+        output[offset..offset+4].copy_from_slice(&self.max_recipients.to_le_bytes());
+        offset += 4;
+        output[offset..offset+4].copy_from_slice(&self.airdropped.to_le_bytes());
+        offset += 4;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(&self.merkle_root);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let seller = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let campaign_id = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let tier = input[offset];
+        offset += 1;
+        let max_recipients = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let airdropped = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let merkle_root = input[offset..offset+32].try_into().unwrap();
+        Ok(Self {
+            is_initialized,
+            model,
+            seller,
+            campaign_id,
+            tier,
+            max_recipients,
+            airdropped,
+            created_at,
+            updated_at,
+            merkle_root,
+        })
+    }
+}
+
+// 定义钱包链下签名订单的nonce状态，防止同一订单被重放：小于min_nonce的nonce全部失效，
+// [min_nonce, min_nonce + ORDER_NONCE_WINDOW)内已使用的nonce记录在位图中；
+// 使用超出窗口的nonce会让窗口前移，窗口外更早的未使用nonce随之失效
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OrderNonces {
+    pub is_initialized: bool,
+    pub wallet: Pubkey,
+    pub min_nonce: u64,
+    pub used: [u8; 32],
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl OrderNonces {
+    fn is_marked(&self, offset: u64) -> bool {
+        self.used[(offset / 8) as usize] & (1 << (offset % 8)) != 0
+    }
+
+    // 把窗口起点移到min_nonce，位图随之平移
+    fn advance(&mut self, min_nonce: u64) {
+        let shift = min_nonce - self.min_nonce;
+        let mut used = [0u8; 32];
+        for offset in 0..ORDER_NONCE_WINDOW {
+            let previous = offset.saturating_add(shift);
+            if previous < ORDER_NONCE_WINDOW && self.is_marked(previous) {
+                used[(offset / 8) as usize] |= 1 << (offset % 8);
+            }
+        }
+        self.used = used;
+        self.min_nonce = min_nonce;
+    }
+
+    // nonce是否仍可用于结算订单
+    pub fn is_usable(&self, nonce: u64) -> bool {
+        match nonce.checked_sub(self.min_nonce) {
+            Some(offset) => offset >= ORDER_NONCE_WINDOW || !self.is_marked(offset),
+            None => false,
+        }
+    }
+
+    // 结算订单时消耗nonce，已使用或已失效的nonce报错
+    pub fn consume(&mut self, nonce: u64) -> ProgramResult {
+        if !self.is_usable(nonce) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if nonce - self.min_nonce >= ORDER_NONCE_WINDOW {
+            self.advance(nonce - ORDER_NONCE_WINDOW + 1);
+        }
+        let offset = nonce - self.min_nonce;
+        self.used[(offset / 8) as usize] |= 1 << (offset % 8);
+        Ok(())
+    }
+
+    // 钱包作废所有小于min_nonce的订单，min_nonce只能增大
+    pub fn cancel_below(&mut self, min_nonce: u64) -> ProgramResult {
+        if min_nonce <= self.min_nonce {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.advance(min_nonce);
+        Ok(())
+    }
+}
+
+impl IsInitialized for OrderNonces {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for OrderNonces {}
+
+impl Pack for OrderNonces {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u64, [u8; 32], i64, i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.wallet.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.min_nonce.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(&self.used);
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let wallet = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let min_nonce = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let used = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            wallet,
+            min_nonce,
+            used,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义卖家链下签名的出售订单：在expires_at之前按总价price出售tier档位的quantity个席位，
+// 可以分多次成交，全部成交后nonce记录在卖家的订单nonce账户中防止重放；卖家调整价格只需重新签名，无需链上交易
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SignedOrder {
+    pub price: u64,
+    pub tier: u8,
+    pub quantity: u32,
+    pub expires_at: i64,
+    pub nonce: u64,
+}
+
+impl SignedOrder {
+    pub const MESSAGE_LEN: usize = SIGNED_ORDER_DOMAIN.len() + 32 + 32 + 8 + 1 + 4 + 8 + 8;
+
+    // 卖家签名的消息: 域前缀 + 程序ID + 模型 + price + tier + quantity + expires_at + nonce（整数均为小端）
+    pub fn message(&self, program_id: &Pubkey, model: &Pubkey) -> Vec<u8> {
+        let mut message = Vec::with_capacity(Self::MESSAGE_LEN);
+        message.extend_from_slice(SIGNED_ORDER_DOMAIN);
+        message.extend_from_slice(program_id.as_ref());
+        message.extend_from_slice(model.as_ref());
+        message.extend_from_slice(&self.price.to_le_bytes());
+        message.push(self.tier);
+        message.extend_from_slice(&self.quantity.to_le_bytes());
+        message.extend_from_slice(&self.expires_at.to_le_bytes());
+        message.extend_from_slice(&self.nonce.to_le_bytes());
+        message
+    }
+
+    // 检查订单在now时是否仍然有效
+    pub fn require_live(&self, now: i64) -> ProgramResult {
+        if now >= self.expires_at {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+}
+
+// 定义签名订单的成交进度，多次部分成交累计到filled，全部成交后订单nonce被消耗
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OrderFill {
+    pub is_initialized: bool,
+    pub seller: Pubkey,
+    pub model: Pubkey,
+    pub nonce: u64,
+    pub quantity: u32,
+    pub filled: u32,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl OrderFill {
+    // 成交fill_quantity个席位，返回本次应付的金额：按累计成交量向上取整分摊总价，
+    // 因此各次金额之和恰好等于订单总价
+    pub fn fill(&mut self, order: &SignedOrder, fill_quantity: u32) -> Result<u64, ProgramError> {
+        if order.quantity != self.quantity {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let filled = self.filled.checked_add(fill_quantity).ok_or(ProgramError::ArithmeticOverflow)?;
+        if fill_quantity == 0 || filled > self.quantity {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let quantity = self.quantity as u64;
+        let amount = proration::charge(order.price, quantity, filled as u64)
+            - proration::charge(order.price, quantity, self.filled as u64);
+        self.filled = filled;
+        Ok(amount)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.filled == self.quantity
+    }
+}
+
+impl IsInitialized for OrderFill {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for OrderFill {}
+
+impl Pack for OrderFill {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, u64, u32, u32, i64, i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.seller.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.nonce.to_le_bytes());
+        offset += 8;
+        output[offset..offset+4].copy_from_slice(&self.quantity.to_le_bytes());
+        offset += 4;
+        output[offset..offset+4].copy_from_slice(&self.filled.to_le_bytes());
+        offset += 4;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let seller = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let nonce = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let quantity = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let filled = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            seller,
+            model,
+            nonce,
+            quantity,
+            filled,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// BN254的基域和标量域模数（大端）
+const BN254_FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+const BN254_SCALAR_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+
+// 计算G1点的相反点(x, p - y)，无穷远点（全零）保持不变
+fn negate_g1(point: &[u8]) -> [u8; 64] {
+    let mut negated = [0u8; 64];
+    negated[..32].copy_from_slice(&point[..32]);
+    if point[32..].iter().all(|b| *b == 0) {
+        return negated;
+    }
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut diff = BN254_FIELD_MODULUS[i] as i16 - point[32 + i] as i16 - borrow;
+        borrow = (diff < 0) as i16;
+        if diff < 0 {
+            diff += 256;
+        }
+        negated[32 + i] = diff as u8;
+    }
+    negated
+}
+
+// 校验Groth16证明: e(-A, B)·e(alpha, beta)·e(vk_x, gamma)·e(C, delta) = 1，
+// 其中vk_x = IC0 + Σ input_i·IC_(i+1)；所有点和标量均为alt_bn128系统调用使用的大端编码
+fn verify_groth16(verifying_key: &[u8], proof: &[u8], public_inputs: &[[u8; 32]]) -> ProgramResult {
+    if proof.len() != GROTH16_PROOF_LEN
+        || ProofSystem::Groth16Bn254.public_input_count(verifying_key)? != public_inputs.len()
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let alpha = &verifying_key[..64];
+    let beta = &verifying_key[64..192];
+    let gamma = &verifying_key[192..320];
+    let delta = &verifying_key[320..448];
+    let ic = &verifying_key[GROTH16_VK_FIXED_LEN..];
+
+    let mut vk_x = ic[..64].to_vec();
+    for (i, input) in public_inputs.iter().enumerate() {
+        // 公开输入必须小于标量域模数，否则同一证明可以对应多组输入
+        if input[..] >= BN254_SCALAR_MODULUS[..] {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let term = alt_bn128_multiplication(&[&ic[(i + 1) * 64..(i + 2) * 64], &input[..]].concat())
+            .map_err(|_| ProgramError::InvalidArgument)?;
+        vk_x = alt_bn128_addition(&[&vk_x[..], &term[..]].concat()).map_err(|_| ProgramError::InvalidArgument)?;
+    }
+
+    let pairing_input = [
+        &negate_g1(&proof[..64])[..],
+        &proof[64..192],
+        alpha,
+        beta,
+        &vk_x[..],
+        gamma,
+        &proof[192..256],
+        delta,
+    ]
+    .concat();
+    let result = alt_bn128_pairing(&pairing_input).map_err(|_| ProgramError::InvalidArgument)?;
+    if result.len() != 32 || result[..31].iter().any(|b| *b != 0) || result[31] != 1 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+// 解析证明账户数据: 证明 + u8公开输入数 + 每个32字节的公开输入
+fn parse_proof_account(data: &[u8]) -> Result<(&[u8], Vec<[u8; 32]>), ProgramError> {
+    let proof = data.get(..GROTH16_PROOF_LEN).ok_or(ProgramError::InvalidAccountData)?;
+    let (input_count, mut rest) = unpack_u8(&data[GROTH16_PROOF_LEN..]).map_err(|_| ProgramError::InvalidAccountData)?;
+    if input_count as usize > MAX_PROOF_PUBLIC_INPUTS {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut public_inputs = Vec::with_capacity(input_count as usize);
+    for _ in 0..input_count {
+        let (input, next) = unpack_hash(rest).map_err(|_| ProgramError::InvalidAccountData)?;
+        public_inputs.push(input);
+        rest = next;
+    }
+    Ok((proof, public_inputs))
+}
+
+// 定义模型某种语言的本地化元数据，长描述存放在链下，由description_uri引用
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LocalizedMetadata {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub lang: String,
+    pub name: String,
+    pub description_uri: String,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl IsInitialized for LocalizedMetadata {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for LocalizedMetadata {}
+
+impl Pack for LocalizedMetadata {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, [u8; MAX_LANG_CODE_LEN], [u8; MAX_LOCALIZED_NAME_LEN],
+        [u8; MAX_LOCALIZED_URI_LEN], i64, i64
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        pack_fixed_str(&mut output[offset..offset+MAX_LANG_CODE_LEN], &self.lang);
+        offset += MAX_LANG_CODE_LEN;
+        pack_fixed_str(&mut output[offset..offset+MAX_LOCALIZED_NAME_LEN], &self.name);
+        offset += MAX_LOCALIZED_NAME_LEN;
+        pack_fixed_str(&mut output[offset..offset+MAX_LOCALIZED_URI_LEN], &self.description_uri);
+        offset += MAX_LOCALIZED_URI_LEN;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let lang = unpack_fixed_str(&input[offset..offset+MAX_LANG_CODE_LEN])?;
+        offset += MAX_LANG_CODE_LEN;
+        let name = unpack_fixed_str(&input[offset..offset+MAX_LOCALIZED_NAME_LEN])?;
+        offset += MAX_LOCALIZED_NAME_LEN;
+        let description_uri = unpack_fixed_str(&input[offset..offset+MAX_LOCALIZED_URI_LEN])?;
+        offset += MAX_LOCALIZED_URI_LEN;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            model,
+            lang,
+            name,
+            description_uri,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义评价，只有持有购买凭证的买家才能评价，每张凭证一次
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Review {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub reviewer: Pubkey,
+    pub receipt: Pubkey,
+    // 1到5分
+    pub rating: u8,
+    // 链下评价正文的哈希
+    pub content_hash: [u8; 32],
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl IsInitialized for Review {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Review {}
+
+impl Pack for Review {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, Pubkey, u8, [u8; 32], i64, i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.reviewer.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.receipt.as_ref());
+        offset += 32;
+        output[offset] = self.rating;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(&self.content_hash);
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let reviewer = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let receipt = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let rating = input[offset];
+        offset += 1;
+        let content_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            model,
+            reviewer,
+            receipt,
+            rating,
+            content_hash,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义卖家收益金库，托管待提取的销售收入，为冻结、分期释放和费用抵扣预留空间
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProceedsVault {
+    pub is_initialized: bool,
+    pub seller: Pubkey,
+    // 卖家当前可提取的金额，不包含金库账户的租金
+    pub balance: u64,
+    pub total_withdrawn: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 卖家的预扣设置：每笔卖家收入按withholding_bps预扣，托管在金库中且只能释放给withholding_destination，
+    // 用于税务或合规账户；全零表示不预扣
+    pub withholding_bps: u16,
+    pub withholding_destination: Pubkey,
+    // 待释放和累计的预扣金额，待释放的预扣款不计入balance
+    pub withheld_balance: u64,
+    pub total_withheld: u64,
+}
+
+impl ProceedsVault {
+    // 卖家收入amount中应预扣的金额，向下取整
+    pub fn withholding_for(&self, amount: u64) -> u64 {
+        fee_at_bps(amount, self.withholding_bps as u64)
+    }
+
+    // 修改预扣设置：比例和目标账户必须同时设置或同时清零，仍有待释放的预扣款时不能更换目标账户
+    pub fn set_withholding(&mut self, destination: Pubkey, withholding_bps: u16) -> ProgramResult {
+        if (destination == Pubkey::default()) != (withholding_bps == 0) || withholding_bps as u64 > BPS_DENOMINATOR {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.withheld_balance > 0 && destination != self.withholding_destination {
+            msg!("Release {} withheld lamports before changing the destination", self.withheld_balance);
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.withholding_destination = destination;
+        self.withholding_bps = withholding_bps;
+        Ok(())
+    }
+
+    // 记入一笔预扣款
+    pub fn record_withholding(&mut self, amount: u64) -> ProgramResult {
+        self.withheld_balance = self.withheld_balance
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        self.total_withheld = self.total_withheld
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+impl IsInitialized for ProceedsVault {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for ProceedsVault {}
+
+impl Pack for ProceedsVault {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u64, u64, i64, i64, u16, Pubkey, u64, u64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.seller.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.balance.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_withdrawn.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+2].copy_from_slice(&self.withholding_bps.to_le_bytes());
+        offset += 2;
+        output[offset..offset+32].copy_from_slice(self.withholding_destination.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.withheld_balance.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_withheld.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let seller = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let balance = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_withdrawn = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let withholding_bps = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        offset += 2;
+        let withholding_destination = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let withheld_balance = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_withheld = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            seller,
+            balance,
+            total_withdrawn,
+            created_at,
+            updated_at,
+            withholding_bps,
+            withholding_destination,
+            withheld_balance,
+            total_withheld,
+        })
+    }
+}
+
+// 定义销售账本中的一条成交记录
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SaleRecord {
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+    pub payment_mint: Pubkey,
+}
+
+impl Sealed for SaleRecord {}
+
+impl Pack for SaleRecord {
+    const LEN: usize = packed_len!(Pubkey, u64, u64, Pubkey);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let mut offset = 0;
+        output[offset..offset+32].copy_from_slice(self.buyer.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.amount.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.slot.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(self.payment_mint.as_ref());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let mut offset = 0;
+        let buyer = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let amount = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let payment_mint = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        Ok(Self {
+            buyer,
+            amount,
+            slot,
+            payment_mint,
+        })
+    }
+}
+
+// 定义模型销售账本的一页，只追加不修改，页序号由模型的成交记录数量推导
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SalesLedgerPage {
+    pub is_initialized: bool,
+    pub model: Pubkey,
+    pub page_index: u32,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub entries: Vec<SaleRecord>,
+}
+
+impl IsInitialized for SalesLedgerPage {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for SalesLedgerPage {}
+
+impl Pack for SalesLedgerPage {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u32, i64, i64, u16, [SaleRecord; SALES_LEDGER_PAGE_CAPACITY]
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+4].copy_from_slice(&self.page_index.to_le_bytes());
+        offset += 4;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+2].copy_from_slice(&(self.entries.len() as u16).to_le_bytes());
+        offset += 2;
+        for entry in &self.entries {
+            entry.pack_into_slice(&mut output[offset..offset+SaleRecord::LEN]);
+            offset += SaleRecord::LEN;
+        }
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let page_index = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+        offset += 4;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let entry_count = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap()) as usize;
+        if entry_count > SALES_LEDGER_PAGE_CAPACITY {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset += 2;
+        let mut entries = Vec::with_capacity(entry_count);
+        for _ in 0..entry_count {
+            entries.push(SaleRecord::unpack_from_slice(&input[offset..offset+SaleRecord::LEN])?);
+            offset += SaleRecord::LEN;
+        }
+        Ok(Self {
+            is_initialized,
+            model,
+            page_index,
+            created_at,
+            updated_at,
+            entries,
+        })
+    }
+}
+
+// 定义争议中的一条证据，只记录链下证据（交付日志、产物哈希等）的内容哈希
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DisputeEvidence {
+    pub submitter: Pubkey,
+    pub content_hash: [u8; 32],
+    pub submitted_at: i64,
+}
+
+impl Sealed for DisputeEvidence {}
+
+impl Pack for DisputeEvidence {
+    const LEN: usize = packed_len!(Pubkey, [u8; 32], i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let mut offset = 0;
+        output[offset..offset+32].copy_from_slice(self.submitter.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(&self.content_hash);
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.submitted_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let mut offset = 0;
+        let submitter = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let content_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let submitted_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            submitter,
+            content_hash,
+            submitted_at,
+        })
+    }
+}
+
+// 定义买家就一笔购买发起的争议，买卖双方提交的证据只能追加不能修改，供仲裁方裁决时查阅
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dispute {
+    pub is_initialized: bool,
+    pub receipt: Pubkey,
+    pub model: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub evidence: Vec<DisputeEvidence>,
+}
+
+impl Dispute {
+    // 追加一条证据，提交者必须是争议的买方或卖方，且未超过其证据条数上限
+    pub fn add_evidence(&mut self, submitter: &Pubkey, content_hash: [u8; 32], now: i64) -> ProgramResult {
+        if *submitter != self.buyer && *submitter != self.seller {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let submitted = self.evidence.iter().filter(|entry| entry.submitter == *submitter).count();
+        if submitted >= MAX_EVIDENCE_PER_PARTY {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        self.evidence.push(DisputeEvidence {
+            submitter: *submitter,
+            content_hash,
+            submitted_at: now,
+        });
+        self.updated_at = now;
+        Ok(())
+    }
+}
+
+impl IsInitialized for Dispute {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Dispute {}
+
+impl Pack for Dispute {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, Pubkey, Pubkey, i64, i64, u8,
+        [DisputeEvidence; MAX_EVIDENCE_PER_PARTY * 2]
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.receipt.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.model.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.buyer.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.seller.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset] = self.evidence.len() as u8;
+        offset += 1;
+        for entry in &self.evidence {
+            entry.pack_into_slice(&mut output[offset..offset+DisputeEvidence::LEN]);
+            offset += DisputeEvidence::LEN;
+        }
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let receipt = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let buyer = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let seller = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let evidence_count = input[offset] as usize;
+        if evidence_count > MAX_EVIDENCE_PER_PARTY * 2 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        offset += 1;
+        let mut evidence = Vec::with_capacity(evidence_count);
+        for _ in 0..evidence_count {
+            evidence.push(DisputeEvidence::unpack_from_slice(&input[offset..offset+DisputeEvidence::LEN])?);
+            offset += DisputeEvidence::LEN;
+        }
+        Ok(Self {
+            is_initialized,
+            receipt,
+            model,
+            buyer,
+            seller,
+            created_at,
+            updated_at,
+            evidence,
+        })
+    }
+}
+
+// 定义紧急资金恢复提案，守护者多签通过后需等待时间锁才能执行
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecoveryRequest {
+    pub is_initialized: bool,
+    pub escrow: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    // 链下证据（错误分析、交易记录等）的哈希
+    pub evidence_hash: [u8; 32],
+    // 支付提案账户租金的账户，关闭提案时租金退回该账户
+    pub payer: Pubkey,
+    pub approvals: u8,
+    pub executable_at: i64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl IsInitialized for RecoveryRequest {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for RecoveryRequest {}
+
+impl Pack for RecoveryRequest {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, u64, [u8; 32], Pubkey, u8, i64, i64, i64
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.escrow.as_ref());
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.destination.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.amount.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(&self.evidence_hash);
+        offset += 32;
+        output[offset..offset+32].copy_from_slice(self.payer.as_ref());
+        offset += 32;
+        output[offset] = self.approvals;
+        offset += 1;
+        output[offset..offset+8].copy_from_slice(&self.executable_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let escrow = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let destination = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let amount = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let evidence_hash = input[offset..offset+32].try_into().unwrap();
+        offset += 32;
+        let payer = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let approvals = input[offset];
+        offset += 1;
+        let executable_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            escrow,
+            destination,
+            amount,
+            evidence_hash,
+            payer,
+            approvals,
+            executable_at,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义市场金库，提款必须先排队，等待时间锁到期后才能执行
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Treasury {
+    pub is_initialized: bool,
+    // 提款排队后需要等待的秒数
+    pub withdrawal_delay: i64,
+    // 当前排队中的提款，pending_amount为0表示没有排队的提款
+    pub pending_amount: u64,
+    pub pending_destination: Pubkey,
+    pub pending_executable_at: i64,
+    pub total_withdrawn: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+    // 累计收取的市场手续费
+    pub total_fees: u64,
+    // 忠诚度积分规则：每支付1 lamport累计accrual_bps/10000个积分，每个积分在结账时抵扣redemption_rate lamports；
+    // 抵扣由市场手续费承担，因此不能超过该笔成交的手续费
+    pub loyalty_accrual_bps: u16,
+    pub loyalty_redemption_rate: u64,
+    // 累计因积分抵扣少收的手续费
+    pub total_loyalty_discounts: u64,
+    // 首次销售和转售的手续费率（基点）；primary_fee_set为false表示管理员尚未设置首次销售费率，
+    // 此时沿用按保证金的默认费率，设置后0即表示首次销售免手续费；转售费率为0表示不收费
+    pub primary_fee_set: bool,
+    pub primary_fee_bps: u16,
+    pub secondary_fee_bps: u16,
+}
+
+impl Treasury {
+    // 首次销售的手续费率，设置了首次销售费率时质押卖家的费率仍不高于STAKED_FEE_BPS
+    pub fn primary_fee_rate(&self, staked: u64) -> u64 {
+        match self.primary_fee_bps as u64 {
+            _ if !self.primary_fee_set => marketplace_fee_bps(staked),
+            fee_bps if staked >= STAKED_SELLER_MIN_BOND => fee_bps.min(STAKED_FEE_BPS),
+            fee_bps => fee_bps,
+        }
+    }
+
+    // 支付amount获得的积分，向下取整
+    pub fn loyalty_points_for(&self, amount: u64) -> u64 {
+        (amount as u128 * self.loyalty_accrual_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    // 兑换points个积分抵扣的金额；未开放兑换或抵扣超过手续费时失败
+    pub fn loyalty_discount(&self, points: u64, fee: u64) -> Result<u64, ProgramError> {
+        if points == 0 {
+            return Ok(0);
+        }
+        if self.loyalty_redemption_rate == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let discount = points
+            .checked_mul(self.loyalty_redemption_rate)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        if discount > fee {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(discount)
+    }
+}
+
+impl IsInitialized for Treasury {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Treasury {}
+
+impl Pack for Treasury {
+    const LEN: usize = packed_len!(
+        [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, i64, u64, Pubkey, i64, u64, i64, i64, u64, u16, u64, u64, bool, u16, u16
+    );
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+8].copy_from_slice(&self.withdrawal_delay.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.pending_amount.to_le_bytes());
+        offset += 8;
+        output[offset..offset+32].copy_from_slice(self.pending_destination.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.pending_executable_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_withdrawn.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_fees.to_le_bytes());
+        offset += 8;
+        output[offset..offset+2].copy_from_slice(&self.loyalty_accrual_bps.to_le_bytes());
+        offset += 2;
+        output[offset..offset+8].copy_from_slice(&self.loyalty_redemption_rate.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.total_loyalty_discounts.to_le_bytes());
+        offset += 8;
+        output[offset] = self.primary_fee_set as u8;
+        offset += 1;
+        output[offset..offset+2].copy_from_slice(&self.primary_fee_bps.to_le_bytes());
+        offset += 2;
+        output[offset..offset+2].copy_from_slice(&self.secondary_fee_bps.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let withdrawal_delay = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let pending_amount = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let pending_destination = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let pending_executable_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_withdrawn = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_fees = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let loyalty_accrual_bps = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        offset += 2;
+        let loyalty_redemption_rate = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let total_loyalty_discounts = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let primary_fee_set = input[offset] != 0;
+        offset += 1;
+        let primary_fee_bps = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        offset += 2;
+        let secondary_fee_bps = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            withdrawal_delay,
+            pending_amount,
+            pending_destination,
+            pending_executable_at,
+            total_withdrawn,
+            created_at,
+            updated_at,
+            total_fees,
+            loyalty_accrual_bps,
+            loyalty_redemption_rate,
+            total_loyalty_discounts,
+            primary_fee_set,
+            primary_fee_bps,
+            secondary_fee_bps,
+        })
+    }
+}
+
+// 定义卖家保证金，质押金额决定卖家成交时的手续费率
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SellerBond {
+    pub is_initialized: bool,
+    pub seller: Pubkey,
+    // 当前质押的金额，不包含保证金账户的租金
+    pub staked: u64,
+    // 创建和最近更新的unix时间戳，来自Clock系统变量
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl IsInitialized for SellerBond {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for SellerBond {}
+
+impl Pack for SellerBond {
+    const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u64, i64, i64);
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        output[offset] = self.is_initialized as u8;
+        offset += 1;
+        output[offset..offset+32].copy_from_slice(self.seller.as_ref());
+        offset += 32;
+        output[offset..offset+8].copy_from_slice(&self.staked.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+        offset += 8;
+        output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        check_discriminator::<Self>(input)?;
+        let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+        let is_initialized = input[offset] != 0;
+        offset += 1;
+        let seller = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+        offset += 32;
+        let staked = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        offset += 8;
+        let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+        Ok(Self {
+            is_initialized,
+            seller,
+            staked,
+            created_at,
+            updated_at,
+        })
+    }
+}
+
+// 定义程序支持的指令，模型文件等大块数据直接借用指令数据，不在BPF堆上复制
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarketplaceInstruction<'a> {
+    // 创建新的AIModel
+    // 由程序创建AIModel账户，租金由所有者支付
+    // 基础价格和各档位价格需要在配置的上架价格范围内
+    // 账户: [签名, 可写] 新的AIModel账户, [签名, 可写] 所有者账户, [] 系统程序, [] 配置PDA,
+    //       可选 [] 指令sysvar：传入时前一条指令必须是所有者对sha256(model_file)的Ed25519签名校验
+    CreateAIModel {
+        name: String,
+        description: String,
+        price: u64,
+        pricing_mode: PricingMode,
+        license_tiers: Vec<LicenseTier>,
+        per_seat: bool,
+        volume_discounts: Vec<VolumeDiscount>,
+        vault_proceeds: bool,
+        model_file: &'a [u8],
+        model_format: ModelFormat,
+        license: String,
+    },
+    // 购买AIModel的指定许可证档位和席位数，amount仅在随意付模式下生效，
+    // purchase_index为买家此前购买该模型的次数
+    // 账户: [可写] AIModel账户, [签名, 可写] 买家, [可写] 卖家,
+    //       [可写] 购买凭证PDA, [可写] 卖家统计PDA, [] 系统程序,
+    //       [可写] 卖家收益金库PDA（模型使用金库结算时才会写入）,
+    //       [可写] 当前销售账本分页PDA, [可写] 市场金库PDA, [] 卖家保证金PDA, [可写] 买家统计PDA,
+    //       [可写] 买家当前凭证索引分页PDA, purchase_index大于0时还需 [] 上一次购买的凭证PDA,
+    //       购买drop模型时还需 [可写] 买家的购买节流PDA, 模型要求真人验证时还需 [] 买家的Civic网关令牌，
+    //       有慈善分成时还需 [可写] 慈善机构PDA, 需要成交备忘时还需 [] SPL Memo程序，这些尾部账户顺序不限
+    // 购买已弃用的模型时acknowledge_deprecation必须为true，reference为写入购买凭证并随Purchased事件输出的对账参考号
+    PurchaseAIModel {
+        amount: u64,
+        tier_index: u8,
+        quantity: u32,
+        purchase_index: u32,
+        acknowledge_deprecation: bool,
+        reference: [u8; 32],
+    },
+    // 校验钱包是否持有模型的购买凭证，校验失败时指令返回错误
+    // 账户: [] 购买凭证账户, [] AIModel账户, [] 钱包账户
+    VerifyLicense,
+    // 从收益金库向卖家提取指定金额
+    // 账户: [签名, 可写] 卖家, [可写] 卖家收益金库PDA
+    WithdrawProceeds {
+        amount: u64,
+    },
+    // 创建市场配置，只有程序的升级权限账户可以执行
+    // 账户: [签名, 可写] 管理员, [可写] 配置PDA, [] 程序数据账户, [] 系统程序
+    InitializeConfig,
+    // 在日志和返回数据中输出程序版本与功能位，不需要任何账户
+    GetVersion,
+    // 管理员设置功能开关位
+    // 账户: [签名] 超级管理员, [可写] 配置PDA, 可选 [] 角色PDA
+    SetFeatureFlags {
+        feature_flags: u64,
+    },
+    // 管理员设置紧急资金恢复的守护者、所需签名数和时间锁
+    // 账户: [签名] 超级管理员, [可写] 配置PDA, 可选 [] 角色PDA
+    SetRecoveryGuardians {
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+        recovery_delay: i64,
+    },
+    // 守护者多签提议从托管账户中恢复因程序错误而无法提取的资金，托管账户可以是收益金库、挂单、出价、
+    // 出价的托管PDA、推理请求、租用流、委托或额度PDA，只能恢复超出免租金额和账面余额的部分
+    // 账户: [签名, 可写] 付款账户, [可写] 恢复提案PDA, [] 托管账户, [] 配置PDA,
+    //       [] 系统程序, 其余为签名的守护者账户
+    ProposeRecovery {
+        amount: u64,
+        destination: Pubkey,
+        evidence_hash: [u8; 32],
+    },
+    // 时间锁到期后执行资金恢复，任何人都可以调用
+    // 账户: [可写] 恢复提案PDA, [可写] 托管账户, [可写] 接收账户, [可写] 提案付款账户
+    ExecuteRecovery,
+    // 任一守护者可以在执行前取消资金恢复提案
+    // 账户: [签名] 守护者, [可写] 恢复提案PDA, [可写] 提案付款账户, [] 配置PDA
+    CancelRecovery,
+    // 管理员创建市场金库并设置提款时间锁，时间锁不少于MIN_WITHDRAWAL_DELAY_SECONDS
+    // 账户: [签名, 可写] 财务, [] 配置PDA, [可写] 金库PDA, [] 系统程序, 可选 [] 角色PDA
+    InitializeTreasury {
+        withdrawal_delay: i64,
+    },
+    // 管理员发起金库提款，时间锁到期前不能执行，执行前可以通过CancelWithdrawal取消
+    // 账户: [签名] 财务, [] 配置PDA, [可写] 金库PDA, 可选 [] 角色PDA
+    QueueWithdrawal {
+        amount: u64,
+        destination: Pubkey,
+    },
+    // 时间锁到期后执行排队中的金库提款，任何人都可以调用
+    // 账户: [可写] 金库PDA, [可写] 接收账户
+    ExecuteWithdrawal,
+    // 卖家向保证金PDA质押lamports
+    // 账户: [签名, 可写] 卖家, [可写] 保证金PDA, [] 系统程序
+    DepositBond {
+        amount: u64,
+    },
+    // 卖家取回质押的保证金
+    // 账户: [签名, 可写] 卖家, [可写] 保证金PDA
+    WithdrawBond {
+        amount: u64,
+    },
+    // 卖家以指定价格挂单，创建挂单PDA；draft为true时以草稿创建，之后通过SetListingStatus发布
+    // 账户: [签名, 可写] 卖家, [] AIModel账户, [可写] 挂单PDA, [] 系统程序
+    Sell {
+        price: u64,
+        tier_index: u8,
+        quantity: u32,
+        draft: bool,
+    },
+    // 买家以指定价格出价，创建出价PDA和它的托管PDA并将出价金额转入托管
+    // 账户: [签名, 可写] 买家, [] AIModel账户, [可写] 出价PDA, [可写] 托管PDA, [] 系统程序
+    Buy {
+        price: u64,
+        tier_index: u8,
+        quantity: u32,
+    },
+    // 关闭挂单或出价并退回租金；关闭出价时托管PDA随之关闭，退回其中剩余的金额
+    // 账户: [签名, 可写] 挂单的卖家或出价的买家, [可写] 挂单PDA或出价PDA, 关闭出价时还需 [可写] 托管PDA
+    CancelTrade,
+    // 撮合价格一致的在售挂单和等待成交的出价，用出价托管的金额完成结算，任何人都可以调用；
+    // 成交后出价记录购买凭证，挂单保持在售
+    // 账户: [签名, 可写] 付款账户, [可写] 买家, [可写] 出价PDA, [可写] 托管PDA, [可写] 挂单PDA,
+    //       其余与PurchaseAIModel相同（不含买家）: [可写] AIModel账户, [可写] 卖家, [可写] 购买凭证账户,
+    //       [可写] 卖家统计PDA, [] 系统程序, [可写] 收益金库PDA, [可写] 销售账本PDA,
+    //       [可写] 市场金库PDA, [] 卖家保证金PDA, [可写] 买家统计PDA, [可写] 买家当前凭证索引分页PDA,
+    //       purchase_index大于0时还需 [] 上一次购买的凭证PDA, 撮合drop模型时还需 [可写] 买家的购买节流PDA（由付款账户出资创建）,
+    //       模型要求真人验证时还需 [] 买家的Civic网关令牌，这些尾部账户顺序不限
+    ExecuteSale {
+        purchase_index: u32,
+    },
+    // 模型所有者将模型关联到已有NFT的Token Metadata账户，所有者必须是已验证的创作者
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, [] Token Metadata账户, 可选 [] 组织PDA
+    AttachTokenMetadata,
+    // 持有购买凭证的买家发表评价，计入买家的已验证评价数和模型所有者的评分统计
+    // 账户: [签名, 可写] 买家, [] 购买凭证账户, [可写] 评价PDA, [可写] 买家统计PDA, [] 系统程序,
+    //       [] AIModel账户, [可写] 模型所有者的卖家统计PDA
+    SubmitReview {
+        rating: u8,
+        content_hash: [u8; 32],
+    },
+    // 管理员创建指定种类徽章的不可转让Token-2022 mint
+    // 账户: [签名, 可写] 超级管理员, [] 配置PDA, [可写] 徽章mint PDA, [] Token-2022程序, [] 系统程序, 可选 [] 角色PDA
+    InitializeBadgeMint {
+        kind: u8,
+    },
+    // 达到里程碑的钱包领取一枚不可转让的徽章代币，每个钱包每种徽章只能领取一次
+    // 账户: [签名, 可写] 钱包, [] 买家统计PDA, [可写] 徽章领取记录PDA, [可写] 徽章mint PDA,
+    //       [可写] 钱包的Token-2022关联代币账户, [] Token-2022程序, [] 关联代币账户程序, [] 系统程序
+    ClaimBadge {
+        kind: u8,
+    },
+    // 买家就一笔购买发起争议，evidence_hash为首条证据
+    // 账户: [签名, 可写] 买家, [] 购买凭证PDA, [] AIModel账户, [可写] 争议PDA, [] 系统程序
+    OpenDispute {
+        evidence_hash: [u8; 32],
+    },
+    // 争议的买方或卖方追加一条证据的内容哈希
+    // 账户: [签名] 买家或卖家, [可写] 争议PDA
+    AttachDisputeEvidence {
+        evidence_hash: [u8; 32],
+    },
+    // 模型更新权限设置链下产物的存储位置、引用和内容哈希，需要验证的产物在确认前不能购买
+    // 账户: [签名] 模型更新权限, [可写] AIModel账户,
+    //       可选 [] 指令sysvar：传入时前一条指令必须是模型所有者对artifact_hash的Ed25519签名校验, 可选 [] 组织PDA
+    SetModelArtifact {
+        storage_class: u8,
+        artifact_uri: String,
+        artifact_hash: [u8; 32],
+        artifact_size_bytes: u64,
+    },
+    // 管理员设置产物验证人
+    // 账户: [签名] 超级管理员, [可写] 配置PDA, 可选 [] 角色PDA
+    SetArtifactAttestor {
+        attestor: Pubkey,
+    },
+    // 验证人确认产物存在且内容哈希与模型声明的一致
+    // 账户: [签名] 验证人, [] 配置PDA, [可写] AIModel账户, 可选 [] 角色PDA
+    AttestArtifact {
+        artifact_hash: [u8; 32],
+    },
+    // 模型更新权限发布新版本，版本号必须严格大于最近发布的版本
+    // 账户: [签名, 可写] 模型更新权限, [可写] AIModel账户, [可写] 新版本PDA, [] 系统程序, 可选 [] 组织PDA
+    PublishModelVersion {
+        version: SemVer,
+        artifact_hash: [u8; 32],
+    },
+    // 模型更新权限为最近发布的版本添加更新日志，并链接到上一条更新日志
+    // 账户: [签名, 可写] 模型更新权限, [可写] AIModel账户, [] 最近发布的版本PDA, [可写] 更新日志PDA, [] 系统程序, 可选 [] 组织PDA
+    AddChangelogEntry {
+        content_hash: [u8; 32],
+        uri: String,
+        summary: String,
+    },
+    // 模型所有者弃用模型，可以同时指定后继模型
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 后继AIModel账户, 可选 [] 组织PDA
+    DeprecateModel,
+    // 关注卖家，首次被关注时创建卖家统计PDA
+    // 账户: [签名, 可写] 关注者, [] 卖家, [可写] 关注关系PDA, [可写] 卖家统计PDA, [] 系统程序
+    FollowSeller,
+    // 取消关注卖家，关系PDA的租金退回关注者
+    // 账户: [签名, 可写] 关注者, [] 卖家, [可写] 关注关系PDA, [可写] 卖家统计PDA
+    UnfollowSeller,
+    // 收藏模型
+    // 账户: [签名, 可写] 钱包, [可写] AIModel账户, [可写] 收藏关系PDA, [] 系统程序
+    FavoriteModel,
+    // 取消收藏模型，关系PDA的租金退回钱包
+    // 账户: [签名, 可写] 钱包, [可写] AIModel账户, [可写] 收藏关系PDA
+    UnfavoriteModel,
+    // 模型所有者修改基础价格（随意付模式下为底价），并发出PriceChanged事件；新价格需要在配置的上架价格范围内
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, [] 配置PDA, 可选 [] 组织PDA
+    UpdatePrice {
+        price: u64,
+    },
+    // 管理员设置精选列表的标签和内容，列表不存在时创建；剩余账户按展示顺序传入的AIModel账户即为新的列表内容
+    // 账户: [签名, 可写] 版主, [] 配置PDA, [可写] 精选列表PDA, [] 系统程序, 可选 [] 角色PDA, [] AIModel账户...
+    SetFeaturedList {
+        list_id: u32,
+        label: String,
+    },
+    // 管理员在分类注册表中注册分类，注册表不存在时创建
+    // 账户: [签名, 可写] 版主, [] 配置PDA, [可写] 分类注册表PDA, [] 系统程序, 可选 [] 角色PDA
+    RegisterCategory {
+        id: u16,
+        parent: u16,
+        label: String,
+    },
+    // 模型更新权限设置模型分类，category_id为0时清除分类
+    // 账户: [签名] 模型更新权限, [可写] AIModel账户, [] 分类注册表PDA, 可选 [] 组织PDA
+    SetModelCategory {
+        category_id: u16,
+    },
+    // 模型更新权限添加或更新某种语言的本地化名称和描述URI
+    // 账户: [签名, 可写] 模型更新权限, [] AIModel账户, [可写] 本地化元数据PDA, [] 系统程序, 可选 [] 组织PDA
+    SetLocalizedMetadata {
+        lang: String,
+        name: String,
+        description_uri: String,
+    },
+    // 模型更新权限设置链下元数据JSON的URI和内容哈希
+    // 账户: [签名] 模型更新权限, [可写] AIModel账户, 可选 [] 组织PDA
+    SetMetadataUri {
+        metadata_uri: String,
+        metadata_hash: [u8; 32],
+    },
+    // 设置模型的更新权限，模型所有者或当前更新权限均可调用；设为组织PDA后由组织的编辑和管理员行使
+    // 账户: [签名] 模型所有者或当前更新权限, [可写] AIModel账户
+    SetUpdateAuthority {
+        update_authority: Pubkey,
+    },
+    // 模型所有者将收款和定价权转给新的所有者，更新权限保持不变
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    SetModelOwner {
+        new_owner: Pubkey,
+    },
+    // 模型更新权限修改列出的创作者，除非由创作者本人签名，否则需要创作者再调用VerifyCreator
+    // 账户: [签名] 模型更新权限, [可写] AIModel账户, 可选 [] 组织PDA
+    SetModelCreator {
+        creator: Pubkey,
+    },
+    // 列出的创作者签名确认该上架，将creator_verified置为true
+    // 账户: [签名] 创作者, [可写] AIModel账户
+    VerifyCreator,
+    // 管理员设置审核预言机
+    // 账户: [签名] 超级管理员, [可写] 配置PDA, 可选 [] 角色PDA
+    SetListingOracle {
+        oracle: Pubkey,
+    },
+    // 模型所有者为上架开启审核，开启后不能关闭，审核通过前不能购买
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    RequireListingApproval,
+    // 审核预言机确认已扫描当前产物并通过上架，记录扫描报告哈希；artifact_hash必须与模型当前产物一致
+    // 账户: [签名] 审核预言机, [] 配置PDA, [可写] AIModel账户, 可选 [] 角色PDA
+    ApproveListing {
+        artifact_hash: [u8; 32],
+        attestation_hash: [u8; 32],
+    },
+    // 管理员注册或更新溯源证明验证器
+    // 账户: [签名, 可写] 超级管理员, [] 配置PDA, [可写] 验证器PDA, [] 系统程序, 可选 [] 角色PDA
+    RegisterProvenanceVerifier {
+        verifier_id: u32,
+        proof_system: u8,
+        claim: String,
+        verifying_key: Vec<u8>,
+    },
+    // 模型更新权限提交证明账户，通过验证器校验后写入模型的溯源证明
+    // 账户: [签名, 可写] 模型更新权限, [] AIModel账户, [] 验证器PDA, [] 证明账户, [可写] 溯源证明PDA, [] 系统程序,
+    //       可选 [] 组织PDA
+    VerifyProvenance,
+    // 工作节点提交用某个模型版本处理输入得到的输出哈希
+    // 账户: [签名, 可写] 工作节点, [] AIModel账户, [] 模型版本PDA, [可写] 推理结果承诺PDA, [] 系统程序
+    CommitInferenceResult {
+        input_hash: [u8; 32],
+        output_hash: [u8; 32],
+    },
+    // 请求者对模型发起付费推理请求，max_price托管在请求PDA中
+    // 账户: [签名, 可写] 请求者, [] AIModel账户, [可写] 推理请求PDA, [] 系统程序
+    // challenge_window为结果提交后请求者可以挑战的秒数
+    EnqueueInferenceRequest {
+        request_id: u64,
+        prompt_hash: [u8; 32],
+        max_price: u64,
+        deadline: i64,
+        challenge_window: i64,
+    },
+    // 锁定了请求的工作节点凭结果承诺提交结果和报价price（不超过max_price），output_hash必须与承诺一致；
+    // 挑战期结束后才结算
+    // 账户: [签名] 工作节点, [] 请求者, [可写] 推理请求PDA, [] 推理结果承诺PDA,
+    //       [] AIModel账户, [可写] 工作节点PDA
+    SubmitResult {
+        output_hash: [u8; 32],
+        proof_uri: String,
+        price: u64,
+    },
+    // 请求者在截止时间后取消未完成的请求，关闭请求PDA并取回托管金额和租金
+    // 账户: [签名, 可写] 请求者, [可写] 推理请求PDA
+    CancelInferenceRequest,
+    // 运营者注册工作节点或更新其公开的能力
+    // 账户: [签名, 可写] 运营者, [可写] 工作节点PDA, [] 系统程序
+    RegisterWorker {
+        hardware_class: u8,
+        supported_formats: u8,
+        price_per_unit: u64,
+        endpoint_hash: [u8; 32],
+    },
+    // 运营者向工作节点PDA追加质押
+    // 账户: [签名, 可写] 运营者, [可写] 工作节点PDA, [] 系统程序
+    StakeWorker {
+        amount: u64,
+    },
+    // 运营者在最近一次完成请求满WORKER_UNSTAKE_DELAY后取回质押
+    // 账户: [签名, 可写] 运营者, [可写] 工作节点PDA
+    UnstakeWorker {
+        amount: u64,
+    },
+    // 管理员设置推理仲裁者
+    // 账户: [签名] 超级管理员, [可写] 配置PDA, 可选 [] 角色PDA
+    SetInferenceArbiter {
+        arbiter: Pubkey,
+    },
+    // 罚没已提交结果的工作节点质押并转给请求者（尚未结算时同时退回托管金额）：由仲裁者签名裁定，或由任何人提交仲裁者对同一输入复现的、
+    // 输出哈希不一致的结果承诺
+    // 账户: [签名] 仲裁者或任意提交者, [] 配置PDA, [可写] 工作节点PDA, [可写] 推理请求PDA, [可写] 请求者,
+    //       [] 工作节点的结果承诺PDA, 可选 [] 仲裁者的结果承诺PDA
+    SlashWorker,
+    // 已注册且达到最低质押的工作节点锁定推理请求，锁定期内其他工作节点不能锁定或完成该请求
+    // 账户: [签名] 工作节点, [可写] 推理请求PDA, [] 工作节点PDA
+    ClaimJob,
+    // 任何人都可以调用的清理指令，释放已到期的锁定
+    // 账户: [可写] 推理请求PDA
+    ReassignExpiredClaim,
+    // 请求者在挑战期内对提交的结果提出异议，由仲裁者通过UpholdResult或SlashWorker裁定
+    // 账户: [签名] 请求者, [可写] 推理请求PDA
+    ChallengeResult,
+    // 任何人都可以调用的清理指令，挑战期结束且未被挑战的结果按报价结算给工作节点，余额退回请求者
+    // 账户: [可写] 工作节点, [可写] 请求者, [可写] 推理请求PDA
+    SettleInferenceRequest,
+    // 仲裁者驳回挑战，按报价结算给工作节点
+    // 账户: [签名] 仲裁者, [] 配置PDA, [可写] 工作节点, [可写] 请求者, [可写] 推理请求PDA, 可选 [] 角色PDA
+    UpholdResult,
+    // 模型所有者设置流式租用的每slot费率，0表示关闭流式租用；已开启的租用流不受影响
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    SetRentalRate {
+        rate_per_slot: u64,
+    },
+    // 租用者预存押金开启流式租用，押金至少覆盖一个slot
+    // 账户: [签名, 可写] 租用者, [] AIModel账户, [可写] 租用流PDA, [] 系统程序
+    OpenRentalStream {
+        deposit: u64,
+    },
+    // 所有者提取截至当前slot已流出的租金
+    // 账户: [签名, 可写] 所有者, [可写] 租用流PDA
+    WithdrawRentalStream,
+    // 所有者或租用者随时关闭租用流：已流出的租金付给所有者，未使用的押金和PDA租金退回租用者
+    // 账户: [签名] 所有者或租用者, [可写] 所有者, [可写] 租用者, [可写] 租用流PDA
+    CloseRentalStream,
+    // 买家向卖家发起委托开发，按里程碑金额一次性托管全部资金
+    // 账户: [签名, 可写] 买家, [可写] 委托PDA, [] 系统程序
+    FundCommission {
+        commission_id: u64,
+        seller: Pubkey,
+        milestone_amounts: Vec<u64>,
+    },
+    // 卖家提交里程碑的交付物哈希，买家确认前可以重新提交
+    // 账户: [签名] 卖家, [可写] 委托PDA
+    SubmitMilestone {
+        index: u8,
+        deliverable_hash: [u8; 32],
+    },
+    // 买家确认已交付的里程碑
+    // 账户: [签名] 买家, [可写] 委托PDA
+    ApproveMilestone {
+        index: u8,
+    },
+    // 卖家领取已确认里程碑的托管金额
+    // 账户: [签名, 可写] 卖家, [可写] 委托PDA
+    ClaimMilestone {
+        index: u8,
+    },
+    // 买家或卖家对已交付的里程碑发起争议，由委托仲裁者裁定
+    // 账户: [签名] 买家或卖家, [可写] 委托PDA
+    DisputeMilestone {
+        index: u8,
+    },
+    // 管理员设置委托仲裁者
+    // 账户: [签名] 超级管理员, [可写] 配置PDA, 可选 [] 角色PDA
+    SetCommissionArbiter {
+        arbiter: Pubkey,
+    },
+    // 仲裁者裁定有争议的里程碑：release为true时视为确认，由卖家领取；否则金额退回买家
+    // 账户: [签名] 仲裁者, [] 配置PDA, [可写] 买家, [可写] 委托PDA, 可选 [] 角色PDA
+    ResolveMilestoneDispute {
+        index: u8,
+        release: bool,
+    },
+    // 所有里程碑都已领取或退款后关闭委托，租金退回买家
+    // 账户: [签名, 可写] 买家, [可写] 委托PDA
+    CloseCommission,
+    // 模型所有者定义订阅方案的计费周期、续订宽限期和档位，方案不存在时创建；档位只能追加或修改，不能删除
+    // 账户: [签名, 可写] 模型所有者或所有者组织的管理员, [] 配置PDA, [] AIModel账户, [可写] 订阅方案PDA, [] 系统程序, 可选 [] 组织PDA
+    SetSubscriptionTiers {
+        period_slots: u64,
+        tiers: Vec<SubscriptionTier>,
+        grace_period_slots: u64,
+    },
+    // 订阅者按档位价格支付一个周期开始订阅，订阅已过期时重新开始
+    // 账户: [签名, 可写] 订阅者, [可写] 模型所有者, [] 配置PDA, [] AIModel账户, [] 订阅方案PDA,
+    //       [可写] 订阅PDA, [] 系统程序
+    Subscribe {
+        tier: u8,
+    },
+    // 订阅者在周期中途变更档位：升级按剩余时间补交差价，降级把剩余价值折算为更长的剩余时间
+    // 账户: [签名, 可写] 订阅者, [可写] 模型所有者, [] 配置PDA, [] AIModel账户, [] 订阅方案PDA,
+    //       [可写] 订阅PDA, [] 系统程序
+    ChangeTier {
+        tier: u8,
+    },
+    // 校验钱包的订阅仍然有效且档位包含全部所需的访问范围
+    // 账户: [] 订阅PDA, [] 订阅方案PDA, [] 钱包
+    VerifySubscription {
+        access_scopes: u32,
+    },
+    // 订阅者向订阅PDA预存用于自动续订的lamports
+    // 账户: [签名, 可写] 订阅者, [可写] 订阅PDA, [] 系统程序
+    FundSubscriptionRenewal {
+        amount: u64,
+    },
+    // 任何人都可以调用的续订清理指令，到期（含宽限期内）的订阅用预存余额按档位当前价格续订一个周期
+    // 账户: [可写] 模型所有者, [] 配置PDA, [] AIModel账户, [] 订阅方案PDA, [可写] 订阅PDA
+    RenewSubscription,
+    // 任何人都可以调用的清理指令，关闭超过宽限期仍未续订或已取消且到期的订阅，剩余余额和租金退回订阅者
+    // 账户: [可写] 订阅者, [] 订阅方案PDA, [可写] 订阅PDA
+    LapseSubscription,
+    // 订阅者取消自动续订，访问保持到已付费的期限，预存的续订余额立即退回
+    // 账户: [签名, 可写] 订阅者, [可写] 订阅PDA
+    CancelSubscription,
+    // 创建组织，创建者成为第一名管理员
+    // 账户: [签名, 可写] 创建者, [可写] 组织PDA, [] 系统程序
+    CreateOrganization {
+        org_id: u32,
+        name: String,
+    },
+    // 组织管理员添加成员或修改成员角色
+    // 账户: [签名] 组织管理员, [可写] 组织PDA
+    SetOrganizationMember {
+        member: Pubkey,
+        role: u8,
+    },
+    // 组织管理员移除成员，组织至少保留一名管理员
+    // 账户: [签名] 组织管理员, [可写] 组织PDA
+    RemoveOrganizationMember {
+        member: Pubkey,
+    },
+    // 组织管理员从组织PDA提取销售收入等资金，PDA保留免租金额
+    // 账户: [签名, 可写] 组织管理员, [可写] 组织PDA
+    WithdrawOrganizationFunds {
+        amount: u64,
+    },
+    // 超级管理员授予角色，角色PDA已存在时报错
+    // 账户: [签名, 可写] 超级管理员, [] 配置PDA, [可写] 角色PDA, [] 系统程序, 可选 [] 超级管理员的角色PDA
+    GrantRole {
+        role: u8,
+        holder: Pubkey,
+    },
+    // 超级管理员撤销角色，关闭角色PDA并把租金退给授予该角色时出资的管理员
+    // 账户: [签名, 可写] 超级管理员, [] 配置PDA, [可写] 角色PDA, 可选 [] 超级管理员的角色PDA,
+    //       授予者不是签名者时还需附加 [可写] 授予角色的管理员
+    RevokeRole {
+        role: u8,
+        holder: Pubkey,
+    },
+    // 买家在购买凭证中绑定API密钥的SHA-256，供链下推理网关鉴权；重新绑定即轮换密钥，全零哈希解除绑定
+    // 账户: [签名] 买家, [可写] 购买凭证PDA
+    BindApiKey {
+        api_key_hash: [u8; 32],
+    },
+    // 管理员设置计量权限
+    // 账户: [签名] 超级管理员, [可写] 配置PDA, 可选 [] 角色PDA
+    SetMeteringAuthority {
+        authority: Pubkey,
+    },
+    // 计量权限上报许可证在当前计量周期的请求数，首次上报时创建用量计数PDA；
+    // 超出许可证档位限额的批次被拒绝，只计入拒绝数并记录日志
+    // 账户: [签名, 可写] 计量权限, [] 配置PDA, [] 购买凭证PDA, [] AIModel账户, [可写] 用量计数PDA, [] 系统程序, 可选 [] 角色PDA
+    RecordUsage {
+        requests: u32,
+    },
+    // 钱包作废所有nonce小于min_nonce的链下签名订单，nonce账户不存在时创建
+    // 账户: [签名, 可写] 钱包, [可写] 订单nonce PDA, [] 系统程序
+    CancelOrderNonces {
+        min_nonce: u64,
+    },
+    // 买家按卖家链下签名的订单成交fill_quantity个席位，前一条指令必须是卖家对订单消息的Ed25519签名校验；
+    // 成交金额按订单总价分摊，其余与PurchaseAIModel相同；订单在过期前可以分多次成交，
+    // 首次成交时由买家创建订单成交进度PDA和卖家的订单nonce账户
+    // 账户: [签名, 可写] 买家, [可写] 卖家订单nonce PDA, [可写] 订单成交进度PDA, [] 指令sysvar,
+    //       [可写] AIModel账户, [可写] 卖家, 其余同PurchaseAIModel的购买凭证PDA及之后的账户,
+    //       尾部账户只识别购买节流PDA和Civic网关令牌，不支持慈善分成和成交备忘
+    SettleSignedOrder {
+        order: SignedOrder,
+        fill_quantity: u32,
+        purchase_index: u32,
+        acknowledge_deprecation: bool,
+    },
+    // 模型所有者设置许可证发行上限，0表示不限；设置后只能调低且不能低于已发行数量
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    SetMaxSupply {
+        max_supply: u32,
+    },
+    // 模型所有者把不限量的模型设为开放版次，end_slot之后不能再购买；设置后只能提前截止
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    SetOpenEditionEnd {
+        end_slot: u64,
+    },
+    // 任何人在开放版次截止后定格最终发行数量
+    // 账户: [可写] AIModel账户
+    FinalizeOpenEdition,
+    // 模型所有者设置前代模型许可证持有者升级时的折扣
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    SetUpgradeDiscount {
+        discount_bps: u16,
+    },
+    // 买家销毁前代模型的购买凭证（关闭并把租金退给凭证的出资人），以升级折扣购买其后继模型的许可证；
+    // 前代模型的successor必须指向新模型
+    // 账户: [签名, 可写] 买家, [可写] 前代模型的购买凭证PDA, [] 前代AIModel账户,
+    //       [可写] 新AIModel账户, [可写] 卖家, 其余同PurchaseAIModel的购买凭证PDA及之后的账户,
+    //       尾部账户只识别购买节流PDA和Civic网关令牌，不支持慈善分成和成交备忘；
+    //       凭证出资人不在上述账户中时还需在末尾附加 [可写] 凭证出资人
+    UpgradeLicense {
+        tier_index: u8,
+        quantity: u32,
+        purchase_index: u32,
+    },
+    // 模型所有者设置许可证能否转让以及转让版税，只影响之后的转让
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    SetLicenseTransferability {
+        transferability: u8,
+        royalty_bps: u16,
+    },
+    // 许可证持有者把购买凭证转让给接收者，接收者支付price，其中的版税付给卖家，按转售费率收取的手续费转入市场金库，
+    // 其余付给持有者；原凭证关闭，接收者获得purchase_index为其此前购买该模型次数的新凭证，需要开启FEATURE_RESALE
+    // 账户: [签名, 可写] 持有者, [签名, 可写] 接收者, [] 配置PDA, [] AIModel账户, [可写] 卖家,
+    //       [可写] 原购买凭证PDA, [可写] 新购买凭证PDA, [] 系统程序, [可写] 市场金库PDA,
+    //       purchase_index大于0时还需 [] 接收者上一次购买的凭证PDA,
+    //       原凭证的租金退给其出资人，出资人不在上述账户中时还需在末尾附加 [可写] 原凭证出资人
+    TransferLicense {
+        price: u64,
+        purchase_index: u32,
+    },
+    // 模型所有者吊销成交时档位允许吊销的许可证，reason_hash为链下违约记录等吊销依据的SHA-256；
+    // 凭证关闭，租金退还给凭证的出资人，并输出可审计的LicenseRevoked事件
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [] AIModel账户, [可写] 购买凭证PDA, [可写] 持有者,
+    //       出资人不在上述账户中时还需在末尾附加 [可写] 凭证出资人, 可选 [] 组织PDA
+    RevokeLicense {
+        reason_hash: [u8; 32],
+    },
+    // 期限许可证持有者按档位当前的折后标价续费一个期限，与流式订阅相互独立；
+    // 手续费转入市场金库，其余付给卖家或存入卖家的收益金库
+    // 账户: [签名, 可写] 持有者, [可写] 购买凭证PDA, [] AIModel账户, [可写] 卖家,
+    //       [可写] 卖家收益金库PDA, [可写] 市场金库PDA, [] 卖家保证金PDA, [] 系统程序
+    RenewLicense,
+    // 模型所有者设置按量计费的单价，只影响之后的结算
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    SetUsageUnitPrice {
+        unit_price: u64,
+    },
+    // 钱包向自己以mint计价的预付额度充值，mint为全零时充值lamports，否则转入Token-2022代币；额度PDA不存在时创建
+    // 账户: [签名, 可写] 钱包, [可写] 预付额度PDA, [] 系统程序,
+    //       mint非全零时还需 [] mint, [可写] 钱包的代币账户, [可写] 额度PDA的关联代币账户, [] Token-2022程序, [] 关联代币账户程序
+    TopUpCredits {
+        mint: Pubkey,
+        amount: u64,
+    },
+    // 按模型当前的单价把用量计数中尚未结算的请求从买家的预付额度中扣款，任何人都可以发起；
+    // 手续费转入市场金库，其余付给卖家或存入卖家的收益金库，额度不足时只结算额度覆盖的部分
+    // 账户: [签名, 可写] 发起者（首次创建收益金库时出资）, [可写] 用量计数PDA, [] 购买凭证PDA, [] AIModel账户,
+    //       [可写] 买家预付额度PDA, [可写] 卖家, [可写] 卖家收益金库PDA, [可写] 市场金库PDA, [] 卖家保证金PDA, [] 系统程序
+    SettleUsage,
+    // 计量权限从钱包的预付额度中扣减amount，对应的SOL或代币付给收款方，供下载等链下计量的服务扣费
+    // 账户: [签名] 计量权限, [] 配置PDA, [可写] 预付额度PDA, [可写] 收款方（SOL额度为收款钱包，代币额度为收款代币账户）,
+    //       代币额度还需 [] mint, [可写] 额度PDA的关联代币账户, [] Token-2022程序, [可写] 额度所属钱包；
+    //       代币额度扣完且关联代币账户的实际余额为0时关闭该账户，租金退给充值时出资创建它的额度所属钱包，
+    //       有人额外转入代币时保留该账户；
+    //       计量权限未设置时可由Oracle角色签名
+    BurnCredits {
+        amount: u64,
+    },
+    // 管理员启用市场额度代币：创建以reserve_mint小数位数为准的额度mint PDA，只能启用一次
+    // 账户: [签名, 可写] 管理员, [可写] 配置PDA, [] 储备mint, [可写] 额度mint PDA, [] Token-2022程序, [] 系统程序
+    ConfigureCreditMint,
+    // 买家存入amount储备代币，按1:1铸造等量的市场额度代币；两个关联代币账户不存在时创建
+    // 账户: [签名, 可写] 买家, [] 配置PDA, [] 储备mint, [可写] 额度mint PDA, [] 储备权限PDA,
+    //       [可写] 买家的储备代币账户, [可写] 储备权限的关联代币账户, [可写] 买家的额度代币关联账户,
+    //       [] Token-2022程序, [] 关联代币账户程序, [] 系统程序
+    BuyCredits {
+        amount: u64,
+    },
+    // 以市场额度代币购买模型，标价按额度代币的最小单位计，额度按1:1销毁并从储备中把等量储备代币付给卖家和市场金库
+    // 账户: 与PurchaseAIModel相同直到 [可写] 买家当前凭证索引分页PDA，随后为
+    //       [] 配置PDA, [可写] 额度mint PDA, [可写] 买家的额度代币账户, [] 储备mint, [可写] 储备权限的关联代币账户,
+    //       [] 储备权限PDA, [可写] 卖家的储备代币关联账户, [可写] 市场金库的储备代币关联账户, [] Token-2022程序,
+    //       [] 关联代币账户程序, purchase_index大于0时还需 [] 上一次购买的凭证PDA,
+    //       购买drop模型时还需 [可写] 买家的购买节流PDA, 模型要求真人验证时还需 [] 买家的Civic网关令牌；
+    //       卖家或市场金库的储备代币关联账户尚不存在时由买家出资创建；
+    //       买家的额度代币账户余额用完时关闭，租金退给买家
+    PurchaseWithCredits {
+        amount: u64,
+        tier_index: u8,
+        quantity: u32,
+        purchase_index: u32,
+        acknowledge_deprecation: bool,
+    },
+    // 与PurchaseAIModel相同，但在结账时兑换points个忠诚度积分抵扣付款，抵扣不能超过该笔成交的市场手续费
+    // 账户: 与PurchaseAIModel相同
+    PurchaseWithPoints {
+        amount: u64,
+        tier_index: u8,
+        quantity: u32,
+        purchase_index: u32,
+        acknowledge_deprecation: bool,
+        points: u64,
+    },
+    // 管理员或Treasurer设置忠诚度积分的累计比例和兑换价格，redemption_rate为0表示暂停兑换
+    // 账户: [签名] 管理员, [] 配置PDA, [可写] 市场金库PDA
+    SetLoyaltyRates {
+        accrual_bps: u16,
+        redemption_rate: u64,
+    },
+    // 管理员创建指定成就的不可转让Token-2022徽章mint
+    // 账户: [签名, 可写] 超级管理员, [] 配置PDA, [可写] 成就徽章mint PDA, [] Token-2022程序, [] 系统程序, 可选 [] 角色PDA
+    InitializeAchievementMint {
+        achievement: u8,
+    },
+    // 钱包领取自己已达成的成就，在统计中标记为已领取；mint_badge为true时同时发放一枚成就徽章代币
+    // 账户: [签名, 可写] 钱包, [可写] 卖家统计PDA（买家成就为买家统计PDA），
+    //       mint_badge为true时还需 [可写] 成就徽章mint PDA, [可写] 钱包的Token-2022关联代币账户,
+    //       [] Token-2022程序, [] 关联代币账户程序, [] 系统程序
+    ClaimAchievement {
+        achievement: u8,
+        mint_badge: bool,
+    },
+    // 模型所有者创建空投活动，并预存max_recipients份空投凭证的租金；merkle_root非零时名单中的钱包可以自行领取
+    // 账户: [签名, 可写] 模型所有者, [] AIModel账户, [可写] 空投活动PDA, [] 系统程序
+    CreateAirdropCampaign {
+        campaign_id: u64,
+        tier_index: u8,
+        max_recipients: u32,
+        merkle_root: [u8; 32],
+    },
+    // 模型所有者向一批钱包空投免费许可证，每个钱包在同一活动中只能获得一份
+    // 账户: [签名] 模型所有者, [可写] 空投活动PDA, [可写] AIModel账户, [] 系统程序,
+    //       之后每个接收者依次为 [] 接收钱包, [可写] 空投凭证PDA
+    AirdropLicenses,
+    // 关闭空投活动，未使用的租金预存退回模型所有者；已空投的凭证不受影响
+    // 账户: [签名, 可写] 模型所有者, [可写] 空投活动PDA
+    CloseAirdropCampaign,
+    // 空投名单中的钱包凭Merkle证明领取许可证，凭证租金由活动账户支付
+    // 账户: [签名] 领取钱包, [可写] 空投活动PDA, [可写] AIModel账户, [可写] 空投凭证PDA, [] 系统程序
+    ClaimAirdrop {
+        proof: Vec<[u8; 32]>,
+    },
+    // 模型所有者设置白名单预售：public_sale_slot之前只有白名单中的钱包能以presale_price每席位购买，
+    // presale_price按档位0计，其他档位按标价比例折算；白名单叶子带有每个钱包的限购席位数；
+    // merkle_root为全零时关闭预售；public_sale_slot非零时公开购买要等到该slot
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    SetPresale {
+        merkle_root: [u8; 32],
+        presale_price: u64,
+        public_sale_slot: u64,
+    },
+    // 白名单中的钱包凭Merkle证明在预售期间以预售价购买，allocation为该钱包叶子中的限购席位数，
+    // 多次预售购买的席位累计不能超过allocation
+    // 账户: 与PurchaseAIModel相同，但必须附加 [可写] 买家的购买节流PDA，用于记录已购买的预售席位
+    PurchasePresale {
+        tier_index: u8,
+        quantity: u32,
+        purchase_index: u32,
+        acknowledge_deprecation: bool,
+        allocation: u32,
+        proof: Vec<[u8; 32]>,
+    },
+    // 通过Wormhole发布模型上架（kind为0）或许可证成交（kind为1）的跨链公告，需要开启FEATURE_CROSS_CHAIN_ANNOUNCEMENTS；
+    // 付款人支付Wormhole消息手续费和消息账户租金
+    // 账户: [签名, 可写] 付款人, [] 配置PDA, [] AIModel账户, [] 本程序的Wormhole发送者PDA,
+    //       [可写] Wormhole桥配置, [签名, 可写] 新消息账户, [可写] 发送者序号账户, [可写] Wormhole手续费账户,
+    //       [] Clock sysvar, [] Rent sysvar, [] 系统程序, [] Wormhole核心桥程序,
+    //       kind为1时还需 [] 购买凭证账户
+    AnnounceListing {
+        kind: u8,
+        nonce: u32,
+    },
+    // Oracle角色推送价格源的最新价格（每个计价单位对应的lamports）和置信区间，价格源不存在时创建
+    // 账户: [签名, 可写] 预言机, [] 配置PDA, [可写] 价格源PDA, [] 系统程序, 可选 [] 角色PDA
+    UpdatePriceFeed {
+        feed_id: u64,
+        price: u64,
+        confidence: u64,
+    },
+    // 模型所有者设置按价格源标价：档位0每个席位quote_price个计价最小单位，以及价格源降级时的判定阈值和备用价格，
+    // 其他档位按标价相对档位0的比例折算；price_feed为全零时恢复按lamports标价
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    SetOraclePricing {
+        price_feed: Pubkey,
+        quote_price: u64,
+        max_staleness_slots: u64,
+        max_confidence_bps: u16,
+        fallback_price: u64,
+    },
+    // 按价格源换算的价格购买按价格源标价的模型，成交价超过max_amount时失败
+    // 账户: [] 价格源PDA, 之后与PurchaseAIModel相同
+    PurchaseAtOraclePrice {
+        max_amount: u64,
+        tier_index: u8,
+        quantity: u32,
+        purchase_index: u32,
+        acknowledge_deprecation: bool,
+    },
+    // 管理员设置上架价格的下限和上限，max_price为0表示不设上限
+    // 账户: [签名] 超级管理员, [可写] 配置PDA, 可选 [] 角色PDA
+    SetPriceBounds {
+        min_price: u64,
+        max_price: u64,
+    },
+    // 模型所有者把模型标记为drop并设置防机器人节流，两个参数都为0时取消；
+    // drop模型只能通过直接购买指令购买，且需要传入买家的购买节流PDA
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    SetDropThrottle {
+        cooldown_slots: u64,
+        max_per_slot: u32,
+    },
+    // 模型所有者要求买家出示gatekeeper_network签发的有效Civic网关令牌才能购买，全零时取消；
+    // 与drop模型一样只能通过直接购买指令购买
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, 可选 [] 组织PDA
+    SetVerificationRequirement {
+        gatekeeper_network: Pubkey,
+    },
+    // 管理员或Treasurer设置首次销售和转售的手续费率（基点），设置后首次销售费率为0即免手续费；
+    // 质押卖家的首次销售费率始终不高于STAKED_FEE_BPS，设为DEFAULT_FEE_BPS即与未设置时的费率一致
+    // 账户: [签名] 管理员, [] 配置PDA, [可写] 市场金库PDA
+    SetFeeSchedule {
+        primary_fee_bps: u16,
+        secondary_fee_bps: u16,
+    },
+    // 管理员登记收款钱包为wallet的慈善机构，或修改已登记机构的启用状态
+    // 账户: [签名, 可写] 超级管理员, [] 配置PDA, [可写] 慈善机构PDA, [] 系统程序, 可选 [] 角色PDA
+    RegisterCharity {
+        wallet: Pubkey,
+        active: bool,
+    },
+    // 模型所有者把成交额的charity_bps捐给登记的慈善机构，从卖家收入中扣除，全零时取消；
+    // 设置了慈善分成的模型只能通过直接购买指令购买
+    // 账户: [签名] 模型所有者或所有者组织的管理员, [可写] AIModel账户, charity非零时还需 [] 慈善机构PDA, 可选 [] 组织PDA
+    SetListingCharity {
+        charity: Pubkey,
+        charity_bps: u16,
+    },
+    // 买家设置偏好的慈善机构，直接购买时传入其PDA即在成交额之外另捐charity_bps，全零时取消
+    // 账户: [签名, 可写] 买家, [可写] 买家统计PDA, [] 系统程序, charity非零时还需 [] 慈善机构PDA
+    SetBuyerCharity {
+        charity: Pubkey,
+        charity_bps: u16,
+    },
+    // 慈善机构的收款钱包提取托管的捐款
+    // 账户: [签名, 可写] 收款钱包, [可写] 慈善机构PDA
+    WithdrawCharityDonations {
+        amount: u64,
+    },
+    // 卖家设置每笔收入（销售、续费和按量计费）的预扣比例和目标账户，预扣款托管在收益金库，全零时取消；
+    // 仍有待释放的预扣款时不能更换目标账户
+    // 账户: [签名, 可写] 卖家, [可写] 收益金库PDA, [] 系统程序
+    SetWithholding {
+        destination: Pubkey,
+        withholding_bps: u16,
+    },
+    // 把收益金库中待释放的预扣款全部转给卖家设置的目标账户，任何人都可以发起
+    // 账户: [可写] 收益金库PDA, [可写] 预扣目标账户
+    ReleaseWithheldProceeds,
+    // Treasurer、超级管理员或任一守护者在执行前取消排队中的金库提款
+    // 账户: [签名] 取消者, [] 配置PDA, [可写] 金库PDA, 可选 [] 角色PDA
+    CancelWithdrawal,
+    // 卖家转换挂单的生命周期状态（见state::ListingStatus）：发布草稿、暂停、恢复在售或下架
+    // 账户: [签名] 卖家, [] AIModel账户, [可写] 挂单PDA
+    SetListingStatus {
+        status: u8,
+    },
+}
+
+impl<'a> MarketplaceInstruction<'a> {
+    // 从指令数据中解析指令，首字节为指令标签
+    pub fn unpack(input: &'a [u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+        Ok(match tag {
+            0 => {
+                let (name, rest) = unpack_string(rest)?;
+                let (description, rest) = unpack_string(rest)?;
+                let (price, rest) = unpack_u64(rest)?;
+                let (pricing_mode, rest) = unpack_u8(rest)?;
+                let pricing_mode = PricingMode::from_u8(pricing_mode)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                let (tier_count, mut rest) = unpack_u8(rest)?;
+                let mut license_tiers = Vec::with_capacity(tier_count as usize);
+                for _ in 0..tier_count {
+                    let (tier_price, next) = unpack_u64(rest)?;
+                    let (terms_uri, next) = unpack_string(next)?;
+                    let (requests_per_epoch, next) = unpack_u64(next)?;
+                    let (revocable, next) = unpack_u8(next)?;
+                    let (term_slots, next) = unpack_u64(next)?;
+                    license_tiers.push(LicenseTier {
+                        price: tier_price,
+                        terms_uri,
+                        requests_per_epoch,
+                        revocable: revocable != 0,
+                        term_slots,
+                    });
+                    rest = next;
+                }
+                let (per_seat, rest) = unpack_u8(rest)?;
+                let (discount_count, mut rest) = unpack_u8(rest)?;
+                let mut volume_discounts = Vec::with_capacity(discount_count as usize);
+                for _ in 0..discount_count {
+                    let (min_seats, next) = unpack_u32(rest)?;
+                    let (discount_bps, next) = unpack_u16(next)?;
+                    volume_discounts.push(VolumeDiscount {
+                        min_seats,
+                        discount_bps,
+                    });
+                    rest = next;
+                }
+                let (vault_proceeds, rest) = unpack_u8(rest)?;
+                let (model_file, rest) = unpack_byte_slice(rest)?;
+                let (model_format, rest) = unpack_u8(rest)?;
+                let model_format = ModelFormat::from_u8(model_format)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                let (license, _) = unpack_string(rest)?;
+                Self::CreateAIModel {
+                    name,
+                    description,
+                    price,
+                    pricing_mode,
+                    license_tiers,
+                    per_seat: per_seat != 0,
+                    volume_discounts,
+                    vault_proceeds: vault_proceeds != 0,
+                    model_file,
+                    model_format,
+                    license,
+                }
+            }
+            1 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (tier_index, rest) = unpack_u8(rest)?;
+                let (quantity, rest) = unpack_u32(rest)?;
+                let (purchase_index, rest) = unpack_u32(rest)?;
+                let (acknowledge_deprecation, rest) = unpack_u8(rest)?;
+                let (reference, _) = unpack_hash(rest)?;
+                Self::PurchaseAIModel {
+                    amount,
+                    tier_index,
+                    quantity,
+                    purchase_index,
+                    acknowledge_deprecation: acknowledge_deprecation != 0,
+                    reference,
+                }
+            }
+            2 => Self::VerifyLicense,
+            3 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::WithdrawProceeds { amount }
+            }
+            4 => Self::InitializeConfig,
+            5 => Self::GetVersion,
+            6 => {
+                let (feature_flags, _) = unpack_u64(rest)?;
+                Self::SetFeatureFlags { feature_flags }
+            }
+            7 => {
+                let (guardian_count, mut rest) = unpack_u8(rest)?;
+                let mut guardians = Vec::with_capacity(guardian_count as usize);
+                for _ in 0..guardian_count {
+                    let (guardian, next) = unpack_pubkey(rest)?;
+                    guardians.push(guardian);
+                    rest = next;
+                }
+                let (threshold, rest) = unpack_u8(rest)?;
+                let (recovery_delay, _) = unpack_i64(rest)?;
+                Self::SetRecoveryGuardians {
+                    guardians,
+                    threshold,
+                    recovery_delay,
+                }
+            }
+            8 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (destination, rest) = unpack_pubkey(rest)?;
+                let (evidence_hash, _) = unpack_hash(rest)?;
+                Self::ProposeRecovery {
+                    amount,
+                    destination,
+                    evidence_hash,
+                }
+            }
+            9 => Self::ExecuteRecovery,
+            10 => Self::CancelRecovery,
+            11 => {
+                let (withdrawal_delay, _) = unpack_i64(rest)?;
+                Self::InitializeTreasury { withdrawal_delay }
+            }
+            12 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (destination, _) = unpack_pubkey(rest)?;
+                Self::QueueWithdrawal { amount, destination }
+            }
+            13 => Self::ExecuteWithdrawal,
+            14 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::DepositBond { amount }
+            }
+            15 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::WithdrawBond { amount }
+            }
+            16 => {
+                let (price, rest) = unpack_u64(rest)?;
+                let (tier_index, rest) = unpack_u8(rest)?;
+                let (quantity, rest) = unpack_u32(rest)?;
+                let (draft, _) = unpack_u8(rest)?;
+                Self::Sell {
+                    price,
+                    tier_index,
+                    quantity,
+                    draft: draft != 0,
+                }
+            }
+            17 => {
+                let (price, rest) = unpack_u64(rest)?;
+                let (tier_index, rest) = unpack_u8(rest)?;
+                let (quantity, _) = unpack_u32(rest)?;
+                Self::Buy {
+                    price,
+                    tier_index,
+                    quantity,
+                }
+            }
+            18 => Self::CancelTrade,
+            19 => {
+                let (purchase_index, _) = unpack_u32(rest)?;
+                Self::ExecuteSale { purchase_index }
+            }
+            20 => Self::AttachTokenMetadata,
+            21 => {
+                let (rating, rest) = unpack_u8(rest)?;
+                let (content_hash, _) = unpack_hash(rest)?;
+                Self::SubmitReview { rating, content_hash }
+            }
+            22 => {
+                let (kind, _) = unpack_u8(rest)?;
+                Self::InitializeBadgeMint { kind }
+            }
+            23 => {
+                let (kind, _) = unpack_u8(rest)?;
+                Self::ClaimBadge { kind }
+            }
+            24 => {
+                let (evidence_hash, _) = unpack_hash(rest)?;
+                Self::OpenDispute { evidence_hash }
+            }
+            25 => {
+                let (evidence_hash, _) = unpack_hash(rest)?;
+                Self::AttachDisputeEvidence { evidence_hash }
+            }
+            26 => {
+                let (storage_class, rest) = unpack_u8(rest)?;
+                let (artifact_uri, rest) = unpack_string(rest)?;
+                let (artifact_hash, rest) = unpack_hash(rest)?;
+                let (artifact_size_bytes, _) = unpack_u64(rest)?;
+                Self::SetModelArtifact {
+                    storage_class,
+                    artifact_uri,
+                    artifact_hash,
+                    artifact_size_bytes,
+                }
+            }
+            27 => {
+                let (attestor, _) = unpack_pubkey(rest)?;
+                Self::SetArtifactAttestor { attestor }
+            }
+            28 => {
+                let (artifact_hash, _) = unpack_hash(rest)?;
+                Self::AttestArtifact { artifact_hash }
+            }
+            29 => {
+                let (major, rest) = unpack_u32(rest)?;
+                let (minor, rest) = unpack_u32(rest)?;
+                let (patch, rest) = unpack_u32(rest)?;
+                let (artifact_hash, _) = unpack_hash(rest)?;
+                Self::PublishModelVersion {
+                    version: SemVer { major, minor, patch },
+                    artifact_hash,
+                }
+            }
+            30 => {
+                let (content_hash, rest) = unpack_hash(rest)?;
+                let (uri, rest) = unpack_string(rest)?;
+                let (summary, _) = unpack_string(rest)?;
+                Self::AddChangelogEntry {
+                    content_hash,
+                    uri,
+                    summary,
+                }
+            }
+            31 => Self::DeprecateModel,
+            32 => Self::FollowSeller,
+            33 => Self::UnfollowSeller,
+            34 => Self::FavoriteModel,
+            35 => Self::UnfavoriteModel,
+            36 => {
+                let (price, _) = unpack_u64(rest)?;
+                Self::UpdatePrice { price }
+            }
+            37 => {
+                let (list_id, rest) = unpack_u32(rest)?;
+                let (label, _) = unpack_string(rest)?;
+                Self::SetFeaturedList { list_id, label }
+            }
+            38 => {
+                let (id, rest) = unpack_u16(rest)?;
+                let (parent, rest) = unpack_u16(rest)?;
+                let (label, _) = unpack_string(rest)?;
+                Self::RegisterCategory { id, parent, label }
+            }
+            39 => {
+                let (category_id, _) = unpack_u16(rest)?;
+                Self::SetModelCategory { category_id }
+            }
+            40 => {
+                let (lang, rest) = unpack_string(rest)?;
+                let (name, rest) = unpack_string(rest)?;
+                let (description_uri, _) = unpack_string(rest)?;
+                Self::SetLocalizedMetadata {
+                    lang,
+                    name,
+                    description_uri,
+                }
+            }
+            41 => {
+                let (metadata_uri, rest) = unpack_string(rest)?;
+                let (metadata_hash, _) = unpack_hash(rest)?;
+                Self::SetMetadataUri {
+                    metadata_uri,
+                    metadata_hash,
+                }
+            }
+            42 => {
+                let (update_authority, _) = unpack_pubkey(rest)?;
+                Self::SetUpdateAuthority { update_authority }
+            }
+            43 => {
+                let (new_owner, _) = unpack_pubkey(rest)?;
+                Self::SetModelOwner { new_owner }
+            }
+            44 => {
+                let (creator, _) = unpack_pubkey(rest)?;
+                Self::SetModelCreator { creator }
+            }
+            45 => Self::VerifyCreator,
+            46 => {
+                let (oracle, _) = unpack_pubkey(rest)?;
+                Self::SetListingOracle { oracle }
+            }
+            47 => Self::RequireListingApproval,
+            48 => {
+                let (artifact_hash, rest) = unpack_hash(rest)?;
+                let (attestation_hash, _) = unpack_hash(rest)?;
+                Self::ApproveListing {
+                    artifact_hash,
+                    attestation_hash,
+                }
+            }
+            49 => {
+                let (verifier_id, rest) = unpack_u32(rest)?;
+                let (proof_system, rest) = unpack_u8(rest)?;
+                let (claim, rest) = unpack_string(rest)?;
+                let (verifying_key, _) = unpack_bytes(rest)?;
+                Self::RegisterProvenanceVerifier {
+                    verifier_id,
+                    proof_system,
+                    claim,
+                    verifying_key,
+                }
+            }
+            50 => Self::VerifyProvenance,
+            51 => {
+                let (input_hash, rest) = unpack_hash(rest)?;
+                let (output_hash, _) = unpack_hash(rest)?;
+                Self::CommitInferenceResult { input_hash, output_hash }
+            }
+            52 => {
+                let (request_id, rest) = unpack_u64(rest)?;
+                let (prompt_hash, rest) = unpack_hash(rest)?;
+                let (max_price, rest) = unpack_u64(rest)?;
+                let (deadline, rest) = unpack_i64(rest)?;
+                let (challenge_window, _) = unpack_i64(rest)?;
+                Self::EnqueueInferenceRequest {
+                    request_id,
+                    prompt_hash,
+                    max_price,
+                    deadline,
+                    challenge_window,
+                }
+            }
+            53 => {
+                let (output_hash, rest) = unpack_hash(rest)?;
+                let (proof_uri, rest) = unpack_string(rest)?;
+                let (price, _) = unpack_u64(rest)?;
+                Self::SubmitResult {
+                    output_hash,
+                    proof_uri,
+                    price,
+                }
+            }
+            54 => Self::CancelInferenceRequest,
+            55 => {
+                let (hardware_class, rest) = unpack_u8(rest)?;
+                let (supported_formats, rest) = unpack_u8(rest)?;
+                let (price_per_unit, rest) = unpack_u64(rest)?;
+                let (endpoint_hash, _) = unpack_hash(rest)?;
+                Self::RegisterWorker {
+                    hardware_class,
+                    supported_formats,
+                    price_per_unit,
+                    endpoint_hash,
+                }
+            }
+            56 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::StakeWorker { amount }
+            }
+            57 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::UnstakeWorker { amount }
+            }
+            58 => {
+                let (arbiter, _) = unpack_pubkey(rest)?;
+                Self::SetInferenceArbiter { arbiter }
+            }
+            59 => Self::SlashWorker,
+            60 => Self::ClaimJob,
+            61 => Self::ReassignExpiredClaim,
+            62 => Self::ChallengeResult,
+            63 => Self::SettleInferenceRequest,
+            64 => Self::UpholdResult,
+            65 => {
+                let (rate_per_slot, _) = unpack_u64(rest)?;
+                Self::SetRentalRate { rate_per_slot }
+            }
+            66 => {
+                let (deposit, _) = unpack_u64(rest)?;
+                Self::OpenRentalStream { deposit }
+            }
+            67 => Self::WithdrawRentalStream,
+            68 => Self::CloseRentalStream,
+            69 => {
+                let (commission_id, rest) = unpack_u64(rest)?;
+                let (seller, rest) = unpack_pubkey(rest)?;
+                let (milestone_count, mut rest) = unpack_u8(rest)?;
+                let mut milestone_amounts = Vec::with_capacity(milestone_count as usize);
+                for _ in 0..milestone_count {
+                    let (amount, next) = unpack_u64(rest)?;
+                    milestone_amounts.push(amount);
+                    rest = next;
+                }
+                Self::FundCommission {
+                    commission_id,
+                    seller,
+                    milestone_amounts,
+                }
+            }
+            70 => {
+                let (index, rest) = unpack_u8(rest)?;
+                let (deliverable_hash, _) = unpack_hash(rest)?;
+                Self::SubmitMilestone { index, deliverable_hash }
+            }
+            71 => {
+                let (index, _) = unpack_u8(rest)?;
+                Self::ApproveMilestone { index }
+            }
+            72 => {
+                let (index, _) = unpack_u8(rest)?;
+                Self::ClaimMilestone { index }
+            }
+            73 => {
+                let (index, _) = unpack_u8(rest)?;
+                Self::DisputeMilestone { index }
+            }
+            74 => {
+                let (arbiter, _) = unpack_pubkey(rest)?;
+                Self::SetCommissionArbiter { arbiter }
+            }
+            75 => {
+                let (index, rest) = unpack_u8(rest)?;
+                let (release, _) = unpack_u8(rest)?;
+                Self::ResolveMilestoneDispute {
+                    index,
+                    release: release != 0,
+                }
+            }
+            76 => Self::CloseCommission,
+            77 => {
+                let (period_slots, rest) = unpack_u64(rest)?;
+                let (tier_count, mut rest) = unpack_u8(rest)?;
+                let mut tiers = Vec::with_capacity(tier_count as usize);
+                for _ in 0..tier_count {
+                    let (price, next) = unpack_u64(rest)?;
+                    let (access_scopes, next) = unpack_u32(next)?;
+                    tiers.push(SubscriptionTier {
+                        price,
+                        access_scopes,
+                    });
+                    rest = next;
+                }
+                let (grace_period_slots, _) = unpack_u64(rest)?;
+                Self::SetSubscriptionTiers {
+                    period_slots,
+                    tiers,
+                    grace_period_slots,
+                }
+            }
+            78 => {
+                let (tier, _) = unpack_u8(rest)?;
+                Self::Subscribe { tier }
+            }
+            79 => {
+                let (tier, _) = unpack_u8(rest)?;
+                Self::ChangeTier { tier }
+            }
+            80 => {
+                let (access_scopes, _) = unpack_u32(rest)?;
+                Self::VerifySubscription { access_scopes }
+            }
+            81 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::FundSubscriptionRenewal { amount }
+            }
+            82 => Self::RenewSubscription,
+            83 => Self::LapseSubscription,
+            84 => Self::CancelSubscription,
+            85 => {
+                let (org_id, rest) = unpack_u32(rest)?;
+                let (name, _) = unpack_string(rest)?;
+                Self::CreateOrganization { org_id, name }
+            }
+            86 => {
+                let (member, rest) = unpack_pubkey(rest)?;
+                let (role, _) = unpack_u8(rest)?;
+                Self::SetOrganizationMember { member, role }
+            }
+            87 => {
+                let (member, _) = unpack_pubkey(rest)?;
+                Self::RemoveOrganizationMember { member }
+            }
+            88 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::WithdrawOrganizationFunds { amount }
+            }
+            89 => {
+                let (role, rest) = unpack_u8(rest)?;
+                let (holder, _) = unpack_pubkey(rest)?;
+                Self::GrantRole { role, holder }
+            }
+            90 => {
+                let (role, rest) = unpack_u8(rest)?;
+                let (holder, _) = unpack_pubkey(rest)?;
+                Self::RevokeRole { role, holder }
+            }
+            91 => {
+                let (api_key_hash, _) = unpack_hash(rest)?;
+                Self::BindApiKey { api_key_hash }
+            }
+            92 => {
+                let (authority, _) = unpack_pubkey(rest)?;
+                Self::SetMeteringAuthority { authority }
+            }
+            93 => {
+                let (requests, _) = unpack_u32(rest)?;
+                Self::RecordUsage { requests }
+            }
+            94 => {
+                let (min_nonce, _) = unpack_u64(rest)?;
+                Self::CancelOrderNonces { min_nonce }
+            }
+            95 => {
+                let (price, rest) = unpack_u64(rest)?;
+                let (tier, rest) = unpack_u8(rest)?;
+                let (quantity, rest) = unpack_u32(rest)?;
+                let (expires_at, rest) = unpack_i64(rest)?;
+                let (nonce, rest) = unpack_u64(rest)?;
+                let (fill_quantity, rest) = unpack_u32(rest)?;
+                let (purchase_index, rest) = unpack_u32(rest)?;
+                let (acknowledge_deprecation, _) = unpack_u8(rest)?;
+                Self::SettleSignedOrder {
+                    order: SignedOrder {
+                        price,
+                        tier,
+                        quantity,
+                        expires_at,
+                        nonce,
+                    },
+                    fill_quantity,
+                    purchase_index,
+                    acknowledge_deprecation: acknowledge_deprecation != 0,
+                }
+            }
+            96 => {
+                let (max_supply, _) = unpack_u32(rest)?;
+                Self::SetMaxSupply { max_supply }
+            }
+            97 => {
+                let (end_slot, _) = unpack_u64(rest)?;
+                Self::SetOpenEditionEnd { end_slot }
+            }
+            98 => Self::FinalizeOpenEdition,
+            99 => {
+                let (discount_bps, _) = unpack_u16(rest)?;
+                Self::SetUpgradeDiscount { discount_bps }
+            }
+            100 => {
+                let (tier_index, rest) = unpack_u8(rest)?;
+                let (quantity, rest) = unpack_u32(rest)?;
+                let (purchase_index, _) = unpack_u32(rest)?;
+                Self::UpgradeLicense {
+                    tier_index,
+                    quantity,
+                    purchase_index,
+                }
+            }
+            101 => {
+                let (transferability, rest) = unpack_u8(rest)?;
+                let (royalty_bps, _) = unpack_u16(rest)?;
+                Self::SetLicenseTransferability { transferability, royalty_bps }
+            }
+            102 => {
+                let (price, rest) = unpack_u64(rest)?;
+                let (purchase_index, _) = unpack_u32(rest)?;
+                Self::TransferLicense { price, purchase_index }
+            }
+            103 => {
+                let (reason_hash, _) = unpack_hash(rest)?;
+                Self::RevokeLicense { reason_hash }
+            }
+            104 => Self::RenewLicense,
+            105 => {
+                let (unit_price, _) = unpack_u64(rest)?;
+                Self::SetUsageUnitPrice { unit_price }
+            }
+            106 => {
+                let (mint, rest) = unpack_pubkey(rest)?;
+                let (amount, _) = unpack_u64(rest)?;
+                Self::TopUpCredits { mint, amount }
+            }
+            107 => Self::SettleUsage,
+            108 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::BurnCredits { amount }
+            }
+            109 => Self::ConfigureCreditMint,
+            110 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::BuyCredits { amount }
+            }
+            111 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (tier_index, rest) = unpack_u8(rest)?;
+                let (quantity, rest) = unpack_u32(rest)?;
+                let (purchase_index, rest) = unpack_u32(rest)?;
+                let (acknowledge_deprecation, _) = unpack_u8(rest)?;
+                Self::PurchaseWithCredits {
+                    amount,
+                    tier_index,
+                    quantity,
+                    purchase_index,
+                    acknowledge_deprecation: acknowledge_deprecation != 0,
+                }
+            }
+            112 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (tier_index, rest) = unpack_u8(rest)?;
+                let (quantity, rest) = unpack_u32(rest)?;
+                let (purchase_index, rest) = unpack_u32(rest)?;
+                let (acknowledge_deprecation, rest) = unpack_u8(rest)?;
+                let (points, _) = unpack_u64(rest)?;
+                Self::PurchaseWithPoints {
+                    amount,
+                    tier_index,
+                    quantity,
+                    purchase_index,
+                    acknowledge_deprecation: acknowledge_deprecation != 0,
+                    points,
+                }
+            }
+            113 => {
+                let (accrual_bps, rest) = unpack_u16(rest)?;
+                let (redemption_rate, _) = unpack_u64(rest)?;
+                Self::SetLoyaltyRates { accrual_bps, redemption_rate }
+            }
+            114 => {
+                let (achievement, _) = unpack_u8(rest)?;
+                Self::InitializeAchievementMint { achievement }
+            }
+            115 => {
+                let (achievement, rest) = unpack_u8(rest)?;
+                let (mint_badge, _) = unpack_u8(rest)?;
+                Self::ClaimAchievement { achievement, mint_badge: mint_badge != 0 }
+            }
+            116 => {
+                let (campaign_id, rest) = unpack_u64(rest)?;
+                let (tier_index, rest) = unpack_u8(rest)?;
+                let (max_recipients, rest) = unpack_u32(rest)?;
+                let (merkle_root, _) = unpack_hash(rest)?;
+                Self::CreateAirdropCampaign { campaign_id, tier_index, max_recipients, merkle_root }
+            }
+            117 => Self::AirdropLicenses,
+            118 => Self::CloseAirdropCampaign,
+            119 => {
+                let (proof, _) = unpack_merkle_proof(rest)?;
+                Self::ClaimAirdrop { proof }
+            }
+            120 => {
+                let (merkle_root, rest) = unpack_hash(rest)?;
+                let (presale_price, rest) = unpack_u64(rest)?;
+                let (public_sale_slot, _) = unpack_u64(rest)?;
+                Self::SetPresale { merkle_root, presale_price, public_sale_slot }
+            }
+            121 => {
+                let (tier_index, rest) = unpack_u8(rest)?;
+                let (quantity, rest) = unpack_u32(rest)?;
+                let (purchase_index, rest) = unpack_u32(rest)?;
+                let (acknowledge_deprecation, rest) = unpack_u8(rest)?;
+                let (allocation, rest) = unpack_u32(rest)?;
+                let (proof, _) = unpack_merkle_proof(rest)?;
+                Self::PurchasePresale {
+                    tier_index,
+                    quantity,
+                    purchase_index,
+                    acknowledge_deprecation: acknowledge_deprecation != 0,
+                    allocation,
+                    proof,
+                }
+            }
+            122 => {
+                let (kind, rest) = unpack_u8(rest)?;
+                let (nonce, _) = unpack_u32(rest)?;
+                Self::AnnounceListing { kind, nonce }
+            }
+            123 => {
+                let (feed_id, rest) = unpack_u64(rest)?;
+                let (price, rest) = unpack_u64(rest)?;
+                let (confidence, _) = unpack_u64(rest)?;
+                Self::UpdatePriceFeed { feed_id, price, confidence }
+            }
+            124 => {
+                let (price_feed, rest) = unpack_pubkey(rest)?;
+                let (quote_price, rest) = unpack_u64(rest)?;
+                let (max_staleness_slots, rest) = unpack_u64(rest)?;
+                let (max_confidence_bps, rest) = unpack_u16(rest)?;
+                let (fallback_price, _) = unpack_u64(rest)?;
+                Self::SetOraclePricing {
+                    price_feed,
+                    quote_price,
+                    max_staleness_slots,
+                    max_confidence_bps,
+                    fallback_price,
+                }
+            }
+            125 => {
+                let (max_amount, rest) = unpack_u64(rest)?;
+                let (tier_index, rest) = unpack_u8(rest)?;
+                let (quantity, rest) = unpack_u32(rest)?;
+                let (purchase_index, rest) = unpack_u32(rest)?;
+                let (acknowledge_deprecation, _) = unpack_u8(rest)?;
+                Self::PurchaseAtOraclePrice {
+                    max_amount,
+                    tier_index,
+                    quantity,
+                    purchase_index,
+                    acknowledge_deprecation: acknowledge_deprecation != 0,
+                }
+            }
+            126 => {
+                let (min_price, rest) = unpack_u64(rest)?;
+                let (max_price, _) = unpack_u64(rest)?;
+                Self::SetPriceBounds { min_price, max_price }
+            }
+            127 => {
+                let (cooldown_slots, rest) = unpack_u64(rest)?;
+                let (max_per_slot, _) = unpack_u32(rest)?;
+                Self::SetDropThrottle { cooldown_slots, max_per_slot }
+            }
+            128 => {
+                let (gatekeeper_network, _) = unpack_pubkey(rest)?;
+                Self::SetVerificationRequirement { gatekeeper_network }
+            }
+            129 => {
+                let (primary_fee_bps, rest) = unpack_u16(rest)?;
+                let (secondary_fee_bps, _) = unpack_u16(rest)?;
+                Self::SetFeeSchedule { primary_fee_bps, secondary_fee_bps }
+            }
+            130 => {
+                let (wallet, rest) = unpack_pubkey(rest)?;
+                let (active, _) = unpack_u8(rest)?;
+                Self::RegisterCharity { wallet, active: active != 0 }
+            }
+            131 => {
+                let (charity, rest) = unpack_pubkey(rest)?;
+                let (charity_bps, _) = unpack_u16(rest)?;
+                Self::SetListingCharity { charity, charity_bps }
+            }
+            132 => {
+                let (charity, rest) = unpack_pubkey(rest)?;
+                let (charity_bps, _) = unpack_u16(rest)?;
+                Self::SetBuyerCharity { charity, charity_bps }
+            }
+            133 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::WithdrawCharityDonations { amount }
+            }
+            134 => {
+                let (destination, rest) = unpack_pubkey(rest)?;
+                let (withholding_bps, _) = unpack_u16(rest)?;
+                Self::SetWithholding { destination, withholding_bps }
+            }
+            135 => Self::ReleaseWithheldProceeds,
+            136 => Self::CancelWithdrawal,
+            137 => {
+                let (status, _) = unpack_u8(rest)?;
+                Self::SetListingStatus { status }
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        })
+    }
+
+    // 敏感指令（释放托管资金、管理和授权方操作）只允许作为交易的顶层指令执行，
+    // 防止被包装程序通过CPI混淆调用者身份；新增指令时需要在此明确归类，
+    // 是否实际拦截由cpi-guard编译特性控制
+    pub fn requires_top_level(&self) -> bool {
+        match self {
+            Self::CreateAIModel { .. }
+            | Self::PurchaseAIModel { .. }
+            | Self::VerifyLicense
+            | Self::GetVersion
+            | Self::DepositBond { .. }
+            | Self::Sell { .. }
+            | Self::Buy { .. }
+            | Self::SetListingStatus { .. }
+            | Self::AttachTokenMetadata
+            | Self::SubmitReview { .. }
+            | Self::ClaimBadge { .. }
+            | Self::OpenDispute { .. }
+            | Self::AttachDisputeEvidence { .. }
+            | Self::SetModelArtifact { .. }
+            | Self::PublishModelVersion { .. }
+            | Self::AddChangelogEntry { .. }
+            | Self::DeprecateModel
+            | Self::FollowSeller
+            | Self::UnfollowSeller
+            | Self::FavoriteModel
+            | Self::UnfavoriteModel
+            | Self::UpdatePrice { .. }
+            | Self::SetModelCategory { .. }
+            | Self::SetLocalizedMetadata { .. }
+            | Self::SetMetadataUri { .. }
+            | Self::SetUpdateAuthority { .. }
+            | Self::SetModelOwner { .. }
+            | Self::SetModelCreator { .. }
+            | Self::VerifyCreator
+            | Self::RequireListingApproval
+            | Self::VerifyProvenance
+            | Self::CommitInferenceResult { .. }
+            | Self::EnqueueInferenceRequest { .. }
+            | Self::SubmitResult { .. }
+            | Self::RegisterWorker { .. }
+            | Self::StakeWorker { .. }
+            | Self::ClaimJob
+            | Self::ReassignExpiredClaim
+            | Self::ChallengeResult
+            | Self::SetRentalRate { .. }
+            | Self::OpenRentalStream { .. }
+            | Self::FundCommission { .. }
+            | Self::SubmitMilestone { .. }
+            | Self::ApproveMilestone { .. }
+            | Self::DisputeMilestone { .. }
+            | Self::SetSubscriptionTiers { .. }
+            | Self::Subscribe { .. }
+            | Self::ChangeTier { .. }
+            | Self::VerifySubscription { .. }
+            | Self::FundSubscriptionRenewal { .. }
+            | Self::CreateOrganization { .. }
+            | Self::BindApiKey { .. }
+            | Self::RecordUsage { .. }
+            | Self::CancelOrderNonces { .. }
+            | Self::SettleSignedOrder { .. }
+            | Self::SetMaxSupply { .. }
+            | Self::SetOpenEditionEnd { .. }
+            | Self::FinalizeOpenEdition
+            | Self::SetUpgradeDiscount { .. }
+            | Self::UpgradeLicense { .. }
+            | Self::SetLicenseTransferability { .. }
+            | Self::TransferLicense { .. }
+            | Self::RenewLicense
+            | Self::SetUsageUnitPrice { .. }
+            | Self::TopUpCredits { .. }
+            | Self::SettleUsage
+            | Self::BuyCredits { .. }
+            | Self::PurchaseWithCredits { .. }
+            | Self::PurchaseWithPoints { .. }
+            | Self::ClaimAchievement { .. }
+            | Self::CreateAirdropCampaign { .. }
+            | Self::AirdropLicenses
+            | Self::ClaimAirdrop { .. }
+            | Self::SetPresale { .. }
+            | Self::PurchasePresale { .. }
+            | Self::AnnounceListing { .. }
+            | Self::SetOraclePricing { .. }
+            | Self::PurchaseAtOraclePrice { .. }
+            | Self::SetDropThrottle { .. }
+            | Self::SetVerificationRequirement { .. }
+            | Self::SetListingCharity { .. }
+            | Self::SetBuyerCharity { .. }
+            | Self::SetWithholding { .. } => false,
+            Self::InitializeConfig
+            | Self::SetFeatureFlags { .. }
+            | Self::SetRecoveryGuardians { .. }
+            | Self::ProposeRecovery { .. }
+            | Self::ExecuteRecovery
+            | Self::CancelRecovery
+            | Self::InitializeTreasury { .. }
+            | Self::QueueWithdrawal { .. }
+            | Self::ExecuteWithdrawal
+            | Self::CancelWithdrawal
+            | Self::InitializeBadgeMint { .. }
+            | Self::SetArtifactAttestor { .. }
+            | Self::SetListingOracle { .. }
+            | Self::SetInferenceArbiter { .. }
+            | Self::SetCommissionArbiter { .. }
+            | Self::SetMeteringAuthority { .. }
+            | Self::SetFeaturedList { .. }
+            | Self::RegisterCategory { .. }
+            | Self::RegisterProvenanceVerifier { .. }
+            | Self::GrantRole { .. }
+            | Self::RevokeRole { .. }
+            | Self::ConfigureCreditMint
+            | Self::SetLoyaltyRates { .. }
+            | Self::InitializeAchievementMint { .. }
+            | Self::SetPriceBounds { .. }
+            | Self::SetFeeSchedule { .. }
+            | Self::RegisterCharity { .. }
+            | Self::UpdatePriceFeed { .. }
+            | Self::WithdrawProceeds { .. }
+            | Self::WithdrawOrganizationFunds { .. }
+            | Self::WithdrawCharityDonations { .. }
+            | Self::ReleaseWithheldProceeds
+            | Self::WithdrawBond { .. }
+            | Self::CancelTrade
+            | Self::ExecuteSale { .. }
+            | Self::AttestArtifact { .. }
+            | Self::ApproveListing { .. }
+            | Self::CancelInferenceRequest
+            | Self::UnstakeWorker { .. }
+            | Self::SlashWorker
+            | Self::SettleInferenceRequest
+            | Self::UpholdResult
+            | Self::WithdrawRentalStream
+            | Self::CloseRentalStream
+            | Self::ClaimMilestone { .. }
+            | Self::ResolveMilestoneDispute { .. }
+            | Self::CloseCommission
+            | Self::RenewSubscription
+            | Self::LapseSubscription
+            | Self::CancelSubscription
+            | Self::SetOrganizationMember { .. }
+            | Self::RemoveOrganizationMember { .. }
+            | Self::RevokeLicense { .. }
+            | Self::BurnCredits { .. }
+            | Self::CloseAirdropCampaign
+            => true,
+        }
+    }
+
+    // 将指令序列化为指令数据，格式与unpack一致
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            Self::CreateAIModel {
+                name,
+                description,
+                price,
+                pricing_mode,
+                license_tiers,
+                per_seat,
+                volume_discounts,
+                vault_proceeds,
+                model_file,
+                model_format,
+                license,
+            } => {
+                buf.push(0);
+                pack_string(&mut buf, name);
+                pack_string(&mut buf, description);
+                buf.extend_from_slice(&price.to_le_bytes());
+                buf.push(*pricing_mode as u8);
+                buf.push(license_tiers.len() as u8);
+                for tier in license_tiers {
+                    buf.extend_from_slice(&tier.price.to_le_bytes());
+                    pack_string(&mut buf, &tier.terms_uri);
+                    buf.extend_from_slice(&tier.requests_per_epoch.to_le_bytes());
+                    buf.push(tier.revocable as u8);
+                    buf.extend_from_slice(&tier.term_slots.to_le_bytes());
+                }
+                buf.push(*per_seat as u8);
+                buf.push(volume_discounts.len() as u8);
+                for discount in volume_discounts {
+                    buf.extend_from_slice(&discount.min_seats.to_le_bytes());
+                    buf.extend_from_slice(&discount.discount_bps.to_le_bytes());
+                }
+                buf.push(*vault_proceeds as u8);
+                pack_bytes(&mut buf, model_file);
+                buf.push(*model_format as u8);
+                pack_string(&mut buf, license);
+            }
+            Self::PurchaseAIModel {
+                amount,
+                tier_index,
+                quantity,
+                purchase_index,
+                acknowledge_deprecation,
+                reference,
+            } => {
+                buf.push(1);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(*tier_index);
+                buf.extend_from_slice(&quantity.to_le_bytes());
+                buf.extend_from_slice(&purchase_index.to_le_bytes());
+                buf.push(*acknowledge_deprecation as u8);
+                buf.extend_from_slice(reference);
+            }
+            Self::VerifyLicense => buf.push(2),
+            Self::WithdrawProceeds { amount } => {
+                buf.push(3);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::InitializeConfig => buf.push(4),
+            Self::GetVersion => buf.push(5),
+            Self::SetFeatureFlags { feature_flags } => {
+                buf.push(6);
+                buf.extend_from_slice(&feature_flags.to_le_bytes());
+            }
+            Self::SetRecoveryGuardians {
+                guardians,
+                threshold,
+                recovery_delay,
+            } => {
+                buf.push(7);
+                buf.push(guardians.len() as u8);
+                for guardian in guardians {
+                    buf.extend_from_slice(guardian.as_ref());
+                }
+                buf.push(*threshold);
+                buf.extend_from_slice(&recovery_delay.to_le_bytes());
+            }
+            Self::ProposeRecovery {
+                amount,
+                destination,
+                evidence_hash,
+            } => {
+                buf.push(8);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(destination.as_ref());
+                buf.extend_from_slice(evidence_hash);
+            }
+            Self::ExecuteRecovery => buf.push(9),
+            Self::CancelRecovery => buf.push(10),
+            Self::InitializeTreasury { withdrawal_delay } => {
+                buf.push(11);
+                buf.extend_from_slice(&withdrawal_delay.to_le_bytes());
+            }
+            Self::QueueWithdrawal { amount, destination } => {
+                buf.push(12);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(destination.as_ref());
+            }
+            Self::ExecuteWithdrawal => buf.push(13),
+            Self::DepositBond { amount } => {
+                buf.push(14);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::WithdrawBond { amount } => {
+                buf.push(15);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::Sell {
+                price,
+                tier_index,
+                quantity,
+                draft,
+            } => {
+                buf.push(16);
+                buf.extend_from_slice(&price.to_le_bytes());
+                buf.push(*tier_index);
+                buf.extend_from_slice(&quantity.to_le_bytes());
+                buf.push(*draft as u8);
+            }
+            Self::Buy {
+                price,
+                tier_index,
+                quantity,
+            } => {
+                buf.push(17);
+                buf.extend_from_slice(&price.to_le_bytes());
+                buf.push(*tier_index);
+                buf.extend_from_slice(&quantity.to_le_bytes());
+            }
+            Self::CancelTrade => buf.push(18),
+            Self::ExecuteSale { purchase_index } => {
+                buf.push(19);
+                buf.extend_from_slice(&purchase_index.to_le_bytes());
+            }
+            Self::AttachTokenMetadata => buf.push(20),
+            Self::SubmitReview { rating, content_hash } => {
+                buf.push(21);
+                buf.push(*rating);
+                buf.extend_from_slice(content_hash);
+            }
+            Self::InitializeBadgeMint { kind } => {
+                buf.push(22);
+                buf.push(*kind);
+            }
+            Self::ClaimBadge { kind } => {
+                buf.push(23);
+                buf.push(*kind);
+            }
+            Self::OpenDispute { evidence_hash } => {
+                buf.push(24);
+                buf.extend_from_slice(evidence_hash);
+            }
+            Self::AttachDisputeEvidence { evidence_hash } => {
+                buf.push(25);
+                buf.extend_from_slice(evidence_hash);
+            }
+            Self::SetModelArtifact {
+                storage_class,
+                artifact_uri,
+                artifact_hash,
+                artifact_size_bytes,
+            } => {
+                buf.push(26);
+                buf.push(*storage_class);
+                pack_string(&mut buf, artifact_uri);
+                buf.extend_from_slice(artifact_hash);
+                buf.extend_from_slice(&artifact_size_bytes.to_le_bytes());
+            }
+            Self::SetArtifactAttestor { attestor } => {
+                buf.push(27);
+                buf.extend_from_slice(attestor.as_ref());
+            }
+            Self::AttestArtifact { artifact_hash } => {
+                buf.push(28);
+                buf.extend_from_slice(artifact_hash);
+            }
+            Self::PublishModelVersion { version, artifact_hash } => {
+                buf.push(29);
+                buf.extend_from_slice(&version.major.to_le_bytes());
+                buf.extend_from_slice(&version.minor.to_le_bytes());
+                buf.extend_from_slice(&version.patch.to_le_bytes());
+                buf.extend_from_slice(artifact_hash);
+            }
+            Self::AddChangelogEntry {
+                content_hash,
+                uri,
+                summary,
+            } => {
+                buf.push(30);
+                buf.extend_from_slice(content_hash);
+                pack_string(&mut buf, uri);
+                pack_string(&mut buf, summary);
+            }
+            Self::DeprecateModel => buf.push(31),
+            Self::FollowSeller => buf.push(32),
+            Self::UnfollowSeller => buf.push(33),
+            Self::FavoriteModel => buf.push(34),
+            Self::UnfavoriteModel => buf.push(35),
+            Self::UpdatePrice { price } => {
+                buf.push(36);
+                buf.extend_from_slice(&price.to_le_bytes());
+            }
+            Self::SetFeaturedList { list_id, label } => {
+                buf.push(37);
+                buf.extend_from_slice(&list_id.to_le_bytes());
+                pack_string(&mut buf, label);
+            }
+            Self::RegisterCategory { id, parent, label } => {
+                buf.push(38);
+                buf.extend_from_slice(&id.to_le_bytes());
+                buf.extend_from_slice(&parent.to_le_bytes());
+                pack_string(&mut buf, label);
+            }
+            Self::SetModelCategory { category_id } => {
+                buf.push(39);
+                buf.extend_from_slice(&category_id.to_le_bytes());
+            }
+            Self::SetLocalizedMetadata {
+                lang,
+                name,
+                description_uri,
+            } => {
+                buf.push(40);
+                pack_string(&mut buf, lang);
+                pack_string(&mut buf, name);
+                pack_string(&mut buf, description_uri);
+            }
+            Self::SetMetadataUri {
+                metadata_uri,
+                metadata_hash,
+            } => {
+                buf.push(41);
+                pack_string(&mut buf, metadata_uri);
+                buf.extend_from_slice(metadata_hash);
+            }
+            Self::SetUpdateAuthority { update_authority } => {
+                buf.push(42);
+                buf.extend_from_slice(update_authority.as_ref());
+            }
+            Self::SetModelOwner { new_owner } => {
+                buf.push(43);
+                buf.extend_from_slice(new_owner.as_ref());
+            }
+            Self::SetModelCreator { creator } => {
+                buf.push(44);
+                buf.extend_from_slice(creator.as_ref());
+            }
+            Self::VerifyCreator => buf.push(45),
+            Self::SetListingOracle { oracle } => {
+                buf.push(46);
+                buf.extend_from_slice(oracle.as_ref());
+            }
+            Self::RequireListingApproval => buf.push(47),
+            Self::ApproveListing {
+                artifact_hash,
+                attestation_hash,
+            } => {
+                buf.push(48);
+                buf.extend_from_slice(artifact_hash);
+                buf.extend_from_slice(attestation_hash);
+            }
+            Self::RegisterProvenanceVerifier {
+                verifier_id,
+                proof_system,
+                claim,
+                verifying_key,
+            } => {
+                buf.push(49);
+                buf.extend_from_slice(&verifier_id.to_le_bytes());
+                buf.push(*proof_system);
+                pack_string(&mut buf, claim);
+                pack_bytes(&mut buf, verifying_key);
+            }
+            Self::VerifyProvenance => buf.push(50),
+            Self::CommitInferenceResult { input_hash, output_hash } => {
+                buf.push(51);
+                buf.extend_from_slice(input_hash);
+                buf.extend_from_slice(output_hash);
+            }
+            Self::EnqueueInferenceRequest {
+                request_id,
+                prompt_hash,
+                max_price,
+                deadline,
+                challenge_window,
+            } => {
+                buf.push(52);
+                buf.extend_from_slice(&request_id.to_le_bytes());
+                buf.extend_from_slice(prompt_hash);
+                buf.extend_from_slice(&max_price.to_le_bytes());
+                buf.extend_from_slice(&deadline.to_le_bytes());
+                buf.extend_from_slice(&challenge_window.to_le_bytes());
+            }
+            Self::SubmitResult {
+                output_hash,
+                proof_uri,
+                price,
+            } => {
+                buf.push(53);
+                buf.extend_from_slice(output_hash);
+                pack_string(&mut buf, proof_uri);
+                buf.extend_from_slice(&price.to_le_bytes());
+            }
+            Self::CancelInferenceRequest => buf.push(54),
+            Self::RegisterWorker {
+                hardware_class,
+                supported_formats,
+                price_per_unit,
+                endpoint_hash,
+            } => {
+                buf.push(55);
+                buf.push(*hardware_class);
+                buf.push(*supported_formats);
+                buf.extend_from_slice(&price_per_unit.to_le_bytes());
+                buf.extend_from_slice(endpoint_hash);
+            }
+            Self::StakeWorker { amount } => {
+                buf.push(56);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::UnstakeWorker { amount } => {
+                buf.push(57);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::SetInferenceArbiter { arbiter } => {
+                buf.push(58);
+                buf.extend_from_slice(arbiter.as_ref());
+            }
+            Self::SlashWorker => buf.push(59),
+            Self::ClaimJob => buf.push(60),
+            Self::ReassignExpiredClaim => buf.push(61),
+            Self::ChallengeResult => buf.push(62),
+            Self::SettleInferenceRequest => buf.push(63),
+            Self::UpholdResult => buf.push(64),
+            Self::SetRentalRate { rate_per_slot } => {
+                buf.push(65);
+                buf.extend_from_slice(&rate_per_slot.to_le_bytes());
+            }
+            Self::OpenRentalStream { deposit } => {
+                buf.push(66);
+                buf.extend_from_slice(&deposit.to_le_bytes());
+            }
+            Self::WithdrawRentalStream => buf.push(67),
+            Self::CloseRentalStream => buf.push(68),
+            Self::FundCommission {
+                commission_id,
+                seller,
+                milestone_amounts,
+            } => {
+                buf.push(69);
+                buf.extend_from_slice(&commission_id.to_le_bytes());
+                buf.extend_from_slice(seller.as_ref());
+                buf.push(milestone_amounts.len() as u8);
+                for amount in milestone_amounts {
+                    buf.extend_from_slice(&amount.to_le_bytes());
+                }
+            }
+            Self::SubmitMilestone { index, deliverable_hash } => {
+                buf.push(70);
+                buf.push(*index);
+                buf.extend_from_slice(deliverable_hash);
+            }
+            Self::ApproveMilestone { index } => {
+                buf.push(71);
+                buf.push(*index);
+            }
+            Self::ClaimMilestone { index } => {
+                buf.push(72);
+                buf.push(*index);
+            }
+            Self::DisputeMilestone { index } => {
+                buf.push(73);
+                buf.push(*index);
+            }
+            Self::SetCommissionArbiter { arbiter } => {
+                buf.push(74);
+                buf.extend_from_slice(arbiter.as_ref());
+            }
+            Self::ResolveMilestoneDispute { index, release } => {
+                buf.push(75);
+                buf.push(*index);
+                buf.push(*release as u8);
+            }
+            Self::CloseCommission => buf.push(76),
+            Self::SetSubscriptionTiers {
+                period_slots,
+                tiers,
+                grace_period_slots,
+            } => {
+                buf.push(77);
+                buf.extend_from_slice(&period_slots.to_le_bytes());
+                buf.push(tiers.len() as u8);
+                for tier in tiers {
+                    buf.extend_from_slice(&tier.price.to_le_bytes());
+                    buf.extend_from_slice(&tier.access_scopes.to_le_bytes());
+                }
+                buf.extend_from_slice(&grace_period_slots.to_le_bytes());
+            }
+            Self::Subscribe { tier } => {
+                buf.push(78);
+                buf.push(*tier);
+            }
+            Self::ChangeTier { tier } => {
+                buf.push(79);
+                buf.push(*tier);
+            }
+            Self::VerifySubscription { access_scopes } => {
+                buf.push(80);
+                buf.extend_from_slice(&access_scopes.to_le_bytes());
+            }
+            Self::FundSubscriptionRenewal { amount } => {
+                buf.push(81);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::RenewSubscription => buf.push(82),
+            Self::LapseSubscription => buf.push(83),
+            Self::CancelSubscription => buf.push(84),
+            Self::CreateOrganization { org_id, name } => {
+                buf.push(85);
+                buf.extend_from_slice(&org_id.to_le_bytes());
+                pack_string(&mut buf, name);
+            }
+            Self::SetOrganizationMember { member, role } => {
+                buf.push(86);
+                buf.extend_from_slice(member.as_ref());
+                buf.push(*role);
+            }
+            Self::RemoveOrganizationMember { member } => {
+                buf.push(87);
+                buf.extend_from_slice(member.as_ref());
+            }
+            Self::WithdrawOrganizationFunds { amount } => {
+                buf.push(88);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::GrantRole { role, holder } => {
+                buf.push(89);
+                buf.push(*role);
+                buf.extend_from_slice(holder.as_ref());
+            }
+            Self::RevokeRole { role, holder } => {
+                buf.push(90);
+                buf.push(*role);
+                buf.extend_from_slice(holder.as_ref());
+            }
+            Self::BindApiKey { api_key_hash } => {
+                buf.push(91);
+                buf.extend_from_slice(api_key_hash);
+            }
+            Self::SetMeteringAuthority { authority } => {
+                buf.push(92);
+                buf.extend_from_slice(authority.as_ref());
+            }
+            Self::RecordUsage { requests } => {
+                buf.push(93);
+                buf.extend_from_slice(&requests.to_le_bytes());
+            }
+            Self::CancelOrderNonces { min_nonce } => {
+                buf.push(94);
+                buf.extend_from_slice(&min_nonce.to_le_bytes());
+            }
+            Self::SettleSignedOrder {
+                order,
+                fill_quantity,
+                purchase_index,
+                acknowledge_deprecation,
+            } => {
+                buf.push(95);
+                buf.extend_from_slice(&order.price.to_le_bytes());
+                buf.push(order.tier);
+                buf.extend_from_slice(&order.quantity.to_le_bytes());
+                buf.extend_from_slice(&order.expires_at.to_le_bytes());
+                buf.extend_from_slice(&order.nonce.to_le_bytes());
+                buf.extend_from_slice(&fill_quantity.to_le_bytes());
+                buf.extend_from_slice(&purchase_index.to_le_bytes());
+                buf.push(*acknowledge_deprecation as u8);
+            }
+            Self::SetMaxSupply { max_supply } => {
+                buf.push(96);
+                buf.extend_from_slice(&max_supply.to_le_bytes());
+            }
+            Self::SetOpenEditionEnd { end_slot } => {
+                buf.push(97);
+                buf.extend_from_slice(&end_slot.to_le_bytes());
+            }
+            Self::FinalizeOpenEdition => buf.push(98),
+            Self::SetUpgradeDiscount { discount_bps } => {
+                buf.push(99);
+                buf.extend_from_slice(&discount_bps.to_le_bytes());
+            }
+            Self::UpgradeLicense {
+                tier_index,
+                quantity,
+                purchase_index,
+            } => {
+                buf.push(100);
+                buf.push(*tier_index);
+                buf.extend_from_slice(&quantity.to_le_bytes());
+                buf.extend_from_slice(&purchase_index.to_le_bytes());
+            }
+            Self::SetLicenseTransferability { transferability, royalty_bps } => {
+                buf.push(101);
+                buf.push(*transferability);
+                buf.extend_from_slice(&royalty_bps.to_le_bytes());
+            }
+            Self::TransferLicense { price, purchase_index } => {
+                buf.push(102);
+                buf.extend_from_slice(&price.to_le_bytes());
+                buf.extend_from_slice(&purchase_index.to_le_bytes());
+            }
+            Self::RevokeLicense { reason_hash } => {
+                buf.push(103);
+                buf.extend_from_slice(reason_hash);
+            }
+            Self::RenewLicense => buf.push(104),
+            Self::SetUsageUnitPrice { unit_price } => {
+                buf.push(105);
+                buf.extend_from_slice(&unit_price.to_le_bytes());
+            }
+            Self::TopUpCredits { mint, amount } => {
+                buf.push(106);
+                buf.extend_from_slice(mint.as_ref());
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::SettleUsage => buf.push(107),
+            Self::BurnCredits { amount } => {
+                buf.push(108);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::ConfigureCreditMint => buf.push(109),
+            Self::BuyCredits { amount } => {
+                buf.push(110);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::PurchaseWithCredits {
+                amount,
+                tier_index,
+                quantity,
+                purchase_index,
+                acknowledge_deprecation,
+            } => {
+                buf.push(111);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(*tier_index);
+                buf.extend_from_slice(&quantity.to_le_bytes());
+                buf.extend_from_slice(&purchase_index.to_le_bytes());
+                buf.push(*acknowledge_deprecation as u8);
+            }
+            Self::PurchaseWithPoints {
+                amount,
+                tier_index,
+                quantity,
+                purchase_index,
+                acknowledge_deprecation,
+                points,
+            } => {
+                buf.push(112);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(*tier_index);
+                buf.extend_from_slice(&quantity.to_le_bytes());
+                buf.extend_from_slice(&purchase_index.to_le_bytes());
+                buf.push(*acknowledge_deprecation as u8);
+                buf.extend_from_slice(&points.to_le_bytes());
+            }
+            Self::SetLoyaltyRates { accrual_bps, redemption_rate } => {
+                buf.push(113);
+                buf.extend_from_slice(&accrual_bps.to_le_bytes());
+                buf.extend_from_slice(&redemption_rate.to_le_bytes());
+            }
+            Self::InitializeAchievementMint { achievement } => {
+                buf.push(114);
+                buf.push(*achievement);
+            }
+            Self::ClaimAchievement { achievement, mint_badge } => {
+                buf.push(115);
+                buf.push(*achievement);
+                buf.push(*mint_badge as u8);
+            }
+            Self::CreateAirdropCampaign { campaign_id, tier_index, max_recipients, merkle_root } => {
+                buf.push(116);
+                buf.extend_from_slice(&campaign_id.to_le_bytes());
+                buf.push(*tier_index);
+                buf.extend_from_slice(&max_recipients.to_le_bytes());
+                buf.extend_from_slice(merkle_root);
+            }
+            Self::AirdropLicenses => buf.push(117),
+            Self::CloseAirdropCampaign => buf.push(118),
+            Self::ClaimAirdrop { proof } => {
+                buf.push(119);
+                buf.push(proof.len() as u8);
+                for node in proof {
+                    buf.extend_from_slice(node);
+                }
+            }
+            Self::SetPresale { merkle_root, presale_price, public_sale_slot } => {
+                buf.push(120);
+                buf.extend_from_slice(merkle_root);
+                buf.extend_from_slice(&presale_price.to_le_bytes());
+                buf.extend_from_slice(&public_sale_slot.to_le_bytes());
+            }
+            Self::PurchasePresale {
+                tier_index,
+                quantity,
+                purchase_index,
+                acknowledge_deprecation,
+                allocation,
+                proof,
+            } => {
+                buf.push(121);
+                buf.push(*tier_index);
+                buf.extend_from_slice(&quantity.to_le_bytes());
+                buf.extend_from_slice(&purchase_index.to_le_bytes());
+                buf.push(*acknowledge_deprecation as u8);
+                buf.extend_from_slice(&allocation.to_le_bytes());
+                buf.push(proof.len() as u8);
+                for node in proof {
+                    buf.extend_from_slice(node);
+                }
+            }
+            Self::AnnounceListing { kind, nonce } => {
+                buf.push(122);
+                buf.push(*kind);
+                buf.extend_from_slice(&nonce.to_le_bytes());
+            }
+            Self::UpdatePriceFeed { feed_id, price, confidence } => {
+                buf.push(123);
+                buf.extend_from_slice(&feed_id.to_le_bytes());
+                buf.extend_from_slice(&price.to_le_bytes());
+                buf.extend_from_slice(&confidence.to_le_bytes());
+            }
+            Self::SetOraclePricing {
+                price_feed,
+                quote_price,
+                max_staleness_slots,
+                max_confidence_bps,
+                fallback_price,
+            } => {
+                buf.push(124);
+                buf.extend_from_slice(price_feed.as_ref());
+                buf.extend_from_slice(&quote_price.to_le_bytes());
+                buf.extend_from_slice(&max_staleness_slots.to_le_bytes());
+                buf.extend_from_slice(&max_confidence_bps.to_le_bytes());
+                buf.extend_from_slice(&fallback_price.to_le_bytes());
+            }
+            Self::PurchaseAtOraclePrice {
+                max_amount,
+                tier_index,
+                quantity,
+                purchase_index,
+                acknowledge_deprecation,
+            } => {
+                buf.push(125);
+                buf.extend_from_slice(&max_amount.to_le_bytes());
+                buf.push(*tier_index);
+                buf.extend_from_slice(&quantity.to_le_bytes());
+                buf.extend_from_slice(&purchase_index.to_le_bytes());
+                buf.push(*acknowledge_deprecation as u8);
+            }
+            Self::SetPriceBounds { min_price, max_price } => {
+                buf.push(126);
+                buf.extend_from_slice(&min_price.to_le_bytes());
+                buf.extend_from_slice(&max_price.to_le_bytes());
+            }
+            Self::SetDropThrottle { cooldown_slots, max_per_slot } => {
+                buf.push(127);
+                buf.extend_from_slice(&cooldown_slots.to_le_bytes());
+                buf.extend_from_slice(&max_per_slot.to_le_bytes());
+            }
+            Self::SetVerificationRequirement { gatekeeper_network } => {
+                buf.push(128);
+                buf.extend_from_slice(gatekeeper_network.as_ref());
+            }
+            Self::SetFeeSchedule { primary_fee_bps, secondary_fee_bps } => {
+                buf.push(129);
+                buf.extend_from_slice(&primary_fee_bps.to_le_bytes());
+                buf.extend_from_slice(&secondary_fee_bps.to_le_bytes());
+            }
+            Self::RegisterCharity { wallet, active } => {
+                buf.push(130);
+                buf.extend_from_slice(wallet.as_ref());
+                buf.push(*active as u8);
+            }
+            Self::SetListingCharity { charity, charity_bps } => {
+                buf.push(131);
+                buf.extend_from_slice(charity.as_ref());
+                buf.extend_from_slice(&charity_bps.to_le_bytes());
+            }
+            Self::SetBuyerCharity { charity, charity_bps } => {
+                buf.push(132);
+                buf.extend_from_slice(charity.as_ref());
+                buf.extend_from_slice(&charity_bps.to_le_bytes());
+            }
+            Self::WithdrawCharityDonations { amount } => {
+                buf.push(133);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::SetWithholding { destination, withholding_bps } => {
+                buf.push(134);
+                buf.extend_from_slice(destination.as_ref());
+                buf.extend_from_slice(&withholding_bps.to_le_bytes());
+            }
+            Self::ReleaseWithheldProceeds => buf.push(135),
+            Self::CancelWithdrawal => buf.push(136),
+            Self::SetListingStatus { status } => {
+                buf.push(137);
+                buf.push(*status);
+            }
+        }
+        buf
+    }
+}
+
+fn pack_bytes(buf: &mut Vec<u8>, val: &[u8]) {
+    buf.extend_from_slice(&(val.len() as u32).to_le_bytes());
+    buf.extend_from_slice(val);
+}
+
+fn pack_string(buf: &mut Vec<u8>, val: &str) {
+    pack_bytes(buf, val.as_bytes());
+}
+
+fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+    let (&val, rest) = input.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+    Ok((val, rest))
+}
+
+fn unpack_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+    if input.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (val, rest) = input.split_at(2);
+    Ok((u16::from_le_bytes(val.try_into().unwrap()), rest))
+}
+
+fn unpack_u32(input: &[u8]) -> Result<(u32, &[u8]), ProgramError> {
+    if input.len() < 4 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (val, rest) = input.split_at(4);
+    Ok((u32::from_le_bytes(val.try_into().unwrap()), rest))
+}
+
+fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+    if input.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (val, rest) = input.split_at(8);
+    Ok((u64::from_le_bytes(val.try_into().unwrap()), rest))
+}
+
+fn unpack_i64(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
+    let (val, rest) = unpack_u64(input)?;
+    Ok((val as i64, rest))
+}
+
+fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+    let (val, rest) = unpack_hash(input)?;
+    Ok((Pubkey::new_from_array(val), rest))
+}
+
+// 解析32字节的哈希值
+fn unpack_hash(input: &[u8]) -> Result<([u8; 32], &[u8]), ProgramError> {
+    if input.len() < 32 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (val, rest) = input.split_at(32);
+    Ok((val.try_into().unwrap(), rest))
+}
+
+// 解析以u32长度为前缀的字节数组
+// 解析u8节点数 + 每个32字节节点的Merkle证明
+fn unpack_merkle_proof(input: &[u8]) -> Result<(Vec<[u8; 32]>, &[u8]), ProgramError> {
+    let (count, mut rest) = unpack_u8(input)?;
+    if count as usize > MAX_MERKLE_PROOF_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let mut proof = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let (node, next) = unpack_hash(rest)?;
+        proof.push(node);
+        rest = next;
+    }
+    Ok((proof, rest))
+}
+
+// 解析u32长度前缀的字节串，返回对指令数据的借用
+fn unpack_byte_slice(input: &[u8]) -> Result<(&[u8], &[u8]), ProgramError> {
+    let (len, rest) = unpack_u32(input)?;
+    let len = len as usize;
+    if rest.len() < len {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(rest.split_at(len))
+}
+
+fn unpack_bytes(input: &[u8]) -> Result<(Vec<u8>, &[u8]), ProgramError> {
+    let (val, rest) = unpack_byte_slice(input)?;
+    Ok((val.to_vec(), rest))
+}
+
+fn unpack_string(input: &[u8]) -> Result<(String, &[u8]), ProgramError> {
+    let (bytes, rest) = unpack_bytes(input)?;
+    let val = String::from_utf8(bytes).map_err(|_| ProgramError::InvalidInstructionData)?;
+    Ok((val, rest))
+}
+
+// 检查当前指令是否由交易直接调用，通过CPI调用时调用栈高度会大于顶层高度
+fn assert_top_level_invocation() -> ProgramResult {
+    if get_stack_height() > TRANSACTION_LEVEL_STACK_HEIGHT {
+        msg!("Instruction cannot be invoked via CPI");
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+// 通过系统程序CPI创建由本程序拥有的PDA账户，租金由付款账户承担
+fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    program_id: &Pubkey,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            new_account.key,
+            rent.minimum_balance(space),
+            space as u64,
+            program_id,
+        ),
+        &[payer.clone(), new_account.clone(), system_program_account.clone()],
+        &[signer_seeds],
+    )
+}
+
+// 由本程序拥有的funder账户直接划转租金创建PDA账户：funder带有数据，不能作为系统程序转账的付款方，
+// 因此先划转lamports，再以PDA签名分配空间并指定所有者
+fn create_pda_account_funded_by<'a>(
+    funder: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    program_id: &Pubkey,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let required = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(new_account.lamports());
+    **funder.lamports.borrow_mut() = funder
+        .lamports()
+        .checked_sub(required)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    **new_account.lamports.borrow_mut() = new_account
+        .lamports()
+        .checked_add(required)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    invoke_signed(
+        &system_instruction::allocate(new_account.key, space as u64),
+        &[new_account.clone(), system_program_account.clone()],
+        &[signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(new_account.key, program_id),
+        &[new_account.clone(), system_program_account.clone()],
+        &[signer_seeds],
+    )
+}
+
+// 关闭本程序拥有的账户，将全部lamports转给destination并清空数据
+fn close_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let destination_lamports = destination
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **destination.lamports.borrow_mut() = destination_lamports;
+    **account.lamports.borrow_mut() = 0;
+    account.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+// 关闭本程序拥有的账户，把lamports退给创建时出资支付租金的账户。
+// 账户易手或由他人关闭时租金仍归原出资人，出资账户须随指令传入且可写
+fn close_account_to_rent_payer(account: &AccountInfo, rent_payer: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let destination = accounts
+        .iter()
+        .find(|candidate| candidate.key == rent_payer)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if !destination.is_writable {
+        return Err(ProgramError::InvalidArgument);
+    }
+    close_account(account, destination)
+}
+
+// 解析只包含一个签名、且签名/公钥/消息都位于本指令数据内的Ed25519程序指令，返回签名公钥和消息。
+// 布局: u8签名数 + u8填充 + 7个u16偏移（签名、签名所在指令、公钥、公钥所在指令、消息、消息长度、消息所在指令）
+fn ed25519_signed_message(data: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+    const OFFSETS_START: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+    if data.len() < OFFSETS_START + OFFSETS_LEN || data[0] != 1 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let read_u16 = |index: usize| {
+        let at = OFFSETS_START + index * 2;
+        u16::from_le_bytes([data[at], data[at + 1]])
+    };
+    // 引用其他指令中的数据时Ed25519程序校验的内容与本指令无关，因此只接受u16::MAX（本指令）
+    if [read_u16(1), read_u16(3), read_u16(6)].iter().any(|index| *index != u16::MAX) {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let public_key_offset = read_u16(2) as usize;
+    let message_offset = read_u16(4) as usize;
+    let message_size = read_u16(5) as usize;
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    Ok((Pubkey::new_from_array(public_key.try_into().unwrap()), message))
+}
+
+// 通过指令sysvar检查本指令的前一条指令是signer对message的Ed25519签名校验；
+// Ed25519程序在签名无效时会让整笔交易失败，所以这里只需确认它校验的是正确的公钥和消息
+fn verify_ed25519_signature(instructions_account: &AccountInfo, signer: &Pubkey, message: &[u8]) -> ProgramResult {
+    let current_index = sysvar_instructions::load_current_index_checked(instructions_account)?;
+    let previous_index = current_index.checked_sub(1).ok_or(ProgramError::InvalidArgument)?;
+    let previous = sysvar_instructions::load_instruction_at_checked(previous_index as usize, instructions_account)?;
+    if previous.program_id != ed25519_program::id() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (public_key, signed) = ed25519_signed_message(&previous.data)?;
+    if public_key != *signer || signed != message {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+fn load_organization(program_id: &Pubkey, organization_account: &AccountInfo) -> Result<Organization, ProgramError> {
+    if organization_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Organization::unpack(&organization_account.data.borrow())
+}
+
+// 校验签名者能否行使模型的更新权限：签名者本人即为更新权限，
+// 或更新权限是组织PDA、签名者是该组织的编辑或管理员，组织PDA在accounts中传入
+fn require_update_authority(
+    program_id: &Pubkey,
+    model: &AIModel,
+    authority_account: &AccountInfo,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if model.is_update_authority(authority_account.key) {
+        return Ok(());
+    }
+    let authority = model.effective_update_authority();
+    let organization_account = accounts
+        .iter()
+        .find(|account| *account.key == authority)
+        .ok_or(ProgramError::InvalidArgument)?;
+    load_organization(program_id, organization_account)?.require_role(authority_account.key, OrganizationRole::Editor)
+}
+
+// 校验签名者能否行使模型所有者的权限：签名者本人即为所有者，
+// 或所有者是组织PDA、签名者是该组织的管理员，组织PDA在accounts中传入
+fn require_model_owner(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    owner_account: &AccountInfo,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if owner == owner_account.key {
+        return Ok(());
+    }
+    let organization_account = accounts
+        .iter()
+        .find(|account| account.key == owner)
+        .ok_or(ProgramError::InvalidArgument)?;
+    load_organization_as_admin(program_id, organization_account, owner_account).map(|_| ())
+}
+
+// 定义一个处理程序函数来创建新的AIModel
+#[allow(clippy::too_many_arguments)]
+pub fn create_ai_model(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    name: String,
+    description: String,
+    price: u64,
+    pricing_mode: PricingMode,
+    license_tiers: Vec<LicenseTier>,
+    per_seat: bool,
+    volume_discounts: Vec<VolumeDiscount>,
+    vault_proceeds: bool,
+    model_file: &[u8],
+    model_format: ModelFormat,
+    license: String,
+) -> ProgramResult {
+    // 获取账户信息
+    let account_info_iter = &mut accounts.iter();
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let owner_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let instructions_account = next_account_info(account_info_iter).ok();
+
+    // 新账户和所有者都需要签名，新账户的签名用于系统程序创建账户
+    if !ai_model_account.is_signer || !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // 检查许可证档位数量和条款URI长度
+    if license_tiers.len() > MAX_LICENSE_TIERS {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if license_tiers.iter().any(|tier| tier.terms_uri.len() > MAX_TERMS_URI_LEN) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    validate_license(&license)?;
+    if name.len() > MAX_MODEL_NAME_LEN || description.len() > MAX_MODEL_DESCRIPTION_LEN || model_file.len() > MODEL_FILE_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // 检查批量折扣曲线，只有按席位售卖的模型才能设置折扣
+    if volume_discounts.len() > MAX_VOLUME_DISCOUNTS || (!per_seat && !volume_discounts.is_empty()) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if volume_discounts.iter().any(|discount| discount.discount_bps as u64 > BPS_DENOMINATOR) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let tier_prices: Vec<u64> = license_tiers.iter().map(|tier| tier.price).collect();
+    check_listing_prices(program_id, config_account, &[&[price][..], &tier_prices].concat())?;
+
+    // 传入指令sysvar时校验所有者对链上模型文件哈希的签名
+    let artifact_hash = solana_program::hash::hash(model_file).to_bytes();
+    if let Some(instructions_account) = instructions_account {
+        verify_ed25519_signature(instructions_account, owner_account.key, &artifact_hash)?;
+    }
+
+    // 通过系统程序创建AIModel账户，由所有者支付免租金额；账户已存在时创建会失败
+    let rent = Rent::get()?;
+    invoke(
+        &system_instruction::create_account(
+            owner_account.key,
+            ai_model_account.key,
+            rent.minimum_balance(AIModel::LEN),
+            AIModel::LEN as u64,
+            program_id,
+        ),
+        &[
+            owner_account.clone(),
+            ai_model_account.clone(),
+            system_program_account.clone(),
+        ],
+    )?;
+
+    // 初始化AIModel账户并存储数据
+    let now = Clock::get()?.unix_timestamp;
+    let ai_model_data = AIModel {
+        is_initialized: true,
+        name,
+        description,
+        owner: *owner_account.key,
+        update_authority: *owner_account.key,
+        // 创建时owner已签名，默认以owner为已验证的创作者
+        creator: *owner_account.key,
+        creator_verified: true,
+        price,
+        pricing_mode,
+        license_tiers,
+        per_seat,
+        volume_discounts,
+        vault_proceeds,
+        model_format,
+        license,
+        artifact_hash,
+        artifact_signed: instructions_account.is_some(),
+        created_at: now,
+        updated_at: now,
+        ..AIModel::default()
+    };
+    // 模型文件留空打包，再从指令数据直接写入账户缓冲区，不经过中间的Vec
+    let mut data = ai_model_account.data.borrow_mut();
+    ai_model_data.pack_into_slice(&mut data);
+    data[AIModelView::MODEL_FILE_OFFSET..AIModelView::MODEL_FILE_OFFSET + model_file.len()].copy_from_slice(model_file);
+
+    Ok(())
+}
+
+// 定义一个处理程序函数来购买AIModel，付款转给卖家（或卖家的收益金库）并生成购买凭证
+#[allow(clippy::too_many_arguments)]
+pub fn purchase_ai_model(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    tier_index: u8,
+    quantity: u32,
+    purchase_index: u32,
+    acknowledge_deprecation: bool,
+    redeem_points: u64,
+    reference: [u8; 32],
+) -> ProgramResult {
+    purchase_at_price(
+        program_id,
+        accounts,
+        amount,
+        tier_index,
+        quantity,
+        purchase_index,
+        acknowledge_deprecation,
+        SalePrice::Listed,
+        redeem_points,
+        reference,
+    )
+}
+
+// 定义一个处理程序函数，预售期间白名单中的钱包凭Merkle证明以预售价购买，并在购买节流PDA中累计限购
+#[allow(clippy::too_many_arguments)]
+pub fn purchase_presale(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    tier_index: u8,
+    quantity: u32,
+    purchase_index: u32,
+    acknowledge_deprecation: bool,
+    allocation: u32,
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let buyer_account = next_account_info(account_info_iter)?;
+    let system_program_account = accounts.get(5).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let purchase_throttle_account =
+        find_trailing_purchase_throttle(program_id, ai_model_account.key, buyer_account.key, accounts)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let clock = Clock::get()?;
+    let price = unpack_ai_model_boxed(ai_model_account)?.presale_total(
+        ai_model_account.key,
+        buyer_account.key,
+        allocation,
+        &proof,
+        tier_index,
+        quantity,
+        clock.slot,
+    )?;
+    // 先记入预售席位再结算；drop模型在settle_sale中会重新读取同一个节流PDA记录冷却
+    let mut throttle = open_purchase_throttle(
+        program_id,
+        ai_model_account.key,
+        buyer_account.key,
+        purchase_throttle_account,
+        buyer_account,
+        system_program_account,
+    )?;
+    throttle.record_presale(quantity, allocation)?;
+    throttle.updated_at = clock.unix_timestamp;
+    throttle.pack_into_slice(&mut purchase_throttle_account.data.borrow_mut());
+    purchase_at_price(
+        program_id,
+        accounts,
+        price,
+        tier_index,
+        quantity,
+        purchase_index,
+        acknowledge_deprecation,
+        SalePrice::Presale(price),
+        0,
+        [0u8; 32],
+    )
+}
+
+// 定义一个处理程序函数，按价格源换算的价格购买，成交价超过max_amount时失败
+pub fn purchase_at_oracle_price(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_amount: u64,
+    tier_index: u8,
+    quantity: u32,
+    purchase_index: u32,
+    acknowledge_deprecation: bool,
+) -> ProgramResult {
+    let (price_feed_account, sale_accounts) = accounts.split_first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let ai_model_account = sale_accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    if ai_model_account.owner != program_id || price_feed_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if !ai_model_data.is_oracle_priced() || ai_model_data.price_feed != *price_feed_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let feed = PriceFeed::unpack(&price_feed_account.data.borrow())?;
+    let price = ai_model_data.oracle_total(&feed, tier_index, quantity, Clock::get()?.slot)?;
+    if price > max_amount {
+        msg!("Oracle price {} exceeds max amount {}", price, max_amount);
+        return Err(ProgramError::InvalidArgument);
+    }
+    purchase_at_price(
+        program_id,
+        sale_accounts,
+        price,
+        tier_index,
+        quantity,
+        purchase_index,
+        acknowledge_deprecation,
+        SalePrice::Oracle(price),
+        0,
+        [0u8; 32],
+    )
+}
+
+// 直接购买的共用流程，price为成交价格的来源，reference写入购买凭证
+#[allow(clippy::too_many_arguments)]
+fn purchase_at_price(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    tier_index: u8,
+    quantity: u32,
+    purchase_index: u32,
+    acknowledge_deprecation: bool,
+    price: SalePrice,
+    redeem_points: u64,
+    reference: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let buyer_account = next_account_info(account_info_iter)?;
+    let seller_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let seller_stats_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let proceeds_vault_account = next_account_info(account_info_iter)?;
+    let sales_ledger_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let seller_bond_account = next_account_info(account_info_iter)?;
+    let buyer_stats_account = next_account_info(account_info_iter)?;
+    let buyer_receipts_account = next_account_info(account_info_iter)?;
+    let previous_receipt_account = if purchase_index > 0 {
+        Some(next_account_info(account_info_iter)?)
+    } else {
+        None
+    };
+    // 购买节流PDA、验证通行证、慈善机构PDA和Memo程序都是可选的尾部账户：节流PDA和Memo程序按地址区分，
+    // 验证通行证按所有者区分，其余归本程序所有的账户为慈善机构PDA
+    let trailing_accounts = account_info_iter.as_slice();
+    let purchase_throttle_account =
+        find_trailing_purchase_throttle(program_id, ai_model_account.key, buyer_account.key, trailing_accounts);
+    let verification_pass_account = find_trailing_verification_pass(trailing_accounts);
+    let charity_account = trailing_accounts.iter().find(|account| {
+        account.owner == program_id && purchase_throttle_account.map(|throttle| throttle.key) != Some(account.key)
+    });
+    let memo_program_account = trailing_accounts.iter().find(|account| *account.key == SPL_MEMO_PROGRAM_ID);
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    settle_sale(
+        program_id,
+        &SaleAccounts {
+            ai_model: ai_model_account,
+            seller: seller_account,
+            receipt: receipt_account,
+            seller_stats: seller_stats_account,
+            system_program: system_program_account,
+            proceeds_vault: proceeds_vault_account,
+            sales_ledger: sales_ledger_account,
+            treasury: treasury_account,
+            seller_bond: seller_bond_account,
+            buyer_stats: buyer_stats_account,
+            buyer_receipts: buyer_receipts_account,
+            previous_receipt: previous_receipt_account,
+            purchase_throttle: purchase_throttle_account,
+            verification_pass: verification_pass_account,
+            charity: charity_account,
+            memo_program: memo_program_account,
+        },
+        buyer_account.key,
+        &PaymentSource::Wallet(buyer_account),
+        buyer_account,
+        amount,
+        tier_index,
+        quantity,
+        purchase_index,
+        acknowledge_deprecation,
+        price,
+        redeem_points,
+        reference,
+    )
+}
+
+// 在尾部账户中按地址查找买家对该模型的购买节流PDA，没有传入时返回None，由settle_sale决定是否必需
+fn find_trailing_purchase_throttle<'a, 'b>(
+    program_id: &Pubkey,
+    model: &Pubkey,
+    buyer: &Pubkey,
+    trailing_accounts: &'b [AccountInfo<'a>],
+) -> Option<&'b AccountInfo<'a>> {
+    let (purchase_throttle_key, _) = find_purchase_throttle_address(model, buyer, program_id);
+    trailing_accounts.iter().find(|account| *account.key == purchase_throttle_key)
+}
+
+// 在尾部账户中按所有者查找买家的Civic网关令牌，模型要求真人验证时settle_sale要求传入
+fn find_trailing_verification_pass<'a, 'b>(trailing_accounts: &'b [AccountInfo<'a>]) -> Option<&'b AccountInfo<'a>> {
+    trailing_accounts.iter().find(|account| *account.owner == CIVIC_GATEWAY_PROGRAM_ID)
+}
+
+// 结算一笔成交所需的账户，直接购买和撮合成交共用
+struct SaleAccounts<'a, 'b> {
+    ai_model: &'b AccountInfo<'a>,
+    seller: &'b AccountInfo<'a>,
+    receipt: &'b AccountInfo<'a>,
+    seller_stats: &'b AccountInfo<'a>,
+    system_program: &'b AccountInfo<'a>,
+    proceeds_vault: &'b AccountInfo<'a>,
+    sales_ledger: &'b AccountInfo<'a>,
+    treasury: &'b AccountInfo<'a>,
+    seller_bond: &'b AccountInfo<'a>,
+    buyer_stats: &'b AccountInfo<'a>,
+    buyer_receipts: &'b AccountInfo<'a>,
+    // 买家对该模型上一次购买的凭证，首次购买时为None
+    previous_receipt: Option<&'b AccountInfo<'a>>,
+    // 买家对drop模型的购买节流PDA，各成交方式都从尾部账户中按地址查找，购买drop模型时必须传入
+    purchase_throttle: Option<&'b AccountInfo<'a>>,
+    // 买家的验证通行证，各成交方式都从尾部账户中按所有者查找，模型要求真人验证时必须传入
+    verification_pass: Option<&'b AccountInfo<'a>>,
+    // 接受本次慈善分成的慈善机构PDA，同样只有直接购买会传入
+    charity: Option<&'b AccountInfo<'a>>,
+    // SPL Memo程序，传入时成交后通过CPI写入结构化备忘
+    memo_program: Option<&'b AccountInfo<'a>>,
+}
+
+// 成交价格的来源。按标价成交时settle_sale用买家给出的金额计算售价；其余方式以确定的价格成交，
+// 其中只有卖家签名的订单和通过白名单校验的预售价不受公开发售时间限制
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SalePrice {
+    Listed,
+    // 按价格源换算的价格，只用于按价格源标价的模型
+    Oracle(u64),
+    // 升级到后继模型的折扣价
+    Upgrade(u64),
+    // 卖家签名订单上的价格
+    SellerSigned(u64),
+    // 预售白名单中的钱包的预售价
+    Presale(u64),
+}
+
+impl SalePrice {
+    fn fixed(&self) -> Option<u64> {
+        match *self {
+            Self::Listed => None,
+            Self::Oracle(price) | Self::Upgrade(price) | Self::SellerSigned(price) | Self::Presale(price) => Some(price),
+        }
+    }
+
+    // 卖家自己给出的价格或预售价可以在公开发售开始前成交
+    fn waives_sale_window(&self) -> bool {
+        matches!(self, Self::SellerSigned(_) | Self::Presale(_))
+    }
+}
+
+// 成交付款的来源
+enum PaymentSource<'a, 'b> {
+    // 签名的买家钱包，通过系统程序转账
+    Wallet(&'b AccountInfo<'a>),
+    // 本程序拥有的托管账户（如出价的托管PDA），直接扣减lamports
+    Escrow(&'b AccountInfo<'a>),
+    // 买家的市场额度代币，按1:1销毁后从储备中把等量储备代币付给卖家或市场金库
+    Credits(&'b CreditPayment<'a, 'b>),
+}
+
+// 以市场额度代币付款所需的账户
+struct CreditPayment<'a, 'b> {
+    buyer: &'b AccountInfo<'a>,
+    buyer_credit_token: &'b AccountInfo<'a>,
+    credit_mint: &'b AccountInfo<'a>,
+    reserve_mint: &'b AccountInfo<'a>,
+    reserve_token: &'b AccountInfo<'a>,
+    reserve_authority: &'b AccountInfo<'a>,
+    reserve_bump: u8,
+    seller: &'b AccountInfo<'a>,
+    seller_reserve_token: &'b AccountInfo<'a>,
+    treasury: &'b AccountInfo<'a>,
+    treasury_reserve_token: &'b AccountInfo<'a>,
+}
+
+impl<'a> PaymentSource<'a, '_> {
+    fn pay(&self, destination: &AccountInfo<'a>, system_program_account: &AccountInfo<'a>, amount: u64) -> ProgramResult {
+        match self {
+            Self::Wallet(wallet) => invoke(
+                &system_instruction::transfer(wallet.key, destination.key, amount),
+                &[
+                    (*wallet).clone(),
+                    destination.clone(),
+                    system_program_account.clone(),
+                ],
+            ),
+            Self::Escrow(escrow) => {
+                let escrow_lamports = escrow
+                    .lamports()
+                    .checked_sub(amount)
+                    .ok_or(ProgramError::InsufficientFunds)?;
+                let destination_lamports = destination
+                    .lamports()
+                    .checked_add(amount)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+                **escrow.lamports.borrow_mut() = escrow_lamports;
+                **destination.lamports.borrow_mut() = destination_lamports;
+                Ok(())
+            }
+            // 额度付款只会付给卖家和市场金库，分别转入它们的储备代币账户
+            Self::Credits(credits) => {
+                let target = if destination.key == credits.treasury.key {
+                    credits.treasury_reserve_token
+                } else if destination.key == credits.seller.key {
+                    credits.seller_reserve_token
+                } else {
+                    return Err(ProgramError::InvalidArgument);
+                };
+                burn_token_2022(credits.buyer_credit_token, credits.credit_mint, credits.buyer, amount)?;
+                transfer_token_2022(
+                    credits.reserve_token,
+                    credits.reserve_mint,
+                    target,
+                    credits.reserve_authority,
+                    amount,
+                    &[&[CREDIT_RESERVE_SEED, &[credits.reserve_bump]]],
+                )
+            }
+        }
+    }
+
+    // 计入销售账本的付款币种
+    fn payment_mint(&self) -> Pubkey {
+        match self {
+            Self::Wallet(_) | Self::Escrow(_) => NATIVE_PAYMENT_MINT,
+            Self::Credits(credits) => *credits.credit_mint.key,
+        }
+    }
+}
+
+// 在独立的栈帧中解码AIModel并放到堆上，调用方栈帧只保留一个指针。AIModel按值超过800字节，
+// 处理程序都经由它加载完整模型，只读定长字段时改用AIModelView；
+// 各帧的实际大小以cargo build-sbf报告的栈溢出警告为准
+#[inline(never)]
+fn unpack_ai_model_boxed(ai_model_account: &AccountInfo) -> Result<Box<AIModel>, ProgramError> {
+    Ok(Box::new(AIModel::unpack(&ai_model_account.data.borrow())?))
+}
+
+// 结算一笔成交：收取付款、扣除手续费、写入购买凭证并更新统计和销售账本，
+// rent_payer为首次创建统计、金库和账本PDA时出资的签名账户，
+// acknowledge_deprecation表示买家已确认购买的是已弃用的模型，
+// price为成交价格的来源，确定的价格取代链上标价，
+// redeem_points为买家在结账时兑换的忠诚度积分，reference为买家附带的对账参考号（如采购单号哈希）
+#[allow(clippy::too_many_arguments)]
+fn settle_sale<'a>(
+    program_id: &Pubkey,
+    sale: &SaleAccounts<'a, '_>,
+    buyer: &Pubkey,
+    source: &PaymentSource<'a, '_>,
+    rent_payer: &AccountInfo<'a>,
+    amount: u64,
+    tier_index: u8,
+    quantity: u32,
+    purchase_index: u32,
+    acknowledge_deprecation: bool,
+    price: SalePrice,
+    redeem_points: u64,
+    reference: [u8; 32],
+) -> ProgramResult {
+    let SaleAccounts {
+        ai_model: ai_model_account,
+        seller: seller_account,
+        receipt: receipt_account,
+        seller_stats: seller_stats_account,
+        system_program: system_program_account,
+        proceeds_vault: proceeds_vault_account,
+        sales_ledger: sales_ledger_account,
+        treasury: treasury_account,
+        seller_bond: seller_bond_account,
+        buyer_stats: buyer_stats_account,
+        buyer_receipts: buyer_receipts_account,
+        previous_receipt: previous_receipt_account,
+        purchase_throttle: purchase_throttle_account,
+        verification_pass: verification_pass_account,
+        charity: charity_account,
+        memo_program: memo_program_account,
+    } = *sale;
+
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // 检查AIModel账户并确认卖家就是模型所有者
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if ai_model_data.owner != *seller_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    ai_model_data.require_purchasable()?;
+    ai_model_data.require_deprecation_acknowledged(acknowledge_deprecation)?;
+    let clock = Clock::get()?;
+    // 卖家签名的订单和预售不受公开发售时间限制
+    if !price.waives_sale_window() {
+        ai_model_data.require_public_sale(clock.slot)?;
+    }
+    // 按价格源标价的模型只能通过PurchaseAtOraclePrice购买
+    if price == SalePrice::Listed && ai_model_data.is_oracle_priced() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if ai_model_data.gatekeeper_network != Pubkey::default() {
+        let verification_pass_account = verification_pass_account.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        verify_gateway_token(verification_pass_account, buyer, &ai_model_data.gatekeeper_network, clock.unix_timestamp)?;
+    }
+    if ai_model_data.is_drop() {
+        let purchase_throttle_account = purchase_throttle_account.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        ai_model_data.record_drop_purchase(clock.slot)?;
+        let mut throttle = open_purchase_throttle(
+            program_id,
+            ai_model_account.key,
+            buyer,
+            purchase_throttle_account,
+            rent_payer,
+            system_program_account,
+        )?;
+        throttle.record_purchase(clock.slot, ai_model_data.drop_cooldown_slots)?;
+        throttle.updated_at = clock.unix_timestamp;
+        throttle.pack_into_slice(&mut purchase_throttle_account.data.borrow_mut());
+    }
+    let edition = ai_model_data.mint_edition(clock.slot)?;
+
+    // 购买凭证按purchase_index顺序创建，第n次购买要求第n-1次的凭证已经存在
+    let (receipt_key, receipt_bump) =
+        find_receipt_address(ai_model_account.key, buyer, purchase_index, program_id);
+    if receipt_key != *receipt_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !receipt_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    if let Some(previous_receipt_account) = previous_receipt_account {
+        let (previous_key, _) =
+            find_receipt_address(ai_model_account.key, buyer, purchase_index - 1, program_id);
+        if previous_key != *previous_receipt_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if previous_receipt_account.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        PurchaseReceipt::unpack(&previous_receipt_account.data.borrow())?;
+    }
+
+    // 计算成交金额，按金库的首次销售费率和卖家质押的保证金扣除手续费转入市场金库，
+    // 其余付给卖家或存入卖家的收益金库
+    let list_price = ai_model_data.list_price(tier_index, quantity)?;
+    let (list_price, amount_paid) = match price.fixed() {
+        // 卖家确定的价格就是标价，不产生打赏
+        Some(price) => (price, price),
+        None => (list_price, ai_model_data.sale_price(tier_index, quantity, amount)?),
+    };
+    let mut treasury = load_treasury(program_id, treasury_account)?;
+    let fee_bps = treasury.primary_fee_rate(seller_bond_staked(program_id, seller_account.key, seller_bond_account)?);
+    let fee = fee_at_bps(amount_paid, fee_bps);
+    let seller_amount = amount_paid - fee;
+    // 积分抵扣从手续费中扣除，卖家收入不受影响
+    let loyalty_discount = treasury.loyalty_discount(redeem_points, fee)?;
+    let buyer_paid = amount_paid - loyalty_discount;
+    let native_payment = source.payment_mint() == NATIVE_PAYMENT_MINT;
+    let mut buyer_stats = open_buyer_stats(
+        program_id,
+        buyer,
+        buyer_stats_account,
+        rent_payer,
+        system_program_account,
+    )?;
+    // 慈善分成：模型设置的分成从卖家收入中扣除；买家结账时传入慈善机构PDA则按偏好在成交额之外另行捐赠，
+    // 两者都捐给模型指定的机构，模型未指定时捐给买家偏好的机构
+    let listing_donation = ai_model_data.charity_donation(amount_paid).min(seller_amount);
+    let buyer_donation = match charity_account {
+        Some(_) if native_payment => buyer_stats.charity_donation(amount_paid),
+        _ => 0,
+    };
+    let seller_amount = seller_amount - listing_donation;
+    if listing_donation > 0 || buyer_donation > 0 {
+        let charity_account = charity_account.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let charity_key = if ai_model_data.charity != Pubkey::default() {
+            ai_model_data.charity
+        } else {
+            buyer_stats.charity
+        };
+        if *charity_account.key != charity_key {
+            return Err(ProgramError::InvalidArgument);
+        }
+        // 慈善机构PDA只托管lamports
+        if !native_payment {
+            msg!("Charity splits require native payment");
+            return Err(ProgramError::InvalidArgument);
+        }
+        let mut charity = load_active_charity(program_id, charity_account)?;
+        source.pay(charity_account, system_program_account, listing_donation + buyer_donation)?;
+        charity.record_donation(listing_donation + buyer_donation)?;
+        charity.updated_at = clock.unix_timestamp;
+        charity.pack_into_slice(&mut charity_account.data.borrow_mut());
+        msg!(
+            "CharityDonation: charity={} model={} buyer={} listing_donation={} buyer_donation={}",
+            charity_account.key,
+            ai_model_account.key,
+            buyer,
+            listing_donation,
+            buyer_donation
+        );
+    }
+    if fee > loyalty_discount {
+        source.pay(treasury_account, system_program_account, fee - loyalty_discount)?;
+    }
+    // 额度付款的手续费进入金库的储备代币账户，total_fees和积分抵扣只统计lamports
+    if fee > 0 && native_payment {
+        treasury.total_fees = treasury.total_fees
+            .checked_add(fee - loyalty_discount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        treasury.total_loyalty_discounts = treasury.total_loyalty_discounts
+            .checked_add(loyalty_discount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        treasury.updated_at = clock.unix_timestamp;
+        treasury.pack_into_slice(&mut treasury_account.data.borrow_mut());
+    }
+    let seller_amount = withhold_proceeds(
+        program_id,
+        source,
+        seller_account,
+        proceeds_vault_account,
+        system_program_account,
+        seller_amount,
+    )?;
+    // 收益金库只托管lamports，额度付款总是直接付给卖家
+    if ai_model_data.vault_proceeds && native_payment {
+        deposit_proceeds(
+            program_id,
+            rent_payer,
+            source,
+            seller_account,
+            proceeds_vault_account,
+            system_program_account,
+            seller_amount,
+        )?;
+    } else {
+        source.pay(seller_account, system_program_account, seller_amount)?;
+    }
+
+    // 创建并写入购买凭证
+    create_pda_account(
+        rent_payer,
+        receipt_account,
+        system_program_account,
+        program_id,
+        PurchaseReceipt::LEN,
+        &[
+            RECEIPT_SEED,
+            ai_model_account.key.as_ref(),
+            buyer.as_ref(),
+            &purchase_index.to_le_bytes(),
+            &[receipt_bump],
+        ],
+    )?;
+    let receipt = PurchaseReceipt {
+        is_initialized: true,
+        model: *ai_model_account.key,
+        buyer: *buyer,
+        amount_paid: buyer_paid,
+        tier: tier_index,
+        seat_count: quantity,
+        created_at: clock.unix_timestamp,
+        updated_at: clock.unix_timestamp,
+        purchase_index,
+        license: ai_model_data.license.clone(),
+        api_key_hash: [0u8; 32],
+        api_key_bound_at: 0,
+        edition,
+        revocable: ai_model_data.license_tiers.get(tier_index as usize).is_some_and(|tier| tier.revocable),
+        expires_at_slot: match ai_model_data.tier_term_slots(tier_index) {
+            0 => 0,
+            term_slots => clock.slot
+                .checked_add(term_slots)
+                .ok_or(ProgramError::ArithmeticOverflow)?,
+        },
+        fee_bps: fee_bps as u16,
+        fee_paid: fee - loyalty_discount,
+        reference,
+        rent_payer: *rent_payer.key,
+    };
+    receipt.pack_into_slice(&mut receipt_account.data.borrow_mut());
+
+    // 更新卖家统计，首次成交时创建统计PDA
+    let mut seller_stats = open_seller_stats(
+        program_id,
+        seller_account.key,
+        seller_stats_account,
+        rent_payer,
+        system_program_account,
+    )?;
+    seller_stats.total_sales = seller_stats.total_sales
+        .checked_add(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    seller_stats.total_revenue = seller_stats.total_revenue
+        .checked_add(amount_paid)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    seller_stats.total_tips = seller_stats.total_tips
+        .checked_add(amount_paid - list_price.min(amount_paid))
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    seller_stats.refresh_achievements();
+    seller_stats.pack_into_slice(&mut seller_stats_account.data.borrow_mut());
+
+    // 将凭证追加到买家的凭证索引，并更新买家统计
+    append_buyer_receipt(
+        program_id,
+        buyer,
+        buyer_stats.current_receipts_page(),
+        buyer_receipts_account,
+        rent_payer,
+        system_program_account,
+        receipt_key,
+    )?;
+    buyer_stats.total_purchases = buyer_stats.total_purchases
+        .checked_add(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    buyer_stats.total_spent = buyer_stats.total_spent
+        .checked_add(buyer_paid)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    // 先兑换结账使用的积分，再按实付金额累计新积分
+    buyer_stats.redeem_loyalty_points(redeem_points)?;
+    let points_earned = treasury.loyalty_points_for(buyer_paid);
+    buyer_stats.loyalty_points = buyer_stats.loyalty_points.saturating_add(points_earned);
+    buyer_stats.refresh_achievements();
+    buyer_stats.pack_into_slice(&mut buyer_stats_account.data.borrow_mut());
+    if redeem_points > 0 || points_earned > 0 {
+        msg!(
+            "LoyaltyPoints: buyer={} earned={} redeemed={} discount={} balance={}",
+            buyer,
+            points_earned,
+            redeem_points,
+            loyalty_discount,
+            buyer_stats.loyalty_points
+        );
+    }
+
+    // 将本次成交追加到模型的销售账本，并更新模型上的销售统计
+    append_sale_record(
+        program_id,
+        ai_model_account.key,
+        &ai_model_data,
+        sales_ledger_account,
+        rent_payer,
+        system_program_account,
+        SaleRecord {
+            buyer: *buyer,
+            amount: amount_paid,
+            slot: clock.slot,
+            payment_mint: source.payment_mint(),
+        },
+    )?;
+    ai_model_data.record_sale(amount_paid, clock.slot)?;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    events::Purchased {
+        receipt: receipt_key,
+        model: *ai_model_account.key,
+        buyer: *buyer,
+        tier: tier_index,
+        quantity,
+        amount_paid,
+        fee: fee - loyalty_discount,
+        edition,
+        reference,
+    }
+    .emit();
+    if let Some(memo_program_account) = memo_program_account {
+        let memo = sale_memo(ai_model_account.key, buyer, amount_paid, &source.payment_mint(), &receipt_key, &reference);
+        invoke(&spl_memo_instruction(memo.as_bytes()), std::slice::from_ref(memo_program_account))?;
+    }
+    Ok(())
+}
+
+// 成交备忘的文本，字段以空格分隔的key=value表示，reference按base58编码
+fn sale_memo(
+    model: &Pubkey,
+    buyer: &Pubkey,
+    amount: u64,
+    payment_mint: &Pubkey,
+    receipt: &Pubkey,
+    reference: &[u8; 32],
+) -> String {
+    format!(
+        "ai-marketplace:purchase model={} buyer={} amount={} payment_mint={} receipt={} reference={}",
+        model,
+        buyer,
+        amount,
+        payment_mint,
+        receipt,
+        Hash::new_from_array(*reference)
+    )
+}
+
+// 构造不要求签名者的SPL Memo指令
+fn spl_memo_instruction(memo: &[u8]) -> Instruction {
+    Instruction {
+        program_id: SPL_MEMO_PROGRAM_ID,
+        accounts: vec![],
+        data: memo.to_vec(),
+    }
+}
+
+// 将凭证写入买家当前的凭证索引分页，分页写满后由付款账户出资创建下一页
+fn append_buyer_receipt<'a>(
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    page_index: u32,
+    buyer_receipts_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    receipt: Pubkey,
+) -> ProgramResult {
+    let (page_key, page_bump) = find_buyer_receipts_address(buyer, page_index, program_id);
+    if page_key != *buyer_receipts_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if buyer_receipts_account.data_is_empty() {
+        create_pda_account(
+            payer,
+            buyer_receipts_account,
+            system_program_account,
+            program_id,
+            BuyerReceiptsPage::LEN,
+            &[BUYER_RECEIPTS_SEED, buyer.as_ref(), &page_index.to_le_bytes(), &[page_bump]],
+        )?;
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut page = BuyerReceiptsPage::unpack_unchecked(&buyer_receipts_account.data.borrow())?;
+    if !page.is_initialized {
+        page.is_initialized = true;
+        page.buyer = *buyer;
+        page.page_index = page_index;
+        page.created_at = now;
+    }
+    page.updated_at = now;
+    if page.receipts.len() >= BUYER_RECEIPTS_PAGE_CAPACITY {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    page.receipts.push(receipt);
+    page.pack_into_slice(&mut buyer_receipts_account.data.borrow_mut());
+    Ok(())
+}
+
+// 读取卖家统计，账户不存在时由payer出资创建，返回的统计已刷新updated_at
+fn open_purchase_throttle<'a>(
+    program_id: &Pubkey,
+    model: &Pubkey,
+    wallet: &Pubkey,
+    throttle_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+) -> Result<PurchaseThrottle, ProgramError> {
+    let (throttle_key, throttle_bump) = find_purchase_throttle_address(model, wallet, program_id);
+    if throttle_key != *throttle_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if throttle_account.data_is_empty() {
+        create_pda_account(
+            payer,
+            throttle_account,
+            system_program_account,
+            program_id,
+            PurchaseThrottle::LEN,
+            &[PURCHASE_THROTTLE_SEED, model.as_ref(), wallet.as_ref(), &[throttle_bump]],
+        )?;
+    }
+    let mut throttle = PurchaseThrottle::unpack_unchecked(&throttle_account.data.borrow())?;
+    if !throttle.is_initialized {
+        throttle.is_initialized = true;
+        throttle.model = *model;
+        throttle.wallet = *wallet;
+        throttle.created_at = Clock::get()?.unix_timestamp;
+    }
+    Ok(throttle)
+}
+
+fn open_seller_stats<'a>(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    seller_stats_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+) -> Result<SellerStats, ProgramError> {
+    let (seller_stats_key, seller_stats_bump) = find_seller_stats_address(seller, program_id);
+    if seller_stats_key != *seller_stats_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if seller_stats_account.data_is_empty() {
+        create_pda_account(
+            payer,
+            seller_stats_account,
+            system_program_account,
+            program_id,
+            SellerStats::LEN,
+            &[SELLER_STATS_SEED, seller.as_ref(), &[seller_stats_bump]],
+        )?;
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut seller_stats = SellerStats::unpack_unchecked(&seller_stats_account.data.borrow())?;
+    if !seller_stats.is_initialized {
+        seller_stats.is_initialized = true;
+        seller_stats.seller = *seller;
+        seller_stats.created_at = now;
+    }
+    seller_stats.updated_at = now;
+    Ok(seller_stats)
+}
+
+// 创建关注/收藏关系PDA，关系已存在时失败
+fn open_relationship<'a>(
+    program_id: &Pubkey,
+    seed: &[u8],
+    follower_account: &AccountInfo<'a>,
+    target: &Pubkey,
+    relationship_account: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    let (relationship_key, relationship_bump) =
+        Pubkey::find_program_address(&[seed, follower_account.key.as_ref(), target.as_ref()], program_id);
+    if relationship_key != *relationship_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !relationship_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        follower_account,
+        relationship_account,
+        system_program_account,
+        program_id,
+        Relationship::LEN,
+        &[seed, follower_account.key.as_ref(), target.as_ref(), &[relationship_bump]],
+    )?;
+    let now = Clock::get()?.unix_timestamp;
+    let relationship = Relationship {
+        is_initialized: true,
+        follower: *follower_account.key,
+        target: *target,
+        created_at: now,
+        updated_at: now,
+    };
+    relationship.pack_into_slice(&mut relationship_account.data.borrow_mut());
+    Ok(())
+}
+
+// 关闭关注/收藏关系PDA，租金退回关注者
+fn close_relationship(
+    program_id: &Pubkey,
+    seed: &[u8],
+    follower_account: &AccountInfo,
+    target: &Pubkey,
+    relationship_account: &AccountInfo,
+) -> ProgramResult {
+    let (relationship_key, _) =
+        Pubkey::find_program_address(&[seed, follower_account.key.as_ref(), target.as_ref()], program_id);
+    if relationship_key != *relationship_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if relationship_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Relationship::unpack(&relationship_account.data.borrow())?;
+    close_account(relationship_account, follower_account)
+}
+
+// 读取买家统计，账户不存在时由payer出资创建，返回的统计已刷新updated_at
+fn open_buyer_stats<'a>(
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    buyer_stats_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+) -> Result<BuyerStats, ProgramError> {
+    let (buyer_stats_key, buyer_stats_bump) = find_buyer_stats_address(buyer, program_id);
+    if buyer_stats_key != *buyer_stats_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if buyer_stats_account.data_is_empty() {
+        create_pda_account(
+            payer,
+            buyer_stats_account,
+            system_program_account,
+            program_id,
+            BuyerStats::LEN,
+            &[BUYER_STATS_SEED, buyer.as_ref(), &[buyer_stats_bump]],
+        )?;
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut buyer_stats = BuyerStats::unpack_unchecked(&buyer_stats_account.data.borrow())?;
+    if !buyer_stats.is_initialized {
+        buyer_stats.is_initialized = true;
+        buyer_stats.buyer = *buyer;
+        buyer_stats.created_at = now;
+    }
+    buyer_stats.updated_at = now;
+    Ok(buyer_stats)
+}
+
+// 读取并校验登记的慈善机构PDA，停用的机构不接受新的分成
+fn load_active_charity(program_id: &Pubkey, charity_account: &AccountInfo) -> Result<Charity, ProgramError> {
+    if charity_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let charity = Charity::unpack(&charity_account.data.borrow())?;
+    if find_charity_address(&charity.wallet, program_id).0 != *charity_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !charity.active {
+        msg!("Charity {} is not active", charity_account.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(charity)
+}
+
+// 读取卖家质押的保证金金额，卖家尚未创建保证金账户时为0
+fn seller_bond_staked(program_id: &Pubkey, seller: &Pubkey, seller_bond_account: &AccountInfo) -> Result<u64, ProgramError> {
+    if find_seller_bond_address(seller, program_id).0 != *seller_bond_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if seller_bond_account.data_is_empty() {
+        return Ok(0);
+    }
+    if seller_bond_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(SellerBond::unpack(&seller_bond_account.data.borrow())?.staked)
+}
+
+// 将成交记录写入模型当前的账本分页，分页写满后由付款账户出资创建下一页
+fn append_sale_record<'a>(
+    program_id: &Pubkey,
+    model_key: &Pubkey,
+    ai_model_data: &AIModel,
+    sales_ledger_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    record: SaleRecord,
+) -> ProgramResult {
+    let page_index = ai_model_data.current_ledger_page();
+    let (page_key, page_bump) = find_sales_ledger_address(model_key, page_index, program_id);
+    if page_key != *sales_ledger_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if sales_ledger_account.data_is_empty() {
+        create_pda_account(
+            payer,
+            sales_ledger_account,
+            system_program_account,
+            program_id,
+            SalesLedgerPage::LEN,
+            &[SALES_LEDGER_SEED, model_key.as_ref(), &page_index.to_le_bytes(), &[page_bump]],
+        )?;
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut page = SalesLedgerPage::unpack_unchecked(&sales_ledger_account.data.borrow())?;
+    if !page.is_initialized {
+        page.is_initialized = true;
+        page.model = *model_key;
+        page.page_index = page_index;
+        page.created_at = now;
+    }
+    page.updated_at = now;
+    if page.entries.len() >= SALES_LEDGER_PAGE_CAPACITY {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    page.entries.push(record);
+    page.pack_into_slice(&mut sales_ledger_account.data.borrow_mut());
+    Ok(())
+}
+
+// 将买家付款存入卖家的收益金库，首次存入时由rent_payer出资创建金库PDA
+fn deposit_proceeds<'a>(
+    program_id: &Pubkey,
+    rent_payer: &AccountInfo<'a>,
+    source: &PaymentSource<'a, '_>,
+    seller_account: &AccountInfo<'a>,
+    proceeds_vault_account: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let (vault_key, vault_bump) = find_proceeds_vault_address(seller_account.key, program_id);
+    if vault_key != *proceeds_vault_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if proceeds_vault_account.data_is_empty() {
+        create_pda_account(
+            rent_payer,
+            proceeds_vault_account,
+            system_program_account,
+            program_id,
+            ProceedsVault::LEN,
+            &[PROCEEDS_VAULT_SEED, seller_account.key.as_ref(), &[vault_bump]],
+        )?;
+    }
+    source.pay(proceeds_vault_account, system_program_account, amount)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut vault = ProceedsVault::unpack_unchecked(&proceeds_vault_account.data.borrow())?;
+    if !vault.is_initialized {
+        vault.is_initialized = true;
+        vault.seller = *seller_account.key;
+        vault.created_at = now;
+    }
+    vault.updated_at = now;
+    vault.balance = vault.balance
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    vault.pack_into_slice(&mut proceeds_vault_account.data.borrow_mut());
+    Ok(())
+}
+
+// 按卖家收益金库中的预扣设置从卖家收入amount中预扣一部分托管在金库，返回仍应付给卖家的金额；
+// 卖家尚未创建收益金库时不预扣
+fn withhold_proceeds<'a>(
+    program_id: &Pubkey,
+    source: &PaymentSource<'a, '_>,
+    seller_account: &AccountInfo<'a>,
+    proceeds_vault_account: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    amount: u64,
+) -> Result<u64, ProgramError> {
+    if find_proceeds_vault_address(seller_account.key, program_id).0 != *proceeds_vault_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if proceeds_vault_account.data_is_empty() {
+        return Ok(amount);
+    }
+    if proceeds_vault_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vault = ProceedsVault::unpack(&proceeds_vault_account.data.borrow())?;
+    let withheld = vault.withholding_for(amount);
+    if withheld == 0 {
+        return Ok(amount);
+    }
+    // 收益金库只托管lamports
+    if source.payment_mint() != NATIVE_PAYMENT_MINT {
+        msg!("Withholding requires native payment");
+        return Err(ProgramError::InvalidArgument);
+    }
+    source.pay(proceeds_vault_account, system_program_account, withheld)?;
+    vault.record_withholding(withheld)?;
+    vault.updated_at = Clock::get()?.unix_timestamp;
+    vault.pack_into_slice(&mut proceeds_vault_account.data.borrow_mut());
+
+    msg!(
+        "ProceedsWithheld: seller={} amount={} destination={}",
+        seller_account.key,
+        withheld,
+        vault.withholding_destination
+    );
+    Ok(amount - withheld)
+}
+
+// 定义一个处理程序函数，卖家设置每笔收入的预扣比例和目标账户，首次设置时创建收益金库
+pub fn set_withholding(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    destination: Pubkey,
+    withholding_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller_account = next_account_info(account_info_iter)?;
+    let proceeds_vault_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !seller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (vault_key, vault_bump) = find_proceeds_vault_address(seller_account.key, program_id);
+    if vault_key != *proceeds_vault_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if proceeds_vault_account.data_is_empty() {
+        create_pda_account(
+            seller_account,
+            proceeds_vault_account,
+            system_program_account,
+            program_id,
+            ProceedsVault::LEN,
+            &[PROCEEDS_VAULT_SEED, seller_account.key.as_ref(), &[vault_bump]],
+        )?;
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut vault = ProceedsVault::unpack_unchecked(&proceeds_vault_account.data.borrow())?;
+    if !vault.is_initialized {
+        vault.is_initialized = true;
+        vault.seller = *seller_account.key;
+        vault.created_at = now;
+    }
+    vault.set_withholding(destination, withholding_bps)?;
+    vault.updated_at = now;
+    vault.pack_into_slice(&mut proceeds_vault_account.data.borrow_mut());
+
+    msg!(
+        "WithholdingSet: seller={} destination={} withholding_bps={}",
+        seller_account.key,
+        destination,
+        withholding_bps
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，把收益金库中待释放的预扣款全部转给卖家设置的目标账户，任何人都可以发起
+pub fn release_withheld_proceeds(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let proceeds_vault_account = next_account_info(account_info_iter)?;
+    let destination_account = next_account_info(account_info_iter)?;
+
+    if proceeds_vault_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vault = ProceedsVault::unpack(&proceeds_vault_account.data.borrow())?;
+    if find_proceeds_vault_address(&vault.seller, program_id).0 != *proceeds_vault_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if vault.withheld_balance == 0 || vault.withholding_destination != *destination_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let amount = vault.withheld_balance;
+    vault.withheld_balance = 0;
+    vault.updated_at = Clock::get()?.unix_timestamp;
+    vault.pack_into_slice(&mut proceeds_vault_account.data.borrow_mut());
+
+    // 金库由本程序拥有，可以直接扣减其lamports
+    **proceeds_vault_account.lamports.borrow_mut() -= amount;
+    **destination_account.lamports.borrow_mut() += amount;
+
+    msg!("WithheldProceedsReleased: seller={} destination={} amount={}", vault.seller, destination_account.key, amount);
+    Ok(())
+}
+
+// 定义一个处理程序函数，卖家从收益金库提取销售收入
+pub fn withdraw_proceeds(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller_account = next_account_info(account_info_iter)?;
+    let proceeds_vault_account = next_account_info(account_info_iter)?;
+
+    if !seller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if proceeds_vault_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vault = ProceedsVault::unpack(&proceeds_vault_account.data.borrow())?;
+    if vault.seller != *seller_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if amount > vault.balance {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    vault.balance -= amount;
+    vault.updated_at = Clock::get()?.unix_timestamp;
+    vault.total_withdrawn = vault.total_withdrawn
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    vault.pack_into_slice(&mut proceeds_vault_account.data.borrow_mut());
+
+    // 金库由本程序拥有，可以直接扣减其lamports
+    **proceeds_vault_account.lamports.borrow_mut() -= amount;
+    **seller_account.lamports.borrow_mut() += amount;
+
+    msg!("Withdrew {} lamports of proceeds to {}", amount, seller_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，卖家向保证金PDA质押lamports，首次质押时创建保证金账户
+pub fn deposit_bond(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller_account = next_account_info(account_info_iter)?;
+    let seller_bond_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !seller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (bond_key, bond_bump) = find_seller_bond_address(seller_account.key, program_id);
+    if bond_key != *seller_bond_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if seller_bond_account.data_is_empty() {
+        create_pda_account(
+            seller_account,
+            seller_bond_account,
+            system_program_account,
+            program_id,
+            SellerBond::LEN,
+            &[SELLER_BOND_SEED, seller_account.key.as_ref(), &[bond_bump]],
+        )?;
+    }
+    invoke(
+        &system_instruction::transfer(seller_account.key, seller_bond_account.key, amount),
+        &[
+            seller_account.clone(),
+            seller_bond_account.clone(),
+            system_program_account.clone(),
+        ],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut bond = SellerBond::unpack_unchecked(&seller_bond_account.data.borrow())?;
+    if !bond.is_initialized {
+        bond.is_initialized = true;
+        bond.seller = *seller_account.key;
+        bond.created_at = now;
+    }
+    bond.updated_at = now;
+    bond.staked = bond.staked
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    bond.pack_into_slice(&mut seller_bond_account.data.borrow_mut());
+
+    msg!("Seller {} bond staked {} lamports, total {}", seller_account.key, amount, bond.staked);
+    Ok(())
+}
+
+// 定义一个处理程序函数，卖家取回质押的保证金
+pub fn withdraw_bond(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller_account = next_account_info(account_info_iter)?;
+    let seller_bond_account = next_account_info(account_info_iter)?;
+
+    if !seller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if seller_bond_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut bond = SellerBond::unpack(&seller_bond_account.data.borrow())?;
+    if bond.seller != *seller_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if amount > bond.staked {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    bond.staked -= amount;
+    bond.updated_at = Clock::get()?.unix_timestamp;
+    bond.pack_into_slice(&mut seller_bond_account.data.borrow_mut());
+
+    // 保证金账户由本程序拥有，可以直接扣减其lamports
+    **seller_bond_account.lamports.borrow_mut() -= amount;
+    **seller_account.lamports.borrow_mut() += amount;
+
+    msg!("Seller {} bond withdrew {} lamports, remaining {}", seller_account.key, amount, bond.staked);
+    Ok(())
+}
+
+// 定义一个处理程序函数，卖家以指定价格挂单；draft为true时挂单以草稿创建，发布之前不能成交
+pub fn sell(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    price: u64,
+    tier_index: u8,
+    quantity: u32,
+    draft: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let listing_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !seller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if ai_model_data.owner != *seller_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    ai_model_data.list_price(tier_index, quantity)?;
+
+    let (listing_key, listing_bump) = find_listing_address(seller_account.key, ai_model_account.key, price, program_id);
+    if listing_key != *listing_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !listing_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        seller_account,
+        listing_account,
+        system_program_account,
+        program_id,
+        Listing::LEN,
+        &[
+            LISTING_SEED,
+            seller_account.key.as_ref(),
+            ai_model_account.key.as_ref(),
+            &price.to_le_bytes(),
+            &[listing_bump],
+        ],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let listing = Listing {
+        is_initialized: true,
+        status: if draft { ListingStatus::Draft } else { ListingStatus::Active },
+        seller: *seller_account.key,
+        model: *ai_model_account.key,
+        price,
+        tier: tier_index,
+        quantity,
+        fills: 0,
+        created_at: now,
+        updated_at: now,
+    };
+    listing.pack_into_slice(&mut listing_account.data.borrow_mut());
+
+    msg!(
+        "ListingOpened: model={} seller={} price={} tier={} quantity={} status={:?}",
+        listing.model,
+        listing.seller,
+        price,
+        tier_index,
+        quantity,
+        listing.status
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，买家以指定价格出价，出价金额从买家转入出价的托管账户
+pub fn buy(program_id: &Pubkey, accounts: &[AccountInfo], price: u64, tier_index: u8, quantity: u32) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let offer_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if ai_model_data.sale_price(tier_index, quantity, price)? > price {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    let (offer_key, offer_bump) = find_offer_address(buyer_account.key, ai_model_account.key, price, program_id);
+    if offer_key != *offer_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let (escrow_key, escrow_bump) = find_escrow_address(&offer_key, program_id);
+    if escrow_key != *escrow_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !offer_account.data_is_empty() || !escrow_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        buyer_account,
+        offer_account,
+        system_program_account,
+        program_id,
+        Offer::LEN,
+        &[
+            OFFER_SEED,
+            buyer_account.key.as_ref(),
+            ai_model_account.key.as_ref(),
+            &price.to_le_bytes(),
+            &[offer_bump],
+        ],
+    )?;
+    create_pda_account(
+        buyer_account,
+        escrow_account,
+        system_program_account,
+        program_id,
+        Escrow::LEN,
+        &[ESCROW_SEED, offer_key.as_ref(), &[escrow_bump]],
+    )?;
+    PaymentSource::Wallet(buyer_account).pay(escrow_account, system_program_account, price)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let offer = Offer {
+        is_initialized: true,
+        status: OfferStatus::Open,
+        buyer: *buyer_account.key,
+        model: *ai_model_account.key,
+        price,
+        tier: tier_index,
+        quantity,
+        escrow: escrow_key,
+        receipt: Pubkey::default(),
+        created_at: now,
+        updated_at: now,
+    };
+    offer.pack_into_slice(&mut offer_account.data.borrow_mut());
+    let escrow = Escrow {
+        is_initialized: true,
+        status: EscrowStatus::Funded,
+        offer: offer_key,
+        depositor: *buyer_account.key,
+        amount: price,
+        released: 0,
+        created_at: now,
+        updated_at: now,
+    };
+    escrow.pack_into_slice(&mut escrow_account.data.borrow_mut());
+
+    msg!(
+        "OfferOpened: model={} buyer={} price={} tier={} quantity={} escrow={}",
+        offer.model,
+        offer.buyer,
+        price,
+        tier_index,
+        quantity,
+        escrow_key
+    );
+    Ok(())
+}
+
+// 读取并校验挂单，确认地址由卖家、模型和价格推导
+fn load_listing(program_id: &Pubkey, listing_account: &AccountInfo) -> Result<Listing, ProgramError> {
+    if listing_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let listing = Listing::unpack(&listing_account.data.borrow())?;
+    let (listing_key, _) = find_listing_address(&listing.seller, &listing.model, listing.price, program_id);
+    if listing_key != *listing_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(listing)
+}
+
+// 读取并校验出价，确认地址由买家、模型和价格推导
+fn load_offer(program_id: &Pubkey, offer_account: &AccountInfo) -> Result<Offer, ProgramError> {
+    if offer_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let offer = Offer::unpack(&offer_account.data.borrow())?;
+    let (offer_key, _) = find_offer_address(&offer.buyer, &offer.model, offer.price, program_id);
+    if offer_key != *offer_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(offer)
+}
+
+// 读取并校验出价的托管账户，确认它属于offer
+fn load_escrow(program_id: &Pubkey, offer: &Offer, offer_key: &Pubkey, escrow_account: &AccountInfo) -> Result<Escrow, ProgramError> {
+    if escrow_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if offer.escrow != *escrow_account.key || find_escrow_address(offer_key, program_id).0 != *escrow_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let escrow = Escrow::unpack(&escrow_account.data.borrow())?;
+    if escrow.offer != *offer_key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(escrow)
+}
+
+// 定义一个处理程序函数，卖家发布、暂停、恢复或下架挂单；发布和恢复时重新确认卖家仍是模型所有者且档位仍然有效
+pub fn set_listing_status(program_id: &Pubkey, accounts: &[AccountInfo], status: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let listing_account = next_account_info(account_info_iter)?;
+
+    if !seller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let status = ListingStatus::from_u8(status).map_err(|_| ProgramError::InvalidArgument)?;
+    let mut listing = load_listing(program_id, listing_account)?;
+    if listing.seller != *seller_account.key || listing.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if status == ListingStatus::Active {
+        if ai_model_account.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+        if ai_model_data.owner != listing.seller {
+            return Err(ProgramError::InvalidArgument);
+        }
+        ai_model_data.list_price(listing.tier, listing.quantity)?;
+    }
+    listing.transition(status, Clock::get()?.unix_timestamp)?;
+    listing.pack_into_slice(&mut listing_account.data.borrow_mut());
+
+    msg!(
+        "ListingStatusChanged: listing={} model={} seller={} status={:?}",
+        listing_account.key,
+        listing.model,
+        listing.seller,
+        status
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，关闭挂单或出价：卖家可以关闭任意状态的挂单；买家关闭出价时托管账户随之关闭，
+// 未成交的出价退回全部托管金额，已成交的出价退回未用完的金额
+pub fn cancel_trade(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let wallet_account = next_account_info(account_info_iter)?;
+    let trade_account = next_account_info(account_info_iter)?;
+
+    if !wallet_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if trade_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let discriminator = read_discriminator(&trade_account.data.borrow())?;
+    match discriminator {
+        Listing::DISCRIMINATOR => {
+            let listing = load_listing(program_id, trade_account)?;
+            if listing.seller != *wallet_account.key {
+                return Err(ProgramError::InvalidArgument);
+            }
+            close_account(trade_account, wallet_account)?;
+            msg!(
+                "ListingClosed: model={} seller={} price={} status={:?}",
+                listing.model,
+                listing.seller,
+                listing.price,
+                listing.status
+            );
+        }
+        Offer::DISCRIMINATOR => {
+            let escrow_account = next_account_info(account_info_iter)?;
+            let offer = load_offer(program_id, trade_account)?;
+            if offer.buyer != *wallet_account.key {
+                return Err(ProgramError::InvalidArgument);
+            }
+            let escrow = load_escrow(program_id, &offer, trade_account.key, escrow_account)?;
+            close_account(escrow_account, wallet_account)?;
+            close_account(trade_account, wallet_account)?;
+            msg!(
+                "OfferClosed: model={} buyer={} price={} status={:?} refunded={}",
+                offer.model,
+                offer.buyer,
+                offer.price,
+                offer.status,
+                escrow.escrowed_amount()
+            );
+        }
+        _ => return Err(ProgramError::InvalidAccountData),
+    }
+    Ok(())
+}
+
+// 定义一个处理程序函数，撮合在售的挂单和等待成交的出价，用出价托管的金额结算
+pub fn execute_sale(program_id: &Pubkey, accounts: &[AccountInfo], purchase_index: u32) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account = next_account_info(account_info_iter)?;
+    let buyer_account = next_account_info(account_info_iter)?;
+    let offer_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let listing_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let seller_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let seller_stats_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let proceeds_vault_account = next_account_info(account_info_iter)?;
+    let sales_ledger_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let seller_bond_account = next_account_info(account_info_iter)?;
+    let buyer_stats_account = next_account_info(account_info_iter)?;
+    let buyer_receipts_account = next_account_info(account_info_iter)?;
+    let previous_receipt_account = if purchase_index > 0 {
+        Some(next_account_info(account_info_iter)?)
+    } else {
+        None
+    };
+    let trailing_accounts = account_info_iter.as_slice();
+    let purchase_throttle_account =
+        find_trailing_purchase_throttle(program_id, ai_model_account.key, buyer_account.key, trailing_accounts);
+    let verification_pass_account = find_trailing_verification_pass(trailing_accounts);
+
+    if !payer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut offer = load_offer(program_id, offer_account)?;
+    let mut escrow = load_escrow(program_id, &offer, offer_account.key, escrow_account)?;
+    let mut listing = load_listing(program_id, listing_account)?;
+    if listing.status != ListingStatus::Active || offer.status != OfferStatus::Open || escrow.status != EscrowStatus::Funded {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if offer.buyer != *buyer_account.key
+        || listing.seller != *seller_account.key
+        || offer.model != *ai_model_account.key
+        || listing.model != *ai_model_account.key
+        || offer.price != listing.price
+        || offer.tier != listing.tier
+        || offer.quantity != listing.quantity
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    // 挂单之后模型价格可能已经调整，成交金额不能超过买家的出价
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if ai_model_data.sale_price(offer.tier, offer.quantity, offer.price)? > offer.price {
+        return Err(ProgramError::InsufficientFunds);
+    }
+    let escrow_lamports = escrow_account.lamports();
+
+    settle_sale(
+        program_id,
+        &SaleAccounts {
+            ai_model: ai_model_account,
+            seller: seller_account,
+            receipt: receipt_account,
+            seller_stats: seller_stats_account,
+            system_program: system_program_account,
+            proceeds_vault: proceeds_vault_account,
+            sales_ledger: sales_ledger_account,
+            treasury: treasury_account,
+            seller_bond: seller_bond_account,
+            buyer_stats: buyer_stats_account,
+            buyer_receipts: buyer_receipts_account,
+            previous_receipt: previous_receipt_account,
+            purchase_throttle: purchase_throttle_account,
+            verification_pass: verification_pass_account,
+            charity: None,
+            memo_program: None,
+        },
+        buyer_account.key,
+        &PaymentSource::Escrow(escrow_account),
+        payer_account,
+        offer.price,
+        offer.tier,
+        offer.quantity,
+        purchase_index,
+        // 出价时买家无法预知模型之后会被弃用，撮合成交不能替买家确认
+        false,
+        SalePrice::Listed,
+        0,
+        [0u8; 32],
+    )?;
+
+    // 出价中未用完的金额留在托管账户，买家关闭已成交的出价时连同租金取回；挂单成交后保持在售
+    let now = Clock::get()?.unix_timestamp;
+    escrow.release(escrow_lamports - escrow_account.lamports(), now)?;
+    offer.fill(*receipt_account.key, now)?;
+    listing.record_fill(now)?;
+    escrow.pack_into_slice(&mut escrow_account.data.borrow_mut());
+    offer.pack_into_slice(&mut offer_account.data.borrow_mut());
+    listing.pack_into_slice(&mut listing_account.data.borrow_mut());
+    Ok(())
+}
+
+// 从Token Metadata账户中读取mint和创作者列表，布局为
+// key(4) + 更新权限 + mint + name/symbol/uri(u32长度前缀) + u16版税 + Option<Vec<Creator>>
+fn creators_from_token_metadata(data: &[u8]) -> Result<(Pubkey, Vec<(Pubkey, bool)>), ProgramError> {
+    let parse = || -> Result<(Pubkey, Vec<(Pubkey, bool)>), ProgramError> {
+        let (key, rest) = unpack_u8(data)?;
+        if key != TOKEN_METADATA_KEY_V1 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (_update_authority, rest) = unpack_pubkey(rest)?;
+        let (mint, rest) = unpack_pubkey(rest)?;
+        let (_name, rest) = unpack_bytes(rest)?;
+        let (_symbol, rest) = unpack_bytes(rest)?;
+        let (_uri, rest) = unpack_bytes(rest)?;
+        let (_seller_fee_basis_points, rest) = unpack_u16(rest)?;
+        let (has_creators, rest) = unpack_u8(rest)?;
+        let mut creators = Vec::new();
+        if has_creators == 1 {
+            let (creator_count, mut rest) = unpack_u32(rest)?;
+            for _ in 0..creator_count {
+                let (address, next) = unpack_pubkey(rest)?;
+                let (verified, next) = unpack_u8(next)?;
+                let (_share, next) = unpack_u8(next)?;
+                creators.push((address, verified == 1));
+                rest = next;
+            }
+        }
+        Ok((mint, creators))
+    };
+    parse().map_err(|_| ProgramError::InvalidAccountData)
+}
+
+// 定义一个处理程序函数，将模型关联到已有NFT的Token Metadata账户，
+// 模型的名称、描述等元数据此后以NFT元数据为准，无需在链上重复存储
+pub fn attach_token_metadata(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let metadata_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+
+    if *metadata_account.owner != TOKEN_METADATA_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (mint, creators) = creators_from_token_metadata(&metadata_account.data.borrow())?;
+    if find_token_metadata_address(&mint).0 != *metadata_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // 只有已验证的创作者才能关联，防止把他人的NFT挂成自己的模型
+    if !creators.iter().any(|(address, verified)| *verified && address == owner_account.key) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    ai_model_data.metadata_mint = mint;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("AIModel {} attached to token metadata of mint {}", ai_model_account.key, mint);
+    Ok(())
+}
+
+// 定义一个处理程序函数，持有购买凭证的买家发表评价
+pub fn submit_review(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    rating: u8,
+    content_hash: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reviewer_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let review_account = next_account_info(account_info_iter)?;
+    let buyer_stats_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let seller_stats_account = next_account_info(account_info_iter)?;
+
+    if !reviewer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !(1..=5).contains(&rating) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if receipt_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let receipt = PurchaseReceipt::unpack(&receipt_account.data.borrow())?;
+    if receipt.buyer != *reviewer_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (review_key, review_bump) = find_review_address(receipt_account.key, program_id);
+    if review_key != *review_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !review_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        reviewer_account,
+        review_account,
+        system_program_account,
+        program_id,
+        Review::LEN,
+        &[REVIEW_SEED, receipt_account.key.as_ref(), &[review_bump]],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let review = Review {
+        is_initialized: true,
+        model: receipt.model,
+        reviewer: *reviewer_account.key,
+        receipt: *receipt_account.key,
+        rating,
+        content_hash,
+        created_at: now,
+        updated_at: now,
+    };
+    review.pack_into_slice(&mut review_account.data.borrow_mut());
+
+    let mut buyer_stats = open_buyer_stats(
+        program_id,
+        reviewer_account.key,
+        buyer_stats_account,
+        reviewer_account,
+        system_program_account,
+    )?;
+    buyer_stats.verified_reviews = buyer_stats.verified_reviews
+        .checked_add(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    buyer_stats.pack_into_slice(&mut buyer_stats_account.data.borrow_mut());
+
+    // 评分计入模型当前所有者的卖家统计，卖家统计在首笔成交时已创建
+    if ai_model_account.owner != program_id || seller_stats_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if *ai_model_account.key != receipt.model {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let seller = AIModelView::new(&ai_model_account.data.borrow())?.owner();
+    if find_seller_stats_address(&seller, program_id).0 != *seller_stats_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let mut seller_stats = SellerStats::unpack(&seller_stats_account.data.borrow())?;
+    seller_stats.record_rating(rating);
+    seller_stats.updated_at = now;
+    seller_stats.pack_into_slice(&mut seller_stats_account.data.borrow_mut());
+
+    msg!("Review of {} by {}: {} stars", receipt.model, reviewer_account.key, rating);
+    Ok(())
+}
+
+// 创建不可转让的Token-2022 mint PDA，依次CPI InitializeNonTransferableMint和InitializeMint2，
+// mint PDA自身为铸造权限且不设冻结权限，mint_seeds为mint PDA的种子（含bump）
+fn create_non_transferable_mint<'a>(
+    payer: &AccountInfo<'a>,
+    mint_account: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    mint_seeds: &[&[u8]],
+) -> ProgramResult {
+    if !mint_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        payer,
+        mint_account,
+        system_program_account,
+        &TOKEN_2022_PROGRAM_ID,
+        NON_TRANSFERABLE_MINT_LEN,
+        mint_seeds,
+    )?;
+
+    // InitializeNonTransferableMint，必须在InitializeMint2之前调用
+    invoke(
+        &Instruction {
+            program_id: TOKEN_2022_PROGRAM_ID,
+            accounts: vec![AccountMeta::new(*mint_account.key, false)],
+            data: vec![32],
+        },
+        std::slice::from_ref(mint_account),
+    )?;
+    // InitializeMint2: 0位小数，铸造权限为mint自身，无冻结权限
+    let mut data = vec![20, 0];
+    data.extend_from_slice(mint_account.key.as_ref());
+    data.push(0);
+    invoke(
+        &Instruction {
+            program_id: TOKEN_2022_PROGRAM_ID,
+            accounts: vec![AccountMeta::new(*mint_account.key, false)],
+            data,
+        },
+        std::slice::from_ref(mint_account),
+    )
+}
+
+// 向钱包发放一枚徽章代币：关联代币账户不存在时创建，再由mint PDA签名MintTo
+fn mint_badge<'a>(
+    wallet_account: &AccountInfo<'a>,
+    token_account: &AccountInfo<'a>,
+    mint_account: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    token_program_account: &AccountInfo<'a>,
+    mint_seeds: &[&[u8]],
+) -> ProgramResult {
+    if find_token_2022_associated_address(wallet_account.key, mint_account.key) != *token_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_token_2022_associated_account(
+        wallet_account,
+        token_account,
+        wallet_account,
+        mint_account,
+        system_program_account,
+        token_program_account,
+    )?;
+    let mut data = vec![7];
+    data.extend_from_slice(&1u64.to_le_bytes());
+    invoke_signed(
+        &Instruction {
+            program_id: TOKEN_2022_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*mint_account.key, false),
+                AccountMeta::new(*token_account.key, false),
+                AccountMeta::new_readonly(*mint_account.key, true),
+            ],
+            data,
+        },
+        &[mint_account.clone(), token_account.clone()],
+        &[mint_seeds],
+    )
+}
+
+// 定义一个处理程序函数，管理员创建不可转让的徽章mint
+pub fn initialize_badge_mint(program_id: &Pubkey, accounts: &[AccountInfo], kind: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let badge_mint_account = next_account_info(account_info_iter)?;
+    let token_program_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    if *token_program_account.key != TOKEN_2022_PROGRAM_ID || *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let kind = BadgeKind::from_u8(kind)?;
+    let (badge_mint_key, badge_mint_bump) = find_badge_mint_address(kind, program_id);
+    if badge_mint_key != *badge_mint_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_non_transferable_mint(
+        admin_account,
+        badge_mint_account,
+        system_program_account,
+        &[BADGE_MINT_SEED, &[kind as u8], &[badge_mint_bump]],
+    )?;
+
+    msg!("{:?} badge mint initialized: {}", kind, badge_mint_key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，达到里程碑的钱包领取不可转让的徽章代币
+pub fn claim_badge(program_id: &Pubkey, accounts: &[AccountInfo], kind: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let wallet_account = next_account_info(account_info_iter)?;
+    let buyer_stats_account = next_account_info(account_info_iter)?;
+    let badge_claim_account = next_account_info(account_info_iter)?;
+    let badge_mint_account = next_account_info(account_info_iter)?;
+    let token_account = next_account_info(account_info_iter)?;
+    let token_program_account = next_account_info(account_info_iter)?;
+    let associated_token_program_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !wallet_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *token_program_account.key != TOKEN_2022_PROGRAM_ID
+        || *associated_token_program_account.key != ASSOCIATED_TOKEN_PROGRAM_ID
+        || *system_program_account.key != system_program::id()
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let kind = BadgeKind::from_u8(kind)?;
+
+    // 校验里程碑
+    if buyer_stats_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if find_buyer_stats_address(wallet_account.key, program_id).0 != *buyer_stats_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let buyer_stats = BuyerStats::unpack(&buyer_stats_account.data.borrow())?;
+    if !kind.is_earned(&buyer_stats) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // 创建领取记录，防止重复领取
+    let (badge_claim_key, badge_claim_bump) = find_badge_claim_address(kind, wallet_account.key, program_id);
+    if badge_claim_key != *badge_claim_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !badge_claim_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        wallet_account,
+        badge_claim_account,
+        system_program_account,
+        program_id,
+        0,
+        &[BADGE_CLAIM_SEED, &[kind as u8], wallet_account.key.as_ref(), &[badge_claim_bump]],
+    )?;
+
+    let (badge_mint_key, badge_mint_bump) = find_badge_mint_address(kind, program_id);
+    if badge_mint_key != *badge_mint_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    mint_badge(
+        wallet_account,
+        token_account,
+        badge_mint_account,
+        system_program_account,
+        token_program_account,
+        &[BADGE_MINT_SEED, &[kind as u8], &[badge_mint_bump]],
+    )?;
+
+    msg!("{:?} badge claimed by {}", kind, wallet_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员创建成就徽章的不可转让mint
+pub fn initialize_achievement_mint(program_id: &Pubkey, accounts: &[AccountInfo], achievement: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let achievement_mint_account = next_account_info(account_info_iter)?;
+    let token_program_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    if *token_program_account.key != TOKEN_2022_PROGRAM_ID || *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let achievement = Achievement::from_u8(achievement)?;
+    let (mint_key, mint_bump) = find_achievement_mint_address(achievement, program_id);
+    if mint_key != *achievement_mint_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_non_transferable_mint(
+        admin_account,
+        achievement_mint_account,
+        system_program_account,
+        &[ACHIEVEMENT_MINT_SEED, &[achievement as u8], &[mint_bump]],
+    )?;
+
+    msg!("{:?} achievement mint initialized: {}", achievement, mint_key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，钱包领取已达成的成就，并可选地领取成就徽章代币
+pub fn claim_achievement(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    achievement: u8,
+    mint_badge_token: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let wallet_account = next_account_info(account_info_iter)?;
+    let stats_account = next_account_info(account_info_iter)?;
+
+    if !wallet_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let achievement = Achievement::from_u8(achievement)?;
+    if stats_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    if achievement.is_seller_achievement() {
+        if find_seller_stats_address(wallet_account.key, program_id).0 != *stats_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let mut stats = SellerStats::unpack(&stats_account.data.borrow())?;
+        stats.refresh_achievements();
+        claim_achievement_flag(stats.achievements, &mut stats.claimed_achievements, achievement)?;
+        stats.updated_at = now;
+        stats.pack_into_slice(&mut stats_account.data.borrow_mut());
+    } else {
+        if find_buyer_stats_address(wallet_account.key, program_id).0 != *stats_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let mut stats = BuyerStats::unpack(&stats_account.data.borrow())?;
+        stats.refresh_achievements();
+        claim_achievement_flag(stats.achievements, &mut stats.claimed_achievements, achievement)?;
+        stats.updated_at = now;
+        stats.pack_into_slice(&mut stats_account.data.borrow_mut());
+    }
+
+    if mint_badge_token {
+        let achievement_mint_account = next_account_info(account_info_iter)?;
+        let token_account = next_account_info(account_info_iter)?;
+        let token_program_account = next_account_info(account_info_iter)?;
+        let associated_token_program_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+        if *token_program_account.key != TOKEN_2022_PROGRAM_ID
+            || *associated_token_program_account.key != ASSOCIATED_TOKEN_PROGRAM_ID
+            || *system_program_account.key != system_program::id()
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let (mint_key, mint_bump) = find_achievement_mint_address(achievement, program_id);
+        if mint_key != *achievement_mint_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        mint_badge(
+            wallet_account,
+            token_account,
+            achievement_mint_account,
+            system_program_account,
+            token_program_account,
+            &[ACHIEVEMENT_MINT_SEED, &[achievement as u8], &[mint_bump]],
+        )?;
+    }
+
+    msg!(
+        "AchievementClaimed: wallet={} achievement={:?} badge={}",
+        wallet_account.key,
+        achievement,
+        mint_badge_token
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，买家就一笔购买发起争议
+pub fn open_dispute(program_id: &Pubkey, accounts: &[AccountInfo], evidence_hash: [u8; 32]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let dispute_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if receipt_account.owner != program_id || ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let receipt = PurchaseReceipt::unpack(&receipt_account.data.borrow())?;
+    if receipt.buyer != *buyer_account.key || receipt.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let seller = AIModelView::new(&ai_model_account.data.borrow())?.owner();
+
+    let (dispute_key, dispute_bump) = find_dispute_address(receipt_account.key, program_id);
+    if dispute_key != *dispute_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !dispute_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        buyer_account,
+        dispute_account,
+        system_program_account,
+        program_id,
+        Dispute::LEN,
+        &[DISPUTE_SEED, receipt_account.key.as_ref(), &[dispute_bump]],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut dispute = Dispute {
+        is_initialized: true,
+        receipt: *receipt_account.key,
+        model: receipt.model,
+        buyer: receipt.buyer,
+        seller,
+        created_at: now,
+        updated_at: now,
+        evidence: Vec::new(),
+    };
+    dispute.add_evidence(buyer_account.key, evidence_hash, now)?;
+    dispute.pack_into_slice(&mut dispute_account.data.borrow_mut());
+
+    msg!(
+        "DisputeOpened: receipt={} buyer={} seller={} evidence={}",
+        dispute.receipt,
+        dispute.buyer,
+        dispute.seller,
+        Hash::new_from_array(evidence_hash)
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，争议的买方或卖方追加证据
+pub fn attach_dispute_evidence(program_id: &Pubkey, accounts: &[AccountInfo], evidence_hash: [u8; 32]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let submitter_account = next_account_info(account_info_iter)?;
+    let dispute_account = next_account_info(account_info_iter)?;
+
+    if !submitter_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if dispute_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut dispute = Dispute::unpack(&dispute_account.data.borrow())?;
+    dispute.add_evidence(submitter_account.key, evidence_hash, Clock::get()?.unix_timestamp)?;
+    dispute.pack_into_slice(&mut dispute_account.data.borrow_mut());
+
+    msg!(
+        "DisputeEvidenceAttached: receipt={} submitter={} evidence={}",
+        dispute.receipt,
+        submitter_account.key,
+        Hash::new_from_array(evidence_hash)
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者发布新版本，版本号必须单调递增
+pub fn publish_model_version(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    version: SemVer,
+    artifact_hash: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let version_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_update_authority(program_id, &ai_model_data, authority_account, accounts)?;
+    // 0.0.0是未发布任何版本时latest_version的默认值，因此首个版本也必须大于它
+    if version <= ai_model_data.latest_version {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let version_index = ai_model_data.version_count;
+    let (version_key, version_bump) = find_model_version_address(ai_model_account.key, version_index, program_id);
+    if version_key != *version_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_pda_account(
+        authority_account,
+        version_account,
+        system_program_account,
+        program_id,
+        ModelVersion::LEN,
+        &[
+            MODEL_VERSION_SEED,
+            ai_model_account.key.as_ref(),
+            &version_index.to_le_bytes(),
+            &[version_bump],
+        ],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let model_version = ModelVersion {
+        is_initialized: true,
+        model: *ai_model_account.key,
+        version_index,
+        version,
+        artifact_hash,
+        created_at: now,
+        updated_at: now,
+    };
+    model_version.pack_into_slice(&mut version_account.data.borrow_mut());
+
+    ai_model_data.version_count = version_index.checked_add(1).ok_or(ProgramError::InvalidArgument)?;
+    ai_model_data.latest_version = version;
+    ai_model_data.updated_at = now;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "ModelVersionPublished: model={} index={} version={}.{}.{} hash={}",
+        ai_model_account.key,
+        version_index,
+        version.major,
+        version.minor,
+        version.patch,
+        Hash::new_from_array(artifact_hash)
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者为最近发布的版本添加更新日志
+pub fn add_changelog_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    content_hash: [u8; 32],
+    uri: String,
+    summary: String,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let version_account = next_account_info(account_info_iter)?;
+    let changelog_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id || version_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if uri.len() > MAX_CHANGELOG_URI_LEN || summary.len() > MAX_CHANGELOG_SUMMARY_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_update_authority(program_id, &ai_model_data, authority_account, accounts)?;
+
+    // 只能为最近发布的版本添加更新日志，使链表按发布顺序排列
+    let latest_index = ai_model_data.version_count.checked_sub(1).ok_or(ProgramError::InvalidArgument)?;
+    let (latest_version_key, _) = find_model_version_address(ai_model_account.key, latest_index, program_id);
+    if latest_version_key != *version_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let model_version = ModelVersion::unpack(&version_account.data.borrow())?;
+
+    let (changelog_key, changelog_bump) = find_changelog_address(version_account.key, program_id);
+    if changelog_key != *changelog_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !changelog_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        authority_account,
+        changelog_account,
+        system_program_account,
+        program_id,
+        ChangelogEntry::LEN,
+        &[CHANGELOG_SEED, version_account.key.as_ref(), &[changelog_bump]],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let entry = ChangelogEntry {
+        is_initialized: true,
+        model: *ai_model_account.key,
+        model_version: *version_account.key,
+        version: model_version.version,
+        content_hash,
+        uri,
+        summary,
+        previous: ai_model_data.latest_changelog,
+        created_at: now,
+        updated_at: now,
+    };
+    entry.pack_into_slice(&mut changelog_account.data.borrow_mut());
+
+    ai_model_data.latest_changelog = *changelog_account.key;
+    ai_model_data.updated_at = now;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "ChangelogAdded: model={} version={}.{}.{} entry={} previous={}",
+        ai_model_account.key,
+        entry.version.major,
+        entry.version.minor,
+        entry.version.patch,
+        changelog_account.key,
+        entry.previous
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者弃用模型并可选地指定后继模型
+pub fn deprecate_model(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let successor_account = next_account_info(account_info_iter).ok();
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+
+    // 后继模型必须是本程序的另一个有效AIModel，且自身没有被弃用
+    let successor = match successor_account {
+        Some(successor_account) => {
+            if successor_account.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            if successor_account.key == ai_model_account.key {
+                return Err(ProgramError::InvalidArgument);
+            }
+            if unpack_ai_model_boxed(successor_account)?.deprecated {
+                return Err(ProgramError::InvalidArgument);
+            }
+            *successor_account.key
+        }
+        None => Pubkey::default(),
+    };
+
+    ai_model_data.deprecated = true;
+    ai_model_data.successor = successor;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("ModelDeprecated: model={} successor={}", ai_model_account.key, successor);
+    Ok(())
+}
+
+// 定义一个处理程序函数，钱包关注卖家
+pub fn follow_seller(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let follower_account = next_account_info(account_info_iter)?;
+    let seller_account = next_account_info(account_info_iter)?;
+    let follow_account = next_account_info(account_info_iter)?;
+    let seller_stats_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !follower_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if follower_account.key == seller_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    open_relationship(
+        program_id,
+        FOLLOW_SEED,
+        follower_account,
+        seller_account.key,
+        follow_account,
+        system_program_account,
+    )?;
+
+    let mut seller_stats = open_seller_stats(
+        program_id,
+        seller_account.key,
+        seller_stats_account,
+        follower_account,
+        system_program_account,
+    )?;
+    seller_stats.follower_count = seller_stats.follower_count
+        .checked_add(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    seller_stats.pack_into_slice(&mut seller_stats_account.data.borrow_mut());
+
+    msg!("SellerFollowed: follower={} seller={}", follower_account.key, seller_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，钱包取消关注卖家
+pub fn unfollow_seller(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let follower_account = next_account_info(account_info_iter)?;
+    let seller_account = next_account_info(account_info_iter)?;
+    let follow_account = next_account_info(account_info_iter)?;
+    let seller_stats_account = next_account_info(account_info_iter)?;
+
+    if !follower_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    close_relationship(program_id, FOLLOW_SEED, follower_account, seller_account.key, follow_account)?;
+
+    if find_seller_stats_address(seller_account.key, program_id).0 != *seller_stats_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if seller_stats_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut seller_stats = SellerStats::unpack(&seller_stats_account.data.borrow())?;
+    seller_stats.follower_count = seller_stats.follower_count
+        .checked_sub(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    seller_stats.updated_at = Clock::get()?.unix_timestamp;
+    seller_stats.pack_into_slice(&mut seller_stats_account.data.borrow_mut());
+
+    msg!("SellerUnfollowed: follower={} seller={}", follower_account.key, seller_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，钱包收藏模型；收藏数变化不刷新模型的updated_at
+pub fn favorite_model(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let wallet_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let favorite_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !wallet_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    open_relationship(
+        program_id,
+        FAVORITE_SEED,
+        wallet_account,
+        ai_model_account.key,
+        favorite_account,
+        system_program_account,
+    )?;
+
+    ai_model_data.favorite_count = ai_model_data.favorite_count
+        .checked_add(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("ModelFavorited: wallet={} model={}", wallet_account.key, ai_model_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，钱包取消收藏模型
+pub fn unfavorite_model(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let wallet_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let favorite_account = next_account_info(account_info_iter)?;
+
+    if !wallet_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    close_relationship(program_id, FAVORITE_SEED, wallet_account, ai_model_account.key, favorite_account)?;
+
+    ai_model_data.favorite_count = ai_model_data.favorite_count
+        .checked_sub(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("ModelUnfavorited: wallet={} model={}", wallet_account.key, ai_model_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置模型分类，分类必须已在注册表中注册
+pub fn set_model_category(program_id: &Pubkey, accounts: &[AccountInfo], category_id: u16) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let registry_account = next_account_info(account_info_iter)?;
+
+    if !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_update_authority(program_id, &ai_model_data, authority_account, accounts)?;
+    if category_id != 0 {
+        if find_category_registry_address(program_id).0 != *registry_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if registry_account.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let registry = CategoryRegistry::unpack(&registry_account.data.borrow())?;
+        if registry.get(category_id).is_none() {
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+
+    ai_model_data.category_id = category_id;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("AIModel {} category set to {}", ai_model_account.key, category_id);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者添加或更新本地化元数据
+pub fn set_localized_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    lang: String,
+    name: String,
+    description_uri: String,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let localized_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    require_update_authority(program_id, &*unpack_ai_model_boxed(ai_model_account)?, authority_account, accounts)?;
+    validate_lang_code(&lang)?;
+    if name.len() > MAX_LOCALIZED_NAME_LEN || description_uri.len() > MAX_LOCALIZED_URI_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (localized_key, localized_bump) = find_localized_metadata_address(ai_model_account.key, &lang, program_id);
+    if localized_key != *localized_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if localized_account.data_is_empty() {
+        create_pda_account(
+            authority_account,
+            localized_account,
+            system_program_account,
+            program_id,
+            LocalizedMetadata::LEN,
+            &[
+                LOCALIZED_METADATA_SEED,
+                ai_model_account.key.as_ref(),
+                lang.as_bytes(),
+                &[localized_bump],
+            ],
+        )?;
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut localized = LocalizedMetadata::unpack_unchecked(&localized_account.data.borrow())?;
+    if !localized.is_initialized {
+        localized.is_initialized = true;
+        localized.model = *ai_model_account.key;
+        localized.lang = lang;
+        localized.created_at = now;
+    }
+    localized.name = name;
+    localized.description_uri = description_uri;
+    localized.updated_at = now;
+    localized.pack_into_slice(&mut localized_account.data.borrow_mut());
+
+    msg!("AIModel {} localized metadata set for {}", ai_model_account.key, localized.lang);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型更新权限设置链下元数据的URI和内容哈希
+pub fn set_metadata_uri(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    metadata_uri: String,
+    metadata_hash: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_update_authority(program_id, &ai_model_data, authority_account, accounts)?;
+    if metadata_uri.len() > MAX_METADATA_URI_LEN || !metadata_uri.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    ai_model_data.metadata_uri = metadata_uri;
+    ai_model_data.metadata_hash = metadata_hash;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "AIModel {} metadata set: {} hash={}",
+        ai_model_account.key,
+        ai_model_data.metadata_uri,
+        Hash::new_from_array(metadata_hash)
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，设置模型的更新权限；所有者可以随时收回，当前更新权限可以移交给其他密钥
+pub fn set_update_authority(program_id: &Pubkey, accounts: &[AccountInfo], update_authority: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let signer_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !signer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if ai_model_data.owner != *signer_account.key && !ai_model_data.is_update_authority(signer_account.key) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    ai_model_data.update_authority = update_authority;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("AIModel {} update authority set to {}", ai_model_account.key, update_authority);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者转移收款和定价权
+pub fn set_model_owner(program_id: &Pubkey, accounts: &[AccountInfo], new_owner: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    if new_owner == Pubkey::default() {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // 更新权限由owner兼任时先固定为原owner，避免转移所有权的同时隐式转移元数据修改权
+    ai_model_data.update_authority = ai_model_data.effective_update_authority();
+    ai_model_data.owner = new_owner;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("AIModel {} owner set to {}", ai_model_account.key, new_owner);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型更新权限修改列出的创作者
+pub fn set_model_creator(program_id: &Pubkey, accounts: &[AccountInfo], creator: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_update_authority(program_id, &ai_model_data, authority_account, accounts)?;
+
+    ai_model_data.set_creator(creator, authority_account.key);
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "CreatorSet: model={} creator={} verified={}",
+        ai_model_account.key,
+        creator,
+        ai_model_data.creator_verified
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，列出的创作者签名确认上架
+pub fn verify_creator(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let creator_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !creator_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if ai_model_data.creator != *creator_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    ai_model_data.creator_verified = true;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("CreatorVerified: model={} creator={}", ai_model_account.key, creator_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者修改基础价格
+pub fn update_price(program_id: &Pubkey, accounts: &[AccountInfo], price: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    check_listing_prices(program_id, config_account, &[price])?;
+
+    let event = events::PriceChanged {
+        model: *ai_model_account.key,
+        mint: ai_model_data.metadata_mint,
+        old_price: ai_model_data.price,
+        new_price: price,
+    };
+    ai_model_data.price = price;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    event.emit();
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置流式租用费率
+pub fn set_rental_rate(program_id: &Pubkey, accounts: &[AccountInfo], rate_per_slot: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    ai_model_data.rental_rate_per_slot = rate_per_slot;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("RentalRateSet: model={} rate_per_slot={}", ai_model_account.key, rate_per_slot);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置许可证发行上限
+pub fn set_max_supply(program_id: &Pubkey, accounts: &[AccountInfo], max_supply: u32) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    ai_model_data.set_max_supply(max_supply)?;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "MaxSupplySet: model={} max_supply={} minted={}",
+        ai_model_account.key,
+        max_supply,
+        ai_model_data.editions_minted
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置开放版次的截止slot
+pub fn set_open_edition_end(program_id: &Pubkey, accounts: &[AccountInfo], end_slot: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    let clock = Clock::get()?;
+    ai_model_data.set_open_edition_end(end_slot, clock.slot)?;
+    ai_model_data.updated_at = clock.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("OpenEditionEndSet: model={} end_slot={}", ai_model_account.key, end_slot);
+    Ok(())
+}
+
+// 定义一个处理程序函数，开放版次截止后定格最终发行数量
+pub fn finalize_open_edition(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    ai_model_data.finalize_editions(Clock::get()?.slot)?;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "OpenEditionFinalized: model={} editions={}",
+        ai_model_account.key,
+        ai_model_data.editions_minted
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置升级折扣
+pub fn set_upgrade_discount(program_id: &Pubkey, accounts: &[AccountInfo], discount_bps: u16) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if discount_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    ai_model_data.upgrade_discount_bps = discount_bps;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("UpgradeDiscountSet: model={} discount_bps={}", ai_model_account.key, discount_bps);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置许可证的可转让性
+pub fn set_license_transferability(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    transferability: u8,
+    royalty_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let transferability = LicenseTransferability::from_u8(transferability).map_err(|_| ProgramError::InvalidArgument)?;
+    if royalty_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    ai_model_data.license_transferability = transferability;
+    ai_model_data.resale_royalty_bps = royalty_bps;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "LicenseTransferabilitySet: model={} transferability={:?} royalty_bps={}",
+        ai_model_account.key,
+        transferability,
+        royalty_bps
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置按量计费的单价
+pub fn set_usage_unit_price(program_id: &Pubkey, accounts: &[AccountInfo], unit_price: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    ai_model_data.usage_unit_price = unit_price;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("UsageUnitPriceSet: model={} unit_price={}", ai_model_account.key, unit_price);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置白名单预售
+pub fn set_presale(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    merkle_root: [u8; 32],
+    presale_price: u64,
+    public_sale_slot: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    // 设置白名单时必须同时给出预售价和公开发售时间
+    if merkle_root != [0u8; 32] && (presale_price == 0 || public_sale_slot == 0) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    ai_model_data.presale_merkle_root = merkle_root;
+    ai_model_data.presale_price = presale_price;
+    ai_model_data.public_sale_slot = public_sale_slot;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "PresaleSet: model={} presale_price={} public_sale_slot={}",
+        ai_model_account.key,
+        presale_price,
+        public_sale_slot
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置从卖家收入中捐给慈善机构的分成
+pub fn set_listing_charity(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    charity: Pubkey,
+    charity_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    check_charity_choice(program_id, account_info_iter.next(), &charity, charity_bps)?;
+    ai_model_data.charity = charity;
+    ai_model_data.charity_bps = charity_bps;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("ListingCharitySet: model={} charity={} charity_bps={}", ai_model_account.key, charity, charity_bps);
+    Ok(())
+}
+
+// 定义一个处理程序函数，买家设置结账时另行捐赠的慈善机构和比例，首次设置时创建买家统计PDA
+pub fn set_buyer_charity(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    charity: Pubkey,
+    charity_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer_account = next_account_info(account_info_iter)?;
+    let buyer_stats_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_charity_choice(program_id, account_info_iter.next(), &charity, charity_bps)?;
+    let mut buyer_stats = open_buyer_stats(
+        program_id,
+        buyer_account.key,
+        buyer_stats_account,
+        buyer_account,
+        system_program_account,
+    )?;
+    buyer_stats.charity = charity;
+    buyer_stats.charity_bps = charity_bps;
+    buyer_stats.pack_into_slice(&mut buyer_stats_account.data.borrow_mut());
+
+    msg!("BuyerCharitySet: buyer={} charity={} charity_bps={}", buyer_account.key, charity, charity_bps);
+    Ok(())
+}
+
+// 检查慈善分成设置：全零表示取消，否则比例必须在(0, 10000]内且charity_account是登记中的慈善机构PDA
+fn check_charity_choice(
+    program_id: &Pubkey,
+    charity_account: Option<&AccountInfo>,
+    charity: &Pubkey,
+    charity_bps: u16,
+) -> ProgramResult {
+    if *charity == Pubkey::default() && charity_bps == 0 {
+        return Ok(());
+    }
+    if *charity == Pubkey::default() || charity_bps == 0 || charity_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let charity_account = charity_account.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if charity_account.key != charity {
+        return Err(ProgramError::InvalidArgument);
+    }
+    load_active_charity(program_id, charity_account)?;
+    Ok(())
+}
+
+// 定义一个处理程序函数，慈善机构的收款钱包提取托管的捐款
+pub fn withdraw_charity_donations(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let wallet_account = next_account_info(account_info_iter)?;
+    let charity_account = next_account_info(account_info_iter)?;
+
+    if !wallet_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if charity_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut charity = Charity::unpack(&charity_account.data.borrow())?;
+    if charity.wallet != *wallet_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if amount > charity.balance {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    charity.balance -= amount;
+    charity.total_withdrawn = charity.total_withdrawn
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    charity.updated_at = Clock::get()?.unix_timestamp;
+    charity.pack_into_slice(&mut charity_account.data.borrow_mut());
+
+    // 慈善机构PDA由本程序拥有，可以直接扣减其lamports
+    **charity_account.lamports.borrow_mut() -= amount;
+    **wallet_account.lamports.borrow_mut() += amount;
+
+    msg!("CharityWithdrawal: charity={} wallet={} amount={}", charity_account.key, wallet_account.key, amount);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置购买时需要出示的验证通行证
+pub fn set_verification_requirement(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    gatekeeper_network: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    ai_model_data.gatekeeper_network = gatekeeper_network;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("VerificationRequirementSet: model={} gatekeeper_network={}", ai_model_account.key, gatekeeper_network);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置drop的防机器人节流
+pub fn set_drop_throttle(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    cooldown_slots: u64,
+    max_per_slot: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    ai_model_data.drop_cooldown_slots = cooldown_slots;
+    ai_model_data.drop_max_per_slot = max_per_slot;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "DropThrottleSet: model={} cooldown_slots={} max_per_slot={}",
+        ai_model_account.key,
+        cooldown_slots,
+        max_per_slot
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者设置按价格源标价及其降级策略
+pub fn set_oracle_pricing(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    price_feed: Pubkey,
+    quote_price: u64,
+    max_staleness_slots: u64,
+    max_confidence_bps: u16,
+    fallback_price: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    // 设置价格源时必须给出计价金额和过期阈值
+    if price_feed != Pubkey::default() && (quote_price == 0 || max_staleness_slots == 0) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if max_confidence_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidArgument);
+    }
+    ai_model_data.price_feed = price_feed;
+    ai_model_data.quote_price = quote_price;
+    ai_model_data.max_staleness_slots = max_staleness_slots;
+    ai_model_data.max_confidence_bps = max_confidence_bps;
+    ai_model_data.fallback_price = fallback_price;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "OraclePricingSet: model={} feed={} quote_price={} max_staleness_slots={} max_confidence_bps={} fallback_price={}",
+        ai_model_account.key,
+        price_feed,
+        quote_price,
+        max_staleness_slots,
+        max_confidence_bps,
+        fallback_price
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型更新权限设置链下产物，修改后需要重新确认和重新审核
+pub fn set_model_artifact(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    storage_class: u8,
+    artifact_uri: String,
+    artifact_hash: [u8; 32],
+    artifact_size_bytes: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    // 可选的指令sysvar之后还可能跟着组织PDA，按地址区分
+    let instructions_account = account_info_iter.as_slice().first().filter(|account| *account.key == sysvar_instructions::id());
+
+    if !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_update_authority(program_id, &ai_model_data, authority_account, accounts)?;
+    let storage_class = StorageClass::from_u8(storage_class).map_err(|_| ProgramError::InvalidArgument)?;
+    if artifact_uri.len() > MAX_ARTIFACT_URI_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+    storage_class.validate_uri(&artifact_uri)?;
+    storage_class.validate_size(artifact_size_bytes)?;
+    // 签名方必须是收款的owner而不是更新权限，这样绑定的是卖家钱包
+    if let Some(instructions_account) = instructions_account {
+        verify_ed25519_signature(instructions_account, &ai_model_data.owner, &artifact_hash)?;
+    }
+
+    ai_model_data.storage_class = storage_class;
+    ai_model_data.artifact_uri = artifact_uri;
+    ai_model_data.artifact_hash = artifact_hash;
+    ai_model_data.artifact_attested_at = 0;
+    ai_model_data.approval_hash = [0; 32];
+    ai_model_data.approved_at = 0;
+    ai_model_data.artifact_signed = instructions_account.is_some();
+    ai_model_data.artifact_size_bytes = artifact_size_bytes;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "AIModel {} artifact set: {:?} {} ({} bytes) hash={}",
+        ai_model_account.key,
+        storage_class,
+        ai_model_data.artifact_uri,
+        artifact_size_bytes,
+        Hash::new_from_array(artifact_hash)
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员设置产物验证人
+pub fn set_artifact_attestor(program_id: &Pubkey, accounts: &[AccountInfo], attestor: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    let mut config = load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    config.artifact_attestor = attestor;
+    config.sync_version();
+    config.updated_at = Clock::get()?.unix_timestamp;
+    config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+    msg!("Artifact attestor set to {}", attestor);
+    Ok(())
+}
+
+// 定义一个处理程序函数，验证人确认链下产物存在且内容与声明一致，
+// artifact_hash为验证人实际核对的哈希，防止卖家在确认前替换产物
+pub fn attest_artifact(program_id: &Pubkey, accounts: &[AccountInfo], artifact_hash: [u8; 32]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let attestor_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !attestor_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = load_config(program_id, config_account)?;
+    if config.artifact_attestor == Pubkey::default() || config.artifact_attestor != *attestor_account.key {
+        require_role(program_id, &config, Role::Oracle, attestor_account, accounts)?;
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if !ai_model_data.storage_class.requires_attestation() || ai_model_data.artifact_hash != artifact_hash {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    ai_model_data.artifact_attested_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "AIModel {} artifact {} attested by {}",
+        ai_model_account.key,
+        ai_model_data.artifact_uri,
+        attestor_account.key
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员设置审核预言机
+pub fn set_listing_oracle(program_id: &Pubkey, accounts: &[AccountInfo], oracle: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    let mut config = load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    config.listing_oracle = oracle;
+    config.sync_version();
+    config.updated_at = Clock::get()?.unix_timestamp;
+    config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+    msg!("Listing oracle set to {}", oracle);
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员设置上架价格的下限和上限，只影响之后的上架和改价
+pub fn set_price_bounds(program_id: &Pubkey, accounts: &[AccountInfo], min_price: u64, max_price: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    let mut config = load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    if max_price != 0 && max_price < min_price {
+        return Err(ProgramError::InvalidArgument);
+    }
+    config.min_listing_price = min_price;
+    config.max_listing_price = max_price;
+    config.sync_version();
+    config.updated_at = Clock::get()?.unix_timestamp;
+    config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+    msg!("Listing price bounds set to [{}, {}]", min_price, max_price);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者为上架开启审核
+pub fn require_listing_approval(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    require_model_owner(program_id, &ai_model_data.owner, owner_account, accounts)?;
+    if ai_model_data.approval_required {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    ai_model_data.approval_required = true;
+    ai_model_data.updated_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!("AIModel {} now requires listing approval", ai_model_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，审核预言机通过开启审核的上架；
+// artifact_hash为预言机实际扫描的产物哈希，防止卖家在审核后替换产物
+pub fn approve_listing(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    artifact_hash: [u8; 32],
+    attestation_hash: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let oracle_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+
+    if !oracle_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = load_config(program_id, config_account)?;
+    if config.listing_oracle == Pubkey::default() || config.listing_oracle != *oracle_account.key {
+        require_role(program_id, &config, Role::Oracle, oracle_account, accounts)?;
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if !ai_model_data.approval_required || ai_model_data.artifact_hash != artifact_hash {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    ai_model_data.approval_hash = attestation_hash;
+    ai_model_data.approved_at = Clock::get()?.unix_timestamp;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+
+    msg!(
+        "ListingApproved: model={} oracle={} attestation={}",
+        ai_model_account.key,
+        oracle_account.key,
+        Hash::new_from_array(attestation_hash)
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，校验证明账户中的证明并写入模型的溯源证明
+pub fn verify_provenance(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let verifier_account = next_account_info(account_info_iter)?;
+    let proof_account = next_account_info(account_info_iter)?;
+    let provenance_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id || verifier_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    require_update_authority(program_id, &*unpack_ai_model_boxed(ai_model_account)?, authority_account, accounts)?;
+    let verifier = ProvenanceVerifier::unpack(&verifier_account.data.borrow())?;
+
+    let proof_data = proof_account.data.borrow();
+    let (proof, public_inputs) = parse_proof_account(&proof_data)?;
+    verifier.proof_system.verify(&verifier.verifying_key, proof, &public_inputs)?;
+    let proof_hash = solana_program::hash::hash(proof).to_bytes();
+    let public_inputs_hash = solana_program::hash::hash(&public_inputs.concat()).to_bytes();
+    drop(proof_data);
+
+    let (provenance_key, provenance_bump) =
+        find_provenance_address(ai_model_account.key, verifier_account.key, program_id);
+    if provenance_key != *provenance_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if provenance_account.data_is_empty() {
+        create_pda_account(
+            authority_account,
+            provenance_account,
+            system_program_account,
+            program_id,
+            ProvenanceAttestation::LEN,
+            &[
+                PROVENANCE_SEED,
+                ai_model_account.key.as_ref(),
+                verifier_account.key.as_ref(),
+                &[provenance_bump],
+            ],
+        )?;
+    }
+    let attestation = ProvenanceAttestation {
+        is_initialized: true,
+        model: *ai_model_account.key,
+        verifier: *verifier_account.key,
+        proof_hash,
+        public_inputs_hash,
+        verified_at: Clock::get()?.unix_timestamp,
+    };
+    attestation.pack_into_slice(&mut provenance_account.data.borrow_mut());
+
+    msg!(
+        "ProvenanceVerified: model={} verifier={} claim={}",
+        ai_model_account.key,
+        verifier_account.key,
+        verifier.claim
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，工作节点提交推理结果承诺
+pub fn commit_inference_result(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_hash: [u8; 32],
+    output_hash: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let worker_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let version_account = next_account_info(account_info_iter)?;
+    let commitment_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !worker_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id || version_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    AIModelView::new(&ai_model_account.data.borrow())?;
+    let model_version = ModelVersion::unpack(&version_account.data.borrow())?;
+    if model_version.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (commitment_key, commitment_bump) =
+        find_inference_commitment_address(version_account.key, worker_account.key, &input_hash, program_id);
+    if commitment_key != *commitment_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // 承诺账户已存在时创建会失败，因此同一输入不能重复提交或改写
+    create_pda_account(
+        worker_account,
+        commitment_account,
+        system_program_account,
+        program_id,
+        InferenceCommitment::LEN,
+        &[
+            INFERENCE_COMMITMENT_SEED,
+            version_account.key.as_ref(),
+            worker_account.key.as_ref(),
+            &input_hash,
+            &[commitment_bump],
+        ],
+    )?;
+    let commitment = InferenceCommitment {
+        is_initialized: true,
+        model: *ai_model_account.key,
+        model_version: *version_account.key,
+        version: model_version.version,
+        worker: *worker_account.key,
+        input_hash,
+        output_hash,
+        committed_at: Clock::get()?.unix_timestamp,
+    };
+    commitment.pack_into_slice(&mut commitment_account.data.borrow_mut());
+
+    msg!(
+        "InferenceCommitted: model={} version={}.{}.{} worker={} input={} output={}",
+        ai_model_account.key,
+        model_version.version.major,
+        model_version.version.minor,
+        model_version.version.patch,
+        worker_account.key,
+        Hash::new_from_array(input_hash),
+        Hash::new_from_array(output_hash)
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，请求者发起付费推理请求并托管max_price
+pub fn enqueue_inference_request(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    request_id: u64,
+    prompt_hash: [u8; 32],
+    max_price: u64,
+    deadline: i64,
+    challenge_window: i64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let requester_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let request_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !requester_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    unpack_ai_model_boxed(ai_model_account)?.require_purchasable()?;
+    let now = Clock::get()?.unix_timestamp;
+    if deadline <= now || !(0..=MAX_INFERENCE_CHALLENGE_WINDOW).contains(&challenge_window) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (request_key, request_bump) =
+        find_inference_request_address(ai_model_account.key, requester_account.key, request_id, program_id);
+    if request_key != *request_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_pda_account(
+        requester_account,
+        request_account,
+        system_program_account,
+        program_id,
+        InferenceRequest::LEN,
+        &[
+            INFERENCE_REQUEST_SEED,
+            ai_model_account.key.as_ref(),
+            requester_account.key.as_ref(),
+            &request_id.to_le_bytes(),
+            &[request_bump],
+        ],
+    )?;
+    // 托管金额存放在请求PDA中，与租金一起在结算或取消时转出
+    invoke(
+        &system_instruction::transfer(requester_account.key, request_account.key, max_price),
+        &[
+            requester_account.clone(),
+            request_account.clone(),
+            system_program_account.clone(),
+        ],
+    )?;
+
+    let request = InferenceRequest {
+        is_initialized: true,
+        model: *ai_model_account.key,
+        requester: *requester_account.key,
+        request_id,
+        prompt_hash,
+        max_price,
+        deadline,
+        challenge_window,
+        created_at: now,
+        updated_at: now,
+        ..InferenceRequest::default()
+    };
+    request.pack_into_slice(&mut request_account.data.borrow_mut());
+
+    msg!(
+        "InferenceRequested: request={} model={} max_price={} deadline={}",
+        request_account.key,
+        ai_model_account.key,
+        max_price,
+        deadline
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，工作节点凭结果承诺提交推理结果，进入挑战期
+pub fn submit_result(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    output_hash: [u8; 32],
+    proof_uri: String,
+    price: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let worker_account = next_account_info(account_info_iter)?;
+    let requester_account = next_account_info(account_info_iter)?;
+    let request_account = next_account_info(account_info_iter)?;
+    let commitment_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let worker_pda_account = next_account_info(account_info_iter)?;
+
+    if !worker_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if request_account.owner != program_id
+        || commitment_account.owner != program_id
+        || ai_model_account.owner != program_id
+        || worker_pda_account.owner != program_id
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if find_worker_address(worker_account.key, program_id).0 != *worker_pda_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if proof_uri.len() > MAX_PROOF_URI_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut worker = Worker::unpack(&worker_pda_account.data.borrow())?;
+    worker.require_staked()?;
+    let mut request = InferenceRequest::unpack(&request_account.data.borrow())?;
+    if request.requester != *requester_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let commitment = InferenceCommitment::unpack(&commitment_account.data.borrow())?;
+    if commitment.worker != *worker_account.key || commitment.output_hash != output_hash {
+        return Err(ProgramError::InvalidArgument);
+    }
+    request.require_fulfillable_by(&commitment, price)?;
+    if request.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !worker.supports(AIModelView::new(&ai_model_account.data.borrow())?.model_format()?) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    request.status = InferenceRequestStatus::Submitted;
+    request.worker = *worker_account.key;
+    request.commitment = *commitment_account.key;
+    request.price = price;
+    request.output_hash = output_hash;
+    request.proof_uri = proof_uri;
+    request.challenge_ends_at = now.saturating_add(request.challenge_window);
+    request.updated_at = now;
+    request.pack_into_slice(&mut request_account.data.borrow_mut());
+    worker.last_fulfilled_at = now;
+    worker.pack_into_slice(&mut worker_pda_account.data.borrow_mut());
+
+    msg!(
+        "InferenceResultSubmitted: request={} worker={} price={} challenge_ends_at={}",
+        request_account.key,
+        worker_account.key,
+        price,
+        request.challenge_ends_at
+    );
+    Ok(())
+}
+
+// 把托管金额按报价付给工作节点并将余额退回请求者，请求状态置为已结算；
+// 请求PDA由本程序拥有，可以直接扣减其lamports，租金留在账户中，已结算的请求保留供审计
+fn pay_out_inference_request(
+    request: &mut InferenceRequest,
+    request_account: &AccountInfo,
+    worker_account: &AccountInfo,
+    requester_account: &AccountInfo,
+    now: i64,
+) -> ProgramResult {
+    if request.worker != *worker_account.key || request.requester != *requester_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let refund = request.max_price - request.price;
+    request.status = InferenceRequestStatus::Fulfilled;
+    request.updated_at = now;
+    request.pack_into_slice(&mut request_account.data.borrow_mut());
+
+    **request_account.lamports.borrow_mut() -= request.max_price;
+    **worker_account.lamports.borrow_mut() += request.price;
+    **requester_account.lamports.borrow_mut() += refund;
+
+    msg!(
+        "InferenceFulfilled: request={} worker={} price={} refund={}",
+        request_account.key,
+        worker_account.key,
+        request.price,
+        refund
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，请求者在挑战期内挑战提交的结果
+pub fn challenge_result(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let requester_account = next_account_info(account_info_iter)?;
+    let request_account = next_account_info(account_info_iter)?;
+
+    if !requester_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if request_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut request = InferenceRequest::unpack(&request_account.data.borrow())?;
+    if request.requester != *requester_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    request.challenge(now)?;
+    request.updated_at = now;
+    request.pack_into_slice(&mut request_account.data.borrow_mut());
+
+    msg!("InferenceResultChallenged: request={} worker={}", request_account.key, request.worker);
+    Ok(())
+}
+
+// 定义一个处理程序函数，结算挑战期内未被挑战的推理结果
+pub fn settle_inference_request(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let worker_account = next_account_info(account_info_iter)?;
+    let requester_account = next_account_info(account_info_iter)?;
+    let request_account = next_account_info(account_info_iter)?;
+
+    if request_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut request = InferenceRequest::unpack(&request_account.data.borrow())?;
+    request.require_settleable(now)?;
+    pay_out_inference_request(&mut request, request_account, worker_account, requester_account, now)
+}
+
+// 定义一个处理程序函数，仲裁者驳回挑战并结算给工作节点
+pub fn uphold_result(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let arbiter_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let worker_account = next_account_info(account_info_iter)?;
+    let requester_account = next_account_info(account_info_iter)?;
+    let request_account = next_account_info(account_info_iter)?;
+
+    if !arbiter_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = load_config(program_id, config_account)?;
+    if config.inference_arbiter == Pubkey::default() || config.inference_arbiter != *arbiter_account.key {
+        require_role(program_id, &config, Role::Arbiter, arbiter_account, accounts)?;
+    }
+    if request_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut request = InferenceRequest::unpack(&request_account.data.borrow())?;
+    if request.status != InferenceRequestStatus::Challenged {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    pay_out_inference_request(&mut request, request_account, worker_account, requester_account, now)
+}
+
+// 定义一个处理程序函数，请求者在截止时间后取消未完成的推理请求
+// 定义一个处理程序函数，请求者在截止时间后取消未完成的推理请求
+pub fn cancel_inference_request(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let requester_account = next_account_info(account_info_iter)?;
+    let request_account = next_account_info(account_info_iter)?;
+
+    if !requester_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if request_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let request = InferenceRequest::unpack(&request_account.data.borrow())?;
+    if request.requester != *requester_account.key || request.status != InferenceRequestStatus::Open {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if Clock::get()?.unix_timestamp <= request.deadline {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    close_account(request_account, requester_account)?;
+
+    msg!("InferenceCancelled: request={}", request_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，运营者注册工作节点或更新其能力
+pub fn register_worker(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    hardware_class: u8,
+    supported_formats: u8,
+    price_per_unit: u64,
+    endpoint_hash: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let operator_account = next_account_info(account_info_iter)?;
+    let worker_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !operator_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let hardware_class = HardwareClass::from_u8(hardware_class).map_err(|_| ProgramError::InvalidArgument)?;
+    if supported_formats == 0 || supported_formats & !ALL_MODEL_FORMATS != 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (worker_key, worker_bump) = find_worker_address(operator_account.key, program_id);
+    if worker_key != *worker_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if worker_account.data_is_empty() {
+        create_pda_account(
+            operator_account,
+            worker_account,
+            system_program_account,
+            program_id,
+            Worker::LEN,
+            &[WORKER_SEED, operator_account.key.as_ref(), &[worker_bump]],
+        )?;
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut worker = Worker::unpack_unchecked(&worker_account.data.borrow())?;
+    if !worker.is_initialized {
+        worker.is_initialized = true;
+        worker.operator = *operator_account.key;
+        worker.created_at = now;
+    }
+    worker.hardware_class = hardware_class;
+    worker.supported_formats = supported_formats;
+    worker.price_per_unit = price_per_unit;
+    worker.endpoint_hash = endpoint_hash;
+    worker.updated_at = now;
+    worker.pack_into_slice(&mut worker_account.data.borrow_mut());
+
+    msg!(
+        "WorkerRegistered: operator={} hardware={:?} formats={:#07b} price_per_unit={}",
+        operator_account.key,
+        hardware_class,
+        supported_formats,
+        price_per_unit
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，工作节点锁定推理请求
+pub fn claim_job(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let worker_account = next_account_info(account_info_iter)?;
+    let request_account = next_account_info(account_info_iter)?;
+    let worker_pda_account = next_account_info(account_info_iter)?;
+
+    if !worker_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if request_account.owner != program_id || worker_pda_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if find_worker_address(worker_account.key, program_id).0 != *worker_pda_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Worker::unpack(&worker_pda_account.data.borrow())?.require_staked()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut request = InferenceRequest::unpack(&request_account.data.borrow())?;
+    request.claim(worker_account.key, now)?;
+    request.updated_at = now;
+    request.pack_into_slice(&mut request_account.data.borrow_mut());
+
+    msg!(
+        "JobClaimed: request={} worker={} expires_at={}",
+        request_account.key,
+        worker_account.key,
+        request.claim_expires_at
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，释放推理请求上已到期的锁定
+pub fn reassign_expired_claim(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let request_account = next_account_info(account_info_iter)?;
+
+    if request_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut request = InferenceRequest::unpack(&request_account.data.borrow())?;
+    let previous = request.claimed_by;
+    request.release_expired_claim(now)?;
+    request.updated_at = now;
+    request.pack_into_slice(&mut request_account.data.borrow_mut());
+
+    msg!("ClaimExpired: request={} worker={}", request_account.key, previous);
+    Ok(())
+}
+
+// 定义一个处理程序函数，运营者追加工作节点质押
+pub fn stake_worker(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let operator_account = next_account_info(account_info_iter)?;
+    let worker_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !operator_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if worker_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut worker = Worker::unpack(&worker_account.data.borrow())?;
+    if worker.operator != *operator_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    invoke(
+        &system_instruction::transfer(operator_account.key, worker_account.key, amount),
+        &[
+            operator_account.clone(),
+            worker_account.clone(),
+            system_program_account.clone(),
+        ],
+    )?;
+
+    worker.stake = worker.stake
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    worker.updated_at = Clock::get()?.unix_timestamp;
+    worker.pack_into_slice(&mut worker_account.data.borrow_mut());
+
+    msg!("Worker {} staked {} lamports, total {}", operator_account.key, amount, worker.stake);
+    Ok(())
+}
+
+// 定义一个处理程序函数，运营者取回工作节点质押
+pub fn unstake_worker(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let operator_account = next_account_info(account_info_iter)?;
+    let worker_account = next_account_info(account_info_iter)?;
+
+    if !operator_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if worker_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut worker = Worker::unpack(&worker_account.data.borrow())?;
+    if worker.operator != *operator_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    worker.require_unstakable(now)?;
+    if amount > worker.stake {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    worker.stake -= amount;
+    worker.updated_at = now;
+    worker.pack_into_slice(&mut worker_account.data.borrow_mut());
+
+    // 工作节点PDA由本程序拥有，可以直接扣减其lamports
+    **worker_account.lamports.borrow_mut() -= amount;
+    **operator_account.lamports.borrow_mut() += amount;
+
+    msg!("Worker {} unstaked {} lamports, remaining {}", operator_account.key, amount, worker.stake);
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员设置推理仲裁者
+pub fn set_inference_arbiter(program_id: &Pubkey, accounts: &[AccountInfo], arbiter: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    let mut config = load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    config.inference_arbiter = arbiter;
+    config.sync_version();
+    config.updated_at = Clock::get()?.unix_timestamp;
+    config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+    msg!("Inference arbiter set to {}", arbiter);
+    Ok(())
+}
+
+// 判断仲裁者复现的结果承诺是否证伪了工作节点的承诺：同一模型版本、同一输入，输出哈希不同
+fn disproves_commitment(reference: &InferenceCommitment, arbiter: &Pubkey, commitment: &InferenceCommitment) -> bool {
+    reference.worker == *arbiter
+        && reference.model_version == commitment.model_version
+        && reference.input_hash == commitment.input_hash
+        && reference.output_hash != commitment.output_hash
+}
+
+// 定义一个处理程序函数，罚没结果被证伪的工作节点质押并转给请求者
+pub fn slash_worker(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let caller_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let worker_account = next_account_info(account_info_iter)?;
+    let request_account = next_account_info(account_info_iter)?;
+    let requester_account = next_account_info(account_info_iter)?;
+    let commitment_account = next_account_info(account_info_iter)?;
+    let reference_account = next_account_info(account_info_iter).ok();
+
+    if !caller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = load_config(program_id, config_account)?;
+    if config.inference_arbiter == Pubkey::default() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if worker_account.owner != program_id
+        || request_account.owner != program_id
+        || commitment_account.owner != program_id
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut request = InferenceRequest::unpack(&request_account.data.borrow())?;
+    // 结算前（挑战期内或被挑战）罚没时还要把托管金额全部退回请求者
+    let escrow_refund = match request.status {
+        InferenceRequestStatus::Fulfilled => 0,
+        InferenceRequestStatus::Submitted | InferenceRequestStatus::Challenged => request.max_price,
+        _ => return Err(ProgramError::InvalidArgument),
+    };
+    if request.commitment != *commitment_account.key
+        || request.requester != *requester_account.key
+        || find_worker_address(&request.worker, program_id).0 != *worker_account.key
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let commitment = InferenceCommitment::unpack(&commitment_account.data.borrow())?;
+
+    // 仲裁者签名即为裁定；否则必须提供仲裁者复现的不一致结果
+    let disproven = match reference_account {
+        _ if *caller_account.key == config.inference_arbiter => true,
+        Some(reference_account) => {
+            if reference_account.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            let reference = InferenceCommitment::unpack(&reference_account.data.borrow())?;
+            disproves_commitment(&reference, &config.inference_arbiter, &commitment)
+        }
+        None => false,
+    };
+    if !disproven {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut worker = Worker::unpack(&worker_account.data.borrow())?;
+    let slashed = worker.slash_amount();
+    worker.stake -= slashed;
+    worker.updated_at = now;
+    worker.pack_into_slice(&mut worker_account.data.borrow_mut());
+    request.status = InferenceRequestStatus::Slashed;
+    request.updated_at = now;
+    request.pack_into_slice(&mut request_account.data.borrow_mut());
+
+    **worker_account.lamports.borrow_mut() -= slashed;
+    **request_account.lamports.borrow_mut() -= escrow_refund;
+    **requester_account.lamports.borrow_mut() += slashed + escrow_refund;
+
+    msg!(
+        "WorkerSlashed: worker={} request={} amount={} refund={}",
+        request.worker,
+        request_account.key,
+        slashed,
+        escrow_refund
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，租用者预存押金开启流式租用
+pub fn open_rental_stream(program_id: &Pubkey, accounts: &[AccountInfo], deposit: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let renter_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let stream_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !renter_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    ai_model_data.require_purchasable()?;
+    if ai_model_data.rental_rate_per_slot == 0 || deposit < ai_model_data.rental_rate_per_slot {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (stream_key, stream_bump) = find_rental_stream_address(ai_model_account.key, renter_account.key, program_id);
+    if stream_key != *stream_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_pda_account(
+        renter_account,
+        stream_account,
+        system_program_account,
+        program_id,
+        RentalStream::LEN,
+        &[
+            RENTAL_STREAM_SEED,
+            ai_model_account.key.as_ref(),
+            renter_account.key.as_ref(),
+            &[stream_bump],
+        ],
+    )?;
+    invoke(
+        &system_instruction::transfer(renter_account.key, stream_account.key, deposit),
+        &[
+            renter_account.clone(),
+            stream_account.clone(),
+            system_program_account.clone(),
+        ],
+    )?;
+
+    let clock = Clock::get()?;
+    let stream = RentalStream {
+        is_initialized: true,
+        model: *ai_model_account.key,
+        owner: ai_model_data.owner,
+        renter: *renter_account.key,
+        rate_per_slot: ai_model_data.rental_rate_per_slot,
+        start_slot: clock.slot,
+        deposit,
+        withdrawn: 0,
+        created_at: clock.unix_timestamp,
+        updated_at: clock.unix_timestamp,
+    };
+    stream.pack_into_slice(&mut stream_account.data.borrow_mut());
+
+    msg!(
+        "RentalStreamOpened: stream={} model={} renter={} rate_per_slot={} deposit={}",
+        stream_account.key,
+        ai_model_account.key,
+        renter_account.key,
+        stream.rate_per_slot,
+        deposit
+    );
+    Ok(())
+}
+
+fn load_rental_stream(program_id: &Pubkey, stream_account: &AccountInfo) -> Result<RentalStream, ProgramError> {
+    if stream_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    RentalStream::unpack(&stream_account.data.borrow())
+}
+
+// 定义一个处理程序函数，所有者提取已流出的租金
+pub fn withdraw_rental_stream(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let stream_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut stream = load_rental_stream(program_id, stream_account)?;
+    if stream.owner != *owner_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let clock = Clock::get()?;
+    let amount = stream.claimable(clock.slot);
+    stream.withdrawn += amount;
+    stream.updated_at = clock.unix_timestamp;
+    stream.pack_into_slice(&mut stream_account.data.borrow_mut());
+
+    // 租用流PDA由本程序拥有，可以直接扣减其lamports
+    **stream_account.lamports.borrow_mut() -= amount;
+    **owner_account.lamports.borrow_mut() += amount;
+
+    msg!("RentalStreamWithdrawn: stream={} amount={}", stream_account.key, amount);
+    Ok(())
+}
+
+// 定义一个处理程序函数，所有者或租用者关闭租用流并按实际使用的slot结算
+pub fn close_rental_stream(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let signer_account = next_account_info(account_info_iter)?;
+    let owner_account = next_account_info(account_info_iter)?;
+    let renter_account = next_account_info(account_info_iter)?;
+    let stream_account = next_account_info(account_info_iter)?;
+
+    if !signer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let stream = load_rental_stream(program_id, stream_account)?;
+    if stream.owner != *owner_account.key || stream.renter != *renter_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if *signer_account.key != stream.owner && *signer_account.key != stream.renter {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let slot = Clock::get()?.slot;
+    let payout = stream.claimable(slot);
+    let refund = stream.refund(slot);
+
+    // 先付清所有者的租金，剩余的押金和租金随账户关闭退回租用者
+    **stream_account.lamports.borrow_mut() -= payout;
+    **owner_account.lamports.borrow_mut() += payout;
+    close_account(stream_account, renter_account)?;
+
+    msg!(
+        "RentalStreamClosed: stream={} slots={} paid={} refund={}",
+        stream_account.key,
+        slot.saturating_sub(stream.start_slot),
+        stream.streamed(slot),
+        refund
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，买家出资发起委托开发
+pub fn fund_commission(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    commission_id: u64,
+    seller: Pubkey,
+    milestone_amounts: Vec<u64>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer_account = next_account_info(account_info_iter)?;
+    let commission_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if seller == *buyer_account.key
+        || milestone_amounts.is_empty()
+        || milestone_amounts.len() > MAX_COMMISSION_MILESTONES
+        || milestone_amounts.contains(&0)
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let total = milestone_amounts
+        .iter()
+        .try_fold(0u64, |total, amount| total.checked_add(*amount))
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    let (commission_key, commission_bump) =
+        find_commission_address(buyer_account.key, &seller, commission_id, program_id);
+    if commission_key != *commission_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_pda_account(
+        buyer_account,
+        commission_account,
+        system_program_account,
+        program_id,
+        Commission::LEN,
+        &[
+            COMMISSION_SEED,
+            buyer_account.key.as_ref(),
+            seller.as_ref(),
+            &commission_id.to_le_bytes(),
+            &[commission_bump],
+        ],
+    )?;
+    invoke(
+        &system_instruction::transfer(buyer_account.key, commission_account.key, total),
+        &[
+            buyer_account.clone(),
+            commission_account.clone(),
+            system_program_account.clone(),
+        ],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let commission = Commission {
+        is_initialized: true,
+        buyer: *buyer_account.key,
+        seller,
+        commission_id,
+        milestones: milestone_amounts
+            .iter()
+            .map(|amount| Milestone {
+                amount: *amount,
+                updated_at: now,
+                ..Milestone::default()
+            })
+            .collect(),
+        created_at: now,
+        updated_at: now,
+    };
+    commission.pack_into_slice(&mut commission_account.data.borrow_mut());
+
+    msg!(
+        "CommissionFunded: commission={} buyer={} seller={} milestones={} total={}",
+        commission_account.key,
+        buyer_account.key,
+        seller,
+        milestone_amounts.len(),
+        total
+    );
+    Ok(())
+}
+
+fn load_commission(program_id: &Pubkey, commission_account: &AccountInfo) -> Result<Commission, ProgramError> {
+    if commission_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Commission::unpack(&commission_account.data.borrow())
+}
+
+// 定义一个处理程序函数，卖家提交里程碑交付物
+pub fn submit_milestone(program_id: &Pubkey, accounts: &[AccountInfo], index: u8, deliverable_hash: [u8; 32]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller_account = next_account_info(account_info_iter)?;
+    let commission_account = next_account_info(account_info_iter)?;
+
+    if !seller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut commission = load_commission(program_id, commission_account)?;
+    if commission.seller != *seller_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    commission.submit(index, deliverable_hash, now)?;
+    commission.updated_at = now;
+    commission.pack_into_slice(&mut commission_account.data.borrow_mut());
+
+    msg!("MilestoneSubmitted: commission={} index={}", commission_account.key, index);
+    Ok(())
+}
+
+// 定义一个处理程序函数，买家确认里程碑
+pub fn approve_milestone(program_id: &Pubkey, accounts: &[AccountInfo], index: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer_account = next_account_info(account_info_iter)?;
+    let commission_account = next_account_info(account_info_iter)?;
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut commission = load_commission(program_id, commission_account)?;
+    if commission.buyer != *buyer_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    commission.approve(index, now)?;
+    commission.updated_at = now;
+    commission.pack_into_slice(&mut commission_account.data.borrow_mut());
+
+    msg!("MilestoneApproved: commission={} index={}", commission_account.key, index);
+    Ok(())
+}
+
+// 定义一个处理程序函数，卖家领取已确认里程碑的托管金额
+pub fn claim_milestone(program_id: &Pubkey, accounts: &[AccountInfo], index: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller_account = next_account_info(account_info_iter)?;
+    let commission_account = next_account_info(account_info_iter)?;
+
+    if !seller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut commission = load_commission(program_id, commission_account)?;
+    if commission.seller != *seller_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let amount = commission.release(index, now)?;
+    commission.updated_at = now;
+    commission.pack_into_slice(&mut commission_account.data.borrow_mut());
+
+    // 委托PDA由本程序拥有，可以直接扣减其lamports
+    **commission_account.lamports.borrow_mut() -= amount;
+    **seller_account.lamports.borrow_mut() += amount;
+
+    msg!("MilestoneClaimed: commission={} index={} amount={}", commission_account.key, index, amount);
+    Ok(())
+}
+
+// 定义一个处理程序函数，买家或卖家对里程碑发起争议
+pub fn dispute_milestone(program_id: &Pubkey, accounts: &[AccountInfo], index: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let party_account = next_account_info(account_info_iter)?;
+    let commission_account = next_account_info(account_info_iter)?;
+
+    if !party_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut commission = load_commission(program_id, commission_account)?;
+    if commission.buyer != *party_account.key && commission.seller != *party_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    commission.dispute(index, now)?;
+    commission.updated_at = now;
+    commission.pack_into_slice(&mut commission_account.data.borrow_mut());
+
+    msg!("MilestoneDisputed: commission={} index={} by={}", commission_account.key, index, party_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员设置委托仲裁者
+pub fn set_commission_arbiter(program_id: &Pubkey, accounts: &[AccountInfo], arbiter: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    let mut config = load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    config.commission_arbiter = arbiter;
+    config.sync_version();
+    config.updated_at = Clock::get()?.unix_timestamp;
+    config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+    msg!("Commission arbiter set to {}", arbiter);
+    Ok(())
+}
+
+// 定义一个处理程序函数，仲裁者裁定有争议的里程碑
+pub fn resolve_milestone_dispute(program_id: &Pubkey, accounts: &[AccountInfo], index: u8, release: bool) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let arbiter_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let buyer_account = next_account_info(account_info_iter)?;
+    let commission_account = next_account_info(account_info_iter)?;
+
+    if !arbiter_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = load_config(program_id, config_account)?;
+    if config.commission_arbiter == Pubkey::default() || config.commission_arbiter != *arbiter_account.key {
+        require_role(program_id, &config, Role::Arbiter, arbiter_account, accounts)?;
+    }
+    let mut commission = load_commission(program_id, commission_account)?;
+    if commission.buyer != *buyer_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let refund = commission.resolve(index, release, now)?;
+    commission.updated_at = now;
+    commission.pack_into_slice(&mut commission_account.data.borrow_mut());
+
+    **commission_account.lamports.borrow_mut() -= refund;
+    **buyer_account.lamports.borrow_mut() += refund;
+
+    msg!(
+        "MilestoneDisputeResolved: commission={} index={} release={} refund={}",
+        commission_account.key,
+        index,
+        release,
+        refund
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，买家关闭已全部结清的委托
+pub fn close_commission(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer_account = next_account_info(account_info_iter)?;
+    let commission_account = next_account_info(account_info_iter)?;
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let commission = load_commission(program_id, commission_account)?;
+    if commission.buyer != *buyer_account.key || !commission.is_settled() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    close_account(commission_account, buyer_account)?;
+
+    msg!("CommissionClosed: commission={}", commission_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者创建或更新订阅方案
+pub fn set_subscription_tiers(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    period_slots: u64,
+    tiers: Vec<SubscriptionTier>,
+    grace_period_slots: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let plan_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    load_config(program_id, config_account)?.require_feature(FEATURE_SUBSCRIPTIONS)?;
+    if ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let owner = AIModelView::new(&ai_model_account.data.borrow())?.owner();
+    require_model_owner(program_id, &owner, owner_account, accounts)?;
+    // 档位价格必须为正，降级时才能把剩余价值折算为时间
+    // 宽限期不超过一个计费周期
+    if period_slots == 0
+        || grace_period_slots > period_slots
+        || tiers.is_empty()
+        || tiers.len() > MAX_SUBSCRIPTION_TIERS
+        || tiers.iter().any(|tier| tier.price == 0)
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (plan_key, plan_bump) = find_subscription_plan_address(ai_model_account.key, program_id);
+    if plan_key != *plan_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if plan_account.data_is_empty() {
+        create_pda_account(
+            owner_account,
+            plan_account,
+            system_program_account,
+            program_id,
+            SubscriptionPlan::LEN,
+            &[SUBSCRIPTION_PLAN_SEED, ai_model_account.key.as_ref(), &[plan_bump]],
+        )?;
+    }
+    let mut plan = SubscriptionPlan::unpack_unchecked(&plan_account.data.borrow())?;
+    let now = Clock::get()?.unix_timestamp;
+    if !plan.is_initialized {
+        plan.is_initialized = true;
+        plan.model = *ai_model_account.key;
+        plan.created_at = now;
+    }
+    if tiers.len() < plan.tiers.len() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    plan.period_slots = period_slots;
+    plan.tiers = tiers;
+    plan.grace_period_slots = grace_period_slots;
+    plan.updated_at = now;
+    plan.pack_into_slice(&mut plan_account.data.borrow_mut());
+
+    msg!(
+        "SubscriptionTiersSet: model={} period_slots={} grace_period_slots={} tiers={}",
+        ai_model_account.key,
+        period_slots,
+        grace_period_slots,
+        plan.tiers.len()
+    );
+    Ok(())
+}
+
+// 校验订阅相关的模型、所有者和方案账户，返回订阅方案
+fn load_subscription_plan(
+    program_id: &Pubkey,
+    owner_account: &AccountInfo,
+    config_account: &AccountInfo,
+    ai_model_account: &AccountInfo,
+    plan_account: &AccountInfo,
+) -> Result<SubscriptionPlan, ProgramError> {
+    load_config(program_id, config_account)?.require_feature(FEATURE_SUBSCRIPTIONS)?;
+    if ai_model_account.owner != program_id || plan_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if AIModelView::new(&ai_model_account.data.borrow())?.owner() != *owner_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let plan = SubscriptionPlan::unpack(&plan_account.data.borrow())?;
+    if plan.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(plan)
+}
+
+// 定义一个处理程序函数，订阅者开始订阅
+pub fn subscribe(program_id: &Pubkey, accounts: &[AccountInfo], tier: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let subscriber_account = next_account_info(account_info_iter)?;
+    let owner_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let plan_account = next_account_info(account_info_iter)?;
+    let subscription_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !subscriber_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let plan = load_subscription_plan(program_id, owner_account, config_account, ai_model_account, plan_account)?;
+    let price = plan.tier(tier)?.price;
+
+    let (subscription_key, subscription_bump) =
+        find_subscription_address(ai_model_account.key, subscriber_account.key, program_id);
+    if subscription_key != *subscription_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if subscription_account.data_is_empty() {
+        create_pda_account(
+            subscriber_account,
+            subscription_account,
+            system_program_account,
+            program_id,
+            Subscription::LEN,
+            &[
+                SUBSCRIPTION_SEED,
+                ai_model_account.key.as_ref(),
+                subscriber_account.key.as_ref(),
+                &[subscription_bump],
+            ],
+        )?;
+    }
+    let mut subscription = Subscription::unpack_unchecked(&subscription_account.data.borrow())?;
+    let clock = Clock::get()?;
+    if subscription.is_active(clock.slot) {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    if !subscription.is_initialized {
+        subscription.is_initialized = true;
+        subscription.model = *ai_model_account.key;
+        subscription.subscriber = *subscriber_account.key;
+        subscription.created_at = clock.unix_timestamp;
+    }
+
+    // 订阅费直接转给模型所有者
+    invoke(
+        &system_instruction::transfer(subscriber_account.key, owner_account.key, price),
+        &[
+            subscriber_account.clone(),
+            owner_account.clone(),
+            system_program_account.clone(),
+        ],
+    )?;
+
+    subscription.tier = tier;
+    subscription.price = price;
+    subscription.period_slots = plan.period_slots;
+    subscription.canceled_at = 0;
+    subscription.start_slot = clock.slot;
+    subscription.paid_through_slot = clock.slot.saturating_add(plan.period_slots);
+    subscription.updated_at = clock.unix_timestamp;
+    subscription.pack_into_slice(&mut subscription_account.data.borrow_mut());
+
+    msg!(
+        "Subscribed: model={} subscriber={} tier={} paid_through_slot={}",
+        ai_model_account.key,
+        subscriber_account.key,
+        tier,
+        subscription.paid_through_slot
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，订阅者在周期中途变更档位
+pub fn change_tier(program_id: &Pubkey, accounts: &[AccountInfo], tier: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let subscriber_account = next_account_info(account_info_iter)?;
+    let owner_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let plan_account = next_account_info(account_info_iter)?;
+    let subscription_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !subscriber_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let plan = load_subscription_plan(program_id, owner_account, config_account, ai_model_account, plan_account)?;
+    if subscription_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut subscription = Subscription::unpack(&subscription_account.data.borrow())?;
+    if subscription.subscriber != *subscriber_account.key || subscription.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let clock = Clock::get()?;
+    let old_tier = subscription.tier;
+    let charge = subscription.change_tier(tier, plan.tier(tier)?.price, plan.period_slots, clock.slot)?;
+    if charge > 0 {
+        invoke(
+            &system_instruction::transfer(subscriber_account.key, owner_account.key, charge),
+            &[
+                subscriber_account.clone(),
+                owner_account.clone(),
+                system_program_account.clone(),
+            ],
+        )?;
+    }
+    subscription.updated_at = clock.unix_timestamp;
+    subscription.pack_into_slice(&mut subscription_account.data.borrow_mut());
+
+    msg!(
+        "SubscriptionTierChanged: subscription={} from={} to={} charge={} paid_through_slot={}",
+        subscription_account.key,
+        old_tier,
+        tier,
+        charge,
+        subscription.paid_through_slot
+    );
+    Ok(())
+}
+
+fn load_subscription(program_id: &Pubkey, subscription_account: &AccountInfo) -> Result<Subscription, ProgramError> {
+    if subscription_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Subscription::unpack(&subscription_account.data.borrow())
+}
+
+// 定义一个处理程序函数，订阅者预存自动续订余额
+pub fn fund_subscription_renewal(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let subscriber_account = next_account_info(account_info_iter)?;
+    let subscription_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !subscriber_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut subscription = load_subscription(program_id, subscription_account)?;
+    if subscription.subscriber != *subscriber_account.key || amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    invoke(
+        &system_instruction::transfer(subscriber_account.key, subscription_account.key, amount),
+        &[
+            subscriber_account.clone(),
+            subscription_account.clone(),
+            system_program_account.clone(),
+        ],
+    )?;
+    subscription.renewal_balance = subscription.renewal_balance.checked_add(amount).ok_or(ProgramError::InvalidArgument)?;
+    subscription.updated_at = Clock::get()?.unix_timestamp;
+    subscription.pack_into_slice(&mut subscription_account.data.borrow_mut());
+
+    msg!(
+        "SubscriptionRenewalFunded: subscription={} amount={} balance={}",
+        subscription_account.key,
+        amount,
+        subscription.renewal_balance
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，用预存余额续订到期的订阅
+pub fn renew_subscription(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let plan_account = next_account_info(account_info_iter)?;
+    let subscription_account = next_account_info(account_info_iter)?;
+
+    let plan = load_subscription_plan(program_id, owner_account, config_account, ai_model_account, plan_account)?;
+    let mut subscription = load_subscription(program_id, subscription_account)?;
+    if subscription.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let clock = Clock::get()?;
+    let in_grace = clock.slot > subscription.paid_through_slot;
+    let price = plan.tier(subscription.tier)?.price;
+    subscription.renew(price, plan.period_slots, plan.grace_period_slots, clock.slot)?;
+    subscription.updated_at = clock.unix_timestamp;
+    subscription.pack_into_slice(&mut subscription_account.data.borrow_mut());
+
+    // 订阅PDA由本程序拥有，可以直接扣减其lamports
+    **subscription_account.lamports.borrow_mut() -= price;
+    **owner_account.lamports.borrow_mut() += price;
+
+    msg!(
+        "SubscriptionRenewed: subscription={} price={} paid_through_slot={} in_grace={}",
+        subscription_account.key,
+        price,
+        subscription.paid_through_slot,
+        in_grace
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，订阅者取消自动续订
+pub fn cancel_subscription(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let subscriber_account = next_account_info(account_info_iter)?;
+    let subscription_account = next_account_info(account_info_iter)?;
+
+    if !subscriber_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut subscription = load_subscription(program_id, subscription_account)?;
+    if subscription.subscriber != *subscriber_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let clock = Clock::get()?;
+    let refund = subscription.cancel(clock.slot, clock.unix_timestamp)?;
+    subscription.updated_at = clock.unix_timestamp;
+    subscription.pack_into_slice(&mut subscription_account.data.borrow_mut());
+
+    **subscription_account.lamports.borrow_mut() -= refund;
+    **subscriber_account.lamports.borrow_mut() += refund;
+
+    msg!(
+        "SubscriptionCanceled: subscription={} paid_through_slot={} refund={}",
+        subscription_account.key,
+        subscription.paid_through_slot,
+        refund
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，关闭已结束的订阅
+pub fn lapse_subscription(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let subscriber_account = next_account_info(account_info_iter)?;
+    let plan_account = next_account_info(account_info_iter)?;
+    let subscription_account = next_account_info(account_info_iter)?;
+
+    if plan_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let plan = SubscriptionPlan::unpack(&plan_account.data.borrow())?;
+    let subscription = load_subscription(program_id, subscription_account)?;
+    if subscription.subscriber != *subscriber_account.key || subscription.model != plan.model {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !subscription.is_ended(Clock::get()?.slot, plan.grace_period_slots) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    close_account(subscription_account, subscriber_account)?;
+
+    let reason = if subscription.is_canceled() {
+        events::SubscriptionEndReason::Canceled
+    } else {
+        events::SubscriptionEndReason::Lapsed
+    };
+    events::SubscriptionEnded {
+        subscription: *subscription_account.key,
+        model: subscription.model,
+        subscriber: subscription.subscriber,
+        reason,
+        paid_through_slot: subscription.paid_through_slot,
+    }
+    .emit();
+    Ok(())
+}
+
+// 定义一个处理程序函数来校验钱包的订阅是否有效并包含所需的访问范围
+pub fn verify_subscription(program_id: &Pubkey, accounts: &[AccountInfo], access_scopes: u32) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let subscription_account = next_account_info(account_info_iter)?;
+    let plan_account = next_account_info(account_info_iter)?;
+    let wallet_account = next_account_info(account_info_iter)?;
+
+    if subscription_account.owner != program_id || plan_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let subscription = Subscription::unpack(&subscription_account.data.borrow())?;
+    let plan = SubscriptionPlan::unpack(&plan_account.data.borrow())?;
+    if subscription.subscriber != *wallet_account.key || subscription.model != plan.model {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !subscription.is_active(Clock::get()?.slot)
+        || plan.tier(subscription.tier)?.access_scopes & access_scopes != access_scopes
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    msg!("Subscription verified: {} holds tier {} of {}", wallet_account.key, subscription.tier, plan.model);
+    Ok(())
+}
+
+// 定义一个处理程序函数，创建组织
+pub fn create_organization(program_id: &Pubkey, accounts: &[AccountInfo], org_id: u32, name: String) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let creator_account = next_account_info(account_info_iter)?;
+    let organization_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !creator_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if name.is_empty() || name.len() > MAX_ORGANIZATION_NAME_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (organization_key, organization_bump) = find_organization_address(creator_account.key, org_id, program_id);
+    if organization_key != *organization_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_pda_account(
+        creator_account,
+        organization_account,
+        system_program_account,
+        program_id,
+        Organization::LEN,
+        &[
+            ORGANIZATION_SEED,
+            creator_account.key.as_ref(),
+            &org_id.to_le_bytes(),
+            &[organization_bump],
+        ],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let organization = Organization {
+        is_initialized: true,
+        creator: *creator_account.key,
+        org_id,
+        name,
+        members: vec![OrganizationMember {
+            member: *creator_account.key,
+            role: OrganizationRole::Admin,
+        }],
+        created_at: now,
+        updated_at: now,
+    };
+    organization.pack_into_slice(&mut organization_account.data.borrow_mut());
+
+    msg!("OrganizationCreated: organization={} creator={}", organization_account.key, creator_account.key);
+    Ok(())
+}
+
+// 校验签名者是组织管理员，返回组织
+fn load_organization_as_admin(
+    program_id: &Pubkey,
+    organization_account: &AccountInfo,
+    admin_account: &AccountInfo,
+) -> Result<Organization, ProgramError> {
+    if !admin_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let organization = load_organization(program_id, organization_account)?;
+    organization.require_role(admin_account.key, OrganizationRole::Admin)?;
+    Ok(organization)
+}
+
+// 定义一个处理程序函数，组织管理员添加成员或修改成员角色
+pub fn set_organization_member(program_id: &Pubkey, accounts: &[AccountInfo], member: Pubkey, role: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let organization_account = next_account_info(account_info_iter)?;
+
+    let mut organization = load_organization_as_admin(program_id, organization_account, admin_account)?;
+    let role = OrganizationRole::from_u8(role).map_err(|_| ProgramError::InvalidArgument)?;
+    organization.set_member(member, role)?;
+    organization.updated_at = Clock::get()?.unix_timestamp;
+    organization.pack_into_slice(&mut organization_account.data.borrow_mut());
+
+    msg!("OrganizationMemberSet: organization={} member={} role={:?}", organization_account.key, member, role);
+    Ok(())
+}
+
+// 定义一个处理程序函数，组织管理员移除成员
+pub fn remove_organization_member(program_id: &Pubkey, accounts: &[AccountInfo], member: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let organization_account = next_account_info(account_info_iter)?;
+
+    let mut organization = load_organization_as_admin(program_id, organization_account, admin_account)?;
+    organization.remove_member(&member)?;
+    organization.updated_at = Clock::get()?.unix_timestamp;
+    organization.pack_into_slice(&mut organization_account.data.borrow_mut());
+
+    msg!("OrganizationMemberRemoved: organization={} member={}", organization_account.key, member);
+    Ok(())
+}
+
+// 定义一个处理程序函数，组织管理员提取组织PDA中的资金
+pub fn withdraw_organization_funds(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let organization_account = next_account_info(account_info_iter)?;
+
+    load_organization_as_admin(program_id, organization_account, admin_account)?;
+    let available = organization_account
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(Organization::LEN));
+    if amount > available {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    // 组织PDA由本程序拥有，可以直接扣减其lamports
+    **organization_account.lamports.borrow_mut() -= amount;
+    **admin_account.lamports.borrow_mut() += amount;
+
+    msg!("OrganizationFundsWithdrawn: organization={} admin={} amount={}", organization_account.key, admin_account.key, amount);
+    Ok(())
+}
+
+// 定义一个处理程序函数，超级管理员授予角色
+pub fn grant_role(program_id: &Pubkey, accounts: &[AccountInfo], role: u8, holder: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let role_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let role = Role::from_u8(role)?;
+    let (role_key, role_bump) = find_role_address(role, &holder, program_id);
+    if role_key != *role_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !role_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        admin_account,
+        role_account,
+        system_program_account,
+        program_id,
+        RoleAssignment::LEN,
+        &[ROLE_SEED, &[role as u8], holder.as_ref(), &[role_bump]],
+    )?;
+    let assignment = RoleAssignment {
+        is_initialized: true,
+        role,
+        holder,
+        granted_by: *admin_account.key,
+        granted_at: Clock::get()?.unix_timestamp,
+    };
+    assignment.pack_into_slice(&mut role_account.data.borrow_mut());
+
+    msg!("RoleGranted: role={:?} holder={} granted_by={}", role, holder, admin_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，超级管理员撤销角色并关闭角色PDA
+pub fn revoke_role(program_id: &Pubkey, accounts: &[AccountInfo], role: u8, holder: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let role_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    let role = Role::from_u8(role)?;
+    if find_role_address(role, &holder, program_id).0 != *role_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if role_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    // 租金退给授予角色时出资的管理员，而不是执行撤销的管理员
+    let assignment = RoleAssignment::unpack(&role_account.data.borrow())?;
+    close_account_to_rent_payer(role_account, &assignment.granted_by, accounts)?;
+
+    msg!("RoleRevoked: role={:?} holder={} revoked_by={}", role, holder, admin_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，买家在购买凭证中绑定或解除绑定API密钥的哈希
+pub fn bind_api_key(program_id: &Pubkey, accounts: &[AccountInfo], api_key_hash: [u8; 32]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if receipt_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut receipt = PurchaseReceipt::unpack(&receipt_account.data.borrow())?;
+    if receipt.buyer != *buyer_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let bound = api_key_hash != [0u8; 32];
+    receipt.api_key_hash = api_key_hash;
+    receipt.api_key_bound_at = if bound { now } else { 0 };
+    receipt.updated_at = now;
+    receipt.pack_into_slice(&mut receipt_account.data.borrow_mut());
+
+    if bound {
+        msg!("ApiKeyBound: receipt={} model={} buyer={}", receipt_account.key, receipt.model, receipt.buyer);
+    } else {
+        msg!("ApiKeyUnbound: receipt={} model={} buyer={}", receipt_account.key, receipt.model, receipt.buyer);
+    }
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员设置计量权限
+pub fn set_metering_authority(program_id: &Pubkey, accounts: &[AccountInfo], authority: Pubkey) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    let mut config = load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    config.metering_authority = authority;
+    config.sync_version();
+    config.updated_at = Clock::get()?.unix_timestamp;
+    config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+    msg!("Metering authority set to {}", authority);
+    Ok(())
+}
+
+// 定义一个处理程序函数，计量权限上报许可证的请求数，按档位限额接受或拒绝
+pub fn record_usage(program_id: &Pubkey, accounts: &[AccountInfo], requests: u32) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let counter_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = load_config(program_id, config_account)?;
+    if config.metering_authority == Pubkey::default() || config.metering_authority != *authority_account.key {
+        require_role(program_id, &config, Role::Oracle, authority_account, accounts)?;
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if requests == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if receipt_account.owner != program_id || ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let receipt = PurchaseReceipt::unpack(&receipt_account.data.borrow())?;
+    if receipt.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let limit = unpack_ai_model_boxed(ai_model_account)?.tier_request_limit(receipt.tier);
+
+    let (counter_key, counter_bump) = find_usage_counter_address(receipt_account.key, program_id);
+    if counter_key != *counter_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if counter_account.data_is_empty() {
+        create_pda_account(
+            authority_account,
+            counter_account,
+            system_program_account,
+            program_id,
+            UsageCounter::LEN,
+            &[USAGE_COUNTER_SEED, receipt_account.key.as_ref(), &[counter_bump]],
+        )?;
+    }
+    let clock = Clock::get()?;
+    let mut counter = UsageCounter::unpack_unchecked(&counter_account.data.borrow())?;
+    if !counter.is_initialized {
+        counter.is_initialized = true;
+        counter.receipt = *receipt_account.key;
+        counter.model = receipt.model;
+        counter.epoch = clock.slot / METERING_EPOCH_SLOTS;
+        counter.created_at = clock.unix_timestamp;
+    }
+    let accepted = counter.record(requests as u64, limit, clock.slot);
+    counter.updated_at = clock.unix_timestamp;
+    counter.pack_into_slice(&mut counter_account.data.borrow_mut());
+
+    msg!(
+        "{}: receipt={} epoch={} requests={} windowed={} limit={}",
+        if accepted { "UsageRecorded" } else { "UsageRejected" },
+        receipt_account.key,
+        counter.epoch,
+        requests,
+        counter.windowed_requests(clock.slot),
+        limit
+    );
+    Ok(())
+}
+
+// 读取Token-2022 mint账户的小数位数，TransferChecked需要
+fn token_2022_mint_decimals(mint_account: &AccountInfo) -> Result<u8, ProgramError> {
+    if *mint_account.owner != TOKEN_2022_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    // Mint布局: COption<Pubkey>铸币权限(36) + u64供应量(8) + u8小数位数
+    mint_account.data.borrow().get(44).copied().ok_or(ProgramError::InvalidAccountData)
+}
+
+// 通过Token-2022的TransferChecked转账，authority为PDA时由signer_seeds签名
+fn transfer_token_2022<'a>(
+    source: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let mut data = vec![12];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(token_2022_mint_decimals(mint)?);
+    invoke_signed(
+        &Instruction {
+            program_id: TOKEN_2022_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*source.key, false),
+                AccountMeta::new_readonly(*mint.key, false),
+                AccountMeta::new(*destination.key, false),
+                AccountMeta::new_readonly(*authority.key, true),
+            ],
+            data,
+        },
+        &[source.clone(), mint.clone(), destination.clone(), authority.clone()],
+        signer_seeds,
+    )
+}
+
+// 通过Token-2022的Burn销毁owner代币账户中的代币，owner需签名
+fn burn_token_2022<'a>(
+    account: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let mut data = vec![8];
+    data.extend_from_slice(&amount.to_le_bytes());
+    invoke(
+        &Instruction {
+            program_id: TOKEN_2022_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*account.key, false),
+                AccountMeta::new(*mint.key, false),
+                AccountMeta::new_readonly(*owner.key, true),
+            ],
+            data,
+        },
+        &[account.clone(), mint.clone(), owner.clone()],
+    )
+}
+
+// 通过Token-2022的CloseAccount关闭余额为零的代币账户，租金退给destination，owner为PDA时需要传入其签名种子
+fn close_token_2022_account<'a>(
+    account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &Instruction {
+            program_id: TOKEN_2022_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*account.key, false),
+                AccountMeta::new(*destination.key, false),
+                AccountMeta::new_readonly(*owner.key, true),
+            ],
+            data: vec![9],
+        },
+        &[account.clone(), destination.clone(), owner.clone()],
+        signer_seeds,
+    )
+}
+
+// 读取Token-2022代币账户的实际余额（账户数据的32..64为owner，64..72为amount）
+fn token_2022_account_amount(account: &AccountInfo) -> Result<u64, ProgramError> {
+    if account.owner != &TOKEN_2022_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let data = account.data.borrow();
+    let amount = data.get(64..72).ok_or(ProgramError::InvalidAccountData)?;
+    Ok(u64::from_le_bytes(amount.try_into().unwrap()))
+}
+
+// 代币账户的实际余额为0时关闭它，返回是否已关闭。余额以代币账户为准而不是程序记账：
+// 任何人都可以向账户转入代币，此时保留账户而不是让CloseAccount失败导致整笔指令回滚
+fn close_token_2022_account_if_empty<'a>(
+    account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<bool, ProgramError> {
+    if token_2022_account_amount(account)? != 0 {
+        return Ok(false);
+    }
+    close_token_2022_account(account, destination, owner, signer_seeds)?;
+    Ok(true)
+}
+
+// 通过关联代币账户程序的CreateIdempotent为wallet创建mint的Token-2022关联代币账户，已存在时不做任何事
+fn create_token_2022_associated_account<'a>(
+    payer: &AccountInfo<'a>,
+    associated_account: &AccountInfo<'a>,
+    wallet: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    token_program_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    invoke(
+        &Instruction {
+            program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*payer.key, true),
+                AccountMeta::new(*associated_account.key, false),
+                AccountMeta::new_readonly(*wallet.key, false),
+                AccountMeta::new_readonly(*mint.key, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+            ],
+            data: vec![1],
+        },
+        &[
+            payer.clone(),
+            associated_account.clone(),
+            wallet.clone(),
+            mint.clone(),
+            system_program_account.clone(),
+            token_program_account.clone(),
+        ],
+    )
+}
+
+// 定义一个处理程序函数，钱包向自己的预付额度充值SOL或Token-2022代币
+pub fn top_up_credits(program_id: &Pubkey, accounts: &[AccountInfo], mint: Pubkey, amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let wallet_account = next_account_info(account_info_iter)?;
+    let credit_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !wallet_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (credit_key, credit_bump) = find_credit_address(wallet_account.key, &mint, program_id);
+    if credit_key != *credit_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if credit_account.data_is_empty() {
+        create_pda_account(
+            wallet_account,
+            credit_account,
+            system_program_account,
+            program_id,
+            CreditAccount::LEN,
+            &[CREDIT_SEED, wallet_account.key.as_ref(), mint.as_ref(), &[credit_bump]],
+        )?;
+    } else if credit_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if mint == Pubkey::default() {
+        invoke(
+            &system_instruction::transfer(wallet_account.key, credit_account.key, amount),
+            &[wallet_account.clone(), credit_account.clone(), system_program_account.clone()],
+        )?;
+    } else {
+        let mint_account = next_account_info(account_info_iter)?;
+        let wallet_token_account = next_account_info(account_info_iter)?;
+        let vault_token_account = next_account_info(account_info_iter)?;
+        let token_program_account = next_account_info(account_info_iter)?;
+        let associated_token_program_account = next_account_info(account_info_iter)?;
+        if *token_program_account.key != TOKEN_2022_PROGRAM_ID
+            || *associated_token_program_account.key != ASSOCIATED_TOKEN_PROGRAM_ID
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if *mint_account.key != mint
+            || find_token_2022_associated_address(&credit_key, &mint) != *vault_token_account.key
+        {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        create_token_2022_associated_account(
+            wallet_account,
+            vault_token_account,
+            credit_account,
+            mint_account,
+            system_program_account,
+            token_program_account,
+        )?;
+        transfer_token_2022(wallet_token_account, mint_account, vault_token_account, wallet_account, amount, &[])?;
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut credits = CreditAccount::unpack_unchecked(&credit_account.data.borrow())?;
+    if !credits.is_initialized {
+        credits.is_initialized = true;
+        credits.wallet = *wallet_account.key;
+        credits.mint = mint;
+        credits.created_at = now;
+    }
+    credits.deposit(amount)?;
+    credits.updated_at = now;
+    credits.pack_into_slice(&mut credit_account.data.borrow_mut());
+
+    msg!(
+        "CreditsToppedUp: wallet={} mint={} amount={} balance={}",
+        wallet_account.key,
+        mint,
+        amount,
+        credits.balance
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，计量权限从钱包的预付额度中扣减用量费用并付给收款方
+pub fn burn_credits(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let credit_account = next_account_info(account_info_iter)?;
+    let recipient_account = next_account_info(account_info_iter)?;
+
+    if !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = load_config(program_id, config_account)?;
+    if config.metering_authority == Pubkey::default() || config.metering_authority != *authority_account.key {
+        require_role(program_id, &config, Role::Oracle, authority_account, accounts)?;
+    }
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if credit_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut credits = CreditAccount::unpack(&credit_account.data.borrow())?;
+    let (credit_key, credit_bump) = find_credit_address(&credits.wallet, &credits.mint, program_id);
+    if credit_key != *credit_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    credits.spend(amount)?;
+
+    if credits.is_sol() {
+        **credit_account.lamports.borrow_mut() = credit_account
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        **recipient_account.lamports.borrow_mut() = recipient_account
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+    } else {
+        let mint_account = next_account_info(account_info_iter)?;
+        let vault_token_account = next_account_info(account_info_iter)?;
+        let token_program_account = next_account_info(account_info_iter)?;
+        let wallet_account = next_account_info(account_info_iter)?;
+        if *token_program_account.key != TOKEN_2022_PROGRAM_ID {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if *mint_account.key != credits.mint
+            || find_token_2022_associated_address(&credit_key, &credits.mint) != *vault_token_account.key
+        {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if *wallet_account.key != credits.wallet {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let credit_seeds: &[&[u8]] = &[CREDIT_SEED, credits.wallet.as_ref(), credits.mint.as_ref(), &[credit_bump]];
+        transfer_token_2022(vault_token_account, mint_account, recipient_account, credit_account, amount, &[credit_seeds])?;
+        // 额度扣完后托管的代币账户不再需要，关闭它而不是留下零余额账户，再次充值时会重新创建；
+        // 有人额外转入代币时保留账户，额外的代币不计入额度
+        if credits.balance == 0
+            && close_token_2022_account_if_empty(vault_token_account, wallet_account, credit_account, &[credit_seeds])?
+        {
+            msg!("CreditVaultClosed: wallet={} mint={}", credits.wallet, credits.mint);
+        }
+    }
+
+    credits.updated_at = Clock::get()?.unix_timestamp;
+    credits.pack_into_slice(&mut credit_account.data.borrow_mut());
+
+    msg!(
+        "CreditsBurned: wallet={} mint={} amount={} recipient={} balance={}",
+        credits.wallet,
+        credits.mint,
+        amount,
+        recipient_account.key,
+        credits.balance
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，把已计量但未结算的用量从买家的预付额度中扣款给卖家
+pub fn settle_usage(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account = next_account_info(account_info_iter)?;
+    let counter_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    // 按量计费只从SOL额度扣款，lamports托管在额度PDA自身，没有需要清理的代币账户；
+    // 额度PDA记录累计充值和结算供对账，扣完后保留
+    let credit_account = next_account_info(account_info_iter)?;
+    let seller_account = next_account_info(account_info_iter)?;
+    let proceeds_vault_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let seller_bond_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !payer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if counter_account.owner != program_id
+        || receipt_account.owner != program_id
+        || ai_model_account.owner != program_id
+        || credit_account.owner != program_id
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let receipt = PurchaseReceipt::unpack(&receipt_account.data.borrow())?;
+    if find_usage_counter_address(receipt_account.key, program_id).0 != *counter_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // 按量计费以lamports计价，从买家的SOL额度中结算
+    if find_credit_address(&receipt.buyer, &Pubkey::default(), program_id).0 != *credit_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if receipt.model != *ai_model_account.key || ai_model_data.owner != *seller_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut counter = UsageCounter::unpack(&counter_account.data.borrow())?;
+    let mut credits = CreditAccount::unpack(&credit_account.data.borrow())?;
+
+    let unit_price = ai_model_data.usage_unit_price;
+    let (requests, amount) = counter.settle(unit_price, credits.balance)?;
+    if requests == 0 {
+        return Err(ProgramError::InsufficientFunds);
+    }
+    credits.spend(amount)?;
+
+    let clock = Clock::get()?;
+    let source = PaymentSource::Escrow(credit_account);
+    let mut treasury = load_treasury(program_id, treasury_account)?;
+    let fee_bps = treasury.primary_fee_rate(seller_bond_staked(program_id, seller_account.key, seller_bond_account)?);
+    let fee = fee_at_bps(amount, fee_bps);
+    if fee > 0 {
+        source.pay(treasury_account, system_program_account, fee)?;
+        treasury.total_fees = treasury.total_fees
+            .checked_add(fee)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        treasury.updated_at = clock.unix_timestamp;
+        treasury.pack_into_slice(&mut treasury_account.data.borrow_mut());
+    }
+    let seller_amount = withhold_proceeds(
+        program_id,
+        &source,
+        seller_account,
+        proceeds_vault_account,
+        system_program_account,
+        amount - fee,
+    )?;
+    if ai_model_data.vault_proceeds {
+        deposit_proceeds(
+            program_id,
+            payer_account,
+            &source,
+            seller_account,
+            proceeds_vault_account,
+            system_program_account,
+            seller_amount,
+        )?;
+    } else {
+        source.pay(seller_account, system_program_account, seller_amount)?;
+    }
+
+    counter.updated_at = clock.unix_timestamp;
+    counter.pack_into_slice(&mut counter_account.data.borrow_mut());
+    credits.updated_at = clock.unix_timestamp;
+    credits.pack_into_slice(&mut credit_account.data.borrow_mut());
+
+    events::UsageSettled {
+        receipt: *receipt_account.key,
+        model: receipt.model,
+        buyer: receipt.buyer,
+        requests,
+        unit_price,
+        amount,
+        fee,
+        unsettled_requests: counter.unsettled_requests(),
+        remaining_balance: credits.balance,
+    }
+    .emit();
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员启用市场额度代币并创建额度mint
+pub fn configure_credit_mint(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let reserve_mint_account = next_account_info(account_info_iter)?;
+    let credit_mint_account = next_account_info(account_info_iter)?;
+    let token_program_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    let mut config = load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    if *token_program_account.key != TOKEN_2022_PROGRAM_ID || *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if config.credit_reserve_mint != Pubkey::default() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let decimals = token_2022_mint_decimals(reserve_mint_account)?;
+    let (credit_mint_key, credit_mint_bump) = find_credit_mint_address(program_id);
+    if credit_mint_key != *credit_mint_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_pda_account(
+        admin_account,
+        credit_mint_account,
+        system_program_account,
+        &TOKEN_2022_PROGRAM_ID,
+        TOKEN_2022_MINT_LEN,
+        &[CREDIT_MINT_SEED, &[credit_mint_bump]],
+    )?;
+    // InitializeMint2: 与储备mint相同的小数位数，铸造权限为mint自身，无冻结权限
+    let mut data = vec![20, decimals];
+    data.extend_from_slice(credit_mint_key.as_ref());
+    data.push(0);
+    invoke(
+        &Instruction {
+            program_id: TOKEN_2022_PROGRAM_ID,
+            accounts: vec![AccountMeta::new(credit_mint_key, false)],
+            data,
+        },
+        std::slice::from_ref(credit_mint_account),
+    )?;
+
+    config.credit_reserve_mint = *reserve_mint_account.key;
+    config.sync_version();
+    config.updated_at = Clock::get()?.unix_timestamp;
+    config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+    msg!("Credit mint {} initialized, reserve mint {}", credit_mint_key, reserve_mint_account.key);
+    Ok(())
+}
+
+// 读取启用了额度代币的市场配置，并校验储备mint、额度mint和储备权限
+fn load_credit_config(
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+    reserve_mint_account: &AccountInfo,
+    credit_mint_account: &AccountInfo,
+    reserve_authority_account: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    let config = load_config(program_id, config_account)?;
+    if config.credit_reserve_mint == Pubkey::default() {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if config.credit_reserve_mint != *reserve_mint_account.key
+        || find_credit_mint_address(program_id).0 != *credit_mint_account.key
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (reserve_key, reserve_bump) = find_credit_reserve_address(program_id);
+    if reserve_key != *reserve_authority_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(reserve_bump)
+}
+
+// 定义一个处理程序函数，买家存入储备代币并按1:1铸造市场额度代币
+pub fn buy_credits(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let reserve_mint_account = next_account_info(account_info_iter)?;
+    let credit_mint_account = next_account_info(account_info_iter)?;
+    let reserve_authority_account = next_account_info(account_info_iter)?;
+    let buyer_reserve_token_account = next_account_info(account_info_iter)?;
+    let reserve_token_account = next_account_info(account_info_iter)?;
+    let buyer_credit_token_account = next_account_info(account_info_iter)?;
+    let token_program_account = next_account_info(account_info_iter)?;
+    let associated_token_program_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *token_program_account.key != TOKEN_2022_PROGRAM_ID
+        || *associated_token_program_account.key != ASSOCIATED_TOKEN_PROGRAM_ID
+        || *system_program_account.key != system_program::id()
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    load_credit_config(
+        program_id,
+        config_account,
+        reserve_mint_account,
+        credit_mint_account,
+        reserve_authority_account,
+    )?;
+    let (credit_mint_key, credit_mint_bump) = find_credit_mint_address(program_id);
+    if find_token_2022_associated_address(reserve_authority_account.key, reserve_mint_account.key)
+        != *reserve_token_account.key
+        || find_token_2022_associated_address(buyer_account.key, &credit_mint_key) != *buyer_credit_token_account.key
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    create_token_2022_associated_account(
+        buyer_account,
+        reserve_token_account,
+        reserve_authority_account,
+        reserve_mint_account,
+        system_program_account,
+        token_program_account,
+    )?;
+    create_token_2022_associated_account(
+        buyer_account,
+        buyer_credit_token_account,
+        buyer_account,
+        credit_mint_account,
+        system_program_account,
+        token_program_account,
+    )?;
+    transfer_token_2022(
+        buyer_reserve_token_account,
+        reserve_mint_account,
+        reserve_token_account,
+        buyer_account,
+        amount,
+        &[],
+    )?;
+    // MintTo等量的额度代币，由额度mint PDA签名
+    let mut data = vec![7];
+    data.extend_from_slice(&amount.to_le_bytes());
+    invoke_signed(
+        &Instruction {
+            program_id: TOKEN_2022_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(credit_mint_key, false),
+                AccountMeta::new(*buyer_credit_token_account.key, false),
+                AccountMeta::new_readonly(credit_mint_key, true),
+            ],
+            data,
+        },
+        &[credit_mint_account.clone(), buyer_credit_token_account.clone()],
+        &[&[CREDIT_MINT_SEED, &[credit_mint_bump]]],
+    )?;
+
+    msg!("CreditsPurchased: buyer={} amount={}", buyer_account.key, amount);
+    Ok(())
+}
+
+// 定义一个处理程序函数，买家以市场额度代币购买模型
+pub fn purchase_with_credits(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    tier_index: u8,
+    quantity: u32,
+    purchase_index: u32,
+    acknowledge_deprecation: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let buyer_account = next_account_info(account_info_iter)?;
+    let seller_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let seller_stats_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let proceeds_vault_account = next_account_info(account_info_iter)?;
+    let sales_ledger_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let seller_bond_account = next_account_info(account_info_iter)?;
+    let buyer_stats_account = next_account_info(account_info_iter)?;
+    let buyer_receipts_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let credit_mint_account = next_account_info(account_info_iter)?;
+    let buyer_credit_token_account = next_account_info(account_info_iter)?;
+    let reserve_mint_account = next_account_info(account_info_iter)?;
+    let reserve_token_account = next_account_info(account_info_iter)?;
+    let reserve_authority_account = next_account_info(account_info_iter)?;
+    let seller_reserve_token_account = next_account_info(account_info_iter)?;
+    let treasury_reserve_token_account = next_account_info(account_info_iter)?;
+    let token_program_account = next_account_info(account_info_iter)?;
+    let associated_token_program_account = next_account_info(account_info_iter)?;
+    let previous_receipt_account = if purchase_index > 0 {
+        Some(next_account_info(account_info_iter)?)
+    } else {
+        None
+    };
+    let trailing_accounts = account_info_iter.as_slice();
+    let purchase_throttle_account =
+        find_trailing_purchase_throttle(program_id, ai_model_account.key, buyer_account.key, trailing_accounts);
+    let verification_pass_account = find_trailing_verification_pass(trailing_accounts);
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *token_program_account.key != TOKEN_2022_PROGRAM_ID
+        || *associated_token_program_account.key != ASSOCIATED_TOKEN_PROGRAM_ID
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let reserve_bump = load_credit_config(
+        program_id,
+        config_account,
+        reserve_mint_account,
+        credit_mint_account,
+        reserve_authority_account,
+    )?;
+    let reserve_mint = reserve_mint_account.key;
+    if find_token_2022_associated_address(reserve_authority_account.key, reserve_mint) != *reserve_token_account.key
+        || find_token_2022_associated_address(seller_account.key, reserve_mint) != *seller_reserve_token_account.key
+        || find_token_2022_associated_address(treasury_account.key, reserve_mint) != *treasury_reserve_token_account.key
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // 卖家从未创建过储备代币账户时不应导致成交失败，由买家出资幂等地创建收款方的关联代币账户
+    for (wallet_account, token_account) in [
+        (seller_account, seller_reserve_token_account),
+        (treasury_account, treasury_reserve_token_account),
+    ] {
+        if token_account.data_is_empty() {
+            create_token_2022_associated_account(
+                buyer_account,
+                token_account,
+                wallet_account,
+                reserve_mint_account,
+                system_program_account,
+                token_program_account,
+            )?;
+        }
+    }
+
+    let credits = CreditPayment {
+        buyer: buyer_account,
+        buyer_credit_token: buyer_credit_token_account,
+        credit_mint: credit_mint_account,
+        reserve_mint: reserve_mint_account,
+        reserve_token: reserve_token_account,
+        reserve_authority: reserve_authority_account,
+        reserve_bump,
+        seller: seller_account,
+        seller_reserve_token: seller_reserve_token_account,
+        treasury: treasury_account,
+        treasury_reserve_token: treasury_reserve_token_account,
+    };
+    settle_sale(
+        program_id,
+        &SaleAccounts {
+            ai_model: ai_model_account,
+            seller: seller_account,
+            receipt: receipt_account,
+            seller_stats: seller_stats_account,
+            system_program: system_program_account,
+            proceeds_vault: proceeds_vault_account,
+            sales_ledger: sales_ledger_account,
+            treasury: treasury_account,
+            seller_bond: seller_bond_account,
+            buyer_stats: buyer_stats_account,
+            buyer_receipts: buyer_receipts_account,
+            previous_receipt: previous_receipt_account,
+            purchase_throttle: purchase_throttle_account,
+            verification_pass: verification_pass_account,
+            charity: None,
+            memo_program: None,
+        },
+        buyer_account.key,
+        &PaymentSource::Credits(&credits),
+        buyer_account,
+        amount,
+        tier_index,
+        quantity,
+        purchase_index,
+        acknowledge_deprecation,
+        SalePrice::Listed,
+        0,
+        [0u8; 32],
+    )?;
+
+    // 额度代币用完后关闭买家的额度代币账户，租金退给买家，再次BuyCredits时会重新创建
+    if close_token_2022_account_if_empty(buyer_credit_token_account, buyer_account, buyer_account, &[])? {
+        msg!("CreditTokenAccountClosed: buyer={}", buyer_account.key);
+    }
+    Ok(())
+}
+
+// 读取钱包的订单nonce账户，不存在时由payer创建
+fn open_order_nonces<'a>(
+    program_id: &Pubkey,
+    wallet: &Pubkey,
+    nonce_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+) -> Result<OrderNonces, ProgramError> {
+    let (nonce_key, nonce_bump) = find_order_nonce_address(wallet, program_id);
+    if nonce_key != *nonce_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if nonce_account.data_is_empty() {
+        create_pda_account(
+            payer,
+            nonce_account,
+            system_program_account,
+            program_id,
+            OrderNonces::LEN,
+            &[ORDER_NONCE_SEED, wallet.as_ref(), &[nonce_bump]],
+        )?;
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut nonces = OrderNonces::unpack_unchecked(&nonce_account.data.borrow())?;
+    if !nonces.is_initialized {
+        nonces.is_initialized = true;
+        nonces.wallet = *wallet;
+        nonces.created_at = now;
+    }
+    nonces.updated_at = now;
+    Ok(nonces)
+}
+
+// 定义一个处理程序函数，钱包批量作废链下签名订单
+pub fn cancel_order_nonces(program_id: &Pubkey, accounts: &[AccountInfo], min_nonce: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let wallet_account = next_account_info(account_info_iter)?;
+    let nonce_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !wallet_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut nonces = open_order_nonces(
+        program_id,
+        wallet_account.key,
+        nonce_account,
+        wallet_account,
+        system_program_account,
+    )?;
+    nonces.cancel_below(min_nonce)?;
+    nonces.pack_into_slice(&mut nonce_account.data.borrow_mut());
+
+    msg!("OrderNoncesCanceled: wallet={} min_nonce={}", wallet_account.key, min_nonce);
+    Ok(())
+}
+
+// 定义一个处理程序函数，按卖家链下签名的订单成交全部或部分席位
+pub fn settle_signed_order(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    order: SignedOrder,
+    fill_quantity: u32,
+    purchase_index: u32,
+    acknowledge_deprecation: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer_account = next_account_info(account_info_iter)?;
+    let nonce_account = next_account_info(account_info_iter)?;
+    let fill_account = next_account_info(account_info_iter)?;
+    let instructions_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let seller_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let seller_stats_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let proceeds_vault_account = next_account_info(account_info_iter)?;
+    let sales_ledger_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let seller_bond_account = next_account_info(account_info_iter)?;
+    let buyer_stats_account = next_account_info(account_info_iter)?;
+    let buyer_receipts_account = next_account_info(account_info_iter)?;
+    let previous_receipt_account = if purchase_index > 0 {
+        Some(next_account_info(account_info_iter)?)
+    } else {
+        None
+    };
+    let trailing_accounts = account_info_iter.as_slice();
+    let purchase_throttle_account =
+        find_trailing_purchase_throttle(program_id, ai_model_account.key, buyer_account.key, trailing_accounts);
+    let verification_pass_account = find_trailing_verification_pass(trailing_accounts);
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *instructions_account.key != sysvar_instructions::id() || *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    order.require_live(now)?;
+    // 签名者必须是卖家本人，settle_sale会再确认卖家就是模型所有者
+    verify_ed25519_signature(instructions_account, seller_account.key, &order.message(program_id, ai_model_account.key))?;
+
+    // 部分成交期间nonce保持未使用，卖家仍可通过CancelOrderNonces作废剩余部分
+    let mut nonces = open_order_nonces(
+        program_id,
+        seller_account.key,
+        nonce_account,
+        buyer_account,
+        system_program_account,
+    )?;
+    if !nonces.is_usable(order.nonce) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (fill_key, fill_bump) = find_order_fill_address(seller_account.key, order.nonce, program_id);
+    if fill_key != *fill_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if fill_account.data_is_empty() {
+        create_pda_account(
+            buyer_account,
+            fill_account,
+            system_program_account,
+            program_id,
+            OrderFill::LEN,
+            &[ORDER_FILL_SEED, seller_account.key.as_ref(), &order.nonce.to_le_bytes(), &[fill_bump]],
+        )?;
+    }
+    let mut fill = OrderFill::unpack_unchecked(&fill_account.data.borrow())?;
+    if !fill.is_initialized {
+        fill.is_initialized = true;
+        fill.seller = *seller_account.key;
+        fill.model = *ai_model_account.key;
+        fill.nonce = order.nonce;
+        fill.quantity = order.quantity;
+        fill.created_at = now;
+    }
+    if fill.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let amount = fill.fill(&order, fill_quantity)?;
+    fill.updated_at = now;
+    fill.pack_into_slice(&mut fill_account.data.borrow_mut());
+    if fill.is_complete() {
+        nonces.consume(order.nonce)?;
+    }
+    nonces.pack_into_slice(&mut nonce_account.data.borrow_mut());
+
+    settle_sale(
+        program_id,
+        &SaleAccounts {
+            ai_model: ai_model_account,
+            seller: seller_account,
+            receipt: receipt_account,
+            seller_stats: seller_stats_account,
+            system_program: system_program_account,
+            proceeds_vault: proceeds_vault_account,
+            sales_ledger: sales_ledger_account,
+            treasury: treasury_account,
+            seller_bond: seller_bond_account,
+            buyer_stats: buyer_stats_account,
+            buyer_receipts: buyer_receipts_account,
+            previous_receipt: previous_receipt_account,
+            purchase_throttle: purchase_throttle_account,
+            verification_pass: verification_pass_account,
+            charity: None,
+            memo_program: None,
+        },
+        buyer_account.key,
+        &PaymentSource::Wallet(buyer_account),
+        buyer_account,
+        amount,
+        order.tier,
+        fill_quantity,
+        purchase_index,
+        acknowledge_deprecation,
+        SalePrice::SellerSigned(amount),
+        0,
+        [0u8; 32],
+    )?;
+
+    msg!(
+        "SignedOrderSettled: model={} seller={} buyer={} nonce={} seats={} filled={}/{} amount={}",
+        ai_model_account.key,
+        seller_account.key,
+        buyer_account.key,
+        order.nonce,
+        fill_quantity,
+        fill.filled,
+        fill.quantity,
+        amount
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，销毁前代模型的许可证并以升级折扣购买后继模型
+pub fn upgrade_license(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    tier_index: u8,
+    quantity: u32,
+    purchase_index: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let buyer_account = next_account_info(account_info_iter)?;
+    let old_receipt_account = next_account_info(account_info_iter)?;
+    let old_model_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let seller_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let seller_stats_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let proceeds_vault_account = next_account_info(account_info_iter)?;
+    let sales_ledger_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let seller_bond_account = next_account_info(account_info_iter)?;
+    let buyer_stats_account = next_account_info(account_info_iter)?;
+    let buyer_receipts_account = next_account_info(account_info_iter)?;
+    let previous_receipt_account = if purchase_index > 0 {
+        Some(next_account_info(account_info_iter)?)
+    } else {
+        None
+    };
+    let trailing_accounts = account_info_iter.as_slice();
+    let purchase_throttle_account =
+        find_trailing_purchase_throttle(program_id, ai_model_account.key, buyer_account.key, trailing_accounts);
+    let verification_pass_account = find_trailing_verification_pass(trailing_accounts);
+
+    if !buyer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if old_receipt_account.owner != program_id || old_model_account.owner != program_id || ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let old_receipt = PurchaseReceipt::unpack(&old_receipt_account.data.borrow())?;
+    if old_receipt.buyer != *buyer_account.key || old_receipt.model != *old_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if unpack_ai_model_boxed(old_model_account)?.successor != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let price = unpack_ai_model_boxed(ai_model_account)?.upgrade_price(tier_index, quantity)?;
+    close_account_to_rent_payer(old_receipt_account, &old_receipt.rent_payer, accounts)?;
+
+    settle_sale(
+        program_id,
+        &SaleAccounts {
+            ai_model: ai_model_account,
+            seller: seller_account,
+            receipt: receipt_account,
+            seller_stats: seller_stats_account,
+            system_program: system_program_account,
+            proceeds_vault: proceeds_vault_account,
+            sales_ledger: sales_ledger_account,
+            treasury: treasury_account,
+            seller_bond: seller_bond_account,
+            buyer_stats: buyer_stats_account,
+            buyer_receipts: buyer_receipts_account,
+            previous_receipt: previous_receipt_account,
+            purchase_throttle: purchase_throttle_account,
+            verification_pass: verification_pass_account,
+            charity: None,
+            memo_program: None,
+        },
+        buyer_account.key,
+        &PaymentSource::Wallet(buyer_account),
+        buyer_account,
+        price,
+        tier_index,
+        quantity,
+        purchase_index,
+        // 后继模型通常不会被弃用；已弃用时买家应改用PurchaseAIModel显式确认
+        false,
+        SalePrice::Upgrade(price),
+        0,
+        [0u8; 32],
+    )?;
+
+    msg!(
+        "LicenseUpgraded: buyer={} from={} to={} burned_receipt={} price={}",
+        buyer_account.key,
+        old_model_account.key,
+        ai_model_account.key,
+        old_receipt_account.key,
+        price
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，持有者按模型的可转让性把许可证转让给接收者
+pub fn transfer_license(program_id: &Pubkey, accounts: &[AccountInfo], price: u64, purchase_index: u32) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let holder_account = next_account_info(account_info_iter)?;
+    let recipient_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let seller_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let new_receipt_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let previous_receipt_account = if purchase_index > 0 {
+        Some(next_account_info(account_info_iter)?)
+    } else {
+        None
+    };
+
+    if !holder_account.is_signer || !recipient_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    load_config(program_id, config_account)?.require_feature(FEATURE_RESALE)?;
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id || receipt_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if ai_model_data.owner != *seller_account.key || holder_account.key == recipient_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let royalty = ai_model_data.transfer_royalty(price)?;
+    let mut treasury = load_treasury(program_id, treasury_account)?;
+    let fee_bps = treasury.secondary_fee_bps;
+    let fee = fee_at_bps(price, fee_bps as u64);
+    // 版税和转售手续费都从成交价中扣除，剩余部分付给持有者
+    let holder_amount = price
+        .checked_sub(royalty)
+        .and_then(|amount| amount.checked_sub(fee))
+        .ok_or(ProgramError::InvalidArgument)?;
+    let mut receipt = PurchaseReceipt::unpack(&receipt_account.data.borrow())?;
+    if receipt.buyer != *holder_account.key || receipt.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // 新凭证同样按purchase_index顺序创建
+    let (new_receipt_key, new_receipt_bump) =
+        find_receipt_address(ai_model_account.key, recipient_account.key, purchase_index, program_id);
+    if new_receipt_key != *new_receipt_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !new_receipt_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    if let Some(previous_receipt_account) = previous_receipt_account {
+        let (previous_key, _) =
+            find_receipt_address(ai_model_account.key, recipient_account.key, purchase_index - 1, program_id);
+        if previous_key != *previous_receipt_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if previous_receipt_account.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        PurchaseReceipt::unpack(&previous_receipt_account.data.borrow())?;
+    }
+
+    if royalty > 0 {
+        invoke(
+            &system_instruction::transfer(recipient_account.key, seller_account.key, royalty),
+            &[recipient_account.clone(), seller_account.clone(), system_program_account.clone()],
+        )?;
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if fee > 0 {
+        invoke(
+            &system_instruction::transfer(recipient_account.key, treasury_account.key, fee),
+            &[recipient_account.clone(), treasury_account.clone(), system_program_account.clone()],
+        )?;
+        treasury.total_fees = treasury.total_fees
+            .checked_add(fee)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        treasury.updated_at = now;
+        treasury.pack_into_slice(&mut treasury_account.data.borrow_mut());
+    }
+    if holder_amount > 0 {
+        invoke(
+            &system_instruction::transfer(recipient_account.key, holder_account.key, holder_amount),
+            &[recipient_account.clone(), holder_account.clone(), system_program_account.clone()],
+        )?;
+    }
+
+    create_pda_account(
+        recipient_account,
+        new_receipt_account,
+        system_program_account,
+        program_id,
+        PurchaseReceipt::LEN,
+        &[
+            RECEIPT_SEED,
+            ai_model_account.key.as_ref(),
+            recipient_account.key.as_ref(),
+            &purchase_index.to_le_bytes(),
+            &[new_receipt_bump],
+        ],
+    )?;
+    // 档位、席位、许可条款和版次随凭证转移，API密钥绑定和对账参考号不转移，手续费记录为本次转售的
+    let previous_rent_payer = receipt.rent_payer;
+    receipt.buyer = *recipient_account.key;
+    receipt.amount_paid = price;
+    receipt.created_at = now;
+    receipt.updated_at = now;
+    receipt.purchase_index = purchase_index;
+    receipt.api_key_hash = [0u8; 32];
+    receipt.api_key_bound_at = 0;
+    receipt.fee_bps = fee_bps;
+    receipt.fee_paid = fee;
+    receipt.reference = [0u8; 32];
+    receipt.rent_payer = *recipient_account.key;
+    receipt.pack_into_slice(&mut new_receipt_account.data.borrow_mut());
+    close_account_to_rent_payer(receipt_account, &previous_rent_payer, accounts)?;
+
+    msg!(
+        "LicenseTransferred: model={} from={} to={} price={} royalty={} fee={} edition={}",
+        ai_model_account.key,
+        holder_account.key,
+        recipient_account.key,
+        price,
+        royalty,
+        fee,
+        receipt.edition
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者吊销成交时档位允许吊销的许可证
+pub fn revoke_license(program_id: &Pubkey, accounts: &[AccountInfo], reason_hash: [u8; 32]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let holder_account = next_account_info(account_info_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if ai_model_account.owner != program_id || receipt_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let owner = AIModelView::new(&ai_model_account.data.borrow())?.owner();
+    require_model_owner(program_id, &owner, owner_account, accounts)?;
+    let receipt = PurchaseReceipt::unpack(&receipt_account.data.borrow())?;
+    if receipt.model != *ai_model_account.key || receipt.buyer != *holder_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    // 以成交时记录的条款为准，之后修改档位不影响已售出的许可证
+    if !receipt.revocable {
+        return Err(ProgramError::InvalidArgument);
+    }
+    close_account_to_rent_payer(receipt_account, &receipt.rent_payer, accounts)?;
+
+    events::LicenseRevoked {
+        receipt: *receipt_account.key,
+        model: receipt.model,
+        holder: receipt.buyer,
+        revoked_by: *owner_account.key,
+        edition: receipt.edition,
+        reason_hash,
+    }
+    .emit();
+    Ok(())
+}
+
+// 定义一个处理程序函数，期限许可证持有者按当前续费价格延长有效期
+pub fn renew_license(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let holder_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let seller_account = next_account_info(account_info_iter)?;
+    let proceeds_vault_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let seller_bond_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !holder_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if ai_model_account.owner != program_id || receipt_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if ai_model_data.owner != *seller_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut receipt = PurchaseReceipt::unpack(&receipt_account.data.borrow())?;
+    if receipt.buyer != *holder_account.key || receipt.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // 续费价格和期限都以档位当前的设置为准，档位改为永久许可后不再续费
+    let clock = Clock::get()?;
+    let price = ai_model_data.list_price(receipt.tier, receipt.seat_count)?;
+    receipt.renew(ai_model_data.tier_term_slots(receipt.tier), clock.slot)?;
+
+    let source = PaymentSource::Wallet(holder_account);
+    let mut treasury = load_treasury(program_id, treasury_account)?;
+    let fee_bps = treasury.primary_fee_rate(seller_bond_staked(program_id, seller_account.key, seller_bond_account)?);
+    let fee = fee_at_bps(price, fee_bps);
+    if fee > 0 {
+        source.pay(treasury_account, system_program_account, fee)?;
+        treasury.total_fees = treasury.total_fees
+            .checked_add(fee)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        treasury.updated_at = clock.unix_timestamp;
+        treasury.pack_into_slice(&mut treasury_account.data.borrow_mut());
+    }
+    let seller_amount = withhold_proceeds(
+        program_id,
+        &source,
+        seller_account,
+        proceeds_vault_account,
+        system_program_account,
+        price - fee,
+    )?;
+    if ai_model_data.vault_proceeds {
+        deposit_proceeds(
+            program_id,
+            holder_account,
+            &source,
+            seller_account,
+            proceeds_vault_account,
+            system_program_account,
+            seller_amount,
+        )?;
+    } else {
+        source.pay(seller_account, system_program_account, seller_amount)?;
+    }
+
+    receipt.updated_at = clock.unix_timestamp;
+    receipt.pack_into_slice(&mut receipt_account.data.borrow_mut());
+
+    msg!(
+        "LicenseRenewed: receipt={} model={} holder={} price={} expires_at_slot={}",
+        receipt_account.key,
+        receipt.model,
+        receipt.buyer,
+        price,
+        receipt.expires_at_slot
+    );
+    Ok(())
+}
+
+// 检查空投活动账户归本程序所有，并由seller创建
+fn load_airdrop_campaign(
+    program_id: &Pubkey,
+    campaign_account: &AccountInfo,
+    seller: &Pubkey,
+) -> Result<AirdropCampaign, ProgramError> {
+    if campaign_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let campaign = AirdropCampaign::unpack(&campaign_account.data.borrow())?;
+    if campaign.seller != *seller {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(campaign)
+}
+
+// 定义一个处理程序函数，模型所有者创建空投活动并预存空投凭证的租金
+pub fn create_airdrop_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    campaign_id: u64,
+    tier_index: u8,
+    max_recipients: u32,
+    merkle_root: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let campaign_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !seller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() || ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if ai_model_data.owner != *seller_account.key || max_recipients == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    ai_model_data.tier_price(tier_index)?;
+
+    let (campaign_key, campaign_bump) = find_airdrop_campaign_address(ai_model_account.key, campaign_id, program_id);
+    if campaign_key != *campaign_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !campaign_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        seller_account,
+        campaign_account,
+        system_program_account,
+        program_id,
+        AirdropCampaign::LEN,
+        &[AIRDROP_CAMPAIGN_SEED, ai_model_account.key.as_ref(), &campaign_id.to_le_bytes(), &[campaign_bump]],
+    )?;
+    let budget = Rent::get()?
+        .minimum_balance(PurchaseReceipt::LEN)
+        .checked_mul(max_recipients as u64)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    invoke(
+        &system_instruction::transfer(seller_account.key, campaign_account.key, budget),
+        &[seller_account.clone(), campaign_account.clone(), system_program_account.clone()],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let campaign = AirdropCampaign {
+        is_initialized: true,
+        model: *ai_model_account.key,
+        seller: *seller_account.key,
+        campaign_id,
+        tier: tier_index,
+        max_recipients,
+        airdropped: 0,
+        created_at: now,
+        updated_at: now,
+        merkle_root,
+    };
+    campaign.pack_into_slice(&mut campaign_account.data.borrow_mut());
+
+    msg!(
+        "AirdropCampaignCreated: campaign={} model={} tier={} max_recipients={} budget={}",
+        campaign_key,
+        ai_model_account.key,
+        tier_index,
+        max_recipients,
+        budget
+    );
+    Ok(())
+}
+
+// 为钱包创建一份空投许可证凭证，租金由活动账户支付；凭证不在买家的购买序列中，purchase_index固定为0
+#[allow(clippy::too_many_arguments)]
+fn issue_airdrop_receipt<'a>(
+    program_id: &Pubkey,
+    campaign_account: &AccountInfo<'a>,
+    ai_model_account: &AccountInfo,
+    ai_model_data: &mut AIModel,
+    tier: u8,
+    wallet: &Pubkey,
+    receipt_account: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    clock: &Clock,
+) -> ProgramResult {
+    let (receipt_key, receipt_bump) = find_airdrop_receipt_address(campaign_account.key, wallet, program_id);
+    if receipt_key != *receipt_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !receipt_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let edition = ai_model_data.mint_edition(clock.slot)?;
+    create_pda_account_funded_by(
+        campaign_account,
+        receipt_account,
+        system_program_account,
+        program_id,
+        PurchaseReceipt::LEN,
+        &[AIRDROP_RECEIPT_SEED, campaign_account.key.as_ref(), wallet.as_ref(), &[receipt_bump]],
+    )?;
+    let receipt = PurchaseReceipt {
+        is_initialized: true,
+        model: *ai_model_account.key,
+        buyer: *wallet,
+        amount_paid: 0,
+        tier,
+        seat_count: 1,
+        created_at: clock.unix_timestamp,
+        updated_at: clock.unix_timestamp,
+        purchase_index: 0,
+        license: ai_model_data.license.clone(),
+        api_key_hash: [0u8; 32],
+        api_key_bound_at: 0,
+        edition,
+        revocable: ai_model_data.license_tiers.get(tier as usize).is_some_and(|tier| tier.revocable),
+        expires_at_slot: match ai_model_data.tier_term_slots(tier) {
+            0 => 0,
+            term_slots => clock.slot
+                .checked_add(term_slots)
+                .ok_or(ProgramError::ArithmeticOverflow)?,
+        },
+        fee_bps: 0,
+        fee_paid: 0,
+        reference: [0u8; 32],
+        // 租金由活动账户垫付，活动资金来自卖家（调用方已核对模型所有者即活动卖家），关闭时退给卖家
+        rent_payer: ai_model_data.owner,
+    };
+    receipt.pack_into_slice(&mut receipt_account.data.borrow_mut());
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者向一批钱包空投免费许可证
+pub fn airdrop_licenses(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller_account = next_account_info(account_info_iter)?;
+    let campaign_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let recipient_pairs = account_info_iter.as_slice().chunks_exact(2);
+
+    if !seller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() || ai_model_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut campaign = load_airdrop_campaign(program_id, campaign_account, seller_account.key)?;
+    let count = recipient_pairs.len();
+    if campaign.model != *ai_model_account.key || count == 0 || !recipient_pairs.remainder().is_empty() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if ai_model_data.owner != *seller_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    campaign.record_airdrops(count as u32)?;
+
+    let clock = Clock::get()?;
+    for pair in recipient_pairs {
+        issue_airdrop_receipt(
+            program_id,
+            campaign_account,
+            ai_model_account,
+            &mut ai_model_data,
+            campaign.tier,
+            pair[0].key,
+            &pair[1],
+            system_program_account,
+            &clock,
+        )?;
+    }
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+    campaign.updated_at = clock.unix_timestamp;
+    campaign.pack_into_slice(&mut campaign_account.data.borrow_mut());
+
+    msg!(
+        "LicensesAirdropped: campaign={} count={} airdropped={}/{}",
+        campaign_account.key,
+        count,
+        campaign.airdropped,
+        campaign.max_recipients
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，空投名单中的钱包凭Merkle证明领取许可证
+pub fn claim_airdrop(program_id: &Pubkey, accounts: &[AccountInfo], proof: Vec<[u8; 32]>) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let claimant_account = next_account_info(account_info_iter)?;
+    let campaign_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let receipt_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !claimant_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id()
+        || ai_model_account.owner != program_id
+        || campaign_account.owner != program_id
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut campaign = AirdropCampaign::unpack(&campaign_account.data.borrow())?;
+    if campaign.model != *ai_model_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !campaign.verify_recipient(campaign_account.key, claimant_account.key, &proof) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    // 模型转手后活动随之失效，与卖家推送时的所有者检查一致
+    let mut ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    if ai_model_data.owner != campaign.seller {
+        return Err(ProgramError::InvalidArgument);
+    }
+    campaign.record_airdrops(1)?;
+
+    let clock = Clock::get()?;
+    issue_airdrop_receipt(
+        program_id,
+        campaign_account,
+        ai_model_account,
+        &mut ai_model_data,
+        campaign.tier,
+        claimant_account.key,
+        receipt_account,
+        system_program_account,
+        &clock,
+    )?;
+    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+    campaign.updated_at = clock.unix_timestamp;
+    campaign.pack_into_slice(&mut campaign_account.data.borrow_mut());
+
+    msg!(
+        "AirdropClaimed: campaign={} wallet={} airdropped={}/{}",
+        campaign_account.key,
+        claimant_account.key,
+        campaign.airdropped,
+        campaign.max_recipients
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，模型所有者关闭空投活动并取回未使用的租金预存
+pub fn close_airdrop_campaign(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let seller_account = next_account_info(account_info_iter)?;
+    let campaign_account = next_account_info(account_info_iter)?;
+
+    if !seller_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let campaign = load_airdrop_campaign(program_id, campaign_account, seller_account.key)?;
+    close_account(campaign_account, seller_account)?;
+
+    msg!("AirdropCampaignClosed: campaign={} airdropped={}", campaign_account.key, campaign.airdropped);
+    Ok(())
+}
+
+// 定义一个处理程序函数，Oracle角色推送价格源的最新价格，价格源不存在时创建
+pub fn update_price_feed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    feed_id: u64,
+    price: u64,
+    confidence: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let oracle_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let price_feed_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, oracle_account, Role::Oracle, accounts)?;
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if price == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (price_feed_key, price_feed_bump) = find_price_feed_address(feed_id, program_id);
+    if price_feed_key != *price_feed_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if price_feed_account.data_is_empty() {
+        create_pda_account(
+            oracle_account,
+            price_feed_account,
+            system_program_account,
+            program_id,
+            PriceFeed::LEN,
+            &[PRICE_FEED_SEED, &feed_id.to_le_bytes(), &[price_feed_bump]],
+        )?;
+    }
+    let clock = Clock::get()?;
+    let mut feed = PriceFeed::unpack_unchecked(&price_feed_account.data.borrow())?;
+    if !feed.is_initialized {
+        feed.is_initialized = true;
+        feed.feed_id = feed_id;
+        feed.created_at = clock.unix_timestamp;
+    }
+    feed.price = price;
+    feed.confidence = confidence;
+    feed.published_slot = clock.slot;
+    feed.updated_at = clock.unix_timestamp;
+    feed.pack_into_slice(&mut price_feed_account.data.borrow_mut());
+
+    msg!("PriceFeedUpdated: feed={} price={} confidence={} slot={}", feed_id, price, confidence, clock.slot);
+    Ok(())
+}
+
+// 构造Wormhole核心桥的PostMessage指令，桥配置、手续费账户和发送者序号账户由发送者地址推导
+fn wormhole_post_message_instruction(
+    payer: &Pubkey,
+    message: &Pubkey,
+    emitter: &Pubkey,
+    nonce: u32,
+    payload: &[u8],
+) -> Instruction {
+    let (bridge, _) = Pubkey::find_program_address(&[b"Bridge"], &WORMHOLE_CORE_BRIDGE_PROGRAM_ID);
+    let (fee_collector, _) = Pubkey::find_program_address(&[b"fee_collector"], &WORMHOLE_CORE_BRIDGE_PROGRAM_ID);
+    let (sequence, _) = Pubkey::find_program_address(&[b"Sequence", emitter.as_ref()], &WORMHOLE_CORE_BRIDGE_PROGRAM_ID);
+    // 布局: u8指令编号(PostMessage为1) + u32 nonce + u32长度前缀的载荷 + u8确认级别
+    let mut data = Vec::with_capacity(1 + 4 + 4 + payload.len() + 1);
+    data.push(1);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    data.extend_from_slice(payload);
+    data.push(WORMHOLE_CONSISTENCY_FINALIZED);
+    Instruction {
+        program_id: WORMHOLE_CORE_BRIDGE_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(bridge, false),
+            AccountMeta::new(*message, true),
+            AccountMeta::new_readonly(*emitter, true),
+            AccountMeta::new(sequence, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(fee_collector, false),
+            AccountMeta::new_readonly(solana_program::sysvar::clock::id(), false),
+            AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+// 定义一个处理程序函数，通过Wormhole发布模型上架或许可证成交的跨链公告；
+// 公告内容完全取自链上账户，因此任何人都可以付费发布
+pub fn announce_listing(program_id: &Pubkey, accounts: &[AccountInfo], kind: u8, nonce: u32) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let emitter_account = next_account_info(account_info_iter)?;
+    let bridge_account = next_account_info(account_info_iter)?;
+    let message_account = next_account_info(account_info_iter)?;
+    let sequence_account = next_account_info(account_info_iter)?;
+    let fee_collector_account = next_account_info(account_info_iter)?;
+    let clock_sysvar_account = next_account_info(account_info_iter)?;
+    let rent_sysvar_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let wormhole_program_account = next_account_info(account_info_iter)?;
+
+    if !payer_account.is_signer || !message_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    load_config(program_id, config_account)?.require_feature(FEATURE_CROSS_CHAIN_ANNOUNCEMENTS)?;
+    if *wormhole_program_account.key != WORMHOLE_CORE_BRIDGE_PROGRAM_ID
+        || *bridge_account.owner != WORMHOLE_CORE_BRIDGE_PROGRAM_ID
+        || *system_program_account.key != system_program::id()
+        || ai_model_account.owner != program_id
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (emitter_key, emitter_bump) = find_wormhole_emitter_address(program_id);
+    if emitter_key != *emitter_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let ai_model_data = unpack_ai_model_boxed(ai_model_account)?;
+    let mut announcement = ListingAnnouncement {
+        kind: ListingAnnouncementKind::from_u8(kind)?,
+        model: *ai_model_account.key,
+        owner: ai_model_data.owner,
+        artifact_hash: ai_model_data.artifact_hash,
+        price: ai_model_data.price,
+        editions_minted: ai_model_data.editions_minted,
+        total_sales: ai_model_data.total_sales,
+        receipt: Pubkey::default(),
+        buyer: Pubkey::default(),
+        edition: 0,
+        amount_paid: 0,
+    };
+    if announcement.kind == ListingAnnouncementKind::Sold {
+        let receipt_account = next_account_info(account_info_iter)?;
+        if receipt_account.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let receipt = PurchaseReceipt::unpack(&receipt_account.data.borrow())?;
+        if receipt.model != *ai_model_account.key {
+            return Err(ProgramError::InvalidArgument);
+        }
+        announcement.receipt = *receipt_account.key;
+        announcement.buyer = receipt.buyer;
+        announcement.edition = receipt.edition;
+        announcement.amount_paid = receipt.amount_paid;
+    }
+
+    let instruction = wormhole_post_message_instruction(
+        payer_account.key,
+        message_account.key,
+        &emitter_key,
+        nonce,
+        &announcement.to_payload(),
+    );
+    if instruction.accounts[0].pubkey != *bridge_account.key
+        || instruction.accounts[3].pubkey != *sequence_account.key
+        || instruction.accounts[5].pubkey != *fee_collector_account.key
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // 桥配置中记录的消息手续费需要在发布前转入手续费账户
+    let fee = bridge_account
+        .data
+        .borrow()
+        .get(WORMHOLE_BRIDGE_FEE_OFFSET..WORMHOLE_BRIDGE_FEE_OFFSET + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidAccountData)?;
+    if fee > 0 {
+        invoke(
+            &system_instruction::transfer(payer_account.key, fee_collector_account.key, fee),
+            &[payer_account.clone(), fee_collector_account.clone(), system_program_account.clone()],
+        )?;
+    }
+    invoke_signed(
+        &instruction,
+        &[
+            bridge_account.clone(),
+            message_account.clone(),
+            emitter_account.clone(),
+            sequence_account.clone(),
+            payer_account.clone(),
+            fee_collector_account.clone(),
+            clock_sysvar_account.clone(),
+            rent_sysvar_account.clone(),
+            system_program_account.clone(),
+        ],
+        &[&[WORMHOLE_EMITTER_SEED, &[emitter_bump]]],
+    )?;
+
+    msg!(
+        "ListingAnnounced: model={} kind={:?} message={} fee={}",
+        ai_model_account.key,
+        announcement.kind,
+        message_account.key,
+        fee
+    );
+    Ok(())
+}
+
+// 校验Civic网关令牌属于wallet、由network签发、处于有效状态且未过期，布局为
+// u8版本 + Option<Pubkey>父令牌 + Pubkey持有钱包 + Option<Pubkey>身份 + Pubkey网关网络 + Pubkey签发者 + u8状态 + Option<i64>过期时间
+fn verify_gateway_token(token_account: &AccountInfo, wallet: &Pubkey, network: &Pubkey, now: i64) -> ProgramResult {
+    if *token_account.owner != CIVIC_GATEWAY_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_gateway_token(&token_account.data.borrow(), wallet, network, now)
+}
+
+fn check_gateway_token(data: &[u8], wallet: &Pubkey, network: &Pubkey, now: i64) -> ProgramResult {
+    const STATE_ACTIVE: u8 = 0;
+    fn skip_option(data: &[u8], len: usize) -> Result<&[u8], ProgramError> {
+        match data.first() {
+            Some(0) => Ok(&data[1..]),
+            Some(1) if data.len() > len => Ok(&data[1 + len..]),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+    let rest = data.get(1..).ok_or(ProgramError::InvalidAccountData)?;
+    let rest = skip_option(rest, 32)?;
+    let (owner_wallet, rest) = unpack_pubkey(rest).map_err(|_| ProgramError::InvalidAccountData)?;
+    let rest = skip_option(rest, 32)?;
+    let (gatekeeper_network, rest) = unpack_pubkey(rest).map_err(|_| ProgramError::InvalidAccountData)?;
+    let (_, rest) = unpack_pubkey(rest).map_err(|_| ProgramError::InvalidAccountData)?;
+    let (state, rest) = unpack_u8(rest).map_err(|_| ProgramError::InvalidAccountData)?;
+    let expire_time = match rest.first() {
+        Some(0) => None,
+        Some(1) => Some(unpack_i64(&rest[1..]).map_err(|_| ProgramError::InvalidAccountData)?.0),
+        _ => return Err(ProgramError::InvalidAccountData),
+    };
+    if owner_wallet != *wallet || gatekeeper_network != *network {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if state != STATE_ACTIVE || expire_time.is_some_and(|expire_time| now >= expire_time) {
+        msg!("Verification pass is not active");
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+// 从可升级程序的程序数据账户中读取升级权限，布局为
+// u32枚举标签(3) + u64部署slot + Option<Pubkey>
+fn upgrade_authority_from_program_data(data: &[u8]) -> Result<Option<Pubkey>, ProgramError> {
+    if data.len() < 4 + 8 + 1 + 32 || u32::from_le_bytes(data[0..4].try_into().unwrap()) != 3 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    match data[12] {
+        0 => Ok(None),
+        1 => Ok(Some(Pubkey::new_from_array(data[13..45].try_into().unwrap()))),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+// 定义一个处理程序函数来创建市场配置，管理员必须是程序的升级权限账户
+pub fn initialize_config(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let program_data_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !admin_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // 校验程序数据账户并确认签名者是升级权限账户，防止任何人抢先初始化
+    let (program_data_key, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if program_data_key != *program_data_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let upgrade_authority = upgrade_authority_from_program_data(&program_data_account.data.borrow())?;
+    if upgrade_authority != Some(*admin_account.key) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (config_key, config_bump) = find_config_address(program_id);
+    if config_key != *config_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !config_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        admin_account,
+        config_account,
+        system_program_account,
+        program_id,
+        MarketplaceConfig::LEN,
+        &[CONFIG_SEED, &[config_bump]],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut config = MarketplaceConfig {
+        is_initialized: true,
+        admin: *admin_account.key,
+        created_at: now,
+        updated_at: now,
+        ..MarketplaceConfig::default()
+    };
+    config.sync_version();
+    config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+    msg!("Marketplace config initialized, admin {}", admin_account.key);
+    Ok(())
+}
+
+// 读取并校验市场配置PDA
+pub fn load_config(program_id: &Pubkey, config_account: &AccountInfo) -> Result<MarketplaceConfig, ProgramError> {
+    if config_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if find_config_address(program_id).0 != *config_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    MarketplaceConfig::unpack(&config_account.data.borrow())
+}
+
+// 按配置中的价格范围检查上架价格；配置尚未初始化的部署不限制价格，但账户必须是配置PDA
+fn check_listing_prices(program_id: &Pubkey, config_account: &AccountInfo, prices: &[u64]) -> ProgramResult {
+    if config_account.data_is_empty() {
+        if find_config_address(program_id).0 != *config_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        return Ok(());
+    }
+    let config = load_config(program_id, config_account)?;
+    prices.iter().try_for_each(|price| config.check_listing_price(*price))
+}
+
+// 校验签名者持有role角色：配置中的管理员始终通过，
+// 其他签名者需要在accounts中传入自己的该角色或超级管理员角色的PDA
+fn require_role(
+    program_id: &Pubkey,
+    config: &MarketplaceConfig,
+    role: Role,
+    signer_account: &AccountInfo,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if !signer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if config.admin == *signer_account.key {
+        return Ok(());
+    }
+    for held in [role, Role::SuperAdmin] {
+        let (role_key, _) = find_role_address(held, signer_account.key, program_id);
+        let Some(role_account) = accounts.iter().find(|account| *account.key == role_key) else {
+            continue;
+        };
+        if role_account.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let assignment = RoleAssignment::unpack(&role_account.data.borrow())?;
+        if assignment.holder == *signer_account.key && assignment.role.grants(role) {
+            return Ok(());
+        }
+    }
+    Err(ProgramError::InvalidArgument)
+}
+
+// 读取市场配置并确认签名者持有role角色
+fn load_config_with_role(
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+    signer_account: &AccountInfo,
+    role: Role,
+    accounts: &[AccountInfo],
+) -> Result<MarketplaceConfig, ProgramError> {
+    let config = load_config(program_id, config_account)?;
+    require_role(program_id, &config, role, signer_account, accounts)?;
+    Ok(config)
+}
+
+// 定义一个处理程序函数，管理员设置精选列表，列表内容整体替换
+pub fn set_featured_list(program_id: &Pubkey, accounts: &[AccountInfo], list_id: u32, label: String) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let featured_list_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let mut listing_accounts = account_info_iter.as_slice();
+
+    load_config_with_role(program_id, config_account, admin_account, Role::Moderator, accounts)?;
+    // 签名者不是配置管理员时，第一个剩余账户是它的角色PDA而不是AIModel
+    if let Some((first, rest)) = listing_accounts.split_first() {
+        if [Role::Moderator, Role::SuperAdmin]
+            .iter()
+            .any(|role| find_role_address(*role, admin_account.key, program_id).0 == *first.key)
+        {
+            listing_accounts = rest;
+        }
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if label.len() > MAX_FEATURED_LABEL_LEN || listing_accounts.len() > MAX_FEATURED_LISTINGS {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // 每个条目都必须是本程序未弃用的AIModel，且不能重复
+    let mut listings: Vec<Pubkey> = Vec::with_capacity(listing_accounts.len());
+    for listing_account in listing_accounts {
+        if listing_account.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if listings.contains(listing_account.key) || AIModelView::new(&listing_account.data.borrow())?.is_deprecated() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        listings.push(*listing_account.key);
+    }
+
+    let (featured_list_key, featured_list_bump) = find_featured_list_address(list_id, program_id);
+    if featured_list_key != *featured_list_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if featured_list_account.data_is_empty() {
+        create_pda_account(
+            admin_account,
+            featured_list_account,
+            system_program_account,
+            program_id,
+            FeaturedList::LEN,
+            &[FEATURED_LIST_SEED, &list_id.to_le_bytes(), &[featured_list_bump]],
+        )?;
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut featured_list = FeaturedList::unpack_unchecked(&featured_list_account.data.borrow())?;
+    if !featured_list.is_initialized {
+        featured_list.is_initialized = true;
+        featured_list.list_id = list_id;
+        featured_list.created_at = now;
+    }
+    featured_list.label = label;
+    featured_list.listings = listings;
+    featured_list.updated_at = now;
+    featured_list.pack_into_slice(&mut featured_list_account.data.borrow_mut());
+
+    msg!(
+        "FeaturedListSet: list={} label={} listings={}",
+        list_id,
+        featured_list.label,
+        featured_list.listings.len()
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员注册分类
+pub fn register_category(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    id: u16,
+    parent: u16,
+    label: String,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let registry_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, admin_account, Role::Moderator, accounts)?;
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (registry_key, registry_bump) = find_category_registry_address(program_id);
+    if registry_key != *registry_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if registry_account.data_is_empty() {
+        create_pda_account(
+            admin_account,
+            registry_account,
+            system_program_account,
+            program_id,
+            CategoryRegistry::LEN,
+            &[CATEGORY_REGISTRY_SEED, &[registry_bump]],
+        )?;
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut registry = CategoryRegistry::unpack_unchecked(&registry_account.data.borrow())?;
+    if !registry.is_initialized {
+        registry.is_initialized = true;
+        registry.created_at = now;
+    }
+    registry.register(Category { id, parent, label })?;
+    registry.updated_at = now;
+    registry.pack_into_slice(&mut registry_account.data.borrow_mut());
+
+    msg!("CategoryRegistered: id={} parent={}", id, parent);
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员注册或更新溯源证明验证器
+pub fn register_provenance_verifier(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    verifier_id: u32,
+    proof_system: u8,
+    claim: String,
+    verifying_key: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let verifier_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let proof_system = ProofSystem::from_u8(proof_system)?;
+    proof_system.public_input_count(&verifying_key)?;
+    if claim.is_empty() || claim.len() > MAX_PROVENANCE_CLAIM_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (verifier_key, verifier_bump) = find_provenance_verifier_address(verifier_id, program_id);
+    if verifier_key != *verifier_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if verifier_account.data_is_empty() {
+        create_pda_account(
+            admin_account,
+            verifier_account,
+            system_program_account,
+            program_id,
+            ProvenanceVerifier::LEN,
+            &[PROVENANCE_VERIFIER_SEED, &verifier_id.to_le_bytes(), &[verifier_bump]],
+        )?;
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut verifier = ProvenanceVerifier::unpack_unchecked(&verifier_account.data.borrow())?;
+    if !verifier.is_initialized {
+        verifier.is_initialized = true;
+        verifier.verifier_id = verifier_id;
+        verifier.created_at = now;
+    }
+    verifier.proof_system = proof_system;
+    verifier.claim = claim;
+    verifier.verifying_key = verifying_key;
+    verifier.updated_at = now;
+    verifier.pack_into_slice(&mut verifier_account.data.borrow_mut());
+
+    msg!("ProvenanceVerifierRegistered: id={} claim={}", verifier_id, verifier.claim);
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员设置功能开关位
+pub fn set_feature_flags(program_id: &Pubkey, accounts: &[AccountInfo], feature_flags: u64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    let mut config = load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    config.feature_flags = feature_flags;
+    config.sync_version();
+    config.updated_at = Clock::get()?.unix_timestamp;
+    config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+    msg!("Feature flags set to {:#x}", feature_flags);
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员设置紧急资金恢复的守护者多签
+pub fn set_recovery_guardians(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    guardians: Vec<Pubkey>,
+    threshold: u8,
+    recovery_delay: i64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    let mut config = load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    if guardians.len() > MAX_GUARDIANS || threshold == 0 || threshold as usize > guardians.len() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if guardians.iter().enumerate().any(|(i, guardian)| guardians[..i].contains(guardian)) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if recovery_delay < MIN_RECOVERY_DELAY_SECONDS {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    config.guardians = guardians;
+    config.guardian_threshold = threshold;
+    config.recovery_delay = recovery_delay;
+    config.sync_version();
+    config.updated_at = Clock::get()?.unix_timestamp;
+    config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+    msg!(
+        "Recovery guardians set: {} of {}, delay {}s",
+        threshold,
+        config.guardians.len(),
+        recovery_delay
+    );
+    Ok(())
+}
+
+// 计算托管账户中无法被任何指令提取的lamports，即超出免租金额和账面余额的部分，
+// 资金恢复只能动用这部分资金，不会影响用户的账面余额；托管账户按类型标识区分，
+// 账面余额为该账户仍欠用户、之后会由对应指令转出的lamports
+fn stuck_escrow_lamports(program_id: &Pubkey, escrow_account: &AccountInfo) -> Result<u64, ProgramError> {
+    if escrow_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let data = escrow_account.data.borrow();
+    let tracked_balance = match read_discriminator(&data)? {
+        ProceedsVault::DISCRIMINATOR => {
+            let vault = ProceedsVault::unpack(&data)?;
+            vault.balance
+                .checked_add(vault.withheld_balance)
+                .ok_or(ProgramError::ArithmeticOverflow)?
+        }
+        // 出价的托管账户在成交前托管出价金额，成交后托管未用完的部分；挂单和出价本身只占用租金
+        Escrow::DISCRIMINATOR => Escrow::unpack(&data)?.escrowed_amount(),
+        Listing::DISCRIMINATOR | Offer::DISCRIMINATOR => 0,
+        // 结算或罚没前托管max_price，之后只剩租金
+        InferenceRequest::DISCRIMINATOR => {
+            let request = InferenceRequest::unpack(&data)?;
+            match request.status {
+                InferenceRequestStatus::Open | InferenceRequestStatus::Submitted | InferenceRequestStatus::Challenged => {
+                    request.max_price
+                }
+                InferenceRequestStatus::Fulfilled | InferenceRequestStatus::Slashed => 0,
+            }
+        }
+        RentalStream::DISCRIMINATOR => {
+            let stream = RentalStream::unpack(&data)?;
+            stream.deposit.saturating_sub(stream.withdrawn)
+        }
+        Commission::DISCRIMINATOR => Commission::unpack(&data)?.escrowed_amount()?,
+        // SOL额度存放在额度PDA中；代币额度由Token-2022账户托管，PDA只占用租金
+        CreditAccount::DISCRIMINATOR => {
+            let credits = CreditAccount::unpack(&data)?;
+            if credits.mint == Pubkey::default() {
+                credits.balance
+            } else {
+                0
+            }
+        }
+        _ => return Err(ProgramError::InvalidAccountData),
+    };
+    let reserved = Rent::get()?
+        .minimum_balance(escrow_account.data_len())
+        .checked_add(tracked_balance)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(escrow_account.lamports().saturating_sub(reserved))
+}
+
+// 定义一个处理程序函数，守护者多签提议恢复被卡住的托管资金
+pub fn propose_recovery(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    destination: Pubkey,
+    evidence_hash: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_account = next_account_info(account_info_iter)?;
+    let recovery_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    if !payer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let config = load_config(program_id, config_account)?;
+    let approvals = config.count_guardian_approvals(accounts);
+    if config.guardian_threshold == 0 || approvals < config.guardian_threshold {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if amount == 0 || amount > stuck_escrow_lamports(program_id, escrow_account)? {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    let (recovery_key, recovery_bump) = find_recovery_address(escrow_account.key, program_id);
+    if recovery_key != *recovery_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !recovery_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        payer_account,
+        recovery_account,
+        system_program_account,
+        program_id,
+        RecoveryRequest::LEN,
+        &[RECOVERY_SEED, escrow_account.key.as_ref(), &[recovery_bump]],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let request = RecoveryRequest {
+        is_initialized: true,
+        escrow: *escrow_account.key,
+        destination,
+        amount,
+        evidence_hash,
+        payer: *payer_account.key,
+        approvals,
+        executable_at: now
+            .checked_add(config.recovery_delay)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+        created_at: now,
+        updated_at: now,
+    };
+    request.pack_into_slice(&mut recovery_account.data.borrow_mut());
+
+    msg!(
+        "RecoveryProposed: escrow={} destination={} amount={} evidence={} approvals={} executable_at={}",
+        request.escrow,
+        request.destination,
+        request.amount,
+        Hash::new_from_array(request.evidence_hash),
+        request.approvals,
+        request.executable_at
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，在时间锁到期后执行资金恢复
+pub fn execute_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let recovery_account = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let destination_account = next_account_info(account_info_iter)?;
+    let payer_account = next_account_info(account_info_iter)?;
+
+    if recovery_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let request = RecoveryRequest::unpack(&recovery_account.data.borrow())?;
+    if request.escrow != *escrow_account.key
+        || request.destination != *destination_account.key
+        || request.payer != *payer_account.key
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if now < request.executable_at {
+        return Err(ProgramError::InvalidArgument);
+    }
+    // 重新计算可恢复金额，提案之后托管账户的状态可能已经变化
+    if request.amount > stuck_escrow_lamports(program_id, escrow_account)? {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    **escrow_account.lamports.borrow_mut() -= request.amount;
+    **destination_account.lamports.borrow_mut() += request.amount;
+    close_account(recovery_account, payer_account)?;
+
+    msg!(
+        "RecoveryExecuted: escrow={} destination={} amount={} evidence={} approvals={} proposed_at={} executed_at={}",
+        request.escrow,
+        request.destination,
+        request.amount,
+        Hash::new_from_array(request.evidence_hash),
+        request.approvals,
+        request.created_at,
+        now
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，守护者取消资金恢复提案
+pub fn cancel_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let guardian_account = next_account_info(account_info_iter)?;
+    let recovery_account = next_account_info(account_info_iter)?;
+    let payer_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+
+    let config = load_config(program_id, config_account)?;
+    if config.count_guardian_approvals(std::slice::from_ref(guardian_account)) == 0 {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if recovery_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let request = RecoveryRequest::unpack(&recovery_account.data.borrow())?;
+    if request.payer != *payer_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    close_account(recovery_account, payer_account)?;
+
+    msg!(
+        "RecoveryCanceled: escrow={} amount={} canceled_by={}",
+        request.escrow,
+        request.amount,
+        guardian_account.key
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员创建市场金库
+pub fn initialize_treasury(program_id: &Pubkey, accounts: &[AccountInfo], withdrawal_delay: i64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, admin_account, Role::Treasurer, accounts)?;
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if withdrawal_delay < MIN_WITHDRAWAL_DELAY_SECONDS {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (treasury_key, treasury_bump) = find_treasury_address(program_id);
+    if treasury_key != *treasury_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !treasury_account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        admin_account,
+        treasury_account,
+        system_program_account,
+        program_id,
+        Treasury::LEN,
+        &[TREASURY_SEED, &[treasury_bump]],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let treasury = Treasury {
+        is_initialized: true,
+        withdrawal_delay,
+        created_at: now,
+        updated_at: now,
+        ..Treasury::default()
+    };
+    treasury.pack_into_slice(&mut treasury_account.data.borrow_mut());
+
+    msg!("Treasury initialized, withdrawal delay {}s", withdrawal_delay);
+    Ok(())
+}
+
+// 读取并校验市场金库PDA
+fn load_treasury(program_id: &Pubkey, treasury_account: &AccountInfo) -> Result<Treasury, ProgramError> {
+    if treasury_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if find_treasury_address(program_id).0 != *treasury_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Treasury::unpack(&treasury_account.data.borrow())
+}
+
+// 金库中扣除免租金额后可以提取的lamports
+fn treasury_available_lamports(treasury_account: &AccountInfo) -> Result<u64, ProgramError> {
+    let rent_exempt = Rent::get()?.minimum_balance(treasury_account.data_len());
+    Ok(treasury_account.lamports().saturating_sub(rent_exempt))
+}
+
+// 定义一个处理程序函数，管理员发起金库提款，给观察者留出反应时间
+pub fn queue_withdrawal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    destination: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, admin_account, Role::Treasurer, accounts)?;
+    let mut treasury = load_treasury(program_id, treasury_account)?;
+    // 同一时间只允许一笔排队中的提款，避免用新提款覆盖已公示的提款
+    if treasury.pending_amount != 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if amount == 0 || amount > treasury_available_lamports(treasury_account)? {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    treasury.pending_amount = amount;
+    treasury.pending_destination = destination;
+    treasury.pending_executable_at = now
+        .checked_add(treasury.withdrawal_delay)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    treasury.updated_at = now;
+    treasury.pack_into_slice(&mut treasury_account.data.borrow_mut());
+
+    msg!(
+        "WithdrawalQueued: amount={} destination={} executable_at={}",
+        amount,
+        destination,
+        treasury.pending_executable_at
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员或Treasurer设置忠诚度积分的累计比例和兑换价格
+pub fn set_loyalty_rates(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    accrual_bps: u16,
+    redemption_rate: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, admin_account, Role::Treasurer, accounts)?;
+    if accrual_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut treasury = load_treasury(program_id, treasury_account)?;
+    treasury.loyalty_accrual_bps = accrual_bps;
+    treasury.loyalty_redemption_rate = redemption_rate;
+    treasury.updated_at = Clock::get()?.unix_timestamp;
+    treasury.pack_into_slice(&mut treasury_account.data.borrow_mut());
+
+    msg!("LoyaltyRatesSet: accrual_bps={} redemption_rate={}", accrual_bps, redemption_rate);
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员登记慈善机构或修改其启用状态，首次登记时创建慈善机构PDA
+pub fn register_charity(program_id: &Pubkey, accounts: &[AccountInfo], wallet: Pubkey, active: bool) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let charity_account = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, admin_account, Role::SuperAdmin, accounts)?;
+    if *system_program_account.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (charity_key, charity_bump) = find_charity_address(&wallet, program_id);
+    if charity_key != *charity_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if charity_account.data_is_empty() {
+        create_pda_account(
+            admin_account,
+            charity_account,
+            system_program_account,
+            program_id,
+            Charity::LEN,
+            &[CHARITY_SEED, wallet.as_ref(), &[charity_bump]],
+        )?;
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut charity = Charity::unpack_unchecked(&charity_account.data.borrow())?;
+    if !charity.is_initialized {
+        charity.is_initialized = true;
+        charity.wallet = wallet;
+        charity.created_at = now;
+    }
+    charity.active = active;
+    charity.updated_at = now;
+    charity.pack_into_slice(&mut charity_account.data.borrow_mut());
+
+    msg!("CharityRegistered: charity={} wallet={} active={}", charity_account.key, wallet, active);
+    Ok(())
+}
+
+// 定义一个处理程序函数，管理员或Treasurer设置首次销售和转售的手续费率
+pub fn set_fee_schedule(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    primary_fee_bps: u16,
+    secondary_fee_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+
+    load_config_with_role(program_id, config_account, admin_account, Role::Treasurer, accounts)?;
+    if primary_fee_bps as u64 > BPS_DENOMINATOR || secondary_fee_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut treasury = load_treasury(program_id, treasury_account)?;
+    treasury.primary_fee_set = true;
+    treasury.primary_fee_bps = primary_fee_bps;
+    treasury.secondary_fee_bps = secondary_fee_bps;
+    treasury.updated_at = Clock::get()?.unix_timestamp;
+    treasury.pack_into_slice(&mut treasury_account.data.borrow_mut());
+
+    msg!("FeeScheduleSet: primary_fee_bps={} secondary_fee_bps={}", primary_fee_bps, secondary_fee_bps);
+    Ok(())
+}
+
+// 定义一个处理程序函数，在时间锁到期后执行排队中的金库提款
+pub fn execute_withdrawal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let treasury_account = next_account_info(account_info_iter)?;
+    let destination_account = next_account_info(account_info_iter)?;
+
+    let mut treasury = load_treasury(program_id, treasury_account)?;
+    if treasury.pending_amount == 0 || treasury.pending_destination != *destination_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if now < treasury.pending_executable_at {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let amount = treasury.pending_amount;
+    if amount > treasury_available_lamports(treasury_account)? {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    treasury.pending_amount = 0;
+    treasury.pending_destination = Pubkey::default();
+    treasury.pending_executable_at = 0;
+    treasury.total_withdrawn = treasury.total_withdrawn
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    treasury.updated_at = now;
+    treasury.pack_into_slice(&mut treasury_account.data.borrow_mut());
+
+    // 金库由本程序拥有，可以直接扣减其lamports
+    **treasury_account.lamports.borrow_mut() -= amount;
+    **destination_account.lamports.borrow_mut() += amount;
+
+    msg!("WithdrawalExecuted: amount={} destination={}", amount, destination_account.key);
+    Ok(())
+}
+
+// 定义一个处理程序函数，Treasurer或守护者在时间锁到期前否决排队中的金库提款
+pub fn cancel_withdrawal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let canceler_account = next_account_info(account_info_iter)?;
+    let config_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+
+    let config = load_config(program_id, config_account)?;
+    // 守护者不需要角色PDA也能否决提款，其他签名者需要Treasurer或超级管理员角色
+    if config.count_guardian_approvals(std::slice::from_ref(canceler_account)) == 0 {
+        require_role(program_id, &config, Role::Treasurer, canceler_account, accounts)?;
+    }
+    let mut treasury = load_treasury(program_id, treasury_account)?;
+    if treasury.pending_amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let amount = treasury.pending_amount;
+    let destination = treasury.pending_destination;
+    treasury.pending_amount = 0;
+    treasury.pending_destination = Pubkey::default();
+    treasury.pending_executable_at = 0;
+    treasury.updated_at = Clock::get()?.unix_timestamp;
+    treasury.pack_into_slice(&mut treasury_account.data.borrow_mut());
+
+    msg!(
+        "WithdrawalCanceled: amount={} destination={} canceled_by={}",
+        amount,
+        destination,
+        canceler_account.key
+    );
+    Ok(())
+}
+
+// 定义一个处理程序函数来输出程序版本和功能位，返回数据为u32版本号 + u64功能位
+pub fn get_version() -> ProgramResult {
+    msg!("Program version: {}, capabilities: {:#x}", PROGRAM_VERSION, PROGRAM_CAPABILITIES);
+    let mut data = Vec::with_capacity(4 + 8);
+    data.extend_from_slice(&PROGRAM_VERSION.to_le_bytes());
+    data.extend_from_slice(&PROGRAM_CAPABILITIES.to_le_bytes());
+    set_return_data(&data);
+    Ok(())
+}
+
+// 定义一个处理程序函数来校验钱包是否持有AIModel的许可证
+pub fn verify_license(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let receipt_account = next_account_info(account_info_iter)?;
+    let ai_model_account = next_account_info(account_info_iter)?;
+    let wallet_account = next_account_info(account_info_iter)?;
+
+    if receipt_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let receipt = PurchaseReceipt::unpack(&receipt_account.data.borrow())?;
+    if receipt.model != *ai_model_account.key || receipt.buyer != *wallet_account.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if receipt.is_expired(Clock::get()?.slot) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    msg!("License verified: {} holds tier {} of {}", wallet_account.key, receipt.tier, ai_model_account.key);
+    Ok(())
+}
+
+// 指令处理函数，根据指令类型分发到对应的处理程序
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = MarketplaceInstruction::unpack(instruction_data)?;
+    if cfg!(feature = "cpi-guard") && instruction.requires_top_level() {
+        assert_top_level_invocation()?;
+    }
+    match instruction {
+        MarketplaceInstruction::CreateAIModel {
+            name,
+            description,
+            price,
+            pricing_mode,
+            license_tiers,
+            per_seat,
+            volume_discounts,
+            vault_proceeds,
+            model_file,
+            model_format,
+            license,
+        } => {
+            msg!("Instruction: CreateAIModel");
+            create_ai_model(
+                program_id,
+                accounts,
+                name,
+                description,
+                price,
+                pricing_mode,
+                license_tiers,
+                per_seat,
+                volume_discounts,
+                vault_proceeds,
+                model_file,
+                model_format,
+                license,
+            )
+        }
+        MarketplaceInstruction::PurchaseAIModel {
+            amount,
+            tier_index,
+            quantity,
+            purchase_index,
+            acknowledge_deprecation,
+            reference,
+        } => {
+            msg!("Instruction: PurchaseAIModel");
+            purchase_ai_model(
+                program_id,
+                accounts,
+                amount,
+                tier_index,
+                quantity,
+                purchase_index,
+                acknowledge_deprecation,
+                0,
+                reference,
+            )
+        }
+        MarketplaceInstruction::VerifyLicense => {
+            msg!("Instruction: VerifyLicense");
+            verify_license(program_id, accounts)
+        }
+        MarketplaceInstruction::WithdrawProceeds { amount } => {
+            msg!("Instruction: WithdrawProceeds");
+            withdraw_proceeds(program_id, accounts, amount)
+        }
+        MarketplaceInstruction::InitializeConfig => {
+            msg!("Instruction: InitializeConfig");
+            initialize_config(program_id, accounts)
+        }
+        MarketplaceInstruction::GetVersion => {
+            msg!("Instruction: GetVersion");
+            get_version()
+        }
+        MarketplaceInstruction::SetFeatureFlags { feature_flags } => {
+            msg!("Instruction: SetFeatureFlags");
+            set_feature_flags(program_id, accounts, feature_flags)
+        }
+        MarketplaceInstruction::SetRecoveryGuardians {
+            guardians,
+            threshold,
+            recovery_delay,
+        } => {
+            msg!("Instruction: SetRecoveryGuardians");
+            set_recovery_guardians(program_id, accounts, guardians, threshold, recovery_delay)
+        }
+        MarketplaceInstruction::ProposeRecovery {
+            amount,
+            destination,
+            evidence_hash,
+        } => {
+            msg!("Instruction: ProposeRecovery");
+            propose_recovery(program_id, accounts, amount, destination, evidence_hash)
+        }
+        MarketplaceInstruction::ExecuteRecovery => {
+            msg!("Instruction: ExecuteRecovery");
+            execute_recovery(program_id, accounts)
+        }
+        MarketplaceInstruction::CancelRecovery => {
+            msg!("Instruction: CancelRecovery");
+            cancel_recovery(program_id, accounts)
+        }
+        MarketplaceInstruction::InitializeTreasury { withdrawal_delay } => {
+            msg!("Instruction: InitializeTreasury");
+            initialize_treasury(program_id, accounts, withdrawal_delay)
+        }
+        MarketplaceInstruction::QueueWithdrawal { amount, destination } => {
+            msg!("Instruction: QueueWithdrawal");
+            queue_withdrawal(program_id, accounts, amount, destination)
+        }
+        MarketplaceInstruction::ExecuteWithdrawal => {
+            msg!("Instruction: ExecuteWithdrawal");
+            execute_withdrawal(program_id, accounts)
+        }
+        MarketplaceInstruction::CancelWithdrawal => {
+            msg!("Instruction: CancelWithdrawal");
+            cancel_withdrawal(program_id, accounts)
+        }
+        MarketplaceInstruction::DepositBond { amount } => {
+            msg!("Instruction: DepositBond");
+            deposit_bond(program_id, accounts, amount)
+        }
+        MarketplaceInstruction::WithdrawBond { amount } => {
+            msg!("Instruction: WithdrawBond");
+            withdraw_bond(program_id, accounts, amount)
+        }
+        MarketplaceInstruction::Sell {
+            price,
+            tier_index,
+            quantity,
+            draft,
+        } => {
+            msg!("Instruction: Sell");
+            sell(program_id, accounts, price, tier_index, quantity, draft)
+        }
+        MarketplaceInstruction::Buy {
+            price,
+            tier_index,
+            quantity,
+        } => {
+            msg!("Instruction: Buy");
+            buy(program_id, accounts, price, tier_index, quantity)
+        }
+        MarketplaceInstruction::CancelTrade => {
+            msg!("Instruction: CancelTrade");
+            cancel_trade(program_id, accounts)
+        }
+        MarketplaceInstruction::SetListingStatus { status } => {
+            msg!("Instruction: SetListingStatus");
+            set_listing_status(program_id, accounts, status)
+        }
+        MarketplaceInstruction::ExecuteSale { purchase_index } => {
+            msg!("Instruction: ExecuteSale");
+            execute_sale(program_id, accounts, purchase_index)
+        }
+        MarketplaceInstruction::AttachTokenMetadata => {
+            msg!("Instruction: AttachTokenMetadata");
+            attach_token_metadata(program_id, accounts)
+        }
+        MarketplaceInstruction::SubmitReview { rating, content_hash } => {
+            msg!("Instruction: SubmitReview");
+            submit_review(program_id, accounts, rating, content_hash)
+        }
+        MarketplaceInstruction::InitializeBadgeMint { kind } => {
+            msg!("Instruction: InitializeBadgeMint");
+            initialize_badge_mint(program_id, accounts, kind)
+        }
+        MarketplaceInstruction::ClaimBadge { kind } => {
+            msg!("Instruction: ClaimBadge");
+            claim_badge(program_id, accounts, kind)
+        }
+        MarketplaceInstruction::OpenDispute { evidence_hash } => {
+            msg!("Instruction: OpenDispute");
+            open_dispute(program_id, accounts, evidence_hash)
+        }
+        MarketplaceInstruction::AttachDisputeEvidence { evidence_hash } => {
+            msg!("Instruction: AttachDisputeEvidence");
+            attach_dispute_evidence(program_id, accounts, evidence_hash)
+        }
+        MarketplaceInstruction::SetModelArtifact {
+            storage_class,
+            artifact_uri,
+            artifact_hash,
+            artifact_size_bytes,
+        } => {
+            msg!("Instruction: SetModelArtifact");
+            set_model_artifact(program_id, accounts, storage_class, artifact_uri, artifact_hash, artifact_size_bytes)
+        }
+        MarketplaceInstruction::SetArtifactAttestor { attestor } => {
+            msg!("Instruction: SetArtifactAttestor");
+            set_artifact_attestor(program_id, accounts, attestor)
+        }
+        MarketplaceInstruction::AttestArtifact { artifact_hash } => {
+            msg!("Instruction: AttestArtifact");
+            attest_artifact(program_id, accounts, artifact_hash)
+        }
+        MarketplaceInstruction::PublishModelVersion { version, artifact_hash } => {
+            msg!("Instruction: PublishModelVersion");
+            publish_model_version(program_id, accounts, version, artifact_hash)
+        }
+        MarketplaceInstruction::AddChangelogEntry {
+            content_hash,
+            uri,
+            summary,
+        } => {
+            msg!("Instruction: AddChangelogEntry");
+            add_changelog_entry(program_id, accounts, content_hash, uri, summary)
+        }
+        MarketplaceInstruction::DeprecateModel => {
+            msg!("Instruction: DeprecateModel");
+            deprecate_model(program_id, accounts)
+        }
+        MarketplaceInstruction::FollowSeller => {
+            msg!("Instruction: FollowSeller");
+            follow_seller(program_id, accounts)
+        }
+        MarketplaceInstruction::UnfollowSeller => {
+            msg!("Instruction: UnfollowSeller");
+            unfollow_seller(program_id, accounts)
+        }
+        MarketplaceInstruction::FavoriteModel => {
+            msg!("Instruction: FavoriteModel");
+            favorite_model(program_id, accounts)
+        }
+        MarketplaceInstruction::UnfavoriteModel => {
+            msg!("Instruction: UnfavoriteModel");
+            unfavorite_model(program_id, accounts)
+        }
+        MarketplaceInstruction::UpdatePrice { price } => {
+            msg!("Instruction: UpdatePrice");
+            update_price(program_id, accounts, price)
+        }
+        MarketplaceInstruction::SetFeaturedList { list_id, label } => {
+            msg!("Instruction: SetFeaturedList");
+            set_featured_list(program_id, accounts, list_id, label)
+        }
+        MarketplaceInstruction::RegisterCategory { id, parent, label } => {
+            msg!("Instruction: RegisterCategory");
+            register_category(program_id, accounts, id, parent, label)
+        }
+        MarketplaceInstruction::RegisterProvenanceVerifier {
+            verifier_id,
+            proof_system,
+            claim,
+            verifying_key,
+        } => {
+            msg!("Instruction: RegisterProvenanceVerifier");
+            register_provenance_verifier(program_id, accounts, verifier_id, proof_system, claim, verifying_key)
+        }
+        MarketplaceInstruction::VerifyProvenance => {
+            msg!("Instruction: VerifyProvenance");
+            verify_provenance(program_id, accounts)
+        }
+        MarketplaceInstruction::CommitInferenceResult { input_hash, output_hash } => {
+            msg!("Instruction: CommitInferenceResult");
+            commit_inference_result(program_id, accounts, input_hash, output_hash)
+        }
+        MarketplaceInstruction::EnqueueInferenceRequest {
+            request_id,
+            prompt_hash,
+            max_price,
+            deadline,
+            challenge_window,
+        } => {
+            msg!("Instruction: EnqueueInferenceRequest");
+            enqueue_inference_request(program_id, accounts, request_id, prompt_hash, max_price, deadline, challenge_window)
+        }
+        MarketplaceInstruction::SubmitResult {
+            output_hash,
+            proof_uri,
+            price,
+        } => {
+            msg!("Instruction: SubmitResult");
+            submit_result(program_id, accounts, output_hash, proof_uri, price)
+        }
+        MarketplaceInstruction::CancelInferenceRequest => {
+            msg!("Instruction: CancelInferenceRequest");
+            cancel_inference_request(program_id, accounts)
+        }
+        MarketplaceInstruction::RegisterWorker {
+            hardware_class,
+            supported_formats,
+            price_per_unit,
+            endpoint_hash,
+        } => {
+            msg!("Instruction: RegisterWorker");
+            register_worker(program_id, accounts, hardware_class, supported_formats, price_per_unit, endpoint_hash)
+        }
+        MarketplaceInstruction::StakeWorker { amount } => {
+            msg!("Instruction: StakeWorker");
+            stake_worker(program_id, accounts, amount)
+        }
+        MarketplaceInstruction::UnstakeWorker { amount } => {
+            msg!("Instruction: UnstakeWorker");
+            unstake_worker(program_id, accounts, amount)
+        }
+        MarketplaceInstruction::SetInferenceArbiter { arbiter } => {
+            msg!("Instruction: SetInferenceArbiter");
+            set_inference_arbiter(program_id, accounts, arbiter)
+        }
+        MarketplaceInstruction::SlashWorker => {
+            msg!("Instruction: SlashWorker");
+            slash_worker(program_id, accounts)
+        }
+        MarketplaceInstruction::ClaimJob => {
+            msg!("Instruction: ClaimJob");
+            claim_job(program_id, accounts)
+        }
+        MarketplaceInstruction::ReassignExpiredClaim => {
+            msg!("Instruction: ReassignExpiredClaim");
+            reassign_expired_claim(program_id, accounts)
+        }
+        MarketplaceInstruction::ChallengeResult => {
+            msg!("Instruction: ChallengeResult");
+            challenge_result(program_id, accounts)
+        }
+        MarketplaceInstruction::SettleInferenceRequest => {
+            msg!("Instruction: SettleInferenceRequest");
+            settle_inference_request(program_id, accounts)
+        }
+        MarketplaceInstruction::UpholdResult => {
+            msg!("Instruction: UpholdResult");
+            uphold_result(program_id, accounts)
+        }
+        MarketplaceInstruction::SetRentalRate { rate_per_slot } => {
+            msg!("Instruction: SetRentalRate");
+            set_rental_rate(program_id, accounts, rate_per_slot)
+        }
+        MarketplaceInstruction::OpenRentalStream { deposit } => {
+            msg!("Instruction: OpenRentalStream");
+            open_rental_stream(program_id, accounts, deposit)
+        }
+        MarketplaceInstruction::WithdrawRentalStream => {
+            msg!("Instruction: WithdrawRentalStream");
+            withdraw_rental_stream(program_id, accounts)
+        }
+        MarketplaceInstruction::CloseRentalStream => {
+            msg!("Instruction: CloseRentalStream");
+            close_rental_stream(program_id, accounts)
+        }
+        MarketplaceInstruction::FundCommission {
+            commission_id,
+            seller,
+            milestone_amounts,
+        } => {
+            msg!("Instruction: FundCommission");
+            fund_commission(program_id, accounts, commission_id, seller, milestone_amounts)
+        }
+        MarketplaceInstruction::SubmitMilestone { index, deliverable_hash } => {
+            msg!("Instruction: SubmitMilestone");
+            submit_milestone(program_id, accounts, index, deliverable_hash)
+        }
+        MarketplaceInstruction::ApproveMilestone { index } => {
+            msg!("Instruction: ApproveMilestone");
+            approve_milestone(program_id, accounts, index)
+        }
+        MarketplaceInstruction::ClaimMilestone { index } => {
+            msg!("Instruction: ClaimMilestone");
+            claim_milestone(program_id, accounts, index)
+        }
+        MarketplaceInstruction::DisputeMilestone { index } => {
+            msg!("Instruction: DisputeMilestone");
+            dispute_milestone(program_id, accounts, index)
+        }
+        MarketplaceInstruction::SetCommissionArbiter { arbiter } => {
+            msg!("Instruction: SetCommissionArbiter");
+            set_commission_arbiter(program_id, accounts, arbiter)
+        }
+        MarketplaceInstruction::ResolveMilestoneDispute { index, release } => {
+            msg!("Instruction: ResolveMilestoneDispute");
+            resolve_milestone_dispute(program_id, accounts, index, release)
+        }
+        MarketplaceInstruction::CloseCommission => {
+            msg!("Instruction: CloseCommission");
+            close_commission(program_id, accounts)
+        }
+        MarketplaceInstruction::SetSubscriptionTiers {
+            period_slots,
+            tiers,
+            grace_period_slots,
+        } => {
+            msg!("Instruction: SetSubscriptionTiers");
+            set_subscription_tiers(program_id, accounts, period_slots, tiers, grace_period_slots)
+        }
+        MarketplaceInstruction::Subscribe { tier } => {
+            msg!("Instruction: Subscribe");
+            subscribe(program_id, accounts, tier)
+        }
+        MarketplaceInstruction::ChangeTier { tier } => {
+            msg!("Instruction: ChangeTier");
+            change_tier(program_id, accounts, tier)
+        }
+        MarketplaceInstruction::VerifySubscription { access_scopes } => {
+            msg!("Instruction: VerifySubscription");
+            verify_subscription(program_id, accounts, access_scopes)
+        }
+        MarketplaceInstruction::FundSubscriptionRenewal { amount } => {
+            msg!("Instruction: FundSubscriptionRenewal");
+            fund_subscription_renewal(program_id, accounts, amount)
+        }
+        MarketplaceInstruction::RenewSubscription => {
+            msg!("Instruction: RenewSubscription");
+            renew_subscription(program_id, accounts)
+        }
+        MarketplaceInstruction::LapseSubscription => {
+            msg!("Instruction: LapseSubscription");
+            lapse_subscription(program_id, accounts)
+        }
+        MarketplaceInstruction::CancelSubscription => {
+            msg!("Instruction: CancelSubscription");
+            cancel_subscription(program_id, accounts)
+        }
+        MarketplaceInstruction::CreateOrganization { org_id, name } => {
+            msg!("Instruction: CreateOrganization");
+            create_organization(program_id, accounts, org_id, name)
+        }
+        MarketplaceInstruction::SetOrganizationMember { member, role } => {
+            msg!("Instruction: SetOrganizationMember");
+            set_organization_member(program_id, accounts, member, role)
+        }
+        MarketplaceInstruction::RemoveOrganizationMember { member } => {
+            msg!("Instruction: RemoveOrganizationMember");
+            remove_organization_member(program_id, accounts, member)
+        }
+        MarketplaceInstruction::WithdrawOrganizationFunds { amount } => {
+            msg!("Instruction: WithdrawOrganizationFunds");
+            withdraw_organization_funds(program_id, accounts, amount)
+        }
+        MarketplaceInstruction::GrantRole { role, holder } => {
+            msg!("Instruction: GrantRole");
+            grant_role(program_id, accounts, role, holder)
+        }
+        MarketplaceInstruction::RevokeRole { role, holder } => {
+            msg!("Instruction: RevokeRole");
+            revoke_role(program_id, accounts, role, holder)
+        }
+        MarketplaceInstruction::BindApiKey { api_key_hash } => {
+            msg!("Instruction: BindApiKey");
+            bind_api_key(program_id, accounts, api_key_hash)
+        }
+        MarketplaceInstruction::SetMeteringAuthority { authority } => {
+            msg!("Instruction: SetMeteringAuthority");
+            set_metering_authority(program_id, accounts, authority)
+        }
+        MarketplaceInstruction::RecordUsage { requests } => {
+            msg!("Instruction: RecordUsage");
+            record_usage(program_id, accounts, requests)
+        }
+        MarketplaceInstruction::CancelOrderNonces { min_nonce } => {
+            msg!("Instruction: CancelOrderNonces");
+            cancel_order_nonces(program_id, accounts, min_nonce)
+        }
+        MarketplaceInstruction::SettleSignedOrder {
+            order,
+            fill_quantity,
+            purchase_index,
+            acknowledge_deprecation,
+        } => {
+            msg!("Instruction: SettleSignedOrder");
+            settle_signed_order(program_id, accounts, order, fill_quantity, purchase_index, acknowledge_deprecation)
+        }
+        MarketplaceInstruction::SetMaxSupply { max_supply } => {
+            msg!("Instruction: SetMaxSupply");
+            set_max_supply(program_id, accounts, max_supply)
+        }
+        MarketplaceInstruction::SetOpenEditionEnd { end_slot } => {
+            msg!("Instruction: SetOpenEditionEnd");
+            set_open_edition_end(program_id, accounts, end_slot)
+        }
+        MarketplaceInstruction::FinalizeOpenEdition => {
+            msg!("Instruction: FinalizeOpenEdition");
+            finalize_open_edition(program_id, accounts)
+        }
+        MarketplaceInstruction::SetUpgradeDiscount { discount_bps } => {
+            msg!("Instruction: SetUpgradeDiscount");
+            set_upgrade_discount(program_id, accounts, discount_bps)
+        }
+        MarketplaceInstruction::UpgradeLicense {
+            tier_index,
+            quantity,
+            purchase_index,
+        } => {
+            msg!("Instruction: UpgradeLicense");
+            upgrade_license(program_id, accounts, tier_index, quantity, purchase_index)
+        }
+        MarketplaceInstruction::SetLicenseTransferability { transferability, royalty_bps } => {
+            msg!("Instruction: SetLicenseTransferability");
+            set_license_transferability(program_id, accounts, transferability, royalty_bps)
+        }
+        MarketplaceInstruction::TransferLicense { price, purchase_index } => {
+            msg!("Instruction: TransferLicense");
+            transfer_license(program_id, accounts, price, purchase_index)
+        }
+        MarketplaceInstruction::RevokeLicense { reason_hash } => {
+            msg!("Instruction: RevokeLicense");
+            revoke_license(program_id, accounts, reason_hash)
+        }
+        MarketplaceInstruction::RenewLicense => {
+            msg!("Instruction: RenewLicense");
+            renew_license(program_id, accounts)
+        }
+        MarketplaceInstruction::SetUsageUnitPrice { unit_price } => {
+            msg!("Instruction: SetUsageUnitPrice");
+            set_usage_unit_price(program_id, accounts, unit_price)
+        }
+        MarketplaceInstruction::TopUpCredits { mint, amount } => {
+            msg!("Instruction: TopUpCredits");
+            top_up_credits(program_id, accounts, mint, amount)
+        }
+        MarketplaceInstruction::SettleUsage => {
+            msg!("Instruction: SettleUsage");
+            settle_usage(program_id, accounts)
+        }
+        MarketplaceInstruction::BurnCredits { amount } => {
+            msg!("Instruction: BurnCredits");
+            burn_credits(program_id, accounts, amount)
+        }
+        MarketplaceInstruction::ConfigureCreditMint => {
+            msg!("Instruction: ConfigureCreditMint");
+            configure_credit_mint(program_id, accounts)
+        }
+        MarketplaceInstruction::BuyCredits { amount } => {
+            msg!("Instruction: BuyCredits");
+            buy_credits(program_id, accounts, amount)
+        }
+        MarketplaceInstruction::PurchaseWithCredits {
+            amount,
+            tier_index,
+            quantity,
+            purchase_index,
+            acknowledge_deprecation,
+        } => {
+            msg!("Instruction: PurchaseWithCredits");
+            purchase_with_credits(
+                program_id,
+                accounts,
+                amount,
+                tier_index,
+                quantity,
+                purchase_index,
+                acknowledge_deprecation,
+            )
+        }
+        MarketplaceInstruction::SetPriceBounds { min_price, max_price } => {
+            msg!("Instruction: SetPriceBounds");
+            set_price_bounds(program_id, accounts, min_price, max_price)
+        }
+        MarketplaceInstruction::SetDropThrottle { cooldown_slots, max_per_slot } => {
+            msg!("Instruction: SetDropThrottle");
+            set_drop_throttle(program_id, accounts, cooldown_slots, max_per_slot)
+        }
+        MarketplaceInstruction::SetVerificationRequirement { gatekeeper_network } => {
+            msg!("Instruction: SetVerificationRequirement");
+            set_verification_requirement(program_id, accounts, gatekeeper_network)
+        }
+        MarketplaceInstruction::SetFeeSchedule { primary_fee_bps, secondary_fee_bps } => {
+            msg!("Instruction: SetFeeSchedule");
+            set_fee_schedule(program_id, accounts, primary_fee_bps, secondary_fee_bps)
+        }
+        MarketplaceInstruction::RegisterCharity { wallet, active } => {
+            msg!("Instruction: RegisterCharity");
+            register_charity(program_id, accounts, wallet, active)
+        }
+        MarketplaceInstruction::SetListingCharity { charity, charity_bps } => {
+            msg!("Instruction: SetListingCharity");
+            set_listing_charity(program_id, accounts, charity, charity_bps)
+        }
+        MarketplaceInstruction::SetBuyerCharity { charity, charity_bps } => {
+            msg!("Instruction: SetBuyerCharity");
+            set_buyer_charity(program_id, accounts, charity, charity_bps)
+        }
+        MarketplaceInstruction::WithdrawCharityDonations { amount } => {
+            msg!("Instruction: WithdrawCharityDonations");
+            withdraw_charity_donations(program_id, accounts, amount)
+        }
+        MarketplaceInstruction::SetWithholding { destination, withholding_bps } => {
+            msg!("Instruction: SetWithholding");
+            set_withholding(program_id, accounts, destination, withholding_bps)
+        }
+        MarketplaceInstruction::ReleaseWithheldProceeds => {
+            msg!("Instruction: ReleaseWithheldProceeds");
+            release_withheld_proceeds(program_id, accounts)
+        }
+        MarketplaceInstruction::PurchaseWithPoints {
+            amount,
+            tier_index,
+            quantity,
+            purchase_index,
+            acknowledge_deprecation,
+            points,
+        } => {
+            msg!("Instruction: PurchaseWithPoints");
+            purchase_ai_model(
+                program_id,
+                accounts,
+                amount,
+                tier_index,
+                quantity,
+                purchase_index,
+                acknowledge_deprecation,
+                points,
+                [0u8; 32],
+            )
+        }
+        MarketplaceInstruction::SetLoyaltyRates { accrual_bps, redemption_rate } => {
+            msg!("Instruction: SetLoyaltyRates");
+            set_loyalty_rates(program_id, accounts, accrual_bps, redemption_rate)
+        }
+        MarketplaceInstruction::InitializeAchievementMint { achievement } => {
+            msg!("Instruction: InitializeAchievementMint");
+            initialize_achievement_mint(program_id, accounts, achievement)
+        }
+        MarketplaceInstruction::ClaimAchievement { achievement, mint_badge } => {
+            msg!("Instruction: ClaimAchievement");
+            claim_achievement(program_id, accounts, achievement, mint_badge)
+        }
+        MarketplaceInstruction::CreateAirdropCampaign { campaign_id, tier_index, max_recipients, merkle_root } => {
+            msg!("Instruction: CreateAirdropCampaign");
+            create_airdrop_campaign(program_id, accounts, campaign_id, tier_index, max_recipients, merkle_root)
+        }
+        MarketplaceInstruction::AirdropLicenses => {
+            msg!("Instruction: AirdropLicenses");
+            airdrop_licenses(program_id, accounts)
+        }
+        MarketplaceInstruction::CloseAirdropCampaign => {
+            msg!("Instruction: CloseAirdropCampaign");
+            close_airdrop_campaign(program_id, accounts)
+        }
+        MarketplaceInstruction::ClaimAirdrop { proof } => {
+            msg!("Instruction: ClaimAirdrop");
+            claim_airdrop(program_id, accounts, proof)
+        }
+        MarketplaceInstruction::SetPresale { merkle_root, presale_price, public_sale_slot } => {
+            msg!("Instruction: SetPresale");
+            set_presale(program_id, accounts, merkle_root, presale_price, public_sale_slot)
+        }
+        MarketplaceInstruction::PurchasePresale {
+            tier_index,
+            quantity,
+            purchase_index,
+            acknowledge_deprecation,
+            allocation,
+            proof,
+        } => {
+            msg!("Instruction: PurchasePresale");
+            purchase_presale(
+                program_id,
+                accounts,
+                tier_index,
+                quantity,
+                purchase_index,
+                acknowledge_deprecation,
+                allocation,
+                proof,
+            )
+        }
+        MarketplaceInstruction::AnnounceListing { kind, nonce } => {
+            msg!("Instruction: AnnounceListing");
+            announce_listing(program_id, accounts, kind, nonce)
+        }
+        MarketplaceInstruction::UpdatePriceFeed { feed_id, price, confidence } => {
+            msg!("Instruction: UpdatePriceFeed");
+            update_price_feed(program_id, accounts, feed_id, price, confidence)
+        }
+        MarketplaceInstruction::SetOraclePricing {
+            price_feed,
+            quote_price,
+            max_staleness_slots,
+            max_confidence_bps,
+            fallback_price,
+        } => {
+            msg!("Instruction: SetOraclePricing");
+            set_oracle_pricing(
+                program_id,
+                accounts,
+                price_feed,
+                quote_price,
+                max_staleness_slots,
+                max_confidence_bps,
+                fallback_price,
+            )
+        }
+        MarketplaceInstruction::PurchaseAtOraclePrice {
+            max_amount,
+            tier_index,
+            quantity,
+            purchase_index,
+            acknowledge_deprecation,
+        } => {
+            msg!("Instruction: PurchaseAtOraclePrice");
+            purchase_at_oracle_price(
+                program_id,
+                accounts,
+                max_amount,
+                tier_index,
+                quantity,
+                purchase_index,
+                acknowledge_deprecation,
+            )
+        }
+        MarketplaceInstruction::SetModelCategory { category_id } => {
+            msg!("Instruction: SetModelCategory");
+            set_model_category(program_id, accounts, category_id)
+        }
+        MarketplaceInstruction::SetLocalizedMetadata {
+            lang,
+            name,
+            description_uri,
+        } => {
+            msg!("Instruction: SetLocalizedMetadata");
+            set_localized_metadata(program_id, accounts, lang, name, description_uri)
+        }
+        MarketplaceInstruction::SetMetadataUri {
+            metadata_uri,
+            metadata_hash,
+        } => {
+            msg!("Instruction: SetMetadataUri");
+            set_metadata_uri(program_id, accounts, metadata_uri, metadata_hash)
+        }
+        MarketplaceInstruction::SetUpdateAuthority { update_authority } => {
+            msg!("Instruction: SetUpdateAuthority");
+            set_update_authority(program_id, accounts, update_authority)
+        }
+        MarketplaceInstruction::SetModelOwner { new_owner } => {
+            msg!("Instruction: SetModelOwner");
+            set_model_owner(program_id, accounts, new_owner)
+        }
+        MarketplaceInstruction::SetModelCreator { creator } => {
+            msg!("Instruction: SetModelCreator");
+            set_model_creator(program_id, accounts, creator)
+        }
+        MarketplaceInstruction::VerifyCreator => {
+            msg!("Instruction: VerifyCreator");
+            verify_creator(program_id, accounts)
+        }
+        MarketplaceInstruction::SetListingOracle { oracle } => {
+            msg!("Instruction: SetListingOracle");
+            set_listing_oracle(program_id, accounts, oracle)
+        }
+        MarketplaceInstruction::RequireListingApproval => {
+            msg!("Instruction: RequireListingApproval");
+            require_listing_approval(program_id, accounts)
+        }
+        MarketplaceInstruction::ApproveListing {
+            artifact_hash,
+            attestation_hash,
+        } => {
+            msg!("Instruction: ApproveListing");
+            approve_listing(program_id, accounts, artifact_hash, attestation_hash)
+        }
+    }
+}
+
+// 供其他链上程序（例如智能体框架）通过CPI购买模型或校验许可证，
+// 依赖方应启用no-entrypoint特性以避免入口点冲突
+pub mod cpi {
+    use super::*;
+
+    // 购买指令所需的账户
+    pub struct PurchaseAIModel<'a, 'info> {
+        pub ai_model: &'a AccountInfo<'info>,
+        pub buyer: &'a AccountInfo<'info>,
+        pub seller: &'a AccountInfo<'info>,
+        pub receipt: &'a AccountInfo<'info>,
+        pub seller_stats: &'a AccountInfo<'info>,
+        pub system_program: &'a AccountInfo<'info>,
+        pub proceeds_vault: &'a AccountInfo<'info>,
+        pub sales_ledger: &'a AccountInfo<'info>,
+        pub treasury: &'a AccountInfo<'info>,
+        pub seller_bond: &'a AccountInfo<'info>,
+        pub buyer_stats: &'a AccountInfo<'info>,
+        pub buyer_receipts: &'a AccountInfo<'info>,
+        // 买家对该模型上一次购买的凭证，首次购买时为None
+        pub previous_receipt: Option<&'a AccountInfo<'info>>,
+        // 买家的购买节流PDA，即find_purchase_throttle_address推导的地址
+        pub purchase_throttle: &'a AccountInfo<'info>,
+        // 买家的Civic网关令牌，模型要求真人验证时必须传入
+        pub verification_pass: Option<&'a AccountInfo<'info>>,
+        // 接受慈善分成的慈善机构PDA，模型设置了慈善分成时必须传入
+        pub charity: Option<&'a AccountInfo<'info>>,
+        // SPL Memo程序，传入时成交附带结构化备忘
+        pub memo_program: Option<&'a AccountInfo<'info>>,
+    }
+
+    // 校验许可证指令所需的账户
+    pub struct VerifyLicense<'a, 'info> {
+        pub receipt: &'a AccountInfo<'info>,
+        pub ai_model: &'a AccountInfo<'info>,
+        pub wallet: &'a AccountInfo<'info>,
+    }
+
+    // 构造购买指令，sales_ledger为find_sales_ledger_address按模型current_ledger_page推导的地址，
+    // buyer_receipts为find_buyer_receipts_address按买家统计current_receipts_page推导的地址，
+    // purchase_index为买家此前购买该模型的次数，购买已弃用的模型时acknowledge_deprecation必须为true，
+    // reference为写入购买凭证的对账参考号，不需要时传全零
+    #[allow(clippy::too_many_arguments)]
+    pub fn purchase_ai_model_instruction(
+        program_id: &Pubkey,
+        ai_model: &Pubkey,
+        buyer: &Pubkey,
+        seller: &Pubkey,
+        sales_ledger: &Pubkey,
+        buyer_receipts: &Pubkey,
+        amount: u64,
+        tier_index: u8,
+        quantity: u32,
+        purchase_index: u32,
+        acknowledge_deprecation: bool,
+        reference: [u8; 32],
+    ) -> Instruction {
+        let (receipt, _) = find_receipt_address(ai_model, buyer, purchase_index, program_id);
+        let (seller_stats, _) = find_seller_stats_address(seller, program_id);
+        let (proceeds_vault, _) = find_proceeds_vault_address(seller, program_id);
+        let (treasury, _) = find_treasury_address(program_id);
+        let (seller_bond, _) = find_seller_bond_address(seller, program_id);
+        let (buyer_stats, _) = find_buyer_stats_address(buyer, program_id);
+        let mut accounts = vec![
+            AccountMeta::new(*ai_model, false),
+            AccountMeta::new(*buyer, true),
+            AccountMeta::new(*seller, false),
+            AccountMeta::new(receipt, false),
+            AccountMeta::new(seller_stats, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(proceeds_vault, false),
+            AccountMeta::new(*sales_ledger, false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(seller_bond, false),
+            AccountMeta::new(buyer_stats, false),
+            AccountMeta::new(*buyer_receipts, false),
+        ];
+        if purchase_index > 0 {
+            let (previous_receipt, _) = find_receipt_address(ai_model, buyer, purchase_index - 1, program_id);
+            accounts.push(AccountMeta::new_readonly(previous_receipt, false));
+        }
+        // 总是传入购买节流PDA，模型是否为drop由链上状态决定
+        let (purchase_throttle, _) = find_purchase_throttle_address(ai_model, buyer, program_id);
+        accounts.push(AccountMeta::new(purchase_throttle, false));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: MarketplaceInstruction::PurchaseAIModel {
+                amount,
+                tier_index,
+                quantity,
+                purchase_index,
+                acknowledge_deprecation,
+                reference,
+            }
+            .pack(),
+        }
+    }
+
+    // 构造校验许可证指令
+    pub fn verify_license_instruction(
+        program_id: &Pubkey,
+        receipt: &Pubkey,
+        ai_model: &Pubkey,
+        wallet: &Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*receipt, false),
+                AccountMeta::new_readonly(*ai_model, false),
+                AccountMeta::new_readonly(*wallet, false),
+            ],
+            data: MarketplaceInstruction::VerifyLicense.pack(),
+        }
+    }
+
+    // 通过CPI购买模型，买家为PDA时需要传入其签名种子
+    #[allow(clippy::too_many_arguments)]
+    pub fn purchase_ai_model<'info>(
+        program: &AccountInfo<'info>,
+        accounts: PurchaseAIModel<'_, 'info>,
+        amount: u64,
+        tier_index: u8,
+        quantity: u32,
+        purchase_index: u32,
+        acknowledge_deprecation: bool,
+        reference: [u8; 32],
+        signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let mut instruction = purchase_ai_model_instruction(
+            program.key,
+            accounts.ai_model.key,
+            accounts.buyer.key,
+            accounts.seller.key,
+            accounts.sales_ledger.key,
+            accounts.buyer_receipts.key,
+            amount,
+            tier_index,
+            quantity,
+            purchase_index,
+            acknowledge_deprecation,
+            reference,
+        );
+        let mut account_infos = vec![
+            accounts.ai_model.clone(),
+            accounts.buyer.clone(),
+            accounts.seller.clone(),
+            accounts.receipt.clone(),
+            accounts.seller_stats.clone(),
+            accounts.system_program.clone(),
+            accounts.proceeds_vault.clone(),
+            accounts.sales_ledger.clone(),
+            accounts.treasury.clone(),
+            accounts.seller_bond.clone(),
+            accounts.buyer_stats.clone(),
+            accounts.buyer_receipts.clone(),
+            program.clone(),
+        ];
+        if let Some(previous_receipt) = accounts.previous_receipt {
+            account_infos.push(previous_receipt.clone());
+        }
+        account_infos.push(accounts.purchase_throttle.clone());
+        if let Some(verification_pass) = accounts.verification_pass {
+            instruction.accounts.push(AccountMeta::new_readonly(*verification_pass.key, false));
+            account_infos.push(verification_pass.clone());
+        }
+        if let Some(charity) = accounts.charity {
+            instruction.accounts.push(AccountMeta::new(*charity.key, false));
+            account_infos.push(charity.clone());
+        }
+        if let Some(memo_program) = accounts.memo_program {
+            instruction.accounts.push(AccountMeta::new_readonly(*memo_program.key, false));
+            account_infos.push(memo_program.clone());
+        }
+        invoke_signed(&instruction, &account_infos, signer_seeds)
+    }
+
+    // 检查钱包是否持有模型的许可证，receipt应为find_receipt_address按purchase_index为0推导的凭证PDA，
+    // 链下查询同一地址的账户即可得到相同结论
+    pub fn has_license(program_id: &Pubkey, model: &Pubkey, wallet: &Pubkey, receipt: &AccountInfo) -> bool {
+        if find_receipt_address(model, wallet, 0, program_id).0 != *receipt.key || receipt.owner != program_id {
+            return false;
+        }
+        match PurchaseReceipt::unpack(&receipt.data.borrow()) {
+            Ok(receipt) => {
+                receipt.model == *model
+                    && receipt.buyer == *wallet
+                    && (receipt.expires_at_slot == 0
+                        || Clock::get().is_ok_and(|clock| !receipt.is_expired(clock.slot)))
+            }
+            Err(_) => false,
+        }
+    }
+
+    // 通过CPI校验许可证，钱包未持有许可证时返回错误
+    pub fn verify_license<'info>(
+        program: &AccountInfo<'info>,
+        accounts: VerifyLicense<'_, 'info>,
+    ) -> ProgramResult {
+        let instruction = verify_license_instruction(
+            program.key,
+            accounts.receipt.key,
+            accounts.ai_model.key,
+            accounts.wallet.key,
+        );
+        invoke(
+            &instruction,
+            &[
+                accounts.receipt.clone(),
+                accounts.ai_model.clone(),
+                accounts.wallet.clone(),
+                program.clone(),
+            ],
+        )
+    }
+}
+
+// 链下元数据的哈希校验工具，客户端下载metadata_uri指向的文档后用于确认内容未被篡改
+pub mod metadata {
+    use super::*;
+
+    // 计算元数据文档原始字节的SHA-256，即SetMetadataUri中的metadata_hash
+    pub fn hash_metadata(document: &[u8]) -> [u8; 32] {
+        solana_program::hash::hash(document).to_bytes()
+    }
+
+    // 校验下载到的元数据文档与模型记录的哈希一致；模型未设置元数据时总是返回false
+    pub fn verify_metadata(model: &AIModel, document: &[u8]) -> bool {
+        !model.metadata_uri.is_empty() && hash_metadata(document) == model.metadata_hash
+    }
+}
+
+// 市场交易的账户类型。挂单（Listing）、出价（Offer）和出价的托管（Escrow）是各自独立的Pack账户，
+// 带有类型标识、LEN常量和生命周期状态；购买凭证（PurchaseReceipt）的状态由到期slot决定，吊销时直接关闭；
+// 市场配置（MarketplaceConfig）是单例，没有生命周期。
+// AIModel不放在这里也不再拆分：它是模型目录记录而不是交易状态，成交条件已经移到挂单中，
+// 剩下的名称、文件、档位和统计由几乎所有处理程序和AIModelView按固定偏移读取，拆开只会让已部署的模型账户迁移而没有收益
+pub mod state {
+    use super::*;
+
+    // 购买凭证的状态：期限许可证到期后失效，续费后重新生效；吊销的凭证直接关闭
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum ReceiptStatus {
+        Active,
+        Expired,
+    }
+
+    // 定义购买凭证，记录买家购买了哪个模型以及实际支付的金额
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct PurchaseReceipt {
+        pub is_initialized: bool,
+        pub model: Pubkey,
+        pub buyer: Pubkey,
+        pub amount_paid: u64,
+        // 购买的许可证档位
+        pub tier: u8,
+        // 购买的席位数量
+        pub seat_count: u32,
+        // 创建和最近更新的unix时间戳，来自Clock系统变量
+        pub created_at: i64,
+        pub updated_at: i64,
+        // 买家对该模型的第几次购买，与凭证PDA种子一致
+        pub purchase_index: u32,
+        // 成交时模型的使用许可，凭证创建后不再修改
+        pub license: String,
+        // 买家绑定的API密钥的SHA-256，全零表示未绑定；链下推理网关据此鉴权
+        pub api_key_hash: [u8; 32],
+        pub api_key_bound_at: i64,
+        // 该凭证在模型所有许可证中的版次号，从1开始
+        pub edition: u32,
+        // 成交时档位是否允许卖家吊销，凭证创建后不再修改
+        pub revocable: bool,
+        // 期限许可证的到期slot，0表示永久有效；续费后顺延
+        pub expires_at_slot: u64,
+        // 创建该凭证的成交适用的手续费率（首次销售或转售费率）和实际收取的手续费
+        pub fee_bps: u16,
+        pub fee_paid: u64,
+        // 买家购买时附带的对账参考号（如采购单号或订单号的哈希），全零表示未附带；凭证转让时清空
+        pub reference: [u8; 32],
+        // 创建凭证时出资支付租金的签名账户（代付购买时可能不是买家），关闭凭证时租金退给它
+        pub rent_payer: Pubkey,
+    }
+
+    impl IsInitialized for PurchaseReceipt {
+        fn is_initialized(&self) -> bool {
+            self.is_initialized
+        }
+    }
+
+    impl Sealed for PurchaseReceipt {}
+
+    impl Pack for PurchaseReceipt {
+        const LEN: usize = packed_len!(
+            [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, Pubkey, u64, u8, u32, i64, i64, u32, [u8; MAX_LICENSE_LEN], [u8; 32], i64,
+            u32, bool, u64, u16, u64, [u8; 32], Pubkey
+        );
+
+        fn pack_into_slice(&self, output: &mut [u8]) {
+            output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+            let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+            output[offset] = self.is_initialized as u8;
+            offset += 1;
+            output[offset..offset+32].copy_from_slice(self.model.as_ref());
+            offset += 32;
+            output[offset..offset+32].copy_from_slice(self.buyer.as_ref());
+            offset += 32;
+            output[offset..offset+8].copy_from_slice(&self.amount_paid.to_le_bytes());
+            offset += 8;
+            output[offset] = self.tier;
+            offset += 1;
+            output[offset..offset+4].copy_from_slice(&self.seat_count.to_le_bytes());
+            offset += 4;
+            output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+            offset += 8;
+            output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+            offset += 8;
+            output[offset..offset+4].copy_from_slice(&self.purchase_index.to_le_bytes());
+            offset += 4;
+            pack_fixed_str(&mut output[offset..offset+MAX_LICENSE_LEN], &self.license);
+            offset += MAX_LICENSE_LEN;
+            output[offset..offset+32].copy_from_slice(&self.api_key_hash);
+            offset += 32;
+            output[offset..offset+8].copy_from_slice(&self.api_key_bound_at.to_le_bytes());
+            offset += 8;
+            output[offset..offset+4].copy_from_slice(&self.edition.to_le_bytes());
+            offset += 4;
+            output[offset] = self.revocable as u8;
+            offset += 1;
+            output[offset..offset+8].copy_from_slice(&self.expires_at_slot.to_le_bytes());
+            offset += 8;
+            output[offset..offset+2].copy_from_slice(&self.fee_bps.to_le_bytes());
+            offset += 2;
+            output[offset..offset+8].copy_from_slice(&self.fee_paid.to_le_bytes());
+            offset += 8;
+            output[offset..offset+32].copy_from_slice(&self.reference);
+            offset += 32;
+            output[offset..offset+32].copy_from_slice(self.rent_payer.as_ref());
+        }
+
+        fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+            check_discriminator::<Self>(input)?;
+            let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+            let is_initialized = input[offset] != 0;
+            offset += 1;
+            let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let buyer = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let amount_paid = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let tier = input[offset];
+            offset += 1;
+            let seat_count = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+            offset += 4;
+            let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let purchase_index = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+            offset += 4;
+            let license = unpack_fixed_str(&input[offset..offset+MAX_LICENSE_LEN])?;
+            offset += MAX_LICENSE_LEN;
+            let api_key_hash = input[offset..offset+32].try_into().unwrap();
+            offset += 32;
+            let api_key_bound_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let edition = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+            offset += 4;
+            let revocable = input[offset] != 0;
+            offset += 1;
+            let expires_at_slot = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let fee_bps = u16::from_le_bytes(input[offset..offset+2].try_into().unwrap());
+            offset += 2;
+            let fee_paid = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let reference = input[offset..offset+32].try_into().unwrap();
+            offset += 32;
+            let rent_payer = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            Ok(Self {
+                is_initialized,
+                model,
+                buyer,
+                amount_paid,
+                tier,
+                seat_count,
+                created_at,
+                updated_at,
+                purchase_index,
+                license,
+                api_key_hash,
+                api_key_bound_at,
+                edition,
+                revocable,
+                expires_at_slot,
+                fee_bps,
+                fee_paid,
+                reference,
+                rent_payer,
+            })
+        }
+    }
+
+    impl PurchaseReceipt {
+        // 链下网关校验请求携带的API密钥是否与凭证绑定的一致，未绑定时总是不一致
+        pub fn matches_api_key(&self, api_key: &[u8]) -> bool {
+            self.api_key_hash != [0u8; 32] && solana_program::hash::hash(api_key).to_bytes() == self.api_key_hash
+        }
+
+        // 期限许可证在到期slot及之后失效，永久许可证从不失效
+        pub fn is_expired(&self, slot: u64) -> bool {
+            self.expires_at_slot != 0 && slot >= self.expires_at_slot
+        }
+
+        pub fn status(&self, slot: u64) -> ReceiptStatus {
+            if self.is_expired(slot) {
+                ReceiptStatus::Expired
+            } else {
+                ReceiptStatus::Active
+            }
+        }
+
+        // 续费一个期限：未到期时从原到期slot顺延，已到期时从当前slot重新计算
+        pub fn renew(&mut self, term_slots: u64, slot: u64) -> ProgramResult {
+            if self.expires_at_slot == 0 || term_slots == 0 {
+                return Err(ProgramError::InvalidArgument);
+            }
+            self.expires_at_slot = self.expires_at_slot
+                .max(slot)
+                .checked_add(term_slots)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            Ok(())
+        }
+    }
+
+    // 定义市场配置，记录管理员以及写入配置时的程序版本和功能位
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct MarketplaceConfig {
+        pub is_initialized: bool,
+        pub admin: Pubkey,
+        pub program_version: u32,
+        pub capabilities: u64,
+        // 创建和最近更新的unix时间戳，来自Clock系统变量
+        pub created_at: i64,
+        pub updated_at: i64,
+        // 管理员开启的功能位，见FEATURE_*常量
+        pub feature_flags: u64,
+        // 紧急资金恢复的守护者多签：守护者列表、所需签名数和时间锁秒数
+        pub guardians: Vec<Pubkey>,
+        pub guardian_threshold: u8,
+        pub recovery_delay: i64,
+        // 确认链下产物存在且与声明哈希一致的验证人（预言机）公钥
+        pub artifact_attestor: Pubkey,
+        // 扫描产物并通过开启审核的上架的审核预言机公钥
+        pub listing_oracle: Pubkey,
+        // 复现推理结果并裁定争议的仲裁者公钥
+        pub inference_arbiter: Pubkey,
+        // 裁定委托开发里程碑争议的仲裁者公钥
+        pub commission_arbiter: Pubkey,
+        // 上报计量访问用量的计量权限公钥，通常是链下推理网关
+        pub metering_authority: Pubkey,
+        // 市场额度代币的储备mint（如USDC），全零表示未启用额度代币
+        pub credit_reserve_mint: Pubkey,
+        // 上架和改价时基础价格与各档位价格的下限和上限（lamports），0表示不限制，防止误输入的极端价格
+        pub min_listing_price: u64,
+        pub max_listing_price: u64,
+    }
+
+    impl MarketplaceConfig {
+        // 统计账户列表中签名了的不同守护者数量
+        pub fn count_guardian_approvals(&self, accounts: &[AccountInfo]) -> u8 {
+            let mut approved: Vec<&Pubkey> = Vec::with_capacity(self.guardians.len());
+            for account in accounts {
+                if account.is_signer && self.guardians.contains(account.key) && !approved.contains(&account.key) {
+                    approved.push(account.key);
+                }
+            }
+            approved.len() as u8
+        }
+
+        // 检查功能是否已由管理员开启，对应子系统的处理程序在执行前调用
+        pub fn require_feature(&self, feature: u64) -> ProgramResult {
+            if self.feature_flags & feature != feature {
+                msg!("Feature {:#x} is disabled", feature);
+                return Err(ProgramError::InvalidArgument);
+            }
+            Ok(())
+        }
+
+        // 检查上架价格是否在管理员设置的范围内
+        pub fn check_listing_price(&self, price: u64) -> ProgramResult {
+            if price < self.min_listing_price || (self.max_listing_price != 0 && price > self.max_listing_price) {
+                msg!(
+                    "Listing price {} is outside [{}, {}]",
+                    price,
+                    self.min_listing_price,
+                    self.max_listing_price
+                );
+                return Err(ProgramError::InvalidArgument);
+            }
+            Ok(())
+        }
+
+        // 将配置中的版本信息同步为当前程序的版本，管理指令写入配置时调用
+        pub fn sync_version(&mut self) {
+            self.program_version = PROGRAM_VERSION;
+            self.capabilities = PROGRAM_CAPABILITIES;
+        }
+    }
+
+    impl IsInitialized for MarketplaceConfig {
+        fn is_initialized(&self) -> bool {
+            self.is_initialized
+        }
+    }
+
+    impl Sealed for MarketplaceConfig {}
+
+    impl Pack for MarketplaceConfig {
+        const LEN: usize = packed_len!(
+            [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, Pubkey, u32, u64, i64, i64, u64, u8, [Pubkey; MAX_GUARDIANS], u8, i64, Pubkey,
+            Pubkey, Pubkey, Pubkey, Pubkey, Pubkey, u64, u64
+        );
+
+        fn pack_into_slice(&self, output: &mut [u8]) {
+            output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+            let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+            output[offset] = self.is_initialized as u8;
+            offset += 1;
+            output[offset..offset+32].copy_from_slice(self.admin.as_ref());
+            offset += 32;
+            output[offset..offset+4].copy_from_slice(&self.program_version.to_le_bytes());
+            offset += 4;
+            output[offset..offset+8].copy_from_slice(&self.capabilities.to_le_bytes());
+            offset += 8;
+            output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+            offset += 8;
+            output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+            offset += 8;
+            output[offset..offset+8].copy_from_slice(&self.feature_flags.to_le_bytes());
+            offset += 8;
+            output[offset] = self.guardians.len() as u8;
+            offset += 1;
+            for i in 0..MAX_GUARDIANS {
+                let slot = &mut output[offset..offset+32];
+                match self.guardians.get(i) {
+                    Some(guardian) => slot.copy_from_slice(guardian.as_ref()),
+                    None => slot.fill(0),
+                }
+                offset += 32;
+            }
+            output[offset] = self.guardian_threshold;
+            offset += 1;
+            output[offset..offset+8].copy_from_slice(&self.recovery_delay.to_le_bytes());
+            offset += 8;
+            output[offset..offset+32].copy_from_slice(self.artifact_attestor.as_ref());
+            offset += 32;
+            output[offset..offset+32].copy_from_slice(self.listing_oracle.as_ref());
+            offset += 32;
+            output[offset..offset+32].copy_from_slice(self.inference_arbiter.as_ref());
+            offset += 32;
+            output[offset..offset+32].copy_from_slice(self.commission_arbiter.as_ref());
+            offset += 32;
+            output[offset..offset+32].copy_from_slice(self.metering_authority.as_ref());
+            offset += 32;
+            output[offset..offset+32].copy_from_slice(self.credit_reserve_mint.as_ref());
+            offset += 32;
+            output[offset..offset+8].copy_from_slice(&self.min_listing_price.to_le_bytes());
+            offset += 8;
+            output[offset..offset+8].copy_from_slice(&self.max_listing_price.to_le_bytes());
+        }
+
+        fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+            check_discriminator::<Self>(input)?;
+            let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+            let is_initialized = input[offset] != 0;
+            offset += 1;
+            let admin = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let program_version = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+            offset += 4;
+            let capabilities = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let feature_flags = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let guardian_count = input[offset] as usize;
+            if guardian_count > MAX_GUARDIANS {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            offset += 1;
+            let mut guardians = Vec::with_capacity(guardian_count);
+            for i in 0..MAX_GUARDIANS {
+                if i < guardian_count {
+                    guardians.push(Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap()));
+                }
+                offset += 32;
+            }
+            let guardian_threshold = input[offset];
+            offset += 1;
+            let recovery_delay = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let artifact_attestor = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let listing_oracle = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let inference_arbiter = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let commission_arbiter = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let metering_authority = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let credit_reserve_mint = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let min_listing_price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let max_listing_price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            Ok(Self {
+                is_initialized,
+                admin,
+                program_version,
+                capabilities,
+                created_at,
+                updated_at,
+                feature_flags,
+                guardians,
+                guardian_threshold,
+                recovery_delay,
+                artifact_attestor,
+                listing_oracle,
+                inference_arbiter,
+                commission_arbiter,
+                metering_authority,
+                credit_reserve_mint,
+                min_listing_price,
+                max_listing_price,
+            })
+        }
+    }
+
+    // 挂单的生命周期：草稿 -> 在售 <-> 暂停 -> 下架。只有在售的挂单可以成交，下架是终态；
+    // 卖家可以随时关闭任意状态的挂单取回租金
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub enum ListingStatus {
+        #[default]
+        Draft,
+        Active,
+        Paused,
+        Delisted,
+    }
+
+    impl ListingStatus {
+        pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+            match val {
+                0 => Ok(ListingStatus::Draft),
+                1 => Ok(ListingStatus::Active),
+                2 => Ok(ListingStatus::Paused),
+                3 => Ok(ListingStatus::Delisted),
+                _ => Err(ProgramError::InvalidAccountData),
+            }
+        }
+
+        // 卖家可以执行的状态转换
+        pub fn can_transition_to(self, next: Self) -> bool {
+            matches!(
+                (self, next),
+                (Self::Draft, Self::Active)
+                    | (Self::Active, Self::Paused)
+                    | (Self::Paused, Self::Active)
+                    | (Self::Draft | Self::Active | Self::Paused, Self::Delisted)
+            )
+        }
+    }
+
+    // 出价的生命周期：等待成交 -> 已成交。撤回的出价直接关闭，已成交的出价由买家关闭取回租金
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub enum OfferStatus {
+        #[default]
+        Open,
+        Filled,
+    }
+
+    impl OfferStatus {
+        pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+            match val {
+                0 => Ok(OfferStatus::Open),
+                1 => Ok(OfferStatus::Filled),
+                _ => Err(ProgramError::InvalidAccountData),
+            }
+        }
+    }
+
+    // 托管的生命周期：已存入 -> 已付出。出价撤回时托管账户连同全部金额退回买家并关闭
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub enum EscrowStatus {
+        #[default]
+        Funded,
+        Released,
+    }
+
+    impl EscrowStatus {
+        pub fn from_u8(val: u8) -> Result<Self, ProgramError> {
+            match val {
+                0 => Ok(EscrowStatus::Funded),
+                1 => Ok(EscrowStatus::Released),
+                _ => Err(ProgramError::InvalidAccountData),
+            }
+        }
+    }
+
+    // 卖家以指定价格、档位和席位数出售模型的挂单，在售期间可以与多笔条件一致的出价成交
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct Listing {
+        pub is_initialized: bool,
+        pub status: ListingStatus,
+        pub seller: Pubkey,
+        pub model: Pubkey,
+        pub price: u64,
+        pub tier: u8,
+        pub quantity: u32,
+        // 按该挂单成交的次数
+        pub fills: u32,
+        // 创建和最近更新的unix时间戳，来自Clock系统变量
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+
+    impl Listing {
+        // 按卖家的请求转换状态，不允许的转换返回InvalidArgument
+        pub fn transition(&mut self, next: ListingStatus, now: i64) -> ProgramResult {
+            if !self.status.can_transition_to(next) {
+                return Err(ProgramError::InvalidArgument);
+            }
+            self.status = next;
+            self.updated_at = now;
+            Ok(())
+        }
+
+        // 记录一次成交，挂单保持在售
+        pub fn record_fill(&mut self, now: i64) -> ProgramResult {
+            if self.status != ListingStatus::Active {
+                return Err(ProgramError::InvalidArgument);
+            }
+            self.fills = self.fills.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+            self.updated_at = now;
+            Ok(())
+        }
+    }
+
+    impl IsInitialized for Listing {
+        fn is_initialized(&self) -> bool {
+            self.is_initialized
+        }
+    }
+
+    impl Sealed for Listing {}
+
+    impl Pack for Listing {
+        const LEN: usize =
+            packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, ListingStatus, Pubkey, Pubkey, u64, u8, u32, u32, i64, i64);
+
+        fn pack_into_slice(&self, output: &mut [u8]) {
+            output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+            let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+            output[offset] = self.is_initialized as u8;
+            offset += 1;
+            output[offset] = self.status as u8;
+            offset += 1;
+            output[offset..offset+32].copy_from_slice(self.seller.as_ref());
+            offset += 32;
+            output[offset..offset+32].copy_from_slice(self.model.as_ref());
+            offset += 32;
+            output[offset..offset+8].copy_from_slice(&self.price.to_le_bytes());
+            offset += 8;
+            output[offset] = self.tier;
+            offset += 1;
+            output[offset..offset+4].copy_from_slice(&self.quantity.to_le_bytes());
+            offset += 4;
+            output[offset..offset+4].copy_from_slice(&self.fills.to_le_bytes());
+            offset += 4;
+            output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+            offset += 8;
+            output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        }
+
+        fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+            check_discriminator::<Self>(input)?;
+            let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+            let is_initialized = input[offset] != 0;
+            offset += 1;
+            let status = ListingStatus::from_u8(input[offset])?;
+            offset += 1;
+            let seller = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let tier = input[offset];
+            offset += 1;
+            let quantity = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+            offset += 4;
+            let fills = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+            offset += 4;
+            let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            Ok(Self {
+                is_initialized,
+                status,
+                seller,
+                model,
+                price,
+                tier,
+                quantity,
+                fills,
+                created_at,
+                updated_at,
+            })
+        }
+    }
+
+    // 买家以指定价格、档位和席位数发出的出价，出价金额托管在独立的Escrow账户中；
+    // 成交后记录产生的购买凭证，直到买家关闭出价
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct Offer {
+        pub is_initialized: bool,
+        pub status: OfferStatus,
+        pub buyer: Pubkey,
+        pub model: Pubkey,
+        pub price: u64,
+        pub tier: u8,
+        pub quantity: u32,
+        pub escrow: Pubkey,
+        // 成交产生的购买凭证，未成交时为全零
+        pub receipt: Pubkey,
+        // 创建和最近更新的unix时间戳，来自Clock系统变量
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+
+    impl Offer {
+        // 出价与挂单成交，只有等待成交的出价可以成交一次
+        pub fn fill(&mut self, receipt: Pubkey, now: i64) -> ProgramResult {
+            if self.status != OfferStatus::Open {
+                return Err(ProgramError::InvalidArgument);
+            }
+            self.status = OfferStatus::Filled;
+            self.receipt = receipt;
+            self.updated_at = now;
+            Ok(())
+        }
+    }
+
+    impl IsInitialized for Offer {
+        fn is_initialized(&self) -> bool {
+            self.is_initialized
+        }
+    }
+
+    impl Sealed for Offer {}
+
+    impl Pack for Offer {
+        const LEN: usize = packed_len!(
+            [u8; ACCOUNT_DISCRIMINATOR_LEN], bool, OfferStatus, Pubkey, Pubkey, u64, u8, u32, Pubkey, Pubkey, i64, i64
+        );
+
+        fn pack_into_slice(&self, output: &mut [u8]) {
+            output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+            let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+            output[offset] = self.is_initialized as u8;
+            offset += 1;
+            output[offset] = self.status as u8;
+            offset += 1;
+            output[offset..offset+32].copy_from_slice(self.buyer.as_ref());
+            offset += 32;
+            output[offset..offset+32].copy_from_slice(self.model.as_ref());
+            offset += 32;
+            output[offset..offset+8].copy_from_slice(&self.price.to_le_bytes());
+            offset += 8;
+            output[offset] = self.tier;
+            offset += 1;
+            output[offset..offset+4].copy_from_slice(&self.quantity.to_le_bytes());
+            offset += 4;
+            output[offset..offset+32].copy_from_slice(self.escrow.as_ref());
+            offset += 32;
+            output[offset..offset+32].copy_from_slice(self.receipt.as_ref());
+            offset += 32;
+            output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+            offset += 8;
+            output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        }
+
+        fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+            check_discriminator::<Self>(input)?;
+            let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+            let is_initialized = input[offset] != 0;
+            offset += 1;
+            let status = OfferStatus::from_u8(input[offset])?;
+            offset += 1;
+            let buyer = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let model = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let tier = input[offset];
+            offset += 1;
+            let quantity = u32::from_le_bytes(input[offset..offset+4].try_into().unwrap());
+            offset += 4;
+            let escrow = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let receipt = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            Ok(Self {
+                is_initialized,
+                status,
+                buyer,
+                model,
+                price,
+                tier,
+                quantity,
+                escrow,
+                receipt,
+                created_at,
+                updated_at,
+            })
+        }
+    }
+
+    // 出价的托管账户，只托管lamports：amount为存入的出价金额，released为成交时付出的金额，
+    // 二者之差在买家关闭出价时连同租金退回
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct Escrow {
+        pub is_initialized: bool,
+        pub status: EscrowStatus,
+        pub offer: Pubkey,
+        pub depositor: Pubkey,
+        pub amount: u64,
+        pub released: u64,
+        // 创建和最近更新的unix时间戳，来自Clock系统变量
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+
+    impl Escrow {
+        // 仍欠买家的托管金额：成交前是全部出价金额，成交后是出价中未用完的部分
+        pub fn escrowed_amount(&self) -> u64 {
+            self.amount.saturating_sub(self.released)
+        }
+
+        // 成交时付出amount，只能付出一次且不超过存入的金额
+        pub fn release(&mut self, amount: u64, now: i64) -> ProgramResult {
+            if self.status != EscrowStatus::Funded {
+                return Err(ProgramError::InvalidArgument);
+            }
+            if amount > self.amount {
+                return Err(ProgramError::InsufficientFunds);
+            }
+            self.status = EscrowStatus::Released;
+            self.released = amount;
+            self.updated_at = now;
+            Ok(())
+        }
+    }
+
+    impl IsInitialized for Escrow {
+        fn is_initialized(&self) -> bool {
+            self.is_initialized
+        }
+    }
+
+    impl Sealed for Escrow {}
+
+    impl Pack for Escrow {
+        const LEN: usize = packed_len!([u8; ACCOUNT_DISCRIMINATOR_LEN], bool, EscrowStatus, Pubkey, Pubkey, u64, u64, i64, i64);
+
+        fn pack_into_slice(&self, output: &mut [u8]) {
+            output[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Self::DISCRIMINATOR);
+            let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+            output[offset] = self.is_initialized as u8;
+            offset += 1;
+            output[offset] = self.status as u8;
+            offset += 1;
+            output[offset..offset+32].copy_from_slice(self.offer.as_ref());
+            offset += 32;
+            output[offset..offset+32].copy_from_slice(self.depositor.as_ref());
+            offset += 32;
+            output[offset..offset+8].copy_from_slice(&self.amount.to_le_bytes());
+            offset += 8;
+            output[offset..offset+8].copy_from_slice(&self.released.to_le_bytes());
+            offset += 8;
+            output[offset..offset+8].copy_from_slice(&self.created_at.to_le_bytes());
+            offset += 8;
+            output[offset..offset+8].copy_from_slice(&self.updated_at.to_le_bytes());
+        }
+
+        fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+            check_discriminator::<Self>(input)?;
+            let mut offset = ACCOUNT_DISCRIMINATOR_LEN;
+            let is_initialized = input[offset] != 0;
+            offset += 1;
+            let status = EscrowStatus::from_u8(input[offset])?;
+            offset += 1;
+            let offer = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let depositor = Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap());
+            offset += 32;
+            let amount = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let released = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let created_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            offset += 8;
+            let updated_at = i64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
+            Ok(Self {
+                is_initialized,
+                status,
+                offer,
+                depositor,
+                amount,
+                released,
+                created_at,
+                updated_at,
+            })
+        }
+    }
+}
+
+// 订阅变更档位、流式租用结算等按时间折算金额的共用计算。舍入规则：向买家收取的金额向上取整，
+// 抵扣给买家的价值和折算出的时间向下取整，舍入误差总是归于卖家；中间结果用u128计算，超出u64时饱和
+pub mod proration {
+    fn saturate(value: u128) -> u64 {
+        u64::try_from(value).unwrap_or(u64::MAX)
+    }
+
+    // 周期价格为price（周期长period_slots个slot）时，slots个slot的价值，向下取整；周期为0时价值为0
+    pub fn credit(price: u64, period_slots: u64, slots: u64) -> u64 {
+        if period_slots == 0 {
+            return 0;
+        }
+        saturate(price as u128 * slots as u128 / period_slots as u128)
+    }
+
+    // 同credit，但向上取整，用于向买家收费
+    pub fn charge(price: u64, period_slots: u64, slots: u64) -> u64 {
+        if period_slots == 0 {
+            return 0;
+        }
+        saturate((price as u128 * slots as u128).div_ceil(period_slots as u128))
+    }
+
+    // 价值value按周期价格折算为slot数，向下取整；价格为0时饱和为u64::MAX
+    pub fn slots_for(value: u64, price: u64, period_slots: u64) -> u64 {
+        if price == 0 {
+            return u64::MAX;
+        }
+        saturate(value as u128 * period_slots as u128 / price as u128)
+    }
+
+    // 按每slot费率计算elapsed个slot的用量，不超过预付的cap
+    pub fn metered(rate_per_slot: u64, elapsed: u64, cap: u64) -> u64 {
+        rate_per_slot.saturating_mul(elapsed).min(cap)
+    }
+
+    // 提前结束时退回买家的未使用部分
+    pub fn unused(paid: u64, consumed: u64) -> u64 {
+        paid.saturating_sub(consumed)
+    }
+
+    // 周期中途变更档位的结果：需要补交的金额，以及变更后的剩余slot数
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct TierChange {
+        pub charge: u64,
+        pub remaining_slots: u64,
+    }
+
+    // 剩余时间在旧档位下的价值抵扣新档位同样时间的费用：不足时补交差价，剩余时间不变；
+    // 有余时不退款，余额按新价格折算为剩余时间，且剩余时间不会因舍入而缩短
+    pub fn tier_change(old_price: u64, old_period_slots: u64, new_price: u64, new_period_slots: u64, remaining_slots: u64) -> TierChange {
+        let credit = credit(old_price, old_period_slots, remaining_slots);
+        let cost = charge(new_price, new_period_slots, remaining_slots);
+        if cost > credit {
+            TierChange {
+                charge: cost - credit,
+                remaining_slots,
+            }
+        } else {
+            TierChange {
+                charge: 0,
+                remaining_slots: slots_for(credit, new_price, new_period_slots).max(remaining_slots),
+            }
+        }
+    }
+}
+
+// 空投名单和预售白名单使用的Merkle树。叶子和内部节点的哈希带不同前缀以防止第二原像攻击，
+// 内部节点对两个子节点按字节序排序后再哈希，因此证明中不需要记录左右方向
+pub mod merkle {
+    use solana_program::hash::hashv;
+
+    const LEAF_PREFIX: &[u8] = &[0];
+    const NODE_PREFIX: &[u8] = &[1];
+
+    // 由若干字段拼接计算叶子哈希
+    pub fn leaf(parts: &[&[u8]]) -> [u8; 32] {
+        let mut input = Vec::with_capacity(parts.len() + 1);
+        input.push(LEAF_PREFIX);
+        input.extend_from_slice(parts);
+        hashv(&input).to_bytes()
+    }
+
+    fn node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+        let (left, right) = if a <= b { (a, b) } else { (b, a) };
+        hashv(&[NODE_PREFIX, left, right]).to_bytes()
+    }
+
+    // 自叶子沿证明逐层向上计算，结果等于root时证明有效
+    pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: &[u8; 32]) -> bool {
+        proof.iter().fold(*leaf, |hash, sibling| node(&hash, sibling)) == *root
+    }
+
+    // 链下构建名单时使用：计算叶子列表的根，奇数个节点时末尾节点直接进入上一层；空列表的根为全零
+    pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| if pair.len() == 2 { node(&pair[0], &pair[1]) } else { pair[0] })
+                .collect();
+        }
+        level.first().copied().unwrap_or([0u8; 32])
+    }
+
+    // 链下构建名单时使用：计算第index个叶子的证明
+    pub fn proof(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            level = level
+                .chunks(2)
+                .map(|pair| if pair.len() == 2 { node(&pair[0], &pair[1]) } else { pair[0] })
+                .collect();
+            index /= 2;
+        }
+        proof
+    }
+}
+
+// 程序日志中的结构化事件，供关注列表、价格提醒等链下服务订阅交易日志后解析
+pub mod events {
+    use super::*;
+    use std::str::FromStr;
+
+    // 运行时为msg!输出添加的日志前缀
+    const PROGRAM_LOG_PREFIX: &str = "Program log: ";
+
+    // 模型价格变化事件，mint为模型关联的NFT mint（未关联时为全零）
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct PriceChanged {
+        pub model: Pubkey,
+        pub mint: Pubkey,
+        pub old_price: u64,
+        pub new_price: u64,
+    }
+
+    impl PriceChanged {
+        pub const NAME: &'static str = "PriceChanged";
+
+        // 事件的日志文本，emit输出的即为该文本
+        pub fn to_log(&self) -> String {
+            format!(
+                "{}: model={} mint={} old_price={} new_price={}",
+                Self::NAME,
+                self.model,
+                self.mint,
+                self.old_price,
+                self.new_price
+            )
+        }
+
+        pub fn emit(&self) {
+            msg!("{}", self.to_log());
+        }
+
+        // 从一行交易日志中解析事件，兼容带或不带"Program log: "前缀的日志
+        pub fn from_log(line: &str) -> Option<Self> {
+            let line = line.strip_prefix(PROGRAM_LOG_PREFIX).unwrap_or(line);
+            let mut fields = line.strip_prefix(Self::NAME)?.strip_prefix(": ")?.split(' ');
+            let mut field = |name: &str| fields.next()?.strip_prefix(name)?.strip_prefix('=');
+            let model = Pubkey::from_str(field("model")?).ok()?;
+            let mint = Pubkey::from_str(field("mint")?).ok()?;
+            let old_price = field("old_price")?.parse().ok()?;
+            let new_price = field("new_price")?.parse().ok()?;
+            Some(Self {
+                model,
+                mint,
+                old_price,
+                new_price,
+            })
+        }
+
+        // 是否为降价
+        pub fn is_drop(&self) -> bool {
+            self.new_price < self.old_price
+        }
+    }
+
+    // PriceChanged事件的订阅过滤条件，models或mints为空表示不按该字段过滤
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct PriceChangedFilter {
+        pub models: Vec<Pubkey>,
+        pub mints: Vec<Pubkey>,
+        // 只关注降价
+        pub drops_only: bool,
+        // 只关注新价格不高于该值的变化
+        pub max_new_price: Option<u64>,
+    }
+
+    impl PriceChangedFilter {
+        pub fn matches(&self, event: &PriceChanged) -> bool {
+            (self.models.is_empty() || self.models.contains(&event.model))
+                && (self.mints.is_empty() || self.mints.contains(&event.mint))
+                && (!self.drops_only || event.is_drop())
+                && !matches!(self.max_new_price, Some(max) if event.new_price > max)
+        }
+
+        // 从一笔交易的日志中取出满足过滤条件的PriceChanged事件
+        pub fn filter_logs<S: AsRef<str>>(&self, logs: &[S]) -> Vec<PriceChanged> {
+            logs.iter()
+                .filter_map(|line| PriceChanged::from_log(line.as_ref()))
+                .filter(|event| self.matches(event))
+                .collect()
+        }
+    }
+
+    // 订阅结束的原因：超过宽限期未续订而失效，或由订阅者主动取消
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum SubscriptionEndReason {
+        Lapsed,
+        Canceled,
+    }
+
+    impl SubscriptionEndReason {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                SubscriptionEndReason::Lapsed => "lapsed",
+                SubscriptionEndReason::Canceled => "canceled",
+            }
+        }
+
+        pub fn parse(value: &str) -> Option<Self> {
+            match value {
+                "lapsed" => Some(SubscriptionEndReason::Lapsed),
+                "canceled" => Some(SubscriptionEndReason::Canceled),
+                _ => None,
+            }
+        }
+    }
+
+    // 订阅结束事件，paid_through_slot为订阅者已付费的最后期限
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct SubscriptionEnded {
+        pub subscription: Pubkey,
+        pub model: Pubkey,
+        pub subscriber: Pubkey,
+        pub reason: SubscriptionEndReason,
+        pub paid_through_slot: u64,
+    }
+
+    impl SubscriptionEnded {
+        pub const NAME: &'static str = "SubscriptionEnded";
+
+        pub fn to_log(&self) -> String {
+            format!(
+                "{}: subscription={} model={} subscriber={} reason={} paid_through_slot={}",
+                Self::NAME,
+                self.subscription,
+                self.model,
+                self.subscriber,
+                self.reason.as_str(),
+                self.paid_through_slot
+            )
+        }
+
+        pub fn emit(&self) {
+            msg!("{}", self.to_log());
+        }
+
+        pub fn from_log(line: &str) -> Option<Self> {
+            let line = line.strip_prefix(PROGRAM_LOG_PREFIX).unwrap_or(line);
+            let mut fields = line.strip_prefix(Self::NAME)?.strip_prefix(": ")?.split(' ');
+            let mut field = |name: &str| fields.next()?.strip_prefix(name)?.strip_prefix('=');
+            let subscription = Pubkey::from_str(field("subscription")?).ok()?;
+            let model = Pubkey::from_str(field("model")?).ok()?;
+            let subscriber = Pubkey::from_str(field("subscriber")?).ok()?;
+            let reason = SubscriptionEndReason::parse(field("reason")?)?;
+            let paid_through_slot = field("paid_through_slot")?.parse().ok()?;
+            Some(Self {
+                subscription,
+                model,
+                subscriber,
+                reason,
+                paid_through_slot,
+            })
+        }
+    }
+
+    // 许可证被卖家吊销事件，reason_hash以base58输出，供审计时与链下吊销依据比对
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct LicenseRevoked {
+        pub receipt: Pubkey,
+        pub model: Pubkey,
+        pub holder: Pubkey,
+        pub revoked_by: Pubkey,
+        pub edition: u32,
+        pub reason_hash: [u8; 32],
+    }
+
+    impl LicenseRevoked {
+        pub const NAME: &'static str = "LicenseRevoked";
+
+        pub fn to_log(&self) -> String {
+            format!(
+                "{}: receipt={} model={} holder={} revoked_by={} edition={} reason_hash={}",
+                Self::NAME,
+                self.receipt,
+                self.model,
+                self.holder,
+                self.revoked_by,
+                self.edition,
+                Hash::new_from_array(self.reason_hash)
+            )
+        }
+
+        pub fn emit(&self) {
+            msg!("{}", self.to_log());
+        }
+
+        pub fn from_log(line: &str) -> Option<Self> {
+            let line = line.strip_prefix(PROGRAM_LOG_PREFIX).unwrap_or(line);
+            let mut fields = line.strip_prefix(Self::NAME)?.strip_prefix(": ")?.split(' ');
+            let mut field = |name: &str| fields.next()?.strip_prefix(name)?.strip_prefix('=');
+            let receipt = Pubkey::from_str(field("receipt")?).ok()?;
+            let model = Pubkey::from_str(field("model")?).ok()?;
+            let holder = Pubkey::from_str(field("holder")?).ok()?;
+            let revoked_by = Pubkey::from_str(field("revoked_by")?).ok()?;
+            let edition = field("edition")?.parse().ok()?;
+            let reason_hash = Hash::from_str(field("reason_hash")?).ok()?.to_bytes();
+            Some(Self {
+                receipt,
+                model,
+                holder,
+                revoked_by,
+                edition,
+                reason_hash,
+            })
+        }
+    }
+
+    // 成交事件，reference为买家附带的对账参考号，全零表示未附带
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Purchased {
+        pub receipt: Pubkey,
+        pub model: Pubkey,
+        pub buyer: Pubkey,
+        pub tier: u8,
+        pub quantity: u32,
+        pub amount_paid: u64,
+        // 扣除积分抵扣后实际收取的手续费，与购买凭证的fee_paid一致
+        pub fee: u64,
+        pub edition: u32,
+        pub reference: [u8; 32],
+    }
+
+    impl Purchased {
+        pub const NAME: &'static str = "Purchased";
+
+        pub fn to_log(&self) -> String {
+            format!(
+                "{}: receipt={} model={} buyer={} tier={} quantity={} amount_paid={} fee={} edition={} reference={}",
+                Self::NAME,
+                self.receipt,
+                self.model,
+                self.buyer,
+                self.tier,
+                self.quantity,
+                self.amount_paid,
+                self.fee,
+                self.edition,
+                Hash::new_from_array(self.reference)
+            )
+        }
+
+        pub fn emit(&self) {
+            msg!("{}", self.to_log());
+        }
+
+        pub fn from_log(line: &str) -> Option<Self> {
+            let line = line.strip_prefix(PROGRAM_LOG_PREFIX).unwrap_or(line);
+            let mut fields = line.strip_prefix(Self::NAME)?.strip_prefix(": ")?.split(' ');
+            let mut field = |name: &str| fields.next()?.strip_prefix(name)?.strip_prefix('=');
+            let receipt = Pubkey::from_str(field("receipt")?).ok()?;
+            let model = Pubkey::from_str(field("model")?).ok()?;
+            let buyer = Pubkey::from_str(field("buyer")?).ok()?;
+            let tier = field("tier")?.parse().ok()?;
+            let quantity = field("quantity")?.parse().ok()?;
+            let amount_paid = field("amount_paid")?.parse().ok()?;
+            let fee = field("fee")?.parse().ok()?;
+            let edition = field("edition")?.parse().ok()?;
+            let reference = Hash::from_str(field("reference")?).ok()?.to_bytes();
+            Some(Self {
+                receipt,
+                model,
+                buyer,
+                tier,
+                quantity,
+                amount_paid,
+                fee,
+                edition,
+                reference,
+            })
+        }
+    }
+
+    // 按量计费结算事件，逐项列出本次结算的请求数、单价、金额和手续费，以及结算后的剩余情况
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct UsageSettled {
+        pub receipt: Pubkey,
+        pub model: Pubkey,
+        pub buyer: Pubkey,
+        pub requests: u64,
+        pub unit_price: u64,
+        pub amount: u64,
+        pub fee: u64,
+        pub unsettled_requests: u64,
+        pub remaining_balance: u64,
+    }
+
+    impl UsageSettled {
+        pub const NAME: &'static str = "UsageSettled";
+
+        pub fn to_log(&self) -> String {
+            format!(
+                "{}: receipt={} model={} buyer={} requests={} unit_price={} amount={} fee={} unsettled_requests={} remaining_balance={}",
+                Self::NAME,
+                self.receipt,
+                self.model,
+                self.buyer,
+                self.requests,
+                self.unit_price,
+                self.amount,
+                self.fee,
+                self.unsettled_requests,
+                self.remaining_balance
+            )
+        }
+
+        pub fn emit(&self) {
+            msg!("{}", self.to_log());
+        }
+
+        pub fn from_log(line: &str) -> Option<Self> {
+            let line = line.strip_prefix(PROGRAM_LOG_PREFIX).unwrap_or(line);
+            let mut fields = line.strip_prefix(Self::NAME)?.strip_prefix(": ")?.split(' ');
+            let mut field = |name: &str| fields.next()?.strip_prefix(name)?.strip_prefix('=');
+            let receipt = Pubkey::from_str(field("receipt")?).ok()?;
+            let model = Pubkey::from_str(field("model")?).ok()?;
+            let buyer = Pubkey::from_str(field("buyer")?).ok()?;
+            let requests = field("requests")?.parse().ok()?;
+            let unit_price = field("unit_price")?.parse().ok()?;
+            let amount = field("amount")?.parse().ok()?;
+            let fee = field("fee")?.parse().ok()?;
+            let unsettled_requests = field("unsettled_requests")?.parse().ok()?;
+            let remaining_balance = field("remaining_balance")?.parse().ok()?;
+            Some(Self {
+                receipt,
+                model,
+                buyer,
+                requests,
+                unit_price,
+                amount,
+                fee,
+                unsettled_requests,
+                remaining_balance,
+            })
+        }
+    }
+}
+
+// 链下客户端读取账户和发送交易的辅助工具，不依赖具体的RPC客户端：调用方实现AccountFetcher等trait，
+// 把请求转发给RPC，这里负责分批、排列地址、解码和重试；只在链下编译，不进入链上程序
+#[cfg(not(target_os = "solana"))]
+pub mod client {
+    use super::*;
+    use solana_program::message::Message;
+    use std::str::FromStr;
+
+    // getMultipleAccounts单次请求最多100个地址
+    pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+    pub trait AccountFetcher {
+        type Error: From<ProgramError>;
+
+        // 按keys的顺序返回账户数据，账户不存在时为None
+        fn get_multiple_accounts(&self, keys: &[Pubkey]) -> Result<Vec<Option<Vec<u8>>>, Self::Error>;
+    }
+
+    // 按MAX_MULTIPLE_ACCOUNTS分批读取，结果与keys一一对应
+    pub fn fetch_multiple<F: AccountFetcher>(fetcher: &F, keys: &[Pubkey]) -> Result<Vec<Option<Vec<u8>>>, F::Error> {
+        let mut accounts = Vec::with_capacity(keys.len());
+        for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let fetched = fetcher.get_multiple_accounts(chunk)?;
+            if fetched.len() != chunk.len() {
+                return Err(ProgramError::InvalidArgument.into());
+            }
+            accounts.extend(fetched);
+        }
+        Ok(accounts)
+    }
+
+    // 上架详情页需要的全部账户
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ListingAggregate {
+        pub listing: AIModel,
+        // 按version_index排列的发布版本
+        pub versions: Vec<ModelVersion>,
+        // 仍然有效的出价及其地址，已关闭的出价被跳过
+        pub offers: Vec<(Pubkey, Offer)>,
+        // 卖家还没有成交时统计账户不存在
+        pub seller_stats: Option<SellerStats>,
+    }
+
+    // 一次往返取齐上架详情需要预先知道卖家和发布版本数，通常来自索引或上次读取的上架；
+    // 出价地址来自程序账户查询
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ListingAggregateRequest {
+        pub listing: Pubkey,
+        pub seller: Pubkey,
+        pub version_count: u32,
+        pub offers: Vec<Pubkey>,
+    }
+
+    impl ListingAggregateRequest {
+        pub fn for_listing(listing_key: Pubkey, listing: &AIModel, offers: Vec<Pubkey>) -> Self {
+            Self {
+                listing: listing_key,
+                seller: listing.owner,
+                version_count: listing.version_count,
+                offers,
+            }
+        }
+
+        // 地址顺序：上架、卖家统计、各发布版本、各出价
+        pub fn keys(&self, program_id: &Pubkey) -> Vec<Pubkey> {
+            let mut keys = vec![self.listing, find_seller_stats_address(&self.seller, program_id).0];
+            keys.extend((0..self.version_count).map(|index| find_model_version_address(&self.listing, index, program_id).0));
+            keys.extend_from_slice(&self.offers);
+            keys
+        }
+
+        // 按keys的顺序解码读取结果。上架或发布版本不存在、或上架的卖家和版本数与请求不一致时返回InvalidArgument
+        pub fn hydrate(&self, accounts: &[Option<Vec<u8>>]) -> Result<ListingAggregate, ProgramError> {
+            fn existing(data: &Option<Vec<u8>>) -> Result<&[u8], ProgramError> {
+                data.as_deref().ok_or(ProgramError::InvalidArgument)
+            }
+
+            let version_count = self.version_count as usize;
+            if accounts.len() != 2 + version_count + self.offers.len() {
+                return Err(ProgramError::InvalidArgument);
+            }
+            let listing = AIModel::unpack(existing(&accounts[0])?)?;
+            if listing.owner != self.seller || listing.version_count != self.version_count {
+                return Err(ProgramError::InvalidArgument);
+            }
+            let seller_stats = accounts[1].as_deref().map(SellerStats::unpack).transpose()?;
+            let versions = accounts[2..2 + version_count]
+                .iter()
+                .map(|data| ModelVersion::unpack(existing(data)?))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut offers = Vec::with_capacity(self.offers.len());
+            for (key, data) in self.offers.iter().zip(&accounts[2 + version_count..]) {
+                if let Some(data) = data {
+                    let offer = Offer::unpack(data)?;
+                    if offer.model != self.listing {
+                        return Err(ProgramError::InvalidArgument);
+                    }
+                    offers.push((*key, offer));
+                }
+            }
+            Ok(ListingAggregate {
+                listing,
+                versions,
+                offers,
+                seller_stats,
+            })
+        }
+    }
+
+    // getProgramAccounts的服务端筛选条件，与RPC的dataSize和memcmp筛选一一对应
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum AccountFilter {
+        DataSize(u64),
+        Memcmp { offset: usize, bytes: Vec<u8> },
+    }
+
+    impl AccountFilter {
+        pub fn matches(&self, data: &[u8]) -> bool {
+            match self {
+                AccountFilter::DataSize(size) => data.len() as u64 == *size,
+                AccountFilter::Memcmp { offset, bytes } => data.get(*offset..*offset + bytes.len()) == Some(bytes.as_slice()),
+            }
+        }
+    }
+
+    pub trait ProgramAccountScanner: AccountFetcher {
+        // 返回程序名下满足全部筛选条件的账户地址。实现时用dataSlice长度0只取地址，账户数据由get_multiple_accounts分页读取
+        fn get_program_account_keys(&self, program_id: &Pubkey, filters: &[AccountFilter]) -> Result<Vec<Pubkey>, Self::Error>;
+    }
+
+    pub const DEFAULT_LISTING_PAGE_SIZE: usize = MAX_MULTIPLE_ACCOUNTS;
+
+    // 上架查询：卖家和分类通过memcmp在服务端筛选；memcmp只能比较相等，价格上限在解码后于本地筛选
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ListingQuery {
+        filters: Vec<AccountFilter>,
+        max_price: Option<u64>,
+        page_size: usize,
+    }
+
+    impl Default for ListingQuery {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl ListingQuery {
+        pub fn new() -> Self {
+            Self {
+                filters: vec![
+                    AccountFilter::DataSize(AIModel::LEN as u64),
+                    AccountFilter::Memcmp { offset: 0, bytes: AIModel::DISCRIMINATOR.to_vec() },
+                ],
+                max_price: None,
+                page_size: DEFAULT_LISTING_PAGE_SIZE,
+            }
+        }
+
+        pub fn by_owner(mut self, owner: &Pubkey) -> Self {
+            self.filters.push(AccountFilter::Memcmp { offset: AIModelView::OWNER_OFFSET, bytes: owner.to_bytes().to_vec() });
+            self
+        }
+
+        pub fn by_category(mut self, category_id: u16) -> Self {
+            self.filters.push(AccountFilter::Memcmp {
+                offset: AIModelView::CATEGORY_ID_OFFSET,
+                bytes: category_id.to_le_bytes().to_vec(),
+            });
+            self
+        }
+
+        // 只保留标价低于max_price的上架
+        pub fn price_below(mut self, max_price: u64) -> Self {
+            self.max_price = Some(self.max_price.map_or(max_price, |current| current.min(max_price)));
+            self
+        }
+
+        // 每页读取的地址数，价格筛选在读取后进行，返回的上架可能少于page_size
+        pub fn page_size(mut self, page_size: usize) -> Self {
+            self.page_size = page_size.max(1);
+            self
+        }
+
+        pub fn filters(&self) -> &[AccountFilter] {
+            &self.filters
+        }
+
+        // 读取cursor之后的一页。地址按字节序排列，next_cursor为None表示已经读完
+        pub fn fetch_page<F: ProgramAccountScanner>(
+            &self,
+            fetcher: &F,
+            program_id: &Pubkey,
+            cursor: Option<&Pubkey>,
+        ) -> Result<ListingPage, F::Error> {
+            let mut keys = fetcher.get_program_account_keys(program_id, &self.filters)?;
+            keys.sort();
+            let start = cursor.map_or(0, |cursor| keys.partition_point(|key| key <= cursor));
+            let page = &keys[start..keys.len().min(start + self.page_size)];
+            let next_cursor = if start + page.len() < keys.len() { page.last().copied() } else { None };
+
+            let mut listings = Vec::with_capacity(page.len());
+            for (key, data) in page.iter().zip(fetch_multiple(fetcher, page)?) {
+                // 扫描之后被关闭的上架跳过
+                let Some(data) = data else { continue };
+                let listing = AIModel::unpack(&data)?;
+                if matches!(self.max_price, Some(max_price) if listing.price >= max_price) {
+                    continue;
+                }
+                listings.push((*key, listing));
+            }
+            Ok(ListingPage { listings, next_cursor })
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ListingPage {
+        pub listings: Vec<(Pubkey, AIModel)>,
+        pub next_cursor: Option<Pubkey>,
+    }
+
+    // 运行时在程序失败时输出"Program <地址> failed: <原因>"，原因即InstructionError的文本
+    const PROGRAM_FAILED_INFIX: &str = " failed: ";
+    const CUSTOM_PROGRAM_ERROR_PREFIX: &str = "custom program error: 0x";
+
+    // 从失败交易日志中解出的程序错误
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ProgramFailure {
+        // 最外层失败的程序，CPI中的失败会一路向上传递到该程序
+        pub program_id: Pubkey,
+        // 能对应到ProgramError时为Some，reason保留运行时输出的原文
+        pub error: Option<ProgramError>,
+        pub reason: String,
+        // 该程序本次调用中msg!输出的日志，已去掉"Program log: "前缀
+        pub messages: Vec<String>,
+    }
+
+    fn program_error_from_reason(reason: &str) -> Option<ProgramError> {
+        if let Some(code) = reason.strip_prefix(CUSTOM_PROGRAM_ERROR_PREFIX) {
+            return u32::from_str_radix(code, 16).ok().map(ProgramError::Custom);
+        }
+        Some(match reason {
+            "invalid program argument" => ProgramError::InvalidArgument,
+            "invalid instruction data" => ProgramError::InvalidInstructionData,
+            "invalid account data for instruction" => ProgramError::InvalidAccountData,
+            "account data too small for instruction" => ProgramError::AccountDataTooSmall,
+            "insufficient funds for instruction" => ProgramError::InsufficientFunds,
+            "incorrect program id for instruction" => ProgramError::IncorrectProgramId,
+            "missing required signature for instruction" => ProgramError::MissingRequiredSignature,
+            "instruction requires an uninitialized account" => ProgramError::AccountAlreadyInitialized,
+            "instruction requires an initialized account" => ProgramError::UninitializedAccount,
+            "insufficient account keys for instruction" => ProgramError::NotEnoughAccountKeys,
+            "Provided seeds do not result in a valid address" => ProgramError::InvalidSeeds,
+            "Program arithmetic overflowed" => ProgramError::ArithmeticOverflow,
+            _ => return None,
+        })
+    }
+
+    // 解析失败交易的日志，日志中没有程序失败记录时返回None
+    pub fn decode_program_failure(logs: &[String]) -> Option<ProgramFailure> {
+        let (failed_at, program_id, reason) = logs.iter().enumerate().rev().find_map(|(index, line)| {
+            let (program, reason) = line.strip_prefix("Program ")?.split_once(PROGRAM_FAILED_INFIX)?;
+            Some((index, Pubkey::from_str(program).ok()?, reason))
+        })?;
+        let invoke = format!("Program {} invoke [", program_id);
+        let invoked_at = logs[..failed_at].iter().rposition(|line| line.starts_with(&invoke)).unwrap_or(0);
+        let messages = logs[invoked_at..failed_at]
+            .iter()
+            .filter_map(|line| line.strip_prefix("Program log: "))
+            .map(str::to_string)
+            .collect();
+        Some(ProgramFailure {
+            program_id,
+            error: program_error_from_reason(reason),
+            reason: reason.to_string(),
+            messages,
+        })
+    }
+
+    // RPC返回的交易确认状态
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum TransactionStatus {
+        // 节点尚未看到交易执行
+        Pending,
+        Confirmed,
+        // 交易已执行但失败，附带执行日志
+        Failed { logs: Vec<String> },
+    }
+
+    // 发送交易所需的RPC操作，签名由实现方完成。确认状态和区块高度须使用同一承诺级别
+    pub trait TransactionSender {
+        type Signature: Clone;
+        type Error;
+
+        // 最新的blockhash及其最后有效的区块高度
+        fn latest_blockhash(&self) -> Result<(Hash, u64), Self::Error>;
+        // 签名并发送交易。应跳过预检，失败交易的日志从确认状态中取得
+        fn send(&self, message: &Message) -> Result<Self::Signature, Self::Error>;
+        fn status(&self, signature: &Self::Signature) -> Result<TransactionStatus, Self::Error>;
+        fn block_height(&self) -> Result<u64, Self::Error>;
+        // 两次查询确认状态之间的等待，通常睡眠约一个slot
+        fn wait(&self);
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum SendError<E> {
+        Transport(E),
+        // 交易已执行但失败
+        Program(ProgramFailure),
+        // 执行失败但日志中没有程序失败记录，例如手续费不足
+        Failed { logs: Vec<String> },
+        // 用完max_attempts个blockhash仍未确认
+        Expired { attempts: u32 },
+    }
+
+    impl<E> From<E> for SendError<E> {
+        fn from(error: E) -> Self {
+            SendError::Transport(error)
+        }
+    }
+
+    // 发送交易并等待确认。blockhash过期且交易仍未执行时换新的blockhash重签重发，最多max_attempts次。
+    // 只有在确认旧blockhash已过期之后才重发，旧交易不可能再上链，购买等指令不会被重复执行
+    pub fn send_and_confirm<S: TransactionSender>(
+        sender: &S,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+        max_attempts: u32,
+    ) -> Result<S::Signature, SendError<S::Error>> {
+        for _ in 0..max_attempts {
+            let (blockhash, last_valid_block_height) = sender.latest_blockhash()?;
+            let message = Message::new_with_blockhash(instructions, Some(payer), &blockhash);
+            let signature = sender.send(&message)?;
+            loop {
+                // 先取区块高度再查状态：高度已超过有效期时仍为Pending，说明交易没有在有效期内执行
+                let block_height = sender.block_height()?;
+                match sender.status(&signature)? {
+                    TransactionStatus::Confirmed => return Ok(signature),
+                    TransactionStatus::Failed { logs } => {
+                        return Err(match decode_program_failure(&logs) {
+                            Some(failure) => SendError::Program(failure),
+                            None => SendError::Failed { logs },
+                        });
+                    }
+                    TransactionStatus::Pending if block_height > last_valid_block_height => break,
+                    TransactionStatus::Pending => sender.wait(),
+                }
+            }
+        }
+        Err(SendError::Expired { attempts: max_attempts })
+    }
+
+    // 读取上架详情。请求中的卖家或版本数已过期时，按读到的上架重新组织请求再读一次
+    pub fn fetch_listing_aggregate<F: AccountFetcher>(
+        fetcher: &F,
+        program_id: &Pubkey,
+        request: &ListingAggregateRequest,
+    ) -> Result<ListingAggregate, F::Error> {
+        let accounts = fetch_multiple(fetcher, &request.keys(program_id))?;
+        let listing = match &accounts[0] {
+            Some(data) => AIModel::unpack(data)?,
+            None => return Err(ProgramError::InvalidArgument.into()),
+        };
+        if listing.owner == request.seller && listing.version_count == request.version_count {
+            return Ok(request.hydrate(&accounts)?);
+        }
+        let request = ListingAggregateRequest::for_listing(request.listing, &listing, request.offers.clone());
+        let accounts = fetch_multiple(fetcher, &request.keys(program_id))?;
+        Ok(request.hydrate(&accounts)?)
+    }
+}
+
+// 入口点函数
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+// 单元测试夹具：TestAccount持有AccountInfo借用的key、lamports和数据缓冲区，
+// install_syscall_stubs提供Rent和Clock sysvar并模拟系统程序的create_account和transfer，
+// 处理程序不依赖验证器也能在单元测试中运行。开启test-utils功能后对集成方公开，
+// 开启program-test功能后还可以经test_utils::program_test把夹具账户预置到ProgramTest中
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils {
+    use super::*;
+    use solana_program::{
+        entrypoint::SUCCESS,
+        program_stubs::{set_syscall_stubs, SyscallStubs},
+    };
+
+    // 夹具Clock返回的slot和unix时间戳
+    pub const TEST_SLOT: u64 = 1_000;
+    pub const TEST_UNIX_TIMESTAMP: i64 = 1_700_000_000;
+
+    pub struct TestAccount {
+        pub key: Pubkey,
+        pub owner: Pubkey,
+        pub lamports: u64,
+        pub data: Vec<u8>,
+        pub is_signer: bool,
+        pub is_writable: bool,
+    }
+
+    impl TestAccount {
+        // 系统程序拥有、已充值的签名钱包
+        pub fn wallet(lamports: u64) -> Self {
+            Self {
+                key: Pubkey::new_unique(),
+                owner: system_program::id(),
+                lamports,
+                data: Vec::new(),
+                is_signer: true,
+                is_writable: true,
+            }
+        }
+
+        // 待创建的签名账户：没有lamports，数据缓冲区按将要分配的大小预留并清零
+        pub fn uninitialized(space: usize) -> Self {
+            Self { data: vec![0; space], ..Self::wallet(0) }
+        }
+
+        // 本程序拥有的状态账户，数据为state打包后的字节，余额为免租金额
+        pub fn state<T: Pack>(key: Pubkey, program_id: &Pubkey, state: &T) -> Self {
+            let mut data = vec![0; T::LEN];
+            state.pack_into_slice(&mut data);
+            Self {
+                key,
+                owner: *program_id,
+                lamports: Rent::default().minimum_balance(T::LEN),
+                data,
+                is_signer: false,
+                is_writable: true,
+            }
+        }
+
+        // 只读、不签名的程序、sysvar或空PDA账户
+        pub fn readonly(key: Pubkey) -> Self {
+            Self { key, is_signer: false, is_writable: false, ..Self::wallet(0) }
+        }
+
+        pub fn writable(self) -> Self {
+            Self { is_writable: true, ..self }
+        }
+
+        pub fn info(&mut self) -> AccountInfo<'_> {
+            AccountInfo::new(
+                &self.key,
+                self.is_signer,
+                self.is_writable,
+                &mut self.lamports,
+                &mut self.data,
+                &self.owner,
+                false,
+                0,
+            )
+        }
+    }
+
+    // 已上架的固定价格模型，卖家是所有者、更新权限和已验证的创作者
+    pub fn listing(program_id: &Pubkey, seller: &Pubkey, price: u64) -> TestAccount {
+        let model = AIModel {
+            is_initialized: true,
+            name: String::from("test-model"),
+            owner: *seller,
+            update_authority: *seller,
+            creator: *seller,
+            creator_verified: true,
+            price,
+            license: String::from("MIT"),
+            created_at: TEST_UNIX_TIMESTAMP,
+            updated_at: TEST_UNIX_TIMESTAMP,
+            ..AIModel::default()
+        };
+        TestAccount::state(Pubkey::new_unique(), program_id, &model)
+    }
+
+    // receipt对应的购买凭证PDA，地址由模型、买家和purchase_index推导
+    pub fn receipt(program_id: &Pubkey, receipt: &PurchaseReceipt) -> TestAccount {
+        let key = find_receipt_address(&receipt.model, &receipt.buyer, receipt.purchase_index, program_id).0;
+        TestAccount::state(key, program_id, receipt)
+    }
+
+    // 买家的出价PDA和它的托管PDA，托管账户除免租金额外还托管出价金额
+    pub fn bid_escrow(program_id: &Pubkey, offer: &Offer) -> (TestAccount, TestAccount) {
+        let offer_key = find_offer_address(&offer.buyer, &offer.model, offer.price, program_id).0;
+        let escrow_key = find_escrow_address(&offer_key, program_id).0;
+        let escrow = Escrow {
+            is_initialized: true,
+            offer: offer_key,
+            depositor: offer.buyer,
+            amount: offer.price,
+            ..Escrow::default()
+        };
+        let mut escrow_account = TestAccount::state(escrow_key, program_id, &escrow);
+        escrow_account.lamports += offer.price;
+        (TestAccount::state(offer_key, program_id, &Offer { escrow: escrow_key, ..offer.clone() }), escrow_account)
+    }
+
+    // 多步骤场景：按名称登记账户，经process_instruction依次执行指令，再断言余额和账户状态。
+    // 场景持有所有账户，每步重新借出AccountInfo；待创建的PDA用pda预留缓冲区。
+    // 场景在进程内执行、只模拟系统程序，涉及其他程序的流程用program_test（program-test特性）
+    pub struct Scenario {
+        pub program_id: Pubkey,
+        accounts: Vec<ScenarioAccount>,
+    }
+
+    struct ScenarioAccount {
+        name: &'static str,
+        account: TestAccount,
+        // 尚未创建的PDA：预留的缓冲区在系统程序分配空间之前不借给处理程序
+        reserved: bool,
+    }
+
+    thread_local! {
+        // 当前步骤中尚未创建的PDA及其预留缓冲区，供系统程序桩分配空间
+        static RESERVED_DATA: std::cell::RefCell<Vec<(Pubkey, *mut u8, usize)>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    impl Scenario {
+        pub fn new(program_id: Pubkey) -> Self {
+            install_syscall_stubs();
+            Self { program_id, accounts: Vec::new() }
+        }
+
+        pub fn account(mut self, name: &'static str, account: TestAccount) -> Self {
+            self.accounts.push(ScenarioAccount { name, account, reserved: false });
+            self
+        }
+
+        // 尚未创建、由处理程序经系统程序创建的PDA，按账户大小预留缓冲区
+        pub fn pda(mut self, name: &'static str, key: Pubkey, space: usize) -> Self {
+            let account = TestAccount { data: vec![0; space], ..TestAccount::readonly(key).writable() };
+            self.accounts.push(ScenarioAccount { name, account, reserved: true });
+            self
+        }
+
+        fn get(&self, name: &str) -> &ScenarioAccount {
+            self.accounts
+                .iter()
+                .find(|entry| entry.name == name)
+                .unwrap_or_else(|| panic!("unknown scenario account {}", name))
+        }
+
+        pub fn key(&self, name: &str) -> Pubkey {
+            self.get(name).account.key
+        }
+
+        pub fn lamports(&self, name: &str) -> u64 {
+            self.get(name).account.lamports
+        }
+
+        pub fn state<T: Pack + IsInitialized>(&self, name: &str) -> T {
+            let entry = self.get(name);
+            let data: &[u8] = if entry.reserved { &[] } else { &entry.account.data };
+            T::unpack(data).unwrap_or_else(|error| panic!("{} state: {:?}", name, error))
+        }
+
+        // 按names的顺序传入账户执行一条指令；每步从场景持有的账户重新借出AccountInfo，
+        // 同一账户出现多次时共享同一份余额和数据
+        pub fn try_step(&mut self, instruction: &MarketplaceInstruction, names: &[&str]) -> ProgramResult {
+            let program_id = self.program_id;
+            let mut reserved = Vec::new();
+            let infos: Vec<(&str, AccountInfo)> = self
+                .accounts
+                .iter_mut()
+                .map(|entry| {
+                    let account = &mut entry.account;
+                    let data: &mut [u8] = if entry.reserved {
+                        let data = account.data.as_mut_ptr();
+                        reserved.push((account.key, data, account.data.len()));
+                        // 与预留缓冲区同源的空切片，分配空间时由系统程序桩换成完整的缓冲区
+                        unsafe { std::slice::from_raw_parts_mut(data, 0) }
+                    } else {
+                        &mut account.data
+                    };
+                    let info = AccountInfo::new(
+                        &account.key,
+                        account.is_signer,
+                        account.is_writable,
+                        &mut account.lamports,
+                        data,
+                        &account.owner,
+                        false,
+                        0,
+                    );
+                    (entry.name, info)
+                })
+                .collect();
+            let accounts: Vec<AccountInfo> = names
+                .iter()
+                .map(|name| {
+                    infos
+                        .iter()
+                        .find(|(account_name, _)| account_name == name)
+                        .map(|(_, info)| info.clone())
+                        .unwrap_or_else(|| panic!("unknown scenario account {}", name))
+                })
+                .collect();
+            RESERVED_DATA.with(|data| *data.borrow_mut() = reserved);
+            let result = process_instruction(&program_id, &accounts, &instruction.pack());
+            RESERVED_DATA.with(|data| data.borrow_mut().clear());
+            let created: Vec<Pubkey> = infos.iter().filter(|(_, info)| !info.data_is_empty()).map(|(_, info)| *info.key).collect();
+            drop(accounts);
+            drop(infos);
+            for entry in self.accounts.iter_mut().filter(|entry| entry.reserved) {
+                entry.reserved = !created.contains(&entry.account.key);
+            }
+            result
+        }
+
+        pub fn step(mut self, label: &str, instruction: MarketplaceInstruction, names: &[&str]) -> Self {
+            if let Err(error) = self.try_step(&instruction, names) {
+                panic!("scenario step '{}' failed: {:?}", label, error);
+            }
+            self
+        }
+
+        pub fn expect_lamports(self, name: &str, lamports: u64) -> Self {
+            assert_eq!(self.lamports(name), lamports, "lamports of {}", name);
+            self
+        }
+
+        pub fn expect_state<T: Pack + IsInitialized>(self, name: &str, check: impl FnOnce(&T)) -> Self {
+            check(&self.state::<T>(name));
+            self
+        }
+    }
+
+    // 按顺序为一组夹具账户生成处理程序的accounts参数
+    pub fn account_infos(accounts: &mut [TestAccount]) -> Vec<AccountInfo<'_>> {
+        accounts.iter_mut().map(TestAccount::info).collect()
+    }
+
+    struct TestSyscallStubs;
+
+    impl SyscallStubs for TestSyscallStubs {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            let clock = Clock { slot: TEST_SLOT, unix_timestamp: TEST_UNIX_TIMESTAMP, ..Clock::default() };
+            unsafe { *(var_addr as *mut Clock) = clock };
+            SUCCESS
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            SUCCESS
+        }
+
+        // 测试中的处理程序总是作为交易顶层指令执行
+        fn sol_get_stack_height(&self) -> u64 {
+            TRANSACTION_LEVEL_STACK_HEIGHT as u64
+        }
+
+        // 模拟系统程序的create_account（标签0）、assign（1）、transfer（2）和allocate（8），
+        // 错误码与系统程序的SystemError一致
+        fn sol_invoke_signed(
+            &self,
+            instruction: &Instruction,
+            account_infos: &[AccountInfo],
+            signers_seeds: &[&[&[u8]]],
+        ) -> ProgramResult {
+            if instruction.program_id != system_program::id() {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            let account = |index: usize| {
+                let meta = instruction.accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                account_infos
+                    .iter()
+                    .find(|info| *info.key == meta.pubkey)
+                    .ok_or(ProgramError::NotEnoughAccountKeys)
+            };
+            // 签名者要么本身签名，要么是由invoke_signed种子推导出的PDA；不知道所有者时无法校验推导，只要求提供了种子
+            let require_signer = |info: &AccountInfo, owner: Option<&Pubkey>| {
+                let derived = match owner {
+                    Some(owner) => signers_seeds
+                        .iter()
+                        .any(|seeds| Pubkey::create_program_address(seeds, owner).as_ref() == Ok(info.key)),
+                    None => !signers_seeds.is_empty(),
+                };
+                if info.is_signer || derived {
+                    Ok(())
+                } else {
+                    Err(ProgramError::MissingRequiredSignature)
+                }
+            };
+            // 夹具不为CPI分配新的缓冲区：待创建的签名账户须预留同样大小且全为0的缓冲区（TestAccount::uninitialized），
+            // 待创建的PDA由Scenario::pda预留，数据为空时换成预留的缓冲区
+            let allocate = |info: &AccountInfo, space: u64| {
+                if *info.owner != system_program::id() || info.data.borrow().iter().any(|byte| *byte != 0) {
+                    // SystemError::AccountAlreadyInUse
+                    return Err(ProgramError::Custom(0));
+                }
+                if info.data_is_empty() && space > 0 {
+                    let reserved = RESERVED_DATA.with(|reserved| {
+                        reserved.borrow().iter().find(|(key, ..)| key == info.key).map(|&(_, data, len)| (data, len))
+                    });
+                    if let Some((data, len)) = reserved.filter(|&(_, len)| len as u64 == space) {
+                        // 指针来自Scenario在本步骤中持有的预留缓冲区，长度就是缓冲区长度
+                        *info.data.borrow_mut() = unsafe { std::slice::from_raw_parts_mut(data, len) };
+                        return Ok(());
+                    }
+                }
+                if info.data_len() as u64 != space {
+                    msg!("Test fixture {} must reserve {} bytes, found {}", info.key, space, info.data_len());
+                    return Err(ProgramError::AccountDataTooSmall);
+                }
+                Ok(())
+            };
+            let transfer = |from: &AccountInfo, to: &AccountInfo, lamports: u64| {
+                // SystemError::ResultWithNegativeLamports
+                if from.lamports() < lamports {
+                    return Err(ProgramError::Custom(1));
+                }
+                **from.try_borrow_mut_lamports()? -= lamports;
+                **to.try_borrow_mut_lamports()? += lamports;
+                Ok(())
+            };
+            let owner_from = |input: &[u8]| Pubkey::try_from(input).map_err(|_| ProgramError::InvalidInstructionData);
+
+            let (tag, rest) = unpack_u32(&instruction.data)?;
+            match tag {
+                0 => {
+                    let (lamports, rest) = unpack_u64(rest)?;
+                    let (space, owner) = unpack_u64(rest)?;
+                    let owner = owner_from(owner)?;
+                    let (from, to) = (account(0)?, account(1)?);
+                    // SystemError::AccountAlreadyInUse
+                    if to.lamports() != 0 {
+                        return Err(ProgramError::Custom(0));
+                    }
+                    require_signer(from, None)?;
+                    require_signer(to, Some(&owner))?;
+                    allocate(to, space)?;
+                    transfer(from, to, lamports)?;
+                    to.assign(&owner);
+                }
+                1 => {
+                    let owner = owner_from(rest)?;
+                    let to = account(0)?;
+                    require_signer(to, Some(&owner))?;
+                    to.assign(&owner);
+                }
+                2 => {
+                    let (lamports, _) = unpack_u64(rest)?;
+                    let (from, to) = (account(0)?, account(1)?);
+                    require_signer(from, None)?;
+                    transfer(from, to, lamports)?;
+                }
+                8 => {
+                    let (space, _) = unpack_u64(rest)?;
+                    let to = account(0)?;
+                    require_signer(to, None)?;
+                    allocate(to, space)?;
+                }
+                _ => return Err(ProgramError::InvalidInstructionData),
+            }
+            Ok(())
+        }
+    }
+
+    // 所有测试线程共享同一组系统调用桩，只需安装一次
+    pub fn install_syscall_stubs() {
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            set_syscall_stubs(Box::new(TestSyscallStubs));
+        });
+    }
+
+    // 在solana-program-test的银行中执行处理程序：ProgramTest自带系统调用实现，
+    // 同一个测试进程中不要再调用install_syscall_stubs，因此这些测试放在tests/program_test.rs中单独编译。
+    // 签名钱包需要对应的Keypair，由funded_buyer和funded_seller生成并充值
+    #[cfg(feature = "program-test")]
+    pub mod program_test {
+        use super::*;
+        use solana_program_test::{processor, ProgramTest};
+        use solana_sdk::account::Account;
+        use solana_sdk::signature::{Keypair, Signer};
+
+        impl TestAccount {
+            // 转换为ProgramTest::add_account接受的账户
+            pub fn to_account(&self) -> Account {
+                Account {
+                    lamports: self.lamports,
+                    data: self.data.clone(),
+                    owner: self.owner,
+                    executable: false,
+                    rent_epoch: 0,
+                }
+            }
+        }
+
+        // 以process_instruction为内置处理程序创建ProgramTest，并预置accounts中的夹具账户
+        pub fn program_test(program_id: Pubkey, accounts: &[TestAccount]) -> ProgramTest {
+            let mut program_test = ProgramTest::new("solana_ai_marketplace", program_id, processor!(process_instruction));
+            for account in accounts {
+                program_test.add_account(account.key, account.to_account());
+            }
+            program_test
+        }
+
+        // 预置一个由系统程序拥有、余额为lamports的钱包，返回签名用的Keypair
+        fn funded_wallet(program_test: &mut ProgramTest, lamports: u64) -> Keypair {
+            let wallet = Keypair::new();
+            let mut account = TestAccount::wallet(lamports);
+            account.key = wallet.pubkey();
+            program_test.add_account(account.key, account.to_account());
+            wallet
+        }
+
+        // 支付出价和购买的买家钱包
+        pub fn funded_buyer(program_test: &mut ProgramTest, lamports: u64) -> Keypair {
+            funded_wallet(program_test, lamports)
+        }
+
+        // 上架模型并支付挂单租金的卖家钱包
+        pub fn funded_seller(program_test: &mut ProgramTest, lamports: u64) -> Keypair {
+            funded_wallet(program_test, lamports)
+        }
+
+        // 预置卖家以price在售的模型和档位0单席位的在售挂单，返回(模型地址, 挂单PDA)
+        pub fn add_listing(program_test: &mut ProgramTest, program_id: &Pubkey, seller: &Pubkey, price: u64) -> (Pubkey, Pubkey) {
+            let model = listing(program_id, seller, price);
+            let listing_key = find_listing_address(seller, &model.key, price, program_id).0;
+            let listing_state = Listing {
+                is_initialized: true,
+                status: ListingStatus::Active,
+                seller: *seller,
+                model: model.key,
+                price,
+                quantity: 1,
+                created_at: TEST_UNIX_TIMESTAMP,
+                updated_at: TEST_UNIX_TIMESTAMP,
+                ..Listing::default()
+            };
+            let listing_account = TestAccount::state(listing_key, program_id, &listing_state);
+            program_test.add_account(model.key, model.to_account());
+            program_test.add_account(listing_key, listing_account.to_account());
+            (model.key, listing_key)
+        }
+
+        // 预置买家对模型档位0单席位以price出价的出价PDA和已托管出价金额的托管PDA，返回(出价PDA, 托管PDA)
+        pub fn add_escrow(
+            program_test: &mut ProgramTest,
+            program_id: &Pubkey,
+            buyer: &Pubkey,
+            model: &Pubkey,
+            price: u64,
+        ) -> (Pubkey, Pubkey) {
+            let offer = Offer {
+                is_initialized: true,
+                buyer: *buyer,
+                model: *model,
+                price,
+                quantity: 1,
+                created_at: TEST_UNIX_TIMESTAMP,
+                updated_at: TEST_UNIX_TIMESTAMP,
+                ..Offer::default()
+            };
+            let (offer_account, escrow_account) = bid_escrow(program_id, &offer);
+            program_test.add_account(offer_account.key, offer_account.to_account());
+            program_test.add_account(escrow_account.key, escrow_account.to_account());
+            (offer_account.key, escrow_account.key)
+        }
+    }
+}
+
+// 编写测试用例
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_utils::*;
+
+    // create_ai_model的账户：待创建的模型账户、所有者钱包、系统程序、尚未初始化的配置PDA
+    fn create_ai_model_accounts(program_id: &Pubkey) -> Vec<TestAccount> {
+        install_syscall_stubs();
+        vec![
+            TestAccount::uninitialized(AIModel::LEN),
+            TestAccount::wallet(10 * LAMPORTS_PER_SOL),
+            TestAccount::readonly(system_program::id()),
+            TestAccount::readonly(find_config_address(program_id).0),
+        ]
+    }
+
+    fn create_ai_model_instruction() -> MarketplaceInstruction<'static> {
+        MarketplaceInstruction::CreateAIModel {
+            name: String::from("llama-ft"),
+            description: String::from("fine-tuned chat model"),
+            price: 5_000,
+            pricing_mode: PricingMode::Fixed,
+            license_tiers: vec![LicenseTier {
+                price: 20_000,
+                terms_uri: String::from("ar://terms"),
+                requests_per_epoch: 1_000,
+                revocable: false,
+                term_slots: 0,
+            }],
+            per_seat: true,
+            volume_discounts: vec![VolumeDiscount { min_seats: 10, discount_bps: 1_000 }],
+            vault_proceeds: false,
+            model_file: &[7; 64],
+            model_format: ModelFormat::Onnx,
+            license: String::from("Apache-2.0"),
+        }
+    }
+
+    fn run_create_ai_model(
+        program_id: &Pubkey,
+        accounts: &mut [TestAccount],
+        instruction: MarketplaceInstruction,
+    ) -> ProgramResult {
+        let MarketplaceInstruction::CreateAIModel {
+            name,
+            description,
+            price,
+            pricing_mode,
+            license_tiers,
+            per_seat,
+            volume_discounts,
+            vault_proceeds,
+            model_file,
+            model_format,
+            license,
+        } = instruction
+        else {
+            unreachable!()
+        };
+        create_ai_model(
+            program_id,
+            &account_infos(accounts),
+            name,
+            description,
+            price,
+            pricing_mode,
+            license_tiers,
+            per_seat,
+            volume_discounts,
+            vault_proceeds,
+            model_file,
+            model_format,
+            license,
+        )
+    }
+
+    #[test]
+    fn test_create_ai_model() {
+        let program_id = Pubkey::new_unique();
+        let mut accounts = create_ai_model_accounts(&program_id);
+        assert_eq!(run_create_ai_model(&program_id, &mut accounts, create_ai_model_instruction()), Ok(()));
+
+        let rent = Rent::default().minimum_balance(AIModel::LEN);
+        assert_eq!(accounts[0].owner, program_id);
+        assert_eq!(accounts[0].lamports, rent);
+        assert_eq!(accounts[1].lamports, 10 * LAMPORTS_PER_SOL - rent);
+        let model = AIModel::unpack(&accounts[0].data).unwrap();
+        assert_eq!(model.name, "llama-ft");
+        assert_eq!(model.description, "fine-tuned chat model");
+        assert_eq!(model.owner, accounts[1].key);
+        assert_eq!(model.update_authority, accounts[1].key);
+        assert!(model.creator_verified);
+        assert_eq!(model.price, 5_000);
+        assert_eq!(model.license_tiers.len(), 1);
+        assert_eq!(model.volume_discounts.len(), 1);
+        assert_eq!(model.model_format, ModelFormat::Onnx);
+        assert_eq!(model.license, "Apache-2.0");
+        assert_eq!(&model.model_file[..64], &[7; 64]);
+        assert!(model.model_file[64..].iter().all(|byte| *byte == 0));
+        assert_eq!(model.artifact_hash, solana_program::hash::hash(&[7; 64]).to_bytes());
+        assert!(!model.artifact_signed);
+        assert_eq!(model.created_at, TEST_UNIX_TIMESTAMP);
+        let view = AIModelView::new(&accounts[0].data).unwrap();
+        assert_eq!((view.owner(), view.price()), (model.owner, model.price));
+
+        // 账户已存在时系统程序拒绝再次创建
+        let mut existing = create_ai_model_accounts(&program_id);
+        existing[0].lamports = 1;
+        assert_eq!(
+            run_create_ai_model(&program_id, &mut existing, create_ai_model_instruction()),
+            Err(ProgramError::Custom(0))
+        );
+    }
+
+    #[test]
+    fn test_create_ai_model_account_errors() {
+        let program_id = Pubkey::new_unique();
+        let mut accounts = create_ai_model_accounts(&program_id);
+        assert_eq!(
+            run_create_ai_model(&program_id, &mut accounts[..3], create_ai_model_instruction()),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+
+        for signer in 0..2 {
+            let mut accounts = create_ai_model_accounts(&program_id);
+            accounts[signer].is_signer = false;
+            assert_eq!(
+                run_create_ai_model(&program_id, &mut accounts, create_ai_model_instruction()),
+                Err(ProgramError::MissingRequiredSignature)
+            );
+        }
+
+        let mut accounts = create_ai_model_accounts(&program_id);
+        accounts[2] = TestAccount::readonly(Pubkey::new_unique());
+        assert_eq!(
+            run_create_ai_model(&program_id, &mut accounts, create_ai_model_instruction()),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        let mut accounts = create_ai_model_accounts(&program_id);
+        accounts[3] = TestAccount::readonly(Pubkey::new_unique());
+        assert_eq!(
+            run_create_ai_model(&program_id, &mut accounts, create_ai_model_instruction()),
+            Err(ProgramError::InvalidSeeds)
+        );
+
+        // 已初始化的配置限制上架价格，档位价格也在检查之列
+        let config = MarketplaceConfig {
+            is_initialized: true,
+            min_listing_price: 1_000,
+            max_listing_price: 10_000,
+            ..MarketplaceConfig::default()
+        };
+        let mut accounts = create_ai_model_accounts(&program_id);
+        accounts[3] = TestAccount::state(find_config_address(&program_id).0, &program_id, &config);
+        assert_eq!(
+            run_create_ai_model(&program_id, &mut accounts, create_ai_model_instruction()),
+            Err(ProgramError::InvalidArgument)
+        );
+        let config = MarketplaceConfig { max_listing_price: 20_000, ..config };
+        let mut accounts = create_ai_model_accounts(&program_id);
+        accounts[3] = TestAccount::state(find_config_address(&program_id).0, &program_id, &config);
+        assert_eq!(run_create_ai_model(&program_id, &mut accounts, create_ai_model_instruction()), Ok(()));
+
+        let mut accounts = create_ai_model_accounts(&program_id);
+        accounts[1].lamports = 1;
+        assert_eq!(
+            run_create_ai_model(&program_id, &mut accounts, create_ai_model_instruction()),
+            Err(ProgramError::Custom(1))
+        );
+    }
+
+    #[test]
+    fn test_create_ai_model_argument_errors() {
+        let program_id = Pubkey::new_unique();
+        let edits: [fn(&mut MarketplaceInstruction<'static>); 9] = [
+            |instruction| {
+                if let MarketplaceInstruction::CreateAIModel { name, .. } = instruction {
+                    *name = "n".repeat(MAX_MODEL_NAME_LEN + 1);
+                }
+            },
+            |instruction| {
+                if let MarketplaceInstruction::CreateAIModel { description, .. } = instruction {
+                    *description = "d".repeat(MAX_MODEL_DESCRIPTION_LEN + 1);
+                }
+            },
+            |instruction| {
+                if let MarketplaceInstruction::CreateAIModel { license_tiers, .. } = instruction {
+                    license_tiers.resize(MAX_LICENSE_TIERS + 1, license_tiers[0].clone());
+                }
+            },
+            |instruction| {
+                if let MarketplaceInstruction::CreateAIModel { license_tiers, .. } = instruction {
+                    license_tiers[0].terms_uri = format!("ar://{}", "t".repeat(MAX_TERMS_URI_LEN));
+                }
+            },
+            |instruction| {
+                if let MarketplaceInstruction::CreateAIModel { license, .. } = instruction {
+                    *license = String::from("NOT A LICENSE (");
+                }
+            },
+            |instruction| {
+                if let MarketplaceInstruction::CreateAIModel { model_file, .. } = instruction {
+                    *model_file = &[0; MODEL_FILE_LEN + 1];
+                }
+            },
+            // 只有按席位售卖的模型才能设置批量折扣
+            |instruction| {
+                if let MarketplaceInstruction::CreateAIModel { per_seat, .. } = instruction {
+                    *per_seat = false;
+                }
+            },
+            |instruction| {
+                if let MarketplaceInstruction::CreateAIModel { volume_discounts, .. } = instruction {
+                    volume_discounts[0].discount_bps = BPS_DENOMINATOR as u16 + 1;
+                }
+            },
+            |instruction| {
+                if let MarketplaceInstruction::CreateAIModel { volume_discounts, .. } = instruction {
+                    volume_discounts.resize(MAX_VOLUME_DISCOUNTS + 1, volume_discounts[0]);
+                }
+            },
+        ];
+        for edit in edits {
+            let mut instruction = create_ai_model_instruction();
+            edit(&mut instruction);
+            let mut accounts = create_ai_model_accounts(&program_id);
+            assert_eq!(
+                run_create_ai_model(&program_id, &mut accounts, instruction),
+                Err(ProgramError::InvalidArgument)
+            );
+            // 参数检查在创建账户之前，失败的指令不扣所有者的钱
+            assert_eq!(accounts[1].lamports, 10 * LAMPORTS_PER_SOL);
+        }
+    }
+
+    #[test]
+    fn test_fixture_builders() {
+        install_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let mut seller = TestAccount::wallet(LAMPORTS_PER_SOL);
+        let mut buyer = TestAccount::wallet(LAMPORTS_PER_SOL);
+        let mut listing = listing(&program_id, &seller.key, 50_000);
+        assert_eq!(AIModelView::new(&listing.data).unwrap().owner(), seller.key);
+
+        // 卖家吊销可吊销的许可证，凭证租金退回出资的买家
+        let mut receipt = receipt(
+            &program_id,
+            &PurchaseReceipt {
+                is_initialized: true,
+                model: listing.key,
+                buyer: buyer.key,
+                revocable: true,
+                rent_payer: buyer.key,
+                ..PurchaseReceipt::default()
+            },
+        );
+        let receipt_rent = receipt.lamports;
+        assert_eq!(
+            revoke_license(
+                &program_id,
+                &[seller.info(), listing.info(), receipt.info(), buyer.info()],
+                [0u8; 32]
+            ),
+            Ok(())
+        );
+        assert_eq!(buyer.lamports, LAMPORTS_PER_SOL + receipt_rent);
+        assert_eq!(receipt.lamports, 0);
+
+        // 买家撤回出价，托管金额和两个账户的租金一起退回
+        let (mut offer, mut escrow) = bid_escrow(
+            &program_id,
+            &Offer { is_initialized: true, buyer: buyer.key, model: listing.key, price: 50_000, quantity: 1, ..Offer::default() },
+        );
+        let (offer_rent, escrowed) = (offer.lamports, escrow.lamports);
+        let before = buyer.lamports;
+        assert_eq!(cancel_trade(&program_id, &[buyer.info(), offer.info(), escrow.info()]), Ok(()));
+        assert_eq!(buyer.lamports, before + offer_rent + escrowed);
+        assert_eq!(escrowed, 50_000 + Rent::default().minimum_balance(Escrow::LEN));
+        assert_eq!((offer.lamports, escrow.lamports), (0, 0));
+    }
+
+    #[test]
+    fn test_commission_dispute_scenario() {
+        let program_id = Pubkey::new_unique();
+        let seller = TestAccount::wallet(LAMPORTS_PER_SOL);
+        let buyer = TestAccount::wallet(LAMPORTS_PER_SOL);
+        let arbiter = TestAccount::wallet(0);
+        let (seller_key, buyer_key) = (seller.key, buyer.key);
+        let config = MarketplaceConfig {
+            is_initialized: true,
+            commission_arbiter: arbiter.key,
+            ..MarketplaceConfig::default()
+        };
+        let listing_rent = Rent::default().minimum_balance(AIModel::LEN);
+        let commission_rent = Rent::default().minimum_balance(Commission::LEN);
+
+        // 上架 → 委托出价 → 交付并确认第一个里程碑 → 第二个里程碑争议 → 仲裁退款 → 关闭委托
+        let scenario = Scenario::new(program_id)
+            .account("seller", seller)
+            .account("buyer", buyer)
+            .account("arbiter", arbiter)
+            .account("model", TestAccount::uninitialized(AIModel::LEN))
+            .account("system_program", TestAccount::readonly(system_program::id()))
+            .account("config", TestAccount::state(find_config_address(&program_id).0, &program_id, &config))
+            .pda("commission", find_commission_address(&buyer_key, &seller_key, 7, &program_id).0, Commission::LEN)
+            .step("create listing", create_ai_model_instruction(), &["model", "seller", "system_program", "config"])
+            .step(
+                "offer",
+                MarketplaceInstruction::FundCommission {
+                    commission_id: 7,
+                    seller: seller_key,
+                    milestone_amounts: vec![300_000, 200_000],
+                },
+                &["buyer", "commission", "system_program"],
+            )
+            .step(
+                "deliver first milestone",
+                MarketplaceInstruction::SubmitMilestone { index: 0, deliverable_hash: [1; 32] },
+                &["seller", "commission"],
+            )
+            .step("accept", MarketplaceInstruction::ApproveMilestone { index: 0 }, &["buyer", "commission"])
+            .step("claim", MarketplaceInstruction::ClaimMilestone { index: 0 }, &["seller", "commission"])
+            .step(
+                "deliver second milestone",
+                MarketplaceInstruction::SubmitMilestone { index: 1, deliverable_hash: [2; 32] },
+                &["seller", "commission"],
+            )
+            .step("dispute", MarketplaceInstruction::DisputeMilestone { index: 1 }, &["buyer", "commission"])
+            .step(
+                "resolve",
+                MarketplaceInstruction::ResolveMilestoneDispute { index: 1, release: false },
+                &["arbiter", "config", "buyer", "commission"],
+            )
+            .expect_state::<AIModel>("model", |model| assert_eq!(model.owner, seller_key))
+            .expect_state::<Commission>("commission", |commission| {
+                let statuses: Vec<_> = commission.milestones.iter().map(|milestone| milestone.status).collect();
+                assert_eq!(statuses, [MilestoneStatus::Released, MilestoneStatus::Refunded]);
+            })
+            .expect_lamports("commission", commission_rent)
+            .expect_lamports("seller", LAMPORTS_PER_SOL - listing_rent + 300_000)
+            .expect_lamports("buyer", LAMPORTS_PER_SOL - commission_rent - 300_000);
+
+        // 全部结清后买家关闭委托取回租金，关闭后的委托不能再次关闭
+        let mut scenario = scenario
+            .step("close", MarketplaceInstruction::CloseCommission, &["buyer", "commission"])
+            .expect_lamports("commission", 0)
+            .expect_lamports("buyer", LAMPORTS_PER_SOL - 300_000);
+        assert_eq!(
+            scenario.try_step(&MarketplaceInstruction::CloseCommission, &["buyer", "commission"]),
+            Err(ProgramError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn test_listing_sale_dispute_scenario() {
+        let program_id = Pubkey::new_unique();
+        let seller = TestAccount::wallet(LAMPORTS_PER_SOL);
+        let buyer = TestAccount::wallet(LAMPORTS_PER_SOL);
+        let model = TestAccount::uninitialized(AIModel::LEN);
+        let (seller_key, buyer_key, model_key) = (seller.key, buyer.key, model.key);
+        let treasury_key = find_treasury_address(&program_id).0;
+        let receipt_key = find_receipt_address(&model_key, &buyer_key, 0, &program_id).0;
+        let rent = |space| Rent::default().minimum_balance(space);
+        // 档位0单席位的标价，成交按默认费率收取手续费
+        let price = 20_000;
+        let fee = fee_at_bps(price, DEFAULT_FEE_BPS);
+
+        // 上架模型 → 草稿挂单 → 买家出价 → 卖家发布挂单接受出价 → 撮合成交
+        let offer_key = find_offer_address(&buyer_key, &model_key, price, &program_id).0;
+        let sale_accounts = [
+            "buyer", "buyer", "offer", "escrow", "listing", "model", "seller", "receipt", "seller_stats", "system_program",
+            "proceeds_vault", "sales_ledger", "treasury", "seller_bond", "buyer_stats", "buyer_receipts",
+        ];
+        let mut scenario = Scenario::new(program_id)
+            .account("seller", seller)
+            .account("buyer", buyer)
+            .account("stranger", TestAccount::wallet(0))
+            .account("model", model)
+            .account("system_program", TestAccount::readonly(system_program::id()))
+            .account("config", TestAccount::readonly(find_config_address(&program_id).0))
+            .account(
+                "treasury",
+                TestAccount::state(treasury_key, &program_id, &Treasury { is_initialized: true, ..Treasury::default() }),
+            )
+            .account("proceeds_vault", TestAccount::readonly(find_proceeds_vault_address(&seller_key, &program_id).0))
+            .account("seller_bond", TestAccount::readonly(find_seller_bond_address(&seller_key, &program_id).0))
+            .pda("listing", find_listing_address(&seller_key, &model_key, price, &program_id).0, Listing::LEN)
+            .pda("offer", offer_key, Offer::LEN)
+            .pda("escrow", find_escrow_address(&offer_key, &program_id).0, Escrow::LEN)
+            .pda("receipt", receipt_key, PurchaseReceipt::LEN)
+            .pda("seller_stats", find_seller_stats_address(&seller_key, &program_id).0, SellerStats::LEN)
+            .pda("sales_ledger", find_sales_ledger_address(&model_key, 0, &program_id).0, SalesLedgerPage::LEN)
+            .pda("buyer_stats", find_buyer_stats_address(&buyer_key, &program_id).0, BuyerStats::LEN)
+            .pda("buyer_receipts", find_buyer_receipts_address(&buyer_key, 0, &program_id).0, BuyerReceiptsPage::LEN)
+            .pda("dispute", find_dispute_address(&receipt_key, &program_id).0, Dispute::LEN)
+            .step("create model", create_ai_model_instruction(), &["model", "seller", "system_program", "config"])
+            .step(
+                "draft listing",
+                MarketplaceInstruction::Sell { price, tier_index: 0, quantity: 1, draft: true },
+                &["seller", "model", "listing", "system_program"],
+            )
+            .step(
+                "offer",
+                MarketplaceInstruction::Buy { price, tier_index: 0, quantity: 1 },
+                &["buyer", "model", "offer", "escrow", "system_program"],
+            )
+            .expect_lamports("escrow", rent(Escrow::LEN) + price);
+        // 草稿挂单不能成交
+        assert_eq!(
+            scenario.try_step(&MarketplaceInstruction::ExecuteSale { purchase_index: 0 }, &sale_accounts),
+            Err(ProgramError::InvalidArgument)
+        );
+        let scenario = scenario
+            .step(
+                "accept",
+                MarketplaceInstruction::SetListingStatus { status: ListingStatus::Active as u8 },
+                &["seller", "model", "listing"],
+            )
+            .step("settle", MarketplaceInstruction::ExecuteSale { purchase_index: 0 }, &sale_accounts)
+            .expect_lamports("escrow", rent(Escrow::LEN))
+            .expect_lamports("treasury", rent(Treasury::LEN) + fee)
+            .expect_lamports("seller", LAMPORTS_PER_SOL - rent(AIModel::LEN) - rent(Listing::LEN) + price - fee)
+            .expect_state::<Listing>("listing", |listing| {
+                assert_eq!((listing.status, listing.fills), (ListingStatus::Active, 1));
+            })
+            .expect_state::<Offer>("offer", |offer| {
+                assert_eq!((offer.status, offer.receipt), (OfferStatus::Filled, receipt_key));
+            })
+            .expect_state::<Escrow>("escrow", |escrow| {
+                assert_eq!((escrow.status, escrow.released, escrow.escrowed_amount()), (EscrowStatus::Released, price, 0));
+            })
+            .expect_state::<PurchaseReceipt>("receipt", |receipt| {
+                assert_eq!((receipt.model, receipt.buyer), (model_key, buyer_key));
+                assert_eq!((receipt.amount_paid, receipt.fee_paid, receipt.seat_count), (price, fee, 1));
+            })
+            .expect_state::<BuyerReceiptsPage>("buyer_receipts", |page| assert_eq!(page.receipts, [receipt_key]))
+            .expect_state::<SalesLedgerPage>("sales_ledger", |ledger| assert_eq!(ledger.entries.len(), 1))
+            .expect_state::<AIModel>("model", |model| assert_eq!(model.owner, seller_key));
+
+        // 买家发起争议 → 卖家答辩 → 买家补充证据；购买争议由链下仲裁方依据证据裁决
+        let mut scenario = scenario
+            .step(
+                "dispute",
+                MarketplaceInstruction::OpenDispute { evidence_hash: [1; 32] },
+                &["buyer", "receipt", "model", "dispute", "system_program"],
+            )
+            .step(
+                "seller response",
+                MarketplaceInstruction::AttachDisputeEvidence { evidence_hash: [2; 32] },
+                &["seller", "dispute"],
+            )
+            .step(
+                "buyer rebuttal",
+                MarketplaceInstruction::AttachDisputeEvidence { evidence_hash: [3; 32] },
+                &["buyer", "dispute"],
+            )
+            .expect_state::<Dispute>("dispute", |dispute| {
+                assert_eq!((dispute.receipt, dispute.buyer, dispute.seller), (receipt_key, buyer_key, seller_key));
+                let evidence: Vec<_> = dispute.evidence.iter().map(|entry| (entry.submitter, entry.content_hash)).collect();
+                assert_eq!(evidence, [(buyer_key, [1; 32]), (seller_key, [2; 32]), (buyer_key, [3; 32])]);
+            })
+            .step("close filled offer", MarketplaceInstruction::CancelTrade, &["buyer", "offer", "escrow"])
+            .expect_lamports("offer", 0)
+            .expect_lamports("escrow", 0)
+            .expect_lamports(
+                "buyer",
+                LAMPORTS_PER_SOL
+                    - price
+                    - rent(PurchaseReceipt::LEN)
+                    - rent(SellerStats::LEN)
+                    - rent(SalesLedgerPage::LEN)
+                    - rent(BuyerStats::LEN)
+                    - rent(BuyerReceiptsPage::LEN)
+                    - rent(Dispute::LEN),
+            );
+
+        // 争议之外的第三方不能提交证据，同一笔购买也不能重复发起争议
+        assert_eq!(
+            scenario.try_step(&MarketplaceInstruction::AttachDisputeEvidence { evidence_hash: [4; 32] }, &["stranger", "dispute"]),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            scenario.try_step(
+                &MarketplaceInstruction::OpenDispute { evidence_hash: [5; 32] },
+                &["buyer", "receipt", "model", "dispute", "system_program"],
+            ),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+    }
+
+    #[test]
+    fn test_trade_account_lifecycles() {
+        use ListingStatus::*;
+        let statuses = [Draft, Active, Paused, Delisted];
+        let allowed = [(Draft, Active), (Active, Paused), (Paused, Active), (Draft, Delisted), (Active, Delisted), (Paused, Delisted)];
+        for from in statuses {
+            for to in statuses {
+                let mut listing = Listing { is_initialized: true, status: from, ..Listing::default() };
+                let expected = if allowed.contains(&(from, to)) { Ok(()) } else { Err(ProgramError::InvalidArgument) };
+                assert_eq!(listing.transition(to, 5), expected, "{:?} -> {:?}", from, to);
+                assert_eq!(listing.status, if expected.is_ok() { to } else { from });
+            }
+        }
+        assert_eq!(ListingStatus::from_u8(4), Err(ProgramError::InvalidAccountData));
+
+        // 只有在售的挂单记录成交，成交后保持在售
+        let mut listing = Listing { is_initialized: true, status: Paused, ..Listing::default() };
+        assert_eq!(listing.record_fill(7), Err(ProgramError::InvalidArgument));
+        listing.status = Active;
+        assert_eq!(listing.record_fill(7), Ok(()));
+        assert_eq!((listing.status, listing.fills, listing.updated_at), (Active, 1, 7));
+
+        // 出价只能成交一次，托管只能付出一次且不超过存入的金额
+        let receipt = Pubkey::new_unique();
+        let mut offer = Offer { is_initialized: true, price: 1_000, ..Offer::default() };
+        assert_eq!(offer.fill(receipt, 7), Ok(()));
+        assert_eq!((offer.status, offer.receipt), (OfferStatus::Filled, receipt));
+        assert_eq!(offer.fill(receipt, 8), Err(ProgramError::InvalidArgument));
+        let mut escrow = Escrow { is_initialized: true, amount: 1_000, ..Escrow::default() };
+        assert_eq!(escrow.release(1_001, 7), Err(ProgramError::InsufficientFunds));
+        assert_eq!(escrow.release(800, 7), Ok(()));
+        assert_eq!((escrow.status, escrow.escrowed_amount()), (EscrowStatus::Released, 200));
+        assert_eq!(escrow.release(100, 8), Err(ProgramError::InvalidArgument));
+
+        // 期限许可证的凭证到期后失效
+        let receipt = PurchaseReceipt { expires_at_slot: 1_500, ..PurchaseReceipt::default() };
+        assert_eq!(receipt.status(1_499), ReceiptStatus::Active);
+        assert_eq!(receipt.status(1_500), ReceiptStatus::Expired);
+    }
+
+    #[test]
+    fn test_pay_what_you_want_floor() {
+        let fixed = AIModel {
+            price: 100,
+            ..AIModel::default()
+        };
+        assert_eq!(fixed.sale_price(0, 1, 5), Ok(100));
+
+        let ai_model = AIModel {
+            price: 100,
+            pricing_mode: PricingMode::PayWhatYouWant,
+            ..AIModel::default()
+        };
+        assert_eq!(ai_model.sale_price(0, 1, 99), Err(ProgramError::InvalidArgument));
+        assert_eq!(ai_model.sale_price(0, 1, 100), Ok(100));
+        assert_eq!(ai_model.sale_price(0, 1, 250), Ok(250));
+    }
+
+    #[test]
+    fn test_license_tier_price() {
+        let tier = |price| LicenseTier {
+            price,
+            terms_uri: String::from("https://example.com/terms"),
+            requests_per_epoch: price * 10,
+            revocable: price >= 10_000,
+            term_slots: 0,
+        };
+        let ai_model = AIModel {
+            price: 1,
+            license_tiers: vec![tier(100), tier(1_000), tier(10_000)],
+            ..AIModel::default()
+        };
+        assert_eq!(ai_model.sale_price(0, 1, 0), Ok(100));
+        assert_eq!(ai_model.sale_price(2, 1, 0), Ok(10_000));
+        assert_eq!(ai_model.sale_price(3, 1, 0), Err(ProgramError::InvalidArgument));
+        assert_eq!(AIModel::default().tier_price(1), Err(ProgramError::InvalidArgument));
+
+        let mut packed = [0u8; LicenseTier::LEN];
+        tier(100).pack_into_slice(&mut packed);
+        assert_eq!(LicenseTier::unpack_from_slice(&packed), Ok(tier(100)));
+    }
+
+    #[test]
+    fn test_instruction_pack_roundtrip() {
+        let create = MarketplaceInstruction::CreateAIModel {
+            name: String::from("model"),
+            description: String::from("description"),
+            price: 42,
+            pricing_mode: PricingMode::PayWhatYouWant,
+            license_tiers: vec![LicenseTier {
+                price: 7,
+                terms_uri: String::from("ar://terms"),
+                requests_per_epoch: 10_000,
+                revocable: true,
+                term_slots: 216_000,
+            }],
+            per_seat: true,
+            volume_discounts: vec![VolumeDiscount { min_seats: 10, discount_bps: 1_500 }],
+            vault_proceeds: true,
+            model_file: &[1, 2, 3],
+            model_format: ModelFormat::Gguf,
+            license: String::from("Apache-2.0 OR MIT"),
+        };
+        let packed = create.pack();
+        let unpacked = MarketplaceInstruction::unpack(&packed);
+        // 模型文件借用指令数据本身，没有复制
+        if let Ok(MarketplaceInstruction::CreateAIModel { model_file, .. }) = &unpacked {
+            assert!(packed.as_ptr_range().contains(&model_file.as_ptr()));
+        }
+        assert_eq!(unpacked, Ok(create));
+
+        let purchase = MarketplaceInstruction::PurchaseAIModel {
+            amount: 1_000,
+            tier_index: 1,
+            quantity: 12,
+            purchase_index: 3,
+            acknowledge_deprecation: true,
+            reference: [7u8; 32],
+        };
+        assert_eq!(MarketplaceInstruction::unpack(&purchase.pack()), Ok(purchase));
+        assert_eq!(
+            MarketplaceInstruction::unpack(&MarketplaceInstruction::VerifyLicense.pack()),
+            Ok(MarketplaceInstruction::VerifyLicense)
+        );
+
+        let withdraw = MarketplaceInstruction::WithdrawProceeds { amount: 500 };
+        assert_eq!(MarketplaceInstruction::unpack(&withdraw.pack()), Ok(withdraw));
+        for instruction in [
+            MarketplaceInstruction::InitializeConfig,
+            MarketplaceInstruction::GetVersion,
+            MarketplaceInstruction::SetFeatureFlags { feature_flags: FEATURE_AUCTIONS | FEATURE_RESALE },
+            MarketplaceInstruction::SetRecoveryGuardians {
+                guardians: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+                threshold: 2,
+                recovery_delay: MIN_RECOVERY_DELAY_SECONDS,
+            },
+            MarketplaceInstruction::ProposeRecovery {
+                amount: 10,
+                destination: Pubkey::new_unique(),
+                evidence_hash: [7; 32],
+            },
+            MarketplaceInstruction::ExecuteRecovery,
+            MarketplaceInstruction::CancelRecovery,
+            MarketplaceInstruction::InitializeTreasury { withdrawal_delay: MIN_WITHDRAWAL_DELAY_SECONDS },
+            MarketplaceInstruction::QueueWithdrawal {
+                amount: 500,
+                destination: Pubkey::new_unique(),
+            },
+            MarketplaceInstruction::ExecuteWithdrawal,
+            MarketplaceInstruction::DepositBond { amount: 42 },
+            MarketplaceInstruction::WithdrawBond { amount: 7 },
+            MarketplaceInstruction::Sell {
+                price: 1_000,
+                tier_index: 1,
+                quantity: 2,
+                draft: true,
+            },
+            MarketplaceInstruction::Buy {
+                price: 1_000,
+                tier_index: 1,
+                quantity: 2,
+            },
+            MarketplaceInstruction::CancelTrade,
+            MarketplaceInstruction::ExecuteSale { purchase_index: 0 },
+            MarketplaceInstruction::AttachTokenMetadata,
+            MarketplaceInstruction::SubmitReview {
+                rating: 5,
+                content_hash: [3; 32],
+            },
+            MarketplaceInstruction::InitializeBadgeMint { kind: 1 },
+            MarketplaceInstruction::ClaimBadge { kind: 0 },
+            MarketplaceInstruction::OpenDispute { evidence_hash: [1; 32] },
+            MarketplaceInstruction::AttachDisputeEvidence { evidence_hash: [2; 32] },
+            MarketplaceInstruction::SetModelArtifact {
+                storage_class: StorageClass::Arweave as u8,
+                artifact_uri: "bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U".to_string(),
+                artifact_hash: [4; 32],
+                artifact_size_bytes: 7_000_000_000,
+            },
+            MarketplaceInstruction::SetArtifactAttestor { attestor: Pubkey::new_unique() },
+            MarketplaceInstruction::AttestArtifact { artifact_hash: [4; 32] },
+            MarketplaceInstruction::PublishModelVersion {
+                version: SemVer { major: 1, minor: 2, patch: 3 },
+                artifact_hash: [5; 32],
+            },
+            MarketplaceInstruction::AddChangelogEntry {
+                content_hash: [6; 32],
+                uri: String::from("ar://changelog"),
+                summary: String::from("Quantized to int8"),
+            },
+            MarketplaceInstruction::DeprecateModel,
+            MarketplaceInstruction::FollowSeller,
+            MarketplaceInstruction::UnfollowSeller,
+            MarketplaceInstruction::FavoriteModel,
+            MarketplaceInstruction::UnfavoriteModel,
+            MarketplaceInstruction::UpdatePrice { price: 1_500 },
+            MarketplaceInstruction::SetFeaturedList {
+                list_id: 1,
+                label: String::from("Top LLMs"),
+            },
+            MarketplaceInstruction::RegisterCategory {
+                id: 2,
+                parent: 1,
+                label: String::from("Vision"),
+            },
+            MarketplaceInstruction::SetModelCategory { category_id: 2 },
+            MarketplaceInstruction::SetLocalizedMetadata {
+                lang: String::from("zh-Hans"),
+                name: String::from("图像分类模型"),
+                description_uri: String::from("ar://description-zh"),
+            },
+            MarketplaceInstruction::SetMetadataUri {
+                metadata_uri: String::from("ar://metadata"),
+                metadata_hash: [8; 32],
+            },
+            MarketplaceInstruction::SetUpdateAuthority { update_authority: Pubkey::new_unique() },
+            MarketplaceInstruction::SetModelOwner { new_owner: Pubkey::new_unique() },
+            MarketplaceInstruction::SetModelCreator { creator: Pubkey::new_unique() },
+            MarketplaceInstruction::VerifyCreator,
+            MarketplaceInstruction::SetListingOracle { oracle: Pubkey::new_unique() },
+            MarketplaceInstruction::RequireListingApproval,
+            MarketplaceInstruction::ApproveListing {
+                artifact_hash: [9; 32],
+                attestation_hash: [10; 32],
+            },
+            MarketplaceInstruction::RegisterProvenanceVerifier {
+                verifier_id: 1,
+                proof_system: 0,
+                claim: String::from("trained on licensed data"),
+                verifying_key: vec![11; GROTH16_VK_FIXED_LEN + 64],
+            },
+            MarketplaceInstruction::VerifyProvenance,
+            MarketplaceInstruction::CommitInferenceResult {
+                input_hash: [12; 32],
+                output_hash: [13; 32],
+            },
+            MarketplaceInstruction::EnqueueInferenceRequest {
+                request_id: 7,
+                prompt_hash: [14; 32],
+                max_price: 5_000,
+                deadline: 1_700_000_000,
+                challenge_window: 3_600,
+            },
+            MarketplaceInstruction::SubmitResult {
+                output_hash: [13; 32],
+                proof_uri: String::from("ar://proof"),
+                price: 4_000,
+            },
+            MarketplaceInstruction::CancelInferenceRequest,
+            MarketplaceInstruction::RegisterWorker {
+                hardware_class: 2,
+                supported_formats: ModelFormat::Gguf.bit() | ModelFormat::Onnx.bit(),
+                price_per_unit: 100,
+                endpoint_hash: [15; 32],
+            },
+            MarketplaceInstruction::StakeWorker { amount: LAMPORTS_PER_SOL },
+            MarketplaceInstruction::UnstakeWorker { amount: 1 },
+            MarketplaceInstruction::SetInferenceArbiter { arbiter: Pubkey::new_unique() },
+            MarketplaceInstruction::SlashWorker,
+            MarketplaceInstruction::ClaimJob,
+            MarketplaceInstruction::ReassignExpiredClaim,
+            MarketplaceInstruction::ChallengeResult,
+            MarketplaceInstruction::SettleInferenceRequest,
+            MarketplaceInstruction::UpholdResult,
+            MarketplaceInstruction::SetRentalRate { rate_per_slot: 25 },
+            MarketplaceInstruction::OpenRentalStream { deposit: 10_000 },
+            MarketplaceInstruction::WithdrawRentalStream,
+            MarketplaceInstruction::CloseRentalStream,
+            MarketplaceInstruction::FundCommission {
+                commission_id: 3,
+                seller: Pubkey::new_unique(),
+                milestone_amounts: vec![1_000, 2_500],
+            },
+            MarketplaceInstruction::SubmitMilestone {
+                index: 1,
+                deliverable_hash: [14; 32],
+            },
+            MarketplaceInstruction::ApproveMilestone { index: 1 },
+            MarketplaceInstruction::ClaimMilestone { index: 1 },
+            MarketplaceInstruction::DisputeMilestone { index: 0 },
+            MarketplaceInstruction::SetCommissionArbiter { arbiter: Pubkey::new_unique() },
+            MarketplaceInstruction::ResolveMilestoneDispute { index: 0, release: true },
+            MarketplaceInstruction::CloseCommission,
+            MarketplaceInstruction::SetSubscriptionTiers {
+                period_slots: 216_000,
+                tiers: vec![
+                    SubscriptionTier { price: 1_000, access_scopes: ACCESS_SCOPE_INFERENCE },
+                    SubscriptionTier {
+                        price: 5_000,
+                        access_scopes: ACCESS_SCOPE_INFERENCE | ACCESS_SCOPE_DOWNLOAD,
+                    },
+                ],
+                grace_period_slots: 21_600,
+            },
+            MarketplaceInstruction::Subscribe { tier: 1 },
+            MarketplaceInstruction::ChangeTier { tier: 0 },
+            MarketplaceInstruction::VerifySubscription { access_scopes: ACCESS_SCOPE_DOWNLOAD },
+            MarketplaceInstruction::FundSubscriptionRenewal { amount: 10_000 },
+            MarketplaceInstruction::RenewSubscription,
+            MarketplaceInstruction::LapseSubscription,
+            MarketplaceInstruction::CancelSubscription,
+            MarketplaceInstruction::CreateOrganization {
+                org_id: 1,
+                name: String::from("Acme AI"),
+            },
+            MarketplaceInstruction::SetOrganizationMember {
+                member: Pubkey::new_unique(),
+                role: OrganizationRole::Editor as u8,
+            },
+            MarketplaceInstruction::RemoveOrganizationMember { member: Pubkey::new_unique() },
+            MarketplaceInstruction::WithdrawOrganizationFunds { amount: 1_000 },
+            MarketplaceInstruction::GrantRole { role: 1, holder: Pubkey::new_unique() },
+            MarketplaceInstruction::RevokeRole { role: 4, holder: Pubkey::new_unique() },
+            MarketplaceInstruction::BindApiKey { api_key_hash: [21u8; 32] },
+            MarketplaceInstruction::SetMeteringAuthority { authority: Pubkey::new_unique() },
+            MarketplaceInstruction::RecordUsage { requests: 250 },
+            MarketplaceInstruction::CancelOrderNonces { min_nonce: 1_024 },
+            MarketplaceInstruction::SettleSignedOrder {
+                order: SignedOrder {
+                    price: 5_000,
+                    tier: 1,
+                    quantity: 3,
+                    expires_at: 1_700_086_400,
+                    nonce: 42,
+                },
+                fill_quantity: 2,
+                purchase_index: 2,
+                acknowledge_deprecation: true,
+            },
+            MarketplaceInstruction::PurchaseWithPoints {
+                amount: 0,
+                tier_index: 0,
+                quantity: 1,
+                purchase_index: 0,
+                acknowledge_deprecation: false,
+                points: 400,
+            },
+            MarketplaceInstruction::SetLoyaltyRates { accrual_bps: 100, redemption_rate: 1_000 },
+            MarketplaceInstruction::InitializeAchievementMint { achievement: 2 },
+            MarketplaceInstruction::ClaimAchievement { achievement: 1, mint_badge: true },
+            MarketplaceInstruction::CreateAirdropCampaign {
+                campaign_id: 7,
+                tier_index: 1,
+                max_recipients: 500,
+                merkle_root: [8u8; 32],
+            },
+            MarketplaceInstruction::AirdropLicenses,
+            MarketplaceInstruction::CloseAirdropCampaign,
+            MarketplaceInstruction::ClaimAirdrop { proof: vec![[1u8; 32], [2u8; 32]] },
+            MarketplaceInstruction::SetPresale { merkle_root: [5u8; 32], presale_price: 700, public_sale_slot: 9_000 },
+            MarketplaceInstruction::PurchasePresale {
+                tier_index: 0,
+                quantity: 2,
+                purchase_index: 1,
+                acknowledge_deprecation: false,
+                allocation: 5,
+                proof: vec![[3u8; 32]],
+            },
+            MarketplaceInstruction::AnnounceListing { kind: 1, nonce: 42 },
+            MarketplaceInstruction::UpdatePriceFeed { feed_id: 1, price: 6_500_000, confidence: 3_000 },
+            MarketplaceInstruction::SetOraclePricing {
+                price_feed: Pubkey::new_unique(),
+                quote_price: 25_000_000,
+                max_staleness_slots: 150,
+                max_confidence_bps: 200,
+                fallback_price: 200_000_000,
+            },
+            MarketplaceInstruction::PurchaseAtOraclePrice {
+                max_amount: 170_000_000,
+                tier_index: 0,
+                quantity: 1,
+                purchase_index: 0,
+                acknowledge_deprecation: true,
+            },
+            MarketplaceInstruction::SetPriceBounds { min_price: 1_000, max_price: 10_000 * LAMPORTS_PER_SOL },
+            MarketplaceInstruction::SetDropThrottle { cooldown_slots: 150, max_per_slot: 3 },
+            MarketplaceInstruction::SetVerificationRequirement { gatekeeper_network: Pubkey::new_unique() },
+            MarketplaceInstruction::SetFeeSchedule { primary_fee_bps: 250, secondary_fee_bps: 500 },
+            MarketplaceInstruction::RegisterCharity { wallet: Pubkey::new_unique(), active: true },
+            MarketplaceInstruction::SetListingCharity { charity: Pubkey::new_unique(), charity_bps: 500 },
+            MarketplaceInstruction::SetBuyerCharity { charity: Pubkey::new_unique(), charity_bps: 100 },
+            MarketplaceInstruction::WithdrawCharityDonations { amount: 40_000 },
+            MarketplaceInstruction::SetWithholding { destination: Pubkey::new_unique(), withholding_bps: 2_400 },
+            MarketplaceInstruction::ReleaseWithheldProceeds,
+            MarketplaceInstruction::CancelWithdrawal,
+            MarketplaceInstruction::SetListingStatus { status: 2 },
+            MarketplaceInstruction::SetMaxSupply { max_supply: 100 },
+            MarketplaceInstruction::SetOpenEditionEnd { end_slot: 250_000_000 },
+            MarketplaceInstruction::FinalizeOpenEdition,
+            MarketplaceInstruction::SetUpgradeDiscount { discount_bps: 4_000 },
+            MarketplaceInstruction::UpgradeLicense { tier_index: 1, quantity: 5, purchase_index: 0 },
+            MarketplaceInstruction::SetLicenseTransferability { transferability: 1, royalty_bps: 750 },
+            MarketplaceInstruction::TransferLicense { price: 12_000, purchase_index: 1 },
+            MarketplaceInstruction::RevokeLicense { reason_hash: [6; 32] },
+            MarketplaceInstruction::RenewLicense,
+            MarketplaceInstruction::SetUsageUnitPrice { unit_price: 250 },
+            MarketplaceInstruction::TopUpCredits { mint: Pubkey::new_unique(), amount: 5_000_000 },
+            MarketplaceInstruction::SettleUsage,
+            MarketplaceInstruction::BurnCredits { amount: 1_500 },
+            MarketplaceInstruction::ConfigureCreditMint,
+            MarketplaceInstruction::BuyCredits { amount: 25_000_000 },
+            MarketplaceInstruction::PurchaseWithCredits {
+                amount: 0,
+                tier_index: 1,
+                quantity: 3,
+                purchase_index: 2,
+                acknowledge_deprecation: true,
+            },
+        ] {
+            assert_eq!(MarketplaceInstruction::unpack(&instruction.pack()), Ok(instruction));
+        }
+    }
+
+    #[test]
+    fn test_sales_ledger_page_pack() {
+        let record = |amount| SaleRecord {
+            buyer: Pubkey::new_unique(),
+            amount,
+            slot: 42,
+            payment_mint: NATIVE_PAYMENT_MINT,
+        };
+        let page = SalesLedgerPage {
+            is_initialized: true,
+            model: Pubkey::new_unique(),
+            page_index: 3,
+            created_at: 1_700_000_000,
+            updated_at: 1_700_000_100,
+            entries: vec![record(1), record(2)],
+        };
+        let mut packed = vec![0u8; SalesLedgerPage::LEN];
+        page.pack_into_slice(&mut packed);
+        assert_eq!(SalesLedgerPage::unpack(&packed), Ok(page));
+
+        let mut ai_model = AIModel {
+            total_sales: SALES_LEDGER_PAGE_CAPACITY as u64 * 2 - 1,
+            ..AIModel::default()
+        };
+        assert_eq!(ai_model.current_ledger_page(), 1);
+        ai_model.record_sale(500, 77).unwrap();
+        assert_eq!(ai_model.current_ledger_page(), 2);
+        assert_eq!(ai_model.total_volume, 500);
+        assert_eq!(ai_model.last_sold_slot, 77);
+    }
+
+    #[test]
+    fn test_supply_cap_editions() {
+        let mut ai_model = AIModel::default();
+        // 不限量时版次持续递增
+        assert_eq!(ai_model.mint_edition(10), Ok(1));
+        assert_eq!(ai_model.mint_edition(11), Ok(2));
+
+        assert_eq!(ai_model.set_max_supply(1), Err(ProgramError::InvalidArgument));
+        assert_eq!(ai_model.set_max_supply(4), Ok(()));
+        assert_eq!(ai_model.set_max_supply(5), Err(ProgramError::InvalidArgument));
+        assert_eq!(ai_model.set_max_supply(0), Err(ProgramError::InvalidArgument));
+        assert_eq!(ai_model.set_max_supply(3), Ok(()));
+        assert_eq!(ai_model.mint_edition(12), Ok(3));
+        assert_eq!(ai_model.mint_edition(13), Err(ProgramError::InvalidArgument));
+        assert_eq!(ai_model.editions_minted, 3);
+        // 限量的模型不能再改为开放版次
+        assert_eq!(ai_model.set_open_edition_end(100, 13), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_license_transfer_royalty() {
+        let mut ai_model = AIModel { resale_royalty_bps: 1_000, ..AIModel::default() };
+        // 默认仅限本人使用
+        assert_eq!(ai_model.transfer_royalty(5_000), Err(ProgramError::InvalidArgument));
+        ai_model.license_transferability = LicenseTransferability::TransferableWithRoyalty;
+        assert_eq!(ai_model.transfer_royalty(5_000), Ok(500));
+        assert_eq!(ai_model.transfer_royalty(0), Ok(0));
+        ai_model.license_transferability = LicenseTransferability::FreelyTransferable;
+        assert_eq!(ai_model.transfer_royalty(5_000), Ok(0));
+        assert_eq!(LicenseTransferability::from_u8(3), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_upgrade_price() {
+        let mut ai_model = AIModel {
+            price: 1_000,
+            per_seat: true,
+            ..AIModel::default()
+        };
+        assert_eq!(ai_model.upgrade_price(0, 3), Ok(3_000));
+        ai_model.upgrade_discount_bps = 2_500;
+        assert_eq!(ai_model.upgrade_price(0, 3), Ok(2_250));
+        ai_model.upgrade_discount_bps = 10_000;
+        assert_eq!(ai_model.upgrade_price(0, 1), Ok(0));
+        assert_eq!(ai_model.upgrade_price(1, 1), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_open_edition_window() {
+        let mut ai_model = AIModel::default();
+        assert_eq!(ai_model.set_open_edition_end(10, 10), Err(ProgramError::InvalidArgument));
+        assert_eq!(ai_model.set_open_edition_end(100, 10), Ok(()));
+        // 截止slot只能提前
+        assert_eq!(ai_model.set_open_edition_end(200, 20), Err(ProgramError::InvalidArgument));
+        assert_eq!(ai_model.set_open_edition_end(80, 20), Ok(()));
+        assert_eq!(ai_model.set_max_supply(5), Err(ProgramError::InvalidArgument));
+
+        assert_eq!(ai_model.mint_edition(50), Ok(1));
+        assert_eq!(ai_model.finalize_editions(79), Err(ProgramError::InvalidArgument));
+        assert_eq!(ai_model.mint_edition(79), Ok(2));
+        assert_eq!(ai_model.mint_edition(80), Err(ProgramError::InvalidArgument));
+        assert_eq!(ai_model.set_open_edition_end(90, 85), Err(ProgramError::InvalidArgument));
+
+        assert_eq!(ai_model.finalize_editions(80), Ok(()));
+        assert_eq!(ai_model.finalize_editions(81), Err(ProgramError::InvalidArgument));
+        assert_eq!(ai_model.editions_minted, 2);
+    }
+
+    #[test]
+    fn test_upgrade_authority_from_program_data() {
+        let authority = Pubkey::new_unique();
+        let mut data = vec![0u8; 45];
+        data[0..4].copy_from_slice(&3u32.to_le_bytes());
+        data[12] = 1;
+        data[13..45].copy_from_slice(authority.as_ref());
+        assert_eq!(upgrade_authority_from_program_data(&data), Ok(Some(authority)));
+
+        data[12] = 0;
+        assert_eq!(upgrade_authority_from_program_data(&data), Ok(None));
+
+        data[0..4].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(
+            upgrade_authority_from_program_data(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_require_feature() {
+        let config = MarketplaceConfig {
+            feature_flags: FEATURE_SUBSCRIPTIONS,
+            ..MarketplaceConfig::default()
+        };
+        assert_eq!(config.require_feature(FEATURE_SUBSCRIPTIONS), Ok(()));
+        assert_eq!(config.require_feature(FEATURE_AUCTIONS), Err(ProgramError::InvalidArgument));
+        assert_eq!(
+            config.require_feature(FEATURE_SUBSCRIPTIONS | FEATURE_RESALE),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_config_pack_with_guardians() {
+        let config = MarketplaceConfig {
+            is_initialized: true,
+            admin: Pubkey::new_unique(),
+            program_version: PROGRAM_VERSION,
+            capabilities: PROGRAM_CAPABILITIES,
+            guardians: vec![Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()],
+            guardian_threshold: 2,
+            recovery_delay: MIN_RECOVERY_DELAY_SECONDS,
+            credit_reserve_mint: Pubkey::new_unique(),
+            min_listing_price: 1_000,
+            max_listing_price: 10_000 * LAMPORTS_PER_SOL,
+            ..MarketplaceConfig::default()
+        };
+        let mut packed = vec![0u8; MarketplaceConfig::LEN];
+        config.pack_into_slice(&mut packed);
+        assert_eq!(MarketplaceConfig::unpack(&packed), Ok(config));
+    }
+
+    #[test]
+    fn test_listing_price_bounds() {
+        let unbounded = MarketplaceConfig::default();
+        assert_eq!(unbounded.check_listing_price(0), Ok(()));
+        assert_eq!(unbounded.check_listing_price(u64::MAX), Ok(()));
+
+        let config = MarketplaceConfig {
+            min_listing_price: 1_000,
+            max_listing_price: 10_000 * LAMPORTS_PER_SOL,
+            ..MarketplaceConfig::default()
+        };
+        assert_eq!(config.check_listing_price(1), Err(ProgramError::InvalidArgument));
+        assert_eq!(config.check_listing_price(1_000), Ok(()));
+        assert_eq!(config.check_listing_price(10_000 * LAMPORTS_PER_SOL), Ok(()));
+        assert_eq!(config.check_listing_price(10_000_000 * LAMPORTS_PER_SOL), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_fee_schedule() {
+        // 未设置首次销售费率时沿用按保证金的默认费率
+        let treasury = Treasury::default();
+        assert_eq!(treasury.primary_fee_rate(0), DEFAULT_FEE_BPS);
+        assert_eq!(treasury.primary_fee_rate(STAKED_SELLER_MIN_BOND), STAKED_FEE_BPS);
+        assert_eq!(treasury.secondary_fee_bps, 0);
+
+        let treasury = Treasury { primary_fee_set: true, primary_fee_bps: 300, secondary_fee_bps: 50, ..Treasury::default() };
+        assert_eq!(treasury.primary_fee_rate(0), 300);
+        assert_eq!(treasury.primary_fee_rate(STAKED_SELLER_MIN_BOND), STAKED_FEE_BPS);
+        let cheap = Treasury { primary_fee_set: true, primary_fee_bps: 50, ..Treasury::default() };
+        assert_eq!(cheap.primary_fee_rate(STAKED_SELLER_MIN_BOND), 50);
+        // 显式设置为0表示首次销售免手续费，不再回落到默认费率
+        let free = Treasury { primary_fee_set: true, ..Treasury::default() };
+        assert_eq!(free.primary_fee_rate(0), 0);
+        assert_eq!(free.primary_fee_rate(STAKED_SELLER_MIN_BOND), 0);
+        assert_eq!(fee_at_bps(1_000_000, treasury.primary_fee_rate(0)), 30_000);
+        assert_eq!(fee_at_bps(1_000_000, treasury.secondary_fee_bps as u64), 5_000);
+
+        let mut packed = [0u8; Treasury::LEN];
+        treasury.pack_into_slice(&mut packed);
+        assert_eq!(Treasury::unpack_from_slice(&packed), Ok(treasury));
+
+        let receipt = PurchaseReceipt { is_initialized: true, fee_bps: 300, fee_paid: 30_000, ..PurchaseReceipt::default() };
+        let mut packed = vec![0u8; PurchaseReceipt::LEN];
+        receipt.pack_into_slice(&mut packed);
+        assert_eq!(PurchaseReceipt::unpack_from_slice(&packed), Ok(receipt));
+    }
+
+    #[test]
+    fn test_renew_license_uses_fee_schedule() {
+        let program_id = Pubkey::new_unique();
+        let seller = TestAccount::wallet(0);
+        let holder = TestAccount::wallet(LAMPORTS_PER_SOL);
+        let (seller_key, holder_key) = (seller.key, holder.key);
+        let model = AIModel {
+            is_initialized: true,
+            owner: seller_key,
+            license_tiers: vec![LicenseTier { price: 100_000, term_slots: 500, ..LicenseTier::default() }],
+            ..AIModel::default()
+        };
+        let model = TestAccount::state(Pubkey::new_unique(), &program_id, &model);
+        let receipt = receipt(
+            &program_id,
+            &PurchaseReceipt {
+                is_initialized: true,
+                model: model.key,
+                buyer: holder_key,
+                seat_count: 1,
+                expires_at_slot: TEST_SLOT + 10,
+                ..PurchaseReceipt::default()
+            },
+        );
+        let treasury = Treasury { is_initialized: true, primary_fee_set: true, primary_fee_bps: 300, ..Treasury::default() };
+        let treasury_rent = Rent::default().minimum_balance(Treasury::LEN);
+
+        // 续费与首次销售一样按金库设置的首次销售费率收费，并计入金库的手续费统计
+        Scenario::new(program_id)
+            .account("holder", holder)
+            .account("receipt", receipt)
+            .account("model", model)
+            .account("seller", seller)
+            .account("proceeds_vault", TestAccount::readonly(find_proceeds_vault_address(&seller_key, &program_id).0))
+            .account("treasury", TestAccount::state(find_treasury_address(&program_id).0, &program_id, &treasury))
+            .account("seller_bond", TestAccount::readonly(find_seller_bond_address(&seller_key, &program_id).0))
+            .account("system_program", TestAccount::readonly(system_program::id()))
+            .step(
+                "renew",
+                MarketplaceInstruction::RenewLicense,
+                &["holder", "receipt", "model", "seller", "proceeds_vault", "treasury", "seller_bond", "system_program"],
+            )
+            .expect_lamports("treasury", treasury_rent + 3_000)
+            .expect_lamports("seller", 97_000)
+            .expect_lamports("holder", LAMPORTS_PER_SOL - 100_000)
+            .expect_state::<Treasury>("treasury", |treasury| assert_eq!(treasury.total_fees, 3_000))
+            .expect_state::<PurchaseReceipt>("receipt", |receipt| assert_eq!(receipt.expires_at_slot, TEST_SLOT + 510));
+    }
+
+    #[test]
+    fn test_marketplace_fee() {
+        assert_eq!(marketplace_fee(1_000_000, 0), 20_000);
+        assert_eq!(marketplace_fee(1_000_000, STAKED_SELLER_MIN_BOND - 1), 20_000);
+        assert_eq!(marketplace_fee(1_000_000, STAKED_SELLER_MIN_BOND), 10_000);
+        assert_eq!(marketplace_fee(49, 0), 0);
+        assert_eq!(marketplace_fee(u64::MAX, 0), u64::MAX / 50);
+    }
+
+    #[test]
+    fn test_ed25519_signed_message() {
+        let signer = Pubkey::new_unique();
+        let artifact_hash = [7u8; 32];
+        // 偏移区之后依次放公钥、签名和消息
+        let public_key_offset = 16u16;
+        let signature_offset = public_key_offset + 32;
+        let message_offset = signature_offset + 64;
+        let mut data = vec![1u8, 0];
+        for value in [signature_offset, u16::MAX, public_key_offset, u16::MAX, message_offset, 32, u16::MAX] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(signer.as_ref());
+        data.extend_from_slice(&[0u8; 64]);
+        data.extend_from_slice(&artifact_hash);
+
+        let (public_key, message) = ed25519_signed_message(&data).unwrap();
+        assert_eq!(public_key, signer);
+        assert_eq!(message, &artifact_hash);
+
+        // 公钥取自其他指令时拒绝
+        let mut foreign = data.clone();
+        foreign[8..10].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(ed25519_signed_message(&foreign), Err(ProgramError::InvalidInstructionData));
+        // 多个签名或数据被截断时拒绝
+        let mut multiple = data.clone();
+        multiple[0] = 2;
+        assert_eq!(ed25519_signed_message(&multiple), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(ed25519_signed_message(&data[..data.len() - 1]), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_creators_from_token_metadata() {
+        let mint = Pubkey::new_unique();
+        let verified = Pubkey::new_unique();
+        let unverified = Pubkey::new_unique();
+        let mut data = vec![TOKEN_METADATA_KEY_V1];
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(mint.as_ref());
+        pack_string(&mut data, "Model NFT");
+        pack_string(&mut data, "AIM");
+        pack_string(&mut data, "https://example.com/model.json");
+        data.extend_from_slice(&500u16.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&2u32.to_le_bytes());
+        for (creator, is_verified) in [(verified, 1u8), (unverified, 0u8)] {
+            data.extend_from_slice(creator.as_ref());
+            data.push(is_verified);
+            data.push(50);
+        }
+        // 元数据账户尾部还有其他字段，解析时应忽略
+        data.extend_from_slice(&[0; 16]);
+
+        let (parsed_mint, creators) = creators_from_token_metadata(&data).unwrap();
+        assert_eq!(parsed_mint, mint);
+        assert_eq!(creators, vec![(verified, true), (unverified, false)]);
+        assert_eq!(
+            creators_from_token_metadata(&data[..40]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_badge_milestones() {
+        let mut stats = BuyerStats {
+            total_purchases: PURCHASER_BADGE_MIN_PURCHASES - 1,
+            verified_reviews: REVIEWER_BADGE_MIN_REVIEWS,
+            ..BuyerStats::default()
+        };
+        assert!(!BadgeKind::Purchaser.is_earned(&stats));
+        assert!(BadgeKind::Reviewer.is_earned(&stats));
+        stats.total_purchases += 1;
+        assert!(BadgeKind::Purchaser.is_earned(&stats));
+        assert_eq!(BadgeKind::from_u8(2), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_has_license() {
+        let program_id = Pubkey::new_unique();
+        let model = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        let (receipt_key, _) = find_receipt_address(&model, &wallet, 0, &program_id);
+        let receipt = PurchaseReceipt {
+            is_initialized: true,
+            model,
+            buyer: wallet,
+            ..PurchaseReceipt::default()
+        };
+        let mut data = vec![0u8; PurchaseReceipt::LEN];
+        receipt.pack_into_slice(&mut data);
+        let mut lamports = 0;
+        let account = AccountInfo::new(&receipt_key, false, false, &mut lamports, &mut data, &program_id, false, 0);
+        assert!(cpi::has_license(&program_id, &model, &wallet, &account));
+        assert!(!cpi::has_license(&program_id, &model, &Pubkey::new_unique(), &account));
+
+        let (later_key, _) = find_receipt_address(&model, &wallet, 1, &program_id);
+        let mut lamports = 0;
+        let mut data = vec![0u8; PurchaseReceipt::LEN];
+        receipt.pack_into_slice(&mut data);
+        let later = AccountInfo::new(&later_key, false, false, &mut lamports, &mut data, &program_id, false, 0);
+        assert!(!cpi::has_license(&program_id, &model, &wallet, &later));
+    }
+
+    #[test]
+    fn test_receipt_api_key() {
+        let mut receipt = PurchaseReceipt {
+            is_initialized: true,
+            model: Pubkey::new_unique(),
+            buyer: Pubkey::new_unique(),
+            license: String::from("MIT"),
+            ..PurchaseReceipt::default()
+        };
+        // 未绑定时任何密钥都不通过，包括空密钥
+        assert!(!receipt.matches_api_key(b""));
+        assert!(!receipt.matches_api_key(b"sk-live-1"));
+
+        receipt.api_key_hash = solana_program::hash::hash(b"sk-live-1").to_bytes();
+        receipt.api_key_bound_at = 1_700_000_000;
+        let mut data = vec![0u8; PurchaseReceipt::LEN];
+        receipt.pack_into_slice(&mut data);
+        let unpacked = PurchaseReceipt::unpack(&data).unwrap();
+        assert_eq!(unpacked, receipt);
+        assert!(unpacked.matches_api_key(b"sk-live-1"));
+        assert!(!unpacked.matches_api_key(b"sk-live-2"));
+    }
+
+    #[test]
+    fn test_buyer_receipts_page_pack() {
+        let page = BuyerReceiptsPage {
+            is_initialized: true,
+            buyer: Pubkey::new_unique(),
+            page_index: 2,
+            created_at: 1_700_000_000,
+            updated_at: 1_700_000_100,
+            receipts: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        };
+        let mut data = vec![0u8; BuyerReceiptsPage::LEN];
+        page.pack_into_slice(&mut data);
+        assert_eq!(BuyerReceiptsPage::unpack(&data), Ok(page));
+
+        let stats = BuyerStats {
+            total_purchases: BUYER_RECEIPTS_PAGE_CAPACITY as u64 * 2,
+            ..BuyerStats::default()
+        };
+        assert_eq!(stats.current_receipts_page(), 2);
+    }
+
+    #[test]
+    fn test_dispute_evidence() {
+        let buyer = Pubkey::new_unique();
+        let seller = Pubkey::new_unique();
+        let mut dispute = Dispute {
+            is_initialized: true,
+            buyer,
+            seller,
+            ..Dispute::default()
+        };
+        for i in 0..MAX_EVIDENCE_PER_PARTY {
+            assert_eq!(dispute.add_evidence(&buyer, [i as u8; 32], i as i64), Ok(()));
+        }
+        assert_eq!(dispute.add_evidence(&buyer, [0; 32], 0), Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(dispute.add_evidence(&seller, [9; 32], 100), Ok(()));
+        assert_eq!(
+            dispute.add_evidence(&Pubkey::new_unique(), [0; 32], 0),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut data = vec![0u8; Dispute::LEN];
+        dispute.pack_into_slice(&mut data);
+        assert_eq!(Dispute::unpack(&data), Ok(dispute));
+    }
+
+    #[test]
+    fn test_artifact_attestation_gate() {
+        assert_eq!(StorageClass::OnChain.validate_uri(""), Ok(()));
+        assert_eq!(StorageClass::OnChain.validate_uri("x"), Err(ProgramError::InvalidArgument));
+        assert_eq!(StorageClass::Arweave.validate_uri("bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U"), Ok(()));
+        assert_eq!(
+            StorageClass::Arweave.validate_uri("bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt/U"),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        let mut model = AIModel {
+            storage_class: StorageClass::Arweave,
+            ..AIModel::default()
+        };
+        assert_eq!(model.require_purchasable(), Err(ProgramError::InvalidArgument));
+        model.artifact_attested_at = 1_700_000_000;
+        assert_eq!(model.require_purchasable(), Ok(()));
+        assert_eq!(AIModel::default().require_purchasable(), Ok(()));
+
+        let mut gated = AIModel {
+            approval_required: true,
+            ..AIModel::default()
+        };
+        assert_eq!(gated.require_purchasable(), Err(ProgramError::InvalidArgument));
+        gated.approved_at = 1_700_000_000;
+        assert_eq!(gated.require_purchasable(), Ok(()));
+
+        let deprecated = AIModel {
+            deprecated: true,
+            ..AIModel::default()
+        };
+        assert_eq!(deprecated.require_deprecation_acknowledged(false), Err(ProgramError::InvalidArgument));
+        assert_eq!(deprecated.require_deprecation_acknowledged(true), Ok(()));
+        assert_eq!(AIModel::default().require_deprecation_acknowledged(false), Ok(()));
+    }
+
+    #[test]
+    fn test_storage_class_validation() {
+        assert_eq!(StorageClass::Https.validate_uri("https://models.example.com/llama.gguf"), Ok(()));
+        for uri in ["http://models.example.com/a", "https://", "https:///a", "https://a b"] {
+            assert_eq!(StorageClass::Https.validate_uri(uri), Err(ProgramError::InvalidArgument), "{}", uri);
+        }
+
+        assert_eq!(StorageClass::OnChain.validate_size(0), Ok(()));
+        assert_eq!(StorageClass::OnChain.validate_size(MODEL_FILE_LEN as u64), Ok(()));
+        assert_eq!(StorageClass::OnChain.validate_size(MODEL_FILE_LEN as u64 + 1), Err(ProgramError::InvalidArgument));
+        assert_eq!(StorageClass::Ipfs.validate_size(0), Err(ProgramError::InvalidArgument));
+        assert_eq!(StorageClass::Https.validate_size(4_000_000_000), Ok(()));
+        assert!(StorageClass::Https.requires_attestation());
+        assert!(!StorageClass::Ipfs.requires_attestation());
+    }
+
+    #[test]
+    fn test_ipfs_cid_validation() {
+        assert_eq!(validate_ipfs_cid("QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG"), Ok(()));
+        assert_eq!(validate_ipfs_cid("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"), Ok(()));
+        assert_eq!(validate_ipfs_cid("bafkreigh2akiscaildcqabsyg3dfr6chu3fgpregiymsck7e7aqa4s52zy"), Ok(()));
+        assert_eq!(StorageClass::Ipfs.validate_uri("bafkreigh2akiscaildcqabsyg3dfr6chu3fgpregiymsck7e7aqa4s52zy"), Ok(()));
+
+        // 截断的摘要、非法字符、不支持的multibase前缀
+        for cid in [
+            "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbd0",
+            "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzd",
+            "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdI",
+            "fafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi",
+            "https://ipfs.io/ipfs/QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG",
+            "",
+        ] {
+            assert_eq!(validate_ipfs_cid(cid), Err(ProgramError::InvalidArgument), "{}", cid);
+        }
+    }
+
+    #[test]
+    fn test_model_format() {
+        for format in [
+            ModelFormat::Onnx,
+            ModelFormat::SafeTensors,
+            ModelFormat::Gguf,
+            ModelFormat::PyTorch,
+            ModelFormat::TensorFlow,
+        ] {
+            assert_eq!(ModelFormat::from_u8(format as u8), Ok(format));
+        }
+        assert_eq!(ModelFormat::from_u8(5), Err(ProgramError::InvalidAccountData));
+
+        let mut create = MarketplaceInstruction::CreateAIModel {
+            name: String::new(),
+            description: String::new(),
+            price: 1,
+            pricing_mode: PricingMode::Fixed,
+            license_tiers: vec![],
+            per_seat: false,
+            volume_discounts: vec![],
+            vault_proceeds: false,
+            model_file: &[],
+            model_format: ModelFormat::TensorFlow,
+            license: String::new(),
+        }
+        .pack();
+        // 空许可证序列化为4字节长度前缀，格式字节位于其前
+        let format_index = create.len() - 5;
+        create[format_index] = 9;
+        assert_eq!(MarketplaceInstruction::unpack(&create), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_validate_license() {
+        for license in [
+            "MIT",
+            "Apache-2.0 OR MIT",
+            "GPL-2.0+ WITH Classpath-exception-2.0",
+            "(MIT AND BSD-3-Clause) OR LicenseRef-acme-enterprise",
+            "CC-BY-NC-4.0",
+            "ar://bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U",
+            "https://example.com/terms/v2",
+        ] {
+            assert_eq!(validate_license(license), Ok(()), "{}", license);
+        }
+        for license in [
+            "",
+            "MIT OR",
+            "AND MIT",
+            "(MIT",
+            "MIT)",
+            "MIT Apache-2.0",
+            "MIT WITH",
+            "MIT/X11",
+            "((((( MIT )))))",
+            "https://example.com/my terms",
+        ] {
+            assert_eq!(validate_license(license), Err(ProgramError::InvalidArgument), "{}", license);
+        }
+        assert_eq!(validate_license(&"a".repeat(MAX_LICENSE_LEN + 1)), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_semver_ordering() {
+        let v = |major, minor, patch| SemVer { major, minor, patch };
+        assert!(v(1, 0, 0) > v(0, 9, 9));
+        assert!(v(1, 10, 0) > v(1, 9, 99));
+        assert!(v(1, 2, 4) > v(1, 2, 3));
+        assert!(v(0, 0, 1) > SemVer::default());
+
+        assert!(v(1, 4, 0).is_compatible_with(&v(1, 2, 3)));
+        assert!(!v(1, 2, 2).is_compatible_with(&v(1, 2, 3)));
+        assert!(!v(2, 0, 0).is_compatible_with(&v(1, 2, 3)));
+        assert!(v(0, 3, 7).is_compatible_with(&v(0, 3, 1)));
+        assert!(!v(0, 4, 0).is_compatible_with(&v(0, 3, 1)));
+
+        let version = ModelVersion {
+            is_initialized: true,
+            model: Pubkey::new_unique(),
+            version_index: 2,
+            version: v(1, 2, 3),
+            artifact_hash: [7; 32],
+            created_at: 1,
+            updated_at: 2,
+        };
+        let mut packed = [0u8; ModelVersion::LEN];
+        version.pack_into_slice(&mut packed);
+        assert_eq!(ModelVersion::unpack(&packed), Ok(version));
+    }
+
+    #[test]
+    fn test_changelog_entry_pack() {
+        let entry = ChangelogEntry {
+            is_initialized: true,
+            model: Pubkey::new_unique(),
+            model_version: Pubkey::new_unique(),
+            version: SemVer { major: 2, minor: 0, patch: 1 },
+            content_hash: [3; 32],
+            uri: String::from("ipfs://bafkreigh2akiscaildcqabsyg3dfr6chu3fgpregiymsck7e7aqa4s52zy"),
+            summary: String::from("Fix tokenizer regression"),
+            previous: Pubkey::new_unique(),
+            created_at: 10,
+            updated_at: 11,
+        };
+        let mut packed = vec![0u8; ChangelogEntry::LEN];
+        entry.pack_into_slice(&mut packed);
+        assert_eq!(ChangelogEntry::unpack(&packed), Ok(entry));
+    }
+
+    #[test]
+    fn test_relationship_addresses() {
+        let program_id = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        let target = Pubkey::new_unique();
+        // 关注和收藏使用不同的种子，同一对(钱包, 目标)不会冲突
+        assert_ne!(
+            find_follow_address(&wallet, &target, &program_id),
+            find_favorite_address(&wallet, &target, &program_id)
+        );
+        assert_ne!(
+            find_follow_address(&wallet, &target, &program_id),
+            find_follow_address(&target, &wallet, &program_id)
+        );
+
+        let relationship = Relationship {
+            is_initialized: true,
+            follower: wallet,
+            target,
+            created_at: 5,
+            updated_at: 5,
+        };
+        let mut packed = [0u8; Relationship::LEN];
+        relationship.pack_into_slice(&mut packed);
+        assert_eq!(Relationship::unpack(&packed), Ok(relationship));
+    }
+
+    #[test]
+    fn test_price_changed_event_filter() {
+        let watched = Pubkey::new_unique();
+        let drop = events::PriceChanged {
+            model: watched,
+            mint: Pubkey::new_unique(),
+            old_price: 1_000,
+            new_price: 600,
+        };
+        let raise = events::PriceChanged {
+            model: Pubkey::new_unique(),
+            mint: Pubkey::default(),
+            old_price: 100,
+            new_price: 200,
+        };
+        assert_eq!(events::PriceChanged::from_log(&drop.to_log()), Some(drop));
+
+        let logs = vec![
+            String::from("Program log: Instruction: UpdatePrice"),
+            format!("Program log: {}", drop.to_log()),
+            format!("Program log: {}", raise.to_log()),
+            String::from("Program log: PriceChanged: model=garbage"),
+        ];
+        assert_eq!(events::PriceChangedFilter::default().filter_logs(&logs), vec![drop, raise]);
+
+        let drops = events::PriceChangedFilter {
+            drops_only: true,
+            ..Default::default()
+        };
+        assert_eq!(drops.filter_logs(&logs), vec![drop]);
+        let by_model = events::PriceChangedFilter {
+            models: vec![raise.model],
+            ..Default::default()
+        };
+        assert_eq!(by_model.filter_logs(&logs), vec![raise]);
+        let cheap = events::PriceChangedFilter {
+            max_new_price: Some(500),
+            ..Default::default()
+        };
+        assert_eq!(cheap.filter_logs(&logs), vec![raise]);
+    }
+
+    #[test]
+    fn test_featured_list_pack() {
+        let mut list = FeaturedList {
+            is_initialized: true,
+            list_id: 3,
+            label: String::from("Staff picks"),
+            created_at: 1,
+            updated_at: 2,
+            listings: (0..MAX_FEATURED_LISTINGS).map(|_| Pubkey::new_unique()).collect(),
+        };
+        let mut packed = vec![0u8; FeaturedList::LEN];
+        list.pack_into_slice(&mut packed);
+        assert_eq!(FeaturedList::unpack(&packed), Ok(list.clone()));
+
+        // 缩短列表时旧条目被清零，不会残留在账户数据中
+        list.listings.truncate(2);
+        list.pack_into_slice(&mut packed);
+        assert_eq!(FeaturedList::unpack(&packed), Ok(list));
+        assert!(packed[FeaturedList::LEN - 32..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_category_registry() {
+        let category = |id, parent, label: &str| Category {
+            id,
+            parent,
+            label: String::from(label),
+        };
+        let mut registry = CategoryRegistry {
+            is_initialized: true,
+            ..CategoryRegistry::default()
+        };
+        assert_eq!(registry.register(category(1, 0, "Language")), Ok(()));
+        assert_eq!(registry.register(category(2, 1, "Chat")), Ok(()));
+        // 编号为0、重复编号、未注册的上级分类、空标签都会被拒绝
+        for invalid in [category(0, 0, "Zero"), category(2, 0, "Dup"), category(3, 9, "Orphan"), category(4, 0, "")] {
+            assert_eq!(registry.register(invalid), Err(ProgramError::InvalidArgument));
+        }
+        assert_eq!(registry.get(2).map(|c| c.parent), Some(1));
+
+        let mut packed = vec![0u8; CategoryRegistry::LEN];
+        registry.pack_into_slice(&mut packed);
+        assert_eq!(CategoryRegistry::unpack(&packed), Ok(registry.clone()));
+
+        for id in 3..=MAX_CATEGORIES as u16 {
+            registry.register(category(id, 0, "Other")).unwrap();
+        }
+        assert_eq!(
+            registry.register(category(MAX_CATEGORIES as u16 + 1, 0, "Full")),
+            Err(ProgramError::AccountDataTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_validate_lang_code() {
+        for lang in ["en", "zh-Hans", "zh-Hans-CN", "pt-BR", "yue", "es-419"] {
+            assert_eq!(validate_lang_code(lang), Ok(()), "{}", lang);
+        }
+        for lang in ["", "e", "EN", "english", "en_US", "en-", "en--US", "zh-Hans-CN-x-private1"] {
+            assert_eq!(validate_lang_code(lang), Err(ProgramError::InvalidArgument), "{}", lang);
+        }
+    }
+
+    #[test]
+    fn test_verify_metadata() {
+        let document = br#"{"schema_version":1,"name":"llama","description":"8B chat model"}"#;
+        let mut model = AIModel {
+            metadata_uri: String::from("ar://metadata"),
+            metadata_hash: metadata::hash_metadata(document),
+            ..AIModel::default()
+        };
+        assert!(metadata::verify_metadata(&model, document));
+        assert!(!metadata::verify_metadata(&model, br#"{"schema_version":1}"#));
+
+        model.metadata_uri.clear();
+        assert!(!metadata::verify_metadata(&model, document));
+    }
+
+    #[test]
+    fn test_update_authority() {
+        let owner = Pubkey::new_unique();
+        let dev_ops = Pubkey::new_unique();
+        let mut model = AIModel {
+            owner,
+            ..AIModel::default()
+        };
+        // 未单独设置时由owner兼任
+        assert!(model.is_update_authority(&owner));
+        assert!(!model.is_update_authority(&dev_ops));
+
+        model.update_authority = dev_ops;
+        assert!(model.is_update_authority(&dev_ops));
+        assert!(!model.is_update_authority(&owner));
+        assert_eq!(model.effective_update_authority(), dev_ops);
+    }
+
+    #[test]
+    fn test_set_creator_verification() {
+        let creator = Pubkey::new_unique();
+        let impersonator = Pubkey::new_unique();
+        let mut model = AIModel::default();
+
+        // 由他人列出的创作者在其签名确认前不算已验证
+        model.set_creator(creator, &impersonator);
+        assert_eq!(model.creator, creator);
+        assert!(!model.creator_verified);
+
+        model.set_creator(creator, &creator);
+        assert!(model.creator_verified);
+
+        // 更换创作者会清除已验证标记
+        model.set_creator(impersonator, &creator);
+        assert!(!model.creator_verified);
+    }
+
+    #[test]
+    fn test_verify_groth16() {
+        // 用生成元构造满足配对等式的证明：A = 3·G1，B = beta = gamma = delta = G2，alpha = C = IC0 = G1，
+        // 则 -3 + 1 + 1 + 1 = 0
+        let mut g1 = [0u8; 64];
+        g1[31] = 1;
+        g1[63] = 2;
+        let g2: Vec<u8> = [
+            "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+            "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+            "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+            "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+        ]
+        .concat()
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+        .collect();
+        let mut three = [0u8; 32];
+        three[31] = 3;
+        let a = alt_bn128_multiplication(&[&g1[..], &three[..]].concat()).unwrap();
+
+        let verifying_key = [&g1[..], &g2, &g2, &g2, &g1[..]].concat();
+        assert_eq!(ProofSystem::Groth16Bn254.public_input_count(&verifying_key), Ok(0));
+        let proof = [&a[..], &g2, &g1[..]].concat();
+        assert_eq!(ProofSystem::Groth16Bn254.verify(&verifying_key, &proof, &[]), Ok(()));
+
+        // 篡改C或多传公开输入都会失败
+        let forged = [&a[..], &g2, &a[..]].concat();
+        assert_eq!(verify_groth16(&verifying_key, &forged, &[]), Err(ProgramError::InvalidArgument));
+        assert_eq!(verify_groth16(&verifying_key, &proof, &[[0; 32]]), Err(ProgramError::InvalidArgument));
+
+        let mut proof_account = proof.clone();
+        proof_account.push(0);
+        let (parsed, inputs) = parse_proof_account(&proof_account).unwrap();
+        assert_eq!(parsed, &proof[..]);
+        assert!(inputs.is_empty());
+        assert_eq!(parse_proof_account(&proof), Err(ProgramError::InvalidAccountData));
+
+        let verifier = ProvenanceVerifier {
+            is_initialized: true,
+            verifier_id: 1,
+            proof_system: ProofSystem::Groth16Bn254,
+            claim: String::from("trained on licensed data"),
+            verifying_key,
+            created_at: 1,
+            updated_at: 2,
+        };
+        let mut data = vec![0u8; ProvenanceVerifier::LEN];
+        verifier.pack_into_slice(&mut data);
+        assert_eq!(ProvenanceVerifier::unpack(&data), Ok(verifier));
+    }
+
+    #[test]
+    fn test_inference_commitment_pack() {
+        let commitment = InferenceCommitment {
+            is_initialized: true,
+            model: Pubkey::new_unique(),
+            model_version: Pubkey::new_unique(),
+            version: SemVer { major: 1, minor: 2, patch: 3 },
+            worker: Pubkey::new_unique(),
+            input_hash: [1; 32],
+            output_hash: [2; 32],
+            committed_at: 1_700_000_000,
+        };
+        let mut data = vec![0u8; InferenceCommitment::LEN];
+        commitment.pack_into_slice(&mut data);
+        assert_eq!(InferenceCommitment::unpack(&data), Ok(commitment.clone()));
+
+        // 不同输入对应不同的承诺地址
+        let program_id = Pubkey::new_unique();
+        assert_ne!(
+            find_inference_commitment_address(&commitment.model_version, &commitment.worker, &[1; 32], &program_id),
+            find_inference_commitment_address(&commitment.model_version, &commitment.worker, &[2; 32], &program_id)
+        );
+    }
+
+    #[test]
+    fn test_inference_request_fulfillment() {
+        let model = Pubkey::new_unique();
+        let worker = Pubkey::new_unique();
+        let request = InferenceRequest {
+            is_initialized: true,
+            model,
+            requester: Pubkey::new_unique(),
+            request_id: 1,
+            prompt_hash: [3; 32],
+            max_price: 1_000,
+            deadline: 100,
+            claimed_by: worker,
+            claim_expires_at: 50,
+            ..InferenceRequest::default()
+        };
+        let mut data = vec![0u8; InferenceRequest::LEN];
+        request.pack_into_slice(&mut data);
+        assert_eq!(InferenceRequest::unpack(&data), Ok(request.clone()));
+
+        let commitment = InferenceCommitment {
+            is_initialized: true,
+            model,
+            worker,
+            input_hash: [3; 32],
+            committed_at: 100,
+            ..InferenceCommitment::default()
+        };
+        assert_eq!(request.require_fulfillable_by(&commitment, 1_000), Ok(()));
+        // 没有锁定请求的工作节点不能完成
+        let unclaimed = InferenceCommitment { worker: Pubkey::new_unique(), ..commitment.clone() };
+        assert_eq!(request.require_fulfillable_by(&unclaimed, 10), Err(ProgramError::InvalidArgument));
+        assert_eq!(request.require_fulfillable_by(&commitment, 1_001), Err(ProgramError::InvalidArgument));
+
+        // 截止后提交或针对其他输入的承诺不能完成请求
+        let late = InferenceCommitment { committed_at: 101, ..commitment.clone() };
+        assert_eq!(request.require_fulfillable_by(&late, 10), Err(ProgramError::InvalidArgument));
+        let other_input = InferenceCommitment { input_hash: [4; 32], ..commitment.clone() };
+        assert_eq!(request.require_fulfillable_by(&other_input, 10), Err(ProgramError::InvalidArgument));
+
+        let fulfilled = InferenceRequest {
+            status: InferenceRequestStatus::Fulfilled,
+            ..request
+        };
+        assert_eq!(fulfilled.require_fulfillable_by(&commitment, 10), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_inference_job_claims() {
+        let first = Pubkey::new_unique();
+        let second = Pubkey::new_unique();
+        let mut request = InferenceRequest {
+            deadline: 10_000,
+            ..InferenceRequest::default()
+        };
+        assert!(!request.is_claimed(0));
+        // 未锁定时没有可释放的锁定
+        assert_eq!(request.release_expired_claim(0), Err(ProgramError::InvalidArgument));
+
+        assert_eq!(request.claim(&first, 1_000), Ok(()));
+        assert_eq!(request.claim_expires_at, 1_000 + INFERENCE_CLAIM_TIMEOUT);
+        assert_eq!(request.claim(&second, 1_001), Err(ProgramError::InvalidArgument));
+        assert_eq!(request.release_expired_claim(1_001), Err(ProgramError::InvalidArgument));
+
+        let expired = 1_000 + INFERENCE_CLAIM_TIMEOUT;
+        assert!(!request.is_claimed(expired));
+        assert_eq!(request.release_expired_claim(expired), Ok(()));
+        assert_eq!(request.claimed_by, Pubkey::default());
+        assert_eq!(request.claim(&second, expired), Ok(()));
+
+        // 锁定不会超过截止时间，截止后不能再锁定
+        let mut near_deadline = InferenceRequest {
+            deadline: 100,
+            ..InferenceRequest::default()
+        };
+        assert_eq!(near_deadline.claim(&first, 90), Ok(()));
+        assert_eq!(near_deadline.claim_expires_at, 100);
+        assert_eq!(near_deadline.release_expired_claim(100), Ok(()));
+        assert_eq!(near_deadline.claim(&first, 100), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_inference_challenge_window() {
+        let mut request = InferenceRequest {
+            is_initialized: true,
+            status: InferenceRequestStatus::Submitted,
+            proof_uri: String::from("ar://proof"),
+            challenge_ends_at: 1_000,
+            ..InferenceRequest::default()
+        };
+        let mut data = vec![0u8; InferenceRequest::LEN];
+        request.pack_into_slice(&mut data);
+        assert_eq!(InferenceRequest::unpack(&data), Ok(request.clone()));
+
+        // 挑战期内不能结算，挑战期结束后不能再挑战
+        assert_eq!(request.require_settleable(999), Err(ProgramError::InvalidArgument));
+        assert_eq!(request.require_settleable(1_000), Ok(()));
+        let mut late = request.clone();
+        assert_eq!(late.challenge(1_000), Err(ProgramError::InvalidArgument));
+
+        assert_eq!(request.challenge(999), Ok(()));
+        assert_eq!(request.status, InferenceRequestStatus::Challenged);
+        // 被挑战的结果只能由仲裁者裁定
+        assert_eq!(request.require_settleable(2_000), Err(ProgramError::InvalidArgument));
+        assert_eq!(request.challenge(999), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_rental_stream_accrual() {
+        let stream = RentalStream {
+            is_initialized: true,
+            rate_per_slot: 25,
+            start_slot: 1_000,
+            deposit: 1_010,
+            withdrawn: 250,
+            ..RentalStream::default()
+        };
+        let mut data = vec![0u8; RentalStream::LEN];
+        stream.pack_into_slice(&mut data);
+        assert_eq!(RentalStream::unpack(&data), Ok(stream.clone()));
+
+        // 开启前的slot不计费，之后按经过的slot精确计费
+        assert_eq!(stream.streamed(999), 0);
+        assert_eq!(stream.streamed(1_010), 250);
+        assert_eq!(stream.claimable(1_010), 0);
+        assert_eq!(stream.claimable(1_020), 250);
+        assert!(stream.is_active(1_040));
+        // 押金耗尽后不再累计，租用失效
+        assert_eq!(stream.streamed(1_041), 1_010);
+        assert_eq!(stream.streamed(u64::MAX), 1_010);
+        assert!(!stream.is_active(1_041));
+    }
+
+    #[test]
+    fn test_commission_milestones() {
+        let mut commission = Commission {
+            is_initialized: true,
+            commission_id: 3,
+            milestones: vec![
+                Milestone { amount: 1_000, ..Milestone::default() },
+                Milestone { amount: 2_500, ..Milestone::default() },
+            ],
+            ..Commission::default()
+        };
+        let mut data = vec![0u8; Commission::LEN];
+        commission.pack_into_slice(&mut data);
+        assert_eq!(Commission::unpack(&data), Ok(commission.clone()));
+
+        // 未交付、未确认的里程碑不能确认或领取
+        assert_eq!(commission.approve(0, 1), Err(ProgramError::InvalidArgument));
+        assert_eq!(commission.release(0, 1), Err(ProgramError::InvalidArgument));
+        assert_eq!(commission.submit(2, [1; 32], 1), Err(ProgramError::InvalidArgument));
+
+        assert_eq!(commission.submit(0, [1; 32], 1), Ok(()));
+        assert_eq!(commission.approve(0, 2), Ok(()));
+        assert_eq!(commission.release(0, 3), Ok(1_000));
+        assert_eq!(commission.release(0, 3), Err(ProgramError::InvalidArgument));
+        assert!(!commission.is_settled());
+
+        // 有争议的里程碑只能由仲裁者裁定，驳回时金额退回买家
+        assert_eq!(commission.submit(1, [2; 32], 4), Ok(()));
+        assert_eq!(commission.dispute(1, 5), Ok(()));
+        assert_eq!(commission.approve(1, 5), Err(ProgramError::InvalidArgument));
+        let mut released = commission.clone();
+        assert_eq!(released.resolve(1, true, 6), Ok(0));
+        assert_eq!(released.release(1, 7), Ok(2_500));
+        assert_eq!(commission.resolve(1, false, 6), Ok(2_500));
+        assert_eq!(commission.milestones[1].status, MilestoneStatus::Refunded);
+        assert!(commission.is_settled());
+        assert!(released.is_settled());
+    }
+
+    #[test]
+    fn test_subscription_change_tier() {
+        let plan = SubscriptionPlan {
+            is_initialized: true,
+            period_slots: 1_000,
+            tiers: vec![
+                SubscriptionTier { price: 1_000, access_scopes: ACCESS_SCOPE_INFERENCE },
+                SubscriptionTier { price: 4_000, access_scopes: ACCESS_SCOPE_INFERENCE | ACCESS_SCOPE_DOWNLOAD },
+            ],
+            ..SubscriptionPlan::default()
+        };
+        let mut data = vec![0u8; SubscriptionPlan::LEN];
+        plan.pack_into_slice(&mut data);
+        assert_eq!(SubscriptionPlan::unpack(&data), Ok(plan.clone()));
+        assert_eq!(plan.tier(2), Err(ProgramError::InvalidArgument));
+
+        let subscription = Subscription {
+            is_initialized: true,
+            tier: 0,
+            price: 1_000,
+            period_slots: 1_000,
+            start_slot: 0,
+            paid_through_slot: 1_000,
+            ..Subscription::default()
+        };
+        let mut data = vec![0u8; Subscription::LEN];
+        subscription.pack_into_slice(&mut data);
+        assert_eq!(Subscription::unpack(&data), Ok(subscription.clone()));
+
+        // 周期过半时升级，补交剩余一半时间的差价，到期时间不变
+        let mut upgraded = subscription.clone();
+        assert_eq!(upgraded.change_tier(1, 4_000, 1_000, 500), Ok(1_500));
+        assert_eq!((upgraded.tier, upgraded.price, upgraded.paid_through_slot), (1, 4_000, 1_000));
+
+        // 降级不退款，剩余价值2000按新价格折算为500个slot
+        assert_eq!(upgraded.change_tier(0, 1_000, 1_000, 500), Ok(0));
+        assert_eq!(upgraded.paid_through_slot, 500 + 2_000);
+
+        // 补交金额向上取整，过期后不能变更档位
+        let mut odd = subscription.clone();
+        assert_eq!(odd.change_tier(1, 4_000, 1_000, 999), Ok(3));
+        let mut expired = subscription.clone();
+        assert_eq!(expired.change_tier(1, 4_000, 1_000, 1_000), Err(ProgramError::InvalidArgument));
+        assert_eq!(expired.change_tier(0, 1_000, 1_000, 10), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_proration_rounding() {
+        // 整除时收费与抵扣一致，不整除时收费向上、抵扣向下，差额不超过1
+        for slots in 0..=7 {
+            let credit = proration::credit(10, 7, slots);
+            let charge = proration::charge(10, 7, slots);
+            assert_eq!(credit, 10 * slots / 7);
+            assert!(charge >= credit && charge - credit <= 1);
+            assert_eq!(charge == credit, (10 * slots) % 7 == 0);
+        }
+        // 边界slot：0个slot没有价值，整个周期等于周期价格，超过周期按比例增加
+        assert_eq!(proration::credit(1_000, 100, 0), 0);
+        assert_eq!(proration::charge(1_000, 100, 0), 0);
+        assert_eq!(proration::credit(1_000, 100, 100), 1_000);
+        assert_eq!(proration::charge(1_000, 100, 100), 1_000);
+        assert_eq!(proration::credit(1_000, 100, 250), 2_500);
+        assert_eq!(proration::charge(1_000, 100, 1), 10);
+        assert_eq!(proration::charge(1, 100, 1), 1);
+        assert_eq!(proration::credit(1, 100, 99), 0);
+        // 周期为0时不计价，超出u64的结果饱和
+        assert_eq!(proration::credit(1_000, 0, 50), 0);
+        assert_eq!(proration::charge(1_000, 0, 50), 0);
+        assert_eq!(proration::credit(u64::MAX, 1, u64::MAX), u64::MAX);
+        assert_eq!(proration::charge(u64::MAX, u64::MAX, u64::MAX), u64::MAX);
+        assert_eq!(proration::credit(u64::MAX, u64::MAX, 1), 1);
+    }
+
+    #[test]
+    fn test_proration_slots_and_metering() {
+        assert_eq!(proration::slots_for(500, 1_000, 100), 50);
+        assert_eq!(proration::slots_for(999, 1_000, 100), 99);
+        assert_eq!(proration::slots_for(0, 1_000, 100), 0);
+        assert_eq!(proration::slots_for(1, 0, 100), u64::MAX);
+        assert_eq!(proration::slots_for(u64::MAX, 1, u64::MAX), u64::MAX);
+
+        assert_eq!(proration::metered(25, 0, 100), 0);
+        assert_eq!(proration::metered(25, 4, 100), 100);
+        assert_eq!(proration::metered(25, 5, 100), 100);
+        assert_eq!(proration::metered(u64::MAX, u64::MAX, 7), 7);
+        assert_eq!(proration::unused(100, 30), 70);
+        assert_eq!(proration::unused(100, 130), 0);
+    }
+
+    #[test]
+    fn test_close_rental_stream_edge_slots() {
+        install_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let stream_rent = Rent::default().minimum_balance(RentalStream::LEN);
+        // 每slot 10 lamports、押金1000，第100个slot押金耗尽；withdrawn为所有者已提取的部分
+        for (elapsed, withdrawn, payout, refund) in [
+            (0, 0, 0, 1_000),
+            (1, 0, 10, 990),
+            (99, 0, 990, 10),
+            (100, 0, 1_000, 0),
+            (250, 400, 600, 0),
+        ] {
+            let mut owner = TestAccount::wallet(0);
+            let mut renter = TestAccount::wallet(0);
+            let stream = RentalStream {
+                is_initialized: true,
+                owner: owner.key,
+                renter: renter.key,
+                rate_per_slot: 10,
+                start_slot: TEST_SLOT - elapsed,
+                deposit: 1_000,
+                withdrawn,
+                ..RentalStream::default()
+            };
+            let mut stream_account = TestAccount::state(Pubkey::new_unique(), &program_id, &stream);
+            stream_account.lamports += 1_000 - withdrawn;
+            let renter_info = renter.info();
+            assert_eq!(
+                close_rental_stream(&program_id, &[renter_info.clone(), owner.info(), renter_info, stream_account.info()]),
+                Ok(())
+            );
+            assert_eq!((owner.lamports, renter.lamports), (payout, stream_rent + refund), "elapsed {}", elapsed);
+            assert_eq!(stream_account.lamports, 0);
+        }
+    }
+
+    #[test]
+    fn test_cancel_subscription_edge_slots() {
+        install_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let subscription_rent = Rent::default().minimum_balance(Subscription::LEN);
+        // 到期前的最后一个slot仍可取消并退回全部预存余额，到期的slot起不能再取消
+        for (paid_through_slot, result) in [(TEST_SLOT + 1, Ok(())), (TEST_SLOT, Err(ProgramError::InvalidArgument))] {
+            let mut subscriber = TestAccount::wallet(0);
+            let subscription = Subscription {
+                is_initialized: true,
+                subscriber: subscriber.key,
+                paid_through_slot,
+                renewal_balance: 700,
+                ..Subscription::default()
+            };
+            let mut subscription_account = TestAccount::state(Pubkey::new_unique(), &program_id, &subscription);
+            subscription_account.lamports += 700;
+            assert_eq!(cancel_subscription(&program_id, &[subscriber.info(), subscription_account.info()]), result);
+            let refunded = if result.is_ok() { 700 } else { 0 };
+            assert_eq!(subscriber.lamports, refunded);
+            assert_eq!(subscription_account.lamports, subscription_rent + 700 - refunded);
+        }
+    }
+
+    #[test]
+    fn test_change_tier_edge_slots() {
+        install_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let owner_key = Pubkey::new_unique();
+        let model = listing(&program_id, &owner_key, 1_000);
+        let config = MarketplaceConfig { is_initialized: true, feature_flags: FEATURE_SUBSCRIPTIONS, ..MarketplaceConfig::default() };
+        let plan = SubscriptionPlan {
+            is_initialized: true,
+            model: model.key,
+            period_slots: 1_000,
+            tiers: vec![
+                SubscriptionTier { price: 1_000, ..SubscriptionTier::default() },
+                SubscriptionTier { price: 4_000, ..SubscriptionTier::default() },
+            ],
+            ..SubscriptionPlan::default()
+        };
+        // (当前档位, 目标档位, 剩余slot) -> (补交金额, 变更后的到期slot)；只剩一个slot时按向上取整补差价，
+        // 降级的余额折算为更长的剩余时间，已到期的订阅不能变更
+        for (from, to, remaining, expected) in [
+            (0, 1, 1_000, Ok((3_000, TEST_SLOT + 1_000))),
+            (0, 1, 1, Ok((3, TEST_SLOT + 1))),
+            (1, 0, 1, Ok((0, TEST_SLOT + 4))),
+            (0, 1, 0, Err(ProgramError::InvalidArgument)),
+        ] {
+            let subscriber = TestAccount::wallet(LAMPORTS_PER_SOL);
+            let subscription = Subscription {
+                is_initialized: true,
+                model: model.key,
+                subscriber: subscriber.key,
+                tier: from,
+                price: plan.tiers[from as usize].price,
+                period_slots: plan.period_slots,
+                paid_through_slot: TEST_SLOT + remaining,
+                ..Subscription::default()
+            };
+            let mut accounts = [
+                subscriber,
+                TestAccount::readonly(owner_key).writable(),
+                TestAccount::state(find_config_address(&program_id).0, &program_id, &config),
+                TestAccount::state(model.key, &program_id, &AIModel::unpack(&model.data).unwrap()),
+                TestAccount::state(find_subscription_plan_address(&model.key, &program_id).0, &program_id, &plan),
+                TestAccount::state(Pubkey::new_unique(), &program_id, &subscription),
+                TestAccount::readonly(system_program::id()),
+            ];
+            let result = change_tier(&program_id, &account_infos(&mut accounts), to);
+            let [subscriber, owner, _, _, _, subscription_account, _] = accounts;
+            match expected {
+                Ok((charge, paid_through_slot)) => {
+                    assert_eq!(result, Ok(()), "{} -> {} with {} slots left", from, to, remaining);
+                    assert_eq!((subscriber.lamports, owner.lamports), (LAMPORTS_PER_SOL - charge, charge));
+                    let subscription = Subscription::unpack(&subscription_account.data).unwrap();
+                    assert_eq!((subscription.tier, subscription.paid_through_slot), (to, paid_through_slot));
+                }
+                Err(error) => assert_eq!(result, Err(error)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_proration_tier_change() {
+        let change = |old_price, new_price, remaining| proration::tier_change(old_price, 1_000, new_price, 1_000, remaining);
+        // 升级：补交差价，剩余时间不变
+        assert_eq!(change(1_000, 4_000, 500), proration::TierChange { charge: 1_500, remaining_slots: 500 });
+        assert_eq!(change(1_000, 4_000, 1_000), proration::TierChange { charge: 3_000, remaining_slots: 1_000 });
+        assert_eq!(change(1_000, 4_000, 1), proration::TierChange { charge: 3, remaining_slots: 1 });
+        // 降级：不退款，余额折算为更长的剩余时间
+        assert_eq!(change(4_000, 1_000, 500), proration::TierChange { charge: 0, remaining_slots: 2_000 });
+        assert_eq!(change(3_000, 2_000, 1), proration::TierChange { charge: 0, remaining_slots: 1 });
+        // 剩余0个slot时既不收费也不延长
+        assert_eq!(change(1_000, 4_000, 0), proration::TierChange { charge: 0, remaining_slots: 0 });
+        assert_eq!(change(4_000, 1_000, 0), proration::TierChange { charge: 0, remaining_slots: 0 });
+        // 同价变更在整除时免费，剩余时间不因舍入缩短
+        for remaining in 0..=10 {
+            let result = proration::tier_change(7, 3, 7, 3, remaining);
+            assert!(result.remaining_slots >= remaining);
+            assert!(result.charge <= 1);
+        }
+        // 周期长度不同时按各自周期折算
+        assert_eq!(
+            proration::tier_change(1_000, 1_000, 1_000, 500, 250),
+            proration::TierChange { charge: 250, remaining_slots: 250 }
+        );
+    }
+
+    #[test]
+    fn test_subscription_grace_period() {
+        let subscription = Subscription {
+            is_initialized: true,
+            price: 1_000,
+            period_slots: 1_000,
+            start_slot: 100,
+            paid_through_slot: 1_100,
+            renewal_balance: 1_500,
+            ..Subscription::default()
+        };
+        let mut data = vec![0u8; Subscription::LEN];
+        subscription.pack_into_slice(&mut data);
+        assert_eq!(Subscription::unpack(&data), Ok(subscription.clone()));
+
+        // 到期前不能续订，超过宽限期后失效
+        let mut early = subscription.clone();
+        assert_eq!(early.renew(1_200, 1_000, 200, 1_099), Err(ProgramError::InvalidArgument));
+        assert!(!subscription.is_lapsed(1_299, 200));
+        assert!(subscription.is_lapsed(1_300, 200));
+        let mut lapsed = subscription.clone();
+        assert_eq!(lapsed.renew(1_200, 1_000, 200, 1_300), Err(ProgramError::InvalidArgument));
+
+        // 宽限期内续订保留开始时间，新周期紧接原到期时间，按档位当前价格扣款
+        let mut renewed = subscription.clone();
+        assert_eq!(renewed.renew(1_200, 1_000, 200, 1_250), Ok(()));
+        assert_eq!((renewed.start_slot, renewed.paid_through_slot), (100, 2_100));
+        assert_eq!((renewed.price, renewed.renewal_balance), (1_200, 300));
+        assert!(renewed.is_active(1_250));
+
+        // 余额不足时续订失败，状态不变
+        let mut unfunded = renewed.clone();
+        assert_eq!(unfunded.renew(1_200, 1_000, 200, 2_100), Err(ProgramError::InsufficientFunds));
+        assert_eq!(unfunded, renewed);
+    }
+
+    #[test]
+    fn test_cancel_subscription() {
+        let mut subscription = Subscription {
+            is_initialized: true,
+            price: 1_000,
+            period_slots: 1_000,
+            paid_through_slot: 1_100,
+            renewal_balance: 2_000,
+            ..Subscription::default()
+        };
+        assert_eq!(subscription.cancel(1_000, 42), Ok(2_000));
+        assert_eq!(subscription.renewal_balance, 0);
+        assert_eq!(subscription.cancel(1_000, 43), Err(ProgramError::InvalidArgument));
+
+        let mut data = vec![0u8; Subscription::LEN];
+        subscription.pack_into_slice(&mut data);
+        assert_eq!(Subscription::unpack(&data), Ok(subscription.clone()));
+
+        // 取消后访问保持到已付费期限，续订被跳过，到期即结束而没有宽限期
+        assert!(subscription.is_active(1_099));
+        assert!(!subscription.is_ended(1_099, 200));
+        assert!(subscription.is_ended(1_100, 200));
+        subscription.renewal_balance = 1_000;
+        assert_eq!(subscription.renew(1_000, 1_000, 200, 1_150), Err(ProgramError::InvalidArgument));
+
+        // 已经到期的订阅不能再取消
+        let mut expired = Subscription { paid_through_slot: 10, ..Subscription::default() };
+        assert_eq!(expired.cancel(10, 42), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_subscription_ended_log_roundtrip() {
+        for reason in [events::SubscriptionEndReason::Lapsed, events::SubscriptionEndReason::Canceled] {
+            let event = events::SubscriptionEnded {
+                subscription: Pubkey::new_unique(),
+                model: Pubkey::new_unique(),
+                subscriber: Pubkey::new_unique(),
+                reason,
+                paid_through_slot: 1_100,
+            };
+            let log = format!("Program log: {}", event.to_log());
+            assert_eq!(events::SubscriptionEnded::from_log(&log), Some(event));
+        }
+        assert_eq!(
+            events::SubscriptionEnded::from_log("SubscriptionEnded: subscription=x"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_license_revoked_log_roundtrip() {
+        let event = events::LicenseRevoked {
+            receipt: Pubkey::new_unique(),
+            model: Pubkey::new_unique(),
+            holder: Pubkey::new_unique(),
+            revoked_by: Pubkey::new_unique(),
+            edition: 7,
+            reason_hash: solana_program::hash::hash(b"breach notice 2026-03").to_bytes(),
+        };
+        let log = format!("Program log: {}", event.to_log());
+        assert_eq!(events::LicenseRevoked::from_log(&log), Some(event));
+        assert_eq!(events::LicenseRevoked::from_log("LicenseRevoked: receipt=x"), None);
+
+        let receipt = PurchaseReceipt {
+            is_initialized: true,
+            edition: 7,
+            revocable: true,
+            ..PurchaseReceipt::default()
+        };
+        let mut packed = vec![0u8; PurchaseReceipt::LEN];
+        receipt.pack_into_slice(&mut packed);
+        assert_eq!(PurchaseReceipt::unpack_from_slice(&packed), Ok(receipt));
+    }
+
+    #[test]
+    fn test_license_renewal() {
+        let tier = LicenseTier {
+            price: 500,
+            terms_uri: String::from("ar://terms"),
+            term_slots: 1_000,
+            ..LicenseTier::default()
+        };
+        let ai_model = AIModel {
+            license_tiers: vec![tier, LicenseTier::default()],
+            ..AIModel::default()
+        };
+        assert_eq!(ai_model.tier_term_slots(0), 1_000);
+        assert_eq!(ai_model.tier_term_slots(1), 0);
+        assert_eq!(ai_model.tier_term_slots(2), 0);
+
+        let mut receipt = PurchaseReceipt { expires_at_slot: 1_500, ..PurchaseReceipt::default() };
+        assert!(!receipt.is_expired(1_499));
+        assert!(receipt.is_expired(1_500));
+        // 提前续费从原到期slot顺延
+        receipt.renew(1_000, 1_200).unwrap();
+        assert_eq!(receipt.expires_at_slot, 2_500);
+        // 过期后续费从当前slot重新计算
+        receipt.renew(1_000, 4_000).unwrap();
+        assert_eq!(receipt.expires_at_slot, 5_000);
+        assert_eq!(receipt.renew(0, 4_000), Err(ProgramError::InvalidArgument));
+
+        let mut perpetual = PurchaseReceipt::default();
+        assert!(!perpetual.is_expired(u64::MAX));
+        assert_eq!(perpetual.renew(1_000, 0), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_usage_settlement() {
+        let mut counter = UsageCounter { total_requests: 120, ..UsageCounter::default() };
+        assert_eq!(counter.settle(0, 1_000), Err(ProgramError::InvalidArgument));
+        // 额度只够结算100个请求
+        assert_eq!(counter.settle(10, 1_005), Ok((100, 1_000)));
+        assert_eq!(counter.unsettled_requests(), 20);
+        assert_eq!(counter.settle(10, 5), Ok((0, 0)));
+        assert_eq!(counter.settle(10, 1_000), Ok((20, 200)));
+        assert_eq!(counter.unsettled_requests(), 0);
+
+        let mut credits = CreditAccount::default();
+        assert!(credits.is_sol());
+        credits.deposit(1_000).unwrap();
+        credits.spend(700).unwrap();
+        assert_eq!(credits.spend(301), Err(ProgramError::InsufficientFunds));
+        assert_eq!((credits.balance, credits.total_deposited, credits.total_spent), (300, 1_000, 700));
+        let usdc = CreditAccount { mint: Pubkey::new_unique(), ..credits.clone() };
+        assert!(!usdc.is_sol());
+        let mut packed = [0u8; CreditAccount::LEN];
+        usdc.pack_into_slice(&mut packed);
+        assert_eq!(CreditAccount::unpack_from_slice(&packed), Ok(usdc));
+        let (wallet, program_id) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_ne!(
+            find_credit_address(&wallet, &Pubkey::default(), &program_id),
+            find_credit_address(&wallet, &Pubkey::new_unique(), &program_id)
+        );
+
+        let event = events::UsageSettled {
+            receipt: Pubkey::new_unique(),
+            model: Pubkey::new_unique(),
+            buyer: Pubkey::new_unique(),
+            requests: 100,
+            unit_price: 10,
+            amount: 1_000,
+            fee: 25,
+            unsettled_requests: 20,
+            remaining_balance: 5,
+        };
+        let log = format!("Program log: {}", event.to_log());
+        assert_eq!(events::UsageSettled::from_log(&log), Some(event));
+    }
+
+    #[test]
+    fn test_purchase_reference() {
+        let reference = solana_program::hash::hash(b"PO-2024-00042").to_bytes();
+        let event = events::Purchased {
+            receipt: Pubkey::new_unique(),
+            model: Pubkey::new_unique(),
+            buyer: Pubkey::new_unique(),
+            tier: 1,
+            quantity: 5,
+            amount_paid: 250_000,
+            fee: 5_000,
+            edition: 9,
+            reference,
+        };
+        let log = format!("Program log: {}", event.to_log());
+        assert_eq!(events::Purchased::from_log(&log), Some(event));
+        assert_eq!(events::Purchased::from_log("Purchased: receipt=x"), None);
+
+        let model = Pubkey::new_unique();
+        let buyer = Pubkey::new_unique();
+        let receipt_key = Pubkey::new_unique();
+        let memo = sale_memo(&model, &buyer, 250_000, &NATIVE_PAYMENT_MINT, &receipt_key, &reference);
+        assert_eq!(
+            memo,
+            format!(
+                "ai-marketplace:purchase model={} buyer={} amount=250000 payment_mint={} receipt={} reference={}",
+                model,
+                buyer,
+                NATIVE_PAYMENT_MINT,
+                receipt_key,
+                Hash::new_from_array(reference)
+            )
+        );
+        let instruction = spl_memo_instruction(memo.as_bytes());
+        assert_eq!(instruction.program_id, SPL_MEMO_PROGRAM_ID);
+        assert!(instruction.accounts.is_empty());
+        assert_eq!(instruction.data, memo.as_bytes());
+
+        let receipt = PurchaseReceipt { is_initialized: true, reference, ..PurchaseReceipt::default() };
+        let mut packed = vec![0u8; PurchaseReceipt::LEN];
+        receipt.pack_into_slice(&mut packed);
+        assert_eq!(PurchaseReceipt::unpack_from_slice(&packed), Ok(receipt));
+    }
+
+    #[test]
+    fn test_close_account_to_rent_payer() {
+        let program_id = Pubkey::new_unique();
+        let receipt = PurchaseReceipt {
+            is_initialized: true,
+            buyer: Pubkey::new_unique(),
+            rent_payer: Pubkey::new_unique(),
+            ..PurchaseReceipt::default()
+        };
+        let mut packed = vec![0u8; PurchaseReceipt::LEN];
+        receipt.pack_into_slice(&mut packed);
+        assert_eq!(PurchaseReceipt::unpack_from_slice(&packed), Ok(receipt.clone()));
+
+        let receipt_key = Pubkey::new_unique();
+        let system_id = system_program::id();
+        let (mut receipt_lamports, mut holder_lamports, mut payer_lamports) = (2_000_000, 0, 0);
+        let mut holder_data = vec![];
+        let mut payer_data = vec![];
+        let receipt_account =
+            AccountInfo::new(&receipt_key, false, true, &mut receipt_lamports, &mut packed, &program_id, false, 0);
+        let holder_account =
+            AccountInfo::new(&receipt.buyer, true, true, &mut holder_lamports, &mut holder_data, &system_id, false, 0);
+        let payer_account =
+            AccountInfo::new(&receipt.rent_payer, false, true, &mut payer_lamports, &mut payer_data, &system_id, false, 0);
+
+        // 出资人未随指令传入时不能关闭，也不会把租金退给持有者
+        let accounts = [receipt_account.clone(), holder_account.clone()];
+        assert_eq!(
+            close_account_to_rent_payer(&receipt_account, &receipt.rent_payer, &accounts),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        let accounts = [receipt_account.clone(), holder_account.clone(), payer_account.clone()];
+        assert_eq!(close_account_to_rent_payer(&receipt_account, &receipt.rent_payer, &accounts), Ok(()));
+        assert_eq!(payer_account.lamports(), 2_000_000);
+        assert_eq!(holder_account.lamports(), 0);
+        assert_eq!(receipt_account.lamports(), 0);
+        assert!(receipt_account.data.borrow().iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn test_loyalty_points() {
+        let treasury = Treasury {
+            loyalty_accrual_bps: 100,
+            loyalty_redemption_rate: 1_000,
+            ..Treasury::default()
+        };
+        assert_eq!(treasury.loyalty_points_for(250_000), 2_500);
+        assert_eq!(treasury.loyalty_points_for(99), 0);
+        assert_eq!(treasury.loyalty_discount(0, 0), Ok(0));
+        assert_eq!(treasury.loyalty_discount(5, 5_000), Ok(5_000));
+        // 抵扣不能超过手续费
+        assert_eq!(treasury.loyalty_discount(6, 5_000), Err(ProgramError::InvalidArgument));
+        assert_eq!(Treasury::default().loyalty_discount(1, 5_000), Err(ProgramError::InvalidArgument));
+
+        let mut buyer_stats = BuyerStats { loyalty_points: 10, ..BuyerStats::default() };
+        buyer_stats.redeem_loyalty_points(4).unwrap();
+        assert_eq!(buyer_stats.loyalty_points, 6);
+        assert_eq!(buyer_stats.redeem_loyalty_points(7), Err(ProgramError::InsufficientFunds));
+
+        let mut packed = [0u8; Treasury::LEN];
+        treasury.pack_into_slice(&mut packed);
+        assert_eq!(Treasury::unpack_from_slice(&packed), Ok(treasury));
+    }
+
+    #[test]
+    fn test_achievements() {
+        let mut seller_stats = SellerStats { total_sales: 1, ..SellerStats::default() };
+        seller_stats.refresh_achievements();
+        assert_eq!(seller_stats.achievements, Achievement::FirstSale.flag());
+
+        // 9条五星评价还不够，第10条之后平均4.6星达标
+        for _ in 0..9 {
+            seller_stats.record_rating(5);
+        }
+        assert!(!seller_stats.is_top_rated());
+        seller_stats.record_rating(1);
+        assert!(seller_stats.is_top_rated());
+        // 评分下降后已达成的成就不撤销
+        seller_stats.record_rating(1);
+        assert!(!seller_stats.is_top_rated());
+        assert_ne!(seller_stats.achievements & Achievement::TopRated.flag(), 0);
+        assert_eq!(seller_stats.achievements & Achievement::HundredSales.flag(), 0);
+
+        let mut claimed = 0;
+        assert_eq!(
+            claim_achievement_flag(seller_stats.achievements, &mut claimed, Achievement::HundredSales),
+            Err(ProgramError::InvalidArgument)
+        );
+        claim_achievement_flag(seller_stats.achievements, &mut claimed, Achievement::TopRated).unwrap();
+        assert_eq!(
+            claim_achievement_flag(seller_stats.achievements, &mut claimed, Achievement::TopRated),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+
+        let mut buyer_stats = BuyerStats { total_purchases: 1, ..BuyerStats::default() };
+        buyer_stats.refresh_achievements();
+        assert_eq!(buyer_stats.achievements, Achievement::FirstPurchase.flag());
+        assert!(!Achievement::FirstPurchase.is_seller_achievement());
+        for val in 0..4 {
+            assert_eq!(Achievement::from_u8(val).unwrap() as u8, val);
+        }
+        assert_eq!(Achievement::from_u8(4), Err(ProgramError::InvalidInstructionData));
+
+        let mut packed = [0u8; SellerStats::LEN];
+        seller_stats.claimed_achievements = claimed;
+        seller_stats.pack_into_slice(&mut packed);
+        assert_eq!(SellerStats::unpack_from_slice(&packed), Ok(seller_stats));
+    }
+
+    #[test]
+    fn test_airdrop_campaign() {
+        let mut campaign = AirdropCampaign {
+            is_initialized: true,
+            model: Pubkey::new_unique(),
+            seller: Pubkey::new_unique(),
+            campaign_id: 3,
+            tier: 1,
+            max_recipients: 5,
+            airdropped: 0,
+            created_at: 10,
+            updated_at: 10,
+            merkle_root: [0u8; 32],
+        };
+        campaign.record_airdrops(3).unwrap();
+        assert_eq!(campaign.record_airdrops(3), Err(ProgramError::InvalidArgument));
+        campaign.record_airdrops(2).unwrap();
+        assert_eq!(campaign.airdropped, 5);
+
+        let mut packed = [0u8; AirdropCampaign::LEN];
+        campaign.pack_into_slice(&mut packed);
+        assert_eq!(AirdropCampaign::unpack_from_slice(&packed), Ok(campaign.clone()));
+
+        // 同一活动中每个钱包只有一个空投凭证地址，不同活动互不影响
+        let program_id = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique();
+        let (campaign_key, _) = find_airdrop_campaign_address(&campaign.model, campaign.campaign_id, &program_id);
+        let (other_campaign, _) = find_airdrop_campaign_address(&campaign.model, campaign.campaign_id + 1, &program_id);
+        assert_eq!(
+            find_airdrop_receipt_address(&campaign_key, &wallet, &program_id),
+            find_airdrop_receipt_address(&campaign_key, &wallet, &program_id)
+        );
+        assert_ne!(
+            find_airdrop_receipt_address(&campaign_key, &wallet, &program_id).0,
+            find_airdrop_receipt_address(&other_campaign, &wallet, &program_id).0
+        );
+    }
+
+    #[test]
+    fn test_merkle_airdrop_claim() {
+        let program_id = Pubkey::new_unique();
+        let (campaign_key, _) = find_airdrop_campaign_address(&Pubkey::new_unique(), 1, &program_id);
+        let wallets: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let leaves: Vec<[u8; 32]> = wallets
+            .iter()
+            .map(|wallet| merkle::leaf(&[campaign_key.as_ref(), wallet.as_ref()]))
+            .collect();
+        let campaign = AirdropCampaign {
+            is_initialized: true,
+            merkle_root: merkle::root(&leaves),
+            ..AirdropCampaign::default()
+        };
+
+        for (index, wallet) in wallets.iter().enumerate() {
+            let proof = merkle::proof(&leaves, index);
+            assert!(campaign.verify_recipient(&campaign_key, wallet, &proof));
+            // 证明与钱包、活动绑定
+            assert!(!campaign.verify_recipient(&campaign_key, &Pubkey::new_unique(), &proof));
+            assert!(!campaign.verify_recipient(&Pubkey::new_unique(), wallet, &proof));
+        }
+        let mut bad_proof = merkle::proof(&leaves, 0);
+        bad_proof[0][0] ^= 1;
+        assert!(!campaign.verify_recipient(&campaign_key, &wallets[0], &bad_proof));
+        // 未设置Merkle根的活动只能由卖家推送
+        let push_only = AirdropCampaign { merkle_root: [0u8; 32], ..campaign };
+        assert!(!push_only.verify_recipient(&campaign_key, &wallets[0], &merkle::proof(&leaves, 0)));
+
+        let too_long = MarketplaceInstruction::ClaimAirdrop { proof: vec![[0u8; 32]; MAX_MERKLE_PROOF_LEN + 1] }.pack();
+        assert_eq!(MarketplaceInstruction::unpack(&too_long), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_sale_price_sale_window() {
+        // 价格源换算价和升级折扣价仍要等公开发售开始，只有卖家签名的订单和预售价可以提前成交
+        assert_eq!(SalePrice::Listed.fixed(), None);
+        for (price, waived) in [
+            (SalePrice::Listed, false),
+            (SalePrice::Oracle(700), false),
+            (SalePrice::Upgrade(700), false),
+            (SalePrice::SellerSigned(700), true),
+            (SalePrice::Presale(700), true),
+        ] {
+            assert_eq!(price.waives_sale_window(), waived, "{:?}", price);
+            if price != SalePrice::Listed {
+                assert_eq!(price.fixed(), Some(700));
+            }
+        }
+    }
+
+    #[test]
+    fn test_presale_allowlist() {
+        let model = Pubkey::new_unique();
+        let wallets: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let leaves: Vec<[u8; 32]> = wallets
+            .iter()
+            .map(|wallet| merkle::leaf(&[model.as_ref(), wallet.as_ref(), &3u32.to_le_bytes()]))
+            .collect();
+        let tier = |price| LicenseTier { price, ..LicenseTier::default() };
+        let ai_model = AIModel {
+            per_seat: true,
+            license_tiers: vec![tier(1_000), tier(2_500)],
+            volume_discounts: vec![VolumeDiscount { min_seats: 3, discount_bps: 1_000 }],
+            presale_merkle_root: merkle::root(&leaves),
+            presale_price: 300,
+            public_sale_slot: 1_000,
+            ..AIModel::default()
+        };
+
+        let proof = merkle::proof(&leaves, 1);
+        assert_eq!(ai_model.presale_total(&model, &wallets[1], 3, &proof, 0, 2, 999), Ok(600));
+        // 其他档位按相对档位0的标价比例折算，批量折扣同样适用
+        assert_eq!(ai_model.presale_total(&model, &wallets[1], 3, &proof, 1, 1, 999), Ok(750));
+        assert_eq!(ai_model.presale_total(&model, &wallets[1], 3, &proof, 1, 3, 999), Ok(2_025));
+        assert_eq!(ai_model.presale_total(&model, &wallets[1], 3, &proof, 2, 1, 999), Err(ProgramError::InvalidArgument));
+        // 限购席位数是叶子的一部分，虚报的allocation无法通过证明
+        assert_eq!(
+            ai_model.presale_total(&model, &wallets[1], 10, &proof, 0, 1, 999),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            ai_model.presale_total(&model, &Pubkey::new_unique(), 3, &proof, 0, 1, 999),
+            Err(ProgramError::InvalidArgument)
+        );
+        // 公开发售开始后预售结束，标价购买解禁
+        assert_eq!(
+            ai_model.presale_total(&model, &wallets[1], 3, &proof, 0, 1, 1_000),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(ai_model.require_public_sale(999), Err(ProgramError::InvalidArgument));
+        assert_eq!(ai_model.require_public_sale(1_000), Ok(()));
+
+        // 多次预售购买累计不能超过限购席位数
+        let mut throttle = PurchaseThrottle::default();
+        assert_eq!(throttle.record_presale(2, 3), Ok(()));
+        assert_eq!(throttle.record_presale(2, 3), Err(ProgramError::InvalidArgument));
+        assert_eq!(throttle.record_presale(1, 3), Ok(()));
+        assert_eq!(throttle.presale_seats, 3);
+
+        let no_presale = AIModel::default();
+        assert_eq!(no_presale.require_public_sale(0), Ok(()));
+        assert_eq!(
+            no_presale.presale_total(&model, &wallets[0], 1, &[], 0, 1, 0),
+            Err(ProgramError::InvalidArgument)
+        );
     }
-}
 
-// 实现Pack trait来序列化和反序列化AIModel
-impl Pack for AIModel {
-    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 1024;
+    #[test]
+    fn test_listing_announcement_payload() {
+        let announcement = ListingAnnouncement {
+            kind: ListingAnnouncementKind::Sold,
+            model: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            artifact_hash: [4u8; 32],
+            price: 1_500,
+            editions_minted: 12,
+            total_sales: 12,
+            receipt: Pubkey::new_unique(),
+            buyer: Pubkey::new_unique(),
+            edition: 12,
+            amount_paid: 1_500,
+        };
+        let payload = announcement.to_payload();
+        assert_eq!(payload.len(), ListingAnnouncement::LEN);
+        assert_eq!(ListingAnnouncement::from_payload(&payload), Ok(announcement));
+        let mut future_version = payload.clone();
+        future_version[0] = ListingAnnouncement::VERSION + 1;
+        assert_eq!(ListingAnnouncement::from_payload(&future_version), Err(ProgramError::InvalidInstructionData));
 
-    fn pack_into_slice(&self, output: &mut [u8]) {
-        let mut offset = 0;
-        output[offset] = self.is_initialized as u8;
-        offset += 1;
-        output[offset..offset+32].copy_from_slice(self.name.as_bytes());
-        offset += 32;
-        output[offset..offset+32].copy_from_slice(self.description.as_bytes());
-        offset += 32;
-        output[offset..offset+8].copy_from_slice(&self.owner.to_bytes());
-        offset += 8;
-        output[offset..offset+8].copy_from_slice(&self.price.to_le_bytes());
-        offset += 8;
-        output[offset..offset+1024].copy_from_slice(&self.model_file);
+        let program_id = Pubkey::new_unique();
+        let (emitter, _) = find_wormhole_emitter_address(&program_id);
+        let payer = Pubkey::new_unique();
+        let message = Pubkey::new_unique();
+        let instruction = wormhole_post_message_instruction(&payer, &message, &emitter, 7, &payload);
+        assert_eq!(instruction.program_id, WORMHOLE_CORE_BRIDGE_PROGRAM_ID);
+        assert_eq!(instruction.data[0], 1);
+        assert_eq!(instruction.data[1..5], 7u32.to_le_bytes());
+        assert_eq!(instruction.data[5..9], (payload.len() as u32).to_le_bytes());
+        assert_eq!(instruction.data[9..9 + payload.len()], payload[..]);
+        assert_eq!(*instruction.data.last().unwrap(), WORMHOLE_CONSISTENCY_FINALIZED);
+        assert!(instruction.accounts[1].is_signer && instruction.accounts[2].is_signer && instruction.accounts[4].is_signer);
+        assert_eq!(instruction.accounts[2].pubkey, emitter);
     }
 
-    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
-        let mut offset = 0;
-        let is_initialized = match input.get(offset) {
-            Some(val) => *val != 0,
-            None => return Err(ProgramError::InvalidAccountData),
+    #[test]
+    fn test_oracle_pricing_fallback() {
+        // 25美元，价格源报1美元 = 6_500_000 lamports
+        let mut ai_model = AIModel {
+            price_feed: Pubkey::new_unique(),
+            quote_price: 25_000_000,
+            max_staleness_slots: 100,
+            max_confidence_bps: 200,
+            ..AIModel::default()
         };
-        offset += 1;
-        let name = match String::from_utf8(input[offset..offset+32].to_vec()) {
-            Ok(val) => val,
-            Err(_) => return Err(ProgramError::InvalidAccountData),
+        let feed = PriceFeed {
+            is_initialized: true,
+            feed_id: 1,
+            price: 6_500_000,
+            confidence: 65_000,
+            published_slot: 1_000,
+            ..PriceFeed::default()
         };
-        offset += 32;
-        let description = match String::from_utf8(input[offset..offset+32].to_vec()) {
-            Ok(val) => val,
-            Err(_) => return Err(ProgramError::InvalidAccountData),
+        assert_eq!(ai_model.oracle_total(&feed, 0, 1, 1_100), Ok(162_500_000));
+        assert_eq!(ai_model.oracle_total(&feed, 0, 2, 1_000), Err(ProgramError::InvalidArgument));
+
+        // 过期或置信区间过宽时没有备用价格则拒绝成交
+        assert_eq!(ai_model.oracle_total(&feed, 0, 1, 1_101), Err(ProgramError::InvalidArgument));
+        let uncertain = PriceFeed { confidence: 130_001, ..feed.clone() };
+        assert!(uncertain.is_degraded(100, 200, 1_000));
+        assert_eq!(ai_model.oracle_total(&uncertain, 0, 1, 1_000), Err(ProgramError::InvalidArgument));
+        assert!(!uncertain.is_degraded(100, 0, 1_000));
+
+        // 设置备用价格后降级时按备用价格成交
+        ai_model.fallback_price = 200_000_000;
+        ai_model.per_seat = true;
+        assert_eq!(ai_model.oracle_total(&feed, 0, 3, 5_000), Ok(600_000_000));
+        assert_eq!(ai_model.oracle_total(&uncertain, 0, 1, 1_000), Ok(200_000_000));
+        assert_eq!(ai_model.oracle_total(&feed, 0, 1, 1_000), Ok(162_500_000));
+
+        // 报价按档位0计，其他档位按相对档位0的标价比例折算，批量折扣同样适用
+        let tier = |price| LicenseTier { price, ..LicenseTier::default() };
+        ai_model.license_tiers = vec![tier(1_000), tier(3_000)];
+        ai_model.volume_discounts = vec![VolumeDiscount { min_seats: 2, discount_bps: 500 }];
+        assert_eq!(ai_model.oracle_total(&feed, 1, 1, 1_000), Ok(487_500_000));
+        assert_eq!(ai_model.oracle_total(&feed, 0, 2, 1_000), Ok(308_750_000));
+        assert_eq!(ai_model.oracle_total(&feed, 2, 1, 1_000), Err(ProgramError::InvalidArgument));
+        ai_model.license_tiers.clear();
+        ai_model.volume_discounts.clear();
+
+        let mut packed = [0u8; PriceFeed::LEN];
+        feed.pack_into_slice(&mut packed);
+        assert_eq!(PriceFeed::unpack_from_slice(&packed), Ok(feed));
+    }
+
+    #[test]
+    fn test_drop_throttle() {
+        let mut ai_model = AIModel { drop_max_per_slot: 2, ..AIModel::default() };
+        assert!(ai_model.is_drop());
+        for slot in [10, 10] {
+            ai_model.record_drop_purchase(slot).unwrap();
+            ai_model.record_sale(100, slot).unwrap();
+        }
+        assert_eq!(ai_model.record_drop_purchase(10), Err(ProgramError::InvalidArgument));
+        // 新的slot重新计数
+        ai_model.record_drop_purchase(11).unwrap();
+        assert_eq!(ai_model.drop_slot_purchases, 1);
+        assert!(!AIModel::default().is_drop());
+
+        let mut throttle = PurchaseThrottle { is_initialized: true, ..PurchaseThrottle::default() };
+        throttle.record_purchase(100, 50).unwrap();
+        assert_eq!(throttle.record_purchase(149, 50), Err(ProgramError::InvalidArgument));
+        throttle.record_purchase(150, 50).unwrap();
+        assert_eq!(throttle.purchases, 2);
+
+        let mut packed = [0u8; PurchaseThrottle::LEN];
+        throttle.pack_into_slice(&mut packed);
+        assert_eq!(PurchaseThrottle::unpack_from_slice(&packed), Ok(throttle));
+    }
+
+    #[test]
+    fn test_gateway_token_check() {
+        let wallet = Pubkey::new_unique();
+        let network = Pubkey::new_unique();
+        let token = |parent: bool, state: u8, expire_time: Option<i64>| {
+            let mut data = vec![0u8];
+            if parent {
+                data.push(1);
+                data.extend_from_slice(Pubkey::new_unique().as_ref());
+            } else {
+                data.push(0);
+            }
+            data.extend_from_slice(wallet.as_ref());
+            data.push(0);
+            data.extend_from_slice(network.as_ref());
+            data.extend_from_slice(Pubkey::new_unique().as_ref());
+            data.push(state);
+            match expire_time {
+                Some(expire_time) => {
+                    data.push(1);
+                    data.extend_from_slice(&expire_time.to_le_bytes());
+                }
+                None => data.push(0),
+            }
+            data
         };
-        offset += 32;
-        let owner = match Pubkey::new_from_array(input[offset..offset+32].try_into().unwrap()) {
-            Ok(val) => val,
-            Err(_) => return Err(ProgramError::InvalidAccountData),
+
+        assert_eq!(check_gateway_token(&token(false, 0, None), &wallet, &network, 100), Ok(()));
+        assert_eq!(check_gateway_token(&token(true, 0, Some(101)), &wallet, &network, 100), Ok(()));
+        // 过期、吊销或冻结的通行证无效
+        assert_eq!(check_gateway_token(&token(false, 0, Some(100)), &wallet, &network, 100), Err(ProgramError::InvalidArgument));
+        assert_eq!(check_gateway_token(&token(false, 1, None), &wallet, &network, 100), Err(ProgramError::InvalidArgument));
+        assert_eq!(check_gateway_token(&token(false, 2, None), &wallet, &network, 100), Err(ProgramError::InvalidArgument));
+        // 通行证必须属于买家并由模型要求的网关网络签发
+        assert_eq!(
+            check_gateway_token(&token(false, 0, None), &Pubkey::new_unique(), &network, 100),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            check_gateway_token(&token(false, 0, None), &wallet, &Pubkey::new_unique(), 100),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(check_gateway_token(&[0u8; 10], &wallet, &network, 100), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_charity_split() {
+        let ai_model = AIModel { charity: Pubkey::new_unique(), charity_bps: 500, ..AIModel::default() };
+        assert_eq!(ai_model.charity_donation(1_000_000), 50_000);
+        assert_eq!(AIModel::default().charity_donation(1_000_000), 0);
+        let buyer_stats = BuyerStats { charity: ai_model.charity, charity_bps: 100, ..BuyerStats::default() };
+        assert_eq!(buyer_stats.charity_donation(1_000_000), 10_000);
+        assert_eq!(buyer_stats.charity_donation(99), 0);
+
+        let mut charity = Charity { is_initialized: true, wallet: Pubkey::new_unique(), active: true, ..Charity::default() };
+        charity.record_donation(50_000).unwrap();
+        charity.record_donation(10_000).unwrap();
+        assert_eq!((charity.balance, charity.total_donations, charity.donation_count), (60_000, 60_000, 2));
+        charity.balance = u64::MAX;
+        assert_eq!(charity.record_donation(1), Err(ProgramError::ArithmeticOverflow));
+
+        let mut packed = [0u8; Charity::LEN];
+        charity.pack_into_slice(&mut packed);
+        assert_eq!(Charity::unpack_from_slice(&packed), Ok(charity));
+        let mut packed = [0u8; BuyerStats::LEN];
+        buyer_stats.pack_into_slice(&mut packed);
+        assert_eq!(BuyerStats::unpack_from_slice(&packed), Ok(buyer_stats));
+
+        // 比例和机构必须同时设置或同时清零
+        let program_id = Pubkey::new_unique();
+        assert_eq!(check_charity_choice(&program_id, None, &Pubkey::default(), 0), Ok(()));
+        assert_eq!(check_charity_choice(&program_id, None, &Pubkey::default(), 100), Err(ProgramError::InvalidArgument));
+        assert_eq!(check_charity_choice(&program_id, None, &Pubkey::new_unique(), 0), Err(ProgramError::InvalidArgument));
+        assert_eq!(
+            check_charity_choice(&program_id, None, &Pubkey::new_unique(), 10_001),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            check_charity_choice(&program_id, None, &Pubkey::new_unique(), 100),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+
+    #[test]
+    fn test_proceeds_withholding() {
+        let destination = Pubkey::new_unique();
+        let mut vault = ProceedsVault { is_initialized: true, seller: Pubkey::new_unique(), ..ProceedsVault::default() };
+        assert_eq!(vault.withholding_for(1_000_000), 0);
+        // 比例和目标账户必须同时设置或同时清零
+        assert_eq!(vault.set_withholding(destination, 0), Err(ProgramError::InvalidArgument));
+        assert_eq!(vault.set_withholding(Pubkey::default(), 100), Err(ProgramError::InvalidArgument));
+        assert_eq!(vault.set_withholding(destination, 10_001), Err(ProgramError::InvalidArgument));
+
+        vault.set_withholding(destination, 2_400).unwrap();
+        assert_eq!(vault.withholding_for(1_000_000), 240_000);
+        vault.record_withholding(240_000).unwrap();
+        assert_eq!((vault.withheld_balance, vault.total_withheld, vault.balance), (240_000, 240_000, 0));
+
+        // 待释放的预扣款只能释放给原目标账户，释放前不能更换或取消
+        assert_eq!(vault.set_withholding(Pubkey::new_unique(), 2_400), Err(ProgramError::InvalidArgument));
+        assert_eq!(vault.set_withholding(Pubkey::default(), 0), Err(ProgramError::InvalidArgument));
+        vault.set_withholding(destination, 1_000).unwrap();
+
+        let mut packed = [0u8; ProceedsVault::LEN];
+        vault.pack_into_slice(&mut packed);
+        assert_eq!(ProceedsVault::unpack_from_slice(&packed), Ok(vault.clone()));
+
+        vault.withheld_balance = 0;
+        vault.set_withholding(Pubkey::default(), 0).unwrap();
+        assert_eq!(vault.withholding_for(1_000_000), 0);
+    }
+
+    #[test]
+    fn test_ai_model_view() {
+        let owner = Pubkey::new_unique();
+        let mut data = vec![0u8; AIModel::LEN];
+        assert_eq!(AIModelView::new(&data).err(), Some(ProgramError::UninitializedAccount));
+        assert_eq!(AIModelView::new(&data[1..]).err(), Some(ProgramError::InvalidAccountData));
+
+        data[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&AIModel::DISCRIMINATOR);
+        data[ACCOUNT_DISCRIMINATOR_LEN] = 1;
+        data[AIModelView::OWNER_OFFSET..AIModelView::OWNER_OFFSET + 32].copy_from_slice(owner.as_ref());
+        data[AIModelView::PRICE_OFFSET..AIModelView::PRICE_OFFSET + 8].copy_from_slice(&1_500_000u64.to_le_bytes());
+        let view = AIModelView::new(&data).unwrap();
+        assert_eq!(view.owner(), owner);
+        assert_eq!(view.price(), 1_500_000);
+    }
+
+    #[test]
+    fn test_account_discriminator() {
+        let bond = SellerBond {
+            is_initialized: true,
+            seller: Pubkey::new_unique(),
+            staked: 5_000_000,
+            ..Default::default()
         };
-        offset += 32;
-        let price = u64::from_le_bytes(input[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let model_file = input[offset..offset+1024].to_vec();
-        Ok(Self {
-            is_initialized,
-            name,
-            description,
+        let mut data = vec![0u8; SellerBond::LEN];
+        SellerBond::pack(bond.clone(), &mut data).unwrap();
+        assert_eq!(data[..ACCOUNT_DISCRIMINATOR_LEN], SellerBond::DISCRIMINATOR);
+        assert_eq!(SellerBond::unpack(&data), Ok(bond));
+
+        // 换成其他类型的标识后按SellerBond加载必须失败
+        data[..ACCOUNT_DISCRIMINATOR_LEN].copy_from_slice(&Offer::DISCRIMINATOR);
+        assert_eq!(SellerBond::unpack(&data), Err(ProgramError::InvalidAccountData));
+        // 已初始化但标识为全零的数据同样拒绝
+        data[..ACCOUNT_DISCRIMINATOR_LEN].fill(0);
+        assert_eq!(SellerBond::unpack(&data), Err(ProgramError::InvalidAccountData));
+
+        // 新分配的全零账户仍按未初始化账户解码
+        let fresh = vec![0u8; SellerBond::LEN];
+        assert!(!SellerBond::unpack_unchecked(&fresh).unwrap().is_initialized);
+        assert_eq!(SellerBond::unpack(&fresh), Err(ProgramError::UninitializedAccount));
+    }
+
+    #[test]
+    fn test_ai_model_from_slice() {
+        let mut model = AIModel {
+            is_initialized: true,
+            owner: Pubkey::new_unique(),
+            price: 2_000_000,
+            ..Default::default()
+        };
+        let pack = |model: &AIModel| {
+            let mut data = vec![0u8; AIModel::LEN];
+            AIModel::pack(model.clone(), &mut data).unwrap();
+            data
+        };
+
+        let data = pack(&model);
+        assert_eq!(AIModel::owner_from_slice(&data), Ok(model.owner));
+        assert_eq!(AIModel::price_from_slice(&data), Ok(2_000_000));
+        assert_eq!(AIModel::status_from_slice(&data), Ok(ModelStatus::Active));
+
+        model.approval_required = true;
+        assert_eq!(AIModel::status_from_slice(&pack(&model)), Ok(ModelStatus::PendingApproval));
+        model.approved_at = TEST_UNIX_TIMESTAMP;
+        assert_eq!(AIModel::status_from_slice(&pack(&model)), Ok(ModelStatus::Active));
+        model.deprecated = true;
+        assert_eq!(AIModel::status_from_slice(&pack(&model)), Ok(ModelStatus::Deprecated));
+
+        assert_eq!(AIModel::owner_from_slice(&data[..AIModel::LEN - 1]), Err(ProgramError::InvalidAccountData));
+        assert_eq!(AIModel::status_from_slice(&[0u8; AIModel::LEN]), Err(ProgramError::UninitializedAccount));
+    }
+
+    #[test]
+    fn test_state_stack_budget() {
+        const BPF_STACK_FRAME_SIZE: usize = 4096;
+        // settle_sale在同一帧中按值持有的状态，AIModel装箱后只占一个指针，须给其他局部变量留出一半余量
+        let settle_sale_state = std::mem::size_of::<Box<AIModel>>()
+            + std::mem::size_of::<SaleAccounts>()
+            + std::mem::size_of::<PurchaseThrottle>()
+            + std::mem::size_of::<Treasury>()
+            + std::mem::size_of::<BuyerStats>()
+            + std::mem::size_of::<Charity>()
+            + std::mem::size_of::<SellerStats>()
+            + std::mem::size_of::<PurchaseReceipt>();
+        assert!(settle_sale_state <= BPF_STACK_FRAME_SIZE / 2, "settle_sale state uses {} bytes", settle_sale_state);
+        // unpack_ai_model_boxed的帧内最多同时存在解码结果和待装箱的值
+        assert!(
+            2 * std::mem::size_of::<AIModel>() <= BPF_STACK_FRAME_SIZE / 2,
+            "AIModel uses {} bytes",
+            std::mem::size_of::<AIModel>()
+        );
+        // 其余账户由处理程序按值解包，单个处理程序通常同时持有三四个，每个不得超过帧的八分之一；
+        // 超出的类型需要像AIModel那样装箱或改用视图读取
+        for (name, size) in [
+            ("PriceFeed", std::mem::size_of::<PriceFeed>()),
+            ("PurchaseReceipt", std::mem::size_of::<PurchaseReceipt>()),
+            ("SellerStats", std::mem::size_of::<SellerStats>()),
+            ("PurchaseThrottle", std::mem::size_of::<PurchaseThrottle>()),
+            ("Charity", std::mem::size_of::<Charity>()),
+            ("BuyerStats", std::mem::size_of::<BuyerStats>()),
+            ("BuyerReceiptsPage", std::mem::size_of::<BuyerReceiptsPage>()),
+            ("ModelVersion", std::mem::size_of::<ModelVersion>()),
+            ("ChangelogEntry", std::mem::size_of::<ChangelogEntry>()),
+            ("Relationship", std::mem::size_of::<Relationship>()),
+            ("Category", std::mem::size_of::<Category>()),
+            ("CategoryRegistry", std::mem::size_of::<CategoryRegistry>()),
+            ("FeaturedList", std::mem::size_of::<FeaturedList>()),
+            ("ProvenanceVerifier", std::mem::size_of::<ProvenanceVerifier>()),
+            ("ProvenanceAttestation", std::mem::size_of::<ProvenanceAttestation>()),
+            ("InferenceCommitment", std::mem::size_of::<InferenceCommitment>()),
+            ("InferenceRequest", std::mem::size_of::<InferenceRequest>()),
+            ("Worker", std::mem::size_of::<Worker>()),
+            ("RentalStream", std::mem::size_of::<RentalStream>()),
+            ("Organization", std::mem::size_of::<Organization>()),
+            ("Commission", std::mem::size_of::<Commission>()),
+            ("SubscriptionPlan", std::mem::size_of::<SubscriptionPlan>()),
+            ("Subscription", std::mem::size_of::<Subscription>()),
+            ("RoleAssignment", std::mem::size_of::<RoleAssignment>()),
+            ("UsageCounter", std::mem::size_of::<UsageCounter>()),
+            ("CreditAccount", std::mem::size_of::<CreditAccount>()),
+            ("AirdropCampaign", std::mem::size_of::<AirdropCampaign>()),
+            ("OrderNonces", std::mem::size_of::<OrderNonces>()),
+            ("OrderFill", std::mem::size_of::<OrderFill>()),
+            ("LocalizedMetadata", std::mem::size_of::<LocalizedMetadata>()),
+            ("Review", std::mem::size_of::<Review>()),
+            ("ProceedsVault", std::mem::size_of::<ProceedsVault>()),
+            ("SaleRecord", std::mem::size_of::<SaleRecord>()),
+            ("SalesLedgerPage", std::mem::size_of::<SalesLedgerPage>()),
+            ("DisputeEvidence", std::mem::size_of::<DisputeEvidence>()),
+            ("Dispute", std::mem::size_of::<Dispute>()),
+            ("MarketplaceConfig", std::mem::size_of::<MarketplaceConfig>()),
+            ("RecoveryRequest", std::mem::size_of::<RecoveryRequest>()),
+            ("Treasury", std::mem::size_of::<Treasury>()),
+            ("SellerBond", std::mem::size_of::<SellerBond>()),
+            ("Listing", std::mem::size_of::<Listing>()),
+            ("Offer", std::mem::size_of::<Offer>()),
+            ("Escrow", std::mem::size_of::<Escrow>()),
+        ] {
+            assert!(size <= BPF_STACK_FRAME_SIZE / 8, "{} uses {} bytes", name, size);
+        }
+    }
+
+    const ACCOUNT_LAYOUT_SNAPSHOTS: &str = include_str!("snapshots/account_layouts.txt");
+
+    // 每种账户类型的规范实例：除 is_initialized 外再填首尾两个字段，
+    // 任何字段增删、换序或长度变化都会改变序列化后的字节
+    fn canonical_accounts() -> Vec<(&'static str, Vec<u8>)> {
+        fn packed<T: Pack>(state: T) -> Vec<u8> {
+            let mut data = vec![0u8; T::LEN];
+            T::pack(state, &mut data).unwrap();
+            data
+        }
+
+        vec![
+            (
+                "AIModel",
+                packed(AIModel {
+                    is_initialized: true,
+                    name: "first".to_string(),
+                    charity_bps: 0x2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "PriceFeed",
+                packed(PriceFeed {
+                    is_initialized: true,
+                    feed_id: 0x1111_1111_1111_1111,
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "PurchaseReceipt",
+                packed(PurchaseReceipt {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    rent_payer: Pubkey::new_from_array([0x22; 32]),
+                    ..Default::default()
+                }),
+            ),
+            (
+                "SellerStats",
+                packed(SellerStats {
+                    is_initialized: true,
+                    seller: Pubkey::new_from_array([0x11; 32]),
+                    claimed_achievements: 0x2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "PurchaseThrottle",
+                packed(PurchaseThrottle {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Charity",
+                packed(Charity {
+                    is_initialized: true,
+                    wallet: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "BuyerStats",
+                packed(BuyerStats {
+                    is_initialized: true,
+                    buyer: Pubkey::new_from_array([0x11; 32]),
+                    charity_bps: 0x2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "BuyerReceiptsPage",
+                packed(BuyerReceiptsPage {
+                    is_initialized: true,
+                    buyer: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "ModelVersion",
+                packed(ModelVersion {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "ChangelogEntry",
+                packed(ChangelogEntry {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Relationship",
+                packed(Relationship {
+                    is_initialized: true,
+                    follower: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "CategoryRegistry",
+                packed(CategoryRegistry {
+                    is_initialized: true,
+                    created_at: 0x1111_1111_1111_1111,
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "FeaturedList",
+                packed(FeaturedList {
+                    is_initialized: true,
+                    list_id: 0x1111_1111,
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "ProvenanceVerifier",
+                packed(ProvenanceVerifier {
+                    is_initialized: true,
+                    verifier_id: 0x1111_1111,
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "ProvenanceAttestation",
+                packed(ProvenanceAttestation {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    verified_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "InferenceCommitment",
+                packed(InferenceCommitment {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    committed_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "InferenceRequest",
+                packed(InferenceRequest {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    challenge_ends_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Worker",
+                packed(Worker {
+                    is_initialized: true,
+                    operator: Pubkey::new_from_array([0x11; 32]),
+                    last_fulfilled_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "RentalStream",
+                packed(RentalStream {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Organization",
+                packed(Organization {
+                    is_initialized: true,
+                    creator: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Commission",
+                packed(Commission {
+                    is_initialized: true,
+                    buyer: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "SubscriptionPlan",
+                packed(SubscriptionPlan {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    grace_period_slots: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Subscription",
+                packed(Subscription {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    canceled_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "RoleAssignment",
+                packed(RoleAssignment {
+                    is_initialized: true,
+                    holder: Pubkey::new_from_array([0x11; 32]),
+                    granted_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "UsageCounter",
+                packed(UsageCounter {
+                    is_initialized: true,
+                    receipt: Pubkey::new_from_array([0x11; 32]),
+                    settled_requests: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "CreditAccount",
+                packed(CreditAccount {
+                    is_initialized: true,
+                    wallet: Pubkey::new_from_array([0x11; 32]),
+                    mint: Pubkey::new_from_array([0x22; 32]),
+                    ..Default::default()
+                }),
+            ),
+            (
+                "AirdropCampaign",
+                packed(AirdropCampaign {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    merkle_root: [0x22; 32],
+                    ..Default::default()
+                }),
+            ),
+            (
+                "OrderNonces",
+                packed(OrderNonces {
+                    is_initialized: true,
+                    wallet: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "OrderFill",
+                packed(OrderFill {
+                    is_initialized: true,
+                    seller: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "LocalizedMetadata",
+                packed(LocalizedMetadata {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Review",
+                packed(Review {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "ProceedsVault",
+                packed(ProceedsVault {
+                    is_initialized: true,
+                    seller: Pubkey::new_from_array([0x11; 32]),
+                    total_withheld: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "SalesLedgerPage",
+                packed(SalesLedgerPage {
+                    is_initialized: true,
+                    model: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Dispute",
+                packed(Dispute {
+                    is_initialized: true,
+                    receipt: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "MarketplaceConfig",
+                packed(MarketplaceConfig {
+                    is_initialized: true,
+                    admin: Pubkey::new_from_array([0x11; 32]),
+                    max_listing_price: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "RecoveryRequest",
+                packed(RecoveryRequest {
+                    is_initialized: true,
+                    escrow: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Treasury",
+                packed(Treasury {
+                    is_initialized: true,
+                    withdrawal_delay: 0x1111_1111_1111_1111,
+                    secondary_fee_bps: 0x2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "SellerBond",
+                packed(SellerBond {
+                    is_initialized: true,
+                    seller: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Listing",
+                packed(Listing {
+                    is_initialized: true,
+                    status: ListingStatus::Paused,
+                    seller: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Offer",
+                packed(Offer {
+                    is_initialized: true,
+                    status: OfferStatus::Filled,
+                    buyer: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "Escrow",
+                packed(Escrow {
+                    is_initialized: true,
+                    status: EscrowStatus::Released,
+                    offer: Pubkey::new_from_array([0x11; 32]),
+                    updated_at: 0x2222_2222_2222_2222,
+                    ..Default::default()
+                }),
+            ),
+        ]
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // 布局快照：有意修改布局时用 UPDATE_SNAPSHOTS=1 cargo test 重新生成并一起提交
+    #[test]
+    fn test_account_layout_snapshots() {
+        let accounts = canonical_accounts();
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            let snapshot: String = accounts
+                .iter()
+                .map(|(name, data)| format!("{} {}\n", name, to_hex(data)))
+                .collect();
+            let path = std::path::Path::new(file!()).with_file_name("snapshots/account_layouts.txt");
+            std::fs::write(path, snapshot).unwrap();
+            return;
+        }
+
+        let expected: Vec<(&str, &str)> = ACCOUNT_LAYOUT_SNAPSHOTS
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.split_once(' ').unwrap())
+            .collect();
+        assert_eq!(
+            expected.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            accounts.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            "账户类型列表与快照不一致"
+        );
+        for ((name, data), (_, hex)) in accounts.iter().zip(expected.iter()) {
+            assert_eq!(data.len() * 2, hex.len(), "{} 的 LEN 与快照不一致", name);
+            assert_eq!(to_hex(data), *hex, "{} 的序列化字节与快照不一致", name);
+        }
+    }
+
+    // 快照字节必须能被当前代码反序列化回同一个实例
+    #[test]
+    fn test_account_layout_snapshots_unpack() {
+        let snapshots: std::collections::HashMap<&str, Vec<u8>> = ACCOUNT_LAYOUT_SNAPSHOTS
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(name, hex)| (name, from_hex(hex)))
+            .collect();
+        let ai_model = AIModel::unpack(&snapshots["AIModel"]).unwrap();
+        assert_eq!(ai_model.name, "first");
+        assert_eq!(ai_model.charity_bps, 0x2222);
+        let receipt = PurchaseReceipt::unpack(&snapshots["PurchaseReceipt"]).unwrap();
+        assert_eq!(receipt.model, Pubkey::new_from_array([0x11; 32]));
+        assert_eq!(receipt.rent_payer, Pubkey::new_from_array([0x22; 32]));
+        let config = MarketplaceConfig::unpack(&snapshots["MarketplaceConfig"]).unwrap();
+        let mut repacked = vec![0u8; MarketplaceConfig::LEN];
+        MarketplaceConfig::pack(config, &mut repacked).unwrap();
+        assert_eq!(repacked, snapshots["MarketplaceConfig"]);
+    }
+
+    // 按地址返回预置账户数据的AccountFetcher，记录每次请求的地址数
+    struct MockFetcher {
+        accounts: std::collections::HashMap<Pubkey, Vec<u8>>,
+        requests: std::cell::RefCell<Vec<usize>>,
+    }
+
+    impl MockFetcher {
+        fn new() -> Self {
+            Self {
+                accounts: std::collections::HashMap::new(),
+                requests: std::cell::RefCell::new(Vec::new()),
+            }
+        }
+
+        fn insert<T: Pack>(&mut self, key: Pubkey, state: T) {
+            let mut data = vec![0u8; T::LEN];
+            T::pack(state, &mut data).unwrap();
+            self.accounts.insert(key, data);
+        }
+    }
+
+    impl client::AccountFetcher for MockFetcher {
+        type Error = ProgramError;
+
+        fn get_multiple_accounts(&self, keys: &[Pubkey]) -> Result<Vec<Option<Vec<u8>>>, ProgramError> {
+            self.requests.borrow_mut().push(keys.len());
+            Ok(keys.iter().map(|key| self.accounts.get(key).cloned()).collect())
+        }
+    }
+
+    impl client::ProgramAccountScanner for MockFetcher {
+        fn get_program_account_keys(&self, _program_id: &Pubkey, filters: &[client::AccountFilter]) -> Result<Vec<Pubkey>, ProgramError> {
+            Ok(self
+                .accounts
+                .iter()
+                .filter(|(_, data)| filters.iter().all(|filter| filter.matches(data)))
+                .map(|(key, _)| *key)
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_listing_query() {
+        use client::*;
+
+        let program_id = Pubkey::new_unique();
+        let seller = Pubkey::new_unique();
+        let mut fetcher = MockFetcher::new();
+        let mut expected = Vec::new();
+        for (index, price) in [100u64, 200, 300, 400, 500].into_iter().enumerate() {
+            let key = Pubkey::new_unique();
+            let listing = AIModel {
+                is_initialized: true,
+                owner: if index == 4 { Pubkey::new_unique() } else { seller },
+                price,
+                category_id: if index % 2 == 0 { 7 } else { 9 },
+                ..Default::default()
+            };
+            fetcher.insert(key, listing);
+            if index != 4 {
+                expected.push(key);
+            }
+        }
+        // 长度不同的其他账户和长度相同但类型标识不同的数据都不会被扫描到
+        fetcher.insert(Pubkey::new_unique(), SellerStats { is_initialized: true, seller, ..Default::default() });
+        let mut foreign = vec![0u8; AIModel::LEN];
+        foreign[AIModelView::OWNER_OFFSET..AIModelView::OWNER_OFFSET + 32].copy_from_slice(seller.as_ref());
+        fetcher.accounts.insert(Pubkey::new_unique(), foreign);
+        expected.sort();
+
+        // 按卖家分页读取，每页两个地址
+        let query = ListingQuery::new().by_owner(&seller).page_size(2);
+        let first = query.fetch_page(&fetcher, &program_id, None).unwrap();
+        assert_eq!(first.listings.iter().map(|(key, _)| *key).collect::<Vec<_>>(), expected[..2]);
+        assert_eq!(first.next_cursor, Some(expected[1]));
+        let second = query.fetch_page(&fetcher, &program_id, first.next_cursor.as_ref()).unwrap();
+        assert_eq!(second.listings.iter().map(|(key, _)| *key).collect::<Vec<_>>(), expected[2..]);
+        assert_eq!(second.next_cursor, None);
+
+        // 分类和价格上限同时生效
+        let cheap = ListingQuery::new().by_owner(&seller).by_category(7).price_below(300);
+        assert_eq!(cheap.filters().len(), 4);
+        let page = cheap.fetch_page(&fetcher, &program_id, None).unwrap();
+        assert_eq!(page.listings.iter().map(|(_, listing)| listing.price).collect::<Vec<_>>(), vec![100]);
+        assert_eq!(page.next_cursor, None);
+        assert_eq!(ListingQuery::new().by_category(9).fetch_page(&fetcher, &program_id, None).unwrap().listings.len(), 2);
+    }
+
+    // 按脚本返回确认状态的TransactionSender：每发送一次换一个blockhash，每次查询区块高度加1
+    struct ScriptedSender {
+        statuses: std::cell::RefCell<Vec<client::TransactionStatus>>,
+        block_height: std::cell::Cell<u64>,
+        sent: std::cell::RefCell<Vec<Hash>>,
+    }
+
+    impl ScriptedSender {
+        const VALID_BLOCKS: u64 = 2;
+
+        fn new(statuses: Vec<client::TransactionStatus>) -> Self {
+            Self {
+                statuses: std::cell::RefCell::new(statuses),
+                block_height: std::cell::Cell::new(0),
+                sent: std::cell::RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl client::TransactionSender for ScriptedSender {
+        type Signature = usize;
+        type Error = ProgramError;
+
+        fn latest_blockhash(&self) -> Result<(Hash, u64), ProgramError> {
+            Ok((Hash::new_unique(), self.block_height.get() + Self::VALID_BLOCKS))
+        }
+
+        fn send(&self, message: &solana_program::message::Message) -> Result<usize, ProgramError> {
+            self.sent.borrow_mut().push(message.recent_blockhash);
+            Ok(self.sent.borrow().len())
+        }
+
+        fn status(&self, _signature: &usize) -> Result<client::TransactionStatus, ProgramError> {
+            let mut statuses = self.statuses.borrow_mut();
+            Ok(if statuses.is_empty() { client::TransactionStatus::Pending } else { statuses.remove(0) })
+        }
+
+        fn block_height(&self) -> Result<u64, ProgramError> {
+            self.block_height.set(self.block_height.get() + 1);
+            Ok(self.block_height.get())
+        }
+
+        fn wait(&self) {}
+    }
+
+    #[test]
+    fn test_send_and_confirm() {
+        use client::*;
+
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let instruction = Instruction::new_with_bytes(program_id, &[0], vec![AccountMeta::new(payer, true)]);
+
+        // 第一个blockhash过期前一直未执行，换新blockhash后确认
+        let sender = ScriptedSender::new(vec![TransactionStatus::Pending; 3].into_iter().chain([TransactionStatus::Confirmed]).collect());
+        assert_eq!(send_and_confirm(&sender, &payer, std::slice::from_ref(&instruction), 3), Ok(2));
+        let sent = sender.sent.borrow();
+        assert_eq!(sent.len(), 2);
+        assert_ne!(sent[0], sent[1]);
+        drop(sent);
+
+        let sender = ScriptedSender::new(Vec::new());
+        assert_eq!(
+            send_and_confirm(&sender, &payer, std::slice::from_ref(&instruction), 2),
+            Err(SendError::Expired { attempts: 2 })
+        );
+        assert_eq!(sender.sent.borrow().len(), 2);
+
+        // 执行失败时解出最外层失败程序的错误和它输出的日志，不再重发
+        let logs: Vec<String> = [
+            format!("Program {} invoke [1]", program_id),
+            "Program log: Instruction: PurchaseAIModel".to_string(),
+            "Program log: Paying seller".to_string(),
+            "Program 11111111111111111111111111111111 invoke [2]".to_string(),
+            "Transfer: insufficient lamports 10, need 20".to_string(),
+            "Program 11111111111111111111111111111111 failed: custom program error: 0x1".to_string(),
+            format!("Program {} consumed 5000 of 200000 compute units", program_id),
+            format!("Program {} failed: custom program error: 0x1", program_id),
+        ]
+        .into();
+        let sender = ScriptedSender::new(vec![TransactionStatus::Failed { logs: logs.clone() }]);
+        let failure = match send_and_confirm(&sender, &payer, std::slice::from_ref(&instruction), 3) {
+            Err(SendError::Program(failure)) => failure,
+            other => panic!("unexpected result {:?}", other),
+        };
+        assert_eq!(sender.sent.borrow().len(), 1);
+        assert_eq!(failure.program_id, program_id);
+        assert_eq!(failure.error, Some(ProgramError::Custom(1)));
+        assert_eq!(failure.messages, vec!["Instruction: PurchaseAIModel", "Paying seller"]);
+
+        let failure = decode_program_failure(&[
+            format!("Program {} invoke [1]", program_id),
+            format!("Program {} failed: invalid account data for instruction", program_id),
+        ])
+        .unwrap();
+        assert_eq!(failure.error, Some(ProgramError::InvalidAccountData));
+        let failure = decode_program_failure(&[format!("Program {} failed: Computational budget exceeded", program_id)]).unwrap();
+        assert_eq!((failure.error, failure.reason.as_str()), (None, "Computational budget exceeded"));
+        assert_eq!(decode_program_failure(&logs[..4]), None);
+    }
+
+    #[test]
+    fn test_fetch_listing_aggregate() {
+        use client::*;
+
+        let program_id = Pubkey::new_unique();
+        let listing_key = Pubkey::new_unique();
+        let seller = Pubkey::new_unique();
+        let listing = AIModel {
+            is_initialized: true,
+            owner: seller,
+            price: 1_000_000,
+            version_count: 2,
+            ..Default::default()
+        };
+        let mut fetcher = MockFetcher::new();
+        fetcher.insert(listing_key, listing.clone());
+        for version_index in 0..2 {
+            let version = ModelVersion {
+                is_initialized: true,
+                model: listing_key,
+                version_index,
+                ..Default::default()
+            };
+            fetcher.insert(find_model_version_address(&listing_key, version_index, &program_id).0, version);
+        }
+        let stats = SellerStats {
+            is_initialized: true,
+            seller,
+            total_sales: 3,
+            ..Default::default()
+        };
+        fetcher.insert(find_seller_stats_address(&seller, &program_id).0, stats.clone());
+        let bidder = Pubkey::new_unique();
+        let offer_key = find_offer_address(&bidder, &listing_key, 900_000, &program_id).0;
+        let offer = Offer {
+            is_initialized: true,
+            buyer: bidder,
+            model: listing_key,
+            price: 900_000,
+            quantity: 1,
+            ..Default::default()
+        };
+        fetcher.insert(offer_key, offer.clone());
+        let closed_offer_key = Pubkey::new_unique();
+
+        // 请求与链上状态一致时一次往返取齐
+        let request = ListingAggregateRequest::for_listing(listing_key, &listing, vec![offer_key, closed_offer_key]);
+        let aggregate = fetch_listing_aggregate(&fetcher, &program_id, &request).unwrap();
+        assert_eq!(*fetcher.requests.borrow(), vec![6]);
+        assert_eq!((aggregate.listing.owner, aggregate.listing.price), (seller, 1_000_000));
+        assert_eq!(aggregate.versions.iter().map(|v| v.version_index).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(aggregate.offers, vec![(offer_key, offer)]);
+        assert_eq!(aggregate.seller_stats, Some(stats));
+
+        // 版本数过期时按读到的上架补读一次
+        fetcher.requests.borrow_mut().clear();
+        let stale = ListingAggregateRequest {
+            version_count: 1,
+            ..request.clone()
+        };
+        assert_eq!(stale.hydrate(&fetch_multiple(&fetcher, &stale.keys(&program_id)).unwrap()), Err(ProgramError::InvalidArgument));
+        fetcher.requests.borrow_mut().clear();
+        assert_eq!(fetch_listing_aggregate(&fetcher, &program_id, &stale), Ok(aggregate));
+        assert_eq!(*fetcher.requests.borrow(), vec![5, 6]);
+
+        // 超过单次上限的地址分批请求
+        fetcher.requests.borrow_mut().clear();
+        let keys: Vec<Pubkey> = (0..MAX_MULTIPLE_ACCOUNTS + 50).map(|_| Pubkey::new_unique()).collect();
+        assert_eq!(fetch_multiple(&fetcher, &keys).unwrap().len(), keys.len());
+        assert_eq!(*fetcher.requests.borrow(), vec![MAX_MULTIPLE_ACCOUNTS, 50]);
+
+        let missing = ListingAggregateRequest {
+            listing: Pubkey::new_unique(),
+            ..request
+        };
+        assert_eq!(fetch_listing_aggregate(&fetcher, &program_id, &missing), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_close_token_2022_account_if_empty() {
+        let token_account = |owner: Pubkey, data: Vec<u8>| TestAccount {
             owner,
-            price,
-            model_file,
-        })
+            data,
+            ..TestAccount::readonly(Pubkey::new_unique()).writable()
+        };
+        let mut data = vec![0u8; 165];
+        data[64..72].copy_from_slice(&1u64.to_le_bytes());
+        let mut dusted = token_account(TOKEN_2022_PROGRAM_ID, data);
+        let mut destination = TestAccount::wallet(0);
+        let mut authority = TestAccount::wallet(0);
+        let dusted_info = dusted.info();
+        assert_eq!(token_2022_account_amount(&dusted_info), Ok(1));
+        // 有余额时不发起CloseAccount，指令可以继续执行
+        assert_eq!(close_token_2022_account_if_empty(&dusted_info, &destination.info(), &authority.info(), &[]), Ok(false));
+
+        let mut foreign = token_account(Pubkey::new_unique(), vec![0u8; 165]);
+        assert_eq!(token_2022_account_amount(&foreign.info()), Err(ProgramError::IncorrectProgramId));
+        let mut short = token_account(TOKEN_2022_PROGRAM_ID, vec![0u8; 64]);
+        assert_eq!(token_2022_account_amount(&short.info()), Err(ProgramError::InvalidAccountData));
     }
-}
 
-// 定义一个处理程序函数来创建新的AIModel
-pub fn create_ai_model(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    name: String,
-    description: String,
-    price: u64,
-    model_file: Vec<u8>,
-) -> ProgramResult {
-    // 获取账户信息和系统变量
-    let account_info_iter = &mut accounts.iter();
-    let ai_model_account = next_account_info(account_info_iter)?;
-    let owner_account = next_account_info(account_info_iter)?;
-    let rent_sysvar_account = next_account_info(account_info_iter)?;
+    #[test]
+    fn test_stuck_escrow_lamports() {
+        install_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        // 每个托管账户在免租金额和账面余额之外多出100 lamports
+        let stuck = |mut account: TestAccount, tracked: u64| {
+            account.lamports += tracked + 100;
+            assert_eq!(stuck_escrow_lamports(&program_id, &account.info()), Ok(100));
+        };
 
-    // 检查AIModel账户是否已初始化
-    if ai_model_account.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId);
+        let vault = ProceedsVault { is_initialized: true, balance: 70, withheld_balance: 30, ..ProceedsVault::default() };
+        stuck(TestAccount::state(key, &program_id, &vault), 100);
+        let escrow = Escrow { is_initialized: true, amount: 5_000, ..Escrow::default() };
+        stuck(TestAccount::state(key, &program_id, &escrow), 5_000);
+        let released = Escrow { status: EscrowStatus::Released, released: 4_000, ..escrow };
+        stuck(TestAccount::state(key, &program_id, &released), 1_000);
+        let offer = Offer { is_initialized: true, price: 5_000, ..Offer::default() };
+        stuck(TestAccount::state(key, &program_id, &offer), 0);
+        stuck(TestAccount::state(key, &program_id, &Listing { is_initialized: true, price: 5_000, ..Listing::default() }), 0);
+        let request = InferenceRequest { is_initialized: true, max_price: 900, price: 600, ..InferenceRequest::default() };
+        stuck(TestAccount::state(key, &program_id, &request), 900);
+        let fulfilled = InferenceRequest { status: InferenceRequestStatus::Fulfilled, ..request };
+        stuck(TestAccount::state(key, &program_id, &fulfilled), 0);
+        let stream = RentalStream { is_initialized: true, deposit: 1_000, withdrawn: 400, ..RentalStream::default() };
+        stuck(TestAccount::state(key, &program_id, &stream), 600);
+        let milestone = |amount, status| Milestone { amount, status, ..Milestone::default() };
+        let commission = Commission {
+            is_initialized: true,
+            milestones: vec![
+                milestone(300, MilestoneStatus::Released),
+                milestone(200, MilestoneStatus::Disputed),
+                milestone(50, MilestoneStatus::Pending),
+            ],
+            ..Commission::default()
+        };
+        stuck(TestAccount::state(key, &program_id, &commission), 250);
+        let credits = CreditAccount { is_initialized: true, balance: 4_000, ..CreditAccount::default() };
+        stuck(TestAccount::state(key, &program_id, &credits), 4_000);
+        let token_credits = CreditAccount { mint: Pubkey::new_unique(), ..credits };
+        stuck(TestAccount::state(key, &program_id, &token_credits), 0);
+
+        // 其他类型的账户没有可核算的账面余额，不能用于资金恢复
+        let receipt = PurchaseReceipt { is_initialized: true, ..PurchaseReceipt::default() };
+        let mut receipt_account = TestAccount::state(key, &program_id, &receipt);
+        assert_eq!(stuck_escrow_lamports(&program_id, &receipt_account.info()), Err(ProgramError::InvalidAccountData));
     }
-    if ai_model_account.data_len() != AIModel::LEN {
-        return Err(ProgramError::InvalidAccountDataSize);
+
+    #[test]
+    fn test_initialize_treasury_minimum_delay() {
+        install_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let admin = TestAccount::wallet(LAMPORTS_PER_SOL);
+        let config = MarketplaceConfig { is_initialized: true, admin: admin.key, ..MarketplaceConfig::default() };
+        let mut scenario = Scenario::new(program_id)
+            .account("admin", admin)
+            .account("config", TestAccount::state(find_config_address(&program_id).0, &program_id, &config))
+            .account("system_program", TestAccount::readonly(system_program::id()))
+            .pda("treasury", find_treasury_address(&program_id).0, Treasury::LEN);
+        let accounts = ["admin", "config", "treasury", "system_program"];
+
+        // 时间锁短于下限时拒绝创建，避免提款排队后立即可执行
+        for withdrawal_delay in [0, MIN_WITHDRAWAL_DELAY_SECONDS - 1] {
+            assert_eq!(
+                scenario.try_step(&MarketplaceInstruction::InitializeTreasury { withdrawal_delay }, &accounts),
+                Err(ProgramError::InvalidArgument)
+            );
+        }
+        scenario
+            .step(
+                "initialize",
+                MarketplaceInstruction::InitializeTreasury { withdrawal_delay: MIN_WITHDRAWAL_DELAY_SECONDS },
+                &accounts,
+            )
+            .expect_state::<Treasury>("treasury", |treasury| {
+                assert_eq!(treasury.withdrawal_delay, MIN_WITHDRAWAL_DELAY_SECONDS);
+            });
     }
-    if !ai_model_account.is_uninitialized() {
-        return Err(ProgramError::AccountAlreadyInitialized);
+
+    #[test]
+    fn test_cancel_withdrawal() {
+        install_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let admin = TestAccount::wallet(0);
+        let guardian = TestAccount::wallet(0);
+        let destination = TestAccount::wallet(0);
+        let config = MarketplaceConfig {
+            is_initialized: true,
+            admin: admin.key,
+            guardians: vec![guardian.key],
+            ..MarketplaceConfig::default()
+        };
+        let pending = Treasury {
+            is_initialized: true,
+            withdrawal_delay: 3_600,
+            pending_amount: 50_000,
+            pending_destination: destination.key,
+            pending_executable_at: TEST_UNIX_TIMESTAMP - 1,
+            ..Treasury::default()
+        };
+        let config_account = || TestAccount::state(find_config_address(&program_id).0, &program_id, &config);
+        let treasury_account = |treasury: &Treasury| {
+            let mut account = TestAccount::state(find_treasury_address(&program_id).0, &program_id, treasury);
+            account.lamports += pending.pending_amount;
+            account
+        };
+
+        // 没有Treasurer角色的签名者不能取消
+        let mut accounts = [TestAccount::wallet(0), config_account(), treasury_account(&pending)];
+        assert_eq!(
+            cancel_withdrawal(&program_id, &account_infos(&mut accounts)),
+            Err(ProgramError::InvalidArgument)
+        );
+
+        // 守护者和管理员都可以取消，取消后排队信息清空，提款无法再执行
+        for canceler in [guardian, admin] {
+            let mut accounts = [canceler, config_account(), treasury_account(&pending)];
+            assert_eq!(cancel_withdrawal(&program_id, &account_infos(&mut accounts)), Ok(()));
+            let treasury = Treasury::unpack(&accounts[2].data).unwrap();
+            assert_eq!(treasury.pending_amount, 0);
+            assert_eq!(treasury.pending_destination, Pubkey::default());
+            assert_eq!(treasury.pending_executable_at, 0);
+            assert_eq!(treasury.updated_at, TEST_UNIX_TIMESTAMP);
+
+            let [_, _, cancelled] = accounts;
+            let mut accounts = [cancelled, TestAccount::readonly(destination.key).writable()];
+            assert_eq!(execute_withdrawal(&program_id, &account_infos(&mut accounts)), Err(ProgramError::InvalidArgument));
+        }
+
+        // 没有排队中的提款时无法取消
+        let idle = Treasury { is_initialized: true, ..Treasury::default() };
+        let mut accounts = [TestAccount::wallet(0), config_account(), treasury_account(&idle)];
+        accounts[0].key = config.admin;
+        assert_eq!(
+            cancel_withdrawal(&program_id, &account_infos(&mut accounts)),
+            Err(ProgramError::InvalidArgument)
+        );
     }
 
-    // 检查所有者账户是否具有足够的余额来支付租金
-    let rent = &Rent::from_account_info(rent_sysvar_account)?;
-    if !rent.is_exempt(ai_model_account.lamports(), ai_model_account.data_len()) {
-        return Err(ProgramError::AccountNotRentExempt);
+    #[test]
+    fn test_fund_withdrawals_require_top_level() {
+        for instruction in [
+            MarketplaceInstruction::WithdrawProceeds { amount: 1 },
+            MarketplaceInstruction::WithdrawOrganizationFunds { amount: 1 },
+            MarketplaceInstruction::WithdrawCharityDonations { amount: 1 },
+            MarketplaceInstruction::ReleaseWithheldProceeds,
+            MarketplaceInstruction::WithdrawBond { amount: 1 },
+            MarketplaceInstruction::UnstakeWorker { amount: 1 },
+            MarketplaceInstruction::SlashWorker,
+            MarketplaceInstruction::WithdrawRentalStream,
+            MarketplaceInstruction::CloseRentalStream,
+            MarketplaceInstruction::ClaimMilestone { index: 0 },
+            MarketplaceInstruction::ResolveMilestoneDispute { index: 0, release: true },
+            MarketplaceInstruction::CloseCommission,
+            MarketplaceInstruction::BurnCredits { amount: 1 },
+            MarketplaceInstruction::CancelInferenceRequest,
+            MarketplaceInstruction::SettleInferenceRequest,
+            MarketplaceInstruction::UpholdResult,
+            MarketplaceInstruction::RenewSubscription,
+            MarketplaceInstruction::LapseSubscription,
+            MarketplaceInstruction::CancelSubscription,
+            MarketplaceInstruction::CancelTrade,
+            MarketplaceInstruction::ExecuteSale { purchase_index: 0 },
+            MarketplaceInstruction::CloseAirdropCampaign,
+            MarketplaceInstruction::RevokeLicense { reason_hash: [0; 32] },
+            MarketplaceInstruction::SetOrganizationMember { member: Pubkey::new_unique(), role: 0 },
+            MarketplaceInstruction::RemoveOrganizationMember { member: Pubkey::new_unique() },
+            MarketplaceInstruction::AttestArtifact { artifact_hash: [0; 32] },
+            MarketplaceInstruction::ApproveListing { artifact_hash: [0; 32], attestation_hash: [0; 32] },
+            MarketplaceInstruction::QueueWithdrawal { amount: 1, destination: Pubkey::new_unique() },
+            MarketplaceInstruction::ExecuteWithdrawal,
+            MarketplaceInstruction::SetFeatureFlags { feature_flags: 0 },
+        ] {
+            assert!(instruction.requires_top_level(), "{:?}", instruction);
+        }
+        assert!(!MarketplaceInstruction::PurchaseAIModel {
+            amount: 1,
+            tier_index: 0,
+            quantity: 1,
+            purchase_index: 0,
+            acknowledge_deprecation: false,
+            reference: [0; 32],
+        }
+        .requires_top_level());
     }
-    if owner_account.lamports() < rent.minimum_balance(ai_model_account.data_len()) {
-        return Err(ProgramError::InsufficientFunds);
+
+    #[test]
+    fn test_organization_roles() {
+        let admin = Pubkey::new_unique();
+        let editor = Pubkey::new_unique();
+        let outsider = Pubkey::new_unique();
+        let mut organization = Organization {
+            is_initialized: true,
+            name: String::from("Acme AI"),
+            members: vec![OrganizationMember { member: admin, role: OrganizationRole::Admin }],
+            ..Organization::default()
+        };
+        assert_eq!(organization.set_member(editor, OrganizationRole::Editor), Ok(()));
+
+        let mut data = vec![0u8; Organization::LEN];
+        organization.pack_into_slice(&mut data);
+        assert_eq!(Organization::unpack(&data), Ok(organization.clone()));
+
+        // 管理员拥有编辑的权限，编辑不能执行管理员操作
+        assert_eq!(organization.require_role(&admin, OrganizationRole::Editor), Ok(()));
+        assert_eq!(organization.require_role(&editor, OrganizationRole::Editor), Ok(()));
+        assert_eq!(organization.require_role(&editor, OrganizationRole::Admin), Err(ProgramError::InvalidArgument));
+        assert_eq!(organization.require_role(&outsider, OrganizationRole::Editor), Err(ProgramError::InvalidArgument));
+
+        // 不能移除或降级最后一名管理员
+        assert_eq!(organization.clone().remove_member(&admin), Err(ProgramError::InvalidArgument));
+        assert_eq!(organization.clone().set_member(admin, OrganizationRole::Editor), Err(ProgramError::InvalidArgument));
+        assert_eq!(organization.set_member(editor, OrganizationRole::Admin), Ok(()));
+        assert_eq!(organization.remove_member(&admin), Ok(()));
+        assert_eq!(organization.role_of(&admin), None);
+        assert_eq!(organization.remove_member(&outsider), Err(ProgramError::InvalidArgument));
+
+        for _ in organization.members.len()..MAX_ORGANIZATION_MEMBERS {
+            assert_eq!(organization.set_member(Pubkey::new_unique(), OrganizationRole::Editor), Ok(()));
+        }
+        assert_eq!(
+            organization.set_member(outsider, OrganizationRole::Editor),
+            Err(ProgramError::AccountDataTooSmall)
+        );
     }
 
-    // 初始化AIModel账户并存储数据
-    let mut ai_model_data = AIModel::default();
-    ai_model_data.is_initialized = true;
-    ai_model_data.name = name;
-    ai_model_data.description = description;
-    ai_model_data.owner = *owner_account.key;
-    ai_model_data.price = price;
-    ai_model_data.model_file = model_file;
-    ai_model_data.pack_into_slice(&mut ai_model_account.data.borrow_mut());
+    #[test]
+    fn test_organization_admin_acts_as_model_owner() {
+        install_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let mut admin = TestAccount::wallet(0);
+        let mut editor = TestAccount::wallet(0);
+        let organization = Organization {
+            is_initialized: true,
+            members: vec![
+                OrganizationMember { member: admin.key, role: OrganizationRole::Admin },
+                OrganizationMember { member: editor.key, role: OrganizationRole::Editor },
+            ],
+            ..Organization::default()
+        };
+        let mut organization = TestAccount::state(Pubkey::new_unique(), &program_id, &organization);
+        let mut model = listing(&program_id, &organization.key, 1_000);
 
-    // 转移所有者账户的余额以支付租金
-    **owner_account.lamports.borrow_mut() -= rent.minimum_balance(ai_model_account.data_len());
-    **ai_model_account.lamports.borrow_mut() += rent.minimum_balance(ai_model_account.data_len());
+        // 组织拥有的模型由管理员签名设置，必须传入组织PDA；编辑只能行使更新权限
+        assert_eq!(
+            set_rental_rate(&program_id, &[admin.info(), model.info()], 5),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            set_rental_rate(&program_id, &[editor.info(), model.info(), organization.info()], 5),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(set_rental_rate(&program_id, &[admin.info(), model.info(), organization.info()], 5), Ok(()));
+        assert_eq!(set_max_supply(&program_id, &[admin.info(), model.info(), organization.info()], 10), Ok(()));
+        let model_data = AIModel::unpack(&model.data).unwrap();
+        assert_eq!((model_data.rental_rate_per_slot, model_data.max_supply), (5, 10));
+    }
 
-    Ok(())
-}
+    #[test]
+    fn test_role_assignment() {
+        let assignment = RoleAssignment {
+            is_initialized: true,
+            role: Role::Treasurer,
+            holder: Pubkey::new_unique(),
+            granted_by: Pubkey::new_unique(),
+            granted_at: 1_700_000_000,
+        };
+        let mut data = vec![0u8; RoleAssignment::LEN];
+        assignment.pack_into_slice(&mut data);
+        assert_eq!(RoleAssignment::unpack(&data), Ok(assignment));
 
-// 入口点函数
-entrypoint!(process_instruction);
+        // 超级管理员拥有全部角色的权限，其他角色只拥有自身
+        assert!(Role::SuperAdmin.grants(Role::Treasurer));
+        assert!(Role::Moderator.grants(Role::Moderator));
+        assert!(!Role::Moderator.grants(Role::SuperAdmin));
+        assert!(!Role::Oracle.grants(Role::Arbiter));
+        assert_eq!(Role::from_u8(5), Err(ProgramError::InvalidInstructionData));
+
+        // 同一持有者的不同角色使用不同的PDA
+        let program_id = Pubkey::new_unique();
+        let holder = Pubkey::new_unique();
+        assert_ne!(
+            find_role_address(Role::Oracle, &holder, &program_id).0,
+            find_role_address(Role::Arbiter, &holder, &program_id).0
+        );
+    }
 
-// 编写测试用例
-#[cfg(test)]
-mod tests {
-    use super::*;
-    
     #[test]
-    fn test_create_ai_model() {
-        // 编写测试逻辑
-        // ...
+    fn test_usage_counter_sliding_window() {
+        let mut counter = UsageCounter { is_initialized: true, ..UsageCounter::default() };
+        // 不限额时总是接受
+        assert!(counter.record(1_000_000, 0, 10));
+        counter = UsageCounter { is_initialized: true, ..UsageCounter::default() };
+
+        assert!(counter.record(60, 100, 10));
+        assert!(counter.record(40, 100, 20));
+        assert!(!counter.record(1, 100, 30));
+        assert_eq!((counter.epoch_requests, counter.rejected_requests), (100, 1));
+
+        // 进入下一周期的一半时，上一周期的100个请求按一半计入窗口
+        let half = METERING_EPOCH_SLOTS + METERING_EPOCH_SLOTS / 2;
+        counter.advance(half);
+        assert_eq!(counter.windowed_requests(half), 50);
+        assert!(!counter.record(51, 100, half));
+        assert!(counter.record(50, 100, half));
+        assert_eq!(counter.total_requests, 150);
+
+        // 间隔超过一个周期后窗口清零
+        assert!(counter.record(100, 100, METERING_EPOCH_SLOTS * 3));
+        assert_eq!((counter.epoch, counter.previous_epoch_requests), (3, 0));
+
+        let mut data = vec![0u8; UsageCounter::LEN];
+        counter.pack_into_slice(&mut data);
+        assert_eq!(UsageCounter::unpack(&data), Ok(counter));
+    }
+
+    #[test]
+    fn test_order_nonces() {
+        let mut nonces = OrderNonces { is_initialized: true, ..OrderNonces::default() };
+        // nonce可以乱序使用，但每个只能用一次
+        assert_eq!(nonces.consume(5), Ok(()));
+        assert_eq!(nonces.consume(2), Ok(()));
+        assert_eq!(nonces.consume(5), Err(ProgramError::InvalidArgument));
+        assert!(nonces.is_usable(3));
+
+        // 作废小于10的nonce后，已使用的位随窗口平移
+        assert_eq!(nonces.consume(12), Ok(()));
+        assert_eq!(nonces.cancel_below(10), Ok(()));
+        assert!(!nonces.is_usable(3));
+        assert!(!nonces.is_usable(12));
+        assert!(nonces.is_usable(11));
+        assert_eq!(nonces.cancel_below(10), Err(ProgramError::InvalidArgument));
+
+        // 超出窗口的nonce让窗口前移，仍在窗口内的已用nonce保持已用
+        let far = 12 + ORDER_NONCE_WINDOW - 1;
+        assert_eq!(nonces.consume(far), Ok(()));
+        assert_eq!(nonces.min_nonce, 12);
+        assert!(!nonces.is_usable(12));
+        assert_eq!(nonces.consume(far + 1), Ok(()));
+        assert_eq!(nonces.min_nonce, 13);
+        assert!(!nonces.is_usable(12));
+        assert!(!nonces.is_usable(far));
+
+        let mut data = vec![0u8; OrderNonces::LEN];
+        nonces.pack_into_slice(&mut data);
+        assert_eq!(OrderNonces::unpack(&data), Ok(nonces));
+    }
+
+    #[test]
+    fn test_signed_order_message() {
+        let program_id = Pubkey::new_unique();
+        let model = Pubkey::new_unique();
+        let order = SignedOrder {
+            price: 5_000,
+            tier: 2,
+            quantity: 10,
+            expires_at: 1_700_000_000,
+            nonce: 7,
+        };
+        let message = order.message(&program_id, &model);
+        assert_eq!(message.len(), SignedOrder::MESSAGE_LEN);
+        assert!(message.starts_with(SIGNED_ORDER_DOMAIN));
+        assert_eq!(&message[message.len() - 8..], &7u64.to_le_bytes());
+
+        // 任一字段或模型不同都会得到不同的消息
+        assert_ne!(SignedOrder { price: 4_999, ..order }.message(&program_id, &model), message);
+        assert_ne!(order.message(&program_id, &Pubkey::new_unique()), message);
+        assert_ne!(order.message(&Pubkey::new_unique(), &model), message);
+
+        assert_eq!(order.require_live(1_699_999_999), Ok(()));
+        assert_eq!(order.require_live(1_700_000_000), Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn test_order_partial_fills() {
+        let order = SignedOrder {
+            price: 1_000,
+            tier: 0,
+            quantity: 3,
+            expires_at: 1_700_000_000,
+            nonce: 9,
+        };
+        let mut fill = OrderFill { is_initialized: true, quantity: 3, ..OrderFill::default() };
+        // 按累计成交量向上取整分摊，各次之和等于总价
+        assert_eq!(fill.fill(&order, 1), Ok(334));
+        assert!(!fill.is_complete());
+        assert_eq!(fill.fill(&order, 3), Err(ProgramError::InvalidArgument));
+        assert_eq!(fill.fill(&order, 0), Err(ProgramError::InvalidArgument));
+        assert_eq!(fill.fill(&order, 1), Ok(333));
+        assert_eq!(fill.fill(&order, 1), Ok(333));
+        assert!(fill.is_complete());
+        assert_eq!(fill.fill(&order, 1), Err(ProgramError::InvalidArgument));
+
+        // 同一nonce下数量不同的订单不能继续成交
+        let mut other = OrderFill { is_initialized: true, quantity: 5, ..OrderFill::default() };
+        assert_eq!(other.fill(&order, 1), Err(ProgramError::InvalidArgument));
+
+        let mut data = vec![0u8; OrderFill::LEN];
+        fill.pack_into_slice(&mut data);
+        assert_eq!(OrderFill::unpack(&data), Ok(fill));
+    }
+
+    #[test]
+    fn test_worker_capabilities() {
+        let worker = Worker {
+            is_initialized: true,
+            operator: Pubkey::new_unique(),
+            hardware_class: HardwareClass::DatacenterGpu,
+            supported_formats: ModelFormat::Gguf.bit() | ModelFormat::SafeTensors.bit(),
+            price_per_unit: 100,
+            endpoint_hash: [1; 32],
+            created_at: 1,
+            updated_at: 2,
+            stake: MIN_WORKER_STAKE,
+            last_fulfilled_at: 3,
+        };
+        assert!(worker.supports(ModelFormat::Gguf));
+        assert!(worker.supports(ModelFormat::SafeTensors));
+        assert!(!worker.supports(ModelFormat::Onnx));
+        assert_eq!(ModelFormat::TensorFlow.bit() | ALL_MODEL_FORMATS, ALL_MODEL_FORMATS);
+
+        let mut data = vec![0u8; Worker::LEN];
+        worker.pack_into_slice(&mut data);
+        assert_eq!(Worker::unpack(&data), Ok(worker));
+    }
+
+    #[test]
+    fn test_worker_stake_and_slash() {
+        let mut worker = Worker {
+            stake: MIN_WORKER_STAKE - 1,
+            ..Worker::default()
+        };
+        assert_eq!(worker.require_staked(), Err(ProgramError::InsufficientFunds));
+        worker.stake = 2 * MIN_WORKER_STAKE;
+        assert_eq!(worker.require_staked(), Ok(()));
+        assert_eq!(worker.slash_amount(), MIN_WORKER_STAKE);
+
+        // 从未完成过请求时可以随时取回，完成请求后需等待
+        assert_eq!(worker.require_unstakable(0), Ok(()));
+        worker.last_fulfilled_at = 1_000;
+        assert_eq!(worker.require_unstakable(1_000 + WORKER_UNSTAKE_DELAY - 1), Err(ProgramError::InvalidArgument));
+        assert_eq!(worker.require_unstakable(1_000 + WORKER_UNSTAKE_DELAY), Ok(()));
+
+        let arbiter = Pubkey::new_unique();
+        let commitment = InferenceCommitment {
+            model_version: Pubkey::new_unique(),
+            worker: Pubkey::new_unique(),
+            input_hash: [1; 32],
+            output_hash: [2; 32],
+            ..InferenceCommitment::default()
+        };
+        let reference = InferenceCommitment {
+            worker: arbiter,
+            output_hash: [3; 32],
+            ..commitment.clone()
+        };
+        assert!(disproves_commitment(&reference, &arbiter, &commitment));
+        // 输出一致、或复现者不是仲裁者时不构成证伪
+        let agreeing = InferenceCommitment { output_hash: [2; 32], ..reference.clone() };
+        assert!(!disproves_commitment(&agreeing, &arbiter, &commitment));
+        assert!(!disproves_commitment(&reference, &Pubkey::new_unique(), &commitment));
+    }
+
+    #[test]
+    fn test_volume_discount() {
+        let ai_model = AIModel {
+            price: 1_000,
+            per_seat: true,
+            volume_discounts: vec![
+                VolumeDiscount { min_seats: 10, discount_bps: 1_500 },
+                VolumeDiscount { min_seats: 50, discount_bps: 2_500 },
+            ],
+            ..AIModel::default()
+        };
+        assert_eq!(ai_model.list_price(0, 9), Ok(9_000));
+        assert_eq!(ai_model.list_price(0, 10), Ok(8_500));
+        assert_eq!(ai_model.list_price(0, 100), Ok(75_000));
+        assert_eq!(ai_model.list_price(0, 0), Err(ProgramError::InvalidArgument));
+
+        let single_seat = AIModel {
+            price: 1_000,
+            ..AIModel::default()
+        };
+        assert_eq!(single_seat.list_price(0, 1), Ok(1_000));
+        assert_eq!(single_seat.list_price(0, 2), Err(ProgramError::InvalidArgument));
     }
 }
@@ -0,0 +1,82 @@
+// 在solana-program-test的银行中执行处理程序，覆盖进程内Scenario模拟不到的真实系统程序CPI和签名校验；
+// 运行方式: cargo test --features program-test
+use solana_ai_marketplace::test_utils::program_test::{add_escrow, add_listing, funded_buyer, funded_seller, program_test};
+use solana_ai_marketplace::{find_escrow_address, find_offer_address, MarketplaceInstruction};
+use solana_ai_marketplace::state::{Escrow, EscrowStatus, Offer, OfferStatus};
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::Transaction;
+
+#[tokio::test]
+async fn test_buy_escrows_offer() {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = program_test(program_id, &[]);
+    let seller = funded_seller(&mut program_test, LAMPORTS_PER_SOL);
+    let buyer = funded_buyer(&mut program_test, LAMPORTS_PER_SOL);
+    let price = 50_000;
+    let (model, _) = add_listing(&mut program_test, &program_id, &seller.pubkey(), price);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // 买家出价，出价PDA和托管PDA经系统程序CPI创建，出价金额转入托管
+    let offer_key = find_offer_address(&buyer.pubkey(), &model, price, &program_id).0;
+    let escrow_key = find_escrow_address(&offer_key, &program_id).0;
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(buyer.pubkey(), true),
+            AccountMeta::new_readonly(model, false),
+            AccountMeta::new(offer_key, false),
+            AccountMeta::new(escrow_key, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: MarketplaceInstruction::Buy { price, tier_index: 0, quantity: 1 }.pack(),
+    };
+    let transaction =
+        Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[&payer, &buyer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let rent = banks_client.get_rent().await.unwrap();
+    let escrow_account = banks_client.get_account(escrow_key).await.unwrap().unwrap();
+    assert_eq!(escrow_account.owner, program_id);
+    assert_eq!(escrow_account.lamports, rent.minimum_balance(Escrow::LEN) + price);
+    let escrow = Escrow::unpack(&escrow_account.data).unwrap();
+    assert_eq!((escrow.status, escrow.offer, escrow.amount), (EscrowStatus::Funded, offer_key, price));
+    let offer = Offer::unpack(&banks_client.get_account(offer_key).await.unwrap().unwrap().data).unwrap();
+    assert_eq!((offer.status, offer.buyer, offer.escrow), (OfferStatus::Open, buyer.pubkey(), escrow_key));
+}
+
+#[tokio::test]
+async fn test_cancel_funded_escrow_refunds_buyer() {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = program_test(program_id, &[]);
+    let seller = funded_seller(&mut program_test, LAMPORTS_PER_SOL);
+    let buyer = funded_buyer(&mut program_test, LAMPORTS_PER_SOL);
+    let price = 50_000;
+    let (model, _) = add_listing(&mut program_test, &program_id, &seller.pubkey(), price);
+    let (offer_key, escrow_key) = add_escrow(&mut program_test, &program_id, &buyer.pubkey(), &model, price);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // 撤回出价，出价和托管两个账户的全部lamports退回买家，交易费由付款账户承担
+    let offer_lamports = banks_client.get_balance(offer_key).await.unwrap();
+    let escrow_lamports = banks_client.get_balance(escrow_key).await.unwrap();
+    let instruction = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(buyer.pubkey(), true),
+            AccountMeta::new(offer_key, false),
+            AccountMeta::new(escrow_key, false),
+        ],
+        data: MarketplaceInstruction::CancelTrade.pack(),
+    };
+    let transaction =
+        Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[&payer, &buyer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(banks_client.get_balance(buyer.pubkey()).await.unwrap(), LAMPORTS_PER_SOL + offer_lamports + escrow_lamports);
+    assert!(banks_client.get_account(escrow_key).await.unwrap().is_none());
+    assert!(banks_client.get_account(offer_key).await.unwrap().is_none());
+}